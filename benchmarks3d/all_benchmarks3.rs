@@ -24,7 +24,10 @@ mod joint_revolute3;
 mod keva3;
 mod pyramid3;
 mod stacks3;
+mod station3;
+mod tiles3;
 mod trimesh3;
+mod truck3;
 
 enum Command {
     Run(String),
@@ -58,8 +61,14 @@ pub fn main() {
         ("Convex polyhedron", convex_polyhedron3::init_world),
         ("Heightfield", heightfield3::init_world),
         ("Stacks", stacks3::init_world),
+        ("Station", station3::init_world),
+        ("Station aggregate", station3::init_world_aggregate),
+        ("Tiles", tiles3::init_world),
+        ("Tiles welded", tiles3::init_world_welded),
         ("Pyramid", pyramid3::init_world),
         ("Trimesh", trimesh3::init_world),
+        ("Truck", truck3::init_world),
+        ("Truck empty", truck3::init_world_empty),
         ("Joint ball", joint_ball3::init_world),
         ("Joint fixed", joint_fixed3::init_world),
         ("Joint revolute", joint_revolute3::init_world),
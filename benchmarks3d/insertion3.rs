@@ -0,0 +1,86 @@
+use rapier3d::dynamics::{RigidBody, RigidBodyBuilder, RigidBodySet};
+use rapier3d::geometry::{Collider, ColliderBuilder, ColliderSet};
+use std::time::{Duration, Instant};
+
+const NUM_BODIES: usize = 50_000;
+const NUM_RUNS: usize = 10;
+
+fn level() -> Vec<(RigidBody, Vec<Collider>)> {
+    let width = (NUM_BODIES as f32).sqrt() as usize;
+
+    (0..NUM_BODIES)
+        .map(|i| {
+            let x = (i % width) as f32 * 2.0;
+            let z = (i / width) as f32 * 2.0;
+            let body = RigidBodyBuilder::new_dynamic()
+                .translation(x, 1.0, z)
+                .build();
+            let colliders = vec![
+                ColliderBuilder::cuboid(0.5, 0.5, 0.5).build(),
+                ColliderBuilder::ball(0.25)
+                    .translation(0.0, 0.75, 0.0)
+                    .build(),
+            ];
+            (body, colliders)
+        })
+        .collect()
+}
+
+fn insert_one_by_one(level: Vec<(RigidBody, Vec<Collider>)>) -> Duration {
+    let mut bodies = RigidBodySet::new();
+    let mut colliders = ColliderSet::new();
+    let start = Instant::now();
+
+    for (body, body_colliders) in level {
+        let handle = bodies.insert(body);
+
+        for collider in body_colliders {
+            colliders.insert(collider, handle, &mut bodies);
+        }
+    }
+
+    start.elapsed()
+}
+
+fn insert_batch(level: Vec<(RigidBody, Vec<Collider>)>) -> Duration {
+    let mut bodies = RigidBodySet::new();
+    let mut colliders = ColliderSet::new();
+    let start = Instant::now();
+
+    let (level_bodies, level_colliders): (Vec<_>, Vec<_>) = level.into_iter().unzip();
+    let handles = bodies.insert_batch(level_bodies);
+    colliders.insert_batch(
+        handles
+            .into_iter()
+            .zip(level_colliders)
+            .flat_map(|(handle, body_colliders)| {
+                body_colliders
+                    .into_iter()
+                    .map(move |collider| (collider, handle))
+            }),
+        &mut bodies,
+    );
+
+    start.elapsed()
+}
+
+/*
+ * Compares the time needed to insert the bodies and colliders of a level,
+ * one by one, and with `RigidBodySet::insert_batch` and `ColliderSet::insert_batch`.
+ */
+pub fn main() {
+    let mut one_by_one = Duration::default();
+    let mut batch = Duration::default();
+
+    for _ in 0..NUM_RUNS {
+        one_by_one += insert_one_by_one(level());
+        batch += insert_batch(level());
+    }
+
+    println!(
+        "Insertion of {} bodies with 2 colliders each (mean over {} runs):",
+        NUM_BODIES, NUM_RUNS
+    );
+    println!("    one by one: {:?}", one_by_one / NUM_RUNS as u32);
+    println!("    batch:      {:?}", batch / NUM_RUNS as u32);
+}
@@ -0,0 +1,157 @@
+use rapier3d::dynamics::{
+    CCDSolver, IntegrationParameters, JointSet, RigidBodyBuilder, RigidBodyHandle, RigidBodySet,
+    UserConstraintSet,
+};
+use rapier3d::geometry::{BroadPhase, ColliderBuilder, ColliderSet, NarrowPhase};
+use rapier3d::na::Vector3;
+use rapier3d::pipeline::PhysicsPipeline;
+#[cfg(feature = "parallel")]
+use rapier3d::rayon::prelude::*;
+use std::time::{Duration, Instant};
+
+const NUM_BODIES: usize = 20_000;
+const NUM_RUNS: usize = 100;
+
+struct World {
+    pipeline: PhysicsPipeline,
+    broad_phase: BroadPhase,
+    narrow_phase: NarrowPhase,
+    bodies: RigidBodySet,
+    colliders: ColliderSet,
+    joints: JointSet,
+    user_constraints: UserConstraintSet,
+    ccd: CCDSolver,
+    handles: Vec<RigidBodyHandle>,
+}
+
+impl World {
+    fn new() -> Self {
+        let width = (NUM_BODIES as f32).sqrt() as usize;
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut handles = Vec::new();
+
+        for i in 0..NUM_BODIES {
+            let x = (i % width) as f32 * 2.0;
+            let z = (i / width) as f32 * 2.0;
+            let body = RigidBodyBuilder::new_dynamic()
+                .translation(x, 0.0, z)
+                .build();
+            let handle = bodies.insert(body);
+            colliders.insert(ColliderBuilder::ball(0.5).build(), handle, &mut bodies);
+            handles.push(handle);
+        }
+
+        let mut world = Self {
+            pipeline: PhysicsPipeline::new(),
+            broad_phase: BroadPhase::new(),
+            narrow_phase: NarrowPhase::new(),
+            bodies,
+            colliders,
+            joints: JointSet::new(),
+            user_constraints: UserConstraintSet::new(),
+            ccd: CCDSolver::new(),
+            handles,
+        };
+        world.step();
+        world
+    }
+
+    fn step(&mut self) -> Duration {
+        let start = Instant::now();
+        self.pipeline.step(
+            &Vector3::zeros(),
+            &IntegrationParameters::default(),
+            &mut self.broad_phase,
+            &mut self.narrow_phase,
+            &mut self.bodies,
+            &mut self.colliders,
+            &mut self.joints,
+            &mut self.user_constraints,
+            &mut self.ccd,
+            &(),
+            &(),
+        );
+        start.elapsed()
+    }
+}
+
+// Only one body out of ten is modified, alternating the direction of its velocity.
+fn target_velocity(run: usize) -> Vector3<f32> {
+    Vector3::x() * if run % 2 == 0 { 1.0 } else { -1.0 }
+}
+
+fn modify_with_get_mut(world: &mut World, run: usize) -> Duration {
+    let start = Instant::now();
+
+    for handle in world.handles.iter().step_by(10) {
+        world.bodies[*handle].set_linvel(target_velocity(run), true);
+    }
+
+    start.elapsed() + world.step()
+}
+
+fn modify_with_iter_mut(world: &mut World, run: usize) -> Duration {
+    let start = Instant::now();
+
+    for (handle, rb) in world.bodies.iter_mut() {
+        if handle.into_raw_parts().0 % 10 == 0 {
+            rb.set_linvel(target_velocity(run), true);
+        }
+    }
+
+    start.elapsed() + world.step()
+}
+
+#[cfg(feature = "parallel")]
+fn modify_with_par_iter_mut_tracked(world: &mut World, run: usize) -> Duration {
+    let start = Instant::now();
+
+    world.bodies.par_iter_mut_tracked().for_each(|mut rb| {
+        if rb.handle().into_raw_parts().0 % 10 == 0 {
+            rb.set_linvel(target_velocity(run), true);
+        }
+    });
+
+    start.elapsed() + world.step()
+}
+
+/*
+ * Compares the time needed to modify a tenth of the bodies of a world, and to
+ * run the following timestep, with the different ways of modifying them.
+ */
+pub fn main() {
+    let mut get_mut_world = World::new();
+    let mut iter_mut_world = World::new();
+    let mut get_mut = Duration::default();
+    let mut iter_mut = Duration::default();
+
+    for run in 0..NUM_RUNS {
+        get_mut += modify_with_get_mut(&mut get_mut_world, run);
+        iter_mut += modify_with_iter_mut(&mut iter_mut_world, run);
+    }
+
+    println!(
+        "Modification of {} bodies out of {}, and timestep (mean over {} runs):",
+        NUM_BODIES / 10,
+        NUM_BODIES,
+        NUM_RUNS
+    );
+    println!("    get_mut:              {:?}", get_mut / NUM_RUNS as u32);
+    println!("    iter_mut:             {:?}", iter_mut / NUM_RUNS as u32);
+
+    #[cfg(feature = "parallel")]
+    {
+        let mut world = World::new();
+        let mut par_iter_mut_tracked = Duration::default();
+
+        for run in 0..NUM_RUNS {
+            par_iter_mut_tracked += modify_with_par_iter_mut_tracked(&mut world, run);
+        }
+
+        println!(
+            "    par_iter_mut_tracked: {:?}",
+            par_iter_mut_tracked / NUM_RUNS as u32
+        );
+    }
+}
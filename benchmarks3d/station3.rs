@@ -0,0 +1,91 @@
+use na::{Isometry3, Point3, Vector3};
+use rapier3d::dynamics::{JointSet, RigidBodyBuilder, RigidBodySet};
+use rapier3d::geometry::{ColliderBuilder, ColliderSet};
+use rapier_testbed3d::Testbed;
+
+pub fn init_world(testbed: &mut Testbed) {
+    init_station(testbed, false)
+}
+
+pub fn init_world_aggregate(testbed: &mut Testbed) {
+    init_station(testbed, true)
+}
+
+fn init_station(testbed: &mut Testbed, aggregate: bool) {
+    /*
+     * World
+     */
+    let mut bodies = RigidBodySet::new();
+    let mut colliders = ColliderSet::new();
+    let joints = JointSet::new();
+
+    /*
+     * A slowly spinning station: a ring of 2,000 tiles attached to a single rigid-body.
+     */
+    let num_around = 50;
+    let num_along = 40;
+    let radius = num_around as f32 / std::f32::consts::PI / 2.0;
+
+    let rigid_body = RigidBodyBuilder::new_dynamic()
+        .angvel(Vector3::new(0.0, 0.2, 0.0))
+        .gravity_scale(0.0)
+        .can_sleep(false)
+        .build();
+    let station_handle = bodies.insert(rigid_body);
+    bodies[station_handle].use_aggregate_proxy(aggregate);
+
+    for i in 0..num_around {
+        let angle = i as f32 * std::f32::consts::PI * 2.0 / num_around as f32;
+
+        for j in 0..num_along {
+            let y = j as f32 - num_along as f32 / 2.0;
+            let pos = Isometry3::new(
+                Vector3::new(angle.cos() * radius, y, angle.sin() * radius),
+                Vector3::y() * -angle,
+            );
+            let collider = ColliderBuilder::cuboid(0.1, 0.5, 0.5)
+                .density(100.0)
+                .position_wrt_parent(pos)
+                .build();
+            colliders.insert(collider, station_handle, &mut bodies);
+        }
+    }
+
+    /*
+     * Create the balls bouncing inside of the station.
+     */
+    let num = 8;
+    let rad = 0.4;
+    let shift = 1.5;
+
+    for i in 0..num {
+        for j in 0..num {
+            for k in 0..num {
+                let x = i as f32 * shift - num as f32 * shift / 2.0;
+                let y = j as f32 * shift - num as f32 * shift / 2.0;
+                let z = k as f32 * shift - num as f32 * shift / 2.0;
+
+                // Build the rigid body.
+                let rigid_body = RigidBodyBuilder::new_dynamic()
+                    .translation(x, y, z)
+                    .linvel(x, 0.0, z)
+                    .gravity_scale(0.0)
+                    .build();
+                let handle = bodies.insert(rigid_body);
+                let collider = ColliderBuilder::ball(rad).restitution(1.0).build();
+                colliders.insert(collider, handle, &mut bodies);
+            }
+        }
+    }
+
+    /*
+     * Set up the testbed.
+     */
+    testbed.set_world(bodies, colliders, joints);
+    testbed.look_at(Point3::new(30.0, 30.0, 30.0), Point3::origin());
+}
+
+fn main() {
+    let testbed = Testbed::from_builders(0, vec![("Station", init_world)]);
+    testbed.run()
+}
@@ -0,0 +1,79 @@
+use na::Point3;
+use rapier3d::dynamics::{JointSet, RigidBodyBuilder, RigidBodySet};
+use rapier3d::geometry::{ColliderBuilder, ColliderSet, WeldOptions};
+use rapier_testbed3d::Testbed;
+
+pub fn init_world(testbed: &mut Testbed) {
+    init_tiles(testbed, false)
+}
+
+pub fn init_world_welded(testbed: &mut Testbed) {
+    init_tiles(testbed, true)
+}
+
+fn init_tiles(testbed: &mut Testbed, weld: bool) {
+    /*
+     * World
+     */
+    let mut bodies = RigidBodySet::new();
+    let mut colliders = ColliderSet::new();
+    let joints = JointSet::new();
+
+    /*
+     * Ground: a map of ~50k unit tiles.
+     */
+    let num_tiles = 224;
+    let half_size = num_tiles as f32 / 2.0;
+
+    let rigid_body = RigidBodyBuilder::new_static().build();
+    let ground_handle = bodies.insert(rigid_body);
+
+    for i in 0..num_tiles {
+        for k in 0..num_tiles {
+            let x = i as f32 - half_size;
+            let z = k as f32 - half_size;
+            let collider = ColliderBuilder::cuboid(0.5, 0.5, 0.5)
+                .translation(x, -0.5, z)
+                .build();
+            colliders.insert(collider, ground_handle, &mut bodies);
+        }
+    }
+
+    if weld {
+        colliders.weld_static_colliders(&mut bodies, &WeldOptions::default());
+    }
+
+    /*
+     * Create the balls rolling over the tiles.
+     */
+    let num = 40;
+    let rad = 0.4;
+    let shift = 4.0;
+
+    for i in 0..num {
+        for k in 0..num {
+            let x = i as f32 * shift - num as f32 * shift / 2.0;
+            let z = k as f32 * shift - num as f32 * shift / 2.0;
+
+            // Build the rigid body.
+            let rigid_body = RigidBodyBuilder::new_dynamic()
+                .translation(x, rad, z)
+                .linvel(2.0, 0.0, 1.0)
+                .build();
+            let handle = bodies.insert(rigid_body);
+            let collider = ColliderBuilder::ball(rad).build();
+            colliders.insert(collider, handle, &mut bodies);
+        }
+    }
+
+    /*
+     * Set up the testbed.
+     */
+    testbed.set_world(bodies, colliders, joints);
+    testbed.look_at(Point3::new(100.0, 100.0, 100.0), Point3::origin());
+}
+
+fn main() {
+    let testbed = Testbed::from_builders(0, vec![("Tiles", init_world)]);
+    testbed.run()
+}
@@ -0,0 +1,101 @@
+use na::Point3;
+use rapier3d::dynamics::{CoefficientCombineRule, JointSet, RigidBodyBuilder, RigidBodySet};
+use rapier3d::geometry::{ColliderBuilder, ColliderSet};
+use rapier_testbed3d::Testbed;
+
+fn build_world(testbed: &mut Testbed, num_layers: usize) {
+    /*
+     * World
+     */
+    let mut bodies = RigidBodySet::new();
+    let mut colliders = ColliderSet::new();
+    let joints = JointSet::new();
+
+    /*
+     * Frictionless ground, so the truck keeps its velocity.
+     */
+    let ground_size = 1000.0;
+    let ground_height = 0.1;
+
+    let rigid_body = RigidBodyBuilder::new_static()
+        .translation(0.0, -ground_height, 0.0)
+        .build();
+    let handle = bodies.insert(rigid_body);
+    let collider = ColliderBuilder::cuboid(ground_size, ground_height, ground_size)
+        .friction(0.0)
+        .friction_combine_rule(CoefficientCombineRule::Min)
+        .build();
+    colliders.insert(collider, handle, &mut bodies);
+
+    /*
+     * The truck bed.
+     */
+    let speed = 5.0;
+    let truck_height = 0.5;
+    let rigid_body = RigidBodyBuilder::new_dynamic()
+        .translation(-ground_size / 2.0, truck_height, 0.0)
+        .linvel(speed, 0.0, 0.0)
+        .build();
+    let truck = bodies.insert(rigid_body);
+    let collider = ColliderBuilder::cuboid(10.0, truck_height, 5.0)
+        .density(100.0)
+        .build();
+    colliders.insert(collider, truck, &mut bodies);
+
+    /*
+     * The crates, resting on the truck bed.
+     */
+    let num = 10;
+    let rad = 0.4;
+    let shift = rad * 2.0 + 0.1;
+    let centerx = shift * (num as f32 - 1.0) / 2.0;
+    let centerz = shift * (num as f32 - 1.0) / 2.0;
+
+    for j in 0..num_layers {
+        for i in 0..num {
+            for k in 0..num {
+                let x = i as f32 * shift - centerx - ground_size / 2.0;
+                let y = truck_height * 2.0 + rad + j as f32 * rad * 2.0;
+                let z = k as f32 * shift - centerz;
+
+                let rigid_body = RigidBodyBuilder::new_dynamic()
+                    .translation(x, y, z)
+                    .linvel(speed, 0.0, 0.0)
+                    .build();
+                let handle = bodies.insert(rigid_body);
+                let collider = ColliderBuilder::cuboid(rad, rad, rad).build();
+                colliders.insert(collider, handle, &mut bodies);
+            }
+        }
+    }
+
+    /*
+     * Set up the testbed.
+     */
+    testbed.set_world(bodies, colliders, joints);
+    let params = testbed.integration_parameters_mut();
+    params.manifold_freezing_linear_threshold = 1.0e-3;
+    params.manifold_freezing_angular_threshold = 1.0e-3;
+    testbed.look_at(
+        Point3::new(-ground_size / 2.0 - 30.0, 30.0, 30.0),
+        Point3::new(-ground_size / 2.0, 0.0, 0.0),
+    );
+}
+
+pub fn init_world(testbed: &mut Testbed) {
+    // 200 crates.
+    build_world(testbed, 2)
+}
+
+pub fn init_world_empty(testbed: &mut Testbed) {
+    // The baseline: the same truck without any crate.
+    build_world(testbed, 0)
+}
+
+fn main() {
+    let testbed = Testbed::from_builders(
+        0,
+        vec![("Truck", init_world), ("Truck empty", init_world_empty)],
+    );
+    testbed.run()
+}
@@ -0,0 +1,11 @@
+#! /bin/bash
+
+set -e
+
+### Check the 2D versions.
+cd build/rapier2d && cargo check --no-default-features --features dim2,f32,no_std && cd ../..
+cd build/rapier2d-f64 && cargo check --no-default-features --features dim2,f64,no_std && cd ../..
+
+### Check the 3D versions.
+cd build/rapier3d && cargo check --no-default-features --features dim3,f32,no_std && cd ../..
+cd build/rapier3d-f64 && cargo check --no-default-features --features dim3,f64,no_std && cd ../..
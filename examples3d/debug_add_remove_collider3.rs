@@ -1,6 +1,6 @@
 use na::Point3;
 use rapier3d::dynamics::{JointSet, RigidBodyBuilder, RigidBodySet};
-use rapier3d::geometry::{ColliderBuilder, ColliderSet};
+use rapier3d::geometry::{ColliderBuilder, ColliderSet, RemoveSettleMode};
 use rapier_testbed3d::Testbed;
 
 pub fn init_world(testbed: &mut Testbed) {
@@ -38,7 +38,12 @@ pub fn init_world(testbed: &mut Testbed) {
         // Remove then re-add the ground collider.
         let coll = physics
             .colliders
-            .remove(ground_collider_handle, &mut physics.bodies, true)
+            .remove(
+                ground_collider_handle,
+                &mut physics.bodies,
+                true,
+                RemoveSettleMode::Immediate,
+            )
             .unwrap();
         ground_collider_handle = physics
             .colliders
@@ -1,6 +1,6 @@
 use na::{Isometry3, Point3, Vector3};
 use rapier3d::dynamics::{JointSet, RigidBodyBuilder, RigidBodySet};
-use rapier3d::geometry::{ColliderBuilder, ColliderSet};
+use rapier3d::geometry::{ColliderBuilder, ColliderSet, RemoveSettleMode};
 use rapier_testbed3d::Testbed;
 
 pub fn init_world(testbed: &mut Testbed) {
@@ -79,7 +79,12 @@ pub fn init_world(testbed: &mut Testbed) {
             step = snapped_frame;
 
             for handle in &extra_colliders {
-                physics.colliders.remove(*handle, &mut physics.bodies, true);
+                physics.colliders.remove(
+                    *handle,
+                    &mut physics.bodies,
+                    true,
+                    RemoveSettleMode::Immediate,
+                );
             }
 
             extra_colliders.clear();
@@ -90,7 +95,7 @@ pub fn init_world(testbed: &mut Testbed) {
         // ground.set_position(Isometry3::translation(0.0, step as f32 * 0.001, 0.0), false);
         // let coll = physics
         //     .colliders
-        //     .remove(ground_collider_handle, &mut physics.bodies, true)
+        //     .remove(ground_collider_handle, &mut physics.bodies, true, RemoveSettleMode::Immediate)
         //     .unwrap();
         let coll = ColliderBuilder::cuboid(ground_size, ground_height + step as f32 * 0.01, 0.4)
             .friction(0.15)
@@ -1,5 +1,5 @@
-use crate::counters::Timer;
-use std::fmt::{Display, Formatter, Result};
+use crate::counters::{Clock, Timer};
+use core::fmt::{Display, Formatter, Result};
 
 /// Performance counters related to continuous collision detection (CCD).
 #[derive(Default, Clone, Copy)]
@@ -36,6 +36,14 @@ impl CCDCounters {
         self.broad_phase_time.reset();
         self.narrow_phase_time.reset();
     }
+
+    /// Sets the clock read by all the timers.
+    pub fn set_clock<C: Clock>(&mut self) {
+        self.toi_computation_time.set_clock::<C>();
+        self.solver_time.set_clock::<C>();
+        self.broad_phase_time.set_clock::<C>();
+        self.narrow_phase_time.set_clock::<C>();
+    }
 }
 
 impl Display for CCDCounters {
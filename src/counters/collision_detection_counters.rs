@@ -1,5 +1,5 @@
-use crate::counters::Timer;
-use std::fmt::{Display, Formatter, Result};
+use crate::counters::{Clock, Timer};
+use core::fmt::{Display, Formatter, Result};
 
 /// Performance counters related to collision detection.
 #[derive(Default, Clone, Copy)]
@@ -28,6 +28,12 @@ impl CollisionDetectionCounters {
         self.broad_phase_time.reset();
         self.narrow_phase_time.reset();
     }
+
+    /// Sets the clock read by all the timers.
+    pub fn set_clock<C: Clock>(&mut self) {
+        self.broad_phase_time.set_clock::<C>();
+        self.narrow_phase_time.set_clock::<C>();
+    }
 }
 
 impl Display for CollisionDetectionCounters {
@@ -1,12 +1,14 @@
 //! Counters for benchmarking various parts of the physics engine.
 
-use std::fmt::{Display, Formatter, Result};
+use core::fmt::{Display, Formatter, Result};
 
 pub use self::ccd_counters::CCDCounters;
 pub use self::collision_detection_counters::CollisionDetectionCounters;
 pub use self::solver_counters::SolverCounters;
 pub use self::stages_counters::StagesCounters;
-pub use self::timer::Timer;
+#[cfg(not(feature = "no_std"))]
+pub use self::timer::InstantClock;
+pub use self::timer::{Clock, Timer};
 
 mod ccd_counters;
 mod collision_detection_counters;
@@ -126,6 +128,19 @@ impl Counters {
             self.ccd.reset();
         }
     }
+
+    /// Sets the clock read by all the timers.
+    ///
+    /// The timers read the clock of the `instant` crate by default. With the `no_std` feature,
+    /// they don't measure anything until a clock is set.
+    pub fn set_clock<C: Clock>(&mut self) {
+        self.step_time.set_clock::<C>();
+        self.custom.set_clock::<C>();
+        self.stages.set_clock::<C>();
+        self.cd.set_clock::<C>();
+        self.solver.set_clock::<C>();
+        self.ccd.set_clock::<C>();
+    }
 }
 
 macro_rules! measure_method {
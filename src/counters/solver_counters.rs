@@ -1,5 +1,5 @@
-use crate::counters::Timer;
-use std::fmt::{Display, Formatter, Result};
+use crate::counters::{Clock, Timer};
+use core::fmt::{Display, Formatter, Result};
 
 /// Performance counters related to constraints resolution.
 #[derive(Default, Clone, Copy)]
@@ -18,6 +18,12 @@ pub struct SolverCounters {
     pub position_assembly_time: Timer,
     /// Time spent for the update of the position of the bodies.
     pub position_resolution_time: Timer,
+    /// Number of position constraint solves, i.e., the number of position constraints of each
+    /// solved island times the number of position iterations.
+    pub num_position_constraint_solves: usize,
+    /// Number of islands whose position correction was deferred to the next timestep because
+    /// the `IntegrationParameters::position_solve_budget` was exhausted.
+    pub num_deferred_position_islands: usize,
 }
 
 impl SolverCounters {
@@ -31,6 +37,8 @@ impl SolverCounters {
             velocity_update_time: Timer::new(),
             position_assembly_time: Timer::new(),
             position_resolution_time: Timer::new(),
+            num_position_constraint_solves: 0,
+            num_deferred_position_islands: 0,
         }
     }
 
@@ -43,6 +51,17 @@ impl SolverCounters {
         self.velocity_update_time.reset();
         self.position_assembly_time.reset();
         self.position_resolution_time.reset();
+        self.num_position_constraint_solves = 0;
+        self.num_deferred_position_islands = 0;
+    }
+
+    /// Sets the clock read by all the timers.
+    pub fn set_clock<C: Clock>(&mut self) {
+        self.velocity_resolution_time.set_clock::<C>();
+        self.velocity_assembly_time.set_clock::<C>();
+        self.velocity_update_time.set_clock::<C>();
+        self.position_assembly_time.set_clock::<C>();
+        self.position_resolution_time.set_clock::<C>();
     }
 }
 
@@ -62,6 +81,16 @@ impl Display for SolverCounters {
             f,
             "Position resolution time: {}",
             self.position_resolution_time
+        )?;
+        writeln!(
+            f,
+            "Number of position constraint solves: {}",
+            self.num_position_constraint_solves
+        )?;
+        writeln!(
+            f,
+            "Number of deferred position islands: {}",
+            self.num_deferred_position_islands
         )
     }
 }
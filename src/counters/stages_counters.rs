@@ -1,5 +1,5 @@
-use crate::counters::Timer;
-use std::fmt::{Display, Formatter, Result};
+use crate::counters::{Clock, Timer};
+use core::fmt::{Display, Formatter, Result};
 
 /// Performance counters related to each stage of the time step.
 #[derive(Default, Clone, Copy)]
@@ -36,6 +36,15 @@ impl StagesCounters {
         self.solver_time.reset();
         self.ccd_time.reset();
     }
+
+    /// Sets the clock read by all the timers.
+    pub fn set_clock<C: Clock>(&mut self) {
+        self.update_time.set_clock::<C>();
+        self.collision_detection_time.set_clock::<C>();
+        self.island_construction_time.set_clock::<C>();
+        self.solver_time.set_clock::<C>();
+        self.ccd_time.set_clock::<C>();
+    }
 }
 
 impl Display for StagesCounters {
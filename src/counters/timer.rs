@@ -1,10 +1,53 @@
-use std::fmt::{Display, Error, Formatter};
+use core::fmt::{Display, Error, Formatter};
+
+/// A clock read by the timers of the performance counters.
+///
+/// With the `no_std` feature, there is no default clock: the timers don't measure anything
+/// until a clock is set with `Counters::set_clock` or `Timer::set_clock`.
+pub trait Clock {
+    /// The current time in milliseconds, measured from an arbitrary origin.
+    fn now() -> f64;
+}
+
+/// The default clock of the timers, based on the `instant` crate.
+#[cfg(not(feature = "no_std"))]
+pub struct InstantClock;
+
+#[cfg(not(feature = "no_std"))]
+impl Clock for InstantClock {
+    fn now() -> f64 {
+        instant::now()
+    }
+}
+
+// The clock of the timers until another one is set with the `no_std` feature.
+#[cfg(feature = "no_std")]
+struct NoClock;
+
+#[cfg(feature = "no_std")]
+impl Clock for NoClock {
+    fn now() -> f64 {
+        0.0
+    }
+}
+
+#[cfg(not(feature = "no_std"))]
+type DefaultClock = InstantClock;
+#[cfg(feature = "no_std")]
+type DefaultClock = NoClock;
 
 /// A timer.
-#[derive(Copy, Clone, Debug, Default)]
+#[derive(Copy, Clone, Debug)]
 pub struct Timer {
     time: f64,
     start: Option<f64>,
+    now: fn() -> f64,
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Timer {
@@ -13,9 +56,20 @@ impl Timer {
         Timer {
             time: 0.0,
             start: None,
+            now: DefaultClock::now,
         }
     }
 
+    /// Sets the clock read by this timer.
+    pub fn set_clock<C: Clock>(&mut self) {
+        self.now = C::now;
+    }
+
+    /// The clock read by this timer.
+    pub(crate) fn clock(&self) -> fn() -> f64 {
+        self.now
+    }
+
     /// Resets the timer to 0.
     pub fn reset(&mut self) {
         self.time = 0.0
@@ -24,20 +78,20 @@ impl Timer {
     /// Start the timer.
     pub fn start(&mut self) {
         self.time = 0.0;
-        self.start = Some(instant::now());
+        self.start = Some((self.now)());
     }
 
     /// Pause the timer.
     pub fn pause(&mut self) {
         if let Some(start) = self.start {
-            self.time += instant::now() - start;
+            self.time += (self.now)() - start;
         }
         self.start = None;
     }
 
     /// Resume the timer.
     pub fn resume(&mut self) {
-        self.start = Some(instant::now());
+        self.start = Some((self.now)());
     }
 
     /// The measured time between the last `.start()` and `.pause()` calls.
@@ -3,13 +3,15 @@
 //! See <https://github.com/fitzgen/generational-arena/blob/master/src/lib.rs>.
 //! This has been modified to have a fully deterministic deserialization (including for the order of
 //! Index attribution after a deserialization of the arena.
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp;
+use core::convert::TryInto;
+use core::iter::{self, Extend, FromIterator, FusedIterator};
+use core::mem;
+use core::ops;
+use core::slice;
 use parry::partitioning::IndexedData;
-use std::cmp;
-use std::iter::{self, Extend, FromIterator, FusedIterator};
-use std::mem;
-use std::ops;
-use std::slice;
-use std::vec;
 
 /// The `Arena` allows inserting and removing elements that are referred to by
 /// `Index`.
@@ -565,6 +567,107 @@ impl<T> Arena<T> {
         (item1, item2)
     }
 
+    /// Get exclusive references to the elements at the given indices in this arena.
+    ///
+    /// `None` is returned for the indices of the elements that are not in the arena.
+    ///
+    /// # Panics
+    ///
+    /// Panics if two indices are equal.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// use rapier::data::arena::Arena;
+    ///
+    /// let mut arena = Arena::new();
+    /// let idx1 = arena.insert(0);
+    /// let idx2 = arena.insert(1);
+    /// let idx3 = arena.insert(2);
+    ///
+    /// {
+    ///     let [item1, item2, item3] = arena.get_many_mut([idx1, idx2, idx3]);
+    ///
+    ///     *item1.unwrap() = 3;
+    ///     *item2.unwrap() = 4;
+    ///     *item3.unwrap() = 5;
+    /// }
+    ///
+    /// assert_eq!(arena[idx1], 3);
+    /// assert_eq!(arena[idx2], 4);
+    /// assert_eq!(arena[idx3], 5);
+    /// ```
+    pub fn get_many_mut<const N: usize>(&mut self, indices: [Index; N]) -> [Option<&mut T>; N] {
+        for (k, i) in indices.iter().enumerate() {
+            assert!(!indices[..k].contains(i), "The indices must be distinct.");
+        }
+
+        // Two distinct indices of elements in the arena point to distinct slots, so these slots
+        // can be split off one after the other, in increasing order.
+        let mut order: Vec<_> = (0..N).filter(|k| self.get(indices[*k]).is_some()).collect();
+        order.sort_unstable_by_key(|k| indices[*k].index);
+
+        let mut result: Vec<Option<&mut T>> = (0..N).map(|_| None).collect();
+        let mut rest = &mut self.items[..];
+        let mut offset = 0;
+
+        for k in order {
+            let (_, tail) = mem::take(&mut rest).split_at_mut(indices[k].index - offset);
+            let (entry, tail) = tail.split_first_mut().unwrap();
+            rest = tail;
+            offset = indices[k].index + 1;
+
+            if let Entry::Occupied { value, .. } = entry {
+                result[k] = Some(value);
+            }
+        }
+
+        result
+            .try_into()
+            .unwrap_or_else(|_| unreachable!("The result has one element per index."))
+    }
+
+    /// Iterates through exclusive references to the elements at the given indices in this arena.
+    ///
+    /// The elements are yielded in the order of their slots, not in the order of `indices`. The
+    /// indices of the elements that are not in the arena are skipped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if two indices refer to the same slot.
+    pub fn iter_many_mut(
+        &mut self,
+        mut indices: Vec<Index>,
+    ) -> impl Iterator<Item = (Index, &mut T)> {
+        indices.sort_unstable_by_key(|i| i.index);
+        assert!(
+            indices.windows(2).all(|w| w[0].index != w[1].index),
+            "The indices must be distinct."
+        );
+
+        // Like with `Self::get_many_mut`, the slots are split off one after the other.
+        let mut rest = &mut self.items[..];
+        let mut offset = 0;
+
+        indices.into_iter().filter_map(move |i| {
+            if i.index - offset >= rest.len() {
+                return None;
+            }
+
+            let (_, tail) = mem::take(&mut rest).split_at_mut(i.index - offset);
+            let (entry, tail) = tail.split_first_mut().unwrap();
+            rest = tail;
+            offset = i.index + 1;
+
+            match entry {
+                Entry::Occupied { generation, value } if *generation == i.generation => {
+                    Some((i, value))
+                }
+                _ => None,
+            }
+        })
+    }
+
     /// Get the length of this arena.
     ///
     /// The length is the number of elements the arena holds.
@@ -638,6 +741,12 @@ impl<T> Arena<T> {
         self.items.len()
     }
 
+    /// The number of bytes allocated by this arena, not counting the
+    /// heap allocations owned by its elements.
+    pub fn allocated_bytes(&self) -> usize {
+        self.items.capacity() * core::mem::size_of::<Entry<T>>()
+    }
+
     /// Allocate space for `additional_capacity` more elements in the arena.
     ///
     /// # Panics
@@ -655,22 +764,88 @@ impl<T> Arena<T> {
     /// # let _: Arena<usize> = arena;
     /// ```
     pub fn reserve(&mut self, additional_capacity: usize) {
+        if additional_capacity == 0 {
+            return;
+        }
+
         let start = self.items.len();
         let end = self.items.len() + additional_capacity;
-        let old_head = self.free_list_head;
         self.items.reserve_exact(additional_capacity);
         self.items.extend((start..end).map(|i| {
             if i == end - 1 {
-                Entry::Free {
-                    next_free: old_head,
-                }
+                Entry::Free { next_free: None }
             } else {
                 Entry::Free {
                     next_free: Some(i + 1),
                 }
             }
         }));
-        self.free_list_head = Some(start);
+
+        // The new entries are appended to the end of the free list so that the
+        // free entries already present are still used first. This way, reserving
+        // doesn't affect the indices given to the next inserted elements.
+        let mut last_free = match self.free_list_head {
+            Some(head) => head,
+            None => {
+                self.free_list_head = Some(start);
+                return;
+            }
+        };
+
+        while let Entry::Free {
+            next_free: Some(next_free),
+        } = self.items[last_free]
+        {
+            last_free = next_free;
+        }
+
+        self.items[last_free] = Entry::Free {
+            next_free: Some(start),
+        };
+    }
+
+    /// Releases the excess capacity of this arena.
+    ///
+    /// Only the free entries following the last element of the arena can be released, so
+    /// the indices of the elements are unaffected. The other free entries are reused in the
+    /// same order as before by the next insertions.
+    pub fn shrink_to_fit(&mut self) {
+        // NOTE: keep at least one entry, like `Self::with_capacity`.
+        let new_len = self
+            .items
+            .iter()
+            .rposition(|entry| matches!(entry, Entry::Occupied { .. }))
+            .map_or(1, |i| i + 1);
+
+        if new_len < self.items.len() {
+            // Unlink the released entries from the free list.
+            let mut next = self.free_list_head.take();
+            let mut last_kept = None;
+
+            while let Some(i) = next {
+                next = match self.items[i] {
+                    Entry::Free { next_free } => next_free,
+                    Entry::Occupied { .. } => panic!("corrupt free list"),
+                };
+
+                if i < new_len {
+                    match last_kept {
+                        Some(last) => self.items[last] = Entry::Free { next_free: Some(i) },
+                        None => self.free_list_head = Some(i),
+                    }
+
+                    last_kept = Some(i);
+                }
+            }
+
+            if let Some(last) = last_kept {
+                self.items[last] = Entry::Free { next_free: None };
+            }
+
+            self.items.truncate(new_len);
+        }
+
+        self.items.shrink_to_fit();
     }
 
     /// Iterate over shared references to the elements in this arena.
@@ -727,6 +902,58 @@ impl<T> Arena<T> {
         }
     }
 
+    /// Iterate in parallel over shared references to the elements in this arena.
+    ///
+    /// Yields pairs of `(Index, &T)` items, in no particular order.
+    #[cfg(feature = "parallel")]
+    pub fn par_iter(&self) -> impl rayon::iter::ParallelIterator<Item = (Index, &T)> + '_
+    where
+        T: Sync,
+    {
+        use rayon::prelude::*;
+
+        self.items
+            .par_iter()
+            .enumerate()
+            .filter_map(|(index, entry)| match entry {
+                Entry::Occupied { generation, value } => Some((
+                    Index {
+                        index,
+                        generation: *generation,
+                    },
+                    value,
+                )),
+                Entry::Free { .. } => None,
+            })
+    }
+
+    /// Iterate in parallel over exclusive references to the elements in this arena.
+    ///
+    /// Yields pairs of `(Index, &mut T)` items, in no particular order.
+    #[cfg(feature = "parallel")]
+    pub fn par_iter_mut(
+        &mut self,
+    ) -> impl rayon::iter::ParallelIterator<Item = (Index, &mut T)> + '_
+    where
+        T: Send,
+    {
+        use rayon::prelude::*;
+
+        self.items
+            .par_iter_mut()
+            .enumerate()
+            .filter_map(|(index, entry)| match entry {
+                Entry::Occupied { generation, value } => Some((
+                    Index {
+                        index,
+                        generation: *generation,
+                    },
+                    value,
+                )),
+                Entry::Free { .. } => None,
+            })
+    }
+
     /// Iterate over elements of the arena and remove them.
     ///
     /// Yields pairs of `(Index, T)` items.
@@ -1,4 +1,5 @@
 use crate::data::arena::Index;
+use alloc::vec::Vec;
 
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 #[derive(Clone, Debug)]
@@ -13,6 +14,25 @@ impl<T> Coarena<T> {
         Self { data: Vec::new() }
     }
 
+    /// The number of bytes allocated by this coarena, not counting the
+    /// heap allocations owned by its elements.
+    pub fn allocated_bytes(&self) -> usize {
+        self.data.capacity() * core::mem::size_of::<(u64, T)>()
+    }
+
+    /// Releases the excess capacity of this coarena.
+    ///
+    /// The removed elements following the last element of the coarena are released too.
+    pub fn shrink_to_fit(&mut self) {
+        let new_len = self
+            .data
+            .iter()
+            .rposition(|(g, _)| *g != u32::MAX as u64)
+            .map_or(0, |i| i + 1);
+        self.data.truncate(new_len);
+        self.data.shrink_to_fit();
+    }
+
     /// Gets a specific element from the coarena, if it exists.
     pub fn get(&self, index: Index) -> Option<&T> {
         let (i, g) = index.into_raw_parts();
@@ -43,6 +63,20 @@ impl<T> Coarena<T> {
         self.data[i1] = (g1, value);
     }
 
+    /// Removes the element associated to the given index, if it exists.
+    ///
+    /// The element is no longer retrieved with this index, even if the arena
+    /// element it identifies still exists.
+    pub fn remove(&mut self, index: Index) -> Option<T>
+    where
+        T: Clone,
+    {
+        let (i, g) = index.into_raw_parts();
+        let elt = self.data.get_mut(i).filter(|(gg, _)| *gg == g)?;
+        elt.0 = u32::MAX as u64;
+        Some(elt.1.clone())
+    }
+
     /// Ensure that elements at the two given indices exist in this coarena, and return their reference.
     ///
     /// Missing elements are created automatically and initialized with the `default` value.
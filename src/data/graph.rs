@@ -4,8 +4,9 @@
 // - It is always undirected.
 //! A stripped-down version of petgraph's UnGraph.
 
-use std::cmp::max;
-use std::ops::{Index, IndexMut};
+use alloc::vec::Vec;
+use core::cmp::max;
+use core::ops::{Index, IndexMut};
 
 /// Node identifier.
 #[derive(Copy, Clone, Default, PartialEq, PartialOrd, Eq, Ord, Hash, Debug)]
@@ -163,6 +164,19 @@ impl<N, E> Graph<N, E> {
         }
     }
 
+    /// The number of bytes allocated by this graph, not counting the
+    /// heap allocations owned by its node and edge weights.
+    pub fn allocated_bytes(&self) -> usize {
+        self.nodes.capacity() * core::mem::size_of::<Node<N>>()
+            + self.edges.capacity() * core::mem::size_of::<Edge<E>>()
+    }
+
+    /// Releases the excess capacity of the nodes and edges of this graph.
+    pub fn shrink_to_fit(&mut self) {
+        self.nodes.shrink_to_fit();
+        self.edges.shrink_to_fit();
+    }
+
     /// Add a node (also called vertex) with associated data `weight` to the graph.
     ///
     /// Computes in **O(1)** time.
@@ -519,7 +533,7 @@ impl<N, E> Graph<N, E> {
 
 /// An iterator over either the nodes without edges to them or from them.
 pub struct Externals<'a, N: 'a> {
-    iter: std::iter::Enumerate<std::slice::Iter<'a, Node<N>>>,
+    iter: core::iter::Enumerate<core::slice::Iter<'a, Node<N>>>,
     dir: Direction,
 }
 
@@ -794,7 +808,7 @@ where
 
 /// Iterator over all nodes of a graph.
 pub struct NodeReferences<'a, N: 'a> {
-    iter: std::iter::Enumerate<std::slice::Iter<'a, Node<N>>>,
+    iter: core::iter::Enumerate<core::slice::Iter<'a, Node<N>>>,
 }
 
 impl<'a, N> Iterator for NodeReferences<'a, N> {
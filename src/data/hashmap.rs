@@ -0,0 +1,20 @@
+//! The hash maps and sets used by Rapier, with or without `std`.
+
+/// The hash map from `std`.
+#[cfg(not(feature = "no_std"))]
+pub type HashMap<K, V> = std::collections::HashMap<K, V>;
+
+/// The hash set from `std`.
+#[cfg(not(feature = "no_std"))]
+pub type HashSet<T> = std::collections::HashSet<T>;
+
+#[cfg(feature = "no_std")]
+type FxBuildHasher = core::hash::BuildHasherDefault<rustc_hash::FxHasher>;
+
+/// A hash map based on `hashbrown`, used instead of the one from `std` with the `no_std` feature.
+#[cfg(feature = "no_std")]
+pub type HashMap<K, V> = hashbrown::HashMap<K, V, FxBuildHasher>;
+
+/// A hash set based on `hashbrown`, used instead of the one from `std` with the `no_std` feature.
+#[cfg(feature = "no_std")]
+pub type HashSet<T> = hashbrown::HashSet<T, FxBuildHasher>;
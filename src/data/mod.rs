@@ -1,8 +1,10 @@
 //! Data structures modified with guaranteed deterministic behavior after deserialization.
 
 pub use self::coarena::Coarena;
+pub(crate) use self::hashmap::{HashMap, HashSet};
 
 pub mod arena;
 mod coarena;
 pub(crate) mod graph;
+mod hashmap;
 pub mod pubsub;
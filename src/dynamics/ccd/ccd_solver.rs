@@ -4,9 +4,10 @@ use crate::geometry::{ColliderSet, IntersectionEvent, NarrowPhase};
 use crate::math::Real;
 use crate::parry::utils::SortedPair;
 use crate::pipeline::{EventHandler, QueryPipeline, QueryPipelineMode};
+use alloc::collections::BinaryHeap;
+use alloc::{vec, vec::Vec};
 use parry::query::{DefaultQueryDispatcher, QueryDispatcher};
 use parry::utils::hashmap::HashMap;
-use std::collections::BinaryHeap;
 
 pub enum PredictedImpacts {
     Impacts(HashMap<RigidBodyHandle, Real>),
@@ -208,7 +209,7 @@ impl CCDSolver {
          *
          */
         // TODO: don't iterate through all the colliders.
-        for (ch1, co1) in colliders.iter() {
+        for (ch1, co1) in colliders.iter().filter(|(_, co)| !co.is_detached()) {
             let rb1 = &bodies[co1.parent()];
             if rb1.is_ccd_active() {
                 let aabb = co1.compute_swept_aabb(&(rb1.next_position * co1.position_wrt_parent()));
@@ -51,6 +51,11 @@ impl TOIEntry {
     ) -> Option<Self> {
         assert!(start_time <= end_time);
 
+        if b1.is_projectile() && b2.is_projectile() {
+            // Projectiles never hit each other.
+            return None;
+        }
+
         let linvel1 = frozen1.is_none() as u32 as Real * b1.linvel();
         let linvel2 = frozen2.is_none() as u32 as Real * b2.linvel();
         let angvel1 = frozen1.is_none() as u32 as Real * b1.angvel();
@@ -142,13 +147,13 @@ impl TOIEntry {
 }
 
 impl PartialOrd for TOIEntry {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
         (-self.toi).partial_cmp(&(-other.toi))
     }
 }
 
 impl Ord for TOIEntry {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
         self.partial_cmp(other).unwrap()
     }
 }
@@ -1,4 +1,228 @@
+use crate::geometry::ManifoldFreezing;
 use crate::math::Real;
+use crate::pipeline::{OnNonFinite, PipelineLimits};
+use alloc::string::{String, ToString};
+use alloc::{vec, vec::Vec};
+use core::fmt;
+use core::ops::RangeInclusive;
+use core::time::Duration;
+
+/// The method used by the constraints solver to apply restitution.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub enum RestitutionModel {
+    /// Restitution is part of the right-hand-side of the non-penetration constraints.
+    ///
+    /// This is cheap, but can add energy to the system when one body is in contact with
+    /// several other bouncy bodies (e.g. a column of bouncy balls).
+    Rhs,
+    /// Restitution is applied by a separate pass executed after the velocity constraints
+    /// are solved, targeting the approach velocities measured before the velocity solve.
+    ///
+    /// This pass runs `IntegrationParameters::max_velocity_iterations` times so that the
+    /// simultaneous collisions of several bodies don't lose energy.
+    PostSolve,
+}
+
+impl Default for RestitutionModel {
+    fn default() -> Self {
+        RestitutionModel::Rhs
+    }
+}
+
+/// A limit on the work done by the position solver during a single timestep.
+///
+/// See `IntegrationParameters::position_solve_budget` for details.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub enum PositionSolveBudget {
+    /// The maximum number of position constraint solves per timestep.
+    ///
+    /// Solving an island costs its number of position constraints times
+    /// `IntegrationParameters::max_position_iterations`.
+    ConstraintSolves(usize),
+    /// The time after which the position solver stops solving islands during a timestep.
+    ///
+    /// The island being solved when this time elapses is completed, so the actual position
+    /// resolution time exceeds this duration by at most the resolution time of one island.
+    /// The time is measured with the clock of the `PhysicsPipeline::counters`, so this budget is
+    /// never exhausted with the `no_std` feature if no clock was set with `Counters::set_clock`.
+    Duration(Duration),
+}
+
+/// The value of one of the parameters of `IntegrationParameters`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ParamValue {
+    /// A real-valued parameter.
+    Real(Real),
+    /// An integer parameter, e.g., an iteration count.
+    Integer(usize),
+    /// An optional integer parameter, where `None` has a special meaning (e.g. "unbounded").
+    OptionalInteger(Option<usize>),
+    /// An optional real-valued parameter, where `None` has a special meaning (e.g. "disabled").
+    OptionalReal(Option<Real>),
+    /// A parameter enabling or disabling a feature.
+    Bool(bool),
+    /// The value of `IntegrationParameters::restitution_model`.
+    RestitutionModel(RestitutionModel),
+    /// The value of `IntegrationParameters::position_solve_budget`.
+    PositionSolveBudget(Option<PositionSolveBudget>),
+    /// The value of `IntegrationParameters::on_non_finite`.
+    OnNonFinite(OnNonFinite),
+    /// The value of `IntegrationParameters::pipeline_limits`.
+    PipelineLimits(PipelineLimits),
+}
+
+impl ParamValue {
+    /// The numerical value checked against the valid range of the parameter, if any.
+    fn as_real(&self) -> Option<Real> {
+        match self {
+            ParamValue::Real(value) => Some(*value),
+            ParamValue::Integer(value) => Some(*value as Real),
+            ParamValue::OptionalInteger(value) => value.map(|value| value as Real),
+            ParamValue::OptionalReal(value) => *value,
+            ParamValue::Bool(_)
+            | ParamValue::RestitutionModel(_)
+            | ParamValue::PositionSolveBudget(_)
+            | ParamValue::OnNonFinite(_)
+            | ParamValue::PipelineLimits(_) => None,
+        }
+    }
+}
+
+/// The description of one of the parameters of `IntegrationParameters`, returned by
+/// `IntegrationParameters::describe`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParamDescriptor {
+    /// The name of the field of `IntegrationParameters` holding this parameter.
+    pub name: &'static str,
+    /// The current value of the parameter.
+    pub value: ParamValue,
+    /// The range of valid values of the parameter, or `None` for non-numerical parameters.
+    ///
+    /// For `ParamValue::OptionalInteger` and `ParamValue::OptionalReal` parameters, the range only
+    /// constrains the `Some` values.
+    pub range: Option<RangeInclusive<Real>>,
+    /// A one-line description of the effect of the parameter.
+    pub effect: &'static str,
+}
+
+impl ParamDescriptor {
+    fn real(
+        name: &'static str,
+        value: Real,
+        range: RangeInclusive<Real>,
+        effect: &'static str,
+    ) -> Self {
+        Self {
+            name,
+            value: ParamValue::Real(value),
+            range: Some(range),
+            effect,
+        }
+    }
+
+    fn integer(
+        name: &'static str,
+        value: usize,
+        range: RangeInclusive<Real>,
+        effect: &'static str,
+    ) -> Self {
+        Self {
+            name,
+            value: ParamValue::Integer(value),
+            range: Some(range),
+            effect,
+        }
+    }
+
+    fn optional_integer(
+        name: &'static str,
+        value: Option<usize>,
+        range: RangeInclusive<Real>,
+        effect: &'static str,
+    ) -> Self {
+        Self {
+            name,
+            value: ParamValue::OptionalInteger(value),
+            range: Some(range),
+            effect,
+        }
+    }
+
+    fn optional_real(
+        name: &'static str,
+        value: Option<Real>,
+        range: RangeInclusive<Real>,
+        effect: &'static str,
+    ) -> Self {
+        Self {
+            name,
+            value: ParamValue::OptionalReal(value),
+            range: Some(range),
+            effect,
+        }
+    }
+
+    fn boolean(name: &'static str, value: bool, effect: &'static str) -> Self {
+        Self {
+            name,
+            value: ParamValue::Bool(value),
+            range: None,
+            effect,
+        }
+    }
+}
+
+/// An error returned by `IntegrationParameters::set_by_name`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParamError {
+    /// No parameter has the given name.
+    UnknownParam(String),
+    /// The value doesn't have the same type as the parameter.
+    WrongType {
+        /// The name of the parameter.
+        name: &'static str,
+        /// The current value of the parameter, with the expected type.
+        expected: ParamValue,
+        /// The rejected value.
+        value: ParamValue,
+    },
+    /// The value is outside of the range of valid values of the parameter.
+    OutOfRange {
+        /// The name of the parameter.
+        name: &'static str,
+        /// The rejected value.
+        value: Real,
+        /// The range of valid values of the parameter.
+        range: RangeInclusive<Real>,
+    },
+}
+
+impl fmt::Display for ParamError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParamError::UnknownParam(name) => write!(f, "unknown integration parameter `{}`", name),
+            ParamError::WrongType {
+                name,
+                expected,
+                value,
+            } => write!(
+                f,
+                "the integration parameter `{}` cannot be set to {:?}, expected a value like {:?}",
+                name, value, expected
+            ),
+            ParamError::OutOfRange { name, value, range } => write!(
+                f,
+                "the integration parameter `{}` cannot be set to {}, expected a value in {:?}",
+                name, value, range
+            ),
+        }
+    }
+}
+
+#[cfg(not(feature = "no_std"))]
+impl std::error::Error for ParamError {}
 
 /// Parameters for a time-step of the physics engine.
 #[derive(Copy, Clone)]
@@ -73,6 +297,152 @@ pub struct IntegrationParameters {
     pub min_island_size: usize,
     /// Maximum number of substeps performed by the solver (default: `1`).
     pub max_ccd_substeps: usize,
+    /// The method used to apply restitution on bouncy contacts (default: `RestitutionModel::Rhs`).
+    pub restitution_model: RestitutionModel,
+    /// If `true`, contacts between a dynamic body and a static or kinematic body start the velocity
+    /// solve with, at least, the impulse cancelling the velocity gained by the dynamic body due to
+    /// gravity during this timestep (default: `false`).
+    ///
+    /// This reduces the sagging of heavy bodies resting on platforms when few velocity iterations are used.
+    pub gravity_compensation_on_ground_contacts: bool,
+    /// If `true`, the joints of each island are solved in the order of their distance, in the joint
+    /// graph, to the closest static or kinematic body (default: `false`).
+    ///
+    /// This propagates the constraints from the anchored end of chains of bodies towards their free
+    /// end, which stabilizes long chains when few velocity iterations are used. The distances are
+    /// only recomputed when joints are added or removed.
+    pub joint_chain_ordering: bool,
+    /// Velocity below which every body of an island must stay for the island to be considered
+    /// quasi-static (default: `0.0`, i.e., disabled).
+    ///
+    /// The constraints of an island which stayed quasi-static, with the same bodies and contact manifolds,
+    /// during `quasi_static_min_steps` consecutive steps are not regenerated: the constraints of the
+    /// previous step are reused and solved with `quasi_static_velocity_iterations` iterations only.
+    /// Islands containing joints always regenerate their constraints. This is not supported by the
+    /// parallel solver yet.
+    pub quasi_static_velocity_threshold: Real,
+    /// Number of consecutive quasi-static steps after which an island starts reusing its
+    /// constraints (default: `10`).
+    pub quasi_static_min_steps: usize,
+    /// Maximum number of velocity iterations performed on islands reusing their constraints (default: `1`).
+    pub quasi_static_velocity_iterations: usize,
+    /// Maximum relative translation of two colliders in contact for their contact manifolds to be
+    /// frozen (default: `0.0`, i.e., disabled).
+    ///
+    /// The contact manifolds of a pair of colliders whose relative pose stayed within
+    /// `manifold_freezing_linear_threshold` and `manifold_freezing_angular_threshold` of the same
+    /// relative pose during `manifold_freezing_min_steps` consecutive steps are frozen: they are no
+    /// longer recomputed by the narrow-phase, and their contact points move rigidly with the colliders.
+    /// This saves the narrow-phase work of bodies in resting contact which can't sleep, e.g., the
+    /// cargo of a moving vehicle. A pair is unfrozen as soon as its relative pose moves past the
+    /// thresholds, or as soon as the total impulse applied by its contacts changes abruptly.
+    pub manifold_freezing_linear_threshold: Real,
+    /// Maximum relative rotation angle of two colliders in contact for their contact manifolds to be
+    /// frozen (default: `0.0`).
+    ///
+    /// See `manifold_freezing_linear_threshold` for details.
+    pub manifold_freezing_angular_threshold: Real,
+    /// Number of consecutive steps after which the contact manifolds of two colliders with a still
+    /// relative pose are frozen (default: `10`).
+    pub manifold_freezing_min_steps: usize,
+    /// Number of constraints or bodies each thread of the parallel solver grabs at once
+    /// (default: `None`).
+    ///
+    /// Small batches balance the work better between threads, but make them contend more on the
+    /// shared work counters. If `None`, the batch size is chosen for each island depending on its
+    /// number of constraints and on the number of threads. This is ignored without the `parallel`
+    /// feature.
+    pub parallel_batch_size: Option<usize>,
+    /// The maximum work done by the position solver during a timestep (default: `None`, i.e.,
+    /// unbounded).
+    ///
+    /// Large penetrations, e.g., after an explosion threw many bodies into each other, make the
+    /// position solver spike during several timesteps. With a budget, the islands are solved by
+    /// decreasing penetration depth until the budget is exhausted, and the position correction of
+    /// the remaining islands is deferred: they are solved first during the next timestep. This
+    /// bounds the position resolution time, and spreads the correction of the penetrations over
+    /// several timesteps. The first island with position constraints is always solved, even if it
+    /// exceeds the budget alone. The velocity solver is never limited, so the momentum exchanges
+    /// stay correct. With a `PositionSolveBudget::Duration` budget, the simulation isn't
+    /// deterministic anymore.
+    ///
+    /// The number of deferred islands is reported by `SolverCounters::num_deferred_position_islands`,
+    /// and `PhysicsHooks::on_island_solved` is still called for them. With the `parallel` feature,
+    /// the islands to solve are selected before solving them concurrently, so only
+    /// `PositionSolveBudget::ConstraintSolves` budgets are supported: a
+    /// `PositionSolveBudget::Duration` budget makes the timestep panic.
+    pub position_solve_budget: Option<PositionSolveBudget>,
+    /// The maximum ratio between the effective masses of two dynamic bodies at their contacts
+    /// (default: `None`, i.e., unbounded).
+    ///
+    /// A heavy body resting on a much lighter one, e.g. a 1000 kg crate on a 1 kg table, needs
+    /// many velocity iterations to be stable. With a maximum ratio, whenever the effective masses
+    /// of the two bodies at a contact differ by more than this ratio, the effective mass of the
+    /// lighter body is raised, for the contact constraints of this pair only, so that the ratio
+    /// isn't exceeded. This isn't physically correct, since the lighter body is pushed as if it
+    /// was heavier, but keeps such stacks stable at low iteration counts. The contacts with a
+    /// non-dynamic body, or with a body of a higher dominance group, are never affected because
+    /// that body already behaves as if it had an infinite mass.
+    ///
+    /// The mass multipliers applied to the bodies of a contact manifold are reported by
+    /// `ContactManifoldData::effective_mass_multipliers`.
+    pub max_effective_mass_ratio: Option<Real>,
+    /// Whether the contacts involving kinematic bodies are computed at the position these
+    /// kinematic bodies will have at the end of the timestep (default: `false`).
+    ///
+    /// This lets dynamic bodies react to a kinematic body in the same timestep it reaches them,
+    /// instead of being pushed out of it once it already penetrated them. Dynamic bodies keep
+    /// using their current positions, and the broad-phase AABBs of the kinematic colliders cover
+    /// both their current and next positions. Contact events involving kinematic bodies may be
+    /// emitted one timestep earlier.
+    pub use_predicted_kinematic_positions: bool,
+    /// Whether sticking contacts are pulled back to the point where they started sticking
+    /// (default: `false`).
+    ///
+    /// Without friction anchors, friction only cancels the tangential relative velocity at each
+    /// contact, so bodies resting on slopes slowly creep downhill because of the accumulated
+    /// velocity errors. With friction anchors, each contact remembers where it started sticking
+    /// and a fraction `erp` of its tangential drift from that anchor is corrected at each timestep,
+    /// as long as the friction impulse stays inside of the friction cone. A contact is re-anchored
+    /// whenever it slides.
+    pub friction_anchors: bool,
+    /// Maximum number of contacts through which sleeping bodies are woken up in a single
+    /// timestep (default: `None`, i.e., unbounded).
+    ///
+    /// When a body wakes up, every body touching it, directly or through other bodies, is woken
+    /// up too. In dense scenes, this can wake up thousands of bodies in a single timestep. With
+    /// a maximum depth, only the bodies at most this number of contacts away from an awake body
+    /// are woken up during a timestep. The others are woken up during the next timesteps,
+    /// spreading the cost over several frames.
+    ///
+    /// The bodies which are not woken up yet stay frozen in place, even if a body they touch moved,
+    /// and act as static bodies for the awake bodies touching them. Bodies attached by joints are
+    /// always woken up together, regardless of this depth.
+    pub max_wake_propagation_depth: Option<u32>,
+    /// Whether the contacts of awake bodies wake up the sleeping bodies they touch (default: `true`).
+    ///
+    /// If `true`, each sleeping body is woken up according to its `RigidBody::wake_policy`. If
+    /// `false`, sleeping bodies are only woken up explicitly or through joints, as if they all had
+    /// the `WakePolicy::Never` policy. Sleeping bodies which are not woken up act as static bodies
+    /// for the awake bodies touching them.
+    pub contacts_wake_sleeping_bodies: bool,
+    /// The maximum norm of the linear velocity of the dynamic rigid-bodies (default: `1.0e4`
+    /// (meters per second)).
+    ///
+    /// The velocities are clamped right after the integration of the forces, before the
+    /// constraints are solved, so that a single exploding body can't generate huge swept AABBs
+    /// or predicted positions. Each clamping is reported to the `EventHandler`.
+    pub max_linear_velocity: Real,
+    /// The maximum norm of the angular velocity of the dynamic rigid-bodies (default: `1.0e4`
+    /// (radians per second)).
+    ///
+    /// See `max_linear_velocity` for details.
+    pub max_angular_velocity: Real,
+    /// What to do when a rigid-body with a non-finite state (e.g. a NaN velocity) is detected
+    /// (default: `OnNonFinite::ResetVelocityAndReport`).
+    pub on_non_finite: OnNonFinite,
+    /// Safety limits protecting the simulation against degenerate scenes (default: no limit).
+    pub pipeline_limits: PipelineLimits,
 }
 
 impl IntegrationParameters {
@@ -148,11 +518,457 @@ impl IntegrationParameters {
         }
     }
 
+    /// Returns a copy of these parameters adapted to the timestep length `new_dt`.
+    ///
+    /// The parameters expressed per timestep are rescaled so that the simulation behaves,
+    /// per simulated second, like it does with the current parameters:
+    /// - The error reduction parameters are converted such that the same proportion of the
+    ///   positional errors is corrected after one second.
+    /// - The warmstart coefficient is converted such that the same proportion of the cached
+    ///   impulses survives after one second.
+    /// - The maximum corrections and `min_ccd_dt` are scaled by `new_dt / self.dt`.
+    ///
+    /// Parameters expressed in distance units, and iteration counts, are left unchanged.
+    pub fn rescaled_for_dt(&self, new_dt: Real) -> Self {
+        if self.dt == 0.0 {
+            return Self {
+                dt: new_dt,
+                ..*self
+            };
+        }
+
+        let ratio = new_dt / self.dt;
+        let rescale_fraction = |fraction: Real| 1.0 - (1.0 - fraction).max(0.0).powf(ratio);
+
+        Self {
+            dt: new_dt,
+            min_ccd_dt: self.min_ccd_dt * ratio,
+            erp: rescale_fraction(self.erp),
+            joint_erp: rescale_fraction(self.joint_erp),
+            velocity_based_erp: rescale_fraction(self.velocity_based_erp),
+            warmstart_coeff: self.warmstart_coeff.powf(ratio),
+            max_linear_correction: self.max_linear_correction * ratio,
+            max_angular_correction: self.max_angular_correction * ratio,
+            ..*self
+        }
+    }
+
+    /// Converts a spring stiffness and damping into the equivalent error reduction parameter (ERP)
+    /// and constraint force mixing (CFM) for the timestep length `dt`.
+    ///
+    /// The stiffness and damping are expressed for a unit mass. This returns `(erp, cfm)`.
+    pub fn spring_to_erp_cfm(stiffness: Real, damping: Real, dt: Real) -> (Real, Real) {
+        let denom = dt * stiffness + damping;
+
+        if denom == 0.0 {
+            (0.0, 0.0)
+        } else {
+            (dt * stiffness / denom, 1.0 / denom)
+        }
+    }
+
+    /// Converts an error reduction parameter (ERP) and constraint force mixing (CFM) into the
+    /// equivalent spring stiffness and damping for the timestep length `dt`.
+    ///
+    /// This is the inverse of `Self::spring_to_erp_cfm`. This returns `(stiffness, damping)`,
+    /// expressed for a unit mass.
+    pub fn erp_cfm_to_spring(erp: Real, cfm: Real, dt: Real) -> (Real, Real) {
+        if cfm == 0.0 || dt == 0.0 {
+            (0.0, 0.0)
+        } else {
+            (erp / (dt * cfm), (1.0 - erp) / cfm)
+        }
+    }
+
+    /// Describes every parameter: its name, current value, range of valid values, and effect.
+    ///
+    /// This can be used to build tuning interfaces, or to validate remote configurations
+    /// against the parameters supported by this version of Rapier.
+    pub fn describe(&self) -> Vec<ParamDescriptor> {
+        // Destructure exhaustively so that a new field can't be added without describing it.
+        let IntegrationParameters {
+            dt,
+            min_ccd_dt,
+            erp,
+            joint_erp,
+            warmstart_coeff,
+            warmstart_correction_slope,
+            velocity_solve_fraction,
+            velocity_based_erp,
+            allowed_linear_error,
+            prediction_distance,
+            allowed_angular_error,
+            max_linear_correction,
+            max_angular_correction,
+            max_velocity_iterations,
+            max_position_iterations,
+            min_island_size,
+            max_ccd_substeps,
+            restitution_model,
+            gravity_compensation_on_ground_contacts,
+            joint_chain_ordering,
+            quasi_static_velocity_threshold,
+            quasi_static_min_steps,
+            quasi_static_velocity_iterations,
+            manifold_freezing_linear_threshold,
+            manifold_freezing_angular_threshold,
+            manifold_freezing_min_steps,
+            parallel_batch_size,
+            position_solve_budget,
+            max_effective_mass_ratio,
+            use_predicted_kinematic_positions,
+            friction_anchors,
+            max_wake_propagation_depth,
+            contacts_wake_sleeping_bodies,
+            max_linear_velocity,
+            max_angular_velocity,
+            on_non_finite,
+            pipeline_limits,
+        } = *self;
+
+        vec![
+            ParamDescriptor::real("dt", dt, 0.0..=1.0, "The timestep length, in seconds."),
+            ParamDescriptor::real(
+                "min_ccd_dt",
+                min_ccd_dt,
+                0.0..=1.0,
+                "The smallest timestep length generated by CCD substepping.",
+            ),
+            ParamDescriptor::real(
+                "erp",
+                erp,
+                0.0..=1.0,
+                "The proportion of the contact penetrations corrected at each timestep.",
+            ),
+            ParamDescriptor::real(
+                "joint_erp",
+                joint_erp,
+                0.0..=1.0,
+                "The proportion of the joint position errors corrected at each timestep.",
+            ),
+            ParamDescriptor::real(
+                "warmstart_coeff",
+                warmstart_coeff,
+                0.0..=1.0,
+                "The proportion of the impulses of the previous timestep reused to initialize the solver.",
+            ),
+            ParamDescriptor::real(
+                "warmstart_correction_slope",
+                warmstart_correction_slope,
+                0.0..=Real::MAX,
+                "How much the warmstart impulses are reduced after strong impacts.",
+            ),
+            ParamDescriptor::real(
+                "velocity_solve_fraction",
+                velocity_solve_fraction,
+                0.0..=1.0,
+                "The proportion of the constraint velocity errors cancelled by the velocity solver.",
+            ),
+            ParamDescriptor::real(
+                "velocity_based_erp",
+                velocity_based_erp,
+                0.0..=1.0,
+                "The proportion of the constraint violations corrected by the velocity solver.",
+            ),
+            ParamDescriptor::real(
+                "allowed_linear_error",
+                allowed_linear_error,
+                0.0..=Real::MAX,
+                "The penetration depth the solver doesn't attempt to correct.",
+            ),
+            ParamDescriptor::real(
+                "prediction_distance",
+                prediction_distance,
+                0.0..=Real::MAX,
+                "The maximum distance between two colliders generating predictive contacts.",
+            ),
+            ParamDescriptor::real(
+                "allowed_angular_error",
+                allowed_angular_error,
+                0.0..=Real::MAX,
+                "The angular drift of joint limits the solver doesn't attempt to correct.",
+            ),
+            ParamDescriptor::real(
+                "max_linear_correction",
+                max_linear_correction,
+                0.0..=Real::MAX,
+                "The maximum translation applied by one iteration of the position solver.",
+            ),
+            ParamDescriptor::real(
+                "max_angular_correction",
+                max_angular_correction,
+                0.0..=Real::MAX,
+                "The maximum rotation applied by one iteration of the position solver.",
+            ),
+            ParamDescriptor::integer(
+                "max_velocity_iterations",
+                max_velocity_iterations,
+                1.0..=1024.0,
+                "The number of iterations of the velocity solver.",
+            ),
+            ParamDescriptor::integer(
+                "max_position_iterations",
+                max_position_iterations,
+                0.0..=1024.0,
+                "The number of iterations of the position solver.",
+            ),
+            ParamDescriptor::integer(
+                "min_island_size",
+                min_island_size,
+                1.0..=Real::MAX,
+                "The minimum number of dynamic bodies in each active island.",
+            ),
+            ParamDescriptor::integer(
+                "max_ccd_substeps",
+                max_ccd_substeps,
+                1.0..=1024.0,
+                "The maximum number of substeps performed by CCD.",
+            ),
+            ParamDescriptor {
+                name: "restitution_model",
+                value: ParamValue::RestitutionModel(restitution_model),
+                range: None,
+                effect: "The method used to apply restitution on bouncy contacts.",
+            },
+            ParamDescriptor::boolean(
+                "gravity_compensation_on_ground_contacts",
+                gravity_compensation_on_ground_contacts,
+                "Whether ground contacts start the solve with the impulse cancelling gravity.",
+            ),
+            ParamDescriptor::boolean(
+                "joint_chain_ordering",
+                joint_chain_ordering,
+                "Whether joints are solved from the anchored end of joint chains.",
+            ),
+            ParamDescriptor::real(
+                "quasi_static_velocity_threshold",
+                quasi_static_velocity_threshold,
+                0.0..=Real::MAX,
+                "The velocity below which an island is quasi-static and reuses its constraints.",
+            ),
+            ParamDescriptor::integer(
+                "quasi_static_min_steps",
+                quasi_static_min_steps,
+                1.0..=Real::MAX,
+                "The number of quasi-static timesteps before an island reuses its constraints.",
+            ),
+            ParamDescriptor::integer(
+                "quasi_static_velocity_iterations",
+                quasi_static_velocity_iterations,
+                1.0..=1024.0,
+                "The number of velocity iterations of islands reusing their constraints.",
+            ),
+            ParamDescriptor::real(
+                "manifold_freezing_linear_threshold",
+                manifold_freezing_linear_threshold,
+                0.0..=Real::MAX,
+                "The relative translation below which contact manifolds are frozen.",
+            ),
+            ParamDescriptor::real(
+                "manifold_freezing_angular_threshold",
+                manifold_freezing_angular_threshold,
+                0.0..=Real::MAX,
+                "The relative rotation below which contact manifolds are frozen.",
+            ),
+            ParamDescriptor::integer(
+                "manifold_freezing_min_steps",
+                manifold_freezing_min_steps,
+                1.0..=Real::MAX,
+                "The number of still timesteps before contact manifolds are frozen.",
+            ),
+            ParamDescriptor::optional_integer(
+                "parallel_batch_size",
+                parallel_batch_size,
+                1.0..=Real::MAX,
+                "The number of constraints or bodies each thread of the parallel solver grabs at once.",
+            ),
+            ParamDescriptor {
+                name: "position_solve_budget",
+                value: ParamValue::PositionSolveBudget(position_solve_budget),
+                range: None,
+                effect: "The maximum work done by the position solver during a timestep.",
+            },
+            ParamDescriptor::optional_real(
+                "max_effective_mass_ratio",
+                max_effective_mass_ratio,
+                1.0..=Real::MAX,
+                "The maximum ratio between the effective masses of two dynamic bodies in contact.",
+            ),
+            ParamDescriptor::boolean(
+                "use_predicted_kinematic_positions",
+                use_predicted_kinematic_positions,
+                "Whether contacts use the end-of-timestep positions of kinematic bodies.",
+            ),
+            ParamDescriptor::boolean(
+                "friction_anchors",
+                friction_anchors,
+                "Whether sticking contacts are pulled back to where they started sticking.",
+            ),
+            ParamDescriptor::optional_integer(
+                "max_wake_propagation_depth",
+                max_wake_propagation_depth.map(|depth| depth as usize),
+                0.0..=1.0e6,
+                "The maximum number of contacts through which bodies are woken up per timestep.",
+            ),
+            ParamDescriptor::boolean(
+                "contacts_wake_sleeping_bodies",
+                contacts_wake_sleeping_bodies,
+                "Whether the contacts of awake bodies wake up the sleeping bodies they touch.",
+            ),
+            ParamDescriptor::real(
+                "max_linear_velocity",
+                max_linear_velocity,
+                0.0..=Real::MAX,
+                "The maximum linear velocity of the dynamic bodies.",
+            ),
+            ParamDescriptor::real(
+                "max_angular_velocity",
+                max_angular_velocity,
+                0.0..=Real::MAX,
+                "The maximum angular velocity of the dynamic bodies.",
+            ),
+            ParamDescriptor {
+                name: "on_non_finite",
+                value: ParamValue::OnNonFinite(on_non_finite),
+                range: None,
+                effect: "The reaction to the rigid-bodies with a non-finite state.",
+            },
+            ParamDescriptor {
+                name: "pipeline_limits",
+                value: ParamValue::PipelineLimits(pipeline_limits),
+                range: None,
+                effect: "The safety limits protecting the simulation against degenerate scenes.",
+            },
+        ]
+    }
+
+    /// Sets the parameter with the given name, as named by `Self::describe`.
+    ///
+    /// The parameter is left unchanged and an error is returned if there is no parameter with
+    /// this name, if the value doesn't have the type of the parameter, or if the value is outside
+    /// of the range of valid values of the parameter.
+    pub fn set_by_name(&mut self, name: &str, value: ParamValue) -> Result<(), ParamError> {
+        let descriptor = self
+            .describe()
+            .into_iter()
+            .find(|descriptor| descriptor.name == name)
+            .ok_or_else(|| ParamError::UnknownParam(name.to_string()))?;
+
+        if core::mem::discriminant(&descriptor.value) != core::mem::discriminant(&value) {
+            return Err(ParamError::WrongType {
+                name: descriptor.name,
+                expected: descriptor.value,
+                value,
+            });
+        }
+
+        if let (Some(range), Some(real_value)) = (&descriptor.range, value.as_real()) {
+            // NOTE: this also rejects NaNs.
+            if !range.contains(&real_value) {
+                return Err(ParamError::OutOfRange {
+                    name: descriptor.name,
+                    value: real_value,
+                    range: range.clone(),
+                });
+            }
+        }
+
+        match (descriptor.name, value) {
+            ("dt", ParamValue::Real(v)) => self.dt = v,
+            ("min_ccd_dt", ParamValue::Real(v)) => self.min_ccd_dt = v,
+            ("erp", ParamValue::Real(v)) => self.erp = v,
+            ("joint_erp", ParamValue::Real(v)) => self.joint_erp = v,
+            ("warmstart_coeff", ParamValue::Real(v)) => self.warmstart_coeff = v,
+            ("warmstart_correction_slope", ParamValue::Real(v)) => {
+                self.warmstart_correction_slope = v
+            }
+            ("velocity_solve_fraction", ParamValue::Real(v)) => self.velocity_solve_fraction = v,
+            ("velocity_based_erp", ParamValue::Real(v)) => self.velocity_based_erp = v,
+            ("allowed_linear_error", ParamValue::Real(v)) => self.allowed_linear_error = v,
+            ("prediction_distance", ParamValue::Real(v)) => self.prediction_distance = v,
+            ("allowed_angular_error", ParamValue::Real(v)) => self.allowed_angular_error = v,
+            ("max_linear_correction", ParamValue::Real(v)) => self.max_linear_correction = v,
+            ("max_angular_correction", ParamValue::Real(v)) => self.max_angular_correction = v,
+            ("max_velocity_iterations", ParamValue::Integer(v)) => self.max_velocity_iterations = v,
+            ("max_position_iterations", ParamValue::Integer(v)) => self.max_position_iterations = v,
+            ("min_island_size", ParamValue::Integer(v)) => self.min_island_size = v,
+            ("max_ccd_substeps", ParamValue::Integer(v)) => self.max_ccd_substeps = v,
+            ("restitution_model", ParamValue::RestitutionModel(v)) => self.restitution_model = v,
+            ("gravity_compensation_on_ground_contacts", ParamValue::Bool(v)) => {
+                self.gravity_compensation_on_ground_contacts = v
+            }
+            ("joint_chain_ordering", ParamValue::Bool(v)) => self.joint_chain_ordering = v,
+            ("quasi_static_velocity_threshold", ParamValue::Real(v)) => {
+                self.quasi_static_velocity_threshold = v
+            }
+            ("quasi_static_min_steps", ParamValue::Integer(v)) => self.quasi_static_min_steps = v,
+            ("quasi_static_velocity_iterations", ParamValue::Integer(v)) => {
+                self.quasi_static_velocity_iterations = v
+            }
+            ("manifold_freezing_linear_threshold", ParamValue::Real(v)) => {
+                self.manifold_freezing_linear_threshold = v
+            }
+            ("manifold_freezing_angular_threshold", ParamValue::Real(v)) => {
+                self.manifold_freezing_angular_threshold = v
+            }
+            ("manifold_freezing_min_steps", ParamValue::Integer(v)) => {
+                self.manifold_freezing_min_steps = v
+            }
+            ("parallel_batch_size", ParamValue::OptionalInteger(v)) => self.parallel_batch_size = v,
+            ("position_solve_budget", ParamValue::PositionSolveBudget(v)) => {
+                self.position_solve_budget = v
+            }
+            ("max_effective_mass_ratio", ParamValue::OptionalReal(v)) => {
+                self.max_effective_mass_ratio = v
+            }
+            ("use_predicted_kinematic_positions", ParamValue::Bool(v)) => {
+                self.use_predicted_kinematic_positions = v
+            }
+            ("friction_anchors", ParamValue::Bool(v)) => self.friction_anchors = v,
+            ("max_wake_propagation_depth", ParamValue::OptionalInteger(v)) => {
+                self.max_wake_propagation_depth = v.map(|depth| depth as u32)
+            }
+            ("contacts_wake_sleeping_bodies", ParamValue::Bool(v)) => {
+                self.contacts_wake_sleeping_bodies = v
+            }
+            ("max_linear_velocity", ParamValue::Real(v)) => self.max_linear_velocity = v,
+            ("max_angular_velocity", ParamValue::Real(v)) => self.max_angular_velocity = v,
+            ("on_non_finite", ParamValue::OnNonFinite(v)) => self.on_non_finite = v,
+            ("pipeline_limits", ParamValue::PipelineLimits(v)) => self.pipeline_limits = v,
+            _ => unreachable!("Every described parameter must be settable by name."),
+        }
+
+        Ok(())
+    }
+
     /// Convenience: `velocity_based_erp / dt`
     #[inline]
     pub(crate) fn velocity_based_erp_inv_dt(&self) -> Real {
         self.velocity_based_erp * self.inv_dt()
     }
+
+    /// The manifold freezing settings, or `None` if manifold freezing is disabled.
+    pub(crate) fn manifold_freezing(&self) -> Option<ManifoldFreezing> {
+        if self.manifold_freezing_linear_threshold > 0.0 {
+            Some(ManifoldFreezing {
+                linear_threshold: self.manifold_freezing_linear_threshold,
+                angular_threshold: self.manifold_freezing_angular_threshold,
+                min_steps: self.manifold_freezing_min_steps,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Convenience: `erp / dt` if `friction_anchors` is enabled, zero otherwise.
+    #[inline]
+    pub(crate) fn friction_anchor_erp_inv_dt(&self) -> Real {
+        if self.friction_anchors {
+            self.erp * self.inv_dt()
+        } else {
+            0.0
+        }
+    }
 }
 
 impl Default for IntegrationParameters {
@@ -181,6 +997,177 @@ impl Default for IntegrationParameters {
             // tons of islands, reducing SIMD parallelism opportunities.
             min_island_size: 128,
             max_ccd_substeps: 1,
+            restitution_model: RestitutionModel::Rhs,
+            gravity_compensation_on_ground_contacts: false,
+            joint_chain_ordering: false,
+            quasi_static_velocity_threshold: 0.0,
+            quasi_static_min_steps: 10,
+            quasi_static_velocity_iterations: 1,
+            manifold_freezing_linear_threshold: 0.0,
+            manifold_freezing_angular_threshold: 0.0,
+            manifold_freezing_min_steps: 10,
+            parallel_batch_size: None,
+            position_solve_budget: None,
+            max_effective_mass_ratio: None,
+            use_predicted_kinematic_positions: false,
+            friction_anchors: false,
+            max_wake_propagation_depth: None,
+            contacts_wake_sleeping_bodies: true,
+            max_linear_velocity: 1.0e4,
+            max_angular_velocity: 1.0e4,
+            on_non_finite: OnNonFinite::ResetVelocityAndReport,
+            pipeline_limits: PipelineLimits::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{IntegrationParameters, ParamError, ParamValue, PositionSolveBudget};
+    use crate::pipeline::{OnNonFinite, PipelineLimits};
+
+    #[test]
+    fn every_field_is_described() {
+        macro_rules! assert_described {
+            ($($field:ident),*) => {
+                // This fails to compile if a field is missing from the list.
+                let IntegrationParameters { $($field: _),* } = IntegrationParameters::default();
+                let names: Vec<_> = IntegrationParameters::default()
+                    .describe()
+                    .iter()
+                    .map(|descriptor| descriptor.name)
+                    .collect();
+                let fields = [$(stringify!($field)),*];
+
+                assert_eq!(names.len(), fields.len());
+                for field in &fields {
+                    assert!(names.contains(field), "`{}` is not described.", field);
+                }
+            };
+        }
+
+        assert_described!(
+            dt,
+            min_ccd_dt,
+            erp,
+            joint_erp,
+            warmstart_coeff,
+            warmstart_correction_slope,
+            velocity_solve_fraction,
+            velocity_based_erp,
+            allowed_linear_error,
+            prediction_distance,
+            allowed_angular_error,
+            max_linear_correction,
+            max_angular_correction,
+            max_velocity_iterations,
+            max_position_iterations,
+            min_island_size,
+            max_ccd_substeps,
+            restitution_model,
+            gravity_compensation_on_ground_contacts,
+            joint_chain_ordering,
+            quasi_static_velocity_threshold,
+            quasi_static_min_steps,
+            quasi_static_velocity_iterations,
+            manifold_freezing_linear_threshold,
+            manifold_freezing_angular_threshold,
+            manifold_freezing_min_steps,
+            parallel_batch_size,
+            position_solve_budget,
+            max_effective_mass_ratio,
+            use_predicted_kinematic_positions,
+            friction_anchors,
+            max_wake_propagation_depth,
+            contacts_wake_sleeping_bodies,
+            max_linear_velocity,
+            max_angular_velocity,
+            on_non_finite,
+            pipeline_limits
+        );
+    }
+
+    #[test]
+    fn every_described_param_can_be_set_by_name() {
+        let mut params = IntegrationParameters::default();
+
+        for descriptor in params.describe() {
+            let value = match descriptor.value {
+                ParamValue::Real(_) => {
+                    ParamValue::Real(*descriptor.range.as_ref().unwrap().start())
+                }
+                ParamValue::Integer(_) => {
+                    ParamValue::Integer(*descriptor.range.as_ref().unwrap().start() as usize)
+                }
+                ParamValue::OptionalInteger(_) => ParamValue::OptionalInteger(None),
+                ParamValue::OptionalReal(_) => {
+                    ParamValue::OptionalReal(Some(*descriptor.range.as_ref().unwrap().start()))
+                }
+                ParamValue::Bool(value) => ParamValue::Bool(!value),
+                value @ ParamValue::RestitutionModel(_) => value,
+                ParamValue::PositionSolveBudget(_) => ParamValue::PositionSolveBudget(Some(
+                    PositionSolveBudget::ConstraintSolves(100),
+                )),
+                ParamValue::OnNonFinite(_) => ParamValue::OnNonFinite(OnNonFinite::Panic),
+                ParamValue::PipelineLimits(_) => ParamValue::PipelineLimits(PipelineLimits {
+                    max_contact_pairs: 100,
+                    ..PipelineLimits::default()
+                }),
+            };
+
+            params.set_by_name(descriptor.name, value).unwrap();
+            let new_value = params
+                .describe()
+                .into_iter()
+                .find(|new_descriptor| new_descriptor.name == descriptor.name)
+                .unwrap()
+                .value;
+            assert_eq!(new_value, value);
         }
     }
+
+    #[test]
+    fn set_by_name_rejects_invalid_values() {
+        let mut params = IntegrationParameters::default();
+
+        assert_eq!(
+            params.set_by_name("velocity_solve_fraction", ParamValue::Real(7.0)),
+            Err(ParamError::OutOfRange {
+                name: "velocity_solve_fraction",
+                value: 7.0,
+                range: 0.0..=1.0,
+            })
+        );
+        assert_eq!(params.velocity_solve_fraction, 1.0);
+
+        assert!(params
+            .set_by_name("erp", ParamValue::Real(crate::math::Real::NAN))
+            .is_err());
+        assert!(params
+            .set_by_name("max_velocity_iterations", ParamValue::Integer(0))
+            .is_err());
+        assert!(params
+            .set_by_name("max_velocity_iterations", ParamValue::Real(8.0))
+            .is_err());
+        assert_eq!(
+            params.set_by_name("velocity_fraction", ParamValue::Real(0.5)),
+            Err(ParamError::UnknownParam("velocity_fraction".to_string()))
+        );
+        assert_eq!(params.max_velocity_iterations, 4);
+        assert_eq!(params.erp, 0.2);
+
+        params
+            .set_by_name("velocity_solve_fraction", ParamValue::Real(0.5))
+            .unwrap();
+        assert_eq!(params.velocity_solve_fraction, 0.5);
+    }
+
+    #[test]
+    fn spring_erp_cfm_roundtrip() {
+        let dt = 1.0 / 60.0;
+        let (erp, cfm) = IntegrationParameters::spring_to_erp_cfm(100.0, 5.0, dt);
+        let (stiffness, damping) = IntegrationParameters::erp_cfm_to_spring(erp, cfm, dt);
+        assert!((stiffness - 100.0).abs() < 1.0e-2);
+        assert!((damping - 5.0).abs() < 1.0e-3);
+    }
 }
@@ -5,6 +5,7 @@ use crate::dynamics::{
 };
 use crate::geometry::{ColliderParent, InteractionGraph, NarrowPhase};
 use crate::math::Real;
+use alloc::{vec, vec::Vec};
 
 /// Structure responsible for maintaining the set of active rigid-bodies, and
 /// putting non-moving rigid-bodies to sleep to save computation times.
@@ -144,16 +145,16 @@ impl IslandManager {
     ) where
         Set: ComponentSet<T>,
     {
-        use std::sync::atomic::Ordering;
+        use core::sync::atomic::Ordering;
 
         let island_range = self.active_islands[island_id]..self.active_islands[island_id + 1];
-        let bodies = std::sync::atomic::AtomicPtr::new(&mut bodies as *mut _);
+        let bodies = core::sync::atomic::AtomicPtr::new(&mut bodies as *mut _);
         self.active_dynamic_set[island_range]
             .par_iter()
             .for_each_init(
                 || bodies.load(Ordering::Relaxed),
                 |bodies, handle| {
-                    let bodies: &mut Set = unsafe { std::mem::transmute(*bodies) };
+                    let bodies: &mut Set = unsafe { core::mem::transmute(*bodies) };
                     if let Some(rb) = bodies.get_mut_internal(handle.0) {
                         f(*handle, rb)
                     }
@@ -163,7 +164,7 @@ impl IslandManager {
      */
 
     #[cfg(feature = "parallel")]
-    pub(crate) fn active_island_range(&self, island_id: usize) -> std::ops::Range<usize> {
+    pub(crate) fn active_island_range(&self, island_id: usize) -> core::ops::Range<usize> {
         self.active_islands[island_id]..self.active_islands[island_id + 1]
     }
 
@@ -1,4 +1,4 @@
-use crate::dynamics::SpringModel;
+use crate::dynamics::{RigidBody, SpringModel};
 use crate::math::{Point, Real, Rotation, Vector};
 
 #[derive(Copy, Clone)]
@@ -46,6 +46,14 @@ impl BallJoint {
         Self::with_impulse(local_anchor1, local_anchor2, Vector::zeros())
     }
 
+    /// Creates a new [`BallJoint`] attached at the world-space point `world_anchor`, given the
+    /// current positions of the two bodies it will be attached to.
+    pub fn from_world_anchor(rb1: &RigidBody, rb2: &RigidBody, world_anchor: Point<Real>) -> Self {
+        let local_anchor1 = rb1.position().inverse_transform_point(&world_anchor);
+        let local_anchor2 = rb2.position().inverse_transform_point(&world_anchor);
+        Self::new(local_anchor1, local_anchor2)
+    }
+
     pub(crate) fn with_impulse(
         local_anchor1: Point<Real>,
         local_anchor2: Point<Real>,
@@ -1,3 +1,4 @@
+use crate::dynamics::RigidBody;
 use crate::math::{Isometry, Real, SpacialVector};
 
 #[derive(Copy, Clone)]
@@ -31,6 +32,18 @@ impl FixedJoint {
         }
     }
 
+    /// Creates a new fixed joint whose frames of reference coincide with the world-space frame
+    /// `world_anchor`, given the current positions of the two bodies it will be attached to.
+    pub fn from_world_anchor(
+        rb1: &RigidBody,
+        rb2: &RigidBody,
+        world_anchor: Isometry<Real>,
+    ) -> Self {
+        let local_anchor1 = rb1.position().inv_mul(&world_anchor);
+        let local_anchor2 = rb2.position().inv_mul(&world_anchor);
+        Self::new(local_anchor1, local_anchor2)
+    }
+
     /// Can a SIMD constraint be used for resolving this joint?
     pub fn supports_simd_constraints(&self) -> bool {
         true
@@ -0,0 +1,70 @@
+use crate::math::Real;
+#[cfg(feature = "dim3")]
+use crate::math::Vector;
+#[cfg(feature = "dim3")]
+use na::Unit;
+
+#[derive(Copy, Clone)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+/// A joint that couples the rotation speeds of two bodies, like a pair of gears.
+///
+/// This joint ensures that the angular velocity of the first body along `local_axis1` is
+/// `ratio` times the angular velocity of the second body along `local_axis2`. It is a
+/// velocity-level coupling that does not remove any degree of freedom by itself, so it is
+/// typically used between two bodies attached to a common chassis by revolute joints.
+///
+/// The angular velocities are measured in world-space so the chassis should not rotate
+/// along the axes of the gears.
+pub struct GearJoint {
+    /// The rotation axis of the first body, expressed in its local space.
+    #[cfg(feature = "dim3")]
+    pub local_axis1: Unit<Vector<Real>>,
+    /// The rotation axis of the second body, expressed in its local space.
+    #[cfg(feature = "dim3")]
+    pub local_axis2: Unit<Vector<Real>>,
+    /// The ratio between the rotation speeds of the first and second bodies.
+    ///
+    /// A negative ratio makes both bodies rotate in opposite directions, like meshing gears.
+    pub ratio: Real,
+    /// The angular impulse applied by this joint on the first body along its axis.
+    ///
+    /// The angular impulse applied to the second body along its axis is given by `-ratio * impulse`.
+    pub impulse: Real,
+    // The angle the bodies drifted from the ratio because of the solver's inaccuracies.
+    pub(crate) drift: Real,
+}
+
+impl GearJoint {
+    /// Creates a new gear joint with the given rotation axes, expressed in the local-space
+    /// of the affected bodies, and ratio between their rotation speeds.
+    #[cfg(feature = "dim3")]
+    pub fn new(
+        local_axis1: Unit<Vector<Real>>,
+        local_axis2: Unit<Vector<Real>>,
+        ratio: Real,
+    ) -> Self {
+        Self {
+            local_axis1,
+            local_axis2,
+            ratio,
+            impulse: 0.0,
+            drift: 0.0,
+        }
+    }
+
+    /// Creates a new gear joint with the given ratio between the rotation speeds of the
+    /// affected bodies.
+    #[cfg(feature = "dim2")]
+    pub fn new(ratio: Real) -> Self {
+        Self {
+            ratio,
+            impulse: 0.0,
+            drift: 0.0,
+        }
+    }
+
+    /// Can a SIMD constraint be used for resolving this joint?
+    pub fn supports_simd_constraints(&self) -> bool {
+        false
+    }
+}
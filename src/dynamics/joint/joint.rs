@@ -1,6 +1,11 @@
 #[cfg(feature = "dim3")]
 use crate::dynamics::RevoluteJoint;
-use crate::dynamics::{BallJoint, FixedJoint, JointHandle, PrismaticJoint, RigidBodyHandle};
+use crate::dynamics::{
+    BallJoint, FixedJoint, GearJoint, JointHandle, PrismaticJoint, PulleyJoint, RigidBody,
+    RigidBodyHandle,
+};
+use crate::math::{Isometry, Point, Real, Vector};
+use na::Unit;
 
 #[derive(Copy, Clone)]
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
@@ -18,6 +23,10 @@ pub enum JointParams {
     /// rotation along one axis.
     RevoluteJoint(RevoluteJoint),
     // GenericJoint(GenericJoint),
+    /// A gear joint that couples the rotation speeds of two bodies.
+    GearJoint(GearJoint),
+    /// A pulley joint that connects two bodies with a rope running through two fixed pulleys.
+    PulleyJoint(PulleyJoint),
 }
 
 impl JointParams {
@@ -30,6 +39,8 @@ impl JointParams {
             // JointParams::GenericJoint(_) => 3,
             #[cfg(feature = "dim3")]
             JointParams::RevoluteJoint(_) => 4,
+            JointParams::GearJoint(_) => 5,
+            JointParams::PulleyJoint(_) => 6,
         }
     }
 
@@ -78,6 +89,53 @@ impl JointParams {
             None
         }
     }
+
+    /// Gets a reference to the underlying gear joint, if `self` is one.
+    pub fn as_gear_joint(&self) -> Option<&GearJoint> {
+        if let JointParams::GearJoint(j) = self {
+            Some(j)
+        } else {
+            None
+        }
+    }
+
+    /// Gets a reference to the underlying pulley joint, if `self` is one.
+    pub fn as_pulley_joint(&self) -> Option<&PulleyJoint> {
+        if let JointParams::PulleyJoint(j) = self {
+            Some(j)
+        } else {
+            None
+        }
+    }
+
+    /// Sets to zero the impulses accumulated by the solver for this joint.
+    ///
+    /// The impulses are expressed differently by the constraints attached to two dynamic bodies
+    /// and by the constraints attached to a single dynamic body, so they can't be used to
+    /// warmstart the solver once the status of one of the attached bodies changed.
+    pub(crate) fn reset_impulses(&mut self) {
+        match self {
+            JointParams::BallJoint(j) => {
+                j.impulse = na::zero();
+                j.motor_impulse = na::zero();
+            }
+            JointParams::FixedJoint(j) => j.impulse = na::zero(),
+            JointParams::PrismaticJoint(j) => {
+                j.impulse = na::zero();
+                j.limits_impulse = 0.0;
+                j.motor_impulse = 0.0;
+            }
+            #[cfg(feature = "dim3")]
+            JointParams::RevoluteJoint(j) => {
+                j.impulse = na::zero();
+                j.world_ang_impulse = na::zero();
+                j.limits_impulse = 0.0;
+                j.motor_impulse = 0.0;
+            }
+            JointParams::GearJoint(j) => j.impulse = 0.0,
+            JointParams::PulleyJoint(j) => j.impulse = 0.0,
+        }
+    }
 }
 
 impl From<BallJoint> for JointParams {
@@ -111,6 +169,66 @@ impl From<PrismaticJoint> for JointParams {
     }
 }
 
+impl From<GearJoint> for JointParams {
+    fn from(j: GearJoint) -> Self {
+        JointParams::GearJoint(j)
+    }
+}
+
+impl From<PulleyJoint> for JointParams {
+    fn from(j: PulleyJoint) -> Self {
+        JointParams::PulleyJoint(j)
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+/// The description of a joint with its anchor and axis expressed in world-space.
+///
+/// See `JointSet::insert_at_world`.
+pub enum WorldJointParams {
+    /// A ball joint attached at the given world-space point.
+    BallJoint(Point<Real>),
+    /// A fixed joint whose frames of reference coincide with the given world-space frame.
+    FixedJoint(Isometry<Real>),
+    /// A prismatic joint attached at the given world-space point, free to translate along the
+    /// given world-space axis.
+    PrismaticJoint(Point<Real>, Unit<Vector<Real>>),
+    #[cfg(feature = "dim3")]
+    /// A revolute joint attached at the given world-space point, free to rotate along the
+    /// given world-space axis.
+    RevoluteJoint(Point<Real>, Unit<Vector<Real>>),
+}
+
+impl WorldJointParams {
+    /// Computes the joint parameters with anchors and axes expressed in the local-spaces of the
+    /// given bodies, at their current positions.
+    pub fn to_local(&self, rb1: &RigidBody, rb2: &RigidBody) -> JointParams {
+        match *self {
+            WorldJointParams::BallJoint(anchor) => {
+                BallJoint::from_world_anchor(rb1, rb2, anchor).into()
+            }
+            WorldJointParams::FixedJoint(anchor) => {
+                FixedJoint::from_world_anchor(rb1, rb2, anchor).into()
+            }
+            WorldJointParams::PrismaticJoint(anchor, axis) => {
+                PrismaticJoint::from_world_anchor(rb1, rb2, anchor, axis).into()
+            }
+            #[cfg(feature = "dim3")]
+            WorldJointParams::RevoluteJoint(anchor, axis) => {
+                RevoluteJoint::from_world_anchor(rb1, rb2, anchor, axis).into()
+            }
+        }
+    }
+}
+
+// Panics if the given joint axis isn't normalized, e.g., if it was built with `Unit::new_unchecked`.
+pub(crate) fn assert_unit_axis(axis: &Unit<Vector<Real>>) {
+    assert!(
+        (axis.norm_squared() - 1.0).abs() <= 1.0e-4,
+        "The axis of a joint must have a unit length."
+    );
+}
+
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 #[derive(Clone)]
 /// A joint attached to two bodies.
@@ -125,8 +243,21 @@ pub struct Joint {
     pub(crate) constraint_index: usize,
     #[cfg(feature = "parallel")]
     pub(crate) position_constraint_index: usize,
+    // The distance, in the joint graph, to the closest non-dynamic body. This is
+    // zero for all the joints if `IntegrationParameters::joint_chain_ordering` is disabled.
+    pub(crate) chain_depth: u32,
+    // Whether the first and second bodies were dynamic when the constraints of this
+    // joint were last built.
+    pub(crate) dynamic_bodies: (bool, bool),
     /// The joint geometric parameters and impulse.
     pub params: JointParams,
+    /// The number of additional velocity iterations performed on this joint (default: `0`).
+    ///
+    /// After the `IntegrationParameters::max_velocity_iterations` iterations of the whole island,
+    /// this joint and the contacts of the dynamic bodies it is attached to are solved this number
+    /// of additional times. This stiffens heavily-loaded joints, e.g., the motors of a robot arm
+    /// carrying a crate, without paying for more iterations on the rest of the scene.
+    pub extra_solver_iterations: u8,
 }
 
 impl Joint {
@@ -138,6 +269,8 @@ impl Joint {
             JointParams::BallJoint(joint) => joint.supports_simd_constraints(),
             #[cfg(feature = "dim3")]
             JointParams::RevoluteJoint(joint) => joint.supports_simd_constraints(),
+            JointParams::GearJoint(joint) => joint.supports_simd_constraints(),
+            JointParams::PulleyJoint(joint) => joint.supports_simd_constraints(),
         }
     }
 }
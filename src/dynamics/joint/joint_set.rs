@@ -2,7 +2,16 @@ use super::Joint;
 use crate::geometry::{InteractionGraph, RigidBodyGraphIndex, TemporaryInteractionIndex};
 
 use crate::data::arena::Arena;
-use crate::dynamics::{JointParams, RigidBodyHandle, RigidBodySet};
+use crate::data::{HashMap, HashSet};
+use crate::dynamics::{
+    BodyPair, JointParams, RigidBodyHandle, RigidBodySet, SpringModel, WorldJointParams,
+};
+use crate::geometry::{ColliderSet, NarrowPhase};
+use crate::math::{AngVector, AngularInertia, Point, Real, Vector, DIM};
+use crate::utils::{WAngularInertia, WBasis, WCross, WDot};
+use alloc::collections::VecDeque;
+use alloc::{vec, vec::Vec};
+use na::{DMatrix, DVector};
 
 /// The unique identifier of a joint added to the joint set.
 /// The unique identifier of a collider added to a collider set.
@@ -31,6 +40,21 @@ impl JointHandle {
     }
 }
 
+/// The force or torque a joint motor has to apply to hold its joint stationary.
+///
+/// See [`JointSet::compute_holding_forces`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct JointHoldingForce {
+    /// The force (for prismatic joints) or torque (for ball and revolute joints) the motor has
+    /// to apply on the second body attached to the joint.
+    ///
+    /// This is signed along the joint axis of the first body, except for 3D ball joints where
+    /// this is the magnitude of the torque.
+    pub force: Real,
+    /// Can the motor apply this force, i.e., is it not greater than `motor_max_impulse / dt`?
+    pub holdable: bool,
+}
+
 pub(crate) type JointIndex = usize;
 pub(crate) type JointGraphEdge = crate::data::graph::Edge<Joint>;
 
@@ -41,6 +65,10 @@ pub struct JointSet {
     // Map joint handles to edge ids on the graph.
     joint_ids: Arena<TemporaryInteractionIndex>,
     joint_graph: InteractionGraph<RigidBodyHandle, Joint>,
+    // Are the joints chain depths computed for `IntegrationParameters::joint_chain_ordering`?
+    chain_ordering: bool,
+    // Was the joint graph modified since the last computation of the joints chain depths?
+    chain_depths_outdated: bool,
 }
 
 impl JointSet {
@@ -49,9 +77,35 @@ impl JointSet {
         Self {
             joint_ids: Arena::new(),
             joint_graph: InteractionGraph::new(),
+            chain_ordering: false,
+            chain_depths_outdated: false,
         }
     }
 
+    /// Creates a new empty set of joints, with enough room for `capacity` joints.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut result = Self::new();
+        result.reserve(capacity);
+        result
+    }
+
+    /// Reserves room for inserting at least `additional` more joints without reallocating.
+    ///
+    /// This doesn't affect the handles given to the next inserted joints.
+    pub fn reserve(&mut self, additional: usize) {
+        let num_free = self.joint_ids.capacity() - self.joint_ids.len();
+        self.joint_ids.reserve(additional.saturating_sub(num_free));
+        self.joint_graph.graph.edges.reserve(additional);
+    }
+
+    /// Releases the excess capacity of this set, e.g., after the removal of many joints.
+    ///
+    /// This doesn't invalidate any joint handle.
+    pub fn shrink_to_fit(&mut self) {
+        self.joint_ids.shrink_to_fit();
+        self.joint_graph.graph.shrink_to_fit();
+    }
+
     /// The number of joints on this set.
     pub fn len(&self) -> usize {
         self.joint_graph.graph.edges.len()
@@ -167,7 +221,7 @@ impl JointSet {
         J: Into<JointParams>,
     {
         let handle = self.joint_ids.insert(0.into());
-        let joint = Joint {
+        let mut joint = Joint {
             body1,
             body2,
             handle: JointHandle(handle),
@@ -175,7 +229,10 @@ impl JointSet {
             constraint_index: 0,
             #[cfg(feature = "parallel")]
             position_constraint_index: 0,
+            chain_depth: 0,
+            dynamic_bodies: (true, true),
             params: joint_params.into(),
+            extra_solver_iterations: 0,
         };
 
         let (rb1, rb2) = bodies.get2_mut_internal(joint.body1, joint.body2);
@@ -183,6 +240,7 @@ impl JointSet {
             rb1.expect("Attempt to attach a joint to a non-existing body."),
             rb2.expect("Attempt to attach a joint to a non-existing body."),
         );
+        joint.dynamic_bodies = (rb1.is_dynamic(), rb2.is_dynamic());
 
         // NOTE: the body won't have a graph index if it does not
         // have any joint attached.
@@ -201,16 +259,268 @@ impl JointSet {
             .add_edge(rb1.joint_graph_index, rb2.joint_graph_index, joint);
 
         self.joint_ids[handle] = id;
+        self.chain_depths_outdated = true;
         JointHandle(handle)
     }
 
+    /// Inserts a new joint, with its anchor and axis expressed in world-space, into this set and
+    /// retrieve its handle.
+    ///
+    /// The anchors and axes of the joint are converted into the local-spaces of the two bodies
+    /// at their current positions, so the joint is satisfied at insertion. Panics if one of the
+    /// bodies doesn't exist, or if the axis of the joint isn't of unit length.
+    pub fn insert_at_world(
+        &mut self,
+        bodies: &mut RigidBodySet,
+        body1: RigidBodyHandle,
+        body2: RigidBodyHandle,
+        world_params: WorldJointParams,
+    ) -> JointHandle {
+        let (rb1, rb2) = (
+            bodies
+                .get(body1)
+                .expect("Attempt to attach a joint to a non-existing body."),
+            bodies
+                .get(body2)
+                .expect("Attempt to attach a joint to a non-existing body."),
+        );
+        let params = world_params.to_local(rb1, rb2);
+        self.insert(bodies, body1, body2, params)
+    }
+
+    /// All the joints attached to at least one rigid-body of the given active island.
+    ///
+    /// Each joint is yielded only once, even if both its bodies belong to the island.
+    /// The island indices are the ones computed during the last call to
+    /// `PhysicsPipeline::step`, so this should be called right after the step, before
+    /// any body is added, removed, or woken up.
+    pub fn island_joints<'a>(
+        &'a self,
+        island_id: usize,
+        bodies: &'a RigidBodySet,
+    ) -> impl Iterator<Item = &'a Joint> + 'a {
+        bodies
+            .active_island(island_id)
+            .iter()
+            .filter_map(move |handle| Some((*handle, bodies.get(*handle)?)))
+            .filter(|(_, rb)| {
+                InteractionGraph::<RigidBodyHandle, Joint>::is_graph_index_valid(
+                    rb.joint_graph_index,
+                )
+            })
+            .flat_map(move |(handle, rb)| {
+                self.joint_graph
+                    .interactions_with(rb.joint_graph_index)
+                    .filter_map(move |(_, _, joint)| {
+                        let other = if joint.body1 == handle {
+                            joint.body2
+                        } else {
+                            joint.body1
+                        };
+                        let other_in_island = bodies.get(other).map_or(false, |rb2| {
+                            rb2.is_dynamic()
+                                && !rb2.is_sleeping()
+                                && rb2.active_island_id == island_id
+                        });
+
+                        // If both bodies are part of this island, only yield
+                        // the joint when visiting its first body.
+                        if !other_in_island || joint.body1 == handle {
+                            Some(joint)
+                        } else {
+                            None
+                        }
+                    })
+            })
+    }
+
+    /// Computes the force each motorized joint has to apply to hold its bodies stationary under
+    /// gravity, the forces applied by the user, and the current contacts.
+    ///
+    /// This assembles the rows of all the joints and contacts connected, through dynamic bodies,
+    /// to the motorized joints, and solves for the constraint forces balancing the external
+    /// forces. The motors lock their joint axis, and the contacts act as fixed supports: they
+    /// can pull as well as push. The limits of the joints, as well as the gear and pulley
+    /// joints, are ignored. Nothing is modified, so this can be called at any time.
+    ///
+    /// Joints are motorized if their `motor_max_impulse` is positive, their motor model isn't
+    /// `SpringModel::Disabled`, and their motor stiffness or damping is non-zero.
+    pub fn compute_holding_forces(
+        &self,
+        bodies: &RigidBodySet,
+        colliders: &ColliderSet,
+        narrow_phase: &NarrowPhase,
+        gravity: &Vector<Real>,
+        dt: Real,
+    ) -> HashMap<JointHandle, JointHoldingForce> {
+        let mut system = HoldingSystem::new();
+        let mut to_visit: Vec<_> = self
+            .iter()
+            .filter(|(_, joint)| joint_motor(&joint.params).is_some())
+            .flat_map(|(_, joint)| vec![joint.body1, joint.body2])
+            .collect();
+        let mut joints = Vec::new();
+        let mut visited_joints = HashSet::default();
+        let mut contact_pairs = Vec::new();
+        let mut visited_contact_pairs = HashSet::default();
+
+        // Collect the dynamic bodies, joints, and contacts, connected to the motorized joints.
+        while let Some(handle) = to_visit.pop() {
+            let rb = match bodies.get(handle) {
+                Some(rb) if rb.is_dynamic() && !system.body_ids.contains_key(&handle) => rb,
+                _ => continue,
+            };
+
+            let inv_inertia = rb.effective_world_inv_inertia_sqrt.squared();
+            let force = gravity * (rb.mass() * rb.gravity_scale) + rb.force;
+            system.body_ids.insert(handle, system.bodies.len());
+            system.bodies.push(HoldingBody {
+                world_com: rb.world_com,
+                effective_inv_mass: rb.effective_inv_mass,
+                inv_inertia,
+                linvel_change: rb.effective_inv_mass.component_mul(&force),
+                angvel_change: inv_inertia.transform_vector(rb.torque),
+            });
+
+            if InteractionGraph::<RigidBodyHandle, Joint>::is_graph_index_valid(
+                rb.joint_graph_index,
+            ) {
+                for (h1, h2, joint) in self.joint_graph.interactions_with(rb.joint_graph_index) {
+                    if visited_joints.insert(joint.handle) {
+                        joints.push(joint);
+                        to_visit.push(h1);
+                        to_visit.push(h2);
+                    }
+                }
+            }
+
+            for collider in &rb.colliders {
+                for (c1, c2, pair) in narrow_phase.contacts_with(*collider).into_iter().flatten() {
+                    if pair.links_bodies() && visited_contact_pairs.insert((c1, c2)) {
+                        contact_pairs.push(pair);
+                        to_visit.extend(colliders.get(c1).map(|c| c.parent()));
+                        to_visit.extend(colliders.get(c2).map(|c| c.parent()));
+                    }
+                }
+            }
+        }
+
+        // (joint handle, first motor row, number of motor rows, max motor impulse)
+        let mut motors = Vec::new();
+
+        for joint in joints {
+            let (b1, b2) = (joint.body1, joint.body2);
+            let (pos1, pos2) = (bodies[b1].position(), bodies[b2].position());
+            let motor = joint_motor(&joint.params);
+
+            match &joint.params {
+                JointParams::BallJoint(ball) => {
+                    let anchor = pos2 * ball.local_anchor2;
+                    system.push_linear_rows(b1, b2, &anchor);
+
+                    if let Some(max_impulse) = motor {
+                        motors.push((joint.handle, system.rows.len(), ANG_DIM, max_impulse));
+                        system.push_angular_rows(b1, b2);
+                    }
+                }
+                JointParams::FixedJoint(fixed) => {
+                    let anchor = Point::from((pos2 * fixed.local_anchor2).translation.vector);
+                    system.push_linear_rows(b1, b2, &anchor);
+                    system.push_angular_rows(b1, b2);
+                }
+                #[cfg(feature = "dim3")]
+                JointParams::RevoluteJoint(revolute) => {
+                    let anchor = pos2 * revolute.local_anchor2;
+                    let axis = pos1 * revolute.local_axis1;
+                    system.push_linear_rows(b1, b2, &anchor);
+
+                    for dir in axis.orthonormal_basis().iter() {
+                        system.push_angular_row(b1, b2, *dir);
+                    }
+
+                    if let Some(max_impulse) = motor {
+                        motors.push((joint.handle, system.rows.len(), 1, max_impulse));
+                        system.push_angular_row(b1, b2, *axis);
+                    }
+                }
+                JointParams::PrismaticJoint(prismatic) => {
+                    let anchor = pos2 * prismatic.local_anchor2;
+                    let axis = pos1 * prismatic.local_axis1;
+
+                    for dir in axis.orthonormal_basis().iter() {
+                        system.push_linear_row(b1, b2, &anchor, *dir);
+                    }
+
+                    system.push_angular_rows(b1, b2);
+
+                    if let Some(max_impulse) = motor {
+                        motors.push((joint.handle, system.rows.len(), 1, max_impulse));
+                        system.push_linear_row(b1, b2, &anchor, *axis);
+                    }
+                }
+                JointParams::GearJoint(_) | JointParams::PulleyJoint(_) => {}
+            }
+        }
+
+        for pair in contact_pairs {
+            let parent1 = colliders.get(pair.pair.collider1).map(|c| c.parent());
+            let parent2 = colliders.get(pair.pair.collider2).map(|c| c.parent());
+
+            if let (Some(b1), Some(b2)) = (parent1, parent2) {
+                for manifold in pair.manifolds.iter().filter(|m| m.data.links_bodies()) {
+                    for contact in &manifold.data.solver_contacts {
+                        system.push_linear_rows(b1, b2, &contact.point);
+                    }
+                }
+            }
+        }
+
+        let forces = system.solve();
+
+        motors
+            .into_iter()
+            .map(|(handle, first_row, num_rows, max_impulse)| {
+                let motor_forces = forces.rows(first_row, num_rows);
+                let force = if num_rows == 1 {
+                    motor_forces[0]
+                } else {
+                    motor_forces.norm()
+                };
+                let holdable = force.abs() * dt <= max_impulse;
+                (handle, JointHoldingForce { force, holdable })
+            })
+            .collect()
+    }
+
     /// Retrieve all the joints happening between two active bodies.
     // NOTE: this is very similar to the code from NarrowPhase::select_active_interactions.
     pub(crate) fn select_active_interactions(
-        &self,
+        &mut self,
         bodies: &RigidBodySet,
+        chain_ordering: bool,
         out: &mut Vec<Vec<JointIndex>>,
     ) {
+        // The constraints of the joints attached to a body which switched between dynamic
+        // and non-dynamic change from one variant to the other, so their impulses can't be
+        // reused. The chain depths depend on the non-dynamic bodies too.
+        for edge in &mut self.joint_graph.graph.edges {
+            let joint = &mut edge.weight;
+            let dynamic_bodies = (
+                bodies[joint.body1].is_dynamic(),
+                bodies[joint.body2].is_dynamic(),
+            );
+
+            if dynamic_bodies != joint.dynamic_bodies {
+                joint.dynamic_bodies = dynamic_bodies;
+                joint.params.reset_impulses();
+                self.chain_depths_outdated = true;
+            }
+        }
+
+        if chain_ordering != self.chain_ordering || (chain_ordering && self.chain_depths_outdated) {
+            self.update_chain_depths(bodies, chain_ordering);
+        }
+
         for out_island in &mut out[..bodies.num_islands()] {
             out_island.clear();
         }
@@ -221,7 +531,7 @@ impl JointSet {
             let rb1 = &bodies[joint.body1];
             let rb2 = &bodies[joint.body2];
 
-            if (rb1.is_dynamic() || rb2.is_dynamic())
+            if (rb1.is_dynamic_with_mass() || rb2.is_dynamic_with_mass())
                 && (!rb1.is_dynamic() || !rb1.is_sleeping())
                 && (!rb2.is_dynamic() || !rb2.is_sleeping())
             {
@@ -234,6 +544,68 @@ impl JointSet {
                 out[island_index].push(i);
             }
         }
+
+        // The joints involving bodies with a higher solver priority are solved last. Joints with
+        // the same priority are solved in the order of their chain depth.
+        // NOTE: the sort is stable so this doesn't change anything if all the priorities are equal.
+        let edges = &self.joint_graph.graph.edges;
+        for out_island in &mut out[..bodies.num_islands()] {
+            out_island.sort_by_key(|i| {
+                let joint = &edges[*i].weight;
+                let priority = bodies.pair_solver_priority(BodyPair::new(joint.body1, joint.body2));
+                (priority, joint.chain_depth)
+            });
+        }
+    }
+
+    /// Computes the chain depth of each joint, i.e., its distance in the joint graph to the
+    /// closest non-dynamic body.
+    ///
+    /// If `chain_ordering` is `false`, all the chain depths are set to zero instead.
+    fn update_chain_depths(&mut self, bodies: &RigidBodySet, chain_ordering: bool) {
+        let graph = &mut self.joint_graph.graph;
+        self.chain_ordering = chain_ordering;
+        self.chain_depths_outdated = false;
+
+        if !chain_ordering {
+            for edge in &mut graph.edges {
+                edge.weight.chain_depth = 0;
+            }
+            return;
+        }
+
+        // Breadth-first search starting from all the non-dynamic bodies.
+        let mut node_depths = vec![u32::MAX; graph.nodes.len()];
+        let mut queue = VecDeque::new();
+
+        for (i, node) in graph.nodes.iter().enumerate() {
+            if bodies.get(node.weight).map_or(true, |rb| !rb.is_dynamic()) {
+                node_depths[i] = 0;
+                queue.push_back(RigidBodyGraphIndex::new(i as u32));
+            }
+        }
+
+        while let Some(node) = queue.pop_front() {
+            let depth = node_depths[node.index()];
+
+            for edge in graph.edges(node) {
+                if let Some((id1, id2)) = graph.edge_endpoints(edge.id()) {
+                    let other = if id1 == node { id2 } else { id1 };
+
+                    if node_depths[other.index()] == u32::MAX {
+                        node_depths[other.index()] = depth + 1;
+                        queue.push_back(other);
+                    }
+                }
+            }
+        }
+
+        // The joints that are not connected to any non-dynamic body are solved last.
+        for edge in &mut graph.edges {
+            let depth1 = node_depths[edge.source().index()];
+            let depth2 = node_depths[edge.target().index()];
+            edge.weight.chain_depth = depth1.min(depth2);
+        }
     }
 
     /// Removes a joint from this set.
@@ -260,6 +632,7 @@ impl JointSet {
         }
 
         let removed_joint = self.joint_graph.graph.remove_edge(id);
+        self.chain_depths_outdated = true;
 
         if let Some(edge) = self.joint_graph.graph.edge_weight(id) {
             self.joint_ids[edge.handle.0] = id;
@@ -268,11 +641,14 @@ impl JointSet {
         removed_joint
     }
 
+    /// Removes all the joints attached to the given rigid-body and returns them.
     pub(crate) fn remove_rigid_body(
         &mut self,
         deleted_id: RigidBodyGraphIndex,
         bodies: &mut RigidBodySet,
-    ) {
+    ) -> Vec<Joint> {
+        let mut removed_joints = Vec::new();
+
         if InteractionGraph::<(), ()>::is_graph_index_valid(deleted_id) {
             // We have to delete each joint one by one in order to:
             // - Wake-up the attached bodies.
@@ -285,7 +661,8 @@ impl JointSet {
                 .collect();
             for (h1, h2, to_delete_handle) in to_delete {
                 let to_delete_edge_id = self.joint_ids.remove(to_delete_handle.0).unwrap();
-                self.joint_graph.graph.remove_edge(to_delete_edge_id);
+                removed_joints.extend(self.joint_graph.graph.remove_edge(to_delete_edge_id));
+                self.chain_depths_outdated = true;
 
                 // Update the id of the edge which took the place of the deleted one.
                 if let Some(j) = self.joint_graph.graph.edge_weight_mut(to_delete_edge_id) {
@@ -305,5 +682,295 @@ impl JointSet {
                 }
             }
         }
+
+        removed_joints
+    }
+}
+
+#[cfg(feature = "dim2")]
+const ANG_DIM: usize = 1;
+#[cfg(feature = "dim3")]
+const ANG_DIM: usize = 3;
+
+// The max impulse of the motor of the given joint, if it is motorized.
+fn joint_motor(params: &JointParams) -> Option<Real> {
+    let (max_impulse, model, stiffness, damping) = match params {
+        JointParams::BallJoint(j) => (
+            j.motor_max_impulse,
+            j.motor_model,
+            j.motor_stiffness,
+            j.motor_damping,
+        ),
+        #[cfg(feature = "dim3")]
+        JointParams::RevoluteJoint(j) => (
+            j.motor_max_impulse,
+            j.motor_model,
+            j.motor_stiffness,
+            j.motor_damping,
+        ),
+        JointParams::PrismaticJoint(j) => (
+            j.motor_max_impulse,
+            j.motor_model,
+            j.motor_stiffness,
+            j.motor_damping,
+        ),
+        JointParams::FixedJoint(_) | JointParams::GearJoint(_) | JointParams::PulleyJoint(_) => {
+            return None
+        }
+    };
+
+    if max_impulse > 0.0 && model != SpringModel::Disabled && (stiffness != 0.0 || damping != 0.0) {
+        Some(max_impulse)
+    } else {
+        None
+    }
+}
+
+// A dynamic body taken into account by `JointSet::compute_holding_forces`.
+struct HoldingBody {
+    world_com: Point<Real>,
+    effective_inv_mass: Vector<Real>,
+    inv_inertia: AngularInertia<Real>,
+    // The velocity changes due to the external forces, per unit of time.
+    linvel_change: Vector<Real>,
+    angvel_change: AngVector<Real>,
+}
+
+// The linear system solved by `JointSet::compute_holding_forces`.
+//
+// Each row of the jacobian only contains the (linear, angular) parts of its dynamic bodies.
+// Its multiplier is the force applied on its second body, the opposite being applied on the first.
+struct HoldingSystem {
+    body_ids: HashMap<RigidBodyHandle, usize>,
+    bodies: Vec<HoldingBody>,
+    rows: Vec<Vec<(usize, Vector<Real>, AngVector<Real>)>>,
+}
+
+impl HoldingSystem {
+    fn new() -> Self {
+        Self {
+            body_ids: HashMap::default(),
+            bodies: Vec::new(),
+            rows: Vec::new(),
+        }
+    }
+
+    fn push_row(
+        &mut self,
+        body1: RigidBodyHandle,
+        body2: RigidBodyHandle,
+        part: impl Fn(&HoldingBody) -> (Vector<Real>, AngVector<Real>),
+    ) {
+        let signs: [(RigidBodyHandle, Real); 2] = [(body1, -1.0), (body2, 1.0)];
+        let row = signs
+            .iter()
+            .filter_map(|(handle, sign)| {
+                let id = *self.body_ids.get(handle)?;
+                let (lin, ang) = part(&self.bodies[id]);
+                Some((id, lin * *sign, ang * *sign))
+            })
+            .collect();
+        self.rows.push(row);
+    }
+
+    // Blocks the motion of the point `anchor` along `dir`.
+    fn push_linear_row(
+        &mut self,
+        body1: RigidBodyHandle,
+        body2: RigidBodyHandle,
+        anchor: &Point<Real>,
+        dir: Vector<Real>,
+    ) {
+        self.push_row(body1, body2, |body| {
+            (dir, (anchor - body.world_com).gcross(dir))
+        })
+    }
+
+    fn push_linear_rows(
+        &mut self,
+        body1: RigidBodyHandle,
+        body2: RigidBodyHandle,
+        anchor: &Point<Real>,
+    ) {
+        for i in 0..DIM {
+            self.push_linear_row(body1, body2, anchor, Vector::ith(i, 1.0));
+        }
+    }
+
+    // Blocks the rotations along `dir`.
+    fn push_angular_row(
+        &mut self,
+        body1: RigidBodyHandle,
+        body2: RigidBodyHandle,
+        dir: AngVector<Real>,
+    ) {
+        self.push_row(body1, body2, |_| (Vector::zeros(), dir))
+    }
+
+    fn push_angular_rows(&mut self, body1: RigidBodyHandle, body2: RigidBodyHandle) {
+        #[cfg(feature = "dim2")]
+        self.push_angular_row(body1, body2, 1.0);
+        #[cfg(feature = "dim3")]
+        for i in 0..ANG_DIM {
+            self.push_angular_row(body1, body2, AngVector::ith(i, 1.0));
+        }
+    }
+
+    // Solves `J M⁻¹ Jᵀ λ = -J M⁻¹ f` with `f` the external forces, so the constraint forces `λ`
+    // cancel the velocity changes along the rows. Redundant rows (e.g. the contacts of a box
+    // resting on the ground) make this singular, so the least-norm solution is computed.
+    // NOTE: the light bodies far from their joints make this badly conditioned, so only the
+    //       singular values at the level of the rounding errors are discarded.
+    fn solve(&self) -> DVector<Real> {
+        let num_rows = self.rows.len();
+
+        if num_rows == 0 {
+            return DVector::zeros(0);
+        }
+
+        let inv_mass_jt: Vec<Vec<_>> = self
+            .rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|(id, lin, ang)| {
+                        let body = &self.bodies[*id];
+                        let lin = body.effective_inv_mass.component_mul(lin);
+                        (*id, lin, body.inv_inertia.transform_vector(*ang))
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut lhs = DMatrix::<Real>::zeros(num_rows, num_rows);
+        let mut rhs = DVector::<Real>::zeros(num_rows);
+
+        for (i, row) in self.rows.iter().enumerate() {
+            for (id, lin, ang) in row {
+                let body = &self.bodies[*id];
+                rhs[i] -= lin.dot(&body.linvel_change) + ang.gdot(body.angvel_change);
+
+                for (j, column) in inv_mass_jt.iter().enumerate() {
+                    for (id2, lin2, ang2) in column {
+                        if id2 == id {
+                            lhs[(i, j)] += lin.dot(lin2) + ang.gdot(*ang2);
+                        }
+                    }
+                }
+            }
+        }
+
+        let svd = lhs.svd(true, true);
+        let eps = svd.singular_values.amax() * Real::EPSILON * num_rows as Real;
+        svd.solve(&rhs, eps)
+            .unwrap_or_else(|_| DVector::zeros(num_rows))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dynamics::{JointSet, RigidBodyBuilder, RigidBodySet};
+    use crate::geometry::{ColliderBuilder, ColliderSet, NarrowPhase};
+    use crate::math::Vector;
+
+    #[test]
+    fn holding_forces_of_motorized_joints_balance_gravity() {
+        use crate::dynamics::{BallJoint, JointHandle, JointParams, RigidBodyHandle};
+        use crate::math::{Point, Real, Rotation};
+
+        // Inserts a ball at `(x, y)`, attached to `parent` by a motorized ball joint at
+        // `length` to its left.
+        fn insert_link(
+            bodies: &mut RigidBodySet,
+            colliders: &mut ColliderSet,
+            joints: &mut JointSet,
+            parent: RigidBodyHandle,
+            parent_anchor: Point<Real>,
+            (x, y): (Real, Real),
+            length: Real,
+        ) -> (RigidBodyHandle, JointHandle) {
+            #[cfg(feature = "dim2")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(x, y);
+            #[cfg(feature = "dim3")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(x, y, 0.0);
+            let link = bodies.insert(rb.build());
+            colliders.insert(ColliderBuilder::ball(0.2).build(), link, bodies);
+
+            let mut joint = BallJoint::new(parent_anchor, Point::from(Vector::x() * -length));
+            joint.configure_motor_position(Rotation::identity(), 1.0, 0.1);
+            (link, joints.insert(bodies, parent, link, joint))
+        }
+
+        let gravity = Vector::y() * -9.81;
+        let dt = 1.0 / 60.0;
+        let narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut joints = JointSet::new();
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+
+        // A single-link pendulum held horizontal.
+        let (bob, pendulum) = insert_link(
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            ground,
+            Point::origin(),
+            (2.0, 0.0),
+            2.0,
+        );
+
+        // A two-link arm held horizontal.
+        #[cfg(feature = "dim2")]
+        let shoulder_anchor = Point::new(0.0, 5.0);
+        #[cfg(feature = "dim3")]
+        let shoulder_anchor = Point::new(0.0, 5.0, 0.0);
+        let (link1, shoulder) = insert_link(
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            ground,
+            shoulder_anchor,
+            (1.5, 5.0),
+            1.5,
+        );
+        let (_, elbow) = insert_link(
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            link1,
+            Point::origin(),
+            (2.5, 5.0),
+            1.0,
+        );
+
+        let mass = bodies[bob].mass();
+        let weight = mass * 9.81;
+        let expected = [
+            (pendulum, weight * 2.0),
+            (shoulder, weight * 1.5 + weight * 2.5),
+            (elbow, weight * 1.0),
+        ];
+
+        let forces =
+            joints.compute_holding_forces(&bodies, &colliders, &narrow_phase, &gravity, dt);
+        assert_eq!(forces.len(), 3);
+
+        for (handle, force) in &expected {
+            let holding = forces[handle];
+            assert!((holding.force - *force).abs() <= *force * 0.01);
+            assert!(holding.holdable);
+        }
+
+        // A motor too weak to hold the pendulum is flagged, without changing the other joints.
+        if let JointParams::BallJoint(joint) = &mut joints.get_mut(pendulum).unwrap().params {
+            joint.motor_max_impulse = weight * 2.0 * dt * 0.5;
+        }
+
+        let weak_forces =
+            joints.compute_holding_forces(&bodies, &colliders, &narrow_phase, &gravity, dt);
+        assert!(!weak_forces[&pendulum].holdable);
+        assert_eq!(weak_forces[&pendulum].force, forces[&pendulum].force);
+        assert!(weak_forces[&shoulder].holdable && weak_forces[&elbow].holdable);
     }
 }
@@ -1,20 +1,24 @@
 pub use self::ball_joint::BallJoint;
 pub use self::fixed_joint::FixedJoint;
+pub use self::gear_joint::GearJoint;
 // pub use self::generic_joint::GenericJoint;
-pub use self::joint::{Joint, JointParams};
+pub use self::joint::{Joint, JointParams, WorldJointParams};
 pub(crate) use self::joint_set::{JointGraphEdge, JointIndex};
-pub use self::joint_set::{JointHandle, JointSet};
+pub use self::joint_set::{JointHandle, JointHoldingForce, JointSet};
 pub use self::prismatic_joint::PrismaticJoint;
+pub use self::pulley_joint::PulleyJoint;
 #[cfg(feature = "dim3")]
 pub use self::revolute_joint::RevoluteJoint;
 pub use self::spring_model::SpringModel;
 
 mod ball_joint;
 mod fixed_joint;
+mod gear_joint;
 // mod generic_joint;
 mod joint;
 mod joint_set;
 mod prismatic_joint;
+mod pulley_joint;
 #[cfg(feature = "dim3")]
 mod revolute_joint;
 mod spring_model;
@@ -1,4 +1,5 @@
-use crate::dynamics::SpringModel;
+use super::joint::assert_unit_axis;
+use crate::dynamics::{RigidBody, SpringModel};
 use crate::math::{Isometry, Point, Real, Vector, DIM};
 use crate::utils::WBasis;
 use na::Unit;
@@ -37,6 +38,16 @@ pub struct PrismaticJoint {
     ///
     /// The impulse applied to the second body is given by `-impulse`.
     pub limits_impulse: Real,
+    /// The restitution coefficient applied when this joint reaches one of its limits.
+    ///
+    /// If non-zero, the relative velocity along the joint's axis is reflected and scaled by
+    /// this coefficient when the limit is hit, instead of being set to zero.
+    pub limit_restitution: Real,
+    /// The width of the band before each limit where the joint is progressively slowed down.
+    ///
+    /// Within this band, the approach velocity is damped more and more as the limit gets closer,
+    /// and reaches zero at the limit.
+    pub limit_softness_band: Real,
 
     /// The target relative angular velocity the motor will attempt to reach.
     pub motor_target_vel: Real,
@@ -77,6 +88,8 @@ impl PrismaticJoint {
             limits_enabled: false,
             limits: [-Real::MAX, Real::MAX],
             limits_impulse: 0.0,
+            limit_restitution: 0.0,
+            limit_softness_band: 0.0,
             motor_target_vel: 0.0,
             motor_target_pos: 0.0,
             motor_stiffness: 0.0,
@@ -135,6 +148,8 @@ impl PrismaticJoint {
             limits_enabled: false,
             limits: [-Real::MAX, Real::MAX],
             limits_impulse: 0.0,
+            limit_restitution: 0.0,
+            limit_softness_band: 0.0,
             motor_target_vel: 0.0,
             motor_target_pos: 0.0,
             motor_stiffness: 0.0,
@@ -145,6 +160,45 @@ impl PrismaticJoint {
         }
     }
 
+    /// Creates a new prismatic joint attached at the world-space point `world_anchor` and free to
+    /// translate along the world-space axis `world_axis`, given the current positions of the two
+    /// bodies it will be attached to.
+    ///
+    /// Panics if `world_axis` isn't of unit length.
+    pub fn from_world_anchor(
+        rb1: &RigidBody,
+        rb2: &RigidBody,
+        world_anchor: Point<Real>,
+        world_axis: Unit<Vector<Real>>,
+    ) -> Self {
+        assert_unit_axis(&world_axis);
+        let (pos1, pos2) = (rb1.position(), rb2.position());
+
+        #[cfg(feature = "dim2")]
+        let joint = Self::new(
+            pos1.inverse_transform_point(&world_anchor),
+            pos1.inverse_transform_unit_vector(&world_axis),
+            pos2.inverse_transform_point(&world_anchor),
+            pos2.inverse_transform_unit_vector(&world_axis),
+        );
+
+        #[cfg(feature = "dim3")]
+        let joint = {
+            // Use the same world-space tangent for both bodies so their bases coincide.
+            let world_tangent = world_axis.orthonormal_basis()[0];
+            Self::new(
+                pos1.inverse_transform_point(&world_anchor),
+                pos1.inverse_transform_unit_vector(&world_axis),
+                pos1.inverse_transform_vector(&world_tangent),
+                pos2.inverse_transform_point(&world_anchor),
+                pos2.inverse_transform_unit_vector(&world_axis),
+                pos2.inverse_transform_vector(&world_tangent),
+            )
+        };
+
+        joint
+    }
+
     /// The local axis of this joint, expressed in the local-space of the first attached body.
     pub fn local_axis1(&self) -> Unit<Vector<Real>> {
         self.local_axis1
@@ -157,8 +211,11 @@ impl PrismaticJoint {
 
     /// Can a SIMD constraint be used for resolving this joint?
     pub fn supports_simd_constraints(&self) -> bool {
-        // SIMD revolute constraints don't support motors right now.
-        self.motor_max_impulse == 0.0 || (self.motor_stiffness == 0.0 && self.motor_damping == 0.0)
+        // SIMD revolute constraints don't support motors nor soft limits right now.
+        (self.motor_max_impulse == 0.0
+            || (self.motor_stiffness == 0.0 && self.motor_damping == 0.0))
+            && self.limit_restitution == 0.0
+            && self.limit_softness_band == 0.0
     }
 
     // FIXME: precompute this?
@@ -0,0 +1,59 @@
+use crate::math::{Point, Real};
+
+#[derive(Copy, Clone)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+/// A joint that connects two bodies with a rope running through two fixed pulleys.
+///
+/// Each body is attached to a rope going up to a fixed point in world-space, its pulley. This
+/// joint ensures that `length1 + ratio * length2` remains equal to `length`, where `length1`
+/// and `length2` are the distances between each body anchor and its pulley. A ratio greater
+/// than one gives a mechanical advantage to the second body.
+///
+/// This is a velocity-level coupling: the rope never goes slack.
+pub struct PulleyJoint {
+    /// Where the rope is attached on the first body, expressed in the local space of the first body.
+    pub local_anchor1: Point<Real>,
+    /// Where the rope is attached on the second body, expressed in the local space of the second body.
+    pub local_anchor2: Point<Real>,
+    /// The world-space position of the pulley of the first body.
+    pub ground_anchor1: Point<Real>,
+    /// The world-space position of the pulley of the second body.
+    pub ground_anchor2: Point<Real>,
+    /// The ratio applied to the length of the rope of the second body.
+    pub ratio: Real,
+    /// The total length `length1 + ratio * length2` of the rope.
+    pub length: Real,
+    /// The impulse applied by the rope attached to the first body.
+    ///
+    /// The impulse applied by the rope attached to the second body is given by `ratio * impulse`.
+    pub impulse: Real,
+}
+
+impl PulleyJoint {
+    /// Creates a new pulley joint from the world-space positions of both pulleys, the rope
+    /// anchors in the local spaces of the respective bodies, the ratio, and the total length
+    /// of the rope.
+    pub fn new(
+        ground_anchor1: Point<Real>,
+        ground_anchor2: Point<Real>,
+        local_anchor1: Point<Real>,
+        local_anchor2: Point<Real>,
+        ratio: Real,
+        length: Real,
+    ) -> Self {
+        Self {
+            local_anchor1,
+            local_anchor2,
+            ground_anchor1,
+            ground_anchor2,
+            ratio,
+            length,
+            impulse: 0.0,
+        }
+    }
+
+    /// Can a SIMD constraint be used for resolving this joint?
+    pub fn supports_simd_constraints(&self) -> bool {
+        false
+    }
+}
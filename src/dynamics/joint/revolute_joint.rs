@@ -1,4 +1,5 @@
-use crate::dynamics::SpringModel;
+use super::joint::assert_unit_axis;
+use crate::dynamics::{RigidBody, SpringModel};
 use crate::math::{Isometry, Point, Real, Vector};
 use crate::utils::WBasis;
 use na::{RealField, Unit, Vector5};
@@ -23,6 +24,26 @@ pub struct RevoluteJoint {
     ///
     /// The impulse applied to the second body is given by `-impulse`.
     pub impulse: Vector5<Real>,
+    /// Whether or not this joint should enforce angular limits along its axis.
+    pub limits_enabled: bool,
+    /// The min and max relative angle, in `[-pi, pi]`, of the attached bodies along this joint's axis.
+    ///
+    /// The angle is measured from `basis1[0]` to `basis2[0]`.
+    pub limits: [Real; 2],
+    /// The angular impulse applied by this joint on the second body to enforce the angular limit along this joint's axis.
+    ///
+    /// The impulse applied to the first body is given by `-impulse`.
+    pub limits_impulse: Real,
+    /// The restitution coefficient applied when this joint reaches one of its limits.
+    ///
+    /// If non-zero, the relative angular velocity along the joint's axis is reflected and
+    /// scaled by this coefficient when the limit is hit, instead of being set to zero.
+    pub limit_restitution: Real,
+    /// The angular width of the band before each limit where the joint is progressively slowed down.
+    ///
+    /// Within this band, the approach velocity is damped more and more as the limit gets closer,
+    /// and reaches zero at the limit.
+    pub limit_softness_band: Real,
 
     /// The target relative angular velocity the motor will attempt to reach.
     pub motor_target_vel: Real,
@@ -66,6 +87,11 @@ impl RevoluteJoint {
             basis1: local_axis1.orthonormal_basis(),
             basis2: local_axis2.orthonormal_basis(),
             impulse: na::zero(),
+            limits_enabled: false,
+            limits: [-Real::MAX, Real::MAX],
+            limits_impulse: 0.0,
+            limit_restitution: 0.0,
+            limit_softness_band: 0.0,
             world_ang_impulse: na::zero(),
             motor_target_vel: 0.0,
             motor_target_pos: 0.0,
@@ -79,10 +105,33 @@ impl RevoluteJoint {
         }
     }
 
+    /// Creates a new revolute joint attached at the world-space point `world_anchor` and free to
+    /// rotate along the world-space axis `world_axis`, given the current positions of the two
+    /// bodies it will be attached to.
+    ///
+    /// Panics if `world_axis` isn't of unit length.
+    pub fn from_world_anchor(
+        rb1: &RigidBody,
+        rb2: &RigidBody,
+        world_anchor: Point<Real>,
+        world_axis: Unit<Vector<Real>>,
+    ) -> Self {
+        assert_unit_axis(&world_axis);
+        let (pos1, pos2) = (rb1.position(), rb2.position());
+        Self::new(
+            pos1.inverse_transform_point(&world_anchor),
+            pos1.inverse_transform_unit_vector(&world_axis),
+            pos2.inverse_transform_point(&world_anchor),
+            pos2.inverse_transform_unit_vector(&world_axis),
+        )
+    }
+
     /// Can a SIMD constraint be used for resolving this joint?
     pub fn supports_simd_constraints(&self) -> bool {
-        // SIMD revolute constraints don't support motors right now.
-        self.motor_max_impulse == 0.0 || (self.motor_stiffness == 0.0 && self.motor_damping == 0.0)
+        // SIMD revolute constraints don't support motors nor limits right now.
+        (self.motor_max_impulse == 0.0
+            || (self.motor_stiffness == 0.0 && self.motor_damping == 0.0))
+            && !self.limits_enabled
     }
 
     /// Set the spring-like model used by the motor to reach the desired target velocity and position.
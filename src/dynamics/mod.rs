@@ -2,7 +2,10 @@
 
 pub use self::ccd::CCDSolver;
 pub use self::coefficient_combine_rule::CoefficientCombineRule;
-pub use self::integration_parameters::IntegrationParameters;
+pub use self::integration_parameters::{
+    IntegrationParameters, ParamDescriptor, ParamError, ParamValue, PositionSolveBudget,
+    RestitutionModel,
+};
 pub(crate) use self::joint::JointGraphEdge;
 pub(crate) use self::joint::JointIndex;
 #[cfg(feature = "dim3")]
@@ -10,21 +13,36 @@ pub use self::joint::RevoluteJoint;
 pub use self::joint::{
     BallJoint,
     FixedJoint,
+    GearJoint,
     Joint,
     JointHandle,
+    JointHoldingForce,
     JointParams,
     JointSet,
     PrismaticJoint,
+    PulleyJoint,
     // GenericJoint
     SpringModel,
+    WorldJointParams,
+};
+pub(crate) use self::rigid_body::{RigidBodyChanges, RigidBodyFlags};
+pub use self::rigid_body::{ActivationStatus, BodyStatus, RigidBody, RigidBodyBuilder, WakePolicy};
+pub(crate) use self::rigid_body_set::RigidBodyArenaChange;
+pub use self::rigid_body_set::{
+    BodyPair, HandleMap, InsertedRigidBody, RemovedRigidBody, RigidBodyHandle, RigidBodySet,
+    RigidBodySetStats,
+};
+#[cfg(feature = "parallel")]
+pub use self::rigid_body_set::TrackedRigidBodyMut;
+pub use self::user_constraint_set::{
+    PositionOffsetsMap, UserConstraintHandle, UserConstraintSet, UserPositionConstraint,
+    UserVelocityConstraint,
 };
-pub(crate) use self::rigid_body::RigidBodyChanges;
-pub use self::rigid_body::{ActivationStatus, BodyStatus, RigidBody, RigidBodyBuilder};
-pub use self::rigid_body_set::{BodyPair, RigidBodyHandle, RigidBodySet};
 #[cfg(not(feature = "parallel"))]
 pub(crate) use self::solver::IslandSolver;
 #[cfg(feature = "parallel")]
 pub(crate) use self::solver::ParallelIslandSolver;
+pub use self::solver::{DeltaVel, ParallelStageStats, SolverStats};
 pub use parry::mass_properties::MassProperties;
 
 mod ccd;
@@ -34,3 +52,4 @@ mod joint;
 mod rigid_body;
 mod rigid_body_set;
 mod solver;
+mod user_constraint_set;
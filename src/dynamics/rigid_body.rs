@@ -1,4 +1,4 @@
-use crate::dynamics::MassProperties;
+use crate::dynamics::{MassProperties, RigidBodyHandle};
 use crate::geometry::{
     Collider, ColliderHandle, ColliderSet, InteractionGraph, RigidBodyGraphIndex,
 };
@@ -6,6 +6,7 @@ use crate::math::{
     AngVector, AngularInertia, Isometry, Point, Real, Rotation, Translation, Vector,
 };
 use crate::utils::{self, WAngularInertia, WCross, WDot};
+use alloc::vec::Vec;
 use na::ComplexField;
 use num::Zero;
 
@@ -28,16 +29,67 @@ pub enum BodyStatus {
     // Disabled,
 }
 
+/// Controls whether a sleeping rigid-body is woken up by the contacts of the awake bodies touching it.
+///
+/// A sleeping rigid-body which isn't woken up by a contact acts as a static body for the awake body
+/// touching it. Explicit calls to `RigidBody::wake_up` and the propagation through joints always
+/// wake up the rigid-body, regardless of its policy.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub enum WakePolicy {
+    /// The rigid-body is woken up by any contact with an awake body.
+    Always,
+    /// The rigid-body is woken up by a contact with an awake body only if the normal impulse
+    /// applied by one of its contact manifolds during the last timestep exceeds this threshold.
+    OnlyStrongImpulse(Real),
+    /// The rigid-body is never woken up by a contact.
+    Never,
+}
+
+impl WakePolicy {
+    /// Is a sleeping rigid-body with this policy woken up by a contact manifold which applied
+    /// the given normal impulse during the last timestep?
+    pub fn is_woken_by_contact(&self, impulse: Real) -> bool {
+        match self {
+            WakePolicy::Always => true,
+            WakePolicy::OnlyStrongImpulse(threshold) => impulse > *threshold,
+            WakePolicy::Never => false,
+        }
+    }
+}
+
+impl Default for WakePolicy {
+    fn default() -> Self {
+        WakePolicy::Always
+    }
+}
+
 bitflags::bitflags! {
     #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
     /// Flags affecting the behavior of the constraints solver for a given contact manifold.
-    pub(crate) struct RigidBodyFlags: u8 {
-        const TRANSLATION_LOCKED = 1 << 0;
+    pub(crate) struct RigidBodyFlags: u16 {
+        // NOTE: the bit 0 was used by the lock of all the translations before the per-axis
+        //       translation locks.
         const ROTATION_LOCKED_X = 1 << 1;
         const ROTATION_LOCKED_Y = 1 << 2;
         const ROTATION_LOCKED_Z = 1 << 3;
+        const ROTATION_LOCKED = Self::ROTATION_LOCKED_X.bits
+            | Self::ROTATION_LOCKED_Y.bits
+            | Self::ROTATION_LOCKED_Z.bits;
         const CCD_ENABLED = 1 << 4;
         const CCD_ACTIVE = 1 << 5;
+        const MASS_UPDATE_DEFERRED = 1 << 6;
+        const FROZEN = 1 << 7;
+        const PROJECTILE = 1 << 8;
+        const POSITION_CORRECTION_DISABLED = 1 << 9;
+        const SUSPENDED = 1 << 10;
+        const TRANSLATION_LOCKED_X = 1 << 11;
+        const TRANSLATION_LOCKED_Y = 1 << 12;
+        const TRANSLATION_LOCKED_Z = 1 << 13;
+        const TRANSLATION_LOCKED = Self::TRANSLATION_LOCKED_X.bits
+            | Self::TRANSLATION_LOCKED_Y.bits
+            | Self::TRANSLATION_LOCKED_Z.bits;
+        const AGGREGATE_PROXY = 1 << 14;
     }
 }
 
@@ -50,6 +102,9 @@ bitflags::bitflags! {
         const SLEEP       = 1 << 2;
         const COLLIDERS   = 1 << 3;
         const BODY_STATUS = 1 << 4;
+        const LOCKED_AXES = 1 << 5;
+        const MASS_PROPERTIES = 1 << 6;
+        const AGGREGATE_PROXY = 1 << 7;
     }
 }
 
@@ -71,12 +126,33 @@ pub struct RigidBody {
     /// resolution. Then it is either validated (ie. we set position := set_position)
     /// or clamped by CCD.
     pub(crate) next_position: Isometry<Real>,
+    /// The position a kinematic rigid-body must reach, and the time left to reach it.
+    pub(crate) kinematic_target: Option<(Isometry<Real>, Real)>,
+    /// The velocities set with `set_kinematic_velocity`, moving a kinematic rigid-body at each
+    /// timestep its next position isn't set explicitly.
+    pub(crate) kinematic_linvel: Vector<Real>,
+    pub(crate) kinematic_angvel: AngVector<Real>,
+    /// Was the next position of this kinematic rigid-body set for the next timestep?
+    pub(crate) next_kinematic_position_set: bool,
+    /// The position of the rigid-body at the beginning of the last timestep.
+    pub(crate) prev_position: Isometry<Real>,
+    /// The position and velocities of the rigid-body at the end of the last timestep it was
+    /// simulated at. Unlike `prev_position`, they are not affected by teleportation.
+    pub(crate) snapshot_position: Isometry<Real>,
+    pub(crate) snapshot_linvel: Vector<Real>,
+    pub(crate) snapshot_angvel: AngVector<Real>,
+    /// The motion of the rigid-body during the last timestep.
+    pub(crate) position_delta: Isometry<Real>,
+    pub(crate) linvel_delta: Vector<Real>,
+    pub(crate) angvel_delta: AngVector<Real>,
     /// The local mass properties of the rigid-body.
     pub(crate) mass_properties: MassProperties,
+    /// The part of `mass_properties` which isn't computed from the attached colliders.
+    pub(crate) additional_mass_properties: MassProperties,
     /// The world-space center of mass of the rigid-body.
     pub world_com: Point<Real>,
-    /// The inverse mass taking into account translation locking.
-    pub effective_inv_mass: Real,
+    /// The inverse mass along each coordinate axis, taking into account translation locking.
+    pub effective_inv_mass: Vector<Real>,
     /// The square-root of the world-space inverse angular inertia tensor of the rigid-body,
     /// taking into account rotation locking.
     pub effective_world_inv_inertia_sqrt: AngularInertia<Real>,
@@ -84,6 +160,11 @@ pub struct RigidBody {
     pub(crate) linvel: Vector<Real>,
     /// The angular velocity of the rigid-body.
     pub(crate) angvel: AngVector<Real>,
+    /// The velocities of the rigid-body during the last timestep, if it was kinematic.
+    pub(crate) last_kinematic_linvel: Vector<Real>,
+    pub(crate) last_kinematic_angvel: AngVector<Real>,
+    /// The velocities of the rigid-body when it was last switched from dynamic to kinematic.
+    pub(crate) velocities_before_kinematic: Option<(Vector<Real>, AngVector<Real>)>,
     /// Damping factor for gradually slowing down the translational motion of the rigid-body.
     pub linear_damping: Real,
     /// Damping factor for gradually slowing down the angular motion of the rigid-body.
@@ -101,29 +182,68 @@ pub struct RigidBody {
     pub(crate) active_set_id: usize,
     pub(crate) active_set_offset: usize,
     pub(crate) active_set_timestamp: u32,
-    flags: RigidBodyFlags,
+    // The active set timestamp of the first island computation following the last user modification.
+    pub(crate) user_changes_timestamp: u32,
+    pub(crate) flags: RigidBodyFlags,
     pub(crate) changes: RigidBodyChanges,
     /// The status of the body, governing how it is affected by external forces.
-    body_status: BodyStatus,
+    pub(crate) body_status: BodyStatus,
     /// The dominance group this rigid-body is part of.
     dominance_group: i8,
+    /// The priority of the constraints involving this rigid-body in the solver ordering.
+    pub(crate) solver_priority: u8,
+    /// Whether this rigid-body is woken up by the contacts of the awake bodies touching it.
+    pub(crate) wake_policy: WakePolicy,
+    /// The maximum angular acceleration the contacts can apply to this rigid-body.
+    pub(crate) max_contact_angular_acceleration: Option<Real>,
     /// User-defined data associated to this rigid-body.
     pub user_data: u128,
     pub(crate) ccd_thickness: Real,
     pub(crate) ccd_max_dist: Real,
 }
 
+// NOTE: a zero inverse mass may either mean a zero or an infinite mass. Both are
+//       degenerate if the angular inertia is degenerate too.
+fn is_degenerate_mass(props: &MassProperties) -> bool {
+    #[cfg(feature = "dim2")]
+    let finite_inertia = props.inv_principal_inertia_sqrt.is_finite();
+    #[cfg(feature = "dim3")]
+    let finite_inertia = props
+        .inv_principal_inertia_sqrt
+        .iter()
+        .all(|e| e.is_finite());
+
+    !props.inv_mass.is_finite()
+        || !finite_inertia
+        || (props.inv_mass == 0.0 && props.inv_principal_inertia_sqrt.is_zero())
+}
+
 impl RigidBody {
     fn new() -> Self {
         Self {
             position: Isometry::identity(),
             next_position: Isometry::identity(),
+            kinematic_target: None,
+            kinematic_linvel: Vector::zeros(),
+            kinematic_angvel: na::zero(),
+            next_kinematic_position_set: false,
+            prev_position: Isometry::identity(),
+            snapshot_position: Isometry::identity(),
+            snapshot_linvel: Vector::zeros(),
+            snapshot_angvel: na::zero(),
+            position_delta: Isometry::identity(),
+            linvel_delta: Vector::zeros(),
+            angvel_delta: na::zero(),
             mass_properties: MassProperties::zero(),
+            additional_mass_properties: MassProperties::zero(),
             world_com: Point::origin(),
-            effective_inv_mass: 0.0,
+            effective_inv_mass: Vector::zeros(),
             effective_world_inv_inertia_sqrt: AngularInertia::zero(),
             linvel: Vector::zeros(),
             angvel: na::zero(),
+            last_kinematic_linvel: Vector::zeros(),
+            last_kinematic_angvel: na::zero(),
+            velocities_before_kinematic: None,
             force: Vector::zeros(),
             torque: na::zero(),
             gravity_scale: 1.0,
@@ -136,10 +256,14 @@ impl RigidBody {
             active_set_id: 0,
             active_set_offset: 0,
             active_set_timestamp: 0,
+            user_changes_timestamp: 0,
             flags: RigidBodyFlags::empty(),
             changes: RigidBodyChanges::all(),
             body_status: BodyStatus::Dynamic,
             dominance_group: 0,
+            solver_priority: 0,
+            wake_policy: WakePolicy::Always,
+            max_contact_angular_acceleration: None,
             user_data: 0,
             ccd_thickness: Real::MAX,
             ccd_max_dist: 0.0,
@@ -156,14 +280,14 @@ impl RigidBody {
     }
 
     pub(crate) fn add_gravity(&mut self, gravity: Vector<Real>) {
-        if self.effective_inv_mass != 0.0 {
+        if !self.effective_inv_mass.is_zero() {
             self.force += gravity * self.gravity_scale * self.mass();
         }
     }
 
     #[cfg(not(feature = "parallel"))] // in parallel solver this is not needed
     pub(crate) fn integrate_accelerations(&mut self, dt: Real) {
-        let linear_acc = self.force * self.effective_inv_mass;
+        let linear_acc = self.force.component_mul(&self.effective_inv_mass);
         let angular_acc = self.effective_world_inv_inertia_sqrt
             * (self.effective_world_inv_inertia_sqrt * self.torque);
 
@@ -177,13 +301,47 @@ impl RigidBody {
     }
 
     /// Sets the status of this rigid-body.
+    ///
+    /// When a kinematic rigid-body becomes dynamic, its velocities are set to the velocities it
+    /// had during the last timestep, so that a body released after being moved kinematically
+    /// keeps its motion. When a dynamic rigid-body becomes kinematic, its velocities are recorded
+    /// and can be retrieved with `self.velocities_before_kinematic()`. The joints attached to this
+    /// rigid-body are updated accordingly at the next timestep.
     pub fn set_body_status(&mut self, status: BodyStatus) {
         if status != self.body_status {
+            match (self.body_status, status) {
+                (BodyStatus::Dynamic, BodyStatus::Kinematic) => {
+                    self.velocities_before_kinematic = Some((self.linvel, self.angvel));
+                    self.last_kinematic_linvel = na::zero();
+                    self.last_kinematic_angvel = na::zero();
+                }
+                (BodyStatus::Kinematic, BodyStatus::Dynamic) => {
+                    self.linvel = self.last_kinematic_linvel;
+                    self.angvel = self.last_kinematic_angvel;
+                }
+                _ => {}
+            }
+
+            if self.body_status == BodyStatus::Kinematic {
+                self.kinematic_linvel = na::zero();
+                self.kinematic_angvel = na::zero();
+                self.next_kinematic_position_set = false;
+            }
+
             self.changes.insert(RigidBodyChanges::BODY_STATUS);
             self.body_status = status;
         }
     }
 
+    /// The linear and angular velocities this rigid-body had when it was last switched from
+    /// dynamic to kinematic with `self.set_body_status`, if it ever was.
+    ///
+    /// This can be used to restore the velocities of a dynamic rigid-body temporarily made
+    /// kinematic.
+    pub fn velocities_before_kinematic(&self) -> Option<(Vector<Real>, AngVector<Real>)> {
+        self.velocities_before_kinematic
+    }
+
     /// The mass properties of this rigid-body.
     #[inline]
     pub fn mass_properties(&self) -> &MassProperties {
@@ -193,21 +351,112 @@ impl RigidBody {
     /// The dominance group of this rigid-body.
     ///
     /// This method always returns `i8::MAX + 1` for non-dynamic
-    /// rigid-bodies.
+    /// rigid-bodies, and for dynamic rigid-bodies with a degenerate mass.
     #[inline]
     pub fn effective_dominance_group(&self) -> i16 {
-        if self.is_dynamic() {
+        if self.is_dynamic_with_mass() {
             self.dominance_group as i16
         } else {
             i8::MAX as i16 + 1
         }
     }
 
-    /// Are the translations of this rigid-body locked?
+    /// The solver priority of this rigid-body.
+    ///
+    /// See `RigidBodyBuilder::solver_priority` for details.
+    #[inline]
+    pub fn solver_priority(&self) -> u8 {
+        self.solver_priority
+    }
+
+    /// Sets the solver priority of this rigid-body.
+    ///
+    /// See `RigidBodyBuilder::solver_priority` for details.
+    pub fn set_solver_priority(&mut self, priority: u8) {
+        self.solver_priority = priority;
+    }
+
+    /// Whether this rigid-body is woken up by the contacts of the awake bodies touching it.
+    #[inline]
+    pub fn wake_policy(&self) -> WakePolicy {
+        self.wake_policy
+    }
+
+    /// Sets whether this rigid-body is woken up by the contacts of the awake bodies touching it.
+    ///
+    /// See `WakePolicy` for details.
+    pub fn set_wake_policy(&mut self, policy: WakePolicy) {
+        self.wake_policy = policy;
+    }
+
+    /// The maximum angular acceleration the contacts can apply to this rigid-body, if any.
+    ///
+    /// See `RigidBodyBuilder::max_contact_angular_acceleration` for details.
+    #[inline]
+    pub fn max_contact_angular_acceleration(&self) -> Option<Real> {
+        self.max_contact_angular_acceleration
+    }
+
+    /// Sets the maximum angular acceleration the contacts can apply to this rigid-body.
+    ///
+    /// See `RigidBodyBuilder::max_contact_angular_acceleration` for details.
+    pub fn set_max_contact_angular_acceleration(&mut self, max_accel: Option<Real>) {
+        self.max_contact_angular_acceleration = max_accel;
+    }
+
+    /// The maximum magnitude of the angular velocity increment the contacts can apply to this
+    /// rigid-body during a timestep of length `dt`, multiplied by the square root of its angular
+    /// inertia tensor like the angular velocity increments computed by the constraints solver.
+    ///
+    /// The largest principal inverse inertia is used for the conversion, so the actual angular
+    /// velocity increment never exceeds `max_contact_angular_acceleration * dt`.
+    pub(crate) fn max_contact_mj_dangvel(&self, dt: Real) -> Real {
+        if let Some(max_accel) = self.max_contact_angular_acceleration {
+            #[cfg(feature = "dim2")]
+            let max_inv_inertia_sqrt = self.mass_properties.inv_principal_inertia_sqrt;
+            #[cfg(feature = "dim3")]
+            let max_inv_inertia_sqrt = self.mass_properties.inv_principal_inertia_sqrt.max();
+
+            if max_inv_inertia_sqrt > 0.0 {
+                return max_accel * dt / max_inv_inertia_sqrt;
+            }
+        }
+
+        Real::MAX
+    }
+
+    /// Are all the translations of this rigid-body locked?
+    #[cfg(feature = "dim2")]
+    pub fn is_translation_locked(&self) -> bool {
+        self.flags
+            .contains(RigidBodyFlags::TRANSLATION_LOCKED_X | RigidBodyFlags::TRANSLATION_LOCKED_Y)
+    }
+
+    /// Are all the translations of this rigid-body locked?
+    #[cfg(feature = "dim3")]
     pub fn is_translation_locked(&self) -> bool {
         self.flags.contains(RigidBodyFlags::TRANSLATION_LOCKED)
     }
 
+    /// Returns `true` for each translational degrees of freedom locked on this rigid-body.
+    #[cfg(feature = "dim2")]
+    pub fn is_translation_locked_per_axis(&self) -> [bool; 2] {
+        [
+            self.flags.contains(RigidBodyFlags::TRANSLATION_LOCKED_X),
+            self.flags.contains(RigidBodyFlags::TRANSLATION_LOCKED_Y),
+        ]
+    }
+
+    /// Returns `true` for each translational degrees of freedom locked on this rigid-body.
+    #[cfg(feature = "dim3")]
+    pub fn is_translation_locked_per_axis(&self) -> [bool; 3] {
+        [
+            self.flags.contains(RigidBodyFlags::TRANSLATION_LOCKED_X),
+            self.flags.contains(RigidBodyFlags::TRANSLATION_LOCKED_Y),
+            self.flags.contains(RigidBodyFlags::TRANSLATION_LOCKED_Z),
+        ]
+    }
+
     /// Are the rotations of this rigid-body locked?
     #[cfg(feature = "dim2")]
     pub fn is_rotation_locked(&self) -> bool {
@@ -224,6 +473,216 @@ impl RigidBody {
         ]
     }
 
+    /// Locks or unlocks all the translations of this rigid-body.
+    pub fn lock_translations(&mut self, locked: bool, wake_up: bool) {
+        self.set_locked_axes(RigidBodyFlags::TRANSLATION_LOCKED, locked, wake_up);
+    }
+
+    /// Locks or unlocks all the rotations of this rigid-body.
+    pub fn lock_rotations(&mut self, locked: bool, wake_up: bool) {
+        self.set_locked_axes(RigidBodyFlags::ROTATION_LOCKED, locked, wake_up);
+    }
+
+    /// Only allow translations of this rigid-body along specific coordinate axes.
+    #[cfg(feature = "dim2")]
+    pub fn restrict_translations(
+        &mut self,
+        allow_translation_x: bool,
+        allow_translation_y: bool,
+        wake_up: bool,
+    ) {
+        self.set_locked_axes(
+            RigidBodyFlags::TRANSLATION_LOCKED_X,
+            !allow_translation_x,
+            wake_up,
+        );
+        self.set_locked_axes(
+            RigidBodyFlags::TRANSLATION_LOCKED_Y,
+            !allow_translation_y,
+            wake_up,
+        );
+    }
+
+    /// Only allow translations of this rigid-body along specific coordinate axes.
+    #[cfg(feature = "dim3")]
+    pub fn restrict_translations(
+        &mut self,
+        allow_translation_x: bool,
+        allow_translation_y: bool,
+        allow_translation_z: bool,
+        wake_up: bool,
+    ) {
+        self.set_locked_axes(
+            RigidBodyFlags::TRANSLATION_LOCKED_X,
+            !allow_translation_x,
+            wake_up,
+        );
+        self.set_locked_axes(
+            RigidBodyFlags::TRANSLATION_LOCKED_Y,
+            !allow_translation_y,
+            wake_up,
+        );
+        self.set_locked_axes(
+            RigidBodyFlags::TRANSLATION_LOCKED_Z,
+            !allow_translation_z,
+            wake_up,
+        );
+    }
+
+    /// Only allow rotations of this rigid-body around specific coordinate axes.
+    #[cfg(feature = "dim3")]
+    pub fn restrict_rotations(
+        &mut self,
+        allow_rotations_x: bool,
+        allow_rotations_y: bool,
+        allow_rotations_z: bool,
+        wake_up: bool,
+    ) {
+        self.set_locked_axes(
+            RigidBodyFlags::ROTATION_LOCKED_X,
+            !allow_rotations_x,
+            wake_up,
+        );
+        self.set_locked_axes(
+            RigidBodyFlags::ROTATION_LOCKED_Y,
+            !allow_rotations_y,
+            wake_up,
+        );
+        self.set_locked_axes(
+            RigidBodyFlags::ROTATION_LOCKED_Z,
+            !allow_rotations_z,
+            wake_up,
+        );
+    }
+
+    // The effective mass properties are recomputed by the `RigidBodySet` at the beginning
+    // of the next timestep.
+    fn set_locked_axes(&mut self, axes: RigidBodyFlags, locked: bool, wake_up: bool) {
+        let mut flags = self.flags;
+        flags.set(axes, locked);
+
+        if flags != self.flags {
+            self.flags = flags;
+            self.changes.insert(RigidBodyChanges::LOCKED_AXES);
+
+            if locked {
+                // Remove the velocities along the axes that just got locked.
+                let lin_axes = [
+                    RigidBodyFlags::TRANSLATION_LOCKED_X,
+                    RigidBodyFlags::TRANSLATION_LOCKED_Y,
+                    RigidBodyFlags::TRANSLATION_LOCKED_Z,
+                ];
+                for (axis, vel) in lin_axes.iter().zip(self.linvel.iter_mut()) {
+                    if axes.contains(*axis) {
+                        *vel = 0.0;
+                    }
+                }
+
+                #[cfg(feature = "dim2")]
+                {
+                    if axes.contains(RigidBodyFlags::ROTATION_LOCKED_Z) {
+                        self.angvel = 0.0;
+                    }
+                }
+                #[cfg(feature = "dim3")]
+                {
+                    let ang_axes = [
+                        RigidBodyFlags::ROTATION_LOCKED_X,
+                        RigidBodyFlags::ROTATION_LOCKED_Y,
+                        RigidBodyFlags::ROTATION_LOCKED_Z,
+                    ];
+                    for (axis, vel) in ang_axes.iter().zip(self.angvel.iter_mut()) {
+                        if axes.contains(*axis) {
+                            *vel = 0.0;
+                        }
+                    }
+                }
+            }
+
+            if self.is_dynamic() && wake_up {
+                self.wake_up(true);
+            }
+        }
+    }
+
+    /// Is this rigid-body frozen?
+    ///
+    /// A frozen rigid-body doesn't move, but is still simulated as a dynamic body with an
+    /// infinite mass so the other bodies of its island keep interacting with it.
+    pub fn is_frozen(&self) -> bool {
+        self.flags.contains(RigidBodyFlags::FROZEN)
+    }
+
+    /// Freezes or unfreezes this rigid-body.
+    ///
+    /// Freezing a rigid-body resets its velocities and the forces applied to it.
+    pub fn set_frozen(&mut self, frozen: bool) {
+        if frozen {
+            self.linvel = na::zero();
+            self.angvel = na::zero();
+            self.force = na::zero();
+            self.torque = na::zero();
+        } else {
+            self.wake_up(true);
+        }
+
+        self.flags.set(RigidBodyFlags::FROZEN, frozen);
+        self.update_world_mass_properties();
+    }
+
+    /// Is this rigid-body a projectile?
+    ///
+    /// Contacts between two projectiles are ignored by default, unless the
+    /// `PhysicsHooksFlags::FILTER_CONTACT_PAIR` hook is enabled.
+    pub fn is_projectile(&self) -> bool {
+        self.flags.contains(RigidBodyFlags::PROJECTILE)
+    }
+
+    /// Marks this rigid-body as a projectile, or not.
+    pub fn set_projectile(&mut self, projectile: bool) {
+        self.flags.set(RigidBodyFlags::PROJECTILE, projectile)
+    }
+
+    /// Do the colliders of this rigid-body share a single broad-phase proxy?
+    pub fn uses_aggregate_proxy(&self) -> bool {
+        self.flags.contains(RigidBodyFlags::AGGREGATE_PROXY)
+    }
+
+    /// Makes all the colliders of this rigid-body share a single broad-phase proxy, or not.
+    ///
+    /// With an aggregate proxy, a rigid-body with many colliders (e.g. a station or a vehicle
+    /// made of hundreds of parts) only moves one AABB in the broad-phase instead of one per
+    /// collider. The colliders are indexed by a BVH in the local-space of the rigid-body, which
+    /// is only rebuilt when colliders are added, removed, or reshaped, and is used to find which
+    /// colliders are close to the other proxies overlapping the aggregate. The `QueryPipeline`
+    /// uses it too for the queries against this rigid-body only.
+    ///
+    /// The pairs of colliders reported to the narrow-phase are the same as without an aggregate
+    /// proxy. This is typically a loss for rigid-bodies with few colliders, or with colliders
+    /// spread so far apart that the AABB of the aggregate overlaps many unrelated proxies.
+    pub fn use_aggregate_proxy(&mut self, enabled: bool) {
+        if self.uses_aggregate_proxy() != enabled {
+            self.flags.set(RigidBodyFlags::AGGREGATE_PROXY, enabled);
+            self.changes.insert(RigidBodyChanges::AGGREGATE_PROXY);
+        }
+    }
+
+    /// Enables or disables the correction of the position of this rigid-body by the solver.
+    ///
+    /// If disabled, this rigid-body is still involved in the position-based correction of the
+    /// other bodies, but its own position is never corrected.
+    pub fn enable_position_correction(&mut self, enabled: bool) {
+        self.flags
+            .set(RigidBodyFlags::POSITION_CORRECTION_DISABLED, !enabled)
+    }
+
+    /// Is the position of this rigid-body corrected by the position solver?
+    pub fn is_position_correction_enabled(&self) -> bool {
+        !self
+            .flags
+            .contains(RigidBodyFlags::POSITION_CORRECTION_DISABLED)
+    }
+
     /// Enables of disable CCD (continuous collision-detection) for this rigid-body.
     pub fn enable_ccd(&mut self, enabled: bool) {
         self.flags.set(RigidBodyFlags::CCD_ENABLED, enabled)
@@ -303,6 +762,37 @@ impl RigidBody {
         self.update_world_mass_properties();
     }
 
+    /// The mass properties added to the contributions of the colliders attached to this rigid-body.
+    pub fn additional_mass_properties(&self) -> &MassProperties {
+        &self.additional_mass_properties
+    }
+
+    /// Sets the mass properties added to the contributions of the colliders attached to this rigid-body.
+    ///
+    /// Unlike `set_mass_properties`, this doesn't discard the mass properties computed from the
+    /// colliders: the previous additional mass properties are replaced by `props`, and the
+    /// colliders attached or removed afterwards keep adding or removing their own contributions.
+    /// This can be used, e.g., to add a lumped mass located at an arbitrary local point with
+    /// `MassProperties::new(local_point, mass, principal_inertia)`.
+    ///
+    /// If `wake_up` is `true` then the rigid-body will be woken up if it was
+    /// put to sleep because it did not move for a while.
+    pub fn set_additional_mass_properties(&mut self, props: MassProperties, wake_up: bool) {
+        let was_degenerate = self.is_degenerate_mass();
+        self.mass_properties -= self.additional_mass_properties;
+        self.mass_properties += props;
+        self.additional_mass_properties = props;
+        self.changes.insert(RigidBodyChanges::MASS_PROPERTIES);
+
+        if !self.is_mass_update_deferred() {
+            self.update_world_mass_properties();
+        }
+
+        if self.is_dynamic() && (wake_up || (was_degenerate && !self.is_degenerate_mass())) {
+            self.wake_up(true);
+        }
+    }
+
     /// The handles of colliders attached to this rigid body.
     pub fn colliders(&self) -> &[ColliderHandle] {
         &self.colliders[..]
@@ -329,6 +819,21 @@ impl RigidBody {
         self.body_status == BodyStatus::Static
     }
 
+    /// Is this rigid body dynamic with neither a mass nor an angular inertia?
+    ///
+    /// This happens for dynamic bodies without any collider, or with only massless
+    /// colliders, and no additional mass properties. A rigid-body with a degenerate mass
+    /// doesn't move and acts as a static body for the constraints solver until a collider
+    /// with a non-zero mass is attached to it.
+    pub fn is_degenerate_mass(&self) -> bool {
+        self.is_dynamic() && is_degenerate_mass(&self.mass_properties)
+    }
+
+    /// Is this rigid body dynamic with a non-degenerate mass?
+    pub(crate) fn is_dynamic_with_mass(&self) -> bool {
+        self.is_dynamic() && !is_degenerate_mass(&self.mass_properties)
+    }
+
     /// The mass of this rigid body.
     ///
     /// Returns zero if this rigid body has an infinite mass.
@@ -379,12 +884,65 @@ impl RigidBody {
         let mass_properties = coll
             .mass_properties()
             .transform_by(coll.position_wrt_parent());
+        let was_degenerate = self.is_degenerate_mass();
         self.colliders.push(handle);
         self.mass_properties += mass_properties;
-        self.update_world_mass_properties();
+
+        if !self.is_mass_update_deferred() {
+            self.update_world_mass_properties();
+        }
+
+        // The first collider with a non-zero mass makes the body simulated again.
+        if was_degenerate && !self.is_degenerate_mass() {
+            self.wake_up(true);
+        }
+    }
+
+    /// Is the update of the world-space mass properties of this body deferred until
+    /// `RigidBodySet::end_mass_update` is called?
+    pub(crate) fn is_mass_update_deferred(&self) -> bool {
+        self.flags.contains(RigidBodyFlags::MASS_UPDATE_DEFERRED)
+    }
+
+    /// Enables or disables the deferred update of the world-space mass properties.
+    ///
+    /// Disabling the deferred update recomputes the world-space mass properties.
+    pub(crate) fn set_mass_update_deferred(&mut self, deferred: bool) {
+        self.flags
+            .set(RigidBodyFlags::MASS_UPDATE_DEFERRED, deferred);
+
+        if !deferred {
+            self.update_world_mass_properties();
+        }
+    }
+
+    /// Is the simulation of this body suspended by `PhysicsPipeline::step_region`?
+    pub(crate) fn is_suspended(&self) -> bool {
+        self.flags.contains(RigidBodyFlags::SUSPENDED)
+    }
+
+    /// Suspends or resumes the simulation of this body without affecting its velocities
+    /// and sleep state.
+    ///
+    /// A suspended body is seen as sleeping by the rest of the pipeline.
+    pub(crate) fn set_suspended(&mut self, suspended: bool) {
+        self.flags.set(RigidBodyFlags::SUSPENDED, suspended);
+        self.activation.sleeping = suspended;
+
+        if suspended {
+            self.reset_step_deltas();
+        }
     }
 
-    pub(crate) fn update_colliders_positions(&mut self, colliders: &mut ColliderSet) {
+    pub(crate) fn update_colliders_positions(
+        &mut self,
+        body: RigidBodyHandle,
+        colliders: &mut ColliderSet,
+    ) {
+        if self.uses_aggregate_proxy() {
+            colliders.set_aggregate_position(body, self.position);
+        }
+
         for handle in &self.colliders {
             // NOTE: we use `get_mut_internal_with_modification_tracking` here because we want to
             //       benefit from the modification tracking to know the colliders
@@ -405,7 +963,10 @@ impl RigidBody {
                 .mass_properties()
                 .transform_by(coll.position_wrt_parent());
             self.mass_properties -= mass_properties;
-            self.update_world_mass_properties();
+
+            if !self.is_mass_update_deferred() {
+                self.update_world_mass_properties();
+            }
         }
     }
 
@@ -415,10 +976,13 @@ impl RigidBody {
     /// it is waken up. It can be woken manually with `self.wake_up` or automatically due to
     /// external forces like contacts.
     pub fn sleep(&mut self) {
-        self.activation.energy = 0.0;
+        self.activation.linear_energy = 0.0;
+        self.activation.angular_energy = 0.0;
         self.activation.sleeping = true;
         self.linvel = na::zero();
         self.angvel = na::zero();
+        self.prev_position = self.position;
+        self.reset_step_deltas();
     }
 
     /// Wakes up this rigid body if it is sleeping.
@@ -431,16 +995,41 @@ impl RigidBody {
             self.activation.sleeping = false;
         }
 
-        if (strong || self.activation.energy == 0.0) && self.is_dynamic() {
-            self.activation.energy = self.activation.threshold.abs() * 2.0;
+        if (strong || !self.activation.is_active()) && self.is_dynamic() {
+            self.activation.set_energies(2.0);
         }
     }
 
     pub(crate) fn update_energy(&mut self) {
         let mix_factor = 0.01;
-        let new_energy = (1.0 - mix_factor) * self.activation.energy
-            + mix_factor * (self.linvel.norm_squared() + self.angvel.gdot(self.angvel));
-        self.activation.energy = new_energy.min(self.activation.threshold.abs() * 4.0);
+        let activation = &mut self.activation;
+        let linear_energy =
+            (1.0 - mix_factor) * activation.linear_energy + mix_factor * self.linvel.norm_squared();
+        let angular_energy = (1.0 - mix_factor) * activation.angular_energy
+            + mix_factor * self.angvel.gdot(self.angvel);
+        activation.linear_energy = linear_energy.min(activation.linear_threshold.powi(2) * 4.0);
+        activation.angular_energy = angular_energy.min(activation.angular_threshold.powi(2) * 4.0);
+    }
+
+    /// The index of the active island this rigid-body was part of during the last timestep.
+    ///
+    /// Returns `None` if this rigid-body isn't dynamic or is sleeping. The index is only valid
+    /// right after a `PhysicsPipeline::step`, and can be given to `RigidBodySet::iter_island`.
+    pub fn island_id(&self) -> Option<usize> {
+        if self.is_dynamic_and_awake() {
+            Some(self.active_island_id)
+        } else {
+            None
+        }
+    }
+
+    /// Is this rigid body dynamic and awake?
+    ///
+    /// Only these bodies are part of an active island, and are moved by the solver. A sleeping
+    /// dynamic body touching an awake body, e.g., because of its `WakePolicy`, acts as a static
+    /// body, and its `active_set_offset` is stale.
+    pub(crate) fn is_dynamic_and_awake(&self) -> bool {
+        self.is_dynamic() && !self.is_sleeping()
     }
 
     /// Is this rigid body sleeping?
@@ -453,14 +1042,24 @@ impl RigidBody {
     }
 
     /// Is the velocity of this body not zero?
+    ///
+    /// A kinematic body is also considered moving if its next position, set by
+    /// `set_next_kinematic_position`, differs from its current position, if it
+    /// didn't reach the target set by `set_next_kinematic_position_with_dt` yet,
+    /// or if it is driven by non-zero velocities set by `set_kinematic_velocity`.
     pub fn is_moving(&self) -> bool {
-        !self.linvel.is_zero() || !self.angvel.is_zero()
+        !self.linvel.is_zero()
+            || !self.angvel.is_zero()
+            || (self.is_kinematic()
+                && (self.next_position != self.position
+                    || self.kinematic_target.is_some()
+                    || self.is_moved_by_kinematic_velocity()))
     }
 
     /// Computes the predict position of this rigid-body after `dt` seconds, taking
     /// into account its velocities and external forces applied to it.
     pub fn predict_position_using_velocity_and_forces(&self, dt: Real) -> Isometry<Real> {
-        let dlinvel = self.force * (self.effective_inv_mass * dt);
+        let dlinvel = self.force.component_mul(&self.effective_inv_mass) * dt;
         let dangvel = self
             .effective_world_inv_inertia_sqrt
             .transform_vector(self.torque * dt);
@@ -479,11 +1078,18 @@ impl RigidBody {
     }
 
     pub(crate) fn apply_damping(&mut self, dt: Real) {
-        self.linvel *= 1.0 / (1.0 + dt * self.linear_damping);
-        self.angvel *= 1.0 / (1.0 + dt * self.angular_damping);
+        // NOTE: the damping is exactly exponential so that its effect
+        //       doesn't depend on the timestep length.
+        self.linvel *= (-dt * self.linear_damping).exp();
+        self.angvel *= (-dt * self.angular_damping).exp();
     }
 
     pub(crate) fn integrate_next_position(&mut self, dt: Real) {
+        if self.is_degenerate_mass() {
+            self.next_position = self.position;
+            return;
+        }
+
         self.next_position = self.integrate_velocity(dt) * self.position;
         let _ = self.next_position.rotation.renormalize_fast();
     }
@@ -543,11 +1149,109 @@ impl RigidBody {
         }
     }
 
+    /// The angular velocity of this rigid-body around the `z` axis.
+    ///
+    /// In 2D, this is the angular velocity of this rigid-body. This can be used by code
+    /// targeting both 2D and 3D.
+    pub fn angvel_z(&self) -> Real {
+        utils::ang::ang_z(&self.angvel)
+    }
+
+    /// Sets the angular velocity of this rigid-body around the `z` axis.
+    ///
+    /// In 2D, this sets the angular velocity of this rigid-body. In 3D, the angular velocity
+    /// components along the `x` and `y` axes are left unchanged. This can be used by code
+    /// targeting both 2D and 3D.
+    ///
+    /// If `wake_up` is `true` then the rigid-body will be woken up if it was
+    /// put to sleep because it did not move for a while.
+    #[cfg(feature = "dim2")]
+    pub fn set_angvel_z(&mut self, angvel_z: Real, wake_up: bool) {
+        self.set_angvel(angvel_z, wake_up)
+    }
+
+    /// Sets the angular velocity of this rigid-body around the `z` axis.
+    ///
+    /// In 2D, this sets the angular velocity of this rigid-body. In 3D, the angular velocity
+    /// components along the `x` and `y` axes are left unchanged. This can be used by code
+    /// targeting both 2D and 3D.
+    ///
+    /// If `wake_up` is `true` then the rigid-body will be woken up if it was
+    /// put to sleep because it did not move for a while.
+    #[cfg(feature = "dim3")]
+    pub fn set_angvel_z(&mut self, angvel_z: Real, wake_up: bool) {
+        let mut angvel = self.angvel;
+        angvel.z = angvel_z;
+        self.set_angvel(angvel, wake_up)
+    }
+
     /// The world-space position of this rigid-body.
     pub fn position(&self) -> &Isometry<Real> {
         &self.position
     }
 
+    /// The world-space position of this rigid-body at the beginning of the last timestep.
+    ///
+    /// This is equal to `self.position()` if this rigid-body has been teleported with
+    /// `set_position` since the last timestep, or if it is sleeping.
+    pub fn previous_position(&self) -> &Isometry<Real> {
+        &self.prev_position
+    }
+
+    /// The position of this rigid-body interpolated between its previous and current positions.
+    ///
+    /// An `alpha` of 0 gives `self.previous_position()` and an `alpha` of 1 gives
+    /// `self.position()`. This is typically used to render the scene smoothly when the
+    /// rendering rate doesn't match the simulation rate.
+    pub fn interpolated_position(&self, alpha: Real) -> Isometry<Real> {
+        self.prev_position.lerp_slerp(&self.position, alpha)
+    }
+
+    /// The displacement of this rigid-body during the last timestep.
+    ///
+    /// The displacement is expressed in the local frame of the rigid-body at the end of the
+    /// previous timestep, i.e., `position_before_step * self.position_delta() == self.position()`.
+    /// This includes the teleportations made with `set_position` since the previous timestep.
+    /// This is the identity for bodies that didn't move, or that fell asleep, during the
+    /// last timestep.
+    pub fn position_delta(&self) -> &Isometry<Real> {
+        &self.position_delta
+    }
+
+    /// The change of the linear and angular velocities of this rigid-body during the last timestep.
+    ///
+    /// This is zero for bodies that didn't move, or that fell asleep, during the last timestep.
+    pub fn velocity_delta(&self) -> (Vector<Real>, AngVector<Real>) {
+        (self.linvel_delta, self.angvel_delta)
+    }
+
+    /// Does the displacement of this rigid-body during the last timestep exceed the given
+    /// distance or angle?
+    pub(crate) fn moved_more_than(&self, threshold_lin: Real, threshold_ang: Real) -> bool {
+        self.position_delta.translation.vector.norm() > threshold_lin
+            || self.position_delta.rotation.angle().abs() > threshold_ang
+    }
+
+    /// Measures the motion of this rigid-body since the last call to this method.
+    pub(crate) fn update_step_deltas(&mut self) {
+        self.position_delta = self.snapshot_position.inv_mul(&self.position);
+        self.linvel_delta = self.linvel - self.snapshot_linvel;
+        self.angvel_delta = self.angvel - self.snapshot_angvel;
+        self.snapshot_position = self.position;
+        self.snapshot_linvel = self.linvel;
+        self.snapshot_angvel = self.angvel;
+    }
+
+    /// Sets the motion of this rigid-body during the last timestep to zero.
+    pub(crate) fn reset_step_deltas(&mut self) {
+        self.position_delta = Isometry::identity();
+        self.linvel_delta = na::zero();
+        self.angvel_delta = na::zero();
+        self.snapshot_position = self.position;
+        self.snapshot_linvel = self.linvel;
+        self.snapshot_angvel = self.angvel;
+    }
+
     /// Sets the position and `next_kinematic_position` of this rigid body.
     ///
     /// This will teleport the rigid-body to the specified position/orientation,
@@ -561,6 +1265,8 @@ impl RigidBody {
         self.changes.insert(RigidBodyChanges::POSITION);
         self.position = pos;
         self.next_position = pos;
+        self.prev_position = pos;
+        self.kinematic_target = None;
 
         // TODO: Do we really need to check that the body isn't dynamic?
         if wake_up && self.is_dynamic() {
@@ -573,35 +1279,203 @@ impl RigidBody {
     }
 
     /// If this rigid body is kinematic, sets its future position after the next timestep integration.
+    ///
+    /// If the next timestep is split into several CCD substeps, the rigid-body moves towards
+    /// this position with a constant velocity during all the substeps.
+    ///
+    /// This takes precedence over the velocities set with `set_kinematic_velocity` for the
+    /// next timestep.
     pub fn set_next_kinematic_position(&mut self, pos: Isometry<Real>) {
         if self.is_kinematic() {
             self.next_position = pos;
+            self.kinematic_target = None;
+            self.next_kinematic_position_set = true;
         }
     }
 
-    pub(crate) fn compute_velocity_from_next_position(&mut self, inv_dt: Real) {
-        let dpos = self.next_position * self.position.inverse();
-        #[cfg(feature = "dim2")]
-        {
-            self.angvel = dpos.rotation.angle() * inv_dt;
+    /// If this rigid body is kinematic, sets the position it must reach in `dt` seconds.
+    ///
+    /// Unlike with `set_next_kinematic_position`, the target can be reached after several
+    /// timesteps, e.g., if `PhysicsPipeline::step` is called several times per frame. The
+    /// rigid-body moves towards the target with a constant velocity: each timestep, or CCD
+    /// substep, of length `h` covers the fraction `h / remaining_time` of the remaining motion.
+    /// The velocities set with `set_kinematic_velocity` are ignored until the target is reached.
+    pub fn set_next_kinematic_position_with_dt(&mut self, pos: Isometry<Real>, dt: Real) {
+        if self.is_kinematic() {
+            self.next_position = self.position;
+            self.kinematic_target = Some((pos, dt));
+            self.next_kinematic_position_set = true;
+        }
+    }
+
+    /// If this rigid body is kinematic, sets the velocities moving it at each timestep.
+    ///
+    /// The next position of the rigid-body is integrated from these velocities at each
+    /// timestep, or CCD substep, instead of being set with `set_next_kinematic_position`.
+    /// The linear velocity is the velocity of the center of mass, and the rotation happens
+    /// around the center of mass. The velocities are kept until they are set again, but are
+    /// ignored during the timesteps the next position of the rigid-body is set with
+    /// `set_next_kinematic_position` or `set_next_kinematic_position_with_dt`, even if they were
+    /// set after it.
+    pub fn set_kinematic_velocity(&mut self, linvel: Vector<Real>, angvel: AngVector<Real>) {
+        if self.is_kinematic() {
+            self.kinematic_linvel = linvel;
+            self.kinematic_angvel = angvel;
+        }
+    }
+
+    /// Is the next position of this kinematic rigid-body integrated from the velocities set with
+    /// `set_kinematic_velocity`?
+    fn uses_kinematic_velocity(&self) -> bool {
+        !self.next_kinematic_position_set && self.kinematic_target.is_none()
+    }
+
+    /// Do the velocities set with `set_kinematic_velocity` move this kinematic rigid-body?
+    fn is_moved_by_kinematic_velocity(&self) -> bool {
+        self.uses_kinematic_velocity()
+            && (!self.kinematic_linvel.is_zero() || !self.kinematic_angvel.is_zero())
+    }
+
+    /// The position reached by this kinematic rigid-body after `dt` seconds, and its remaining
+    /// target at that time.
+    fn kinematic_position_after(
+        &self,
+        dt: Real,
+    ) -> (Isometry<Real>, Option<(Isometry<Real>, Real)>) {
+        match self.kinematic_target {
+            // NOTE: the tolerance avoids a tiny last motion due to rounding errors
+            //       accumulated on the remaining time.
+            Some((target, remaining)) if remaining > dt * (1.0 + 1.0e-4) => (
+                self.position.lerp_slerp(&target, dt / remaining),
+                Some((target, remaining - dt)),
+            ),
+            Some((target, _)) => (target, None),
+            None if self.is_moved_by_kinematic_velocity() => {
+                let com = self.position * self.mass_properties.local_com;
+                let shift = Translation::from(com.coords);
+                let motion = Isometry::new(self.kinematic_linvel * dt, self.kinematic_angvel * dt);
+                (shift * motion * shift.inverse() * self.position, None)
+            }
+            None => (self.next_position, None),
         }
+    }
+
+    /// The position this kinematic rigid-body will have at the end of a (sub)step of length `dt`.
+    pub(crate) fn predicted_kinematic_position(&self, dt: Real) -> Isometry<Real> {
+        self.kinematic_position_after(dt).0
+    }
+
+    /// The velocities moving this rigid-body from its current position to `next_position`
+    /// in `dt` seconds.
+    fn velocity_to(
+        &self,
+        next_position: &Isometry<Real>,
+        dt: Real,
+    ) -> (Vector<Real>, AngVector<Real>) {
+        let inv_dt = utils::inv(dt);
+        let com = self.position * self.mass_properties.local_com;
+        let next_com = next_position * self.mass_properties.local_com;
+        let drot = next_position.rotation * self.position.rotation.inverse();
+        #[cfg(feature = "dim2")]
+        let angvel = drot.angle() * inv_dt;
         #[cfg(feature = "dim3")]
-        {
-            self.angvel = dpos.rotation.scaled_axis() * inv_dt;
+        let angvel = drot.scaled_axis() * inv_dt;
+
+        ((next_com - com) * inv_dt, angvel)
+    }
+
+    /// The velocities moving this rigid-body from its current position to its next position in
+    /// `dt` seconds.
+    ///
+    /// The next position is either the one set by `set_next_kinematic_position`, the one
+    /// reached after `dt` seconds when moving towards the target set by
+    /// `set_next_kinematic_position_with_dt`, or the one integrated from the velocities set by
+    /// `set_kinematic_velocity`, which are then returned as-is.
+    ///
+    /// The linear velocity is the velocity of the center of mass, and the rotation happens
+    /// around the center of mass. This is the velocity used by the solver for kinematic bodies,
+    /// so it is also the velocity imparted by kinematic bodies to the dynamic bodies they touch.
+    pub fn kinematic_velocity(&self, dt: Real) -> (Vector<Real>, AngVector<Real>) {
+        if self.uses_kinematic_velocity() {
+            return (self.kinematic_linvel, self.kinematic_angvel);
         }
-        self.linvel = dpos.translation.vector * inv_dt;
+
+        self.velocity_to(&self.kinematic_position_after(dt).0, dt)
+    }
+
+    /// The predicted position of this rigid-body `t` seconds after the beginning of the next
+    /// timestep of length `dt`.
+    ///
+    /// The time `t` is clamped to `[0, dt]`. For kinematic bodies, this moves from the current
+    /// position at `t = 0` to `self.next_position()` at `t = dt` with the velocities given by
+    /// `self.kinematic_velocity(dt)`. For dynamic bodies, this extrapolates the current position
+    /// using the current velocities, ignoring forces and contacts. Static bodies don't move.
+    pub fn predicted_position_at(&self, t: Real, dt: Real) -> Isometry<Real> {
+        let t = t.min(dt).max(0.0);
+        let (linvel, angvel) = match self.body_status {
+            BodyStatus::Static => return self.position,
+            BodyStatus::Kinematic => self.kinematic_velocity(dt),
+            BodyStatus::Dynamic => (self.linvel, self.angvel),
+        };
+
+        let com = self.position * self.mass_properties.local_com;
+        let shift = Translation::from(com.coords);
+        shift * Isometry::new(linvel * t, angvel * t) * shift.inverse() * self.position
+    }
+
+    /// Sets the next position and the velocities of this kinematic body for a (sub)step of
+    /// length `dt`, with `step_remaining_time` being the time left until the end of the timestep.
+    pub(crate) fn compute_velocity_from_next_position(
+        &mut self,
+        dt: Real,
+        step_remaining_time: Real,
+    ) {
+        if self.kinematic_target.is_none() && self.next_position != self.position {
+            // The position set with `set_next_kinematic_position` must be reached at the
+            // end of the timestep, even if it is split into several substeps.
+            self.kinematic_target = Some((self.next_position, step_remaining_time));
+        }
+
+        let (next_position, target) = self.kinematic_position_after(dt);
+        let (linvel, angvel) = if self.uses_kinematic_velocity() {
+            (self.kinematic_linvel, self.kinematic_angvel)
+        } else {
+            self.velocity_to(&next_position, dt)
+        };
+        self.next_position = next_position;
+        self.kinematic_target = target;
+        self.linvel = linvel;
+        self.angvel = angvel;
     }
 
     pub(crate) fn update_world_mass_properties(&mut self) {
         self.world_com = self.mass_properties.world_com(&self.position);
-        self.effective_inv_mass = self.mass_properties.inv_mass;
+        self.effective_inv_mass = Vector::repeat(self.mass_properties.inv_mass);
         self.effective_world_inv_inertia_sqrt = self
             .mass_properties
             .world_inv_inertia_sqrt(&self.position.rotation);
 
+        // Frozen bodies, and bodies with a degenerate mass, behave as if they had an infinite mass.
+        if self.flags.contains(RigidBodyFlags::FROZEN) || self.is_degenerate_mass() {
+            self.effective_inv_mass = Vector::zeros();
+            self.effective_world_inv_inertia_sqrt = AngularInertia::zero();
+            return;
+        }
+
         // Take into account translation/rotation locking.
-        if self.flags.contains(RigidBodyFlags::TRANSLATION_LOCKED) {
-            self.effective_inv_mass = 0.0;
+        if self.flags.contains(RigidBodyFlags::TRANSLATION_LOCKED_X) {
+            self.effective_inv_mass.x = 0.0;
+        }
+
+        if self.flags.contains(RigidBodyFlags::TRANSLATION_LOCKED_Y) {
+            self.effective_inv_mass.y = 0.0;
+        }
+
+        #[cfg(feature = "dim3")]
+        {
+            if self.flags.contains(RigidBodyFlags::TRANSLATION_LOCKED_Z) {
+                self.effective_inv_mass.z = 0.0;
+            }
         }
 
         #[cfg(feature = "dim2")]
@@ -697,7 +1571,7 @@ impl RigidBody {
     /// This does nothing on non-dynamic bodies.
     pub fn apply_impulse(&mut self, impulse: Vector<Real>, wake_up: bool) {
         if self.body_status == BodyStatus::Dynamic {
-            self.linvel += impulse * self.effective_inv_mass;
+            self.linvel += impulse.component_mul(&self.effective_inv_mass);
 
             if wake_up {
                 self.wake_up(true);
@@ -706,7 +1580,8 @@ impl RigidBody {
     }
 
     /// Applies an angular impulse at the center-of-mass of this rigid-body.
-    /// The impulse is applied right away, changing the angular velocity.
+    /// The impulse is applied right away, changing the angular velocity by the world-space
+    /// inverse angular inertia tensor times `torque_impulse`, except along the locked rotation axes.
     /// This does nothing on non-dynamic bodies.
     #[cfg(feature = "dim2")]
     pub fn apply_torque_impulse(&mut self, torque_impulse: Real, wake_up: bool) {
@@ -721,7 +1596,8 @@ impl RigidBody {
     }
 
     /// Applies an angular impulse at the center-of-mass of this rigid-body.
-    /// The impulse is applied right away, changing the angular velocity.
+    /// The impulse is applied right away, changing the angular velocity by the world-space
+    /// inverse angular inertia tensor times `torque_impulse`, except along the locked rotation axes.
     /// This does nothing on non-dynamic bodies.
     #[cfg(feature = "dim3")]
     pub fn apply_torque_impulse(&mut self, torque_impulse: Vector<Real>, wake_up: bool) {
@@ -757,6 +1633,25 @@ impl RigidBody {
         self.linvel + self.angvel.gcross(dpt)
     }
 
+    /// The velocity the given world-space point on this rigid-body will have during the next
+    /// timestep of length `dt`.
+    ///
+    /// For kinematic bodies, this is the velocity implied by the motion from the current position
+    /// to `self.next_position()`, as given by `self.kinematic_velocity(dt)`, e.g., the surface
+    /// velocity of a moving platform. For dynamic bodies, this is `self.velocity_at_point(point)`.
+    /// Static bodies don't move.
+    pub fn predicted_velocity_at_point(&self, point: &Point<Real>, dt: Real) -> Vector<Real> {
+        match self.body_status {
+            BodyStatus::Static => Vector::zeros(),
+            BodyStatus::Kinematic => {
+                let (linvel, angvel) = self.kinematic_velocity(dt);
+                let com = self.position * self.mass_properties.local_com;
+                linvel + angvel.gcross(point - com)
+            }
+            BodyStatus::Dynamic => self.velocity_at_point(point),
+        }
+    }
+
     /// The kinetic energy of this body.
     pub fn kinetic_energy(&self) -> Real {
         let mut energy = (self.mass() * self.linvel().norm_squared()) / 2.0;
@@ -800,9 +1695,14 @@ pub struct RigidBodyBuilder {
     flags: RigidBodyFlags,
     mass_properties: MassProperties,
     can_sleep: bool,
+    linear_sleep_threshold: Real,
+    angular_sleep_threshold: Real,
     sleeping: bool,
     ccd_enabled: bool,
     dominance_group: i8,
+    solver_priority: u8,
+    wake_policy: WakePolicy,
+    max_contact_angular_acceleration: Option<Real>,
     user_data: u128,
 }
 
@@ -820,9 +1720,14 @@ impl RigidBodyBuilder {
             flags: RigidBodyFlags::empty(),
             mass_properties: MassProperties::zero(),
             can_sleep: true,
+            linear_sleep_threshold: ActivationStatus::default_linear_threshold(),
+            angular_sleep_threshold: ActivationStatus::default_angular_threshold(),
             sleeping: false,
             ccd_enabled: false,
             dominance_group: 0,
+            solver_priority: 0,
+            wake_policy: WakePolicy::Always,
+            max_contact_angular_acceleration: None,
             user_data: 0,
         }
     }
@@ -842,6 +1747,19 @@ impl RigidBodyBuilder {
         Self::new(BodyStatus::Dynamic)
     }
 
+    /// Initializes the builder of a new dynamic rigid body configured as a fast projectile.
+    ///
+    /// The rigid-body has CCD enabled, never sleeps, and its position isn't corrected by the
+    /// position solver. Contacts between two projectiles are ignored by default.
+    pub fn projectile() -> Self {
+        let mut builder = Self::new_dynamic()
+            .ccd_enabled(true)
+            .can_sleep(false)
+            .position_correction_enabled(false);
+        builder.flags.set(RigidBodyFlags::PROJECTILE, true);
+        builder
+    }
+
     /// Sets the scale applied to the gravity force affecting the rigid-body to be created.
     pub fn gravity_scale(mut self, x: Real) -> Self {
         self.gravity_scale = x;
@@ -854,6 +1772,42 @@ impl RigidBodyBuilder {
         self
     }
 
+    /// Sets the solver priority of the rigid-body to be created.
+    ///
+    /// The constraints (contacts and joints) of an island are solved in increasing order of the
+    /// largest solver priority of the two rigid-bodies they involve. Because the constraints solved
+    /// last are the best satisfied, giving a higher priority to gameplay-critical rigid-bodies
+    /// (e.g. a player character) reduces their penetrations when the number of solver iterations
+    /// is low. Defaults to 0.
+    pub fn solver_priority(mut self, priority: u8) -> Self {
+        self.solver_priority = priority;
+        self
+    }
+
+    /// Sets whether the rigid-body to be created is woken up by the contacts of the awake bodies
+    /// touching it.
+    ///
+    /// This is useful for decorative piles of bodies which shouldn't be woken up by every body
+    /// brushing against them. See `WakePolicy` for details. Defaults to `WakePolicy::Always`.
+    pub fn wake_policy(mut self, policy: WakePolicy) -> Self {
+        self.wake_policy = policy;
+        self
+    }
+
+    /// Sets the maximum angular acceleration the contacts can apply to the rigid-body to be
+    /// created.
+    ///
+    /// Small and light rigid-bodies (e.g. debris) hit by heavy and fast bodies can acquire an
+    /// extremely large angular velocity because of their tiny angular inertia. With this limit,
+    /// the solver scales down the angular part of the velocity changes applied by the contacts so
+    /// that they don't increase the angular velocity of the rigid-body by more than
+    /// `max_accel * dt` during a timestep. The linear part of these velocity changes is
+    /// unaffected. Defaults to `None`, i.e., no limit.
+    pub fn max_contact_angular_acceleration(mut self, max_accel: Real) -> Self {
+        self.max_contact_angular_acceleration = Some(max_accel);
+        self
+    }
+
     /// Sets the initial translation of the rigid-body to be created.
     #[cfg(feature = "dim2")]
     pub fn translation(mut self, x: Real, y: Real) -> Self {
@@ -877,6 +1831,14 @@ impl RigidBodyBuilder {
         self
     }
 
+    /// Sets the initial orientation of the rigid-body to be created to a rotation around the `z` axis.
+    ///
+    /// In 2D, this is the same as `self.rotation(angle)`. This can be used by code targeting
+    /// both 2D and 3D.
+    pub fn rotation_z(self, angle: Real) -> Self {
+        self.rotation(utils::ang::ang_from_z(angle))
+    }
+
     /// Sets the initial position (translation and orientation) of the rigid-body to be created.
     pub fn position(mut self, pos: Isometry<Real>) -> Self {
         self.position = pos;
@@ -899,6 +1861,9 @@ impl RigidBodyBuilder {
     /// Therefore, if you want your provided mass properties to be the final
     /// mass properties of your rigid-body, don't attach colliders to it, or
     /// only attach colliders with densities equal to zero.
+    ///
+    /// They can be modified after the rigid-body is built with
+    /// `RigidBody::set_additional_mass_properties`.
     pub fn additional_mass_properties(mut self, props: MassProperties) -> Self {
         self.mass_properties = props;
         self
@@ -918,6 +1883,37 @@ impl RigidBodyBuilder {
         self
     }
 
+    /// Only allow translations of this rigid-body along specific coordinate axes.
+    #[cfg(feature = "dim2")]
+    pub fn restrict_translations(
+        mut self,
+        allow_translation_x: bool,
+        allow_translation_y: bool,
+    ) -> Self {
+        self.flags
+            .set(RigidBodyFlags::TRANSLATION_LOCKED_X, !allow_translation_x);
+        self.flags
+            .set(RigidBodyFlags::TRANSLATION_LOCKED_Y, !allow_translation_y);
+        self
+    }
+
+    /// Only allow translations of this rigid-body along specific coordinate axes.
+    #[cfg(feature = "dim3")]
+    pub fn restrict_translations(
+        mut self,
+        allow_translation_x: bool,
+        allow_translation_y: bool,
+        allow_translation_z: bool,
+    ) -> Self {
+        self.flags
+            .set(RigidBodyFlags::TRANSLATION_LOCKED_X, !allow_translation_x);
+        self.flags
+            .set(RigidBodyFlags::TRANSLATION_LOCKED_Y, !allow_translation_y);
+        self.flags
+            .set(RigidBodyFlags::TRANSLATION_LOCKED_Z, !allow_translation_z);
+        self
+    }
+
     /// Only allow rotations of this rigid-body around specific coordinate axes.
     #[cfg(feature = "dim3")]
     pub fn restrict_rotations(
@@ -1045,18 +2041,44 @@ impl RigidBodyBuilder {
         self
     }
 
+    /// Sets the initial angular velocity of the rigid-body to be created around the `z` axis.
+    ///
+    /// In 2D, this is the same as `self.angvel(angvel)`. This can be used by code targeting
+    /// both 2D and 3D.
+    pub fn angvel_z(self, angvel: Real) -> Self {
+        self.angvel(utils::ang::ang_from_z(angvel))
+    }
+
     /// Sets whether or not the rigid-body to be created can sleep if it reaches a dynamic equilibrium.
     pub fn can_sleep(mut self, can_sleep: bool) -> Self {
         self.can_sleep = can_sleep;
         self
     }
 
+    /// Sets the linear and angular velocities bellow which the rigid-body to be created can fall
+    /// asleep.
+    ///
+    /// The rigid-body is kept awake as long as either its linear or its angular velocity exceeds
+    /// its threshold, e.g., a small angular threshold keeps a slowly spinning wheel awake.
+    pub fn sleep_thresholds(mut self, linear: Real, angular: Real) -> Self {
+        self.linear_sleep_threshold = linear;
+        self.angular_sleep_threshold = angular;
+        self
+    }
+
     /// Enabled continuous collision-detection for this rigid-body.
     pub fn ccd_enabled(mut self, enabled: bool) -> Self {
         self.ccd_enabled = enabled;
         self
     }
 
+    /// Sets whether or not the solver corrects the position of the rigid-body to be created.
+    pub fn position_correction_enabled(mut self, enabled: bool) -> Self {
+        self.flags
+            .set(RigidBodyFlags::POSITION_CORRECTION_DISABLED, !enabled);
+        self
+    }
+
     /// Sets whether or not the rigid-body is to be created asleep.
     pub fn sleeping(mut self, sleeping: bool) -> Self {
         self.sleeping = sleeping;
@@ -1068,24 +2090,35 @@ impl RigidBodyBuilder {
         let mut rb = RigidBody::new();
         rb.next_position = self.position; // FIXME: compute the correct value?
         rb.position = self.position;
+        rb.prev_position = self.position;
         rb.linvel = self.linvel;
         rb.angvel = self.angvel;
+        rb.reset_step_deltas();
         rb.body_status = self.body_status;
         rb.user_data = self.user_data;
         rb.mass_properties = self.mass_properties;
+        rb.additional_mass_properties = self.mass_properties;
         rb.linear_damping = self.linear_damping;
         rb.angular_damping = self.angular_damping;
         rb.gravity_scale = self.gravity_scale;
         rb.flags = self.flags;
         rb.dominance_group = self.dominance_group;
+        rb.solver_priority = self.solver_priority;
+        rb.wake_policy = self.wake_policy;
+        rb.max_contact_angular_acceleration = self.max_contact_angular_acceleration;
         rb.enable_ccd(self.ccd_enabled);
+        rb.activation = ActivationStatus::with_thresholds(
+            self.linear_sleep_threshold,
+            self.angular_sleep_threshold,
+        );
 
         if self.can_sleep && self.sleeping {
             rb.sleep();
         }
 
         if !self.can_sleep {
-            rb.activation.threshold = -1.0;
+            rb.activation.linear_threshold = -1.0;
+            rb.activation.angular_threshold = -1.0;
         }
 
         rb
@@ -1094,40 +2127,65 @@ impl RigidBodyBuilder {
 
 /// The activation status of a body.
 ///
-/// This controls whether a body is sleeping or not.
+/// This controls whether a body is sleeping or not. A body is kept awake as long as either its
+/// linear or its angular velocity, smoothed over the last timesteps, exceeds its threshold.
 ///
-/// * If [`Self::threshold`] is negative, the body never sleeps.
+/// * If [`Self::linear_threshold`] or [`Self::angular_threshold`] is negative, the body never
+///   sleeps.
 #[derive(Copy, Clone, Debug)]
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 pub struct ActivationStatus {
-    /// The threshold pseudo-kinetic energy bellow which the body can fall asleep.
-    pub threshold: Real,
-    /// The current pseudo-kinetic energy of the body.
-    pub energy: Real,
+    /// The linear velocity bellow which the body can fall asleep.
+    pub linear_threshold: Real,
+    /// The angular velocity bellow which the body can fall asleep.
+    pub angular_threshold: Real,
+    /// The current smoothed squared linear velocity of the body.
+    pub linear_energy: Real,
+    /// The current smoothed squared angular velocity of the body.
+    pub angular_energy: Real,
     /// Is this body already sleeping?
     pub sleeping: bool,
 }
 
 impl ActivationStatus {
-    /// The default amount of energy bellow which a body can be put to sleep by nphysics.
-    pub fn default_threshold() -> Real {
-        0.01
+    /// The default linear velocity bellow which a body can be put to sleep.
+    pub fn default_linear_threshold() -> Real {
+        0.1
     }
 
-    /// Create a new activation status initialised with the default activation threshold and is active.
+    /// The default angular velocity bellow which a body can be put to sleep.
+    pub fn default_angular_threshold() -> Real {
+        0.1
+    }
+
+    /// Create a new active activation status initialised with the default activation thresholds.
     pub fn new_active() -> Self {
-        ActivationStatus {
-            threshold: Self::default_threshold(),
-            energy: Self::default_threshold() * 4.0,
+        Self::with_thresholds(
+            Self::default_linear_threshold(),
+            Self::default_angular_threshold(),
+        )
+    }
+
+    /// Create a new active activation status initialised with the given activation thresholds.
+    pub fn with_thresholds(linear_threshold: Real, angular_threshold: Real) -> Self {
+        let mut status = ActivationStatus {
+            linear_threshold,
+            angular_threshold,
+            linear_energy: 0.0,
+            angular_energy: 0.0,
             sleeping: false,
-        }
+        };
+        status.set_energies(4.0);
+        status
     }
 
-    /// Create a new activation status initialised with the default activation threshold and is inactive.
+    /// Create a new inactive activation status initialised with the default activation thresholds.
     pub fn new_inactive() -> Self {
         ActivationStatus {
-            threshold: Self::default_threshold(),
-            energy: 0.0,
+            linear_threshold: Self::default_linear_threshold(),
+            angular_threshold: Self::default_angular_threshold(),
+            linear_energy: 0.0,
+            angular_energy: 0.0,
             sleeping: true,
         }
     }
@@ -1135,6 +2193,166 @@ impl ActivationStatus {
     /// Returns `true` if the body is not asleep.
     #[inline]
     pub fn is_active(&self) -> bool {
-        self.energy != 0.0
+        self.linear_energy != 0.0 || self.angular_energy != 0.0
+    }
+
+    /// Are both the smoothed linear and angular velocities of the body bellow their thresholds?
+    #[inline]
+    pub fn can_sleep(&self) -> bool {
+        self.linear_threshold >= 0.0
+            && self.angular_threshold >= 0.0
+            && self.linear_energy <= self.linear_threshold.powi(2)
+            && self.angular_energy <= self.angular_threshold.powi(2)
+    }
+
+    // Sets the energies to `factor` times the squared thresholds.
+    pub(crate) fn set_energies(&mut self, factor: Real) {
+        self.linear_energy = self.linear_threshold.powi(2) * factor;
+        self.angular_energy = self.angular_threshold.powi(2) * factor;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dynamics::{RigidBodyBuilder, RigidBodySet};
+    use crate::geometry::{ColliderBuilder, ColliderSet};
+    use crate::math::Vector;
+
+    #[test]
+    fn rim_velocity_of_spinning_bodies_matches_their_angular_velocity() {
+        use crate::math::{Isometry, Point, Real};
+
+        let dt = 1.0 / 60.0;
+        let (omega, radius): (Real, Real) = (3.0, 0.5);
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+
+        #[cfg(feature = "dim2")]
+        let (rb, angvel) = (RigidBodyBuilder::new_dynamic().translation(1.0, 2.0), omega);
+        #[cfg(feature = "dim3")]
+        let (rb, angvel) = (
+            RigidBodyBuilder::new_dynamic().translation(1.0, 2.0, 0.0),
+            Vector::z() * omega,
+        );
+        let wheel = bodies.insert(rb.angvel(angvel).build());
+        colliders.insert(ColliderBuilder::ball(radius).build(), wheel, &mut bodies);
+
+        // The rim moves tangentially at `omega * radius`, on top of the linear velocity.
+        let rim = bodies[wheel].world_com + Vector::x() * radius;
+        let rim_velocity = bodies[wheel].velocity_at_point(&rim);
+        assert!((rim_velocity - Vector::y() * omega * radius).norm() < 1.0e-5);
+        assert_eq!(
+            bodies[wheel].predicted_velocity_at_point(&rim, dt),
+            rim_velocity
+        );
+
+        bodies[wheel].set_linvel(Vector::x() * 2.0, true);
+        let rim_velocity = bodies[wheel].velocity_at_point(&rim);
+        let expected = Vector::x() * 2.0 + Vector::y() * omega * radius;
+        assert!((rim_velocity - expected).norm() < 1.0e-5);
+
+        // The surface velocity of a kinematic platform is implied by its next position.
+        let platform = bodies.insert(RigidBodyBuilder::new_kinematic().build());
+        colliders.insert(ColliderBuilder::ball(radius).build(), platform, &mut bodies);
+        #[cfg(feature = "dim2")]
+        let next_position = Isometry::rotation(omega * dt);
+        #[cfg(feature = "dim3")]
+        let next_position = Isometry::rotation(Vector::z() * omega * dt);
+        bodies[platform].set_next_kinematic_position(next_position);
+
+        let rim = Point::from(Vector::x() * radius);
+        let surface_velocity = bodies[platform].predicted_velocity_at_point(&rim, dt);
+        assert!((surface_velocity - Vector::y() * omega * radius).norm() < 1.0e-3);
+        assert_eq!(bodies[platform].velocity_at_point(&rim), Vector::zeros());
+
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        let point = Point::from(Vector::x() * radius);
+        assert_eq!(
+            bodies[ground].predicted_velocity_at_point(&point, dt),
+            Vector::zeros()
+        );
+    }
+
+    #[test]
+    fn torque_impulses_change_the_angular_velocity_by_the_inverse_inertia() {
+        use crate::math::Isometry;
+
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+
+        // An elongated and rotated body, so its world-space inertia tensor isn't diagonal.
+        #[cfg(feature = "dim2")]
+        let (position, collider, angvel) = (
+            Isometry::new(Vector::new(1.0, 2.0), 0.7),
+            ColliderBuilder::cuboid(2.0, 0.3),
+            1.5,
+        );
+        #[cfg(feature = "dim3")]
+        let (position, collider, angvel) = (
+            Isometry::new(Vector::new(1.0, 2.0, 3.0), Vector::new(0.3, -0.7, 0.5)),
+            ColliderBuilder::cuboid(2.0, 0.3, 0.8),
+            Vector::new(0.3, -1.2, 2.0),
+        );
+        let collider = collider.density(2.0).build();
+        let insert_body = |bodies: &mut RigidBodySet, colliders: &mut ColliderSet, rb| {
+            let handle = bodies.insert(rb);
+            colliders.insert(collider.clone(), handle, bodies);
+            handle
+        };
+
+        let free = insert_body(
+            &mut bodies,
+            &mut colliders,
+            RigidBodyBuilder::new_dynamic().position(position).build(),
+        );
+        #[cfg(feature = "dim2")]
+        let locked_rb = RigidBodyBuilder::new_dynamic().lock_rotations();
+        #[cfg(feature = "dim3")]
+        let locked_rb = RigidBodyBuilder::new_dynamic().restrict_rotations(true, false, true);
+        let locked = insert_body(
+            &mut bodies,
+            &mut colliders,
+            locked_rb.position(position).build(),
+        );
+        let fixed = insert_body(
+            &mut bodies,
+            &mut colliders,
+            RigidBodyBuilder::new_static().position(position).build(),
+        );
+
+        // The torque impulse `I * angvel`, with `I` the world-space inertia tensor.
+        let rb = &bodies[free];
+        let inv_inertia_sqrt = rb
+            .mass_properties()
+            .world_inv_inertia_sqrt(&rb.position().rotation);
+        #[cfg(feature = "dim2")]
+        let torque_impulse = angvel / (inv_inertia_sqrt * inv_inertia_sqrt);
+        #[cfg(feature = "dim3")]
+        let torque_impulse = {
+            let inertia_sqrt = inv_inertia_sqrt.inverse_unchecked();
+            inertia_sqrt * (inertia_sqrt * angvel)
+        };
+
+        for handle in &[free, locked, fixed] {
+            bodies
+                .get_mut(*handle)
+                .unwrap()
+                .apply_torque_impulse(torque_impulse, true);
+            assert_eq!(*bodies[*handle].linvel(), Vector::zeros());
+        }
+
+        // The locked rotations and the static body are unaffected.
+        #[cfg(feature = "dim2")]
+        {
+            assert!((bodies[free].angvel() - angvel).abs() < 1.0e-4);
+            assert_eq!(bodies[locked].angvel(), 0.0);
+            assert_eq!(bodies[fixed].angvel(), 0.0);
+        }
+        #[cfg(feature = "dim3")]
+        {
+            assert!((bodies[free].angvel() - angvel).norm() < 1.0e-4);
+            assert_eq!(bodies[locked].angvel().y, 0.0);
+            assert_eq!(*bodies[fixed].angvel(), Vector::zeros());
+        }
     }
 }
@@ -7,6 +7,7 @@ use crate::geometry::{
 use crate::math::{AngVector, AngularInertia, Isometry, Point, Real, Translation, Vector};
 use crate::parry::partitioning::IndexedData;
 use crate::utils::{WCross, WDot};
+use alloc::{vec, vec::Vec};
 use num::Zero;
 
 /// The unique handle of a rigid body added to a `RigidBodySet`.
@@ -2,10 +2,20 @@
 use rayon::prelude::*;
 
 use crate::data::arena::Arena;
-use crate::dynamics::{BodyStatus, Joint, JointSet, RigidBody, RigidBodyChanges};
-use crate::geometry::{ColliderSet, InteractionGraph, NarrowPhase};
+use crate::data::{HashMap, HashSet};
+use crate::dynamics::{BodyStatus, Joint, JointHandle, JointSet, RigidBody, RigidBodyChanges};
+use crate::geometry::{
+    Collider, ColliderHandle, ColliderSet, InteractionGraph, NarrowPhase, RemoveSettleMode, AABB,
+};
+use crate::math::{Isometry, Point, Real, Vector};
+use alloc::vec::Vec;
+#[cfg(feature = "parallel")]
+use core::ops::{Deref, DerefMut};
+use core::ops::{Index, IndexMut};
+use parry::bounding_volume::BoundingVolume;
 use parry::partitioning::IndexedData;
-use std::ops::{Index, IndexMut};
+#[cfg(feature = "parallel")]
+use std::sync::Mutex;
 
 /// The unique handle of a rigid body added to a `RigidBodySet`.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
@@ -43,6 +53,13 @@ impl IndexedData for RigidBodyHandle {
     }
 }
 
+/// An insertion or removal of a rigid-body, logged until the next timestep.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum RigidBodyArenaChange {
+    Inserted(RigidBodyHandle),
+    Removed(RigidBodyHandle),
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 /// A pair of rigid body handles.
@@ -60,28 +77,244 @@ impl BodyPair {
     }
 }
 
+#[derive(Clone)]
+/// A rigid-body removed from a `RigidBodySet`, together with its colliders and joints.
+///
+/// It can be inserted back, with all its colliders and joints, by `RigidBodySet::insert_removed`.
+pub struct RemovedRigidBody {
+    /// The handle the rigid-body had before its removal.
+    pub handle: RigidBodyHandle,
+    /// The removed rigid-body, without its colliders.
+    pub body: RigidBody,
+    /// The colliders that were attached to the rigid-body.
+    ///
+    /// Their position relative to the rigid-body is preserved.
+    pub colliders: Vec<Collider>,
+    /// The joints that were attached to the rigid-body, with the handle of the other body they
+    /// were attached to.
+    ///
+    /// If this other body is removed and inserted back too, its handle must be replaced here by
+    /// its new handle before calling `RigidBodySet::insert_removed`.
+    pub joints: Vec<(RigidBodyHandle, Joint)>,
+}
+
+#[derive(Clone)]
+/// The handles resulting from the insertion of a `RemovedRigidBody`.
+pub struct InsertedRigidBody {
+    /// The new handle of the rigid-body.
+    pub body: RigidBodyHandle,
+    /// The new handles of its colliders, in the same order as `RemovedRigidBody::colliders`.
+    pub colliders: Vec<ColliderHandle>,
+    /// The new handles of its joints, in the same order as `RemovedRigidBody::joints`.
+    ///
+    /// The joints attached to a body that no longer exists are not inserted and are given
+    /// back as `Err` instead.
+    pub joints: Vec<Result<JointHandle, (RigidBodyHandle, Joint)>>,
+}
+
+#[derive(Clone, Debug, Default)]
+/// The new handles of the rigid-bodies, colliders, and joints moved by `RigidBodySet::append`,
+/// indexed by their handles in the sets they were moved from.
+pub struct HandleMap {
+    /// The new handles of the rigid-bodies.
+    pub bodies: HashMap<RigidBodyHandle, RigidBodyHandle>,
+    /// The new handles of the colliders.
+    pub colliders: HashMap<ColliderHandle, ColliderHandle>,
+    /// The new handles of the joints.
+    pub joints: HashMap<JointHandle, JointHandle>,
+}
+
+/// Statistics about the rigid-bodies of a `RigidBodySet`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct RigidBodySetStats {
+    /// The number of rigid-bodies in the set.
+    pub num_bodies: usize,
+    /// The number of dynamic rigid-bodies which are not sleeping.
+    pub num_active_dynamic: usize,
+    /// The number of kinematic rigid-bodies which are not sleeping.
+    pub num_active_kinematic: usize,
+    /// The number of active islands.
+    pub num_active_islands: usize,
+    /// The number of rigid-bodies the set can contain without reallocating.
+    pub capacity: usize,
+    /// The approximate number of bytes allocated by the set.
+    pub bytes: usize,
+}
+
+/// A mutable reference to a rigid-body, yielded by `RigidBodySet::par_iter_mut_tracked`.
+///
+/// The rigid-body is marked as modified the first time it is dereferenced mutably.
+#[cfg(feature = "parallel")]
+pub struct TrackedRigidBodyMut<'a> {
+    handle: RigidBodyHandle,
+    rb: &'a mut RigidBody,
+    shards: &'a ModifiedShards,
+    modified_all_bodies: bool,
+}
+
+#[cfg(feature = "parallel")]
+impl TrackedRigidBodyMut<'_> {
+    /// The handle of this rigid-body.
+    pub fn handle(&self) -> RigidBodyHandle {
+        self.handle
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl Deref for TrackedRigidBodyMut<'_> {
+    type Target = RigidBody;
+
+    fn deref(&self) -> &RigidBody {
+        &*self.rb
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl DerefMut for TrackedRigidBodyMut<'_> {
+    fn deref_mut(&mut self) -> &mut RigidBody {
+        // NOTE: the flag is checked first so the shard is only locked once per body.
+        if !self.modified_all_bodies && !self.rb.changes.contains(RigidBodyChanges::MODIFIED) {
+            let mut shard = self.shards.current().lock().unwrap();
+            RigidBodySet::mark_as_modified(self.handle, &mut *self.rb, &mut shard, false);
+        }
+
+        &mut *self.rb
+    }
+}
+
+// The handles of the rigid-bodies modified by `RigidBodySet::par_iter_mut_tracked`, with one
+// buffer per thread of the rayon thread pool, and one for the other threads, so that the
+// threads modifying different bodies don't contend.
+#[cfg(feature = "parallel")]
+#[derive(Default)]
+struct ModifiedShards(Vec<Mutex<Vec<RigidBodyHandle>>>);
+
+#[cfg(feature = "parallel")]
+impl ModifiedShards {
+    fn ensure_shards_exist(&mut self) {
+        let num_shards = rayon::current_num_threads() + 1;
+
+        if self.0.len() < num_shards {
+            self.0.resize_with(num_shards, Default::default);
+        }
+    }
+
+    fn current(&self) -> &Mutex<Vec<RigidBodyHandle>> {
+        let id = rayon::current_thread_index().map(|i| i + 1).unwrap_or(0);
+        &self.0[id % self.0.len()]
+    }
+
+    fn clear(&mut self) {
+        for shard in &mut self.0 {
+            shard.get_mut().unwrap().clear();
+        }
+    }
+
+    // Moves the handles of all the shards to `modified_bodies`. They are sorted so their
+    // order doesn't depend on the scheduling of the threads.
+    fn drain_into(&mut self, modified_bodies: &mut Vec<RigidBodyHandle>) {
+        let start = modified_bodies.len();
+
+        for shard in &mut self.0 {
+            modified_bodies.append(shard.get_mut().unwrap());
+        }
+
+        modified_bodies[start..].sort_unstable_by_key(|handle| handle.into_raw_parts());
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl Clone for ModifiedShards {
+    fn clone(&self) -> Self {
+        ModifiedShards(
+            self.0
+                .iter()
+                .map(|shard| Mutex::new(shard.lock().unwrap().clone()))
+                .collect(),
+        )
+    }
+}
+
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-serialize", serde(from = "DeserializedRigidBodySet"))]
 #[derive(Clone)]
 /// A set of rigid bodies that can be handled by a physics pipeline.
+///
+/// The active sets and islands are not serialized: they are rebuilt from the sleep state
+/// of the bodies upon deserialization.
 pub struct RigidBodySet {
     // FIXME: Could we avoid this?
     // NOTE: the pub(crate) are needed by the broad phase
     // to avoid borrowing issues. It is also needed for
     // parallelism because the `Receiver` breaks the Sync impl.
     pub(crate) bodies: Arena<RigidBody>,
+    #[cfg_attr(feature = "serde-serialize", serde(skip))]
     pub(crate) active_dynamic_set: Vec<RigidBodyHandle>,
+    #[cfg_attr(feature = "serde-serialize", serde(skip))]
     pub(crate) active_kinematic_set: Vec<RigidBodyHandle>,
     // Set of inactive bodies which have been modified.
     // This typically include static bodies which have been modified.
     pub(crate) modified_inactive_set: Vec<RigidBodyHandle>,
+    #[cfg_attr(feature = "serde-serialize", serde(skip))]
     pub(crate) active_islands: Vec<usize>,
     active_set_timestamp: u32,
     pub(crate) modified_bodies: Vec<RigidBodyHandle>,
     pub(crate) modified_all_bodies: bool,
+    #[cfg(feature = "parallel")]
+    #[cfg_attr(feature = "serde-serialize", serde(skip))]
+    modified_shards: ModifiedShards,
+    // The insertions and removals since the last timestep, in the order they happened.
+    #[cfg_attr(feature = "serde-serialize", serde(skip))]
+    pub(crate) arena_changes: Vec<RigidBodyArenaChange>,
     #[cfg_attr(feature = "serde-serialize", serde(skip))]
     can_sleep: Vec<RigidBodyHandle>, // Workspace.
     #[cfg_attr(feature = "serde-serialize", serde(skip))]
     stack: Vec<RigidBodyHandle>, // Workspace.
+    #[cfg_attr(feature = "serde-serialize", serde(skip))]
+    stack_depths: Vec<u32>, // Workspace.
+    // Sleeping bodies which must be woken up at the next timestep because the wake-up
+    // propagation reached its maximum depth before them.
+    pending_wake_ups: Vec<RigidBodyHandle>,
+    // One body of each island whose position correction was deferred by the position solve
+    // budget. These islands are solved first during the next timestep.
+    pub(crate) deferred_position_islands: Vec<RigidBodyHandle>,
+    #[cfg_attr(feature = "serde-serialize", serde(skip))]
+    suspended: Vec<RigidBodyHandle>,
+    #[cfg_attr(feature = "serde-serialize", serde(skip))]
+    batch_order: Vec<usize>, // Workspace.
+    #[cfg_attr(feature = "serde-serialize", serde(skip))]
+    batch_groups: Vec<usize>, // Workspace.
+}
+
+// The serialized part of a `RigidBodySet`.
+#[cfg(feature = "serde-serialize")]
+#[derive(Deserialize)]
+struct DeserializedRigidBodySet {
+    bodies: Arena<RigidBody>,
+    modified_inactive_set: Vec<RigidBodyHandle>,
+    active_set_timestamp: u32,
+    modified_bodies: Vec<RigidBodyHandle>,
+    modified_all_bodies: bool,
+    pending_wake_ups: Vec<RigidBodyHandle>,
+    deferred_position_islands: Vec<RigidBodyHandle>,
+}
+
+#[cfg(feature = "serde-serialize")]
+impl From<DeserializedRigidBodySet> for RigidBodySet {
+    fn from(data: DeserializedRigidBodySet) -> Self {
+        let mut result = RigidBodySet {
+            bodies: data.bodies,
+            modified_inactive_set: data.modified_inactive_set,
+            active_set_timestamp: data.active_set_timestamp,
+            modified_bodies: data.modified_bodies,
+            modified_all_bodies: data.modified_all_bodies,
+            pending_wake_ups: data.pending_wake_ups,
+            deferred_position_islands: data.deferred_position_islands,
+            ..RigidBodySet::new()
+        };
+        result.rebuild_active_sets();
+        result
+    }
 }
 
 impl RigidBodySet {
@@ -96,16 +329,99 @@ impl RigidBodySet {
             active_set_timestamp: 0,
             modified_bodies: Vec::new(),
             modified_all_bodies: false,
+            #[cfg(feature = "parallel")]
+            modified_shards: ModifiedShards::default(),
+            arena_changes: Vec::new(),
             can_sleep: Vec::new(),
             stack: Vec::new(),
+            stack_depths: Vec::new(),
+            pending_wake_ups: Vec::new(),
+            deferred_position_islands: Vec::new(),
+            suspended: Vec::new(),
+            batch_order: Vec::new(),
+            batch_groups: Vec::new(),
         }
     }
 
+    /// Create a new empty set of rigid bodies, with enough room for `capacity` rigid bodies.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut result = Self::new();
+        result.reserve(capacity);
+        result
+    }
+
+    /// Reserves room for inserting at least `additional` more rigid bodies without reallocating.
+    ///
+    /// This doesn't affect the handles given to the next inserted rigid bodies.
+    pub fn reserve(&mut self, additional: usize) {
+        let num_free = self.bodies.capacity() - self.bodies.len();
+        self.bodies.reserve(additional.saturating_sub(num_free));
+        self.active_dynamic_set.reserve(additional);
+        self.modified_bodies.reserve(additional);
+    }
+
+    /// Releases the excess capacity of this set, e.g., after the removal of many rigid bodies.
+    ///
+    /// This doesn't invalidate any rigid-body handle.
+    pub fn shrink_to_fit(&mut self) {
+        self.bodies.shrink_to_fit();
+        self.active_dynamic_set.shrink_to_fit();
+        self.active_kinematic_set.shrink_to_fit();
+        self.modified_inactive_set.shrink_to_fit();
+        self.active_islands.shrink_to_fit();
+        self.modified_bodies.shrink_to_fit();
+        self.arena_changes.shrink_to_fit();
+        self.pending_wake_ups.shrink_to_fit();
+        self.deferred_position_islands.shrink_to_fit();
+        self.suspended.shrink_to_fit();
+
+        // The workspaces are reallocated as needed at the next timestep.
+        self.can_sleep = Vec::new();
+        self.stack = Vec::new();
+        self.stack_depths = Vec::new();
+        self.batch_order = Vec::new();
+        self.batch_groups = Vec::new();
+    }
+
     /// The number of rigid bodies on this set.
     pub fn len(&self) -> usize {
         self.bodies.len()
     }
 
+    /// Statistics about the rigid-bodies of this set.
+    pub fn stats(&self) -> RigidBodySetStats {
+        let handle_size = core::mem::size_of::<RigidBodyHandle>();
+        let num_handles = self.active_dynamic_set.capacity()
+            + self.active_kinematic_set.capacity()
+            + self.modified_inactive_set.capacity()
+            + self.modified_bodies.capacity()
+            + self.can_sleep.capacity()
+            + self.stack.capacity()
+            + self.pending_wake_ups.capacity()
+            + self.deferred_position_islands.capacity();
+
+        RigidBodySetStats {
+            num_bodies: self.bodies.len(),
+            num_active_dynamic: self.active_dynamic_set.len(),
+            num_active_kinematic: self.active_kinematic_set.len(),
+            num_active_islands: self.active_islands.len().saturating_sub(1),
+            capacity: self.bodies.capacity(),
+            bytes: core::mem::size_of::<Self>()
+                + self.bodies.allocated_bytes()
+                + num_handles * handle_size
+                + self.active_islands.capacity() * core::mem::size_of::<usize>(),
+        }
+    }
+
+    /// The sleeping bodies that will be woken up at the next timestep.
+    ///
+    /// These are the bodies the wake-up propagation didn't reach during the last timestep
+    /// because of `IntegrationParameters::max_wake_propagation_depth`. This may contain the
+    /// handles of bodies removed since the last timestep.
+    pub fn pending_wake_ups(&self) -> &[RigidBodyHandle] {
+        &self.pending_wake_ups
+    }
+
     /// `true` if there are no rigid bodies in this set.
     pub fn is_empty(&self) -> bool {
         self.bodies.is_empty()
@@ -125,6 +441,8 @@ impl RigidBodySet {
 
         let handle = RigidBodyHandle(self.bodies.insert(rb));
         self.modified_bodies.push(handle);
+        self.arena_changes
+            .push(RigidBodyArenaChange::Inserted(handle));
 
         let rb = &mut self.bodies[handle.0];
 
@@ -136,6 +454,35 @@ impl RigidBodySet {
         handle
     }
 
+    /// Inserts several rigid bodies into this set and retrieves their handles, in the same order.
+    ///
+    /// This is equivalent to inserting each rigid body with `Self::insert`, and results in the
+    /// same handles, but the memory needed by all the bodies is reserved at once.
+    pub fn insert_batch(
+        &mut self,
+        bodies: impl IntoIterator<Item = RigidBody>,
+    ) -> Vec<RigidBodyHandle> {
+        let mut bodies = bodies.into_iter();
+        let num_bodies = bodies.size_hint().0;
+        let mut handles = Vec::with_capacity(num_bodies);
+        self.modified_bodies.reserve(num_bodies);
+        self.arena_changes.reserve(num_bodies);
+
+        while let Some(rb) = bodies.next() {
+            // NOTE: the arena is only grown once its free slots are used, so that the
+            //       handles are the same as the ones given by `Self::insert`. It grows at
+            //       least geometrically, like with `Self::insert`, if the size hint is too low.
+            if self.bodies.len() == self.bodies.capacity() {
+                let additional = bodies.size_hint().0 + 1;
+                self.bodies.reserve(additional.max(self.bodies.len()));
+            }
+
+            handles.push(self.insert(rb));
+        }
+
+        handles
+    }
+
     /// Removes a rigid-body, and all its attached colliders and joints, from these sets.
     pub fn remove(
         &mut self,
@@ -143,39 +490,467 @@ impl RigidBodySet {
         colliders: &mut ColliderSet,
         joints: &mut JointSet,
     ) -> Option<RigidBody> {
-        let rb = self.bodies.remove(handle.0)?;
+        let rb = self.remove_from_arena(handle)?;
+        // The aggregate proxy of the rigid-body is dropped once all its colliders are removed.
+        colliders.set_aggregate_proxy(handle, false, &[]);
+
+        /*
+         * Remove colliders attached to this rigid-body.
+         */
+        for collider in &rb.colliders {
+            colliders.remove(*collider, self, false, RemoveSettleMode::Immediate);
+        }
+
+        /*
+         * Remove joints attached to this rigid-body.
+         */
+        joints.remove_rigid_body(rb.joint_graph_index, self);
+
+        Some(rb)
+    }
+
+    /// Removes a rigid-body, and all its attached joints, from these sets, but keeps its colliders
+    /// by attaching them to the rigid-body `new_parent` instead.
+    ///
+    /// The colliders keep their current world-space position, and remain in the broad-phase
+    /// and the narrow-phase. `new_parent` is typically a static rigid-body shared by the colliders
+    /// left behind by several removed bodies, so these colliders become static geometry. Their
+    /// pairs are reported as lost, then found again (except with the colliders already attached
+    /// to `new_parent`) during the next timestep.
+    ///
+    /// # Panics
+    /// Panics if the rigid-body `new_parent` doesn't exist, or is the removed rigid-body.
+    pub fn remove_keeping_colliders(
+        &mut self,
+        handle: RigidBodyHandle,
+        colliders: &mut ColliderSet,
+        joints: &mut JointSet,
+        new_parent: RigidBodyHandle,
+    ) -> Option<RigidBody> {
+        assert!(
+            handle != new_parent && self.contains(new_parent),
+            "The new parent rigid body must exist and differ from the removed rigid body."
+        );
+        let rb = self.remove_from_arena(handle)?;
+        // The aggregate proxy of the rigid-body is dropped once all its colliders are removed.
+        colliders.set_aggregate_proxy(handle, false, &[]);
+
+        /*
+         * Attach the colliders of this rigid-body to the new parent.
+         */
+        for collider in &rb.colliders {
+            colliders.reparent(*collider, new_parent, self);
+        }
+
+        /*
+         * Remove joints attached to this rigid-body.
+         */
+        joints.remove_rigid_body(rb.joint_graph_index, self);
+
+        Some(rb)
+    }
+
+    /// Removes a rigid-body, and all its attached colliders and joints, from these sets,
+    /// and returns all of them.
+    ///
+    /// The result can be inserted back with `Self::insert_removed`.
+    pub fn remove_and_return(
+        &mut self,
+        handle: RigidBodyHandle,
+        colliders: &mut ColliderSet,
+        joints: &mut JointSet,
+    ) -> Option<RemovedRigidBody> {
+        let mut rb = self.remove_from_arena(handle)?;
+        // The aggregate proxy of the rigid-body is dropped once all its colliders are removed.
+        colliders.set_aggregate_proxy(handle, false, &[]);
+
+        /*
+         * Remove colliders attached to this rigid-body.
+         */
+        let collider_handles = rb.colliders.clone();
+        let mut removed_colliders = Vec::with_capacity(collider_handles.len());
+
+        for collider_handle in collider_handles {
+            if let Some(collider) =
+                colliders.remove(collider_handle, self, false, RemoveSettleMode::Immediate)
+            {
+                // Remove the contribution of the collider from the mass properties so
+                // it isn't counted twice when the collider is inserted back.
+                rb.remove_collider_internal(collider_handle, &collider);
+                removed_colliders.push(collider);
+            }
+        }
+
+        /*
+         * Remove joints attached to this rigid-body.
+         */
+        let removed_joints = joints
+            .remove_rigid_body(rb.joint_graph_index, self)
+            .into_iter()
+            .map(|joint| {
+                let other = if joint.body1 == handle {
+                    joint.body2
+                } else {
+                    joint.body1
+                };
+                (other, joint)
+            })
+            .collect();
+
+        Some(RemovedRigidBody {
+            handle,
+            body: rb,
+            colliders: removed_colliders,
+            joints: removed_joints,
+        })
+    }
+
+    /// Removes all the rigid-bodies for which `f` returns `false`, and their attached colliders
+    /// and joints, from these sets.
+    ///
+    /// This is equivalent to removing each of these bodies with `Self::remove`, but the active
+    /// sets are only updated once all the bodies are removed, and the remaining active bodies
+    /// keep their relative order in these sets. Like with `Self::iter_mut`, every body
+    /// retained is considered modified.
+    #[cfg(not(feature = "dev-remove-slow-accessors"))]
+    pub fn retain(
+        &mut self,
+        colliders: &mut ColliderSet,
+        joints: &mut JointSet,
+        mut f: impl FnMut(RigidBodyHandle, &mut RigidBody) -> bool,
+    ) {
+        let removed: Vec<_> = self
+            .iter_mut()
+            .filter_map(|(handle, rb)| if f(handle, rb) { None } else { Some(handle) })
+            .collect();
+
+        if removed.is_empty() {
+            return;
+        }
+
+        /*
+         * Remove joints attached to the removed rigid-bodies.
+         */
+        // NOTE: this is done while all the bodies are still in the arena, so the joint graph
+        //       index of a removed body is updated if its node is moved by another removal.
+        for handle in &removed {
+            let joint_graph_index = self.bodies[handle.0].joint_graph_index;
+            let _ = joints.remove_rigid_body(joint_graph_index, self);
+        }
+
+        /*
+         * Remove the rigid-bodies and their colliders.
+         */
+        for handle in &removed {
+            if let Some(rb) = self.bodies.remove(handle.0) {
+                self.arena_changes
+                    .push(RigidBodyArenaChange::Removed(*handle));
+
+                for collider in &rb.colliders {
+                    colliders.remove(*collider, self, false, RemoveSettleMode::Immediate);
+                }
+            }
+        }
+
         /*
          * Update active sets.
          */
+        let bodies = &mut self.bodies;
         let mut active_sets = [&mut self.active_kinematic_set, &mut self.active_dynamic_set];
 
         for active_set in &mut active_sets {
-            if active_set.get(rb.active_set_id) == Some(&handle) {
-                active_set.swap_remove(rb.active_set_id);
+            active_set.retain(|handle| bodies.contains(handle.0));
 
-                if let Some(replacement) = active_set.get(rb.active_set_id) {
-                    self.bodies[replacement.0].active_set_id = rb.active_set_id;
+            for (id, handle) in active_set.iter().enumerate() {
+                bodies[handle.0].active_set_id = id;
+            }
+        }
+    }
+
+    /// Inserts back a rigid-body, and all its colliders and joints, removed by `Self::remove_and_return`.
+    pub fn insert_removed(
+        &mut self,
+        removed: RemovedRigidBody,
+        colliders: &mut ColliderSet,
+        joints: &mut JointSet,
+    ) -> InsertedRigidBody {
+        let old_handle = removed.handle;
+        let handle = self.insert(removed.body);
+
+        let collider_handles = removed
+            .colliders
+            .into_iter()
+            .map(|collider| colliders.insert(collider, handle, self))
+            .collect();
+
+        let joint_handles = removed
+            .joints
+            .into_iter()
+            .map(|(other, joint)| {
+                // Joints attaching the body to itself.
+                let other = if other == old_handle { handle } else { other };
+
+                if !self.contains(other) {
+                    return Err((other, joint));
+                }
+
+                let (body1, body2) = if joint.body1 == old_handle {
+                    (handle, other)
+                } else {
+                    (other, handle)
+                };
+
+                Ok(joints.insert(self, body1, body2, joint.params))
+            })
+            .collect();
+
+        InsertedRigidBody {
+            body: handle,
+            colliders: collider_handles,
+            joints: joint_handles,
+        }
+    }
+
+    /// Moves all the rigid-bodies, colliders, and joints of another world into these sets.
+    ///
+    /// The colliders of `other_colliders` must be attached to the bodies of `other`, and the
+    /// joints of `other_joints` must link bodies of `other`. The moved elements keep their state,
+    /// including the velocities and the sleep state of the bodies, and the accumulated impulses of
+    /// the joints. Their contacts are computed again by the narrow-phase of this world.
+    ///
+    /// Returns the new handle of each moved element, so references to them can be updated.
+    pub fn append(
+        &mut self,
+        mut other: RigidBodySet,
+        colliders: &mut ColliderSet,
+        mut other_colliders: ColliderSet,
+        joints: &mut JointSet,
+        other_joints: JointSet,
+    ) -> HandleMap {
+        let mut map = HandleMap::default();
+        let mut sleeping = Vec::new();
+
+        /*
+         * Move the rigid-bodies.
+         */
+        for (old_handle, mut rb) in other.bodies.drain() {
+            // Remove the contribution of the colliders from the mass properties so
+            // it isn't counted twice when the colliders are inserted.
+            for collider_handle in rb.colliders.clone() {
+                if let Some(collider) = other_colliders.get(collider_handle) {
+                    rb.remove_collider_internal(collider_handle, collider);
                 }
             }
+
+            let is_sleeping = rb.is_sleeping();
+            let handle = self.insert(rb);
+
+            if is_sleeping {
+                // NOTE: the body status didn't change, so the body isn't woken up when
+                //       its insertion is handled at the next timestep.
+                let rb = &mut self.bodies[handle.0];
+                rb.changes.remove(RigidBodyChanges::BODY_STATUS);
+                sleeping.push((handle, rb.activation));
+            }
+
+            let _ = map.bodies.insert(RigidBodyHandle(old_handle), handle);
         }
 
         /*
-         * Remove colliders attached to this rigid-body.
+         * Move the colliders.
          */
-        for collider in &rb.colliders {
-            colliders.remove(*collider, self, false);
+        let mut exclusions = Vec::new();
+
+        for (old_handle, collider) in other_colliders.colliders.drain() {
+            let old_handle = ColliderHandle(old_handle);
+            for excluded in collider.exclusions() {
+                exclusions.push((old_handle, *excluded));
+            }
+
+            let handle = if collider.is_detached() {
+                colliders.insert_detached(collider)
+            } else {
+                let parent = map.bodies[&collider.parent()];
+                colliders.insert(collider, parent, self)
+            };
+
+            let _ = map.colliders.insert(old_handle, handle);
+        }
+
+        // NOTE: attaching the first collider to a body without mass wakes it up,
+        //       so the sleeping bodies are put back to sleep.
+        for (handle, activation) in sleeping {
+            self.bodies[handle.0].activation = activation;
+        }
+
+        for (handle1, handle2) in exclusions {
+            let _ = colliders.add_exclusion(map.colliders[&handle1], map.colliders[&handle2]);
         }
 
         /*
-         * Remove joints attached to this rigid-body.
+         * Move the joints.
          */
-        joints.remove_rigid_body(rb.joint_graph_index, self);
+        for (old_handle, joint) in other_joints.iter() {
+            let body1 = map.bodies[&joint.body1];
+            let body2 = map.bodies[&joint.body2];
+            let handle = joints.insert(self, body1, body2, joint.params);
+
+            if let Some(new_joint) = joints.get_mut(handle) {
+                new_joint.extra_solver_iterations = joint.extra_solver_iterations;
+            }
+
+            let _ = map.joints.insert(old_handle, handle);
+        }
+
+        map
+    }
+
+    fn remove_from_arena(&mut self, handle: RigidBodyHandle) -> Option<RigidBody> {
+        let rb = self.bodies.remove(handle.0)?;
+        self.arena_changes
+            .push(RigidBodyArenaChange::Removed(handle));
+
+        /*
+         * Update active sets.
+         */
+        let mut active_sets = [&mut self.active_kinematic_set, &mut self.active_dynamic_set];
+
+        for active_set in &mut active_sets {
+            if active_set.get(rb.active_set_id) == Some(&handle) {
+                active_set.swap_remove(rb.active_set_id);
+
+                if let Some(replacement) = active_set.get(rb.active_set_id) {
+                    self.bodies[replacement.0].active_set_id = rb.active_set_id;
+                }
+            }
+        }
 
         Some(rb)
     }
 
-    pub(crate) fn num_islands(&self) -> usize {
-        self.active_islands.len() - 1
+    /// Rebuilds the active sets and the active islands from the sleep state of the bodies.
+    ///
+    /// The bodies keep the position they had in the active sets, so that the next timestep
+    /// processes them in the same order as if the active sets were never rebuilt.
+    #[cfg(feature = "serde-serialize")]
+    fn rebuild_active_sets(&mut self) {
+        fn rebuild_active_set(
+            bodies: &mut Arena<RigidBody>,
+            mut candidates: Vec<(usize, RigidBodyHandle)>,
+        ) -> Vec<RigidBodyHandle> {
+            candidates.sort_unstable_by_key(|(id, h)| (*id, h.0));
+
+            let mut active_set = Vec::with_capacity(candidates.len());
+            let mut outdated = Vec::new();
+
+            // The bodies that were part of the active set occupy the ids `0..len`. Other
+            // bodies (like the ones woken up manually since the last timestep) have an
+            // outdated id, and are appended at the end of the set.
+            for (id, handle) in candidates {
+                if id == active_set.len() {
+                    active_set.push(handle);
+                } else {
+                    outdated.push(handle);
+                }
+            }
+
+            for handle in outdated {
+                bodies[handle.0].active_set_id = active_set.len();
+                active_set.push(handle);
+            }
+
+            active_set
+        }
+
+        let awake_dynamic = self
+            .bodies
+            .iter()
+            .filter(|(_, rb)| rb.is_dynamic_and_awake())
+            .map(|(h, rb)| (rb.active_set_id, RigidBodyHandle(h)))
+            .collect();
+        let kinematic = self
+            .bodies
+            .iter()
+            .filter(|(_, rb)| rb.is_kinematic())
+            .map(|(h, rb)| (rb.active_set_id, RigidBodyHandle(h)))
+            .collect();
+
+        self.active_dynamic_set = rebuild_active_set(&mut self.bodies, awake_dynamic);
+        self.active_kinematic_set = rebuild_active_set(&mut self.bodies, kinematic);
+        self.active_islands.clear();
+
+        if self.active_set_timestamp == 0 {
+            // The islands have never been computed.
+            return;
+        }
+
+        // Only the bodies visited by the last island computation belong to an island.
+        let timestamp = self.active_set_timestamp;
+        let bodies = &self.bodies;
+        let island_bodies = self
+            .active_dynamic_set
+            .iter()
+            .map(|h| &bodies[h.0])
+            .filter(|rb| rb.active_set_timestamp == timestamp);
+        let mut num_islands = 1;
+        let mut island_end = 0;
+
+        for rb in island_bodies.clone() {
+            num_islands = num_islands.max(rb.active_island_id + 1);
+            island_end = island_end.max(rb.active_set_id + 1);
+        }
+
+        self.active_islands.resize(num_islands + 1, usize::MAX);
+        self.active_islands[0] = 0;
+        self.active_islands[num_islands] = island_end;
+
+        for rb in island_bodies {
+            let start = &mut self.active_islands[rb.active_island_id];
+            *start = (*start).min(rb.active_set_id.saturating_sub(rb.active_set_offset));
+        }
+
+        // Islands with no body left start where the next island starts.
+        for i in (0..num_islands).rev() {
+            self.active_islands[i] = self.active_islands[i].min(self.active_islands[i + 1]);
+        }
+    }
+
+    /// The number of active islands computed during the last timestep.
+    ///
+    /// An active island is a set of awake dynamic rigid-bodies interacting with each other
+    /// through contacts or joints. Small islands are merged together until they contain at
+    /// least `IntegrationParameters::min_island_size` bodies. The islands are computed by
+    /// `PhysicsPipeline::step`, so this is only valid right after a step, before any body is
+    /// added, removed, or woken up.
+    pub fn num_islands(&self) -> usize {
+        self.active_islands.len().saturating_sub(1)
+    }
+
+    /// Iterates through all the rigid-bodies of the given active island.
+    ///
+    /// The island indices range from 0 to `self.num_islands()` excluded, and are only
+    /// valid right after a `PhysicsPipeline::step`. Sleeping bodies are not part of any
+    /// island. Nothing is yielded if `island_id` doesn't identify an active island.
+    pub fn iter_island(
+        &self,
+        island_id: usize,
+    ) -> impl Iterator<Item = (RigidBodyHandle, &RigidBody)> {
+        let island_range = if island_id < self.num_islands() {
+            self.active_island_range(island_id)
+        } else {
+            0..0
+        };
+        let bodies = &self.bodies;
+        self.active_dynamic_set[island_range]
+            .iter()
+            .filter_map(move |h| Some((*h, bodies.get(h.0)?)))
+    }
+
+    /// The solver priority of a constraint between the two given rigid-bodies.
+    pub(crate) fn pair_solver_priority(&self, pair: BodyPair) -> u8 {
+        self[pair.body1]
+            .solver_priority
+            .max(self[pair.body2].solver_priority)
     }
 
     /// Forces the specified rigid-body to wake up if it is dynamic.
@@ -196,6 +971,180 @@ impl RigidBodySet {
         }
     }
 
+    /// Sets the user-defined data of the specified rigid-body.
+    ///
+    /// Unlike setting `RigidBody::user_data` through `self.get_mut(handle)`, this doesn't mark the
+    /// rigid-body as modified since its user-defined data has no effect on the simulation.
+    /// Returns `false` if the rigid-body isn't part of this set.
+    pub fn set_user_data(&mut self, handle: RigidBodyHandle, user_data: u128) -> bool {
+        if let Some(rb) = self.bodies.get_mut(handle.0) {
+            rb.user_data = user_data;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Applies impulses at the center-of-mass of many rigid-bodies at once.
+    ///
+    /// This is equivalent to calling `RigidBody::apply_impulse(impulse, true)` on each rigid-body
+    /// of the batch, but much faster for large batches: the batch is processed in the order
+    /// of the rigid-bodies in memory, and each rigid-body is marked as modified and woken up
+    /// only once. Handles of non-dynamic rigid-bodies, or of rigid-bodies not part of this
+    /// set, are ignored. A handle may appear several times in the batch.
+    pub fn apply_impulses(&mut self, batch: &[(RigidBodyHandle, Vector<Real>)]) {
+        self.apply_impulse_batch(
+            batch.len(),
+            |i| batch[i].0,
+            |i, rb| rb.apply_impulse(batch[i].1, false),
+        )
+    }
+
+    /// Applies impulses at world-space points of many rigid-bodies at once.
+    ///
+    /// This is equivalent to calling `RigidBody::apply_impulse_at_point(impulse, point, true)`
+    /// on each rigid-body of the batch. See `Self::apply_impulses` for details.
+    pub fn apply_impulses_at_points(
+        &mut self,
+        batch: &[(RigidBodyHandle, Vector<Real>, Point<Real>)],
+    ) {
+        self.apply_impulse_batch(
+            batch.len(),
+            |i| batch[i].0,
+            |i, rb| rb.apply_impulse_at_point(batch[i].1, batch[i].2, false),
+        )
+    }
+
+    /// Applies in parallel impulses at the center-of-mass of many rigid-bodies at once.
+    ///
+    /// This is the parallel version of `apply_impulses`.
+    #[cfg(feature = "parallel")]
+    pub fn par_apply_impulses(&mut self, batch: &[(RigidBodyHandle, Vector<Real>)]) {
+        self.par_apply_impulse_batch(
+            batch.len(),
+            |i| batch[i].0,
+            |i, rb| rb.apply_impulse(batch[i].1, false),
+        )
+    }
+
+    /// Applies in parallel impulses at world-space points of many rigid-bodies at once.
+    ///
+    /// This is the parallel version of `apply_impulses_at_points`.
+    #[cfg(feature = "parallel")]
+    pub fn par_apply_impulses_at_points(
+        &mut self,
+        batch: &[(RigidBodyHandle, Vector<Real>, Point<Real>)],
+    ) {
+        self.par_apply_impulse_batch(
+            batch.len(),
+            |i| batch[i].0,
+            |i, rb| rb.apply_impulse_at_point(batch[i].1, batch[i].2, false),
+        )
+    }
+
+    // Sorts the indices of the elements of a batch by increasing arena index of their
+    // rigid-body, so that the rigid-bodies are accessed in the order they are stored, and so
+    // that the elements targeting the same rigid-body are contiguous.
+    fn sort_batch(order: &mut Vec<usize>, len: usize, handle: impl Fn(usize) -> RigidBodyHandle) {
+        order.clear();
+        order.extend(0..len);
+        order.sort_unstable_by_key(|i| handle(*i).into_raw_parts().0);
+    }
+
+    fn apply_impulse_batch(
+        &mut self,
+        len: usize,
+        handle: impl Fn(usize) -> RigidBodyHandle,
+        apply: impl Fn(usize, &mut RigidBody),
+    ) {
+        Self::sort_batch(&mut self.batch_order, len, &handle);
+        let mut prev_handle = None;
+
+        for i in &self.batch_order {
+            let handle = handle(*i);
+
+            if let Some(rb) = self.bodies.get_mut(handle.0) {
+                if rb.is_dynamic() {
+                    if prev_handle != Some(handle) {
+                        // NOTE: the body must be marked as modified before waking it up, for the
+                        //       wake-up to be registered.
+                        Self::mark_as_modified(
+                            handle,
+                            rb,
+                            &mut self.modified_bodies,
+                            self.modified_all_bodies,
+                        );
+                        rb.wake_up(true);
+                    }
+
+                    apply(*i, rb);
+                }
+            }
+
+            prev_handle = Some(handle);
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    fn par_apply_impulse_batch(
+        &mut self,
+        len: usize,
+        handle: impl Fn(usize) -> RigidBodyHandle + Send + Sync,
+        apply: impl Fn(usize, &mut RigidBody) + Send + Sync,
+    ) {
+        use core::sync::atomic::Ordering;
+
+        Self::sort_batch(&mut self.batch_order, len, &handle);
+
+        // Find the ranges of the batch targeting each rigid-body, and mark them as modified.
+        // Only the impulses are applied in parallel.
+        self.batch_groups.clear();
+        let mut prev_handle = None;
+
+        for (k, i) in self.batch_order.iter().enumerate() {
+            let handle = handle(*i);
+
+            if prev_handle != Some(handle) {
+                self.batch_groups.push(k);
+
+                if let Some(rb) = self.bodies.get_mut(handle.0) {
+                    if rb.is_dynamic() {
+                        Self::mark_as_modified(
+                            handle,
+                            rb,
+                            &mut self.modified_bodies,
+                            self.modified_all_bodies,
+                        );
+                        rb.wake_up(true);
+                    }
+                }
+            }
+
+            prev_handle = Some(handle);
+        }
+
+        self.batch_groups.push(self.batch_order.len());
+
+        let order = &self.batch_order;
+        let bodies = core::sync::atomic::AtomicPtr::new(&mut self.bodies as *mut _);
+        self.batch_groups.par_windows(2).for_each_init(
+            || bodies.load(Ordering::Relaxed),
+            |bodies, group| {
+                // SAFETY: each group targets a different rigid-body.
+                let bodies: &mut Arena<RigidBody> = unsafe { core::mem::transmute(*bodies) };
+                let group = &order[group[0]..group[1]];
+
+                if let Some(rb) = bodies.get_mut(handle(group[0]).0) {
+                    if rb.is_dynamic() {
+                        for i in group {
+                            apply(*i, rb);
+                        }
+                    }
+                }
+            },
+        );
+    }
+
     /// Gets the rigid-body with the given handle without a known generation.
     ///
     /// This is useful for finding the generation number when only the rigid-body position `i` is known.
@@ -263,6 +1212,58 @@ impl RigidBodySet {
         Some(result)
     }
 
+    /// Gets mutable references to the two rigid-bodies with the given handles, e.g., the two
+    /// rigid-bodies attached by a joint.
+    ///
+    /// `None` is returned for the handles that don't match any rigid-body.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `h1` and `h2` are equal.
+    #[cfg(not(feature = "dev-remove-slow-accessors"))]
+    pub fn get2_mut(
+        &mut self,
+        h1: RigidBodyHandle,
+        h2: RigidBodyHandle,
+    ) -> (Option<&mut RigidBody>, Option<&mut RigidBody>) {
+        let [rb1, rb2] = self.get_many_mut([h1, h2]);
+        (rb1, rb2)
+    }
+
+    /// Gets mutable references to the rigid-bodies with the given handles.
+    ///
+    /// `None` is returned for the handles that don't match any rigid-body.
+    ///
+    /// # Panics
+    ///
+    /// Panics if two handles are equal.
+    #[cfg(not(feature = "dev-remove-slow-accessors"))]
+    pub fn get_many_mut<const N: usize>(
+        &mut self,
+        handles: [RigidBodyHandle; N],
+    ) -> [Option<&mut RigidBody>; N] {
+        let mut indices = [RigidBodyHandle::invalid().0; N];
+
+        for (index, handle) in indices.iter_mut().zip(handles.iter()) {
+            *index = handle.0;
+        }
+
+        let mut result = self.bodies.get_many_mut(indices);
+
+        for (rb, handle) in result.iter_mut().zip(handles.iter()) {
+            if let Some(rb) = rb {
+                Self::mark_as_modified(
+                    *handle,
+                    rb,
+                    &mut self.modified_bodies,
+                    self.modified_all_bodies,
+                );
+            }
+        }
+
+        result
+    }
+
     pub(crate) fn get_mut_internal(&mut self, handle: RigidBodyHandle) -> Option<&mut RigidBody> {
         self.bodies.get_mut(handle.0)
     }
@@ -291,6 +1292,88 @@ impl RigidBodySet {
         self.bodies.get2_mut(h1.0, h2.0)
     }
 
+    /// Starts a batch of collider attachments/detachments on the rigid-body `handle`.
+    ///
+    /// Until `self.end_mass_update(handle)` is called, attaching colliders to, or removing colliders
+    /// from, this rigid-body will no longer recompute its world-space mass properties each time.
+    /// They are recomputed only once by `self.end_mass_update(handle)`, which must be called before
+    /// the next timestep.
+    pub fn begin_mass_update(&mut self, handle: RigidBodyHandle) {
+        if let Some(rb) = self.get_mut_internal_with_modification_tracking(handle) {
+            rb.set_mass_update_deferred(true);
+        }
+    }
+
+    /// Ends a batch of collider attachments/detachments started with `self.begin_mass_update(handle)`
+    /// and recomputes the world-space mass properties of the rigid-body `handle`.
+    pub fn end_mass_update(&mut self, handle: RigidBodyHandle) {
+        if let Some(rb) = self.get_mut_internal_with_modification_tracking(handle) {
+            rb.set_mass_update_deferred(false);
+        }
+    }
+
+    /// Calls `f` with the world-space mass properties updates of the rigid-body `handle` deferred.
+    ///
+    /// This is equivalent to calling `f` between `self.begin_mass_update(handle)` and
+    /// `self.end_mass_update(handle)`.
+    pub fn with_deferred_mass_updates<T>(
+        &mut self,
+        handle: RigidBodyHandle,
+        colliders: &mut ColliderSet,
+        f: impl FnOnce(&mut RigidBodySet, &mut ColliderSet) -> T,
+    ) -> T {
+        self.begin_mass_update(handle);
+        let result = f(self, colliders);
+        self.end_mass_update(handle);
+        result
+    }
+
+    /// Computes a hash of the positions and velocities of all the rigid-bodies on this set.
+    ///
+    /// The rigid-bodies are hashed in handle order, using the exact bit representation
+    /// of their positions and velocities (no quantization is performed). This is cheap
+    /// enough to be computed at each timestep in order to find the first step at which
+    /// two simulations expected to be deterministic diverge.
+    pub fn hash_state(&self) -> u64 {
+        // 64-bits FNV-1a hash, so that the result does not depend on the
+        // version of the standard library's hasher.
+        fn hash_bytes(hash: &mut u64, bytes: &[u8]) {
+            for byte in bytes {
+                *hash ^= *byte as u64;
+                *hash = hash.wrapping_mul(0x100000001b3);
+            }
+        }
+
+        fn hash_reals(hash: &mut u64, reals: &[Real]) {
+            for real in reals {
+                hash_bytes(hash, &real.to_bits().to_le_bytes());
+            }
+        }
+
+        let mut hash = 0xcbf29ce484222325;
+
+        for (handle, rb) in self.bodies.iter() {
+            let (id, generation) = handle.into_raw_parts();
+            hash_bytes(&mut hash, &(id as u64).to_le_bytes());
+            hash_bytes(&mut hash, &generation.to_le_bytes());
+            hash_reals(&mut hash, rb.position.translation.vector.as_slice());
+            #[cfg(feature = "dim2")]
+            hash_reals(
+                &mut hash,
+                &[rb.position.rotation.re, rb.position.rotation.im],
+            );
+            #[cfg(feature = "dim3")]
+            hash_reals(&mut hash, rb.position.rotation.coords.as_slice());
+            hash_reals(&mut hash, rb.linvel.as_slice());
+            #[cfg(feature = "dim2")]
+            hash_reals(&mut hash, &[rb.angvel]);
+            #[cfg(feature = "dim3")]
+            hash_reals(&mut hash, rb.angvel.as_slice());
+        }
+
+        hash
+    }
+
     /// Iterates through all the rigid-bodies on this set.
     pub fn iter(&self) -> impl Iterator<Item = (RigidBodyHandle, &RigidBody)> {
         self.bodies.iter().map(|(h, b)| (RigidBodyHandle(h), b))
@@ -304,6 +1387,64 @@ impl RigidBodySet {
         self.bodies.iter_mut().map(|(h, b)| (RigidBodyHandle(h), b))
     }
 
+    /// Iterates in parallel through all the rigid-bodies on this set.
+    #[cfg(feature = "parallel")]
+    pub fn par_iter(&self) -> impl ParallelIterator<Item = (RigidBodyHandle, &RigidBody)> {
+        self.bodies.par_iter().map(|(h, b)| (RigidBodyHandle(h), b))
+    }
+
+    /// Iterates mutably, and in parallel, through all the rigid-bodies on this set.
+    ///
+    /// Like with `Self::iter_mut`, every rigid-body is considered modified. See
+    /// `Self::par_iter_mut_tracked` to only track the bodies actually modified.
+    #[cfg(all(feature = "parallel", not(feature = "dev-remove-slow-accessors")))]
+    pub fn par_iter_mut(
+        &mut self,
+    ) -> impl ParallelIterator<Item = (RigidBodyHandle, &mut RigidBody)> {
+        self.modified_bodies.clear();
+        self.modified_all_bodies = true;
+        self.bodies
+            .par_iter_mut()
+            .map(|(h, b)| (RigidBodyHandle(h), b))
+    }
+
+    /// Iterates mutably, and in parallel, through all the rigid-bodies on this set.
+    ///
+    /// Unlike with `Self::iter_mut`, only the rigid-bodies actually dereferenced mutably through
+    /// the yielded `TrackedRigidBodyMut` are considered modified, so the next timestep only
+    /// handles the changes made to these bodies. Each thread records the bodies it modifies in
+    /// its own buffer, and these buffers are merged at the beginning of the next timestep, in
+    /// an order which doesn't depend on the scheduling of the threads.
+    #[cfg(all(feature = "parallel", not(feature = "dev-remove-slow-accessors")))]
+    pub fn par_iter_mut_tracked(
+        &mut self,
+    ) -> impl ParallelIterator<Item = TrackedRigidBodyMut<'_>> + '_ {
+        self.modified_shards.ensure_shards_exist();
+        let shards = &self.modified_shards;
+        let modified_all_bodies = self.modified_all_bodies;
+
+        self.bodies
+            .par_iter_mut()
+            .map(move |(handle, rb)| TrackedRigidBodyMut {
+                handle: RigidBodyHandle(handle),
+                rb,
+                shards,
+                modified_all_bodies,
+            })
+    }
+
+    /// Moves the rigid-bodies modified through `Self::par_iter_mut_tracked` to `self.modified_bodies`.
+    pub(crate) fn merge_modified_shards(&mut self) {
+        #[cfg(feature = "parallel")]
+        {
+            if self.modified_all_bodies {
+                self.modified_shards.clear();
+            } else {
+                self.modified_shards.drain_into(&mut self.modified_bodies);
+            }
+        }
+    }
+
     /// Iter through all the active kinematic rigid-bodies on this set.
     pub fn iter_active_kinematic<'a>(
         &'a self,
@@ -324,6 +1465,109 @@ impl RigidBodySet {
             .filter_map(move |h| Some((*h, bodies.get(h.0)?)))
     }
 
+    /// Iter mutably through all the active dynamic rigid-bodies on this set.
+    ///
+    /// Unlike `Self::iter_mut`, only the yielded rigid-bodies are considered modified, like
+    /// with `Self::get_mut`. The rigid-bodies are yielded in the order of the indices of their
+    /// handles.
+    #[cfg(not(feature = "dev-remove-slow-accessors"))]
+    pub fn iter_active_dynamic_mut(
+        &mut self,
+    ) -> impl Iterator<Item = (RigidBodyHandle, &mut RigidBody)> {
+        let indices = self.active_dynamic_set.iter().map(|h| h.0).collect();
+        let modified_bodies = &mut self.modified_bodies;
+        let modified_all_bodies = self.modified_all_bodies;
+
+        self.bodies.iter_many_mut(indices).map(move |(index, rb)| {
+            let handle = RigidBodyHandle(index);
+            Self::mark_as_modified(handle, rb, modified_bodies, modified_all_bodies);
+            (handle, rb)
+        })
+    }
+
+    /// Iter through all the active rigid-bodies which moved by more than the given distance or
+    /// angle during the last timestep.
+    ///
+    /// The displacement of each rigid-body is given by `RigidBody::position_delta`. Sleeping
+    /// rigid-bodies are skipped without being tested.
+    pub fn iter_moved<'a>(
+        &'a self,
+        threshold_lin: Real,
+        threshold_ang: Real,
+    ) -> impl Iterator<Item = (RigidBodyHandle, &'a RigidBody)> {
+        self.iter_active_dynamic()
+            .chain(self.iter_active_kinematic())
+            .filter(move |(_, rb)| rb.moved_more_than(threshold_lin, threshold_ang))
+    }
+
+    /// Copies the positions of the rigid-bodies with the given handles into `out`.
+    ///
+    /// The position of `handles[i]` is written to `out[i]`. The elements of `out` matching
+    /// handles of rigid-bodies that are not part of this set are left unchanged.
+    ///
+    /// Panics if `out` is shorter than `handles`.
+    pub fn copy_positions_into(&self, handles: &[RigidBodyHandle], out: &mut [Isometry<Real>]) {
+        assert!(
+            out.len() >= handles.len(),
+            "The output buffer is too small."
+        );
+
+        for (handle, pos) in handles.iter().zip(out.iter_mut()) {
+            if let Some(rb) = self.bodies.get(handle.0) {
+                *pos = rb.position;
+            }
+        }
+    }
+
+    /// Copies in parallel the positions of the rigid-bodies with the given handles into `out`.
+    ///
+    /// This is the parallel version of `copy_positions_into`.
+    #[cfg(feature = "parallel")]
+    pub fn par_copy_positions_into(&self, handles: &[RigidBodyHandle], out: &mut [Isometry<Real>]) {
+        assert!(
+            out.len() >= handles.len(),
+            "The output buffer is too small."
+        );
+
+        handles
+            .par_iter()
+            .zip(out.par_iter_mut())
+            .for_each(|(handle, pos)| {
+                if let Some(rb) = self.bodies.get(handle.0) {
+                    *pos = rb.position;
+                }
+            });
+    }
+
+    /// Replaces the content of `out` by the handles and positions of all the active dynamic and
+    /// kinematic rigid-bodies.
+    ///
+    /// If `alpha` is set, the positions are interpolated as with
+    /// `RigidBody::interpolated_position`. The capacity of `out` is reused so that no allocation
+    /// happens once it is large enough.
+    pub fn copy_active_positions_into(
+        &self,
+        out: &mut Vec<(RigidBodyHandle, Isometry<Real>)>,
+        alpha: Option<Real>,
+    ) {
+        out.clear();
+        out.reserve(self.active_dynamic_set.len() + self.active_kinematic_set.len());
+
+        for handle in self
+            .active_dynamic_set
+            .iter()
+            .chain(self.active_kinematic_set.iter())
+        {
+            if let Some(rb) = self.bodies.get(handle.0) {
+                let pos = match alpha {
+                    Some(alpha) => rb.interpolated_position(alpha),
+                    None => rb.position,
+                };
+                out.push((*handle, pos));
+            }
+        }
+    }
+
     #[cfg(not(feature = "parallel"))]
     pub(crate) fn iter_active_island<'a>(
         &'a self,
@@ -422,16 +1666,16 @@ impl RigidBodySet {
         island_id: usize,
         f: impl Fn(RigidBodyHandle, &mut RigidBody) + Send + Sync,
     ) {
-        use std::sync::atomic::Ordering;
+        use core::sync::atomic::Ordering;
 
         let island_range = self.active_islands[island_id]..self.active_islands[island_id + 1];
-        let bodies = std::sync::atomic::AtomicPtr::new(&mut self.bodies as *mut _);
+        let bodies = core::sync::atomic::AtomicPtr::new(&mut self.bodies as *mut _);
         self.active_dynamic_set[island_range]
             .par_iter()
             .for_each_init(
                 || bodies.load(Ordering::Relaxed),
                 |bodies, handle| {
-                    let bodies: &mut Arena<RigidBody> = unsafe { std::mem::transmute(*bodies) };
+                    let bodies: &mut Arena<RigidBody> = unsafe { core::mem::transmute(*bodies) };
                     if let Some(rb) = bodies.get_mut(handle.0) {
                         f(*handle, rb)
                     }
@@ -443,7 +1687,7 @@ impl RigidBodySet {
     //     &self.active_dynamic_set
     // }
 
-    pub(crate) fn active_island_range(&self, island_id: usize) -> std::ops::Range<usize> {
+    pub(crate) fn active_island_range(&self, island_id: usize) -> core::ops::Range<usize> {
         self.active_islands[island_id]..self.active_islands[island_id + 1]
     }
 
@@ -506,11 +1750,26 @@ impl RigidBodySet {
                 }
             }
 
+            // The locked axes or the additional mass properties changed, the effective
+            // mass properties must be recomputed.
+            if rb
+                .changes
+                .intersects(RigidBodyChanges::LOCKED_AXES | RigidBodyChanges::MASS_PROPERTIES)
+            {
+                rb.update_world_mass_properties();
+            }
+
+            // The colliders switched from their own proxies to an aggregate proxy, or back.
+            if rb.changes.contains(RigidBodyChanges::AGGREGATE_PROXY) {
+                colliders.set_aggregate_proxy(handle, rb.uses_aggregate_proxy(), &rb.colliders);
+                colliders.set_aggregate_position(handle, rb.position);
+            }
+
             // Update the positions of the colliders.
             if rb.changes.contains(RigidBodyChanges::POSITION)
                 || rb.changes.contains(RigidBodyChanges::COLLIDERS)
             {
-                rb.update_colliders_positions(colliders);
+                rb.update_colliders_positions(handle, colliders);
 
                 if rb.is_static() {
                     modified_inactive_set.push(handle);
@@ -553,6 +1812,8 @@ impl RigidBodySet {
     }
 
     pub(crate) fn handle_user_changes(&mut self, colliders: &mut ColliderSet) {
+        self.merge_modified_shards();
+
         if self.modified_all_bodies {
             // Unfortunately, we have to push all the bodies to `modified_bodies`
             // instead of just calling `maintain_one` on each element i
@@ -567,6 +1828,20 @@ impl RigidBodySet {
         }
 
         for handle in self.modified_bodies.drain(..) {
+            debug_assert!(
+                !self
+                    .bodies
+                    .get(handle.0)
+                    .map(|rb| rb.is_mass_update_deferred())
+                    .unwrap_or(false),
+                "Cannot run a timestep before `RigidBodySet::end_mass_update` is called."
+            );
+
+            if let Some(rb) = self.bodies.get_mut(handle.0) {
+                // Let the solver know this body changed since the last island computation.
+                rb.user_changes_timestamp = self.active_set_timestamp + 1;
+            }
+
             Self::maintain_one(
                 &mut self.bodies,
                 colliders,
@@ -581,6 +1856,119 @@ impl RigidBodySet {
             self.modified_bodies.shrink_to_fit(); // save some memory.
             self.modified_all_bodies = false;
         }
+
+        self.arena_changes.clear();
+    }
+
+    /// Has the given rigid-body been modified by the user right before the last island computation?
+    pub(crate) fn is_modified_by_user(&self, rb: &RigidBody) -> bool {
+        rb.user_changes_timestamp == self.active_set_timestamp
+    }
+
+    /// Suspends the simulation of the active dynamic and kinematic bodies that can't interact
+    /// with the given regions during the next timestep.
+    ///
+    /// A dynamic body is kept active if one of its colliders intersects one of the regions, or
+    /// if it is connected to such a body through contacts and joints, so that whole islands are
+    /// kept. A kinematic body is kept active if one of its colliders intersects one of the
+    /// regions, or if it touches a dynamic body kept active.
+    pub(crate) fn suspend_outside_regions(
+        &mut self,
+        regions: &[AABB],
+        colliders: &ColliderSet,
+        narrow_phase: &NarrowPhase,
+        joint_graph: &InteractionGraph<RigidBodyHandle, Joint>,
+    ) {
+        self.stack.clear();
+
+        for handle in self
+            .active_dynamic_set
+            .iter()
+            .chain(&self.active_kinematic_set)
+        {
+            let rb = &self.bodies[handle.0];
+            let in_region = rb.colliders.iter().any(|co| {
+                let aabb = colliders[*co].compute_aabb();
+                regions.iter().any(|region| region.intersects(&aabb))
+            });
+
+            if in_region {
+                self.stack.push(*handle);
+            }
+        }
+
+        // Propagate through contacts and joints, including through sleeping bodies
+        // that may be woken up during the timestep.
+        let mut kept = HashSet::default();
+
+        while let Some(handle) = self.stack.pop() {
+            let rb = &self.bodies[handle.0];
+
+            if rb.is_static() || !kept.insert(handle) {
+                continue;
+            }
+
+            if rb.is_kinematic() {
+                // Kinematic bodies don't propagate the simulation to the bodies they touch.
+                continue;
+            }
+
+            push_contacting_bodies(rb, colliders, narrow_phase, &mut self.stack);
+
+            for inter in joint_graph.interactions_with(rb.joint_graph_index) {
+                let other = crate::utils::select_other((inter.0, inter.1), handle);
+                self.stack.push(other);
+            }
+        }
+
+        self.suspended.clear();
+
+        for handle in self
+            .active_dynamic_set
+            .iter()
+            .chain(&self.active_kinematic_set)
+        {
+            if !kept.contains(handle) {
+                self.bodies[handle.0].set_suspended(true);
+                self.suspended.push(*handle);
+            }
+        }
+
+        let bodies = &mut self.bodies;
+        let mut active_sets = [&mut self.active_kinematic_set, &mut self.active_dynamic_set];
+
+        for active_set in &mut active_sets {
+            active_set.retain(|h| !bodies[h.0].is_suspended());
+
+            for (id, handle) in active_set.iter().enumerate() {
+                bodies[handle.0].active_set_id = id;
+            }
+        }
+    }
+
+    /// Are some bodies suspended by `Self::suspend_outside_regions`?
+    pub(crate) fn contains_suspended_bodies(&self) -> bool {
+        !self.suspended.is_empty()
+    }
+
+    /// Resumes the simulation of the bodies suspended by `Self::suspend_outside_regions`.
+    pub(crate) fn resume_suspended(&mut self) {
+        for handle in self.suspended.drain(..) {
+            if let Some(rb) = self.bodies.get_mut(handle.0) {
+                rb.set_suspended(false);
+
+                let active_set = if rb.is_kinematic() {
+                    &mut self.active_kinematic_set
+                } else {
+                    &mut self.active_dynamic_set
+                };
+
+                if active_set.get(rb.active_set_id) != Some(&handle) {
+                    rb.active_set_id = active_set.len();
+                    active_set.push(handle);
+                }
+            }
+        }
     }
 
     pub(crate) fn update_active_set_with_contacts(
@@ -589,6 +1977,8 @@ impl RigidBodySet {
         narrow_phase: &NarrowPhase,
         joint_graph: &InteractionGraph<RigidBodyHandle, Joint>,
         min_island_size: usize,
+        max_wake_propagation_depth: Option<u32>,
+        contacts_wake_sleeping_bodies: bool,
     ) {
         assert!(
             min_island_size > 0,
@@ -609,7 +1999,7 @@ impl RigidBodySet {
         for h in self.active_dynamic_set.drain(..).rev() {
             let rb = &mut self.bodies[h.0];
             rb.update_energy();
-            if rb.activation.energy <= rb.activation.threshold {
+            if rb.activation.can_sleep() {
                 // Mark them as sleeping for now. This will
                 // be set to false during the graph traversal
                 // if it should not be put to sleep.
@@ -620,33 +2010,6 @@ impl RigidBodySet {
             }
         }
 
-        // Read all the contacts and push objects touching touching this rigid-body.
-        #[inline(always)]
-        fn push_contacting_bodies(
-            rb: &RigidBody,
-            colliders: &ColliderSet,
-            narrow_phase: &NarrowPhase,
-            stack: &mut Vec<RigidBodyHandle>,
-        ) {
-            for collider_handle in &rb.colliders {
-                if let Some(contacts) = narrow_phase.contacts_with(*collider_handle) {
-                    for inter in contacts {
-                        for manifold in &inter.2.manifolds {
-                            if !manifold.data.solver_contacts.is_empty() {
-                                let other = crate::utils::select_other(
-                                    (inter.0, inter.1),
-                                    *collider_handle,
-                                );
-                                let other_body = colliders[other].parent;
-                                stack.push(other_body);
-                                break;
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
         // Now iterate on all active kinematic bodies and push all the bodies
         // touching them to the stack so they can be woken up.
         for h in self.active_kinematic_set.iter() {
@@ -658,9 +2021,23 @@ impl RigidBodySet {
                 continue;
             }
 
-            push_contacting_bodies(rb, colliders, narrow_phase, &mut self.stack);
+            push_contacting_bodies_to_wake(
+                rb,
+                &self.bodies,
+                colliders,
+                narrow_phase,
+                contacts_wake_sleeping_bodies,
+                &mut self.stack,
+            );
         }
 
+        // The bodies the propagation didn't reach at the last timestep are woken up now.
+        self.stack.append(&mut self.pending_wake_ups);
+
+        // All the bodies pushed so far are the seeds of the propagation.
+        self.stack_depths.clear();
+        self.stack_depths.resize(self.stack.len(), 0);
+
         //        println!("Selection: {}", instant::now() - t);
 
         //        let t = instant::now();
@@ -673,11 +2050,27 @@ impl RigidBodySet {
         let mut island_marker = self.stack.len().max(1) - 1;
 
         while let Some(handle) = self.stack.pop() {
-            let rb = &mut self.bodies[handle.0];
-
-            if rb.active_set_timestamp == self.active_set_timestamp || !rb.is_dynamic() {
+            let depth = self.stack_depths.pop().unwrap_or(0);
+            let rb = match self.bodies.get_mut(handle.0) {
+                Some(rb) => rb,
+                // The body was removed while waiting for a pending wake-up.
+                None => continue,
+            };
+
+            if rb.active_set_timestamp == self.active_set_timestamp
+                || !rb.is_dynamic()
+                || rb.is_suspended()
+            {
                 // We already visited this body and its neighbors.
-                // Also, we don't propagate awake state through static bodies.
+                // Also, we don't propagate awake state through static bodies,
+                // nor through bodies suspended by `PhysicsPipeline::step_region`.
+                continue;
+            }
+
+            if max_wake_propagation_depth.map_or(false, |max_depth| depth > max_depth) {
+                // Too far from the seeds, this body will be woken up at the next timestep
+                // unless it is reached through a shorter path in the meantime.
+                self.pending_wake_ups.push(handle);
                 continue;
             }
 
@@ -701,15 +2094,34 @@ impl RigidBodySet {
 
             // Transmit the active state to all the rigid-bodies with colliders
             // in contact or joined with this collider.
-            push_contacting_bodies(rb, colliders, narrow_phase, &mut self.stack);
+            let rb = &self.bodies[handle.0];
+            push_contacting_bodies_to_wake(
+                rb,
+                &self.bodies,
+                colliders,
+                narrow_phase,
+                contacts_wake_sleeping_bodies,
+                &mut self.stack,
+            );
+            self.stack_depths.resize(self.stack.len(), depth + 1);
 
+            // NOTE: the bodies attached by joints are at the same depth so they are
+            //       never woken up separately.
             for inter in joint_graph.interactions_with(rb.joint_graph_index) {
                 let other = crate::utils::select_other((inter.0, inter.1), handle);
                 self.stack.push(other);
+                self.stack_depths.push(depth);
             }
         }
 
         self.active_islands.push(self.active_dynamic_set.len());
+
+        let bodies = &self.bodies;
+        let timestamp = self.active_set_timestamp;
+        self.pending_wake_ups
+            .retain(|h| bodies[h.0].active_set_timestamp != timestamp);
+        self.pending_wake_ups.sort_unstable_by_key(|h| h.0);
+        self.pending_wake_ups.dedup();
         //        println!(
         //            "Extraction: {}, num islands: {}",
         //            instant::now() - t,
@@ -749,3 +2161,935 @@ impl IndexMut<RigidBodyHandle> for RigidBodySet {
         rb
     }
 }
+
+// Read all the contacts and push objects touching touching this rigid-body.
+#[inline(always)]
+fn push_contacting_bodies(
+    rb: &RigidBody,
+    colliders: &ColliderSet,
+    narrow_phase: &NarrowPhase,
+    stack: &mut Vec<RigidBodyHandle>,
+) {
+    for collider_handle in &rb.colliders {
+        if let Some(contacts) = narrow_phase.contacts_with(*collider_handle) {
+            for inter in contacts {
+                for manifold in &inter.2.manifolds {
+                    if manifold.data.links_bodies() {
+                        let other =
+                            crate::utils::select_other((inter.0, inter.1), *collider_handle);
+                        let other_body = colliders[other].parent;
+                        stack.push(other_body);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Read all the contacts and push the objects touching this rigid-body which are woken up
+// by these contacts, according to their `WakePolicy`.
+#[inline(always)]
+fn push_contacting_bodies_to_wake(
+    rb: &RigidBody,
+    bodies: &Arena<RigidBody>,
+    colliders: &ColliderSet,
+    narrow_phase: &NarrowPhase,
+    contacts_wake_sleeping_bodies: bool,
+    stack: &mut Vec<RigidBodyHandle>,
+) {
+    for collider_handle in &rb.colliders {
+        if let Some(contacts) = narrow_phase.contacts_with(*collider_handle) {
+            for inter in contacts {
+                let other = crate::utils::select_other((inter.0, inter.1), *collider_handle);
+                let other_body = colliders[other].parent;
+                let other_is_asleep = bodies
+                    .get(other_body.0)
+                    .map(|other_rb| !other_rb.activation.is_active())
+                    .unwrap_or(false);
+
+                for manifold in &inter.2.manifolds {
+                    if !manifold.data.links_bodies() {
+                        continue;
+                    }
+
+                    let wakes_other = if other_is_asleep {
+                        // Contacts re-enabled by the physics hooks didn't apply any impulse yet,
+                        // but must wake the other body up right away.
+                        let impulse: Real = if manifold.data.reenabled_by_hooks {
+                            Real::MAX
+                        } else {
+                            manifold.points.iter().map(|pt| pt.data.impulse).sum()
+                        };
+                        contacts_wake_sleeping_bodies
+                            && bodies[other_body.0]
+                                .wake_policy
+                                .is_woken_by_contact(impulse)
+                    } else {
+                        true
+                    };
+
+                    if wakes_other {
+                        stack.push(other_body);
+                        break;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dynamics::{
+        CCDSolver, IntegrationParameters, JointSet, RigidBodyBuilder, RigidBodySet,
+        UserConstraintSet,
+    };
+    use crate::geometry::{BroadPhase, ColliderBuilder, ColliderSet, NarrowPhase};
+    use crate::math::Vector;
+    use crate::pipeline::PhysicsPipeline;
+
+    #[test]
+    fn hash_state_detects_perturbation() {
+        let mut bodies1 = RigidBodySet::new();
+        let handles: Vec<_> = (0..10)
+            .map(|_| bodies1.insert(RigidBodyBuilder::new_dynamic().build()))
+            .collect();
+
+        let mut bodies2 = bodies1.clone();
+        assert_eq!(bodies1.hash_state(), bodies2.hash_state());
+
+        let mut linvel = *bodies2[handles[5]].linvel();
+        linvel.x += 1.0e-7;
+        bodies2[handles[5]].set_linvel(linvel, false);
+        assert_ne!(bodies1.hash_state(), bodies2.hash_state());
+    }
+
+    #[test]
+    fn deferred_mass_updates_match_immediate_updates() {
+        use crate::math::Real;
+
+        let mut colliders = ColliderSet::new();
+        let mut bodies = RigidBodySet::new();
+        let h1 = bodies.insert(RigidBodyBuilder::new_dynamic().build());
+        let h2 = bodies.insert(RigidBodyBuilder::new_dynamic().build());
+
+        for i in 0..30 {
+            let co = ColliderBuilder::ball(0.1 + i as Real * 0.01).build();
+            colliders.insert(co, h1, &mut bodies);
+        }
+
+        bodies.with_deferred_mass_updates(h2, &mut colliders, |bodies, colliders| {
+            for i in 0..30 {
+                let co = ColliderBuilder::ball(0.1 + i as Real * 0.01).build();
+                colliders.insert(co, h2, bodies);
+            }
+        });
+
+        assert_eq!(bodies[h1].mass_properties(), bodies[h2].mass_properties());
+        assert_eq!(bodies[h1].effective_inv_mass, bodies[h2].effective_inv_mass);
+        assert_eq!(bodies[h1].world_com, bodies[h2].world_com);
+    }
+
+    #[test]
+    fn batch_insertions_match_single_insertions() {
+        use crate::dynamics::{RigidBody, RigidBodyHandle};
+        use crate::geometry::{Collider, ColliderHandle};
+        use crate::math::{Isometry, Real};
+
+        fn simulate(
+            batch: bool,
+        ) -> (
+            Vec<RigidBodyHandle>,
+            Vec<ColliderHandle>,
+            Vec<Isometry<Real>>,
+        ) {
+            let mut pipeline = PhysicsPipeline::new();
+            let gravity = Vector::y() * -9.81;
+            let integration_parameters = IntegrationParameters::default();
+            let mut broad_phase = BroadPhase::new();
+            let mut narrow_phase = NarrowPhase::new();
+            let mut bodies = RigidBodySet::new();
+            let mut colliders = ColliderSet::new();
+            let mut ccd = CCDSolver::new();
+            let mut joints = JointSet::new();
+            let mut user_constraints = UserConstraintSet::new();
+
+            let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+            #[cfg(feature = "dim2")]
+            let ground_collider = ColliderBuilder::cuboid(20.0, 0.5).build();
+            #[cfg(feature = "dim3")]
+            let ground_collider = ColliderBuilder::cuboid(20.0, 0.5, 20.0).build();
+            colliders.insert(ground_collider, ground, &mut bodies);
+
+            // Leave some free slots in the arenas.
+            for _ in 0..3 {
+                let handle = bodies.insert(RigidBodyBuilder::new_dynamic().build());
+                colliders.insert(ColliderBuilder::ball(0.5).build(), handle, &mut bodies);
+                bodies.remove(handle, &mut colliders, &mut joints);
+            }
+
+            let new_bodies: Vec<RigidBody> = (0..20)
+                .map(|i| {
+                    let x = (i % 5) as Real * 1.5;
+                    let y = 1.0 + (i / 5) as Real * 1.5;
+                    #[cfg(feature = "dim2")]
+                    let rb = RigidBodyBuilder::new_dynamic().translation(x, y);
+                    #[cfg(feature = "dim3")]
+                    let rb = RigidBodyBuilder::new_dynamic().translation(x, y, 0.0);
+                    rb.build()
+                })
+                .collect();
+            let body_handles = if batch {
+                bodies.insert_batch(new_bodies)
+            } else {
+                new_bodies.into_iter().map(|rb| bodies.insert(rb)).collect()
+            };
+
+            // Two colliders per body, the second one offset from the center of mass.
+            let new_colliders: Vec<(Collider, RigidBodyHandle)> = body_handles
+                .iter()
+                .flat_map(|handle| {
+                    #[cfg(feature = "dim2")]
+                    let offset = ColliderBuilder::ball(0.2).translation(0.5, 0.0);
+                    #[cfg(feature = "dim3")]
+                    let offset = ColliderBuilder::ball(0.2).translation(0.5, 0.0, 0.0);
+                    vec![
+                        (ColliderBuilder::ball(0.5).build(), *handle),
+                        (offset.build(), *handle),
+                    ]
+                })
+                .collect();
+            let collider_handles = if batch {
+                colliders.insert_batch(new_colliders, &mut bodies)
+            } else {
+                new_colliders
+                    .into_iter()
+                    .map(|(co, parent)| colliders.insert(co, parent, &mut bodies))
+                    .collect()
+            };
+
+            for _ in 0..60 {
+                pipeline.step(
+                    &gravity,
+                    &integration_parameters,
+                    &mut broad_phase,
+                    &mut narrow_phase,
+                    &mut bodies,
+                    &mut colliders,
+                    &mut joints,
+                    &mut user_constraints,
+                    &mut ccd,
+                    &(),
+                    &(),
+                );
+            }
+
+            let positions = body_handles
+                .iter()
+                .map(|h| *bodies[*h].position())
+                .collect();
+            (body_handles, collider_handles, positions)
+        }
+
+        assert_eq!(simulate(false), simulate(true));
+    }
+
+    #[test]
+    fn get_many_mut_tracks_the_modifications() {
+        use crate::math::Real;
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::zeros();
+        let integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        let mut handles = vec![];
+
+        for i in 0..3 {
+            #[cfg(feature = "dim2")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(i as Real * 2.0, 0.0);
+            #[cfg(feature = "dim3")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(i as Real * 2.0, 0.0, 0.0);
+            let body = bodies.insert(rb.build());
+            let co = ColliderBuilder::ball(0.5).build();
+            let collider = colliders.insert(co, body, &mut bodies);
+            handles.push((body, collider));
+        }
+
+        pipeline.step(
+            &gravity,
+            &integration_parameters,
+            &mut broad_phase,
+            &mut narrow_phase,
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            &mut user_constraints,
+            &mut ccd,
+            &(),
+            &(),
+        );
+        bodies.remove(handles[2].0, &mut colliders, &mut joints);
+
+        let [rb1, rb2, rb3] = bodies.get_many_mut([handles[0].0, handles[1].0, handles[2].0]);
+        let (rb1, rb2) = (rb1.unwrap(), rb2.unwrap());
+        assert!(rb3.is_none());
+        let mut position1 = *rb1.position();
+        position1.translation.vector.x = 10.0;
+        rb1.set_position(position1, true);
+        let mut position2 = *rb2.position();
+        position2.translation.vector.x = 20.0;
+        rb2.set_position(position2, true);
+
+        let (co1, co2) = colliders.get2_mut(handles[0].1, handles[2].1);
+        assert!(co2.is_none());
+        let co1 = co1.unwrap();
+        let mut position_wrt_parent = *co1.position_wrt_parent();
+        position_wrt_parent.translation.vector.y = 1.0;
+        co1.set_position_wrt_parent(position_wrt_parent);
+
+        pipeline.step(
+            &gravity,
+            &integration_parameters,
+            &mut broad_phase,
+            &mut narrow_phase,
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            &mut user_constraints,
+            &mut ccd,
+            &(),
+            &(),
+        );
+
+        // The colliders followed the modified bodies and positions relative to them.
+        let translation1 = colliders[handles[0].1].position().translation.vector;
+        let translation2 = colliders[handles[1].1].position().translation.vector;
+        assert!((translation1.x - 10.0).abs() < 1.0e-5);
+        assert!((translation1.y - 1.0).abs() < 1.0e-5);
+        assert!((translation2.x - 20.0).abs() < 1.0e-5);
+        assert!(translation2.y.abs() < 1.0e-5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn get2_mut_panics_on_equal_handles() {
+        let mut bodies = RigidBodySet::new();
+        let handle = bodies.insert(RigidBodyBuilder::new_dynamic().build());
+        let _ = bodies.get2_mut(handle, handle);
+    }
+
+    #[test]
+    fn retain_removes_the_rejected_bodies_and_colliders() {
+        use crate::dynamics::BallJoint;
+        use crate::math::{Point, Real};
+
+        let mut pipeline = PhysicsPipeline::new();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+        let mut ccd = CCDSolver::new();
+        let gravity = Vector::y() * -9.81;
+        let params = IntegrationParameters::default();
+
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let floor = ColliderBuilder::cuboid(20.0, 0.5).translation(0.0, -0.5);
+        #[cfg(feature = "dim3")]
+        let floor = ColliderBuilder::cuboid(20.0, 0.5, 20.0).translation(0.0, -0.5, 0.0);
+        let floor = colliders.insert(floor.build(), ground, &mut bodies);
+
+        // A crate, with a handle, jointed to the ground and to a chain of debris.
+        #[cfg(feature = "dim2")]
+        let rb = RigidBodyBuilder::new_dynamic().translation(-3.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let rb = RigidBodyBuilder::new_dynamic().translation(-3.0, 0.5, 0.0);
+        let crate_body = bodies.insert(rb.user_data(1).build());
+        #[cfg(feature = "dim2")]
+        let co = ColliderBuilder::cuboid(0.5, 0.5);
+        #[cfg(feature = "dim3")]
+        let co = ColliderBuilder::cuboid(0.5, 0.5, 0.5);
+        let crate_co = colliders.insert(co.build(), crate_body, &mut bodies);
+        let crate_mass = bodies[crate_body].mass();
+        #[cfg(feature = "dim2")]
+        let co = ColliderBuilder::ball(0.25).translation(0.0, 1.0);
+        #[cfg(feature = "dim3")]
+        let co = ColliderBuilder::ball(0.25).translation(0.0, 1.0, 0.0);
+        colliders.insert(co.user_data(1).build(), crate_body, &mut bodies);
+        let anchor1 = Point::from(Vector::y() * -0.5);
+        let joint = BallJoint::new(anchor1, Point::from(Vector::x() * -3.0));
+        let ground_joint = joints.insert(&mut bodies, crate_body, ground, joint);
+
+        let mut previous = crate_body;
+        let mut anchors = (
+            Point::from(Vector::y() * -0.25),
+            Point::from(Vector::x() * -3.0),
+        );
+
+        for i in 0..10 {
+            #[cfg(feature = "dim2")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(i as Real, 0.25);
+            #[cfg(feature = "dim3")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(i as Real, 0.25, 0.0);
+            let debris = bodies.insert(rb.build());
+            colliders.insert(ColliderBuilder::ball(0.25).build(), debris, &mut bodies);
+            let joint = BallJoint::new(anchors.0, anchors.1);
+            joints.insert(&mut bodies, previous, debris, joint);
+            previous = debris;
+            anchors = (Point::origin(), Point::from(Vector::x() * -1.0));
+        }
+
+        for _ in 0..5 {
+            pipeline.step(
+                &gravity,
+                &params,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            );
+        }
+
+        bodies.retain(&mut colliders, &mut joints, |_, rb| {
+            !rb.is_dynamic() || rb.user_data == 1
+        });
+
+        assert_eq!(bodies.len(), 2);
+        assert_eq!(colliders.len(), 3);
+        assert_eq!(joints.len(), 1);
+        assert!(joints.contains(ground_joint));
+        let active: Vec<_> = bodies.iter_active_dynamic().map(|(h, _)| h).collect();
+        assert_eq!(active, vec![crate_body]);
+        assert_eq!(bodies[crate_body].active_set_id, 0);
+
+        pipeline.step(
+            &gravity,
+            &params,
+            &mut broad_phase,
+            &mut narrow_phase,
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            &mut user_constraints,
+            &mut ccd,
+            &(),
+            &(),
+        );
+
+        let contact_pairs: Vec<_> = narrow_phase
+            .contact_pairs()
+            .map(|pair| (pair.pair.collider1, pair.pair.collider2))
+            .collect();
+        assert!(
+            contact_pairs == vec![(floor, crate_co)] || contact_pairs == vec![(crate_co, floor)]
+        );
+
+        colliders.retain(&mut bodies, |_, co| co.user_data != 1);
+
+        assert_eq!(colliders.len(), 2);
+        assert_eq!(bodies[crate_body].colliders(), &[crate_co]);
+        assert!((bodies[crate_body].mass() - crate_mass).abs() < 1.0e-5 * crate_mass);
+
+        pipeline.step(
+            &gravity,
+            &params,
+            &mut broad_phase,
+            &mut narrow_phase,
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            &mut user_constraints,
+            &mut ccd,
+            &(),
+            &(),
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn par_iter_mut_tracked_only_tracks_the_modified_bodies() {
+        use crate::dynamics::RigidBodyHandle;
+        use crate::math::Real;
+        use rayon::prelude::*;
+
+        let mut pipeline = PhysicsPipeline::new();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+        let mut ccd = CCDSolver::new();
+        let gravity = Vector::zeros();
+        let params = IntegrationParameters::default();
+
+        for i in 0..1000 {
+            #[cfg(feature = "dim2")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(i as Real * 2.0, 0.0);
+            #[cfg(feature = "dim3")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(i as Real * 2.0, 0.0, 0.0);
+            let body = bodies.insert(rb.build());
+            colliders.insert(ColliderBuilder::ball(0.5).build(), body, &mut bodies);
+        }
+
+        pipeline.step(
+            &gravity,
+            &params,
+            &mut broad_phase,
+            &mut narrow_phase,
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            &mut user_constraints,
+            &mut ccd,
+            &(),
+            &(),
+        );
+
+        let is_touched = |handle: RigidBodyHandle| handle.into_raw_parts().0 % 3 == 0;
+        let timestamps: Vec<_> = bodies
+            .iter()
+            .map(|(_, rb)| rb.user_changes_timestamp)
+            .collect();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(8)
+            .build()
+            .unwrap();
+
+        // Every body is read, but only a third of them are modified.
+        pool.install(|| {
+            bodies.par_iter_mut_tracked().for_each(|mut rb| {
+                if is_touched(rb.handle()) {
+                    let linvel = *rb.linvel() + Vector::x();
+                    rb.set_linvel(linvel, true);
+                } else {
+                    assert_eq!(*rb.linvel(), Vector::zeros());
+                }
+            })
+        });
+
+        assert!(!bodies.modified_all_bodies);
+        bodies.merge_modified_shards();
+        let touched: Vec<_> = bodies
+            .iter()
+            .map(|(handle, _)| handle)
+            .filter(|handle| is_touched(*handle))
+            .collect();
+        assert_eq!(bodies.modified_bodies, touched);
+
+        bodies.handle_user_changes(&mut colliders);
+
+        for ((handle, rb), timestamp) in bodies.iter().zip(timestamps) {
+            assert_eq!(rb.user_changes_timestamp != timestamp, is_touched(handle));
+            assert_eq!(rb.linvel().x != 0.0, is_touched(handle));
+        }
+    }
+
+    #[test]
+    fn removed_body_colliders_can_be_kept_as_static_geometry() {
+        use crate::dynamics::BallJoint;
+        use crate::math::Point;
+
+        let mut pipeline = PhysicsPipeline::new();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+        let mut ccd = CCDSolver::new();
+        let gravity = Vector::y() * -9.81;
+        let params = IntegrationParameters::default();
+
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let floor = ColliderBuilder::cuboid(10.0, 0.5).translation(0.0, -0.5);
+        #[cfg(feature = "dim3")]
+        let floor = ColliderBuilder::cuboid(10.0, 0.5, 10.0).translation(0.0, -0.5, 0.0);
+        let floor = colliders.insert(floor.build(), ground, &mut bodies);
+
+        // A pillar on the floor, with a ball jointed on top of it.
+        #[cfg(feature = "dim2")]
+        let (pillar, pillar_co) = (
+            RigidBodyBuilder::new_dynamic().translation(0.0, 0.5),
+            ColliderBuilder::cuboid(0.5, 0.5),
+        );
+        #[cfg(feature = "dim3")]
+        let (pillar, pillar_co) = (
+            RigidBodyBuilder::new_dynamic().translation(0.0, 0.5, 0.0),
+            ColliderBuilder::cuboid(0.5, 0.5, 0.5),
+        );
+        let pillar = bodies.insert(pillar.build());
+        let pillar_co = colliders.insert(pillar_co.build(), pillar, &mut bodies);
+
+        #[cfg(feature = "dim2")]
+        let ball = RigidBodyBuilder::new_dynamic().translation(0.0, 1.25);
+        #[cfg(feature = "dim3")]
+        let ball = RigidBodyBuilder::new_dynamic().translation(0.0, 1.25, 0.0);
+        let ball = bodies.insert(ball.build());
+        let ball_co = colliders.insert(ColliderBuilder::ball(0.25).build(), ball, &mut bodies);
+        let joint = BallJoint::new(
+            Point::from(Vector::y() * 0.5),
+            Point::from(Vector::y() * -0.25),
+        );
+        joints.insert(&mut bodies, pillar, ball, joint);
+
+        for _ in 0..10 {
+            pipeline.step(
+                &gravity,
+                &params,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            );
+        }
+
+        let position = *colliders[pillar_co].position();
+        assert!(narrow_phase.contact_pair(floor, pillar_co).is_some());
+        assert!(bodies
+            .remove_keeping_colliders(pillar, &mut colliders, &mut joints, ground)
+            .is_some());
+
+        assert!(!bodies.contains(pillar));
+        assert_eq!(joints.len(), 0);
+        assert_eq!(colliders[pillar_co].parent(), ground);
+        assert_eq!(bodies[ground].colliders(), &[floor, pillar_co]);
+
+        for _ in 0..60 {
+            pipeline.step(
+                &gravity,
+                &params,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            );
+        }
+
+        // The pillar became static geometry, still supporting the ball.
+        assert_eq!(*colliders[pillar_co].position(), position);
+        assert!(narrow_phase.contact_pair(floor, pillar_co).is_none());
+        assert!(narrow_phase.contact_pair(pillar_co, ball_co).is_some());
+        assert!((bodies[ball].position().translation.vector.y - 1.25).abs() < 0.05);
+    }
+
+    #[test]
+    fn reserving_capacity_does_not_change_the_inserted_handles() {
+        use crate::dynamics::RigidBodyHandle;
+
+        let mut joints = JointSet::new();
+        let mut bodies1 = RigidBodySet::new();
+        let mut bodies2 = RigidBodySet::with_capacity(100);
+        let mut colliders1 = ColliderSet::new();
+        let mut colliders2 = ColliderSet::with_capacity(100);
+
+        fn insert(
+            bodies1: &mut RigidBodySet,
+            bodies2: &mut RigidBodySet,
+            colliders1: &mut ColliderSet,
+            colliders2: &mut ColliderSet,
+        ) -> RigidBodyHandle {
+            let h1 = bodies1.insert(RigidBodyBuilder::new_dynamic().build());
+            let h2 = bodies2.insert(RigidBodyBuilder::new_dynamic().build());
+            assert_eq!(h1, h2);
+            let co1 = colliders1.insert(ColliderBuilder::ball(0.5).build(), h1, bodies1);
+            let co2 = colliders2.insert(ColliderBuilder::ball(0.5).build(), h2, bodies2);
+            assert_eq!(co1, co2);
+            h1
+        }
+
+        let mut handles = Vec::new();
+        for _ in 0..50 {
+            handles.push(insert(
+                &mut bodies1,
+                &mut bodies2,
+                &mut colliders1,
+                &mut colliders2,
+            ));
+        }
+
+        // Removing bodies leaves free slots that must still be reused first after a reservation.
+        for handle in handles.iter().step_by(3) {
+            bodies1.remove(*handle, &mut colliders1, &mut joints);
+            bodies2.remove(*handle, &mut colliders2, &mut joints);
+        }
+
+        bodies2.reserve(1000);
+        colliders2.reserve(1000);
+        assert!(bodies2.bodies.capacity() >= bodies2.len() + 1000);
+
+        for _ in 0..200 {
+            insert(&mut bodies1, &mut bodies2, &mut colliders1, &mut colliders2);
+        }
+    }
+
+    #[test]
+    fn shrink_to_fit_releases_the_memory_of_removed_bodies() {
+        use crate::math::Real;
+
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut joints = JointSet::new();
+
+        let mut balls = Vec::new();
+        for i in 0..1000 {
+            #[cfg(feature = "dim2")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(i as Real - 500.0, 1.0);
+            #[cfg(feature = "dim3")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(i as Real - 500.0, 1.0, 0.0);
+            let handle = bodies.insert(rb.build());
+            colliders.insert(ColliderBuilder::ball(0.5).build(), handle, &mut bodies);
+            balls.push(handle);
+        }
+
+        bodies.handle_user_changes(&mut colliders);
+        let bodies_capacity = bodies.stats().capacity;
+        let bodies_bytes = bodies.stats().bytes;
+        let colliders_capacity = colliders.stats().capacity;
+
+        // Remove 90% of the balls.
+        for handle in &balls[100..] {
+            bodies.remove(*handle, &mut colliders, &mut joints);
+        }
+
+        bodies.handle_user_changes(&mut colliders);
+        bodies.shrink_to_fit();
+        colliders.shrink_to_fit();
+
+        assert!(bodies.stats().capacity < bodies_capacity / 5);
+        assert!(bodies.stats().bytes < bodies_bytes / 5);
+        assert!(colliders.stats().capacity < colliders_capacity / 5);
+
+        // The remaining handles are still valid.
+        for (i, handle) in balls[..100].iter().enumerate() {
+            let x = bodies[*handle].position().translation.x;
+            assert!((x - (i as Real - 500.0)).abs() < 1.0e-3);
+        }
+
+        let handle = bodies.insert(RigidBodyBuilder::new_dynamic().build());
+        assert!(!balls.contains(&handle));
+        assert_eq!(bodies.len(), 101);
+    }
+
+    #[test]
+    fn appended_worlds_keep_their_state_and_links() {
+        use crate::dynamics::BallJoint;
+        use crate::math::Point;
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        colliders.insert(ColliderBuilder::ball(0.5).build(), ground, &mut bodies);
+
+        // The chunk to merge: a sleeping body and a moving pendulum.
+        let mut chunk_bodies = RigidBodySet::new();
+        let mut chunk_colliders = ColliderSet::new();
+        let mut chunk_joints = JointSet::new();
+
+        #[cfg(feature = "dim2")]
+        let (sleeper, rb1, rb2) = (
+            RigidBodyBuilder::new_dynamic().translation(10.0, 5.0),
+            RigidBodyBuilder::new_dynamic()
+                .translation(-10.0, 5.0)
+                .linvel(2.0, 1.0),
+            RigidBodyBuilder::new_dynamic().translation(-8.5, 5.0),
+        );
+        #[cfg(feature = "dim3")]
+        let (sleeper, rb1, rb2) = (
+            RigidBodyBuilder::new_dynamic().translation(10.0, 5.0, 0.0),
+            RigidBodyBuilder::new_dynamic()
+                .translation(-10.0, 5.0, 0.0)
+                .linvel(2.0, 1.0, -1.0),
+            RigidBodyBuilder::new_dynamic().translation(-8.5, 5.0, 0.0),
+        );
+        let sleeper = chunk_bodies.insert(sleeper.build());
+        let h1 = chunk_bodies.insert(rb1.build());
+        let h2 = chunk_bodies.insert(rb2.build());
+        let sleeper_collider = chunk_colliders.insert(
+            ColliderBuilder::ball(0.5).build(),
+            sleeper,
+            &mut chunk_bodies,
+        );
+        // NOTE: the first collider of a body without mass wakes it up, so the body is put
+        //       to sleep once its collider is attached.
+        chunk_bodies[sleeper].sleep();
+        let c1 = chunk_colliders.insert(ColliderBuilder::ball(0.5).build(), h1, &mut chunk_bodies);
+        let c2 = chunk_colliders.insert(ColliderBuilder::ball(0.3).build(), h2, &mut chunk_bodies);
+        assert!(chunk_colliders.add_exclusion(c1, c2));
+        let joint = BallJoint::new(Point::origin(), Point::from(Vector::x() * -1.5));
+        let joint = chunk_joints.insert(&mut chunk_bodies, h1, h2, joint);
+
+        let linvel1 = *chunk_bodies[h1].linvel();
+        let mass1 = chunk_bodies[h1].mass();
+
+        let map = bodies.append(
+            chunk_bodies,
+            &mut colliders,
+            chunk_colliders,
+            &mut joints,
+            chunk_joints,
+        );
+
+        assert_eq!(map.bodies.len(), 3);
+        assert_eq!(map.colliders.len(), 3);
+        assert_eq!(map.joints.len(), 1);
+        assert_eq!(bodies.len(), 4);
+        assert_eq!(colliders.len(), 4);
+
+        let (new_sleeper, new_h1, new_h2) =
+            (map.bodies[&sleeper], map.bodies[&h1], map.bodies[&h2]);
+        let new_sleeper_collider = map.colliders[&sleeper_collider];
+        let (new_c1, new_c2) = (map.colliders[&c1], map.colliders[&c2]);
+        assert_eq!(colliders[new_sleeper_collider].parent(), new_sleeper);
+        assert_eq!(colliders[new_c1].parent(), new_h1);
+        assert_eq!(colliders[new_c2].parent(), new_h2);
+        assert!(colliders.is_excluded(new_c1, new_c2));
+        assert!(colliders.is_excluded(new_c2, new_c1));
+
+        let new_joint = joints.get(map.joints[&joint]).unwrap();
+        assert_eq!((new_joint.body1, new_joint.body2), (new_h1, new_h2));
+
+        assert!(bodies[new_sleeper].is_sleeping());
+        assert_eq!(*bodies[new_h1].linvel(), linvel1);
+        assert_eq!(bodies[new_h1].mass(), mass1);
+
+        pipeline.step(
+            &gravity,
+            &integration_parameters,
+            &mut broad_phase,
+            &mut narrow_phase,
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            &mut user_constraints,
+            &mut ccd,
+            &(),
+            &(),
+        );
+
+        // The sleeping body isn't woken up by the merge, and the pendulum keeps swinging.
+        assert!(bodies[new_sleeper].is_sleeping());
+        assert!(bodies[new_h1].linvel().x > 1.0);
+        let dist = (bodies[new_h2].position().translation.vector
+            - bodies[new_h1].position().translation.vector)
+            .norm();
+        assert!((dist - 1.5).abs() < 1.0e-2);
+    }
+
+    #[test]
+    fn only_the_active_dynamic_bodies_are_iterated_mutably() {
+        use crate::math::Real;
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::zeros();
+        let integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        let mut awake = Vec::new();
+        for i in 0..4 {
+            #[cfg(feature = "dim2")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(i as Real * 3.0, 0.0);
+            #[cfg(feature = "dim3")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(i as Real * 3.0, 0.0, 0.0);
+            let handle = bodies.insert(rb.build());
+            colliders.insert(ColliderBuilder::ball(0.5).build(), handle, &mut bodies);
+            awake.push(handle);
+        }
+
+        #[cfg(feature = "dim2")]
+        let rb = RigidBodyBuilder::new_dynamic().translation(0.0, 10.0);
+        #[cfg(feature = "dim3")]
+        let rb = RigidBodyBuilder::new_dynamic().translation(0.0, 10.0, 0.0);
+        let sleeping = bodies.insert(rb.build());
+        colliders.insert(ColliderBuilder::ball(0.5).build(), sleeping, &mut bodies);
+        let fixed = bodies.insert(RigidBodyBuilder::new_static().build());
+
+        let mut step = |bodies: &mut RigidBodySet, colliders: &mut ColliderSet| {
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                bodies,
+                colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            )
+        };
+
+        step(&mut bodies, &mut colliders);
+
+        // NOTE: the bodies inserted since the last timestep are woken up by the next timestep,
+        //       so the body is put to sleep after the first one. It then leaves the active
+        //       set during the second one.
+        bodies.get_mut(sleeping).unwrap().sleep();
+        let sleeping_y = bodies[sleeping].position().translation.vector.y;
+        step(&mut bodies, &mut colliders);
+
+        let mut iterated: Vec<_> = bodies
+            .iter_active_dynamic_mut()
+            .map(|(handle, rb)| {
+                rb.set_linvel(Vector::y(), true);
+                handle
+            })
+            .collect();
+        iterated.sort_unstable_by_key(|h| h.into_raw_parts());
+        assert_eq!(iterated, awake);
+
+        step(&mut bodies, &mut colliders);
+
+        // The velocities set through the iterator are taken into account by the next step.
+        for handle in &awake {
+            assert_eq!(*bodies[*handle].linvel(), Vector::y());
+            assert!(bodies[*handle].position().translation.vector.y > 0.0);
+        }
+
+        assert!(bodies[sleeping].is_sleeping());
+        assert_eq!(bodies[sleeping].position().translation.vector.y, sleeping_y);
+        assert!(bodies.get(fixed).unwrap().is_static());
+    }
+}
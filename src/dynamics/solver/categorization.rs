@@ -1,5 +1,6 @@
 use crate::dynamics::{JointGraphEdge, JointIndex, RigidBodySet};
 use crate::geometry::{ContactManifold, ContactManifoldIndex};
+use alloc::vec::Vec;
 
 pub(crate) fn categorize_contacts(
     // Unused but useful to simplify the parallel code.
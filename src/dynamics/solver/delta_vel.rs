@@ -1,15 +1,22 @@
 use crate::math::{AngVector, Vector};
+use core::ops::AddAssign;
 use na::{Scalar, SimdRealField};
-use std::ops::AddAssign;
 
+/// The velocity change accumulated on a rigid-body by the velocity constraints solver.
 #[derive(Copy, Clone, Debug)]
 //#[repr(align(64))]
-pub(crate) struct DeltaVel<N: Scalar + Copy> {
+pub struct DeltaVel<N: Scalar + Copy> {
+    /// The linear velocity change.
     pub linear: Vector<N>,
+    /// The angular velocity change, multiplied by the square root of the inertia tensor.
+    ///
+    /// The actual angular velocity change is obtained by multiplying this by
+    /// `RigidBody::effective_world_inv_inertia_sqrt`.
     pub angular: AngVector<N>,
 }
 
 impl<N: SimdRealField> DeltaVel<N> {
+    /// A zero velocity change.
     pub fn zero() -> Self {
         Self {
             linear: na::zero(),
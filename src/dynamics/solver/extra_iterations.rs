@@ -0,0 +1,105 @@
+use super::{AnyJointVelocityConstraint, AnyVelocityConstraint, DeltaVel};
+use crate::dynamics::{JointGraphEdge, JointIndex, RigidBodyHandle, RigidBodySet};
+use crate::geometry::ContactManifold;
+use crate::math::Real;
+use alloc::vec::Vec;
+
+/// The dynamic bodies of an island attached to joints with `Joint::extra_solver_iterations` set.
+pub(crate) struct ExtraIterations {
+    bodies: Vec<(RigidBodyHandle, u8)>,
+}
+
+impl ExtraIterations {
+    pub fn new() -> Self {
+        Self { bodies: Vec::new() }
+    }
+
+    pub fn init(
+        &mut self,
+        bodies: &RigidBodySet,
+        joints: &[JointGraphEdge],
+        joint_indices: &[JointIndex],
+    ) {
+        self.bodies.clear();
+
+        for joint_i in joint_indices {
+            let joint = &joints[*joint_i].weight;
+
+            if joint.extra_solver_iterations > 0 {
+                for handle in [joint.body1, joint.body2].iter() {
+                    if bodies[*handle].is_dynamic() {
+                        self.bodies.push((*handle, joint.extra_solver_iterations));
+                    }
+                }
+            }
+        }
+    }
+
+    /// The number of extra iterations of the given body.
+    ///
+    /// This is the largest number of extra iterations of the joints attached to it.
+    fn of_body(&self, handle: RigidBodyHandle) -> u8 {
+        // NOTE: this set is expected to be very small, a linear search is good enough.
+        self.bodies
+            .iter()
+            .filter(|(h, _)| *h == handle)
+            .map(|(_, n)| *n)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The number of extra iterations of the constraints of a contact manifold.
+    pub fn of_manifold(&self, manifold: &ContactManifold) -> u8 {
+        if self.bodies.is_empty() {
+            0
+        } else {
+            let pair = manifold.data.body_pair;
+            self.of_body(pair.body1).max(self.of_body(pair.body2))
+        }
+    }
+}
+
+/// Appends to `extra_constraints` the constraints in `first_constraint..last_constraint` if
+/// they need `num_extra_iterations > 0` extra iterations.
+pub(crate) fn push_extra_constraints(
+    extra_constraints: &mut Vec<(usize, u8)>,
+    first_constraint: usize,
+    last_constraint: usize,
+    num_extra_iterations: u8,
+) {
+    if num_extra_iterations > 0 {
+        extra_constraints
+            .extend((first_constraint..last_constraint).map(|i| (i, num_extra_iterations)));
+    }
+}
+
+/// Solves the constraints needing extra iterations, after the regular velocity iterations.
+///
+/// Each constraint is given with its number of extra iterations.
+pub(crate) fn solve_extra_iterations(
+    mj_lambdas: &mut [DeltaVel<Real>],
+    joint_constraints: &mut [AnyJointVelocityConstraint],
+    extra_joint_constraints: &[(usize, u8)],
+    contact_constraints: &mut [AnyVelocityConstraint],
+    extra_contact_constraints: &[(usize, u8)],
+) {
+    let max_extra_iterations = extra_joint_constraints
+        .iter()
+        .map(|(_, n)| *n)
+        .max()
+        .unwrap_or(0);
+
+    for i in 0..max_extra_iterations {
+        for (constraint_i, n) in extra_joint_constraints {
+            if i < *n {
+                joint_constraints[*constraint_i].solve(mj_lambdas);
+            }
+        }
+
+        for (constraint_i, n) in extra_contact_constraints {
+            if i < *n {
+                contact_constraints[*constraint_i].solve(mj_lambdas);
+            }
+        }
+    }
+}
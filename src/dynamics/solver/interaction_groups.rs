@@ -1,25 +1,65 @@
 use crate::dynamics::{BodyPair, JointGraphEdge, JointIndex, RigidBodySet};
 use crate::geometry::{ContactManifold, ContactManifoldIndex};
+use alloc::vec::Vec;
 #[cfg(feature = "simd-is-enabled")]
 use {
-    crate::math::{SIMD_LAST_INDEX, SIMD_WIDTH},
+    crate::math::{MAX_MANIFOLD_POINTS, SIMD_LAST_INDEX, SIMD_WIDTH},
+    alloc::vec,
     vec_map::VecMap,
 };
 
 pub(crate) trait PairInteraction {
     fn body_pair(&self) -> BodyPair;
+    /// The order of this interaction among the interactions with the same solver priority.
+    fn solver_order(&self) -> u32;
 }
 
 impl<'a> PairInteraction for &'a mut ContactManifold {
     fn body_pair(&self) -> BodyPair {
         self.data.body_pair
     }
+
+    fn solver_order(&self) -> u32 {
+        0
+    }
 }
 
 impl<'a> PairInteraction for JointGraphEdge {
     fn body_pair(&self) -> BodyPair {
         BodyPair::new(self.weight.body1, self.weight.body2)
     }
+
+    fn solver_order(&self) -> u32 {
+        self.weight.chain_depth
+    }
+}
+
+/// Splits `interaction_indices`, sorted by solver priority and solver order, into runs of
+/// interactions with the same solver priority and solver order.
+pub(crate) fn priority_runs<'a, Interaction: PairInteraction>(
+    bodies: &'a RigidBodySet,
+    interactions: &'a [Interaction],
+    interaction_indices: &'a [usize],
+) -> impl Iterator<Item = &'a [usize]> {
+    let priority = move |i: &usize| {
+        let interaction = &interactions[*i];
+        (
+            bodies.pair_solver_priority(interaction.body_pair()),
+            interaction.solver_order(),
+        )
+    };
+    let mut remaining = interaction_indices;
+
+    core::iter::from_fn(move || {
+        let first = priority(remaining.first()?);
+        let len = remaining
+            .iter()
+            .take_while(|i| priority(i) == first)
+            .count();
+        let (run, rest) = remaining.split_at(len);
+        remaining = rest;
+        Some(run)
+    })
 }
 
 #[cfg(feature = "parallel")]
@@ -30,6 +70,12 @@ pub(crate) struct ParallelInteractionGroups {
     interaction_indices: Vec<usize>,
     // Workspace.
     interaction_colors: Vec<usize>,
+    // Workspace.
+    uncolored: Vec<usize>,
+    // Workspace.
+    deferred: Vec<usize>,
+    // Workspace.
+    color_len: Vec<usize>,
     sorted_interactions: Vec<usize>,
     groups: Vec<usize>,
 }
@@ -41,6 +87,9 @@ impl ParallelInteractionGroups {
             bodies_color: Vec::new(),
             interaction_indices: Vec::new(),
             interaction_colors: Vec::new(),
+            uncolored: Vec::new(),
+            deferred: Vec::new(),
+            color_len: Vec::new(),
             sorted_interactions: Vec::new(),
             groups: Vec::new(),
         }
@@ -54,6 +103,17 @@ impl ParallelInteractionGroups {
         self.groups.len() - 1
     }
 
+    /// Splits the interactions into groups such that two interactions of the same group never
+    /// involve the same dynamic rigid-body.
+    ///
+    /// The groups, and the order of the interactions in each group, only depend on the order of
+    /// `interaction_indices`. Because the constraints of a group don't share any body, they can
+    /// be solved concurrently in any order without affecting the result. This is what makes the
+    /// parallel solver independent from the number of threads.
+    ///
+    /// The `interaction_indices` must be sorted by solver priority and solver order. The
+    /// interactions with different priorities or orders never share a group, and the groups
+    /// are sorted by priority and order.
     pub fn group_interactions<Interaction: PairInteraction>(
         &mut self,
         island_id: usize,
@@ -67,59 +127,81 @@ impl ParallelInteractionGroups {
         self.groups.clear();
         self.sorted_interactions.clear();
         self.interaction_colors.clear();
+        self.color_len.clear();
+        self.uncolored.clear();
 
-        let mut color_len = [0; 128];
-        self.bodies_color.resize(num_island_bodies, 0u128);
         self.interaction_indices
             .extend_from_slice(interaction_indices);
         self.interaction_colors.resize(interaction_indices.len(), 0);
-        let bcolors = &mut self.bodies_color;
 
-        for (interaction_id, color) in self
-            .interaction_indices
-            .iter()
-            .zip(self.interaction_colors.iter_mut())
-        {
-            let body_pair = interactions[*interaction_id].body_pair();
-            let rb1 = &bodies[body_pair.body1];
-            let rb2 = &bodies[body_pair.body2];
-
-            match (rb1.is_static(), rb2.is_static()) {
-                (false, false) => {
-                    let color_mask =
-                        bcolors[rb1.active_set_offset] | bcolors[rb2.active_set_offset];
-                    *color = (!color_mask).trailing_zeros() as usize;
-                    color_len[*color] += 1;
-                    bcolors[rb1.active_set_offset] |= 1 << *color;
-                    bcolors[rb2.active_set_offset] |= 1 << *color;
-                }
-                (true, false) => {
-                    let color_mask = bcolors[rb2.active_set_offset];
-                    *color = (!color_mask).trailing_zeros() as usize;
-                    color_len[*color] += 1;
-                    bcolors[rb2.active_set_offset] |= 1 << *color;
+        // Each pass assigns up to 128 colors. The interactions involving a body that already
+        // used all the colors of the current pass are deferred to the next pass.
+        let mut first_color = 0;
+        let mut run_start = 0;
+
+        for run in priority_runs(bodies, interactions, interaction_indices) {
+            // Each priority run gets its own colors so its constraints are solved after
+            // the ones of the previous runs.
+            self.uncolored.clear();
+            self.uncolored.extend(run_start..run_start + run.len());
+            run_start += run.len();
+
+            while !self.uncolored.is_empty() {
+                self.bodies_color.clear();
+                self.bodies_color.resize(num_island_bodies, 0u128);
+                self.color_len.resize(first_color + 128, 0);
+                self.deferred.clear();
+                let bcolors = &mut self.bodies_color;
+
+                for i in &self.uncolored {
+                    let body_pair = interactions[self.interaction_indices[*i]].body_pair();
+                    let rb1 = &bodies[body_pair.body1];
+                    let rb2 = &bodies[body_pair.body2];
+
+                    // Only awake dynamic bodies have their velocity modified by the solver, so
+                    // only them can create conflicts between two interactions.
+                    let awake1 = rb1.is_dynamic_and_awake();
+                    let awake2 = rb2.is_dynamic_and_awake();
+                    let color_mask = match (awake1, awake2) {
+                        (true, true) => {
+                            bcolors[rb1.active_set_offset] | bcolors[rb2.active_set_offset]
+                        }
+                        (false, true) => bcolors[rb2.active_set_offset],
+                        (true, false) => bcolors[rb1.active_set_offset],
+                        (false, false) => unreachable!(),
+                    };
+
+                    if color_mask == u128::MAX {
+                        self.deferred.push(*i);
+                        continue;
+                    }
+
+                    let color = (!color_mask).trailing_zeros() as usize;
+                    self.interaction_colors[*i] = first_color + color;
+                    self.color_len[first_color + color] += 1;
+
+                    if awake1 {
+                        bcolors[rb1.active_set_offset] |= 1 << color;
+                    }
+                    if awake2 {
+                        bcolors[rb2.active_set_offset] |= 1 << color;
+                    }
                 }
-                (false, true) => {
-                    let color_mask = bcolors[rb1.active_set_offset];
-                    *color = (!color_mask).trailing_zeros() as usize;
-                    color_len[*color] += 1;
-                    bcolors[rb1.active_set_offset] |= 1 << *color;
-                }
-                (true, true) => unreachable!(),
+
+                core::mem::swap(&mut self.uncolored, &mut self.deferred);
+                first_color += 128;
             }
         }
 
-        let mut sort_offsets = [0; 128];
+        let mut sort_offsets = vec![0; self.color_len.len()];
         let mut last_offset = 0;
 
-        for i in 0..128 {
-            if color_len[i] == 0 {
-                break;
+        for (color, len) in self.color_len.iter().enumerate() {
+            if *len != 0 {
+                self.groups.push(last_offset);
+                sort_offsets[color] = last_offset;
+                last_offset += len;
             }
-
-            self.groups.push(last_offset);
-            sort_offsets[i] = last_offset;
-            last_offset += color_len[i];
         }
 
         self.sorted_interactions
@@ -222,8 +304,8 @@ impl InteractionGroups {
             let interaction = &interactions[*interaction_i].weight;
             let body1 = &bodies[interaction.body1];
             let body2 = &bodies[interaction.body2];
-            let is_static1 = !body1.is_dynamic();
-            let is_static2 = !body2.is_dynamic();
+            let is_static1 = !body1.is_dynamic_and_awake();
+            let is_static2 = !body2.is_dynamic_and_awake();
 
             if is_static1 && is_static2 {
                 continue;
@@ -238,8 +320,10 @@ impl InteractionGroups {
             let ijoint = interaction.params.type_id();
             let i1 = body1.active_set_offset;
             let i2 = body2.active_set_offset;
-            let conflicts =
-                self.body_masks[i1] | self.body_masks[i2] | joint_type_conflicts[ijoint];
+            // NOTE: the `active_set_offset` of a static body is meaningless.
+            let mask1 = if is_static1 { 0 } else { self.body_masks[i1] };
+            let mask2 = if is_static2 { 0 } else { self.body_masks[i2] };
+            let conflicts = mask1 | mask2 | joint_type_conflicts[ijoint];
             let conflictfree_targets = !(conflicts & occupied_mask); // The & is because we consider empty buckets as free of conflicts.
             let conflictfree_occupied_targets = conflictfree_targets & occupied_mask;
 
@@ -356,29 +440,38 @@ impl InteractionGroups {
         // NOTE: each bit of the occupied mask indicates what bucket already
         // contains at least one constraint.
         let mut occupied_mask = 0u128;
-        let max_interaction_points = interaction_indices
+
+        // NOTE: the SIMD constraints pad the lanes that have less contacts than
+        //       the others. So we only need to group the manifolds resulting in the
+        //       same number of constraints, instead of the same number of contacts.
+        let num_constraints = |manifold: &ContactManifold| {
+            let num_contacts = manifold.data.num_active_contacts();
+            (num_contacts + MAX_MANIFOLD_POINTS - 1) / MAX_MANIFOLD_POINTS
+        };
+        let max_interaction_constraints = interaction_indices
             .iter()
-            .map(|i| interactions[*i].data.num_active_contacts())
+            .map(|i| num_constraints(&*interactions[*i]))
             .max()
             .unwrap_or(1);
 
         // FIXME: find a way to reduce the number of iteration.
         // There must be a way to iterate just once on every interaction indices
-        // instead of MAX_MANIFOLD_POINTS times.
-        for k in 1..=max_interaction_points {
+        // instead of max_interaction_constraints times.
+        for k in 1..=max_interaction_constraints {
             for interaction_i in interaction_indices {
                 let interaction = &interactions[*interaction_i];
 
                 // FIXME: how could we avoid iterating
                 // on each interaction at every iteration on k?
-                if interaction.data.num_active_contacts() != k {
+                if num_constraints(&**interaction) != k {
                     continue;
                 }
 
                 let body1 = &bodies[interaction.data.body_pair.body1];
                 let body2 = &bodies[interaction.data.body_pair.body2];
-                let is_static1 = !body1.is_dynamic();
-                let is_static2 = !body2.is_dynamic();
+                // NOTE: a sleeping body touching an awake body acts as a static body.
+                let is_static1 = !body1.is_dynamic_and_awake();
+                let is_static2 = !body2.is_dynamic_and_awake();
 
                 // FIXME: don't generate interactions between static bodies in the first place.
                 if is_static1 && is_static2 {
@@ -387,7 +480,10 @@ impl InteractionGroups {
 
                 let i1 = body1.active_set_offset;
                 let i2 = body2.active_set_offset;
-                let conflicts = self.body_masks[i1] | self.body_masks[i2];
+                // NOTE: the `active_set_offset` of a static or sleeping body is meaningless.
+                let mask1 = if is_static1 { 0 } else { self.body_masks[i1] };
+                let mask2 = if is_static2 { 0 } else { self.body_masks[i2] };
+                let conflicts = mask1 | mask2;
                 let conflictfree_targets = !(conflicts & occupied_mask); // The & is because we consider empty buckets as free of conflicts.
                 let conflictfree_occupied_targets = conflictfree_targets & occupied_mask;
 
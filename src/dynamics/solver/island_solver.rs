@@ -2,16 +2,117 @@ use super::{PositionSolver, VelocitySolver};
 use crate::counters::Counters;
 use crate::dynamics::solver::{
     AnyJointPositionConstraint, AnyJointVelocityConstraint, AnyPositionConstraint,
-    AnyVelocityConstraint, SolverConstraints,
+    AnyVelocityConstraint, DeltaVel, ExtraIterations, SolverConstraints, SolverStats,
+};
+use crate::dynamics::{
+    BodyPair, IntegrationParameters, JointGraphEdge, JointIndex, PositionOffsetsMap, RigidBody,
+    RigidBodyHandle, RigidBodySet, UserPositionConstraint, UserVelocityConstraint,
 };
-use crate::dynamics::{IntegrationParameters, JointGraphEdge, JointIndex, RigidBodySet};
 use crate::geometry::{ContactManifold, ContactManifoldIndex};
+use crate::math::Real;
+use crate::utils::WDot;
+use alloc::vec::Vec;
+
+/// The bodies and interactions of an island at the last step, used to detect
+/// if the constraints of the previous step can be reused.
+struct QuasiStaticCache {
+    bodies: Vec<RigidBodyHandle>,
+    manifolds: Vec<(ContactManifoldIndex, BodyPair, usize)>,
+    num_quasi_static_steps: usize,
+}
+
+impl QuasiStaticCache {
+    fn new() -> Self {
+        Self {
+            bodies: Vec::new(),
+            manifolds: Vec::new(),
+            num_quasi_static_steps: 0,
+        }
+    }
+
+    fn is_quasi_static(rb: &RigidBody, bodies: &RigidBodySet, threshold: Real) -> bool {
+        !bodies.is_modified_by_user(rb)
+            && rb.linvel.norm_squared() + rb.angvel.gdot(rb.angvel) <= threshold * threshold
+    }
+
+    /// Updates the cache with the current state of the island, and returns `true` if the
+    /// constraints of the previous step can be reused.
+    fn update(
+        &mut self,
+        island_id: usize,
+        params: &IntegrationParameters,
+        bodies: &RigidBodySet,
+        manifolds: &[&mut ContactManifold],
+        manifold_indices: &[ContactManifoldIndex],
+        joint_indices: &[JointIndex],
+    ) -> bool {
+        let threshold = params.quasi_static_velocity_threshold;
+
+        // NOTE: joints are never reused because their motors may be
+        //       modified by the user without touching the bodies.
+        if threshold <= 0.0 || !joint_indices.is_empty() {
+            self.clear();
+            return false;
+        }
+
+        let island = bodies.active_island(island_id);
+        let manifold_keys = manifold_indices.iter().map(|id| {
+            let manifold = &manifolds[*id];
+            (
+                *id,
+                manifold.data.body_pair,
+                manifold.data.solver_contacts.len(),
+            )
+        });
+
+        let same_interactions =
+            self.bodies == island && self.manifolds.iter().copied().eq(manifold_keys.clone());
+
+        // NOTE: the bodies attached to the manifolds include the non-dynamic
+        //       bodies touching the island, e.g., moving kinematic platforms.
+        let quasi_static = same_interactions
+            && island
+                .iter()
+                .all(|h| Self::is_quasi_static(&bodies[*h], bodies, threshold))
+            && self.manifolds.iter().all(|(_, pair, _)| {
+                Self::is_quasi_static(&bodies[pair.body1], bodies, threshold)
+                    && Self::is_quasi_static(&bodies[pair.body2], bodies, threshold)
+            });
+
+        if quasi_static {
+            self.num_quasi_static_steps += 1;
+        } else {
+            self.num_quasi_static_steps = 0;
+
+            if !same_interactions {
+                self.bodies.clear();
+                self.bodies.extend_from_slice(island);
+                self.manifolds.clear();
+                self.manifolds.extend(manifold_keys);
+            }
+        }
+
+        quasi_static && self.num_quasi_static_steps >= params.quasi_static_min_steps
+    }
+
+    fn clear(&mut self) {
+        self.bodies.clear();
+        self.manifolds.clear();
+        self.num_quasi_static_steps = 0;
+    }
+}
 
 pub struct IslandSolver {
     contact_constraints: SolverConstraints<AnyVelocityConstraint, AnyPositionConstraint>,
     joint_constraints: SolverConstraints<AnyJointVelocityConstraint, AnyJointPositionConstraint>,
     velocity_solver: VelocitySolver,
     position_solver: PositionSolver,
+    quasi_static_cache: QuasiStaticCache,
+    extra_iterations: ExtraIterations,
+    user_constraint_offsets: PositionOffsetsMap,
+    // The deepest penetration of the contacts of the island, only computed
+    // if the position solver has a budget.
+    max_penetration: Real,
 }
 
 impl IslandSolver {
@@ -21,23 +122,74 @@ impl IslandSolver {
             joint_constraints: SolverConstraints::new(),
             velocity_solver: VelocitySolver::new(),
             position_solver: PositionSolver::new(),
+            quasi_static_cache: QuasiStaticCache::new(),
+            extra_iterations: ExtraIterations::new(),
+            user_constraint_offsets: PositionOffsetsMap::new(),
+            max_penetration: 0.0,
         }
     }
 
+    /// Statistics about the constraints of the last island solved by this solver.
+    pub fn stats(&self, detailed: bool) -> SolverStats {
+        let mut result = SolverStats {
+            num_contact_constraints: self.contact_constraints.velocity_constraints.len(),
+            num_joint_constraints: self.joint_constraints.velocity_constraints.len(),
+            ..SolverStats::default()
+        };
+
+        if detailed {
+            result.bytes = core::mem::size_of::<Self>()
+                + self.contact_constraints.allocated_bytes()
+                + self.joint_constraints.allocated_bytes()
+                + self.velocity_solver.mj_lambdas.capacity()
+                    * core::mem::size_of::<DeltaVel<Real>>()
+                + self.position_solver.allocated_bytes();
+        }
+
+        result
+    }
+
+    /// The deepest penetration of the contacts of the last island solved by this solver.
+    ///
+    /// This is only computed if `IntegrationParameters::position_solve_budget` is set.
+    pub fn max_penetration(&self) -> Real {
+        self.max_penetration
+    }
+
+    /// The number of position constraint solves needed to solve the position constraints
+    /// of the last island initialized by this solver.
+    pub fn position_solve_cost(
+        &self,
+        params: &IntegrationParameters,
+        num_user_constraints: usize,
+    ) -> usize {
+        (self.contact_constraints.position_constraints.len()
+            + self.joint_constraints.position_constraints.len()
+            + num_user_constraints)
+            * params.max_position_iterations
+    }
+
     pub fn solve_position_constraints(
         &mut self,
         island_id: usize,
         counters: &mut Counters,
         params: &IntegrationParameters,
         bodies: &mut RigidBodySet,
+        user_constraints: &mut [&mut dyn UserPositionConstraint],
     ) {
         counters.solver.position_resolution_time.resume();
+        if !user_constraints.is_empty() {
+            self.user_constraint_offsets.update(bodies, island_id);
+        }
+
         self.position_solver.solve(
             island_id,
             params,
             bodies,
             &self.contact_constraints.position_constraints,
             &self.joint_constraints.position_constraints,
+            user_constraints,
+            &self.user_constraint_offsets,
         );
         counters.solver.position_resolution_time.pause();
     }
@@ -52,26 +204,74 @@ impl IslandSolver {
         manifold_indices: &[ContactManifoldIndex],
         joints: &mut [JointGraphEdge],
         joint_indices: &[JointIndex],
+        user_constraints: &mut [&mut dyn UserVelocityConstraint],
     ) {
-        let has_constraints = manifold_indices.len() != 0 || joint_indices.len() != 0;
+        let has_constraints =
+            manifold_indices.len() != 0 || joint_indices.len() != 0 || !user_constraints.is_empty();
+
+        self.max_penetration = 0.0;
+        if params.position_solve_budget.is_some() {
+            for manifold_id in manifold_indices {
+                for contact in &manifolds[*manifold_id].data.solver_contacts {
+                    self.max_penetration = self.max_penetration.max(-contact.dist);
+                }
+            }
+        }
 
         if has_constraints {
-            counters.solver.velocity_assembly_time.resume();
-            self.contact_constraints
-                .init(island_id, params, bodies, manifolds, manifold_indices);
-            self.joint_constraints
-                .init(island_id, params, bodies, joints, joint_indices);
-            counters.solver.velocity_assembly_time.pause();
+            let reuse_constraints = self.quasi_static_cache.update(
+                island_id,
+                params,
+                bodies,
+                manifolds,
+                manifold_indices,
+                joint_indices,
+            );
+            let solver_params;
+
+            if reuse_constraints {
+                // The island didn't move: the constraints from the previous step
+                // are still good enough and only need a few iterations.
+                solver_params = IntegrationParameters {
+                    max_velocity_iterations: params
+                        .max_velocity_iterations
+                        .min(params.quasi_static_velocity_iterations),
+                    ..*params
+                };
+            } else {
+                solver_params = *params;
+                counters.solver.velocity_assembly_time.resume();
+                self.extra_iterations.init(bodies, joints, joint_indices);
+                self.contact_constraints.init(
+                    island_id,
+                    params,
+                    bodies,
+                    manifolds,
+                    manifold_indices,
+                    &self.extra_iterations,
+                );
+                self.joint_constraints
+                    .init(island_id, params, bodies, joints, joint_indices);
+                counters.solver.velocity_assembly_time.pause();
+            }
 
             counters.solver.velocity_resolution_time.resume();
+            if !user_constraints.is_empty() {
+                self.user_constraint_offsets.update(bodies, island_id);
+            }
+
             self.velocity_solver.solve(
                 island_id,
-                params,
+                &solver_params,
                 bodies,
                 manifolds,
                 joints,
                 &mut self.contact_constraints.velocity_constraints,
+                &self.contact_constraints.extra_constraints,
                 &mut self.joint_constraints.velocity_constraints,
+                &self.joint_constraints.extra_constraints,
+                user_constraints,
+                &self.user_constraint_offsets,
             );
             counters.solver.velocity_resolution_time.pause();
 
@@ -82,6 +282,7 @@ impl IslandSolver {
             });
             counters.solver.velocity_update_time.pause();
         } else {
+            self.quasi_static_cache.clear();
             self.contact_constraints.clear();
             self.joint_constraints.clear();
             counters.solver.velocity_update_time.resume();
@@ -1,8 +1,10 @@
 use crate::dynamics::{BallJoint, IntegrationParameters, RigidBody};
 #[cfg(feature = "dim2")]
 use crate::math::SdpMatrix;
-use crate::math::{AngularInertia, Isometry, Point, Real, Rotation, SimdReal, SIMD_WIDTH};
-use crate::utils::{WAngularInertia, WCross, WCrossMatrix};
+use crate::math::{AngularInertia, Isometry, Point, Real, Rotation, SimdReal, Vector, SIMD_WIDTH};
+#[cfg(feature = "dim3")]
+use crate::utils::WAddDiagonal;
+use crate::utils::{WAngularInertia, WCross, WCrossMatrix, WReducedInverse};
 use simba::simd::SimdValue;
 
 #[derive(Debug)]
@@ -13,8 +15,8 @@ pub(crate) struct WBallPositionConstraint {
     local_com1: Point<SimdReal>,
     local_com2: Point<SimdReal>,
 
-    im1: SimdReal,
-    im2: SimdReal,
+    im1: Vector<SimdReal>,
+    im2: Vector<SimdReal>,
 
     ii1: AngularInertia<SimdReal>,
     ii2: AngularInertia<SimdReal>,
@@ -31,8 +33,8 @@ impl WBallPositionConstraint {
     ) -> Self {
         let local_com1 = Point::from(array![|ii| rbs1[ii].mass_properties.local_com; SIMD_WIDTH]);
         let local_com2 = Point::from(array![|ii| rbs2[ii].mass_properties.local_com; SIMD_WIDTH]);
-        let im1 = SimdReal::from(array![|ii| rbs1[ii].effective_inv_mass; SIMD_WIDTH]);
-        let im2 = SimdReal::from(array![|ii| rbs2[ii].effective_inv_mass; SIMD_WIDTH]);
+        let im1 = Vector::from(array![|ii| rbs1[ii].effective_inv_mass; SIMD_WIDTH]);
+        let im2 = Vector::from(array![|ii| rbs2[ii].effective_inv_mass; SIMD_WIDTH]);
         let ii1 = AngularInertia::<SimdReal>::from(
             array![|ii| rbs1[ii].effective_world_inv_inertia_sqrt; SIMD_WIDTH],
         )
@@ -81,26 +83,30 @@ impl WBallPositionConstraint {
         // NOTE: the -cmat1 is just a simpler way of doing cmat1.transpose()
         // because it is anti-symmetric.
         #[cfg(feature = "dim3")]
-        let lhs = self.ii1.quadform(&cmat1).add_diagonal(self.im1)
-            + self.ii2.quadform(&cmat2).add_diagonal(self.im2);
+        let lhs = self.ii1.quadform(&cmat1).add_diagonal_vector(&self.im1)
+            + self.ii2.quadform(&cmat2).add_diagonal_vector(&self.im2);
 
         // In 2D we just unroll the computation because
         // it's just easier that way.
         #[cfg(feature = "dim2")]
         let lhs = {
-            let m11 =
-                self.im1 + self.im2 + cmat1.x * cmat1.x * self.ii1 + cmat2.x * cmat2.x * self.ii2;
+            let m11 = self.im1.x
+                + self.im2.x
+                + cmat1.x * cmat1.x * self.ii1
+                + cmat2.x * cmat2.x * self.ii2;
             let m12 = cmat1.x * cmat1.y * self.ii1 + cmat2.x * cmat2.y * self.ii2;
-            let m22 =
-                self.im1 + self.im2 + cmat1.y * cmat1.y * self.ii1 + cmat2.y * cmat2.y * self.ii2;
+            let m22 = self.im1.y
+                + self.im2.y
+                + cmat1.y * cmat1.y * self.ii1
+                + cmat2.y * cmat2.y * self.ii2;
             SdpMatrix::new(m11, m12, m22)
         };
 
-        let inv_lhs = lhs.inverse_unchecked();
+        let inv_lhs = lhs.reduced_inverse();
         let impulse = inv_lhs * -(err * SimdReal::splat(params.joint_erp));
 
-        position1.translation.vector += impulse * self.im1;
-        position2.translation.vector -= impulse * self.im2;
+        position1.translation.vector += self.im1.component_mul(&impulse);
+        position2.translation.vector -= self.im2.component_mul(&impulse);
 
         let angle1 = self.ii1.transform_vector(centered_anchor1.gcross(impulse));
         let angle2 = self.ii2.transform_vector(centered_anchor2.gcross(-impulse));
@@ -121,7 +127,7 @@ impl WBallPositionConstraint {
 pub(crate) struct WBallPositionGroundConstraint {
     position2: [usize; SIMD_WIDTH],
     anchor1: Point<SimdReal>,
-    im2: SimdReal,
+    im2: Vector<SimdReal>,
     ii2: AngularInertia<SimdReal>,
     local_anchor2: Point<SimdReal>,
     local_com2: Point<SimdReal>,
@@ -141,7 +147,7 @@ impl WBallPositionGroundConstraint {
             } else {
                 cparams[ii].local_anchor1
             }; SIMD_WIDTH]);
-        let im2 = SimdReal::from(array![|ii| rbs2[ii].effective_inv_mass; SIMD_WIDTH]);
+        let im2 = Vector::from(array![|ii| rbs2[ii].effective_inv_mass; SIMD_WIDTH]);
         let ii2 = AngularInertia::<SimdReal>::from(
             array![|ii| rbs2[ii].effective_world_inv_inertia_sqrt; SIMD_WIDTH],
         )
@@ -175,19 +181,19 @@ impl WBallPositionGroundConstraint {
         let cmat2 = centered_anchor2.gcross_matrix();
 
         #[cfg(feature = "dim3")]
-        let lhs = self.ii2.quadform(&cmat2).add_diagonal(self.im2);
+        let lhs = self.ii2.quadform(&cmat2).add_diagonal_vector(&self.im2);
 
         #[cfg(feature = "dim2")]
         let lhs = {
-            let m11 = self.im2 + cmat2.x * cmat2.x * self.ii2;
+            let m11 = self.im2.x + cmat2.x * cmat2.x * self.ii2;
             let m12 = cmat2.x * cmat2.y * self.ii2;
-            let m22 = self.im2 + cmat2.y * cmat2.y * self.ii2;
+            let m22 = self.im2.y + cmat2.y * cmat2.y * self.ii2;
             SdpMatrix::new(m11, m12, m22)
         };
 
-        let inv_lhs = lhs.inverse_unchecked();
+        let inv_lhs = lhs.reduced_inverse();
         let impulse = inv_lhs * -(err * SimdReal::splat(params.joint_erp));
-        position2.translation.vector -= impulse * self.im2;
+        position2.translation.vector -= self.im2.component_mul(&impulse);
 
         let angle2 = self.ii2.transform_vector(centered_anchor2.gcross(-impulse));
         position2.rotation = Rotation::new(angle2) * position2.rotation;
@@ -3,7 +3,9 @@ use crate::dynamics::{
     BallJoint, IntegrationParameters, JointGraphEdge, JointIndex, JointParams, RigidBody,
 };
 use crate::math::{AngVector, AngularInertia, Real, SdpMatrix, Vector};
-use crate::utils::{WAngularInertia, WCross, WCrossMatrix};
+#[cfg(feature = "dim3")]
+use crate::utils::WAddDiagonal;
+use crate::utils::{WAngularInertia, WCross, WCrossMatrix, WReducedInverse};
 
 #[derive(Debug)]
 pub(crate) struct BallVelocityConstraint {
@@ -25,8 +27,8 @@ pub(crate) struct BallVelocityConstraint {
     motor_inv_lhs: Option<AngularInertia<Real>>,
     motor_max_impulse: Real,
 
-    im1: Real,
-    im2: Real,
+    im1: Vector<Real>,
+    im2: Vector<Real>,
 
     ii1_sqrt: AngularInertia<Real>,
     ii2_sqrt: AngularInertia<Real>,
@@ -63,12 +65,12 @@ impl BallVelocityConstraint {
                 .effective_world_inv_inertia_sqrt
                 .squared()
                 .quadform(&cmat2)
-                .add_diagonal(im2)
+                .add_diagonal_vector(&im2)
                 + rb1
                     .effective_world_inv_inertia_sqrt
                     .squared()
                     .quadform(&cmat1)
-                    .add_diagonal(im1);
+                    .add_diagonal_vector(&im1);
         }
 
         // In 2D we just unroll the computation because
@@ -77,13 +79,13 @@ impl BallVelocityConstraint {
         {
             let ii1 = rb1.effective_world_inv_inertia_sqrt.squared();
             let ii2 = rb2.effective_world_inv_inertia_sqrt.squared();
-            let m11 = im1 + im2 + cmat1.x * cmat1.x * ii1 + cmat2.x * cmat2.x * ii2;
+            let m11 = im1.x + im2.x + cmat1.x * cmat1.x * ii1 + cmat2.x * cmat2.x * ii2;
             let m12 = cmat1.x * cmat1.y * ii1 + cmat2.x * cmat2.y * ii2;
-            let m22 = im1 + im2 + cmat1.y * cmat1.y * ii1 + cmat2.y * cmat2.y * ii2;
+            let m22 = im1.y + im2.y + cmat1.y * cmat1.y * ii1 + cmat2.y * cmat2.y * ii2;
             lhs = SdpMatrix::new(m11, m12, m22)
         }
 
-        let inv_lhs = lhs.inverse_unchecked();
+        let inv_lhs = lhs.reduced_inverse();
 
         /*
          * Motor part.
@@ -134,7 +136,7 @@ impl BallVelocityConstraint {
                 motor_inv_lhs = if keep_lhs {
                     let ii1 = rb1.effective_world_inv_inertia_sqrt.squared();
                     let ii2 = rb2.effective_world_inv_inertia_sqrt.squared();
-                    Some((ii1 + ii2).inverse_unchecked() * gamma)
+                    Some((ii1 + ii2).reduced_inverse() * gamma)
                 } else {
                     Some(SdpMatrix::diagonal(gamma))
                 };
@@ -173,11 +175,11 @@ impl BallVelocityConstraint {
         let mut mj_lambda1 = mj_lambdas[self.mj_lambda1 as usize];
         let mut mj_lambda2 = mj_lambdas[self.mj_lambda2 as usize];
 
-        mj_lambda1.linear += self.im1 * self.impulse;
+        mj_lambda1.linear += self.im1.component_mul(&self.impulse);
         mj_lambda1.angular += self
             .ii1_sqrt
             .transform_vector(self.r1.gcross(self.impulse) + self.motor_impulse);
-        mj_lambda2.linear -= self.im2 * self.impulse;
+        mj_lambda2.linear -= self.im2.component_mul(&self.impulse);
         mj_lambda2.angular -= self
             .ii2_sqrt
             .transform_vector(self.r2.gcross(self.impulse) + self.motor_impulse);
@@ -196,10 +198,10 @@ impl BallVelocityConstraint {
         let impulse = self.inv_lhs * dvel;
         self.impulse += impulse;
 
-        mj_lambda1.linear += self.im1 * impulse;
+        mj_lambda1.linear += self.im1.component_mul(&impulse);
         mj_lambda1.angular += self.ii1_sqrt.transform_vector(self.r1.gcross(impulse));
 
-        mj_lambda2.linear -= self.im2 * impulse;
+        mj_lambda2.linear -= self.im2.component_mul(&impulse);
         mj_lambda2.angular -= self.ii2_sqrt.transform_vector(self.r2.gcross(impulse));
     }
 
@@ -261,7 +263,7 @@ pub(crate) struct BallVelocityGroundConstraint {
     motor_inv_lhs: Option<AngularInertia<Real>>,
     motor_max_impulse: Real,
 
-    im2: Real,
+    im2: Vector<Real>,
     ii2_sqrt: AngularInertia<Real>,
 }
 
@@ -306,19 +308,19 @@ impl BallVelocityGroundConstraint {
                 .effective_world_inv_inertia_sqrt
                 .squared()
                 .quadform(&cmat2)
-                .add_diagonal(im2);
+                .add_diagonal_vector(&im2);
         }
 
         #[cfg(feature = "dim2")]
         {
             let ii2 = rb2.effective_world_inv_inertia_sqrt.squared();
-            let m11 = im2 + cmat2.x * cmat2.x * ii2;
+            let m11 = im2.x + cmat2.x * cmat2.x * ii2;
             let m12 = cmat2.x * cmat2.y * ii2;
-            let m22 = im2 + cmat2.y * cmat2.y * ii2;
+            let m22 = im2.y + cmat2.y * cmat2.y * ii2;
             lhs = SdpMatrix::new(m11, m12, m22)
         }
 
-        let inv_lhs = lhs.inverse_unchecked();
+        let inv_lhs = lhs.reduced_inverse();
 
         /*
          * Motor part.
@@ -367,7 +369,7 @@ impl BallVelocityGroundConstraint {
             if stiffness != 0.0 || damping != 0.0 {
                 motor_inv_lhs = if keep_lhs {
                     let ii2 = rb2.effective_world_inv_inertia_sqrt.squared();
-                    Some(ii2.inverse_unchecked() * gamma)
+                    Some(ii2.reduced_inverse() * gamma)
                 } else {
                     Some(SdpMatrix::diagonal(gamma))
                 };
@@ -400,7 +402,7 @@ impl BallVelocityGroundConstraint {
 
     pub fn warmstart(&self, mj_lambdas: &mut [DeltaVel<Real>]) {
         let mut mj_lambda2 = mj_lambdas[self.mj_lambda2 as usize];
-        mj_lambda2.linear -= self.im2 * self.impulse;
+        mj_lambda2.linear -= self.im2.component_mul(&self.impulse);
         mj_lambda2.angular -= self
             .ii2_sqrt
             .transform_vector(self.r2.gcross(self.impulse) + self.motor_impulse);
@@ -415,7 +417,7 @@ impl BallVelocityGroundConstraint {
         let impulse = self.inv_lhs * dvel;
         self.impulse += impulse;
 
-        mj_lambda2.linear -= self.im2 * impulse;
+        mj_lambda2.linear -= self.im2.component_mul(&impulse);
         mj_lambda2.angular -= self.ii2_sqrt.transform_vector(self.r2.gcross(impulse));
     }
 
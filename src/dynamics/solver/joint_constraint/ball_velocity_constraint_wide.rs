@@ -5,7 +5,9 @@ use crate::dynamics::{
 use crate::math::{
     AngVector, AngularInertia, Isometry, Point, Real, SdpMatrix, SimdReal, Vector, SIMD_WIDTH,
 };
-use crate::utils::{WAngularInertia, WCross, WCrossMatrix};
+#[cfg(feature = "dim3")]
+use crate::utils::WAddDiagonal;
+use crate::utils::{WAngularInertia, WCross, WCrossMatrix, WReducedInverse};
 use simba::simd::SimdValue;
 
 #[derive(Debug)]
@@ -23,8 +25,8 @@ pub(crate) struct WBallVelocityConstraint {
 
     inv_lhs: SdpMatrix<SimdReal>,
 
-    im1: SimdReal,
-    im2: SimdReal,
+    im1: Vector<SimdReal>,
+    im2: Vector<SimdReal>,
 
     ii1_sqrt: AngularInertia<SimdReal>,
     ii2_sqrt: AngularInertia<SimdReal>,
@@ -42,7 +44,7 @@ impl WBallVelocityConstraint {
         let linvel1 = Vector::from(array![|ii| rbs1[ii].linvel; SIMD_WIDTH]);
         let angvel1 = AngVector::<SimdReal>::from(array![|ii| rbs1[ii].angvel; SIMD_WIDTH]);
         let world_com1 = Point::from(array![|ii| rbs1[ii].world_com; SIMD_WIDTH]);
-        let im1 = SimdReal::from(array![|ii| rbs1[ii].effective_inv_mass; SIMD_WIDTH]);
+        let im1 = Vector::from(array![|ii| rbs1[ii].effective_inv_mass; SIMD_WIDTH]);
         let ii1_sqrt = AngularInertia::<SimdReal>::from(
             array![|ii| rbs1[ii].effective_world_inv_inertia_sqrt; SIMD_WIDTH],
         );
@@ -52,7 +54,7 @@ impl WBallVelocityConstraint {
         let linvel2 = Vector::from(array![|ii| rbs2[ii].linvel; SIMD_WIDTH]);
         let angvel2 = AngVector::<SimdReal>::from(array![|ii| rbs2[ii].angvel; SIMD_WIDTH]);
         let world_com2 = Point::from(array![|ii| rbs2[ii].world_com; SIMD_WIDTH]);
-        let im2 = SimdReal::from(array![|ii| rbs2[ii].effective_inv_mass; SIMD_WIDTH]);
+        let im2 = Vector::from(array![|ii| rbs2[ii].effective_inv_mass; SIMD_WIDTH]);
         let ii2_sqrt = AngularInertia::<SimdReal>::from(
             array![|ii| rbs2[ii].effective_world_inv_inertia_sqrt; SIMD_WIDTH],
         );
@@ -78,8 +80,14 @@ impl WBallVelocityConstraint {
 
         #[cfg(feature = "dim3")]
         {
-            lhs = ii2_sqrt.squared().quadform(&cmat2).add_diagonal(im2)
-                + ii1_sqrt.squared().quadform(&cmat1).add_diagonal(im1);
+            lhs = ii2_sqrt
+                .squared()
+                .quadform(&cmat2)
+                .add_diagonal_vector(&im2)
+                + ii1_sqrt
+                    .squared()
+                    .quadform(&cmat1)
+                    .add_diagonal_vector(&im1);
         }
 
         // In 2D we just unroll the computation because
@@ -88,13 +96,13 @@ impl WBallVelocityConstraint {
         {
             let ii1 = ii1_sqrt.squared();
             let ii2 = ii2_sqrt.squared();
-            let m11 = im1 + im2 + cmat1.x * cmat1.x * ii1 + cmat2.x * cmat2.x * ii2;
+            let m11 = im1.x + im2.x + cmat1.x * cmat1.x * ii1 + cmat2.x * cmat2.x * ii2;
             let m12 = cmat1.x * cmat1.y * ii1 + cmat2.x * cmat2.y * ii2;
-            let m22 = im1 + im2 + cmat1.y * cmat1.y * ii1 + cmat2.y * cmat2.y * ii2;
+            let m22 = im1.y + im2.y + cmat1.y * cmat1.y * ii1 + cmat2.y * cmat2.y * ii2;
             lhs = SdpMatrix::new(m11, m12, m22)
         }
 
-        let inv_lhs = lhs.inverse_unchecked();
+        let inv_lhs = lhs.reduced_inverse();
 
         WBallVelocityConstraint {
             joint_id,
@@ -130,9 +138,9 @@ impl WBallVelocityConstraint {
             ),
         };
 
-        mj_lambda1.linear += self.impulse * self.im1;
+        mj_lambda1.linear += self.im1.component_mul(&self.impulse);
         mj_lambda1.angular += self.ii1_sqrt.transform_vector(self.r1.gcross(self.impulse));
-        mj_lambda2.linear -= self.impulse * self.im2;
+        mj_lambda2.linear -= self.im2.component_mul(&self.impulse);
         mj_lambda2.angular -= self.ii2_sqrt.transform_vector(self.r2.gcross(self.impulse));
 
         for ii in 0..SIMD_WIDTH {
@@ -172,10 +180,10 @@ impl WBallVelocityConstraint {
         let impulse = self.inv_lhs * dvel;
         self.impulse += impulse;
 
-        mj_lambda1.linear += impulse * self.im1;
+        mj_lambda1.linear += self.im1.component_mul(&impulse);
         mj_lambda1.angular += self.ii1_sqrt.transform_vector(self.r1.gcross(impulse));
 
-        mj_lambda2.linear -= impulse * self.im2;
+        mj_lambda2.linear -= self.im2.component_mul(&impulse);
         mj_lambda2.angular -= self.ii2_sqrt.transform_vector(self.r2.gcross(impulse));
 
         for ii in 0..SIMD_WIDTH {
@@ -206,7 +214,7 @@ pub(crate) struct WBallVelocityGroundConstraint {
     pub(crate) impulse: Vector<SimdReal>,
     r2: Vector<SimdReal>,
     inv_lhs: SdpMatrix<SimdReal>,
-    im2: SimdReal,
+    im2: Vector<SimdReal>,
     ii2_sqrt: AngularInertia<SimdReal>,
 }
 
@@ -231,7 +239,7 @@ impl WBallVelocityGroundConstraint {
         let linvel2 = Vector::from(array![|ii| rbs2[ii].linvel; SIMD_WIDTH]);
         let angvel2 = AngVector::<SimdReal>::from(array![|ii| rbs2[ii].angvel; SIMD_WIDTH]);
         let world_com2 = Point::from(array![|ii| rbs2[ii].world_com; SIMD_WIDTH]);
-        let im2 = SimdReal::from(array![|ii| rbs2[ii].effective_inv_mass; SIMD_WIDTH]);
+        let im2 = Vector::from(array![|ii| rbs2[ii].effective_inv_mass; SIMD_WIDTH]);
         let ii2_sqrt = AngularInertia::<SimdReal>::from(
             array![|ii| rbs2[ii].effective_world_inv_inertia_sqrt; SIMD_WIDTH],
         );
@@ -257,7 +265,10 @@ impl WBallVelocityGroundConstraint {
 
         #[cfg(feature = "dim3")]
         {
-            lhs = ii2_sqrt.squared().quadform(&cmat2).add_diagonal(im2);
+            lhs = ii2_sqrt
+                .squared()
+                .quadform(&cmat2)
+                .add_diagonal_vector(&im2);
         }
 
         // In 2D we just unroll the computation because
@@ -265,13 +276,13 @@ impl WBallVelocityGroundConstraint {
         #[cfg(feature = "dim2")]
         {
             let ii2 = ii2_sqrt.squared();
-            let m11 = im2 + cmat2.x * cmat2.x * ii2;
+            let m11 = im2.x + cmat2.x * cmat2.x * ii2;
             let m12 = cmat2.x * cmat2.y * ii2;
-            let m22 = im2 + cmat2.y * cmat2.y * ii2;
+            let m22 = im2.y + cmat2.y * cmat2.y * ii2;
             lhs = SdpMatrix::new(m11, m12, m22)
         }
 
-        let inv_lhs = lhs.inverse_unchecked();
+        let inv_lhs = lhs.reduced_inverse();
 
         WBallVelocityGroundConstraint {
             joint_id,
@@ -295,7 +306,7 @@ impl WBallVelocityGroundConstraint {
             ),
         };
 
-        mj_lambda2.linear -= self.impulse * self.im2;
+        mj_lambda2.linear -= self.im2.component_mul(&self.impulse);
         mj_lambda2.angular -= self.ii2_sqrt.transform_vector(self.r2.gcross(self.impulse));
 
         for ii in 0..SIMD_WIDTH {
@@ -321,7 +332,7 @@ impl WBallVelocityGroundConstraint {
         let impulse = self.inv_lhs * dvel;
         self.impulse += impulse;
 
-        mj_lambda2.linear -= impulse * self.im2;
+        mj_lambda2.linear -= self.im2.component_mul(&impulse);
         mj_lambda2.angular -= self.ii2_sqrt.transform_vector(self.r2.gcross(impulse));
 
         for ii in 0..SIMD_WIDTH {
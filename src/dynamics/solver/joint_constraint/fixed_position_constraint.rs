@@ -1,5 +1,5 @@
 use crate::dynamics::{FixedJoint, IntegrationParameters, RigidBody};
-use crate::math::{AngularInertia, Isometry, Point, Real, Rotation};
+use crate::math::{AngularInertia, Isometry, Point, Real, Rotation, Vector};
 use crate::utils::WAngularInertia;
 
 #[derive(Debug)]
@@ -10,12 +10,12 @@ pub(crate) struct FixedPositionConstraint {
     local_anchor2: Isometry<Real>,
     local_com1: Point<Real>,
     local_com2: Point<Real>,
-    im1: Real,
-    im2: Real,
+    im1: Vector<Real>,
+    im2: Vector<Real>,
     ii1: AngularInertia<Real>,
     ii2: AngularInertia<Real>,
 
-    lin_inv_lhs: Real,
+    lin_inv_lhs: Vector<Real>,
     ang_inv_lhs: AngularInertia<Real>,
 }
 
@@ -25,7 +25,7 @@ impl FixedPositionConstraint {
         let ii2 = rb2.effective_world_inv_inertia_sqrt.squared();
         let im1 = rb1.effective_inv_mass;
         let im2 = rb2.effective_inv_mass;
-        let lin_inv_lhs = 1.0 / (im1 + im2);
+        let lin_inv_lhs = (im1 + im2).map(crate::utils::inv);
         let ang_inv_lhs = (ii1 + ii2).inverse();
 
         Self {
@@ -69,9 +69,9 @@ impl FixedPositionConstraint {
         let anchor1 = position1 * Point::from(self.local_anchor1.translation.vector);
         let anchor2 = position2 * Point::from(self.local_anchor2.translation.vector);
         let err = anchor2 - anchor1;
-        let impulse = err * (self.lin_inv_lhs * params.joint_erp);
-        position1.translation.vector += self.im1 * impulse;
-        position2.translation.vector -= self.im2 * impulse;
+        let impulse = err.component_mul(&self.lin_inv_lhs) * params.joint_erp;
+        position1.translation.vector += self.im1.component_mul(&impulse);
+        position2.translation.vector -= self.im2.component_mul(&impulse);
 
         positions[self.position1 as usize] = position1;
         positions[self.position2 as usize] = position2;
@@ -84,7 +84,7 @@ pub(crate) struct FixedPositionGroundConstraint {
     anchor1: Isometry<Real>,
     local_anchor2: Isometry<Real>,
     local_com2: Point<Real>,
-    im2: Real,
+    im2: Vector<Real>,
     ii2: AngularInertia<Real>,
     impulse: Real,
 }
@@ -130,9 +130,10 @@ impl FixedPositionGroundConstraint {
         let anchor1 = Point::from(self.anchor1.translation.vector);
         let anchor2 = position2 * Point::from(self.local_anchor2.translation.vector);
         let err = anchor2 - anchor1;
-        // NOTE: no need to divide by im2 just to multiply right after.
-        let impulse = err * params.joint_erp;
-        position2.translation.vector -= impulse;
+        // NOTE: we can't just skip the division by im2 because the translations
+        //       of the body may be locked along some axes.
+        let impulse = err.component_mul(&self.im2.map(crate::utils::inv)) * params.joint_erp;
+        position2.translation.vector -= self.im2.component_mul(&impulse);
 
         positions[self.position2 as usize] = position2;
     }
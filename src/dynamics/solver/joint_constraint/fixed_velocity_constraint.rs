@@ -3,9 +3,14 @@ use crate::dynamics::{
     FixedJoint, IntegrationParameters, JointGraphEdge, JointIndex, JointParams, RigidBody,
 };
 use crate::math::{AngularInertia, Real, SpacialVector, Vector, DIM};
-use crate::utils::{WAngularInertia, WCross, WCrossMatrix};
+#[cfg(feature = "dim3")]
+use crate::utils::WAddDiagonal;
+use crate::utils::{WAngularInertia, WCross, WCrossMatrix, WReducedInverse};
 #[cfg(feature = "dim2")]
-use na::{Matrix3, Vector3};
+use {
+    na::{Matrix3, Vector3},
+    parry::utils::SdpMatrix3,
+};
 #[cfg(feature = "dim3")]
 use na::{Matrix6, Vector6};
 
@@ -28,8 +33,8 @@ pub(crate) struct FixedVelocityConstraint {
     #[cfg(feature = "dim2")]
     rhs: Vector3<Real>,
 
-    im1: Real,
-    im2: Real,
+    im1: Vector<Real>,
+    im2: Vector<Real>,
 
     ii1: AngularInertia<Real>,
     ii2: AngularInertia<Real>,
@@ -65,8 +70,8 @@ impl FixedVelocityConstraint {
 
         #[cfg(feature = "dim3")]
         {
-            let lhs00 =
-                ii1.quadform(&rmat1).add_diagonal(im1) + ii2.quadform(&rmat2).add_diagonal(im2);
+            let lhs00 = ii1.quadform(&rmat1).add_diagonal_vector(&im1)
+                + ii2.quadform(&rmat2).add_diagonal_vector(&im2);
             let lhs10 = ii1.transform_matrix(&rmat1) + ii2.transform_matrix(&rmat2);
             let lhs11 = (ii1 + ii2).into_matrix();
 
@@ -83,21 +88,21 @@ impl FixedVelocityConstraint {
         // it's just easier that way.
         #[cfg(feature = "dim2")]
         {
-            let m11 = im1 + im2 + rmat1.x * rmat1.x * ii1 + rmat2.x * rmat2.x * ii2;
+            let m11 = im1.x + im2.x + rmat1.x * rmat1.x * ii1 + rmat2.x * rmat2.x * ii2;
             let m12 = rmat1.x * rmat1.y * ii1 + rmat2.x * rmat2.y * ii2;
-            let m22 = im1 + im2 + rmat1.y * rmat1.y * ii1 + rmat2.y * rmat2.y * ii2;
+            let m22 = im1.y + im2.y + rmat1.y * rmat1.y * ii1 + rmat2.y * rmat2.y * ii2;
             let m13 = rmat1.x * ii1 + rmat2.x * ii2;
             let m23 = rmat1.y * ii1 + rmat2.y * ii2;
             let m33 = ii1 + ii2;
-            lhs = Matrix3::new(m11, m12, m13, m12, m22, m23, m13, m23, m33)
+            lhs = SdpMatrix3::new(m11, m12, m13, m22, m23, m33)
         }
 
         // NOTE: we don't use cholesky in 2D because we only have a 3x3 matrix
         // for which a textbook inverse is still efficient.
         #[cfg(feature = "dim2")]
-        let inv_lhs = lhs.try_inverse().expect("Singular system.");
+        let inv_lhs = lhs.reduced_inverse().into_matrix();
         #[cfg(feature = "dim3")]
-        let inv_lhs = lhs.cholesky().expect("Singular system.").inverse();
+        let inv_lhs = lhs.reduced_inverse();
 
         let lin_dvel = -rb1.linvel - rb1.angvel.gcross(r1) + rb2.linvel + rb2.angvel.gcross(r2);
         let ang_dvel = -rb1.angvel + rb2.angvel;
@@ -159,12 +164,12 @@ impl FixedVelocityConstraint {
         #[cfg(feature = "dim3")]
         let ang_impulse = self.impulse.fixed_rows::<3>(3).into_owned();
 
-        mj_lambda1.linear += self.im1 * lin_impulse;
+        mj_lambda1.linear += self.im1.component_mul(&lin_impulse);
         mj_lambda1.angular += self
             .ii1_sqrt
             .transform_vector(ang_impulse + self.r1.gcross(lin_impulse));
 
-        mj_lambda2.linear -= self.im2 * lin_impulse;
+        mj_lambda2.linear -= self.im2.component_mul(&lin_impulse);
         mj_lambda2.angular -= self
             .ii2_sqrt
             .transform_vector(ang_impulse + self.r2.gcross(lin_impulse));
@@ -200,12 +205,12 @@ impl FixedVelocityConstraint {
         #[cfg(feature = "dim3")]
         let ang_impulse = impulse.fixed_rows::<3>(3).into_owned();
 
-        mj_lambda1.linear += self.im1 * lin_impulse;
+        mj_lambda1.linear += self.im1.component_mul(&lin_impulse);
         mj_lambda1.angular += self
             .ii1_sqrt
             .transform_vector(ang_impulse + self.r1.gcross(lin_impulse));
 
-        mj_lambda2.linear -= self.im2 * lin_impulse;
+        mj_lambda2.linear -= self.im2.component_mul(&lin_impulse);
         mj_lambda2.angular -= self
             .ii2_sqrt
             .transform_vector(ang_impulse + self.r2.gcross(lin_impulse));
@@ -240,7 +245,7 @@ pub(crate) struct FixedVelocityGroundConstraint {
     #[cfg(feature = "dim2")]
     rhs: Vector3<Real>,
 
-    im2: Real,
+    im2: Vector<Real>,
     ii2: AngularInertia<Real>,
     ii2_sqrt: AngularInertia<Real>,
     r2: Vector<Real>,
@@ -279,7 +284,7 @@ impl FixedVelocityGroundConstraint {
 
         #[cfg(feature = "dim3")]
         {
-            let lhs00 = ii2.quadform(&rmat2).add_diagonal(im2);
+            let lhs00 = ii2.quadform(&rmat2).add_diagonal_vector(&im2);
             let lhs10 = ii2.transform_matrix(&rmat2);
             let lhs11 = ii2.into_matrix();
 
@@ -296,19 +301,19 @@ impl FixedVelocityGroundConstraint {
         // it's just easier that way.
         #[cfg(feature = "dim2")]
         {
-            let m11 = im2 + rmat2.x * rmat2.x * ii2;
+            let m11 = im2.x + rmat2.x * rmat2.x * ii2;
             let m12 = rmat2.x * rmat2.y * ii2;
-            let m22 = im2 + rmat2.y * rmat2.y * ii2;
+            let m22 = im2.y + rmat2.y * rmat2.y * ii2;
             let m13 = rmat2.x * ii2;
             let m23 = rmat2.y * ii2;
             let m33 = ii2;
-            lhs = Matrix3::new(m11, m12, m13, m12, m22, m23, m13, m23, m33)
+            lhs = SdpMatrix3::new(m11, m12, m13, m22, m23, m33)
         }
 
         #[cfg(feature = "dim2")]
-        let inv_lhs = lhs.try_inverse().expect("Singular system.");
+        let inv_lhs = lhs.reduced_inverse().into_matrix();
         #[cfg(feature = "dim3")]
-        let inv_lhs = lhs.cholesky().expect("Singular system.").inverse();
+        let inv_lhs = lhs.reduced_inverse();
 
         let lin_dvel = rb2.linvel + rb2.angvel.gcross(r2) - rb1.linvel - rb1.angvel.gcross(r1);
         let ang_dvel = rb2.angvel - rb1.angvel;
@@ -363,7 +368,7 @@ impl FixedVelocityGroundConstraint {
         #[cfg(feature = "dim3")]
         let ang_impulse = self.impulse.fixed_rows::<3>(3).into_owned();
 
-        mj_lambda2.linear -= self.im2 * lin_impulse;
+        mj_lambda2.linear -= self.im2.component_mul(&lin_impulse);
         mj_lambda2.angular -= self
             .ii2_sqrt
             .transform_vector(ang_impulse + self.r2.gcross(lin_impulse));
@@ -394,7 +399,7 @@ impl FixedVelocityGroundConstraint {
         #[cfg(feature = "dim3")]
         let ang_impulse = impulse.fixed_rows::<3>(3).into_owned();
 
-        mj_lambda2.linear -= self.im2 * lin_impulse;
+        mj_lambda2.linear -= self.im2.component_mul(&lin_impulse);
         mj_lambda2.angular -= self
             .ii2_sqrt
             .transform_vector(ang_impulse + self.r2.gcross(lin_impulse));
@@ -8,9 +8,11 @@ use crate::math::{
     AngVector, AngularInertia, CrossMatrix, Isometry, Point, Real, SimdReal, SpacialVector, Vector,
     DIM, SIMD_WIDTH,
 };
-use crate::utils::{WAngularInertia, WCross, WCrossMatrix};
 #[cfg(feature = "dim3")]
-use na::{Cholesky, Matrix6, Vector3, Vector6};
+use crate::utils::WAddDiagonal;
+use crate::utils::{WAngularInertia, WCross, WCrossMatrix, WReducedInverse};
+#[cfg(feature = "dim3")]
+use na::{Matrix6, Vector3, Vector6};
 #[cfg(feature = "dim2")]
 use {
     na::{Matrix3, Vector3},
@@ -36,8 +38,8 @@ pub(crate) struct WFixedVelocityConstraint {
     #[cfg(feature = "dim2")]
     rhs: Vector3<SimdReal>,
 
-    im1: SimdReal,
-    im2: SimdReal,
+    im1: Vector<SimdReal>,
+    im2: Vector<SimdReal>,
 
     ii1: AngularInertia<SimdReal>,
     ii2: AngularInertia<SimdReal>,
@@ -61,7 +63,7 @@ impl WFixedVelocityConstraint {
         let linvel1 = Vector::from(array![|ii| rbs1[ii].linvel; SIMD_WIDTH]);
         let angvel1 = AngVector::<SimdReal>::from(array![|ii| rbs1[ii].angvel; SIMD_WIDTH]);
         let world_com1 = Point::from(array![|ii| rbs1[ii].world_com; SIMD_WIDTH]);
-        let im1 = SimdReal::from(array![|ii| rbs1[ii].effective_inv_mass; SIMD_WIDTH]);
+        let im1 = Vector::from(array![|ii| rbs1[ii].effective_inv_mass; SIMD_WIDTH]);
         let ii1_sqrt = AngularInertia::<SimdReal>::from(
             array![|ii| rbs1[ii].effective_world_inv_inertia_sqrt; SIMD_WIDTH],
         );
@@ -71,7 +73,7 @@ impl WFixedVelocityConstraint {
         let linvel2 = Vector::from(array![|ii| rbs2[ii].linvel; SIMD_WIDTH]);
         let angvel2 = AngVector::<SimdReal>::from(array![|ii| rbs2[ii].angvel; SIMD_WIDTH]);
         let world_com2 = Point::from(array![|ii| rbs2[ii].world_com; SIMD_WIDTH]);
-        let im2 = SimdReal::from(array![|ii| rbs2[ii].effective_inv_mass; SIMD_WIDTH]);
+        let im2 = Vector::from(array![|ii| rbs2[ii].effective_inv_mass; SIMD_WIDTH]);
         let ii2_sqrt = AngularInertia::<SimdReal>::from(
             array![|ii| rbs2[ii].effective_world_inv_inertia_sqrt; SIMD_WIDTH],
         );
@@ -95,8 +97,8 @@ impl WFixedVelocityConstraint {
 
         #[cfg(feature = "dim3")]
         {
-            let lhs00 =
-                ii1.quadform(&rmat1).add_diagonal(im1) + ii2.quadform(&rmat2).add_diagonal(im2);
+            let lhs00 = ii1.quadform(&rmat1).add_diagonal_vector(&im1)
+                + ii2.quadform(&rmat2).add_diagonal_vector(&im2);
             let lhs10 = ii1.transform_matrix(&rmat1) + ii2.transform_matrix(&rmat2);
             let lhs11 = (ii1 + ii2).into_matrix();
 
@@ -113,9 +115,9 @@ impl WFixedVelocityConstraint {
         // it's just easier that way.
         #[cfg(feature = "dim2")]
         {
-            let m11 = im1 + im2 + rmat1.x * rmat1.x * ii1 + rmat2.x * rmat2.x * ii2;
+            let m11 = im1.x + im2.x + rmat1.x * rmat1.x * ii1 + rmat2.x * rmat2.x * ii2;
             let m12 = rmat1.x * rmat1.y * ii1 + rmat2.x * rmat2.y * ii2;
-            let m22 = im1 + im2 + rmat1.y * rmat1.y * ii1 + rmat2.y * rmat2.y * ii2;
+            let m22 = im1.y + im2.y + rmat1.y * rmat1.y * ii1 + rmat2.y * rmat2.y * ii2;
             let m13 = rmat1.x * ii1 + rmat2.x * ii2;
             let m23 = rmat1.y * ii1 + rmat2.y * ii2;
             let m33 = ii1 + ii2;
@@ -125,9 +127,9 @@ impl WFixedVelocityConstraint {
         // NOTE: we don't use cholesky in 2D because we only have a 3x3 matrix
         // for which a textbook inverse is still efficient.
         #[cfg(feature = "dim2")]
-        let inv_lhs = lhs.inverse_unchecked().into_matrix(); // FIXME: don't extract the matrix?
+        let inv_lhs = lhs.reduced_inverse().into_matrix(); // FIXME: don't extract the matrix?
         #[cfg(feature = "dim3")]
-        let inv_lhs = Cholesky::new_unchecked(lhs).inverse();
+        let inv_lhs = lhs.reduced_inverse();
 
         let lin_dvel = -linvel1 - angvel1.gcross(r1) + linvel2 + angvel2.gcross(r2);
         let ang_dvel = -angvel1 + angvel2;
@@ -207,12 +209,12 @@ impl WFixedVelocityConstraint {
         #[cfg(feature = "dim3")]
         let ang_impulse = self.impulse.fixed_rows::<3>(3).into_owned();
 
-        mj_lambda1.linear += lin_impulse * self.im1;
+        mj_lambda1.linear += self.im1.component_mul(&lin_impulse);
         mj_lambda1.angular += self
             .ii1_sqrt
             .transform_vector(ang_impulse + self.r1.gcross(lin_impulse));
 
-        mj_lambda2.linear -= lin_impulse * self.im2;
+        mj_lambda2.linear -= self.im2.component_mul(&lin_impulse);
         mj_lambda2.angular -= self
             .ii2_sqrt
             .transform_vector(ang_impulse + self.r2.gcross(lin_impulse));
@@ -268,12 +270,12 @@ impl WFixedVelocityConstraint {
         #[cfg(feature = "dim3")]
         let ang_impulse = impulse.fixed_rows::<3>(3).into_owned();
 
-        mj_lambda1.linear += lin_impulse * self.im1;
+        mj_lambda1.linear += self.im1.component_mul(&lin_impulse);
         mj_lambda1.angular += self
             .ii1_sqrt
             .transform_vector(ang_impulse + self.r1.gcross(lin_impulse));
 
-        mj_lambda2.linear -= lin_impulse * self.im2;
+        mj_lambda2.linear -= self.im2.component_mul(&lin_impulse);
         mj_lambda2.angular -= self
             .ii2_sqrt
             .transform_vector(ang_impulse + self.r2.gcross(lin_impulse));
@@ -316,7 +318,7 @@ pub(crate) struct WFixedVelocityGroundConstraint {
     #[cfg(feature = "dim2")]
     rhs: Vector3<SimdReal>,
 
-    im2: SimdReal,
+    im2: Vector<SimdReal>,
     ii2: AngularInertia<SimdReal>,
     ii2_sqrt: AngularInertia<SimdReal>,
     r2: Vector<SimdReal>,
@@ -340,7 +342,7 @@ impl WFixedVelocityGroundConstraint {
         let linvel2 = Vector::from(array![|ii| rbs2[ii].linvel; SIMD_WIDTH]);
         let angvel2 = AngVector::<SimdReal>::from(array![|ii| rbs2[ii].angvel; SIMD_WIDTH]);
         let world_com2 = Point::from(array![|ii| rbs2[ii].world_com; SIMD_WIDTH]);
-        let im2 = SimdReal::from(array![|ii| rbs2[ii].effective_inv_mass; SIMD_WIDTH]);
+        let im2 = Vector::from(array![|ii| rbs2[ii].effective_inv_mass; SIMD_WIDTH]);
         let ii2_sqrt = AngularInertia::<SimdReal>::from(
             array![|ii| rbs2[ii].effective_world_inv_inertia_sqrt; SIMD_WIDTH],
         );
@@ -366,7 +368,7 @@ impl WFixedVelocityGroundConstraint {
 
         #[cfg(feature = "dim3")]
         {
-            let lhs00 = ii2.quadform(&rmat2).add_diagonal(im2);
+            let lhs00 = ii2.quadform(&rmat2).add_diagonal_vector(&im2);
             let lhs10 = ii2.transform_matrix(&rmat2);
             let lhs11 = ii2.into_matrix();
 
@@ -381,9 +383,9 @@ impl WFixedVelocityGroundConstraint {
         // it's just easier that way.
         #[cfg(feature = "dim2")]
         {
-            let m11 = im2 + rmat2.x * rmat2.x * ii2;
+            let m11 = im2.x + rmat2.x * rmat2.x * ii2;
             let m12 = rmat2.x * rmat2.y * ii2;
-            let m22 = im2 + rmat2.y * rmat2.y * ii2;
+            let m22 = im2.y + rmat2.y * rmat2.y * ii2;
             let m13 = rmat2.x * ii2;
             let m23 = rmat2.y * ii2;
             let m33 = ii2;
@@ -391,9 +393,9 @@ impl WFixedVelocityGroundConstraint {
         }
 
         #[cfg(feature = "dim2")]
-        let inv_lhs = lhs.inverse_unchecked().into_matrix(); // FIXME: don't do into_matrix?
+        let inv_lhs = lhs.reduced_inverse().into_matrix(); // FIXME: don't do into_matrix?
         #[cfg(feature = "dim3")]
-        let inv_lhs = Cholesky::new_unchecked(lhs).inverse();
+        let inv_lhs = lhs.reduced_inverse();
 
         let lin_dvel = linvel2 + angvel2.gcross(r2) - linvel1 - angvel1.gcross(r1);
         let ang_dvel = angvel2 - angvel1;
@@ -460,7 +462,7 @@ impl WFixedVelocityGroundConstraint {
         #[cfg(feature = "dim3")]
         let ang_impulse = self.impulse.fixed_rows::<3>(3).into_owned();
 
-        mj_lambda2.linear -= lin_impulse * self.im2;
+        mj_lambda2.linear -= self.im2.component_mul(&lin_impulse);
         mj_lambda2.angular -= self
             .ii2_sqrt
             .transform_vector(ang_impulse + self.r2.gcross(lin_impulse));
@@ -500,7 +502,7 @@ impl WFixedVelocityGroundConstraint {
         #[cfg(feature = "dim3")]
         let ang_impulse = impulse.fixed_rows::<3>(3).into_owned();
 
-        mj_lambda2.linear -= lin_impulse * self.im2;
+        mj_lambda2.linear -= self.im2.component_mul(&lin_impulse);
         mj_lambda2.angular -= self
             .ii2_sqrt
             .transform_vector(ang_impulse + self.r2.gcross(lin_impulse));
@@ -0,0 +1,183 @@
+use crate::dynamics::solver::DeltaVel;
+use crate::dynamics::{
+    GearJoint, IntegrationParameters, JointGraphEdge, JointIndex, JointParams, RigidBody,
+};
+use crate::math::{AngVector, Isometry, Real};
+use crate::utils::{WAngularInertia, WDot};
+
+// The world-space angular jacobians of the gear for the first and second bodies of the joint.
+#[cfg(feature = "dim2")]
+fn gear_jacobians(_: &Isometry<Real>, _: &Isometry<Real>, joint: &GearJoint) -> (Real, Real) {
+    (1.0, -joint.ratio)
+}
+
+// The world-space angular jacobians of the gear for the first and second bodies of the joint.
+#[cfg(feature = "dim3")]
+fn gear_jacobians(
+    pos1: &Isometry<Real>,
+    pos2: &Isometry<Real>,
+    joint: &GearJoint,
+) -> (AngVector<Real>, AngVector<Real>) {
+    (
+        pos1 * joint.local_axis1.into_inner(),
+        pos2 * joint.local_axis2.into_inner() * -joint.ratio,
+    )
+}
+
+#[derive(Debug)]
+pub(crate) struct GearVelocityConstraint {
+    mj_lambda1: usize,
+    mj_lambda2: usize,
+
+    joint_id: JointIndex,
+
+    rhs: Real,
+    impulse: Real,
+    inv_lhs: Real,
+    drift: Real,
+
+    // The angular jacobians multiplied by the square root of the inverse inertias.
+    ii1_jac1: AngVector<Real>,
+    ii2_jac2: AngVector<Real>,
+}
+
+impl GearVelocityConstraint {
+    pub fn from_params(
+        params: &IntegrationParameters,
+        joint_id: JointIndex,
+        rb1: &RigidBody,
+        rb2: &RigidBody,
+        joint: &GearJoint,
+    ) -> Self {
+        let (jac1, jac2) = gear_jacobians(&rb1.position, &rb2.position, joint);
+        let ii1_jac1 = rb1.effective_world_inv_inertia_sqrt.transform_vector(jac1);
+        let ii2_jac2 = rb2.effective_world_inv_inertia_sqrt.transform_vector(jac2);
+
+        // The velocity error is the rate at which the bodies drifted during the last timestep.
+        let vel_err = rb1.angvel.gdot(jac1) + rb2.angvel.gdot(jac2);
+        let drift = joint.drift + vel_err * params.dt;
+        let rhs =
+            vel_err * params.velocity_solve_fraction + drift * params.joint_erp * params.inv_dt();
+
+        let lhs = ii1_jac1.gdot(ii1_jac1) + ii2_jac2.gdot(ii2_jac2);
+        let inv_lhs = crate::utils::inv(lhs);
+
+        GearVelocityConstraint {
+            mj_lambda1: rb1.active_set_offset,
+            mj_lambda2: rb2.active_set_offset,
+            joint_id,
+            rhs,
+            impulse: joint.impulse * params.warmstart_coeff,
+            inv_lhs,
+            drift,
+            ii1_jac1,
+            ii2_jac2,
+        }
+    }
+
+    pub fn warmstart(&self, mj_lambdas: &mut [DeltaVel<Real>]) {
+        mj_lambdas[self.mj_lambda1 as usize].angular += self.ii1_jac1 * self.impulse;
+        mj_lambdas[self.mj_lambda2 as usize].angular += self.ii2_jac2 * self.impulse;
+    }
+
+    pub fn solve(&mut self, mj_lambdas: &mut [DeltaVel<Real>]) {
+        let mut mj_lambda1 = mj_lambdas[self.mj_lambda1 as usize];
+        let mut mj_lambda2 = mj_lambdas[self.mj_lambda2 as usize];
+
+        let dvel = self.ii1_jac1.gdot(mj_lambda1.angular)
+            + self.ii2_jac2.gdot(mj_lambda2.angular)
+            + self.rhs;
+        let impulse = -dvel * self.inv_lhs;
+        self.impulse += impulse;
+
+        mj_lambda1.angular += self.ii1_jac1 * impulse;
+        mj_lambda2.angular += self.ii2_jac2 * impulse;
+
+        mj_lambdas[self.mj_lambda1 as usize] = mj_lambda1;
+        mj_lambdas[self.mj_lambda2 as usize] = mj_lambda2;
+    }
+
+    pub fn writeback_impulses(&self, joints_all: &mut [JointGraphEdge]) {
+        let joint = &mut joints_all[self.joint_id].weight;
+        if let JointParams::GearJoint(gear) = &mut joint.params {
+            gear.impulse = self.impulse;
+            gear.drift = self.drift;
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct GearVelocityGroundConstraint {
+    mj_lambda2: usize,
+
+    joint_id: JointIndex,
+
+    rhs: Real,
+    impulse: Real,
+    inv_lhs: Real,
+    drift: Real,
+
+    // The angular jacobian multiplied by the square root of the inverse inertia.
+    ii2_jac2: AngVector<Real>,
+}
+
+impl GearVelocityGroundConstraint {
+    pub fn from_params(
+        params: &IntegrationParameters,
+        joint_id: JointIndex,
+        rb1: &RigidBody,
+        rb2: &RigidBody,
+        joint: &GearJoint,
+        flipped: bool,
+    ) -> Self {
+        let (jac1, jac2) = if flipped {
+            let (jac2, jac1) = gear_jacobians(&rb2.position, &rb1.position, joint);
+            (jac1, jac2)
+        } else {
+            gear_jacobians(&rb1.position, &rb2.position, joint)
+        };
+        let ii2_jac2 = rb2.effective_world_inv_inertia_sqrt.transform_vector(jac2);
+
+        let vel_err = rb1.angvel.gdot(jac1) + rb2.angvel.gdot(jac2);
+        let drift = joint.drift + vel_err * params.dt;
+        let rhs =
+            vel_err * params.velocity_solve_fraction + drift * params.joint_erp * params.inv_dt();
+
+        let inv_lhs = crate::utils::inv(ii2_jac2.gdot(ii2_jac2));
+
+        GearVelocityGroundConstraint {
+            mj_lambda2: rb2.active_set_offset,
+            joint_id,
+            rhs,
+            impulse: joint.impulse * params.warmstart_coeff,
+            inv_lhs,
+            drift,
+            ii2_jac2,
+        }
+    }
+
+    pub fn warmstart(&self, mj_lambdas: &mut [DeltaVel<Real>]) {
+        mj_lambdas[self.mj_lambda2 as usize].angular += self.ii2_jac2 * self.impulse;
+    }
+
+    pub fn solve(&mut self, mj_lambdas: &mut [DeltaVel<Real>]) {
+        let mut mj_lambda2 = mj_lambdas[self.mj_lambda2 as usize];
+
+        let dvel = self.ii2_jac2.gdot(mj_lambda2.angular) + self.rhs;
+        let impulse = -dvel * self.inv_lhs;
+        self.impulse += impulse;
+
+        mj_lambda2.angular += self.ii2_jac2 * impulse;
+
+        mj_lambdas[self.mj_lambda2 as usize] = mj_lambda2;
+    }
+
+    // FIXME: duplicated code with the non-ground constraint.
+    pub fn writeback_impulses(&self, joints_all: &mut [JointGraphEdge]) {
+        let joint = &mut joints_all[self.joint_id].weight;
+        if let JointParams::GearJoint(gear) = &mut joint.params {
+            gear.impulse = self.impulse;
+            gear.drift = self.drift;
+        }
+    }
+}
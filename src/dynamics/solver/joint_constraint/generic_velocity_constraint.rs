@@ -5,7 +5,7 @@ use crate::dynamics::{
 use crate::math::{AngularInertia, Dim, Isometry, Real, Rotation, SpacialVector, Vector, DIM};
 use crate::na::UnitQuaternion;
 use crate::parry::math::{AngDim, SpatialVector};
-use crate::utils::{WAngularInertia, WCross, WCrossMatrix};
+use crate::utils::{WAngularInertia, WCross, WCrossMatrix, WReducedInverse};
 #[cfg(feature = "dim3")]
 use na::{Matrix3, Matrix6, Vector3, Vector6, U3};
 #[cfg(feature = "dim2")]
@@ -144,7 +144,7 @@ impl GenericVelocityConstraint {
             }
         }
 
-        delassus.try_inverse().unwrap()
+        delassus.reduced_inverse()
     }
 
     pub fn compute_position_error(
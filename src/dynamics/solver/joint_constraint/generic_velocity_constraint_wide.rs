@@ -8,9 +8,9 @@ use crate::math::{
     AngVector, AngularInertia, CrossMatrix, Dim, Isometry, Point, Real, SimdReal, SpacialVector,
     Vector, SIMD_WIDTH,
 };
-use crate::utils::{WAngularInertia, WCross, WCrossMatrix};
+use crate::utils::{WAngularInertia, WCross, WCrossMatrix, WReducedInverse};
 #[cfg(feature = "dim3")]
-use na::{Cholesky, Matrix6, Vector6, U3};
+use na::{Matrix6, Vector6, U3};
 #[cfg(feature = "dim2")]
 use {
     na::{Matrix3, Vector3},
@@ -125,9 +125,9 @@ impl WGenericVelocityConstraint {
         // NOTE: we don't use cholesky in 2D because we only have a 3x3 matrix
         // for which a textbook inverse is still efficient.
         #[cfg(feature = "dim2")]
-        let inv_lhs = lhs.inverse_unchecked().into_matrix(); // FIXME: don't extract the matrix?
+        let inv_lhs = lhs.reduced_inverse().into_matrix(); // FIXME: don't extract the matrix?
         #[cfg(feature = "dim3")]
-        let inv_lhs = Cholesky::new_unchecked(lhs).inverse();
+        let inv_lhs = lhs.reduced_inverse();
 
         let lin_dvel = -linvel1 - angvel1.gcross(r1) + linvel2 + angvel2.gcross(r2);
         let ang_dvel = -angvel1 + angvel2;
@@ -366,9 +366,9 @@ impl WGenericVelocityGroundConstraint {
         }
 
         #[cfg(feature = "dim2")]
-        let inv_lhs = lhs.inverse_unchecked().into_matrix(); // FIXME: don't do into_matrix?
+        let inv_lhs = lhs.reduced_inverse().into_matrix(); // FIXME: don't do into_matrix?
         #[cfg(feature = "dim3")]
-        let inv_lhs = Cholesky::new_unchecked(lhs).inverse();
+        let inv_lhs = lhs.reduced_inverse();
 
         let lin_dvel = linvel2 + angvel2.gcross(r2) - linvel1 - angvel1.gcross(r1);
         let ang_dvel = angvel2 - angvel1;
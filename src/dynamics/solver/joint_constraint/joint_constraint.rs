@@ -1,6 +1,8 @@
 use super::{
     BallVelocityConstraint, BallVelocityGroundConstraint, FixedVelocityConstraint,
-    FixedVelocityGroundConstraint, PrismaticVelocityConstraint, PrismaticVelocityGroundConstraint,
+    FixedVelocityGroundConstraint, GearVelocityConstraint, GearVelocityGroundConstraint,
+    PrismaticVelocityConstraint, PrismaticVelocityGroundConstraint, PulleyVelocityConstraint,
+    PulleyVelocityGroundConstraint,
 };
 #[cfg(feature = "dim3")]
 use super::{RevoluteVelocityConstraint, RevoluteVelocityGroundConstraint};
@@ -59,6 +61,10 @@ pub(crate) enum AnyJointVelocityConstraint {
     #[cfg(feature = "dim3")]
     #[cfg(feature = "simd-is-enabled")]
     WRevoluteGroundConstraint(WRevoluteVelocityGroundConstraint),
+    GearConstraint(GearVelocityConstraint),
+    GearGroundConstraint(GearVelocityGroundConstraint),
+    PulleyConstraint(PulleyVelocityConstraint),
+    PulleyGroundConstraint(PulleyVelocityGroundConstraint),
     #[allow(dead_code)] // The Empty variant is only used with parallel code.
     Empty,
 }
@@ -95,6 +101,12 @@ impl AnyJointVelocityConstraint {
             JointParams::RevoluteJoint(p) => AnyJointVelocityConstraint::RevoluteConstraint(
                 RevoluteVelocityConstraint::from_params(params, joint_id, rb1, rb2, p),
             ),
+            JointParams::GearJoint(p) => AnyJointVelocityConstraint::GearConstraint(
+                GearVelocityConstraint::from_params(params, joint_id, rb1, rb2, p),
+            ),
+            JointParams::PulleyJoint(p) => AnyJointVelocityConstraint::PulleyConstraint(
+                PulleyVelocityConstraint::from_params(params, joint_id, rb1, rb2, p),
+            ),
         }
     }
 
@@ -142,6 +154,9 @@ impl AnyJointVelocityConstraint {
                     WRevoluteVelocityConstraint::from_params(params, joint_id, rbs1, rbs2, joints),
                 )
             }
+            JointParams::GearJoint(_) | JointParams::PulleyJoint(_) => {
+                unreachable!("Gear and pulley joints don't support SIMD constraints.")
+            }
         }
     }
 
@@ -156,7 +171,7 @@ impl AnyJointVelocityConstraint {
         let flipped = !rb2.is_dynamic();
 
         if flipped {
-            std::mem::swap(&mut rb1, &mut rb2);
+            core::mem::swap(&mut rb1, &mut rb2);
         }
 
         match &joint.params {
@@ -182,6 +197,12 @@ impl AnyJointVelocityConstraint {
             JointParams::RevoluteJoint(p) => RevoluteVelocityGroundConstraint::from_params(
                 params, joint_id, rb1, rb2, p, flipped,
             ),
+            JointParams::GearJoint(p) => AnyJointVelocityConstraint::GearGroundConstraint(
+                GearVelocityGroundConstraint::from_params(params, joint_id, rb1, rb2, p, flipped),
+            ),
+            JointParams::PulleyJoint(p) => AnyJointVelocityConstraint::PulleyGroundConstraint(
+                PulleyVelocityGroundConstraint::from_params(params, joint_id, rb1, rb2, p, flipped),
+            ),
         }
     }
 
@@ -198,7 +219,7 @@ impl AnyJointVelocityConstraint {
 
         for ii in 0..SIMD_WIDTH {
             if !rbs2[ii].is_dynamic() {
-                std::mem::swap(&mut rbs1[ii], &mut rbs2[ii]);
+                core::mem::swap(&mut rbs1[ii], &mut rbs2[ii]);
                 flipped[ii] = true;
             }
         }
@@ -247,6 +268,9 @@ impl AnyJointVelocityConstraint {
                     ),
                 )
             }
+            JointParams::GearJoint(_) | JointParams::PulleyJoint(_) => {
+                unreachable!("Gear and pulley joints don't support SIMD constraints.")
+            }
         }
     }
 
@@ -286,6 +310,10 @@ impl AnyJointVelocityConstraint {
             #[cfg(feature = "dim3")]
             #[cfg(feature = "simd-is-enabled")]
             AnyJointVelocityConstraint::WRevoluteGroundConstraint(c) => c.warmstart(mj_lambdas),
+            AnyJointVelocityConstraint::GearConstraint(c) => c.warmstart(mj_lambdas),
+            AnyJointVelocityConstraint::GearGroundConstraint(c) => c.warmstart(mj_lambdas),
+            AnyJointVelocityConstraint::PulleyConstraint(c) => c.warmstart(mj_lambdas),
+            AnyJointVelocityConstraint::PulleyGroundConstraint(c) => c.warmstart(mj_lambdas),
             AnyJointVelocityConstraint::Empty => unreachable!(),
         }
     }
@@ -326,6 +354,10 @@ impl AnyJointVelocityConstraint {
             #[cfg(feature = "dim3")]
             #[cfg(feature = "simd-is-enabled")]
             AnyJointVelocityConstraint::WRevoluteGroundConstraint(c) => c.solve(mj_lambdas),
+            AnyJointVelocityConstraint::GearConstraint(c) => c.solve(mj_lambdas),
+            AnyJointVelocityConstraint::GearGroundConstraint(c) => c.solve(mj_lambdas),
+            AnyJointVelocityConstraint::PulleyConstraint(c) => c.solve(mj_lambdas),
+            AnyJointVelocityConstraint::PulleyGroundConstraint(c) => c.solve(mj_lambdas),
             AnyJointVelocityConstraint::Empty => unreachable!(),
         }
     }
@@ -386,6 +418,12 @@ impl AnyJointVelocityConstraint {
             AnyJointVelocityConstraint::WRevoluteGroundConstraint(c) => {
                 c.writeback_impulses(joints_all)
             }
+            AnyJointVelocityConstraint::GearConstraint(c) => c.writeback_impulses(joints_all),
+            AnyJointVelocityConstraint::GearGroundConstraint(c) => c.writeback_impulses(joints_all),
+            AnyJointVelocityConstraint::PulleyConstraint(c) => c.writeback_impulses(joints_all),
+            AnyJointVelocityConstraint::PulleyGroundConstraint(c) => {
+                c.writeback_impulses(joints_all)
+            }
             AnyJointVelocityConstraint::Empty => unreachable!(),
         }
     }
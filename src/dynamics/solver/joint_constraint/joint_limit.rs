@@ -0,0 +1,72 @@
+use crate::dynamics::IntegrationParameters;
+use crate::math::Real;
+
+/// Computes the right-hand-side and the impulse bounds of a one-dimensional joint limit.
+///
+/// Here, `dist` is the relative position of the bodies along the limited axis, and `vel` their
+/// relative velocity along this axis. A positive limit impulse increases the relative velocity.
+/// Returns `None` if the limit is inactive.
+///
+/// Within `softness_band` before the hard limit, the approach velocity is damped more and more
+/// as the limit gets closer, reaching zero at the limit. If the hard limit is reached while
+/// approaching it, the relative velocity is reflected and scaled by `restitution`, like for
+/// contacts.
+pub(crate) fn limit_rhs_and_bounds(
+    params: &IntegrationParameters,
+    limits: [Real; 2],
+    restitution: Real,
+    softness_band: Real,
+    dist: Real,
+    vel: Real,
+) -> Option<(Real, (Real, Real))> {
+    let (min_limit, max_limit) = (limits[0], limits[1]);
+    let band = softness_band.max(0.0);
+    // Only the limit closest to the current position is enabled.
+    let max_side = dist * 2.0 > min_limit + max_limit;
+    let min_enabled = !max_side && dist < min_limit + band;
+    let max_enabled = max_side && max_limit - band < dist;
+
+    if !min_enabled && !max_enabled {
+        return None;
+    }
+
+    let impulse_limits = (
+        if max_enabled { -Real::INFINITY } else { 0.0 },
+        if min_enabled { Real::INFINITY } else { 0.0 },
+    );
+
+    // The signed distance beyond the hard limit, and the velocity towards it.
+    let (penetration, approach_vel) = if max_enabled {
+        (dist - max_limit, vel)
+    } else {
+        (min_limit - dist, -vel)
+    };
+
+    let rhs = if penetration < 0.0 {
+        vel * params.velocity_solve_fraction * (band + penetration) / band
+    } else if restitution != 0.0 && approach_vel > 0.0 {
+        vel * (1.0 + restitution)
+    } else {
+        vel * params.velocity_solve_fraction
+            + ((dist - max_limit).max(0.0) - (min_limit - dist).max(0.0))
+                * params.velocity_based_erp_inv_dt()
+    };
+
+    Some((rhs, impulse_limits))
+}
+
+/// The signed angle, in `]-pi, pi]`, between `ref1` and `ref2` along `axis`.
+#[cfg(feature = "dim3")]
+pub(crate) fn revolute_angle(
+    axis: &crate::math::Vector<Real>,
+    ref1: &crate::math::Vector<Real>,
+    ref2: &crate::math::Vector<Real>,
+) -> Real {
+    ref1.cross(ref2).dot(axis).atan2(ref1.dot(ref2))
+}
+
+/// The error of `angle` beyond the given limits, or zero if it is within the limits.
+#[cfg(feature = "dim3")]
+pub(crate) fn limit_error(limits: [Real; 2], angle: Real) -> Real {
+    (angle - limits[1]).max(0.0) - (limits[0] - angle).max(0.0)
+}
@@ -51,6 +51,9 @@ pub(crate) enum AnyJointPositionConstraint {
     WRevoluteJoint(WRevolutePositionConstraint),
     #[cfg(all(feature = "dim3", feature = "simd-is-enabled"))]
     WRevoluteGroundConstraint(WRevolutePositionGroundConstraint),
+    // Gears and pulleys are velocity-level couplings: their drift is corrected
+    // by their velocity constraint.
+    VelocityOnlyJoint,
     #[allow(dead_code)] // The Empty variant is only used with parallel code.
     Empty,
 }
@@ -77,6 +80,9 @@ impl AnyJointPositionConstraint {
             JointParams::RevoluteJoint(p) => AnyJointPositionConstraint::RevoluteJoint(
                 RevolutePositionConstraint::from_params(rb1, rb2, p),
             ),
+            JointParams::GearJoint(_) | JointParams::PulleyJoint(_) => {
+                AnyJointPositionConstraint::VelocityOnlyJoint
+            }
         }
     }
 
@@ -119,6 +125,9 @@ impl AnyJointPositionConstraint {
                     WRevolutePositionConstraint::from_params(rbs1, rbs2, joints),
                 )
             }
+            JointParams::GearJoint(_) | JointParams::PulleyJoint(_) => {
+                unreachable!("Gear and pulley joints don't support SIMD constraints.")
+            }
         }
     }
 
@@ -128,7 +137,7 @@ impl AnyJointPositionConstraint {
         let flipped = !rb2.is_dynamic();
 
         if flipped {
-            std::mem::swap(&mut rb1, &mut rb2);
+            core::mem::swap(&mut rb1, &mut rb2);
         }
 
         match &joint.params {
@@ -150,6 +159,9 @@ impl AnyJointPositionConstraint {
             JointParams::RevoluteJoint(p) => AnyJointPositionConstraint::RevoluteGroundConstraint(
                 RevolutePositionGroundConstraint::from_params(rb1, rb2, p, flipped),
             ),
+            JointParams::GearJoint(_) | JointParams::PulleyJoint(_) => {
+                AnyJointPositionConstraint::VelocityOnlyJoint
+            }
         }
     }
 
@@ -161,7 +173,7 @@ impl AnyJointPositionConstraint {
 
         for ii in 0..SIMD_WIDTH {
             if !rbs2[ii].is_dynamic() {
-                std::mem::swap(&mut rbs1[ii], &mut rbs2[ii]);
+                core::mem::swap(&mut rbs1[ii], &mut rbs2[ii]);
                 flipped[ii] = true;
             }
         }
@@ -200,6 +212,9 @@ impl AnyJointPositionConstraint {
                     WRevolutePositionGroundConstraint::from_params(rbs1, rbs2, joints, flipped),
                 )
             }
+            JointParams::GearJoint(_) | JointParams::PulleyJoint(_) => {
+                unreachable!("Gear and pulley joints don't support SIMD constraints.")
+            }
         }
     }
 
@@ -237,6 +252,7 @@ impl AnyJointPositionConstraint {
             AnyJointPositionConstraint::WRevoluteJoint(c) => c.solve(params, positions),
             #[cfg(all(feature = "dim3", feature = "simd-is-enabled"))]
             AnyJointPositionConstraint::WRevoluteGroundConstraint(c) => c.solve(params, positions),
+            AnyJointPositionConstraint::VelocityOnlyJoint => {}
             AnyJointPositionConstraint::Empty => unreachable!(),
         }
     }
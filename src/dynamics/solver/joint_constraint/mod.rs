@@ -18,6 +18,7 @@ pub(self) use fixed_velocity_constraint::{FixedVelocityConstraint, FixedVelocity
 pub(self) use fixed_velocity_constraint_wide::{
     WFixedVelocityConstraint, WFixedVelocityGroundConstraint,
 };
+pub(self) use gear_velocity_constraint::{GearVelocityConstraint, GearVelocityGroundConstraint};
 // pub(self) use generic_position_constraint::{
 //     GenericPositionConstraint, GenericPositionGroundConstraint,
 // };
@@ -49,6 +50,9 @@ pub(self) use prismatic_velocity_constraint::{
 pub(self) use prismatic_velocity_constraint_wide::{
     WPrismaticVelocityConstraint, WPrismaticVelocityGroundConstraint,
 };
+pub(self) use pulley_velocity_constraint::{
+    PulleyVelocityConstraint, PulleyVelocityGroundConstraint,
+};
 #[cfg(feature = "dim3")]
 pub(self) use revolute_position_constraint::{
     RevolutePositionConstraint, RevolutePositionGroundConstraint,
@@ -78,6 +82,7 @@ mod fixed_position_constraint_wide;
 mod fixed_velocity_constraint;
 #[cfg(feature = "simd-is-enabled")]
 mod fixed_velocity_constraint_wide;
+mod gear_velocity_constraint;
 // mod generic_position_constraint;
 // #[cfg(feature = "simd-is-enabled")]
 // mod generic_position_constraint_wide;
@@ -85,6 +90,7 @@ mod fixed_velocity_constraint_wide;
 // #[cfg(feature = "simd-is-enabled")]
 // mod generic_velocity_constraint_wide;
 mod joint_constraint;
+mod joint_limit;
 mod joint_position_constraint;
 mod prismatic_position_constraint;
 #[cfg(feature = "simd-is-enabled")]
@@ -92,6 +98,7 @@ mod prismatic_position_constraint_wide;
 mod prismatic_velocity_constraint;
 #[cfg(feature = "simd-is-enabled")]
 mod prismatic_velocity_constraint_wide;
+mod pulley_velocity_constraint;
 #[cfg(feature = "dim3")]
 mod revolute_position_constraint;
 #[cfg(all(feature = "dim3", feature = "simd-is-enabled"))]
@@ -8,13 +8,13 @@ pub(crate) struct PrismaticPositionConstraint {
     position1: usize,
     position2: usize,
 
-    im1: Real,
-    im2: Real,
+    im1: Vector<Real>,
+    im2: Vector<Real>,
 
     ii1: AngularInertia<Real>,
     ii2: AngularInertia<Real>,
 
-    lin_inv_lhs: Real,
+    lin_inv_lhs: Vector<Real>,
     ang_inv_lhs: AngularInertia<Real>,
 
     limits: [Real; 2],
@@ -32,7 +32,7 @@ impl PrismaticPositionConstraint {
         let ii2 = rb2.effective_world_inv_inertia_sqrt.squared();
         let im1 = rb1.effective_inv_mass;
         let im2 = rb2.effective_inv_mass;
-        let lin_inv_lhs = 1.0 / (im1 + im2);
+        let lin_inv_lhs = (im1 + im2).map(crate::utils::inv);
         let ang_inv_lhs = (ii1 + ii2).inverse();
 
         Self {
@@ -87,9 +87,9 @@ impl PrismaticPositionConstraint {
             err += *axis1 * (limit_err - self.limits[1]);
         }
 
-        let impulse = err * (self.lin_inv_lhs * params.joint_erp);
-        position1.translation.vector += self.im1 * impulse;
-        position2.translation.vector -= self.im2 * impulse;
+        let impulse = err.component_mul(&self.lin_inv_lhs) * params.joint_erp;
+        position1.translation.vector += self.im1.component_mul(&impulse);
+        position2.translation.vector -= self.im2.component_mul(&impulse);
 
         positions[self.position1 as usize] = position1;
         positions[self.position2 as usize] = position2;
@@ -99,6 +99,7 @@ impl PrismaticPositionConstraint {
 #[derive(Debug)]
 pub(crate) struct PrismaticPositionGroundConstraint {
     position2: usize,
+    im2: Vector<Real>,
     frame1: Isometry<Real>,
     local_frame2: Isometry<Real>,
     axis1: Unit<Vector<Real>>,
@@ -136,6 +137,7 @@ impl PrismaticPositionGroundConstraint {
             axis1,
             local_axis2,
             position2: rb2.active_set_offset,
+            im2: rb2.effective_inv_mass,
             limits: cparams.limits,
         }
     }
@@ -161,9 +163,10 @@ impl PrismaticPositionGroundConstraint {
             err += *self.axis1 * (limit_err - self.limits[1]);
         }
 
-        // NOTE: no need to divide by im2 just to multiply right after.
-        let impulse = err * params.joint_erp;
-        position2.translation.vector -= impulse;
+        // NOTE: we can't just skip the division by im2 because the translations
+        //       of the body may be locked along some axes.
+        let impulse = err.component_mul(&self.im2.map(crate::utils::inv)) * params.joint_erp;
+        position2.translation.vector -= self.im2.component_mul(&impulse);
 
         positions[self.position2 as usize] = position2;
     }
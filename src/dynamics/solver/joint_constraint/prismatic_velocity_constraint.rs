@@ -1,11 +1,12 @@
+use super::joint_limit::limit_rhs_and_bounds;
 use crate::dynamics::solver::DeltaVel;
 use crate::dynamics::{
     IntegrationParameters, JointGraphEdge, JointIndex, JointParams, PrismaticJoint, RigidBody,
 };
 use crate::math::{AngularInertia, Real, Vector};
-use crate::utils::{WAngularInertia, WCross, WCrossMatrix, WDot};
+use crate::utils::{WAngularInertia, WCross, WCrossMatrix, WDot, WReducedInverse};
 #[cfg(feature = "dim3")]
-use na::{Cholesky, Matrix3x2, Matrix5, Vector5};
+use na::{Matrix3, Matrix3x2, Matrix5, Vector5};
 #[cfg(feature = "dim2")]
 use {
     na::{Matrix2, Vector2},
@@ -63,8 +64,8 @@ pub(crate) struct PrismaticVelocityConstraint {
     #[cfg(feature = "dim3")]
     basis1: Matrix3x2<Real>,
 
-    im1: Real,
-    im2: Real,
+    im1: Vector<Real>,
+    im2: Vector<Real>,
 
     ii1_sqrt: AngularInertia<Real>,
     ii2_sqrt: AngularInertia<Real>,
@@ -111,12 +112,12 @@ impl PrismaticVelocityConstraint {
             let r2_mat_b1 = r2_mat * basis1;
 
             lhs = Matrix5::zeros();
-            let lhs00 = ii1.quadform3x2(&r1_mat_b1).add_diagonal(im1)
-                + ii2.quadform3x2(&r2_mat_b1).add_diagonal(im2);
+            let lhs00 = ii1.quadform3x2(&r1_mat_b1).into_matrix()
+                + ii2.quadform3x2(&r2_mat_b1).into_matrix()
+                + basis1.tr_mul(&(Matrix3::from_diagonal(&(im1 + im2)) * basis1));
             let lhs10 = ii1 * r1_mat_b1 + ii2 * r2_mat_b1;
             let lhs11 = (ii1 + ii2).into_matrix();
-            lhs.fixed_slice_mut::<2, 2>(0, 0)
-                .copy_from(&lhs00.into_matrix());
+            lhs.fixed_slice_mut::<2, 2>(0, 0).copy_from(&lhs00);
             lhs.fixed_slice_mut::<3, 2>(2, 0).copy_from(&lhs10);
             lhs.fixed_slice_mut::<3, 3>(2, 2).copy_from(&lhs11);
         }
@@ -125,7 +126,9 @@ impl PrismaticVelocityConstraint {
         {
             let b1r1 = basis1.dot(&r1_mat);
             let b2r2 = basis1.dot(&r2_mat);
-            let m11 = im1 + im2 + b1r1 * ii1 * b1r1 + b2r2 * ii2 * b2r2;
+            let m11 = basis1.dot(&(im1 + im2).component_mul(&basis1))
+                + b1r1 * ii1 * b1r1
+                + b2r2 * ii2 * b2r2;
             let m12 = basis1.dot(&r1_mat) * ii1 + basis1.dot(&r2_mat) * ii2;
             let m22 = ii1 + ii2;
             lhs = SdpMatrix2::new(m11, m12, m22);
@@ -137,9 +140,9 @@ impl PrismaticVelocityConstraint {
         // NOTE: we don't use Cholesky in 2D because we only have a 2x2 matrix
         // for which a textbook inverse is still efficient.
         #[cfg(feature = "dim2")]
-        let inv_lhs = lhs.inverse_unchecked().into_matrix();
+        let inv_lhs = lhs.reduced_inverse().into_matrix();
         #[cfg(feature = "dim3")]
-        let inv_lhs = Cholesky::new_unchecked(lhs).inverse();
+        let inv_lhs = lhs.reduced_inverse();
 
         let linvel_err = basis1.tr_mul(&(anchor_linvel2 - anchor_linvel1));
         let angvel_err = rb2.angvel - rb1.angvel;
@@ -204,7 +207,8 @@ impl PrismaticVelocityConstraint {
         if stiffness != 0.0 || damping != 0.0 {
             motor_inv_lhs = if keep_lhs {
                 let inv_projected_mass = crate::utils::inv(
-                    im1 + im2
+                    axis1.dot(&im1.component_mul(&axis1))
+                        + axis2.dot(&im2.component_mul(&axis2))
                         + gcross1.gdot(ii1.transform_vector(gcross1))
                         + gcross2.gdot(ii2.transform_vector(gcross2)),
                 );
@@ -233,26 +237,24 @@ impl PrismaticVelocityConstraint {
         if joint.limits_enabled {
             let danchor = anchor2 - anchor1;
             let dist = danchor.dot(&axis1);
+            let vel = anchor_linvel2.dot(&axis2) - anchor_linvel1.dot(&axis1);
 
             // TODO: allow predictive constraint activation.
 
-            let (min_limit, max_limit) = (joint.limits[0], joint.limits[1]);
-            let min_enabled = dist < min_limit;
-            let max_enabled = max_limit < dist;
-
-            limits_impulse_limits.0 = if max_enabled { -Real::INFINITY } else { 0.0 };
-            limits_impulse_limits.1 = if min_enabled { Real::INFINITY } else { 0.0 };
-
-            limits_active = min_enabled || max_enabled;
-            if limits_active {
-                limits_rhs = (anchor_linvel2.dot(&axis2) - anchor_linvel1.dot(&axis1))
-                    * params.velocity_solve_fraction;
-
-                limits_rhs += ((dist - max_limit).max(0.0) - (min_limit - dist).max(0.0))
-                    * velocity_based_erp_inv_dt;
+            if let Some((rhs, impulse_limits)) = limit_rhs_and_bounds(
+                params,
+                joint.limits,
+                joint.limit_restitution,
+                joint.limit_softness_band,
+                dist,
+                vel,
+            ) {
+                limits_active = true;
+                limits_rhs = rhs;
+                limits_impulse_limits = impulse_limits;
 
                 limits_inv_lhs = crate::utils::inv(
-                    im1 + im2
+                    limits_forcedir2.dot(&(im1 + im2).component_mul(&limits_forcedir2))
                         + gcross1.gdot(ii1.transform_vector(gcross1))
                         + gcross2.gdot(ii2.transform_vector(gcross2)),
                 );
@@ -303,12 +305,12 @@ impl PrismaticVelocityConstraint {
         #[cfg(feature = "dim3")]
         let ang_impulse = self.impulse.fixed_rows::<3>(2).into_owned();
 
-        mj_lambda1.linear += self.im1 * lin_impulse;
+        mj_lambda1.linear += self.im1.component_mul(&lin_impulse);
         mj_lambda1.angular += self
             .ii1_sqrt
             .transform_vector(ang_impulse + self.r1.gcross(lin_impulse));
 
-        mj_lambda2.linear -= self.im2 * lin_impulse;
+        mj_lambda2.linear -= self.im2.component_mul(&lin_impulse);
         mj_lambda2.angular -= self
             .ii2_sqrt
             .transform_vector(ang_impulse + self.r2.gcross(lin_impulse));
@@ -318,10 +320,10 @@ impl PrismaticVelocityConstraint {
             let lin_impulse1 = self.motor_axis1 * self.motor_impulse;
             let lin_impulse2 = self.motor_axis2 * self.motor_impulse;
 
-            mj_lambda1.linear += lin_impulse1 * self.im1;
+            mj_lambda1.linear += self.im1.component_mul(&lin_impulse1);
             mj_lambda1.angular += self.ii1_sqrt.transform_vector(self.r1.gcross(lin_impulse1));
 
-            mj_lambda2.linear -= lin_impulse2 * self.im2;
+            mj_lambda2.linear -= self.im2.component_mul(&lin_impulse2);
             mj_lambda2.angular -= self.ii2_sqrt.transform_vector(self.r2.gcross(lin_impulse2));
         }
 
@@ -329,11 +331,11 @@ impl PrismaticVelocityConstraint {
         if self.limits_active {
             let limit_impulse1 = -self.limits_forcedir2 * self.limits_impulse;
             let limit_impulse2 = self.limits_forcedir2 * self.limits_impulse;
-            mj_lambda1.linear += self.im1 * limit_impulse1;
+            mj_lambda1.linear += self.im1.component_mul(&limit_impulse1);
             mj_lambda1.angular += self
                 .ii1_sqrt
                 .transform_vector(self.r1.gcross(limit_impulse1));
-            mj_lambda2.linear += self.im2 * limit_impulse2;
+            mj_lambda2.linear += self.im2.component_mul(&limit_impulse2);
             mj_lambda2.angular += self
                 .ii2_sqrt
                 .transform_vector(self.r2.gcross(limit_impulse2));
@@ -363,12 +365,12 @@ impl PrismaticVelocityConstraint {
         #[cfg(feature = "dim3")]
         let ang_impulse = impulse.fixed_rows::<3>(2).into_owned();
 
-        mj_lambda1.linear += self.im1 * lin_impulse;
+        mj_lambda1.linear += self.im1.component_mul(&lin_impulse);
         mj_lambda1.angular += self
             .ii1_sqrt
             .transform_vector(ang_impulse + self.r1.gcross(lin_impulse));
 
-        mj_lambda2.linear -= self.im2 * lin_impulse;
+        mj_lambda2.linear -= self.im2.component_mul(&lin_impulse);
         mj_lambda2.angular -= self
             .ii2_sqrt
             .transform_vector(ang_impulse + self.r2.gcross(lin_impulse));
@@ -394,9 +396,9 @@ impl PrismaticVelocityConstraint {
             let lin_impulse1 = limits_forcedir1 * dimpulse;
             let lin_impulse2 = limits_forcedir2 * dimpulse;
 
-            mj_lambda1.linear += self.im1 * lin_impulse1;
+            mj_lambda1.linear += self.im1.component_mul(&lin_impulse1);
             mj_lambda1.angular += self.ii1_sqrt.transform_vector(self.r1.gcross(lin_impulse1));
-            mj_lambda2.linear += self.im2 * lin_impulse2;
+            mj_lambda2.linear += self.im2.component_mul(&lin_impulse2);
             mj_lambda2.angular += self.ii2_sqrt.transform_vector(self.r2.gcross(lin_impulse2));
         }
     }
@@ -424,9 +426,9 @@ impl PrismaticVelocityConstraint {
             let lin_impulse1 = self.motor_axis1 * dimpulse;
             let lin_impulse2 = self.motor_axis2 * dimpulse;
 
-            mj_lambda1.linear += lin_impulse1 * self.im1;
+            mj_lambda1.linear += self.im1.component_mul(&lin_impulse1);
             mj_lambda1.angular += self.ii1_sqrt.transform_vector(self.r1.gcross(lin_impulse1));
-            mj_lambda2.linear -= lin_impulse2 * self.im2;
+            mj_lambda2.linear -= self.im2.component_mul(&lin_impulse2);
             mj_lambda2.angular -= self.ii2_sqrt.transform_vector(self.r2.gcross(lin_impulse2));
         }
     }
@@ -479,6 +481,7 @@ pub(crate) struct PrismaticVelocityGroundConstraint {
     limits_forcedir2: Vector<Real>,
     limits_impulse: Real,
     limits_rhs: Real,
+    limits_inv_lhs: Real,
     /// min/max applied impulse due to limits
     limits_impulse_limits: (Real, Real),
 
@@ -493,7 +496,7 @@ pub(crate) struct PrismaticVelocityGroundConstraint {
     #[cfg(feature = "dim3")]
     basis1: Matrix3x2<Real>,
 
-    im2: Real,
+    im2: Vector<Real>,
     ii2_sqrt: AngularInertia<Real>,
 }
 
@@ -574,11 +577,11 @@ impl PrismaticVelocityGroundConstraint {
             let r2_mat_b1 = r2_mat * basis1;
 
             lhs = Matrix5::zeros();
-            let lhs00 = ii2.quadform3x2(&r2_mat_b1).add_diagonal(im2);
+            let lhs00 = ii2.quadform3x2(&r2_mat_b1).into_matrix()
+                + basis1.tr_mul(&(Matrix3::from_diagonal(&im2) * basis1));
             let lhs10 = ii2 * r2_mat_b1;
             let lhs11 = ii2.into_matrix();
-            lhs.fixed_slice_mut::<2, 2>(0, 0)
-                .copy_from(&lhs00.into_matrix());
+            lhs.fixed_slice_mut::<2, 2>(0, 0).copy_from(&lhs00);
             lhs.fixed_slice_mut::<3, 2>(2, 0).copy_from(&lhs10);
             lhs.fixed_slice_mut::<3, 3>(2, 2).copy_from(&lhs11);
         }
@@ -586,7 +589,7 @@ impl PrismaticVelocityGroundConstraint {
         #[cfg(feature = "dim2")]
         {
             let b2r2 = basis1.dot(&r2_mat);
-            let m11 = im2 + b2r2 * ii2 * b2r2;
+            let m11 = basis1.dot(&im2.component_mul(&basis1)) + b2r2 * ii2 * b2r2;
             let m12 = basis1.dot(&r2_mat) * ii2;
             let m22 = ii2;
             lhs = SdpMatrix2::new(m11, m12, m22);
@@ -598,9 +601,9 @@ impl PrismaticVelocityGroundConstraint {
         // NOTE: we don't use Cholesky in 2D because we only have a 2x2 matrix
         // for which a textbook inverse is still efficient.
         #[cfg(feature = "dim2")]
-        let inv_lhs = lhs.inverse_unchecked().into_matrix();
+        let inv_lhs = lhs.reduced_inverse().into_matrix();
         #[cfg(feature = "dim3")]
-        let inv_lhs = Cholesky::new_unchecked(lhs).inverse();
+        let inv_lhs = lhs.reduced_inverse();
 
         let linvel_err = basis1.tr_mul(&(anchor_linvel2 - anchor_linvel1));
         let angvel_err = rb2.angvel - rb1.angvel;
@@ -665,7 +668,11 @@ impl PrismaticVelocityGroundConstraint {
         }
 
         if stiffness != 0.0 || damping != 0.0 {
-            motor_inv_lhs = if keep_lhs { gamma / im2 } else { gamma };
+            motor_inv_lhs = if keep_lhs {
+                gamma * crate::utils::inv(axis2.dot(&im2.component_mul(&axis2)))
+            } else {
+                gamma
+            };
             motor_rhs /= gamma;
         }
 
@@ -682,28 +689,29 @@ impl PrismaticVelocityGroundConstraint {
         let limits_forcedir2 = axis2.into_inner();
         let mut limits_rhs = 0.0;
         let mut limits_impulse = 0.0;
+        let mut limits_inv_lhs = 0.0;
         let mut limits_impulse_limits = (0.0, 0.0);
 
         if joint.limits_enabled {
             let danchor = anchor2 - anchor1;
             let dist = danchor.dot(&axis1);
+            let vel = anchor_linvel2.dot(&axis2) - anchor_linvel1.dot(&axis1);
 
             // TODO: allow predictive constraint activation.
 
-            let (min_limit, max_limit) = (joint.limits[0], joint.limits[1]);
-            let min_enabled = dist < min_limit;
-            let max_enabled = max_limit < dist;
-
-            limits_impulse_limits.0 = if max_enabled { -Real::INFINITY } else { 0.0 };
-            limits_impulse_limits.1 = if min_enabled { Real::INFINITY } else { 0.0 };
-
-            limits_active = min_enabled || max_enabled;
-            if limits_active {
-                limits_rhs = (anchor_linvel2.dot(&axis2) - anchor_linvel1.dot(&axis1))
-                    * params.velocity_solve_fraction;
-
-                limits_rhs += ((dist - max_limit).max(0.0) - (min_limit - dist).max(0.0))
-                    * velocity_based_erp_inv_dt;
+            if let Some((rhs, impulse_limits)) = limit_rhs_and_bounds(
+                params,
+                joint.limits,
+                joint.limit_restitution,
+                joint.limit_softness_band,
+                dist,
+                vel,
+            ) {
+                limits_active = true;
+                limits_rhs = rhs;
+                limits_impulse_limits = impulse_limits;
+                limits_inv_lhs =
+                    crate::utils::inv(limits_forcedir2.dot(&im2.component_mul(&limits_forcedir2)));
 
                 limits_impulse = joint
                     .limits_impulse
@@ -722,6 +730,7 @@ impl PrismaticVelocityGroundConstraint {
             limits_forcedir2,
             limits_impulse: limits_impulse * params.warmstart_coeff,
             limits_rhs,
+            limits_inv_lhs,
             limits_impulse_limits,
             motor_rhs,
             motor_inv_lhs,
@@ -744,16 +753,16 @@ impl PrismaticVelocityGroundConstraint {
         #[cfg(feature = "dim3")]
         let ang_impulse = self.impulse.fixed_rows::<3>(2).into_owned();
 
-        mj_lambda2.linear -= self.im2 * lin_impulse;
+        mj_lambda2.linear -= self.im2.component_mul(&lin_impulse);
         mj_lambda2.angular -= self
             .ii2_sqrt
             .transform_vector(ang_impulse + self.r2.gcross(lin_impulse));
 
         // Warmstart motors.
-        mj_lambda2.linear -= self.axis2 * (self.im2 * self.motor_impulse);
+        mj_lambda2.linear -= self.im2.component_mul(&self.axis2) * self.motor_impulse;
 
         // Warmstart limits.
-        mj_lambda2.linear += self.limits_forcedir2 * (self.im2 * self.limits_impulse);
+        mj_lambda2.linear += self.im2.component_mul(&self.limits_forcedir2) * self.limits_impulse;
 
         mj_lambdas[self.mj_lambda2 as usize] = mj_lambda2;
     }
@@ -776,7 +785,7 @@ impl PrismaticVelocityGroundConstraint {
         #[cfg(feature = "dim3")]
         let ang_impulse = impulse.fixed_rows::<3>(2).into_owned();
 
-        mj_lambda2.linear -= self.im2 * lin_impulse;
+        mj_lambda2.linear -= self.im2.component_mul(&lin_impulse);
         mj_lambda2.angular -= self
             .ii2_sqrt
             .transform_vector(ang_impulse + self.r2.gcross(lin_impulse));
@@ -790,13 +799,13 @@ impl PrismaticVelocityGroundConstraint {
                 .limits_forcedir2
                 .dot(&(mj_lambda2.linear + ang_vel2.gcross(self.r2)))
                 + self.limits_rhs;
-            let new_impulse = (self.limits_impulse - lin_dvel / self.im2)
+            let new_impulse = (self.limits_impulse - lin_dvel * self.limits_inv_lhs)
                 .max(self.limits_impulse_limits.0)
                 .min(self.limits_impulse_limits.1);
             let dimpulse = new_impulse - self.limits_impulse;
             self.limits_impulse = new_impulse;
 
-            mj_lambda2.linear += self.limits_forcedir2 * (self.im2 * dimpulse);
+            mj_lambda2.linear += self.im2.component_mul(&self.limits_forcedir2) * dimpulse;
         }
     }
 
@@ -811,7 +820,7 @@ impl PrismaticVelocityGroundConstraint {
             let dimpulse = new_impulse - self.motor_impulse;
             self.motor_impulse = new_impulse;
 
-            mj_lambda2.linear -= self.axis2 * (self.im2 * dimpulse);
+            mj_lambda2.linear -= self.im2.component_mul(&self.axis2) * dimpulse;
         }
     }
 
@@ -7,10 +7,10 @@ use crate::dynamics::{
 use crate::math::{
     AngVector, AngularInertia, Isometry, Point, Real, SimdBool, SimdReal, Vector, SIMD_WIDTH,
 };
-use crate::utils::{WAngularInertia, WCross, WCrossMatrix, WDot};
+use crate::utils::{WAngularInertia, WCross, WCrossMatrix, WDot, WReducedInverse};
 
 #[cfg(feature = "dim3")]
-use na::{Cholesky, Matrix3x2, Matrix5, Vector3, Vector5};
+use na::{Matrix3, Matrix3x2, Matrix5, Vector3, Vector5};
 
 #[cfg(feature = "dim2")]
 use {
@@ -60,8 +60,8 @@ pub(crate) struct WPrismaticVelocityConstraint {
     #[cfg(feature = "dim3")]
     basis1: Matrix3x2<SimdReal>,
 
-    im1: SimdReal,
-    im2: SimdReal,
+    im1: Vector<SimdReal>,
+    im2: Vector<SimdReal>,
 
     ii1_sqrt: AngularInertia<SimdReal>,
     ii2_sqrt: AngularInertia<SimdReal>,
@@ -79,7 +79,7 @@ impl WPrismaticVelocityConstraint {
         let linvel1 = Vector::from(array![|ii| rbs1[ii].linvel; SIMD_WIDTH]);
         let angvel1 = AngVector::<SimdReal>::from(array![|ii| rbs1[ii].angvel; SIMD_WIDTH]);
         let world_com1 = Point::from(array![|ii| rbs1[ii].world_com; SIMD_WIDTH]);
-        let im1 = SimdReal::from(array![|ii| rbs1[ii].effective_inv_mass; SIMD_WIDTH]);
+        let im1 = Vector::from(array![|ii| rbs1[ii].effective_inv_mass; SIMD_WIDTH]);
         let ii1_sqrt = AngularInertia::<SimdReal>::from(
             array![|ii| rbs1[ii].effective_world_inv_inertia_sqrt; SIMD_WIDTH],
         );
@@ -89,7 +89,7 @@ impl WPrismaticVelocityConstraint {
         let linvel2 = Vector::from(array![|ii| rbs2[ii].linvel; SIMD_WIDTH]);
         let angvel2 = AngVector::<SimdReal>::from(array![|ii| rbs2[ii].angvel; SIMD_WIDTH]);
         let world_com2 = Point::from(array![|ii| rbs2[ii].world_com; SIMD_WIDTH]);
-        let im2 = SimdReal::from(array![|ii| rbs2[ii].effective_inv_mass; SIMD_WIDTH]);
+        let im2 = Vector::from(array![|ii| rbs2[ii].effective_inv_mass; SIMD_WIDTH]);
         let ii2_sqrt = AngularInertia::<SimdReal>::from(
             array![|ii| rbs2[ii].effective_world_inv_inertia_sqrt; SIMD_WIDTH],
         );
@@ -155,12 +155,12 @@ impl WPrismaticVelocityConstraint {
             let r2_mat_b1 = r2_mat * basis1;
 
             lhs = Matrix5::zeros();
-            let lhs00 = ii1.quadform3x2(&r1_mat_b1).add_diagonal(im1)
-                + ii2.quadform3x2(&r2_mat_b1).add_diagonal(im2);
+            let lhs00 = ii1.quadform3x2(&r1_mat_b1).into_matrix()
+                + ii2.quadform3x2(&r2_mat_b1).into_matrix()
+                + basis1.tr_mul(&(Matrix3::from_diagonal(&(im1 + im2)) * basis1));
             let lhs10 = ii1 * r1_mat_b1 + ii2 * r2_mat_b1;
             let lhs11 = (ii1 + ii2).into_matrix();
-            lhs.fixed_slice_mut::<2, 2>(0, 0)
-                .copy_from(&lhs00.into_matrix());
+            lhs.fixed_slice_mut::<2, 2>(0, 0).copy_from(&lhs00);
             lhs.fixed_slice_mut::<3, 2>(2, 0).copy_from(&lhs10);
             lhs.fixed_slice_mut::<3, 3>(2, 2).copy_from(&lhs11);
         }
@@ -169,7 +169,9 @@ impl WPrismaticVelocityConstraint {
         {
             let b1r1 = basis1.dot(&r1_mat);
             let b2r2 = basis1.dot(&r2_mat);
-            let m11 = im1 + im2 + b1r1 * ii1 * b1r1 + b2r2 * ii2 * b2r2;
+            let m11 = basis1.dot(&(im1 + im2).component_mul(&basis1))
+                + b1r1 * ii1 * b1r1
+                + b2r2 * ii2 * b2r2;
             let m12 = basis1.dot(&r1_mat) * ii1 + basis1.dot(&r2_mat) * ii2;
             let m22 = ii1 + ii2;
             lhs = SdpMatrix2::new(m11, m12, m22);
@@ -181,9 +183,9 @@ impl WPrismaticVelocityConstraint {
         // NOTE: we don't use Cholesky in 2D because we only have a 2x2 matrix
         // for which a textbook inverse is still efficient.
         #[cfg(feature = "dim2")]
-        let inv_lhs = lhs.inverse_unchecked().into_matrix();
+        let inv_lhs = lhs.reduced_inverse().into_matrix();
         #[cfg(feature = "dim3")]
-        let inv_lhs = Cholesky::new_unchecked(lhs).inverse();
+        let inv_lhs = lhs.reduced_inverse();
 
         let linvel_err = basis1.tr_mul(&(anchor_linvel2 - anchor_linvel1));
         let angvel_err = angvel2 - angvel1;
@@ -270,11 +272,11 @@ impl WPrismaticVelocityConstraint {
                         .simd_max(limits_impulse_limits.0)
                         .simd_min(limits_impulse_limits.1);
 
-                limits_inv_lhs = SimdReal::splat(1.0)
-                    / (im1
-                        + im2
-                        + gcross1.gdot(ii1.transform_vector(gcross1))
-                        + gcross2.gdot(ii2.transform_vector(gcross2)));
+                let inv_r = limits_forcedir2.dot(&(im1 + im2).component_mul(&limits_forcedir2))
+                    + gcross1.gdot(ii1.transform_vector(gcross1))
+                    + gcross2.gdot(ii2.transform_vector(gcross2));
+                // The translations and rotations of the bodies may be locked.
+                limits_inv_lhs = (SimdReal::splat(1.0) / inv_r).select(inv_r.simd_gt(zero), zero);
             }
         }
 
@@ -325,12 +327,12 @@ impl WPrismaticVelocityConstraint {
         #[cfg(feature = "dim3")]
         let ang_impulse = self.impulse.fixed_rows::<3>(2).into_owned();
 
-        mj_lambda1.linear += lin_impulse * self.im1;
+        mj_lambda1.linear += self.im1.component_mul(&lin_impulse);
         mj_lambda1.angular += self
             .ii1_sqrt
             .transform_vector(ang_impulse + self.r1.gcross(lin_impulse));
 
-        mj_lambda2.linear -= lin_impulse * self.im2;
+        mj_lambda2.linear -= self.im2.component_mul(&lin_impulse);
         mj_lambda2.angular -= self
             .ii2_sqrt
             .transform_vector(ang_impulse + self.r2.gcross(lin_impulse));
@@ -340,11 +342,11 @@ impl WPrismaticVelocityConstraint {
             let limit_impulse1 = -self.limits_forcedir2 * self.limits_impulse;
             let limit_impulse2 = self.limits_forcedir2 * self.limits_impulse;
 
-            mj_lambda1.linear += limit_impulse1 * self.im1;
+            mj_lambda1.linear += self.im1.component_mul(&limit_impulse1);
             mj_lambda1.angular += self
                 .ii1_sqrt
                 .transform_vector(self.r1.gcross(limit_impulse1));
-            mj_lambda2.linear += limit_impulse2 * self.im2;
+            mj_lambda2.linear += self.im2.component_mul(&limit_impulse2);
             mj_lambda2.angular += self
                 .ii2_sqrt
                 .transform_vector(self.r2.gcross(limit_impulse2));
@@ -384,12 +386,12 @@ impl WPrismaticVelocityConstraint {
         #[cfg(feature = "dim3")]
         let ang_impulse = impulse.fixed_rows::<3>(2).into_owned();
 
-        mj_lambda1.linear += lin_impulse * self.im1;
+        mj_lambda1.linear += self.im1.component_mul(&lin_impulse);
         mj_lambda1.angular += self
             .ii1_sqrt
             .transform_vector(ang_impulse + self.r1.gcross(lin_impulse));
 
-        mj_lambda2.linear -= lin_impulse * self.im2;
+        mj_lambda2.linear -= self.im2.component_mul(&lin_impulse);
         mj_lambda2.angular -= self
             .ii2_sqrt
             .transform_vector(ang_impulse + self.r2.gcross(lin_impulse));
@@ -419,9 +421,9 @@ impl WPrismaticVelocityConstraint {
             let lin_impulse1 = limits_forcedir1 * dimpulse;
             let lin_impulse2 = limits_forcedir2 * dimpulse;
 
-            mj_lambda1.linear += lin_impulse1 * self.im1;
+            mj_lambda1.linear += self.im1.component_mul(&lin_impulse1);
             mj_lambda1.angular += self.ii1_sqrt.transform_vector(self.r1.gcross(lin_impulse1));
-            mj_lambda2.linear += lin_impulse2 * self.im2;
+            mj_lambda2.linear += self.im2.component_mul(&lin_impulse2);
             mj_lambda2.angular += self.ii2_sqrt.transform_vector(self.r2.gcross(lin_impulse2));
         }
     }
@@ -494,6 +496,7 @@ pub(crate) struct WPrismaticVelocityGroundConstraint {
     limits_forcedir2: Vector<SimdReal>,
     limits_impulse: SimdReal,
     limits_rhs: SimdReal,
+    limits_inv_lhs: SimdReal,
     limits_impulse_limits: (SimdReal, SimdReal),
 
     axis2: Vector<SimdReal>,
@@ -502,7 +505,7 @@ pub(crate) struct WPrismaticVelocityGroundConstraint {
     #[cfg(feature = "dim3")]
     basis1: Matrix3x2<SimdReal>,
 
-    im2: SimdReal,
+    im2: Vector<SimdReal>,
     ii2_sqrt: AngularInertia<SimdReal>,
 }
 
@@ -524,7 +527,7 @@ impl WPrismaticVelocityGroundConstraint {
         let linvel2 = Vector::from(array![|ii| rbs2[ii].linvel; SIMD_WIDTH]);
         let angvel2 = AngVector::<SimdReal>::from(array![|ii| rbs2[ii].angvel; SIMD_WIDTH]);
         let world_com2 = Point::from(array![|ii| rbs2[ii].world_com; SIMD_WIDTH]);
-        let im2 = SimdReal::from(array![|ii| rbs2[ii].effective_inv_mass; SIMD_WIDTH]);
+        let im2 = Vector::from(array![|ii| rbs2[ii].effective_inv_mass; SIMD_WIDTH]);
         let ii2_sqrt = AngularInertia::<SimdReal>::from(
             array![|ii| rbs2[ii].effective_world_inv_inertia_sqrt; SIMD_WIDTH],
         );
@@ -583,11 +586,11 @@ impl WPrismaticVelocityGroundConstraint {
             let r2_mat_b1 = r2_mat * basis1;
 
             lhs = Matrix5::zeros();
-            let lhs00 = ii2.quadform3x2(&r2_mat_b1).add_diagonal(im2);
+            let lhs00 = ii2.quadform3x2(&r2_mat_b1).into_matrix()
+                + basis1.tr_mul(&(Matrix3::from_diagonal(&im2) * basis1));
             let lhs10 = ii2 * r2_mat_b1;
             let lhs11 = ii2.into_matrix();
-            lhs.fixed_slice_mut::<2, 2>(0, 0)
-                .copy_from(&lhs00.into_matrix());
+            lhs.fixed_slice_mut::<2, 2>(0, 0).copy_from(&lhs00);
             lhs.fixed_slice_mut::<3, 2>(2, 0).copy_from(&lhs10);
             lhs.fixed_slice_mut::<3, 3>(2, 2).copy_from(&lhs11);
         }
@@ -595,7 +598,7 @@ impl WPrismaticVelocityGroundConstraint {
         #[cfg(feature = "dim2")]
         {
             let b2r2 = basis1.dot(&r2_mat);
-            let m11 = im2 + b2r2 * ii2 * b2r2;
+            let m11 = basis1.dot(&im2.component_mul(&basis1)) + b2r2 * ii2 * b2r2;
             let m12 = basis1.dot(&r2_mat) * ii2;
             let m22 = ii2;
             lhs = SdpMatrix2::new(m11, m12, m22);
@@ -607,9 +610,9 @@ impl WPrismaticVelocityGroundConstraint {
         // NOTE: we don't use Cholesky in 2D because we only have a 2x2 matrix
         // for which a textbook inverse is still efficient.
         #[cfg(feature = "dim2")]
-        let inv_lhs = lhs.inverse_unchecked().into_matrix();
+        let inv_lhs = lhs.reduced_inverse().into_matrix();
         #[cfg(feature = "dim3")]
-        let inv_lhs = Cholesky::new_unchecked(lhs).inverse();
+        let inv_lhs = lhs.reduced_inverse();
 
         let linvel_err = basis1.tr_mul(&(anchor_linvel2 - anchor_linvel1));
         let angvel_err = angvel2 - angvel1;
@@ -664,6 +667,7 @@ impl WPrismaticVelocityGroundConstraint {
         let mut limits_active = false;
         let mut limits_rhs = zero;
         let mut limits_impulse = zero;
+        let mut limits_inv_lhs = zero;
         let mut limits_impulse_limits = (zero, zero);
 
         let limits_enabled = SimdBool::from(array![|ii| cparams[ii].limits_enabled; SIMD_WIDTH]);
@@ -694,6 +698,10 @@ impl WPrismaticVelocityGroundConstraint {
                     SimdReal::from(array![|ii| cparams[ii].limits_impulse; SIMD_WIDTH])
                         .simd_max(limits_impulse_limits.0)
                         .simd_min(limits_impulse_limits.1);
+
+                let inv_r = limits_forcedir2.dot(&im2.component_mul(&limits_forcedir2));
+                // The translations of the body may be locked.
+                limits_inv_lhs = (SimdReal::splat(1.0) / inv_r).select(inv_r.simd_gt(zero), zero);
             }
         }
 
@@ -707,6 +715,7 @@ impl WPrismaticVelocityGroundConstraint {
             limits_forcedir2,
             limits_rhs,
             limits_impulse: limits_impulse * SimdReal::splat(params.warmstart_coeff),
+            limits_inv_lhs,
             limits_impulse_limits,
             basis1,
             inv_lhs,
@@ -732,12 +741,12 @@ impl WPrismaticVelocityGroundConstraint {
         #[cfg(feature = "dim3")]
         let ang_impulse = self.impulse.fixed_rows::<3>(2).into_owned();
 
-        mj_lambda2.linear -= lin_impulse * self.im2;
+        mj_lambda2.linear -= self.im2.component_mul(&lin_impulse);
         mj_lambda2.angular -= self
             .ii2_sqrt
             .transform_vector(ang_impulse + self.r2.gcross(lin_impulse));
 
-        mj_lambda2.linear += self.limits_forcedir2 * (self.im2 * self.limits_impulse);
+        mj_lambda2.linear += self.im2.component_mul(&self.limits_forcedir2) * self.limits_impulse;
 
         for ii in 0..SIMD_WIDTH {
             mj_lambdas[self.mj_lambda2[ii] as usize].linear = mj_lambda2.linear.extract(ii);
@@ -763,7 +772,7 @@ impl WPrismaticVelocityGroundConstraint {
         #[cfg(feature = "dim3")]
         let ang_impulse = impulse.fixed_rows::<3>(2).into_owned();
 
-        mj_lambda2.linear -= lin_impulse * self.im2;
+        mj_lambda2.linear -= self.im2.component_mul(&lin_impulse);
         mj_lambda2.angular -= self
             .ii2_sqrt
             .transform_vector(ang_impulse + self.r2.gcross(lin_impulse));
@@ -779,13 +788,13 @@ impl WPrismaticVelocityGroundConstraint {
                 .limits_forcedir2
                 .dot(&(mj_lambda2.linear + ang_vel2.gcross(self.r2)))
                 + self.limits_rhs;
-            let new_impulse = (self.limits_impulse - lin_dvel / self.im2)
+            let new_impulse = (self.limits_impulse - lin_dvel * self.limits_inv_lhs)
                 .simd_max(self.limits_impulse_limits.0)
                 .simd_min(self.limits_impulse_limits.1);
             let dimpulse = new_impulse - self.limits_impulse;
             self.limits_impulse = new_impulse;
 
-            mj_lambda2.linear += self.limits_forcedir2 * (self.im2 * dimpulse);
+            mj_lambda2.linear += self.im2.component_mul(&self.limits_forcedir2) * dimpulse;
         }
     }
 
@@ -0,0 +1,252 @@
+use crate::dynamics::solver::DeltaVel;
+use crate::dynamics::{
+    IntegrationParameters, JointGraphEdge, JointIndex, JointParams, PulleyJoint, RigidBody,
+};
+use crate::math::{AngVector, Point, Real, Vector};
+use crate::utils::{WAngularInertia, WCross, WDot};
+
+// The linear and angular jacobians of a rope attached to the given body, scaled by
+// `scale`, together with the length of the rope.
+fn rope_jacobians(
+    rb: &RigidBody,
+    local_anchor: &Point<Real>,
+    ground_anchor: &Point<Real>,
+    scale: Real,
+) -> (Vector<Real>, AngVector<Real>, Real) {
+    let anchor = rb.position * local_anchor;
+    let rope = anchor - ground_anchor;
+    let length = rope.norm();
+    let dir = rope
+        .try_normalize(Real::EPSILON)
+        .unwrap_or_else(Vector::zeros);
+    let lin_jac = dir * -scale;
+    let ang_jac = (anchor - rb.world_com).gcross(lin_jac);
+
+    (lin_jac, ang_jac, length)
+}
+
+#[derive(Debug)]
+pub(crate) struct PulleyVelocityConstraint {
+    mj_lambda1: usize,
+    mj_lambda2: usize,
+
+    joint_id: JointIndex,
+
+    rhs: Real,
+    impulse: Real,
+    inv_lhs: Real,
+
+    lin_jac1: Vector<Real>,
+    lin_jac2: Vector<Real>,
+    // The angular jacobians multiplied by the square root of the inverse inertias.
+    ii1_ang_jac1: AngVector<Real>,
+    ii2_ang_jac2: AngVector<Real>,
+
+    im1: Vector<Real>,
+    im2: Vector<Real>,
+}
+
+impl PulleyVelocityConstraint {
+    pub fn from_params(
+        params: &IntegrationParameters,
+        joint_id: JointIndex,
+        rb1: &RigidBody,
+        rb2: &RigidBody,
+        joint: &PulleyJoint,
+    ) -> Self {
+        let (lin_jac1, ang_jac1, length1) =
+            rope_jacobians(rb1, &joint.local_anchor1, &joint.ground_anchor1, 1.0);
+        let (lin_jac2, ang_jac2, length2) = rope_jacobians(
+            rb2,
+            &joint.local_anchor2,
+            &joint.ground_anchor2,
+            joint.ratio,
+        );
+        let ii1_ang_jac1 = rb1
+            .effective_world_inv_inertia_sqrt
+            .transform_vector(ang_jac1);
+        let ii2_ang_jac2 = rb2
+            .effective_world_inv_inertia_sqrt
+            .transform_vector(ang_jac2);
+        let im1 = rb1.effective_inv_mass;
+        let im2 = rb2.effective_inv_mass;
+
+        let vel_err = rb1.linvel.dot(&lin_jac1)
+            + rb1.angvel.gdot(ang_jac1)
+            + rb2.linvel.dot(&lin_jac2)
+            + rb2.angvel.gdot(ang_jac2);
+        let pos_err = joint.length - length1 - length2 * joint.ratio;
+        let rhs =
+            vel_err * params.velocity_solve_fraction + pos_err * params.joint_erp * params.inv_dt();
+
+        let lhs = lin_jac1.dot(&im1.component_mul(&lin_jac1))
+            + ii1_ang_jac1.gdot(ii1_ang_jac1)
+            + lin_jac2.dot(&im2.component_mul(&lin_jac2))
+            + ii2_ang_jac2.gdot(ii2_ang_jac2);
+
+        PulleyVelocityConstraint {
+            mj_lambda1: rb1.active_set_offset,
+            mj_lambda2: rb2.active_set_offset,
+            joint_id,
+            rhs,
+            impulse: joint.impulse * params.warmstart_coeff,
+            inv_lhs: crate::utils::inv(lhs),
+            lin_jac1,
+            lin_jac2,
+            ii1_ang_jac1,
+            ii2_ang_jac2,
+            im1,
+            im2,
+        }
+    }
+
+    pub fn warmstart(&self, mj_lambdas: &mut [DeltaVel<Real>]) {
+        let mut mj_lambda1 = mj_lambdas[self.mj_lambda1 as usize];
+        let mut mj_lambda2 = mj_lambdas[self.mj_lambda2 as usize];
+
+        mj_lambda1.linear += self.im1.component_mul(&self.lin_jac1) * self.impulse;
+        mj_lambda1.angular += self.ii1_ang_jac1 * self.impulse;
+        mj_lambda2.linear += self.im2.component_mul(&self.lin_jac2) * self.impulse;
+        mj_lambda2.angular += self.ii2_ang_jac2 * self.impulse;
+
+        mj_lambdas[self.mj_lambda1 as usize] = mj_lambda1;
+        mj_lambdas[self.mj_lambda2 as usize] = mj_lambda2;
+    }
+
+    pub fn solve(&mut self, mj_lambdas: &mut [DeltaVel<Real>]) {
+        let mut mj_lambda1 = mj_lambdas[self.mj_lambda1 as usize];
+        let mut mj_lambda2 = mj_lambdas[self.mj_lambda2 as usize];
+
+        let dvel = mj_lambda1.linear.dot(&self.lin_jac1)
+            + self.ii1_ang_jac1.gdot(mj_lambda1.angular)
+            + mj_lambda2.linear.dot(&self.lin_jac2)
+            + self.ii2_ang_jac2.gdot(mj_lambda2.angular)
+            + self.rhs;
+        let impulse = -dvel * self.inv_lhs;
+        self.impulse += impulse;
+
+        mj_lambda1.linear += self.im1.component_mul(&self.lin_jac1) * impulse;
+        mj_lambda1.angular += self.ii1_ang_jac1 * impulse;
+        mj_lambda2.linear += self.im2.component_mul(&self.lin_jac2) * impulse;
+        mj_lambda2.angular += self.ii2_ang_jac2 * impulse;
+
+        mj_lambdas[self.mj_lambda1 as usize] = mj_lambda1;
+        mj_lambdas[self.mj_lambda2 as usize] = mj_lambda2;
+    }
+
+    pub fn writeback_impulses(&self, joints_all: &mut [JointGraphEdge]) {
+        let joint = &mut joints_all[self.joint_id].weight;
+        if let JointParams::PulleyJoint(pulley) = &mut joint.params {
+            pulley.impulse = self.impulse;
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct PulleyVelocityGroundConstraint {
+    mj_lambda2: usize,
+
+    joint_id: JointIndex,
+
+    rhs: Real,
+    impulse: Real,
+    inv_lhs: Real,
+
+    lin_jac2: Vector<Real>,
+    // The angular jacobian multiplied by the square root of the inverse inertia.
+    ii2_ang_jac2: AngVector<Real>,
+
+    im2: Vector<Real>,
+}
+
+impl PulleyVelocityGroundConstraint {
+    pub fn from_params(
+        params: &IntegrationParameters,
+        joint_id: JointIndex,
+        rb1: &RigidBody,
+        rb2: &RigidBody,
+        joint: &PulleyJoint,
+        flipped: bool,
+    ) -> Self {
+        let ((lin_jac1, ang_jac1, length1), (lin_jac2, ang_jac2, length2)) = if flipped {
+            (
+                rope_jacobians(
+                    rb1,
+                    &joint.local_anchor2,
+                    &joint.ground_anchor2,
+                    joint.ratio,
+                ),
+                rope_jacobians(rb2, &joint.local_anchor1, &joint.ground_anchor1, 1.0),
+            )
+        } else {
+            (
+                rope_jacobians(rb1, &joint.local_anchor1, &joint.ground_anchor1, 1.0),
+                rope_jacobians(
+                    rb2,
+                    &joint.local_anchor2,
+                    &joint.ground_anchor2,
+                    joint.ratio,
+                ),
+            )
+        };
+        let ii2_ang_jac2 = rb2
+            .effective_world_inv_inertia_sqrt
+            .transform_vector(ang_jac2);
+        let im2 = rb2.effective_inv_mass;
+
+        let vel_err = rb1.linvel.dot(&lin_jac1)
+            + rb1.angvel.gdot(ang_jac1)
+            + rb2.linvel.dot(&lin_jac2)
+            + rb2.angvel.gdot(ang_jac2);
+        let pos_err = if flipped {
+            joint.length - length1 * joint.ratio - length2
+        } else {
+            joint.length - length1 - length2 * joint.ratio
+        };
+        let rhs =
+            vel_err * params.velocity_solve_fraction + pos_err * params.joint_erp * params.inv_dt();
+
+        let lhs = lin_jac2.dot(&im2.component_mul(&lin_jac2)) + ii2_ang_jac2.gdot(ii2_ang_jac2);
+
+        PulleyVelocityGroundConstraint {
+            mj_lambda2: rb2.active_set_offset,
+            joint_id,
+            rhs,
+            impulse: joint.impulse * params.warmstart_coeff,
+            inv_lhs: crate::utils::inv(lhs),
+            lin_jac2,
+            ii2_ang_jac2,
+            im2,
+        }
+    }
+
+    pub fn warmstart(&self, mj_lambdas: &mut [DeltaVel<Real>]) {
+        let mut mj_lambda2 = mj_lambdas[self.mj_lambda2 as usize];
+        mj_lambda2.linear += self.im2.component_mul(&self.lin_jac2) * self.impulse;
+        mj_lambda2.angular += self.ii2_ang_jac2 * self.impulse;
+        mj_lambdas[self.mj_lambda2 as usize] = mj_lambda2;
+    }
+
+    pub fn solve(&mut self, mj_lambdas: &mut [DeltaVel<Real>]) {
+        let mut mj_lambda2 = mj_lambdas[self.mj_lambda2 as usize];
+
+        let dvel = mj_lambda2.linear.dot(&self.lin_jac2)
+            + self.ii2_ang_jac2.gdot(mj_lambda2.angular)
+            + self.rhs;
+        let impulse = -dvel * self.inv_lhs;
+        self.impulse += impulse;
+
+        mj_lambda2.linear += self.im2.component_mul(&self.lin_jac2) * impulse;
+        mj_lambda2.angular += self.ii2_ang_jac2 * impulse;
+
+        mj_lambdas[self.mj_lambda2 as usize] = mj_lambda2;
+    }
+
+    // FIXME: duplicated code with the non-ground constraint.
+    pub fn writeback_impulses(&self, joints_all: &mut [JointGraphEdge]) {
+        let joint = &mut joints_all[self.joint_id].weight;
+        if let JointParams::PulleyJoint(pulley) = &mut joint.params {
+            pulley.impulse = self.impulse;
+        }
+    }
+}
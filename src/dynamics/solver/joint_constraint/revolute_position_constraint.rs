@@ -1,6 +1,7 @@
+use super::joint_limit::{limit_error, revolute_angle};
 use crate::dynamics::{IntegrationParameters, RevoluteJoint, RigidBody};
 use crate::math::{AngularInertia, Isometry, Point, Real, Rotation, Vector};
-use crate::utils::{WAngularInertia, WCross, WCrossMatrix};
+use crate::utils::{WAddDiagonal, WAngularInertia, WCross, WCrossMatrix, WReducedInverse};
 use na::Unit;
 
 #[derive(Debug)]
@@ -11,8 +12,8 @@ pub(crate) struct RevolutePositionConstraint {
     local_com1: Point<Real>,
     local_com2: Point<Real>,
 
-    im1: Real,
-    im2: Real,
+    im1: Vector<Real>,
+    im2: Vector<Real>,
 
     ii1: AngularInertia<Real>,
     ii2: AngularInertia<Real>,
@@ -26,6 +27,9 @@ pub(crate) struct RevolutePositionConstraint {
     local_axis2: Unit<Vector<Real>>,
     local_basis1: [Vector<Real>; 2],
     local_basis2: [Vector<Real>; 2],
+
+    limits_enabled: bool,
+    limits: [Real; 2],
 }
 
 impl RevolutePositionConstraint {
@@ -52,6 +56,8 @@ impl RevolutePositionConstraint {
             position2: rb2.active_set_offset,
             local_basis1: cparams.basis1,
             local_basis2: cparams.basis2,
+            limits_enabled: cparams.limits_enabled,
+            limits: cparams.limits,
         }
     }
 
@@ -73,13 +79,13 @@ impl RevolutePositionConstraint {
             let lhs = (self
                 .ii2
                 .quadform(&r2.gcross_matrix())
-                .add_diagonal(self.im2)
+                .add_diagonal_vector(&self.im2)
                 + self
                     .ii1
                     .quadform(&r1.gcross_matrix())
-                    .add_diagonal(self.im1))
+                    .add_diagonal_vector(&self.im1))
             .into_matrix();
-            let inv_lhs = lhs.try_inverse().unwrap();
+            let inv_lhs = lhs.reduced_inverse();
 
             let delta_tra = anchor2 - anchor1;
             let lin_error = delta_tra * params.joint_erp;
@@ -89,8 +95,8 @@ impl RevolutePositionConstraint {
             let rot2 = self.ii2 * r2.gcross(lin_impulse);
             position1.rotation = Rotation::new(rot1) * position1.rotation;
             position2.rotation = Rotation::new(-rot2) * position2.rotation;
-            position1.translation.vector += self.im1 * lin_impulse;
-            position2.translation.vector -= self.im2 * lin_impulse;
+            position1.translation.vector += self.im1.component_mul(&lin_impulse);
+            position2.translation.vector -= self.im2.component_mul(&lin_impulse);
         }
 
         /*
@@ -101,7 +107,16 @@ impl RevolutePositionConstraint {
             let axis2 = position2 * self.local_axis2;
             let delta_rot =
                 Rotation::rotation_between_axis(&axis1, &axis2).unwrap_or_else(Rotation::identity);
-            let ang_error = delta_rot.scaled_axis() * params.joint_erp;
+            let mut ang_error = delta_rot.scaled_axis();
+
+            if self.limits_enabled {
+                let ref1 = position1 * self.local_basis1[0];
+                let ref2 = position2 * self.local_basis2[0];
+                let angle = revolute_angle(&axis1, &ref1, &ref2);
+                ang_error += *axis1 * limit_error(self.limits, angle);
+            }
+
+            let ang_error = ang_error * params.joint_erp;
             let ang_impulse = self.ang_inv_lhs.transform_vector(ang_error);
 
             position1.rotation =
@@ -119,7 +134,7 @@ impl RevolutePositionConstraint {
 pub(crate) struct RevolutePositionGroundConstraint {
     position2: usize,
     local_com2: Point<Real>,
-    im2: Real,
+    im2: Vector<Real>,
     ii2: AngularInertia<Real>,
     anchor1: Point<Real>,
     local_anchor2: Point<Real>,
@@ -128,6 +143,9 @@ pub(crate) struct RevolutePositionGroundConstraint {
 
     basis1: [Vector<Real>; 2],
     local_basis2: [Vector<Real>; 2],
+
+    limits_enabled: bool,
+    limits: [Real; 2],
 }
 
 impl RevolutePositionGroundConstraint {
@@ -143,6 +161,7 @@ impl RevolutePositionGroundConstraint {
         let local_axis2;
         let basis1;
         let local_basis2;
+        let limits;
 
         if flipped {
             anchor1 = rb1.next_position * cparams.local_anchor2;
@@ -154,6 +173,7 @@ impl RevolutePositionGroundConstraint {
                 rb1.next_position * cparams.basis2[1],
             ];
             local_basis2 = cparams.basis1;
+            limits = [-cparams.limits[1], -cparams.limits[0]];
         } else {
             anchor1 = rb1.next_position * cparams.local_anchor1;
             local_anchor2 = cparams.local_anchor2;
@@ -164,6 +184,7 @@ impl RevolutePositionGroundConstraint {
                 rb1.next_position * cparams.basis1[1],
             ];
             local_basis2 = cparams.basis2;
+            limits = cparams.limits;
         };
 
         Self {
@@ -177,6 +198,8 @@ impl RevolutePositionGroundConstraint {
             position2: rb2.active_set_offset,
             basis1,
             local_basis2,
+            limits_enabled: cparams.limits_enabled,
+            limits,
         }
     }
 
@@ -194,9 +217,9 @@ impl RevolutePositionGroundConstraint {
             let lhs = self
                 .ii2
                 .quadform(&r2.gcross_matrix())
-                .add_diagonal(self.im2)
+                .add_diagonal_vector(&self.im2)
                 .into_matrix();
-            let inv_lhs = lhs.try_inverse().unwrap();
+            let inv_lhs = lhs.reduced_inverse();
 
             let delta_tra = anchor2 - self.anchor1;
             let lin_error = delta_tra * params.joint_erp;
@@ -204,7 +227,7 @@ impl RevolutePositionGroundConstraint {
 
             let rot2 = self.ii2 * r2.gcross(lin_impulse);
             position2.rotation = Rotation::new(-rot2) * position2.rotation;
-            position2.translation.vector -= self.im2 * lin_impulse;
+            position2.translation.vector -= self.im2.component_mul(&lin_impulse);
         }
 
         /*
@@ -214,7 +237,15 @@ impl RevolutePositionGroundConstraint {
             let axis2 = position2 * self.local_axis2;
             let delta_rot = Rotation::rotation_between_axis(&self.axis1, &axis2)
                 .unwrap_or_else(Rotation::identity);
-            let ang_error = delta_rot.scaled_axis() * params.joint_erp;
+            let mut ang_error = delta_rot.scaled_axis();
+
+            if self.limits_enabled {
+                let ref2 = position2 * self.local_basis2[0];
+                let angle = revolute_angle(&self.axis1, &self.basis1[0], &ref2);
+                ang_error += *self.axis1 * limit_error(self.limits, angle);
+            }
+
+            let ang_error = ang_error * params.joint_erp;
             position2.rotation = Rotation::new(-ang_error) * position2.rotation;
         }
 
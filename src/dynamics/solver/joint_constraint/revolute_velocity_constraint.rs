@@ -1,10 +1,11 @@
+use super::joint_limit::{limit_rhs_and_bounds, revolute_angle};
 use crate::dynamics::solver::{AnyJointVelocityConstraint, DeltaVel};
 use crate::dynamics::{
     IntegrationParameters, JointGraphEdge, JointIndex, JointParams, RevoluteJoint, RigidBody,
 };
 use crate::math::{AngularInertia, Real, Rotation, Vector};
-use crate::utils::{WAngularInertia, WCross, WCrossMatrix};
-use na::{Cholesky, Matrix3x2, Matrix5, UnitQuaternion, Vector5};
+use crate::utils::{WAddDiagonal, WAngularInertia, WCross, WCrossMatrix, WReducedInverse};
+use na::{Matrix3x2, Matrix5, UnitQuaternion, Vector5};
 
 #[derive(Debug)]
 pub(crate) struct RevoluteVelocityConstraint {
@@ -30,11 +31,21 @@ pub(crate) struct RevoluteVelocityConstraint {
     motor_axis1: Vector<Real>,
     motor_axis2: Vector<Real>,
 
+    limits_active: bool,
+    limits_impulse: Real,
+    limits_rhs: Real,
+    limits_inv_lhs: Real,
+    /// min/max applied impulse due to limits
+    limits_impulse_limits: (Real, Real),
+    /// Impulse the locked DoFs apply for each unit of limit impulse,
+    /// so that the limit only moves the body along the free axis.
+    limits_dofs_impulse: Vector5<Real>,
+
     basis1: Matrix3x2<Real>,
     basis2: Matrix3x2<Real>,
 
-    im1: Real,
-    im2: Real,
+    im1: Vector<Real>,
+    im2: Vector<Real>,
 
     ii1_sqrt: AngularInertia<Real>,
     ii2_sqrt: AngularInertia<Real>,
@@ -76,8 +87,8 @@ impl RevoluteVelocityConstraint {
 
         let mut lhs = Matrix5::zeros();
 
-        let lhs00 =
-            ii2.quadform(&r2_mat).add_diagonal(im2) + ii1.quadform(&r1_mat).add_diagonal(im1);
+        let lhs00 = ii2.quadform(&r2_mat).add_diagonal_vector(&im2)
+            + ii1.quadform(&r1_mat).add_diagonal_vector(&im1);
         let lhs10 = basis2.tr_mul(&(ii2 * r2_mat)) + basis1.tr_mul(&(ii1 * r1_mat));
         let lhs11 = (ii1.quadform3x2(&basis1) + ii2.quadform3x2(&basis2)).into_matrix();
 
@@ -88,7 +99,7 @@ impl RevoluteVelocityConstraint {
         lhs.fixed_slice_mut::<2, 3>(3, 0).copy_from(&lhs10);
         lhs.fixed_slice_mut::<2, 2>(3, 3).copy_from(&lhs11);
 
-        let inv_lhs = Cholesky::new_unchecked(lhs).inverse();
+        let inv_lhs = lhs.reduced_inverse();
 
         let linvel_err =
             (rb2.linvel + rb2.angvel.gcross(r2)) - (rb1.linvel + rb1.angvel.gcross(r1));
@@ -154,6 +165,58 @@ impl RevoluteVelocityConstraint {
             motor_rhs /= gamma;
         }
 
+        /*
+         * Limits.
+         */
+        let mut limits_active = false;
+        let mut limits_rhs = 0.0;
+        let mut limits_impulse = 0.0;
+        let mut limits_inv_lhs = 0.0;
+        let mut limits_impulse_limits = (0.0, 0.0);
+        let mut limits_dofs_impulse = Vector5::zeros();
+
+        if joint.limits_enabled {
+            let ref1 = rb1.position * joint.basis1[0];
+            let ref2 = rb2.position * joint.basis2[0];
+            let angle = revolute_angle(&motor_axis1, &ref1, &ref2);
+            let vel = rb2.angvel.dot(&motor_axis2) - rb1.angvel.dot(&motor_axis1);
+
+            if let Some((rhs, impulse_limits)) = limit_rhs_and_bounds(
+                params,
+                joint.limits,
+                joint.limit_restitution,
+                joint.limit_softness_band,
+                angle,
+                vel,
+            ) {
+                limits_active = true;
+                limits_rhs = rhs;
+                limits_impulse_limits = impulse_limits;
+
+                // Velocity change due to a unit limit impulse, and the impulse
+                // the locked DoFs need to cancel its effect on the anchors.
+                let ang_vel1 = -ii1.transform_vector(motor_axis1);
+                let ang_vel2 = ii2.transform_vector(motor_axis2);
+                let lin_dvel = ang_vel2.gcross(r2) - ang_vel1.gcross(r1);
+                let ang_dvel = basis2.tr_mul(&ang_vel2) - basis1.tr_mul(&ang_vel1);
+                limits_dofs_impulse = inv_lhs
+                    * Vector5::new(lin_dvel.x, lin_dvel.y, lin_dvel.z, ang_dvel.x, ang_dvel.y);
+
+                let lin_impulse = limits_dofs_impulse.fixed_rows::<3>(0).into_owned();
+                let ang_impulse = limits_dofs_impulse.fixed_rows::<2>(3).into_owned();
+                let ang_vel1 =
+                    ang_vel1 + ii1.transform_vector(basis1 * ang_impulse + r1.gcross(lin_impulse));
+                let ang_vel2 =
+                    ang_vel2 - ii2.transform_vector(basis2 * ang_impulse + r2.gcross(lin_impulse));
+                limits_inv_lhs =
+                    crate::utils::inv(ang_vel2.dot(&motor_axis2) - ang_vel1.dot(&motor_axis1));
+                limits_impulse = joint
+                    .limits_impulse
+                    .max(limits_impulse_limits.0)
+                    .min(limits_impulse_limits.1);
+            }
+        }
+
         /*
          * Adjust the warmstart impulse.
          * If the velocity along the free axis is somewhat high,
@@ -192,6 +255,12 @@ impl RevoluteVelocityConstraint {
             motor_axis2,
             motor_impulse,
             motor_angle,
+            limits_active,
+            limits_impulse: limits_impulse * params.warmstart_coeff,
+            limits_rhs,
+            limits_inv_lhs,
+            limits_impulse_limits,
+            limits_dofs_impulse,
         }
     }
 
@@ -204,12 +273,12 @@ impl RevoluteVelocityConstraint {
         let ang_impulse1 = self.basis1 * self.impulse.fixed_rows::<2>(3).into_owned();
         let ang_impulse2 = self.basis2 * self.impulse.fixed_rows::<2>(3).into_owned();
 
-        mj_lambda1.linear += self.im1 * lin_impulse1;
+        mj_lambda1.linear += self.im1.component_mul(&lin_impulse1);
         mj_lambda1.angular += self
             .ii1_sqrt
             .transform_vector(ang_impulse1 + self.r1.gcross(lin_impulse1));
 
-        mj_lambda2.linear -= self.im2 * lin_impulse2;
+        mj_lambda2.linear -= self.im2.component_mul(&lin_impulse2);
         mj_lambda2.angular -= self
             .ii2_sqrt
             .transform_vector(ang_impulse2 + self.r2.gcross(lin_impulse2));
@@ -226,6 +295,18 @@ impl RevoluteVelocityConstraint {
                 .transform_vector(self.motor_axis2 * self.motor_impulse);
         }
 
+        /*
+         * Limits
+         */
+        if self.limits_active {
+            mj_lambda1.angular -= self
+                .ii1_sqrt
+                .transform_vector(self.motor_axis1 * self.limits_impulse);
+            mj_lambda2.angular += self
+                .ii2_sqrt
+                .transform_vector(self.motor_axis2 * self.limits_impulse);
+        }
+
         mj_lambdas[self.mj_lambda1 as usize] = mj_lambda1;
         mj_lambdas[self.mj_lambda2 as usize] = mj_lambda2;
     }
@@ -240,23 +321,51 @@ impl RevoluteVelocityConstraint {
         let rhs =
             Vector5::new(lin_dvel.x, lin_dvel.y, lin_dvel.z, ang_dvel.x, ang_dvel.y) + self.rhs;
         let impulse = self.inv_lhs * rhs;
+        self.apply_dofs_impulse(impulse, mj_lambda1, mj_lambda2);
+    }
+
+    fn apply_dofs_impulse(
+        &mut self,
+        impulse: Vector5<Real>,
+        mj_lambda1: &mut DeltaVel<Real>,
+        mj_lambda2: &mut DeltaVel<Real>,
+    ) {
         self.impulse += impulse;
         let lin_impulse1 = impulse.fixed_rows::<3>(0).into_owned();
         let lin_impulse2 = impulse.fixed_rows::<3>(0).into_owned();
         let ang_impulse1 = self.basis1 * impulse.fixed_rows::<2>(3).into_owned();
         let ang_impulse2 = self.basis2 * impulse.fixed_rows::<2>(3).into_owned();
 
-        mj_lambda1.linear += self.im1 * lin_impulse1;
+        mj_lambda1.linear += self.im1.component_mul(&lin_impulse1);
         mj_lambda1.angular += self
             .ii1_sqrt
             .transform_vector(ang_impulse1 + self.r1.gcross(lin_impulse1));
 
-        mj_lambda2.linear -= self.im2 * lin_impulse2;
+        mj_lambda2.linear -= self.im2.component_mul(&lin_impulse2);
         mj_lambda2.angular -= self
             .ii2_sqrt
             .transform_vector(ang_impulse2 + self.r2.gcross(lin_impulse2));
     }
 
+    fn solve_limits(&mut self, mj_lambda1: &mut DeltaVel<Real>, mj_lambda2: &mut DeltaVel<Real>) {
+        if self.limits_active {
+            let ang_vel1 = self.ii1_sqrt.transform_vector(mj_lambda1.angular);
+            let ang_vel2 = self.ii2_sqrt.transform_vector(mj_lambda2.angular);
+            let ang_dvel = ang_vel2.dot(&self.motor_axis2) - ang_vel1.dot(&self.motor_axis1)
+                + self.limits_rhs;
+
+            let new_impulse = (self.limits_impulse - ang_dvel * self.limits_inv_lhs)
+                .max(self.limits_impulse_limits.0)
+                .min(self.limits_impulse_limits.1);
+            let dimpulse = new_impulse - self.limits_impulse;
+            self.limits_impulse = new_impulse;
+
+            mj_lambda1.angular -= self.ii1_sqrt.transform_vector(self.motor_axis1 * dimpulse);
+            mj_lambda2.angular += self.ii2_sqrt.transform_vector(self.motor_axis2 * dimpulse);
+            self.apply_dofs_impulse(self.limits_dofs_impulse * dimpulse, mj_lambda1, mj_lambda2);
+        }
+    }
+
     fn solve_motors(&mut self, mj_lambda1: &mut DeltaVel<Real>, mj_lambda2: &mut DeltaVel<Real>) {
         if self.motor_inv_lhs != 0.0 {
             let ang_vel1 = self.ii1_sqrt.transform_vector(mj_lambda1.angular);
@@ -281,6 +390,7 @@ impl RevoluteVelocityConstraint {
         let mut mj_lambda1 = mj_lambdas[self.mj_lambda1 as usize];
         let mut mj_lambda2 = mj_lambdas[self.mj_lambda2 as usize];
 
+        self.solve_limits(&mut mj_lambda1, &mut mj_lambda2);
         self.solve_dofs(&mut mj_lambda1, &mut mj_lambda2);
         self.solve_motors(&mut mj_lambda1, &mut mj_lambda2);
 
@@ -297,6 +407,7 @@ impl RevoluteVelocityConstraint {
             revolute.prev_axis1 = self.motor_axis1;
             revolute.motor_last_angle = self.motor_angle;
             revolute.motor_impulse = self.motor_impulse;
+            revolute.limits_impulse = self.limits_impulse;
         }
     }
 }
@@ -321,9 +432,19 @@ pub(crate) struct RevoluteVelocityGroundConstraint {
     // Exists just for writing it into the joint.
     motor_angle: Real,
 
+    limits_active: bool,
+    limits_impulse: Real,
+    limits_rhs: Real,
+    limits_inv_lhs: Real,
+    /// min/max applied impulse due to limits
+    limits_impulse_limits: (Real, Real),
+    /// Impulse the locked DoFs apply for each unit of limit impulse,
+    /// so that the limit only moves the body along the free axis.
+    limits_dofs_impulse: Vector5<Real>,
+
     basis2: Matrix3x2<Real>,
 
-    im2: Real,
+    im2: Vector<Real>,
 
     ii2_sqrt: AngularInertia<Real>,
 }
@@ -381,7 +502,7 @@ impl RevoluteVelocityGroundConstraint {
         let r2_mat = r2.gcross_matrix();
 
         let mut lhs = Matrix5::zeros();
-        let lhs00 = ii2.quadform(&r2_mat).add_diagonal(im2);
+        let lhs00 = ii2.quadform(&r2_mat).add_diagonal_vector(&im2);
         let lhs10 = basis2.tr_mul(&(ii2 * r2_mat));
         let lhs11 = ii2.quadform3x2(&basis2).into_matrix();
 
@@ -392,7 +513,7 @@ impl RevoluteVelocityGroundConstraint {
         lhs.fixed_slice_mut::<2, 3>(3, 0).copy_from(&lhs10);
         lhs.fixed_slice_mut::<2, 2>(3, 3).copy_from(&lhs11);
 
-        let inv_lhs = Cholesky::new_unchecked(lhs).inverse();
+        let inv_lhs = lhs.reduced_inverse();
 
         let linvel_err =
             (rb2.linvel + rb2.angvel.gcross(r2)) - (rb1.linvel + rb1.angvel.gcross(r1));
@@ -460,6 +581,61 @@ impl RevoluteVelocityGroundConstraint {
         let motor_impulse = na::clamp(joint.motor_impulse, -motor_max_impulse, motor_max_impulse)
             * params.warmstart_coeff;
 
+        /*
+         * Limits part.
+         */
+        let mut limits_active = false;
+        let mut limits_rhs = 0.0;
+        let mut limits_impulse = 0.0;
+        let mut limits_inv_lhs = 0.0;
+        let mut limits_impulse_limits = (0.0, 0.0);
+        let mut limits_dofs_impulse = Vector5::zeros();
+
+        if joint.limits_enabled {
+            let (ref2, limits) = if flipped {
+                (
+                    rb2.position * joint.basis1[0],
+                    [-joint.limits[1], -joint.limits[0]],
+                )
+            } else {
+                (rb2.position * joint.basis2[0], joint.limits)
+            };
+            let ref1 = basis1.column(0).into_owned();
+            let angle = revolute_angle(&axis1, &ref1, &ref2);
+            let vel = rb2.angvel.dot(&axis2) - rb1.angvel.dot(&axis1);
+
+            if let Some((rhs, impulse_limits)) = limit_rhs_and_bounds(
+                params,
+                limits,
+                joint.limit_restitution,
+                joint.limit_softness_band,
+                angle,
+                vel,
+            ) {
+                limits_active = true;
+                limits_rhs = rhs;
+                limits_impulse_limits = impulse_limits;
+
+                // Velocity change due to a unit limit impulse, and the impulse
+                // the locked DoFs need to cancel its effect on the anchor.
+                let ang_vel2 = ii2.transform_vector(axis2);
+                let lin_dvel = ang_vel2.gcross(r2);
+                let ang_dvel = basis2.tr_mul(&ang_vel2);
+                limits_dofs_impulse = inv_lhs
+                    * Vector5::new(lin_dvel.x, lin_dvel.y, lin_dvel.z, ang_dvel.x, ang_dvel.y);
+
+                let lin_impulse = limits_dofs_impulse.fixed_rows::<3>(0).into_owned();
+                let ang_impulse = limits_dofs_impulse.fixed_rows::<2>(3).into_owned();
+                let ang_vel2 =
+                    ang_vel2 - ii2.transform_vector(basis2 * ang_impulse + r2.gcross(lin_impulse));
+                limits_inv_lhs = crate::utils::inv(ang_vel2.dot(&axis2));
+                limits_impulse = joint
+                    .limits_impulse
+                    .max(limits_impulse_limits.0)
+                    .min(limits_impulse_limits.1);
+            }
+        }
+
         let result = RevoluteVelocityGroundConstraint {
             joint_id,
             mj_lambda2: rb2.active_set_offset,
@@ -476,6 +652,12 @@ impl RevoluteVelocityGroundConstraint {
             motor_max_impulse,
             motor_rhs,
             motor_angle,
+            limits_active,
+            limits_impulse: limits_impulse * params.warmstart_coeff,
+            limits_rhs,
+            limits_inv_lhs,
+            limits_impulse_limits,
+            limits_dofs_impulse,
         };
 
         AnyJointVelocityConstraint::RevoluteGroundConstraint(result)
@@ -487,7 +669,7 @@ impl RevoluteVelocityGroundConstraint {
         let lin_impulse = self.impulse.fixed_rows::<3>(0).into_owned();
         let ang_impulse = self.basis2 * self.impulse.fixed_rows::<2>(3).into_owned();
 
-        mj_lambda2.linear -= self.im2 * lin_impulse;
+        mj_lambda2.linear -= self.im2.component_mul(&lin_impulse);
         mj_lambda2.angular -= self
             .ii2_sqrt
             .transform_vector(ang_impulse + self.r2.gcross(lin_impulse));
@@ -501,6 +683,15 @@ impl RevoluteVelocityGroundConstraint {
                 .transform_vector(self.motor_axis2 * self.motor_impulse);
         }
 
+        /*
+         * Limits
+         */
+        if self.limits_active {
+            mj_lambda2.angular += self
+                .ii2_sqrt
+                .transform_vector(self.motor_axis2 * self.limits_impulse);
+        }
+
         mj_lambdas[self.mj_lambda2 as usize] = mj_lambda2;
     }
 
@@ -512,16 +703,36 @@ impl RevoluteVelocityGroundConstraint {
         let rhs =
             Vector5::new(lin_dvel.x, lin_dvel.y, lin_dvel.z, ang_dvel.x, ang_dvel.y) + self.rhs;
         let impulse = self.inv_lhs * rhs;
+        self.apply_dofs_impulse(impulse, mj_lambda2);
+    }
+
+    fn apply_dofs_impulse(&mut self, impulse: Vector5<Real>, mj_lambda2: &mut DeltaVel<Real>) {
         self.impulse += impulse;
         let lin_impulse = impulse.fixed_rows::<3>(0).into_owned();
         let ang_impulse = self.basis2 * impulse.fixed_rows::<2>(3).into_owned();
 
-        mj_lambda2.linear -= self.im2 * lin_impulse;
+        mj_lambda2.linear -= self.im2.component_mul(&lin_impulse);
         mj_lambda2.angular -= self
             .ii2_sqrt
             .transform_vector(ang_impulse + self.r2.gcross(lin_impulse));
     }
 
+    fn solve_limits(&mut self, mj_lambda2: &mut DeltaVel<Real>) {
+        if self.limits_active {
+            let ang_vel2 = self.ii2_sqrt.transform_vector(mj_lambda2.angular);
+            let ang_dvel = ang_vel2.dot(&self.motor_axis2) + self.limits_rhs;
+
+            let new_impulse = (self.limits_impulse - ang_dvel * self.limits_inv_lhs)
+                .max(self.limits_impulse_limits.0)
+                .min(self.limits_impulse_limits.1);
+            let dimpulse = new_impulse - self.limits_impulse;
+            self.limits_impulse = new_impulse;
+
+            mj_lambda2.angular += self.ii2_sqrt.transform_vector(self.motor_axis2 * dimpulse);
+            self.apply_dofs_impulse(self.limits_dofs_impulse * dimpulse, mj_lambda2);
+        }
+    }
+
     fn solve_motors(&mut self, mj_lambda2: &mut DeltaVel<Real>) {
         if self.motor_inv_lhs != 0.0 {
             let ang_vel2 = self.ii2_sqrt.transform_vector(mj_lambda2.angular);
@@ -543,6 +754,7 @@ impl RevoluteVelocityGroundConstraint {
     pub fn solve(&mut self, mj_lambdas: &mut [DeltaVel<Real>]) {
         let mut mj_lambda2 = mj_lambdas[self.mj_lambda2 as usize];
 
+        self.solve_limits(&mut mj_lambda2);
         self.solve_dofs(&mut mj_lambda2);
         self.solve_motors(&mut mj_lambda2);
 
@@ -556,6 +768,7 @@ impl RevoluteVelocityGroundConstraint {
             revolute.impulse = self.impulse;
             revolute.motor_impulse = self.motor_impulse;
             revolute.motor_last_angle = self.motor_angle;
+            revolute.limits_impulse = self.limits_impulse;
         }
     }
 }
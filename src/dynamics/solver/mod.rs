@@ -12,9 +12,11 @@ pub(self) use self::parallel_velocity_solver::ParallelVelocitySolver;
 pub(self) use self::position_solver::PositionSolver;
 #[cfg(not(feature = "parallel"))]
 pub(self) use self::solver_constraints::SolverConstraints;
+pub use self::solver_stats::{ParallelStageStats, SolverStats};
 #[cfg(not(feature = "parallel"))]
 pub(self) use self::velocity_solver::VelocitySolver;
-pub(self) use delta_vel::DeltaVel;
+pub use delta_vel::DeltaVel;
+pub(self) use extra_iterations::{push_extra_constraints, solve_extra_iterations, ExtraIterations};
 pub(self) use interaction_groups::*;
 pub(self) use joint_constraint::*;
 pub(self) use position_constraint::*;
@@ -34,6 +36,7 @@ pub(self) use velocity_ground_constraint_wide::*;
 
 mod categorization;
 mod delta_vel;
+mod extra_iterations;
 mod interaction_groups;
 #[cfg(not(feature = "parallel"))]
 mod island_solver;
@@ -56,6 +59,7 @@ mod position_ground_constraint_wide;
 mod position_solver;
 #[cfg(not(feature = "parallel"))]
 mod solver_constraints;
+mod solver_stats;
 mod velocity_constraint;
 mod velocity_constraint_element;
 #[cfg(feature = "simd-is-enabled")]
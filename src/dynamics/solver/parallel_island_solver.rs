@@ -1,15 +1,25 @@
-use super::{DeltaVel, ParallelInteractionGroups, ParallelVelocitySolver};
+use super::{DeltaVel, ExtraIterations, ParallelInteractionGroups, ParallelVelocitySolver};
 use crate::dynamics::solver::{
     AnyJointPositionConstraint, AnyJointVelocityConstraint, AnyPositionConstraint,
-    AnyVelocityConstraint, ParallelPositionSolver, ParallelSolverConstraints,
+    AnyVelocityConstraint, ParallelPositionSolver, ParallelSolverConstraints, ParallelStageStats,
+    SolverStats,
+};
+use crate::dynamics::{
+    IntegrationParameters, JointGraphEdge, JointIndex, PositionOffsetsMap, RigidBodySet,
+    UserPositionConstraint, UserVelocityConstraint,
 };
-use crate::dynamics::{IntegrationParameters, JointGraphEdge, JointIndex, RigidBodySet};
 use crate::geometry::{ContactManifold, ContactManifoldIndex};
 use crate::math::{Isometry, Real};
 use crate::utils::WAngularInertia;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicUsize, Ordering};
 use rayon::Scope;
-use std::sync::atomic::{AtomicUsize, Ordering};
 
+// NOTE: the index streams (the `*_index` counters) are only used to distribute the work
+//       between the threads: the atomicity of `fetch_add` is enough so they use `Relaxed`.
+//       The completion counters (the `num_*` counters) publish the results of the work
+//       done so far, so they are incremented with `Release` and waited on with `Acquire`
+//       by `ThreadContext::lock_until_ge`. No `SeqCst` total order is needed anywhere.
 #[macro_export]
 #[doc(hidden)]
 macro_rules! concurrent_loop {
@@ -19,7 +29,7 @@ macro_rules! concurrent_loop {
 
         if max_index > 0 {
             loop {
-                let start_index = $index_stream.fetch_add($batch_size, Ordering::SeqCst);
+                let start_index = $index_stream.fetch_add($batch_size, Ordering::Relaxed);
                 if start_index > max_index {
                     break;
                 }
@@ -29,7 +39,7 @@ macro_rules! concurrent_loop {
                     $f
                 }
 
-                $index_count.fetch_add(end_index - start_index, Ordering::SeqCst);
+                $index_count.fetch_add(end_index - start_index, Ordering::Release);
             }
         }
     };
@@ -40,7 +50,7 @@ macro_rules! concurrent_loop {
 
         if max_index > 0 {
             loop {
-                let start_index = $index_stream.fetch_add($batch_size, Ordering::SeqCst);
+                let start_index = $index_stream.fetch_add($batch_size, Ordering::Relaxed);
                 if start_index > max_index {
                     break;
                 }
@@ -73,6 +83,10 @@ pub(crate) struct ThreadContext {
     pub body_force_integration_index: AtomicUsize,
     pub num_force_integrated_bodies: AtomicUsize,
     pub num_integrated_bodies: AtomicUsize,
+    pub extra_iterations_index: AtomicUsize,
+    pub num_solved_extra_iterations: AtomicUsize,
+    pub user_velocity_constraint_index: AtomicUsize,
+    pub num_solved_user_velocity_constraints: AtomicUsize,
     // Position solver.
     pub position_constraint_initialization_index: AtomicUsize,
     pub num_initialized_position_constraints: AtomicUsize,
@@ -81,12 +95,16 @@ pub(crate) struct ThreadContext {
     pub solve_position_interaction_index: AtomicUsize,
     pub num_solved_position_interactions: AtomicUsize,
     pub position_writeback_index: AtomicUsize,
+    pub user_position_constraint_index: AtomicUsize,
+    pub num_solved_user_position_constraints: AtomicUsize,
+    pub num_written_back_positions: AtomicUsize,
+    // Statistics.
+    pub num_wait_spins: AtomicUsize,
 }
 
 impl ThreadContext {
     pub fn new(batch_size: usize) -> Self {
         ThreadContext {
-            // TODO perhaps there is some optimal value we can compute depending on the island size?
             batch_size,
             constraint_initialization_index: AtomicUsize::new(0),
             num_initialized_constraints: AtomicUsize::new(0),
@@ -104,6 +122,10 @@ impl ThreadContext {
             num_force_integrated_bodies: AtomicUsize::new(0),
             body_integration_index: AtomicUsize::new(0),
             num_integrated_bodies: AtomicUsize::new(0),
+            extra_iterations_index: AtomicUsize::new(0),
+            num_solved_extra_iterations: AtomicUsize::new(0),
+            user_velocity_constraint_index: AtomicUsize::new(0),
+            num_solved_user_velocity_constraints: AtomicUsize::new(0),
             position_constraint_initialization_index: AtomicUsize::new(0),
             num_initialized_position_constraints: AtomicUsize::new(0),
             position_joint_constraint_initialization_index: AtomicUsize::new(0),
@@ -111,17 +133,80 @@ impl ThreadContext {
             solve_position_interaction_index: AtomicUsize::new(0),
             num_solved_position_interactions: AtomicUsize::new(0),
             position_writeback_index: AtomicUsize::new(0),
+            user_position_constraint_index: AtomicUsize::new(0),
+            num_solved_user_position_constraints: AtomicUsize::new(0),
+            num_written_back_positions: AtomicUsize::new(0),
+            num_wait_spins: AtomicUsize::new(0),
         }
     }
 
-    pub fn lock_until_ge(val: &AtomicUsize, target: usize) {
+    /// The batch size used when `IntegrationParameters::parallel_batch_size` is `None`.
+    ///
+    /// Each thread gets a few batches of the average group of independent constraints so the work
+    /// stays balanced, without making the threads contend on the work counters for every constraint.
+    pub fn auto_batch_size(
+        num_constraints: usize,
+        num_groups: usize,
+        num_bodies: usize,
+        num_threads: usize,
+    ) -> usize {
+        const BATCHES_PER_THREAD: usize = 4;
+        const MIN_BATCH_SIZE: usize = 4;
+        const MAX_BATCH_SIZE: usize = 256;
+
+        let work_per_group = if num_groups > 0 {
+            num_constraints / num_groups
+        } else {
+            num_bodies
+        };
+
+        (work_per_group / (num_threads.max(1) * BATCHES_PER_THREAD))
+            .max(MIN_BATCH_SIZE)
+            .min(MAX_BATCH_SIZE)
+    }
+
+    pub fn lock_until_ge(&self, val: &AtomicUsize, target: usize) {
         if target > 0 {
-            // let backoff = crossbeam::utils::Backoff::new();
-            std::sync::atomic::fence(Ordering::SeqCst);
-            while val.load(Ordering::Relaxed) < target {
-                // backoff.spin();
-                // std::thread::yield_now();
+            let mut num_spins = 0;
+
+            // NOTE: the `Acquire` load synchronizes with the `Release` increments of the
+            //       completion counter, so the results of the work counted are visible.
+            while val.load(Ordering::Acquire) < target {
+                num_spins += 1;
             }
+
+            if num_spins > 0 {
+                self.num_wait_spins.fetch_add(num_spins, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Contention statistics of the tasks which shared this context.
+    pub fn stage_stats(&self) -> ParallelStageStats {
+        let index_streams = [
+            &self.constraint_initialization_index,
+            &self.joint_constraint_initialization_index,
+            &self.warmstart_contact_index,
+            &self.warmstart_joint_index,
+            &self.solve_interaction_index,
+            &self.impulse_writeback_index,
+            &self.joint_writeback_index,
+            &self.body_integration_index,
+            &self.body_force_integration_index,
+            &self.position_constraint_initialization_index,
+            &self.position_joint_constraint_initialization_index,
+            &self.solve_position_interaction_index,
+            &self.position_writeback_index,
+        ];
+        // Each claim advances its index stream by exactly `batch_size`.
+        let claimed: usize = index_streams
+            .iter()
+            .map(|stream| stream.load(Ordering::Relaxed))
+            .sum();
+
+        ParallelStageStats {
+            num_claimed_batches: claimed / self.batch_size,
+            num_wait_spins: self.num_wait_spins.load(Ordering::Relaxed),
         }
     }
 }
@@ -135,7 +220,13 @@ pub struct ParallelIslandSolver {
         ParallelSolverConstraints<AnyVelocityConstraint, AnyPositionConstraint>,
     parallel_joint_constraints:
         ParallelSolverConstraints<AnyJointVelocityConstraint, AnyJointPositionConstraint>,
-    thread: ThreadContext,
+    velocity_thread: ThreadContext,
+    position_thread: ThreadContext,
+    extra_iterations: ExtraIterations,
+    user_constraint_offsets: PositionOffsetsMap,
+    // The deepest penetration of the contacts of the island, only computed
+    // if the position solver has a budget.
+    max_penetration: Real,
 }
 
 impl ParallelIslandSolver {
@@ -147,22 +238,98 @@ impl ParallelIslandSolver {
             parallel_joint_groups: ParallelInteractionGroups::new(),
             parallel_contact_constraints: ParallelSolverConstraints::new(),
             parallel_joint_constraints: ParallelSolverConstraints::new(),
-            thread: ThreadContext::new(8),
+            velocity_thread: ThreadContext::new(1),
+            position_thread: ThreadContext::new(1),
+            extra_iterations: ExtraIterations::new(),
+            user_constraint_offsets: PositionOffsetsMap::new(),
+            max_penetration: 0.0,
         }
     }
 
+    /// The deepest penetration of the contacts of the last island solved by this solver.
+    ///
+    /// This is only computed if `IntegrationParameters::position_solve_budget` is set.
+    pub fn max_penetration(&self) -> Real {
+        self.max_penetration
+    }
+
+    /// The number of position constraint solves needed to solve the position constraints
+    /// of the last island initialized by this solver.
+    pub fn position_solve_cost(
+        &self,
+        params: &IntegrationParameters,
+        num_user_constraints: usize,
+    ) -> usize {
+        (self.parallel_contact_constraints.position_constraints.len()
+            + self.parallel_joint_constraints.position_constraints.len()
+            + num_user_constraints)
+            * params.max_position_iterations
+    }
+
+    /// Collects the offsets of the bodies of the island `island_id` given to its user constraints.
+    ///
+    /// This must be called before the parallel resolution of the island, because the other
+    /// islands are modified concurrently during the resolution.
+    pub fn update_user_constraint_offsets(&mut self, bodies: &RigidBodySet, island_id: usize) {
+        self.user_constraint_offsets.update(bodies, island_id);
+    }
+
+    /// Initializes the user velocity constraints of the island `island_id`.
+    ///
+    /// This must be called before the parallel resolution of the island, because the user
+    /// constraints read the whole rigid-body set.
+    pub fn init_user_velocity_constraints(
+        &mut self,
+        island_id: usize,
+        params: &IntegrationParameters,
+        bodies: &RigidBodySet,
+        user_constraints: &mut [&mut dyn UserVelocityConstraint],
+    ) {
+        if !user_constraints.is_empty() {
+            self.user_constraint_offsets.update(bodies, island_id);
+
+            for constraint in user_constraints {
+                constraint.initialize(params, bodies, &self.user_constraint_offsets);
+            }
+        }
+    }
+
+    /// Statistics about the constraints of the last island solved by this solver.
+    pub fn stats(&self, detailed: bool) -> SolverStats {
+        let mut result = SolverStats {
+            num_contact_constraints: self.parallel_contact_constraints.velocity_constraints.len(),
+            num_joint_constraints: self.parallel_joint_constraints.velocity_constraints.len(),
+            parallel_batch_size: self.velocity_thread.batch_size,
+            parallel_velocity_stage: self.velocity_thread.stage_stats(),
+            parallel_position_stage: self.position_thread.stage_stats(),
+            ..SolverStats::default()
+        };
+
+        if detailed {
+            result.bytes = core::mem::size_of::<Self>()
+                + self.parallel_contact_constraints.allocated_bytes()
+                + self.parallel_joint_constraints.allocated_bytes()
+                + self.mj_lambdas.capacity() * core::mem::size_of::<DeltaVel<Real>>()
+                + self.positions.capacity() * core::mem::size_of::<Isometry<Real>>();
+        }
+
+        result
+    }
+
     pub fn solve_position_constraints<'s>(
         &'s mut self,
         scope: &Scope<'s>,
         island_id: usize,
         params: &'s IntegrationParameters,
         bodies: &'s mut RigidBodySet,
+        user_constraints: &'s mut Vec<&mut dyn UserPositionConstraint>,
     ) {
         let num_threads = rayon::current_num_threads();
         // TODO: not sure this is the best value. Also, perhaps it is better to interleave tasks of each island?
         let num_task_per_island = num_threads; // (num_threads / num_islands).max(1);
-        // TODO: could we compute some kind of optimal value here?
-        self.thread = ThreadContext::new(8);
+        // The position constraints are the same as the velocity constraints so we reuse
+        // the batch size selected for the velocity resolution.
+        self.position_thread = ThreadContext::new(self.velocity_thread.batch_size);
         self.positions.clear();
         self.positions
             .resize(bodies.active_island(island_id).len(), Isometry::identity());
@@ -170,25 +337,30 @@ impl ParallelIslandSolver {
         for _ in 0..num_task_per_island {
             // We use AtomicPtr because it is Send+Sync while *mut is not.
             // See https://internals.rust-lang.org/t/shouldnt-pointers-be-send-sync-or/8818
-            let thread = &self.thread;
-            let positions = std::sync::atomic::AtomicPtr::new(&mut self.positions as *mut _);
-            let bodies = std::sync::atomic::AtomicPtr::new(bodies as *mut _);
-            let parallel_contact_constraints =
-                std::sync::atomic::AtomicPtr::new(&mut self.parallel_contact_constraints as *mut _);
+            let thread = &self.position_thread;
+            let offsets = &self.user_constraint_offsets;
+            let positions = core::sync::atomic::AtomicPtr::new(&mut self.positions as *mut _);
+            let bodies = core::sync::atomic::AtomicPtr::new(bodies as *mut _);
+            let user_constraints = core::sync::atomic::AtomicPtr::new(user_constraints as *mut _);
+            let parallel_contact_constraints = core::sync::atomic::AtomicPtr::new(
+                &mut self.parallel_contact_constraints as *mut _,
+            );
             let parallel_joint_constraints =
-                std::sync::atomic::AtomicPtr::new(&mut self.parallel_joint_constraints as *mut _);
+                core::sync::atomic::AtomicPtr::new(&mut self.parallel_joint_constraints as *mut _);
 
             scope.spawn(move |_| {
                 // Transmute *mut -> &mut
                 let positions: &mut Vec<Isometry<Real>> =
-                    unsafe { std::mem::transmute(positions.load(Ordering::Relaxed)) };
+                    unsafe { core::mem::transmute(positions.load(Ordering::Relaxed)) };
                 let bodies: &mut RigidBodySet =
-                    unsafe { std::mem::transmute(bodies.load(Ordering::Relaxed)) };
+                    unsafe { core::mem::transmute(bodies.load(Ordering::Relaxed)) };
+                let user_constraints: &mut Vec<&mut dyn UserPositionConstraint> =
+                    unsafe { core::mem::transmute(user_constraints.load(Ordering::Relaxed)) };
                 let parallel_contact_constraints: &mut ParallelSolverConstraints<AnyVelocityConstraint, AnyPositionConstraint> = unsafe {
-                    std::mem::transmute(parallel_contact_constraints.load(Ordering::Relaxed))
+                    core::mem::transmute(parallel_contact_constraints.load(Ordering::Relaxed))
                 };
                 let parallel_joint_constraints: &mut ParallelSolverConstraints<AnyJointVelocityConstraint, AnyJointPositionConstraint> = unsafe {
-                    std::mem::transmute(parallel_joint_constraints.load(Ordering::Relaxed))
+                    core::mem::transmute(parallel_joint_constraints.load(Ordering::Relaxed))
                 };
 
                 enable_flush_to_zero!(); // Ensure this is enabled on each thread.
@@ -206,22 +378,26 @@ impl ParallelIslandSolver {
                     }
                 }
 
-                ThreadContext::lock_until_ge(&thread.num_integrated_bodies, active_bodies.len());
+                thread.lock_until_ge(&thread.num_integrated_bodies, active_bodies.len());
 
                 ParallelPositionSolver::solve(
                     &thread,
                     params,
                     positions,
                     parallel_contact_constraints,
-                    parallel_joint_constraints
+                    parallel_joint_constraints,
+                    user_constraints,
+                    offsets,
                 );
 
                 // Write results back to rigid bodies.
                 concurrent_loop! {
                     let batch_size = thread.batch_size;
-                    for handle in active_bodies[thread.position_writeback_index] {
+                    for handle in active_bodies[thread.position_writeback_index, thread.num_written_back_positions] {
                         let rb = &mut bodies[handle.0];
-                        rb.set_next_position(positions[rb.active_set_offset]);
+                        if rb.is_position_correction_enabled() {
+                            rb.set_next_position(positions[rb.active_set_offset]);
+                        }
                     }
                 }
             })
@@ -238,12 +414,21 @@ impl ParallelIslandSolver {
         manifold_indices: &'s [ContactManifoldIndex],
         joints: &'s mut Vec<JointGraphEdge>,
         joint_indices: &[JointIndex],
+        user_constraints: &'s mut Vec<&mut dyn UserVelocityConstraint>,
     ) {
         let num_threads = rayon::current_num_threads();
         // TODO: not sure this is the best value. Also, perhaps it is better to interleave tasks of each island?
         let num_task_per_island = num_threads; // (num_threads / num_islands).max(1);
-        // TODO: could we compute some kind of optimal value here?
-        self.thread = ThreadContext::new(8);
+
+        self.max_penetration = 0.0;
+        if params.position_solve_budget.is_some() {
+            for manifold_id in manifold_indices {
+                for contact in &manifolds[*manifold_id].data.solver_contacts {
+                    self.max_penetration = self.max_penetration.max(-contact.dist);
+                }
+            }
+        }
+
         self.parallel_groups
             .group_interactions(island_id, bodies, manifolds, manifold_indices);
         self.parallel_joint_groups
@@ -261,6 +446,26 @@ impl ParallelIslandSolver {
             &self.parallel_joint_groups,
         );
 
+        self.extra_iterations.init(bodies, joints, joint_indices);
+        let extra_iterations = &self.extra_iterations;
+        self.parallel_contact_constraints
+            .init_extra_constraints(|i| extra_iterations.of_manifold(&manifolds[i]));
+        self.parallel_joint_constraints
+            .init_extra_constraints(|i| joints[i].weight.extra_solver_iterations);
+
+        let batch_size = params.parallel_batch_size.unwrap_or_else(|| {
+            let contacts = &self.parallel_contact_constraints;
+            let joints = &self.parallel_joint_constraints;
+            ThreadContext::auto_batch_size(
+                contacts.constraint_descs.len() + joints.constraint_descs.len(),
+                contacts.parallel_desc_groups.len().saturating_sub(1)
+                    + joints.parallel_desc_groups.len().saturating_sub(1),
+                bodies.active_island(island_id).len(),
+                num_threads,
+            )
+        });
+        self.velocity_thread = ThreadContext::new(batch_size.max(1));
+
         self.mj_lambdas.clear();
         self.mj_lambdas
             .resize(bodies.active_island(island_id).len(), DeltaVel::zero());
@@ -271,31 +476,35 @@ impl ParallelIslandSolver {
         for _ in 0..num_task_per_island {
             // We use AtomicPtr because it is Send+Sync while *mut is not.
             // See https://internals.rust-lang.org/t/shouldnt-pointers-be-send-sync-or/8818
-            let thread = &self.thread;
-            let mj_lambdas = std::sync::atomic::AtomicPtr::new(&mut self.mj_lambdas as *mut _);
-            let bodies = std::sync::atomic::AtomicPtr::new(bodies as *mut _);
-            let manifolds = std::sync::atomic::AtomicPtr::new(manifolds as *mut _);
-            let joints = std::sync::atomic::AtomicPtr::new(joints as *mut _);
-            let parallel_contact_constraints =
-                std::sync::atomic::AtomicPtr::new(&mut self.parallel_contact_constraints as *mut _);
+            let thread = &self.velocity_thread;
+            let mj_lambdas = core::sync::atomic::AtomicPtr::new(&mut self.mj_lambdas as *mut _);
+            let bodies = core::sync::atomic::AtomicPtr::new(bodies as *mut _);
+            let manifolds = core::sync::atomic::AtomicPtr::new(manifolds as *mut _);
+            let joints = core::sync::atomic::AtomicPtr::new(joints as *mut _);
+            let user_constraints = core::sync::atomic::AtomicPtr::new(user_constraints as *mut _);
+            let parallel_contact_constraints = core::sync::atomic::AtomicPtr::new(
+                &mut self.parallel_contact_constraints as *mut _,
+            );
             let parallel_joint_constraints =
-                std::sync::atomic::AtomicPtr::new(&mut self.parallel_joint_constraints as *mut _);
+                core::sync::atomic::AtomicPtr::new(&mut self.parallel_joint_constraints as *mut _);
 
             scope.spawn(move |_| {
                 // Transmute *mut -> &mut
                 let mj_lambdas: &mut Vec<DeltaVel<Real>> =
-                    unsafe { std::mem::transmute(mj_lambdas.load(Ordering::Relaxed)) };
+                    unsafe { core::mem::transmute(mj_lambdas.load(Ordering::Relaxed)) };
                 let bodies: &mut RigidBodySet =
-                    unsafe { std::mem::transmute(bodies.load(Ordering::Relaxed)) };
+                    unsafe { core::mem::transmute(bodies.load(Ordering::Relaxed)) };
                 let manifolds: &mut Vec<&mut ContactManifold> =
-                    unsafe { std::mem::transmute(manifolds.load(Ordering::Relaxed)) };
+                    unsafe { core::mem::transmute(manifolds.load(Ordering::Relaxed)) };
                 let joints: &mut Vec<JointGraphEdge> =
-                    unsafe { std::mem::transmute(joints.load(Ordering::Relaxed)) };
+                    unsafe { core::mem::transmute(joints.load(Ordering::Relaxed)) };
+                let user_constraints: &mut Vec<&mut dyn UserVelocityConstraint> =
+                    unsafe { core::mem::transmute(user_constraints.load(Ordering::Relaxed)) };
                 let parallel_contact_constraints: &mut ParallelSolverConstraints<AnyVelocityConstraint, AnyPositionConstraint> = unsafe {
-                    std::mem::transmute(parallel_contact_constraints.load(Ordering::Relaxed))
+                    core::mem::transmute(parallel_contact_constraints.load(Ordering::Relaxed))
                 };
                 let parallel_joint_constraints: &mut ParallelSolverConstraints<AnyJointVelocityConstraint, AnyJointPositionConstraint> = unsafe {
-                    std::mem::transmute(parallel_joint_constraints.load(Ordering::Relaxed))
+                    core::mem::transmute(parallel_joint_constraints.load(Ordering::Relaxed))
                 };
 
                 enable_flush_to_zero!(); // Ensure this is enabled on each thread.
@@ -315,23 +524,23 @@ impl ParallelIslandSolver {
                             // NOTE: `dvel.angular` is actually storing angular velocity delta multiplied
                             //       by the square root of the inertia tensor:
                             dvel.angular += rb.effective_world_inv_inertia_sqrt * rb.torque * params.dt;
-                            dvel.linear += rb.force * (rb.effective_inv_mass * params.dt);
+                            dvel.linear += rb.force.component_mul(&rb.effective_inv_mass) * params.dt;
                         }
                     }
 
                     // We need to wait for every body to be force-integrated because their
                     // angular and linear velocities are needed by the constraints initialization.
-                    ThreadContext::lock_until_ge(&thread.num_force_integrated_bodies, active_bodies.len());
+                    thread.lock_until_ge(&thread.num_force_integrated_bodies, active_bodies.len());
                 }
 
 
                 parallel_contact_constraints.fill_constraints(&thread, params, bodies, manifolds);
                 parallel_joint_constraints.fill_constraints(&thread, params, bodies, joints);
-                ThreadContext::lock_until_ge(
+                thread.lock_until_ge(
                     &thread.num_initialized_constraints,
                     parallel_contact_constraints.constraint_descs.len(),
                 );
-                ThreadContext::lock_until_ge(
+                thread.lock_until_ge(
                     &thread.num_initialized_joint_constraints,
                     parallel_joint_constraints.constraint_descs.len(),
                 );
@@ -343,7 +552,8 @@ impl ParallelIslandSolver {
                         joints,
                         mj_lambdas,
                         parallel_contact_constraints,
-                        parallel_joint_constraints
+                        parallel_joint_constraints,
+                        user_constraints,
                 );
 
                 // Write results back to rigid bodies and integrate velocities.
@@ -2,9 +2,9 @@ use super::{AnyJointPositionConstraint, AnyPositionConstraint, ThreadContext};
 use crate::dynamics::solver::{
     AnyJointVelocityConstraint, AnyVelocityConstraint, ParallelSolverConstraints,
 };
-use crate::dynamics::IntegrationParameters;
+use crate::dynamics::{IntegrationParameters, PositionOffsetsMap, UserPositionConstraint};
 use crate::math::{Isometry, Real};
-use std::sync::atomic::Ordering;
+use core::sync::atomic::Ordering;
 
 pub(crate) struct ParallelPositionSolver;
 
@@ -21,9 +21,12 @@ impl ParallelPositionSolver {
             AnyJointVelocityConstraint,
             AnyJointPositionConstraint,
         >,
+        user_constraints: &mut [&mut dyn UserPositionConstraint],
+        offsets: &PositionOffsetsMap,
     ) {
         if contact_constraints.constraint_descs.is_empty()
             && joint_constraints.constraint_descs.is_empty()
+            && user_constraints.is_empty()
         {
             return;
         }
@@ -38,14 +41,14 @@ impl ParallelPositionSolver {
             // before starting the next one.
             let mut start_index = thread
                 .solve_position_interaction_index
-                .fetch_add(thread.batch_size, Ordering::SeqCst);
+                .fetch_add(thread.batch_size, Ordering::Relaxed);
             let mut batch_size = thread.batch_size;
             let contact_descs = &contact_constraints.constraint_descs[..];
             let joint_descs = &joint_constraints.constraint_descs[..];
             let mut target_num_desc = 0;
             let mut shift = 0;
 
-            for _ in 0..params.max_position_iterations {
+            for iteration in 0..params.max_position_iterations {
                 macro_rules! solve {
                     ($part: expr) => {
                         // Joint groups.
@@ -75,19 +78,19 @@ impl ParallelPositionSolver {
 
                                 thread
                                     .num_solved_position_interactions
-                                    .fetch_add(num_solved, Ordering::SeqCst);
+                                    .fetch_add(num_solved, Ordering::Release);
 
                                 if batch_size == 0 {
                                     start_index = thread
                                         .solve_position_interaction_index
-                                        .fetch_add(thread.batch_size, Ordering::SeqCst);
+                                        .fetch_add(thread.batch_size, Ordering::Relaxed);
                                     start_index -= shift;
                                     batch_size = thread.batch_size;
                                 } else {
                                     start_index += num_solved;
                                 }
                             }
-                            ThreadContext::lock_until_ge(
+                            thread.lock_until_ge(
                                 &thread.num_solved_position_interactions,
                                 target_num_desc,
                             );
@@ -98,6 +101,32 @@ impl ParallelPositionSolver {
                 solve!(joint_constraints);
                 shift += joint_descs.len();
                 start_index -= joint_descs.len();
+
+                if !user_constraints.is_empty() {
+                    // User constraints are solved serially by the first thread getting here.
+                    if thread
+                        .user_position_constraint_index
+                        .compare_exchange(
+                            iteration,
+                            iteration + 1,
+                            Ordering::Relaxed,
+                            Ordering::Relaxed,
+                        )
+                        .is_ok()
+                    {
+                        for constraint in user_constraints.iter_mut() {
+                            constraint.solve(params, positions, offsets);
+                        }
+
+                        thread
+                            .num_solved_user_position_constraints
+                            .fetch_add(1, Ordering::Release);
+                    }
+
+                    thread
+                        .lock_until_ge(&thread.num_solved_user_position_constraints, iteration + 1);
+                }
+
                 solve!(contact_constraints);
                 shift += contact_descs.len();
                 start_index -= contact_descs.len();
@@ -1,5 +1,7 @@
 use super::ParallelInteractionGroups;
-use super::{AnyJointVelocityConstraint, AnyVelocityConstraint, ThreadContext};
+use super::{
+    push_extra_constraints, AnyJointVelocityConstraint, AnyVelocityConstraint, ThreadContext,
+};
 use crate::dynamics::solver::categorization::{categorize_contacts, categorize_joints};
 use crate::dynamics::solver::{
     AnyJointPositionConstraint, AnyPositionConstraint, InteractionGroups, PositionConstraint,
@@ -15,7 +17,8 @@ use crate::{
     },
     math::SIMD_WIDTH,
 };
-use std::sync::atomic::Ordering;
+use alloc::vec::Vec;
+use core::sync::atomic::Ordering;
 
 // pub fn init_constraint_groups(
 //     &mut self,
@@ -50,6 +53,8 @@ pub(crate) struct ParallelSolverConstraints<VelocityConstraint, PositionConstrai
     pub position_constraints: Vec<PositionConstraint>,
     pub constraint_descs: Vec<(usize, ConstraintDesc)>,
     pub parallel_desc_groups: Vec<usize>,
+    // The velocity constraints solved during extra iterations, with their number of extra iterations.
+    pub extra_constraints: Vec<(usize, u8)>,
 }
 
 impl<VelocityConstraint, PositionConstraint>
@@ -65,6 +70,54 @@ impl<VelocityConstraint, PositionConstraint>
             position_constraints: Vec::new(),
             constraint_descs: Vec::new(),
             parallel_desc_groups: Vec::new(),
+            extra_constraints: Vec::new(),
+        }
+    }
+
+    pub fn allocated_bytes(&self) -> usize {
+        (self.not_ground_interactions.capacity()
+            + self.ground_interactions.capacity()
+            + self.parallel_desc_groups.capacity())
+            * core::mem::size_of::<usize>()
+            + self.velocity_constraints.capacity() * core::mem::size_of::<VelocityConstraint>()
+            + self.position_constraints.capacity() * core::mem::size_of::<PositionConstraint>()
+            + self.constraint_descs.capacity() * core::mem::size_of::<(usize, ConstraintDesc)>()
+            + self.extra_constraints.capacity() * core::mem::size_of::<(usize, u8)>()
+    }
+
+    /// Collects the velocity constraints needing extra iterations, given the number of
+    /// extra iterations of each interaction.
+    ///
+    /// This must be called after `self.init_constraint_groups`.
+    pub fn init_extra_constraints(&mut self, extra_iterations: impl Fn(usize) -> u8) {
+        self.extra_constraints.clear();
+
+        for (i, (first_constraint, desc)) in self.constraint_descs.iter().enumerate() {
+            let last_constraint = self
+                .constraint_descs
+                .get(i + 1)
+                .map(|desc| desc.0)
+                .unwrap_or(self.velocity_constraints.len());
+            let num_extra_iterations = match desc {
+                ConstraintDesc::NongroundNongrouped(interaction_i)
+                | ConstraintDesc::GroundNongrouped(interaction_i) => {
+                    extra_iterations(*interaction_i)
+                }
+                #[cfg(feature = "simd-is-enabled")]
+                ConstraintDesc::NongroundGrouped(interactions_i)
+                | ConstraintDesc::GroundGrouped(interactions_i) => interactions_i
+                    .iter()
+                    .map(|i| extra_iterations(*i))
+                    .max()
+                    .unwrap_or(0),
+            };
+
+            push_extra_constraints(
+                &mut self.extra_constraints,
+                *first_constraint,
+                last_constraint,
+                num_extra_iterations,
+            );
         }
     }
 }
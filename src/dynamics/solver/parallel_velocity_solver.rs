@@ -1,11 +1,16 @@
-use super::{AnyJointVelocityConstraint, AnyVelocityConstraint, DeltaVel, ThreadContext};
+use super::{
+    solve_extra_iterations, AnyJointVelocityConstraint, AnyVelocityConstraint, DeltaVel,
+    ThreadContext,
+};
 use crate::dynamics::solver::{
     AnyJointPositionConstraint, AnyPositionConstraint, ParallelSolverConstraints,
 };
-use crate::dynamics::{IntegrationParameters, JointGraphEdge};
+use crate::dynamics::{
+    IntegrationParameters, JointGraphEdge, RestitutionModel, UserVelocityConstraint,
+};
 use crate::geometry::ContactManifold;
 use crate::math::Real;
-use std::sync::atomic::Ordering;
+use core::sync::atomic::Ordering;
 
 pub(crate) struct ParallelVelocitySolver {}
 
@@ -24,13 +29,43 @@ impl ParallelVelocitySolver {
             AnyJointVelocityConstraint,
             AnyJointPositionConstraint,
         >,
+        user_constraints: &mut [&mut dyn UserVelocityConstraint],
     ) {
         if contact_constraints.constraint_descs.is_empty()
             && joint_constraints.constraint_descs.is_empty()
+            && user_constraints.is_empty()
         {
             return;
         }
 
+        // User constraints are processed serially by the first thread getting to each
+        // of their slots: slot 0 warmstarts them (they are initialized before the
+        // parallel resolution), slot `i + 1` solves them
+        // at the velocity iteration `i`, and the last slot writes their impulses back.
+        macro_rules! user_slot(
+            ($slot: expr, |$constraint: ident| $body: expr) => {
+                if !user_constraints.is_empty() {
+                    if thread
+                        .user_velocity_constraint_index
+                        .compare_exchange($slot, $slot + 1, Ordering::Relaxed, Ordering::Relaxed)
+                        .is_ok()
+                    {
+                        for $constraint in user_constraints.iter_mut() {
+                            $body;
+                        }
+
+                        thread
+                            .num_solved_user_velocity_constraints
+                            .fetch_add(1, Ordering::Release);
+                    }
+
+                    thread.lock_until_ge(&thread.num_solved_user_velocity_constraints, $slot + 1);
+                }
+            }
+        );
+
+        user_slot!(0, |constraint| constraint.warmstart(mj_lambdas));
+
         /*
          * Warmstart constraints.
          */
@@ -42,7 +77,7 @@ impl ParallelVelocitySolver {
             let mut target_num_desc = 0;
             let mut start_index = thread
                 .warmstart_contact_index
-                .fetch_add(thread.batch_size, Ordering::SeqCst);
+                .fetch_add(thread.batch_size, Ordering::Relaxed);
             let mut batch_size = thread.batch_size;
             let mut shift = 0;
 
@@ -70,12 +105,12 @@ impl ParallelVelocitySolver {
 
                             thread
                                 .num_warmstarted_contacts
-                                .fetch_add(num_solved, Ordering::SeqCst);
+                                .fetch_add(num_solved, Ordering::Release);
 
                             if batch_size == 0 {
                                 start_index = thread
                                     .warmstart_contact_index
-                                    .fetch_add(thread.batch_size, Ordering::SeqCst);
+                                    .fetch_add(thread.batch_size, Ordering::Relaxed);
                                 start_index -= shift;
                                 batch_size = thread.batch_size;
                             } else {
@@ -83,7 +118,7 @@ impl ParallelVelocitySolver {
                             }
                         }
 
-                        ThreadContext::lock_until_ge(&thread.num_warmstarted_contacts, target_num_desc);
+                        thread.lock_until_ge(&thread.num_warmstarted_contacts, target_num_desc);
                     }
                 }
             );
@@ -104,80 +139,113 @@ impl ParallelVelocitySolver {
             // before starting the next one.
             let mut start_index = thread
                 .solve_interaction_index
-                .fetch_add(thread.batch_size, Ordering::SeqCst);
+                .fetch_add(thread.batch_size, Ordering::Relaxed);
             let mut batch_size = thread.batch_size;
             let contact_descs = &contact_constraints.constraint_descs[..];
             let joint_descs = &joint_constraints.constraint_descs[..];
             let mut target_num_desc = 0;
             let mut shift = 0;
 
-            for _ in 0..params.max_velocity_iterations {
-                macro_rules! solve {
-                    ($part: expr) => {
-                        // Joint groups.
-                        for group in $part.parallel_desc_groups.windows(2) {
-                            let num_descs_in_group = group[1] - group[0];
-
-                            target_num_desc += num_descs_in_group;
-
-                            while start_index < group[1] {
-                                let end_index = (start_index + batch_size).min(group[1]);
-
-                                let constraints = if end_index == $part.constraint_descs.len() {
-                                    &mut $part.velocity_constraints
-                                        [$part.constraint_descs[start_index].0..]
-                                } else {
-                                    &mut $part.velocity_constraints[$part.constraint_descs
-                                        [start_index]
-                                        .0
-                                        ..$part.constraint_descs[end_index].0]
-                                };
-
-                                //                                println!(
-                                //                                    "Solving a constraint {:?}.",
-                                //                                    rayon::current_thread_index()
-                                //                                );
-                                for constraint in constraints {
-                                    constraint.solve(mj_lambdas);
-                                }
-
-                                let num_solved = end_index - start_index;
-                                batch_size -= num_solved;
-
-                                thread
-                                    .num_solved_interactions
-                                    .fetch_add(num_solved, Ordering::SeqCst);
-
-                                if batch_size == 0 {
-                                    start_index = thread
-                                        .solve_interaction_index
-                                        .fetch_add(thread.batch_size, Ordering::SeqCst);
-                                    start_index -= shift;
-                                    batch_size = thread.batch_size;
-                                } else {
-                                    start_index += num_solved;
-                                }
+            macro_rules! solve {
+                ($part: expr, $solve: ident) => {
+                    // Joint groups.
+                    for group in $part.parallel_desc_groups.windows(2) {
+                        let num_descs_in_group = group[1] - group[0];
+
+                        target_num_desc += num_descs_in_group;
+
+                        while start_index < group[1] {
+                            let end_index = (start_index + batch_size).min(group[1]);
+
+                            let constraints = if end_index == $part.constraint_descs.len() {
+                                &mut $part.velocity_constraints
+                                    [$part.constraint_descs[start_index].0..]
+                            } else {
+                                &mut $part.velocity_constraints[$part.constraint_descs
+                                    [start_index]
+                                    .0
+                                    ..$part.constraint_descs[end_index].0]
+                            };
+
+                            //                                println!(
+                            //                                    "Solving a constraint {:?}.",
+                            //                                    rayon::current_thread_index()
+                            //                                );
+                            for constraint in constraints {
+                                constraint.$solve(mj_lambdas);
+                            }
+
+                            let num_solved = end_index - start_index;
+                            batch_size -= num_solved;
+
+                            thread
+                                .num_solved_interactions
+                                .fetch_add(num_solved, Ordering::Release);
+
+                            if batch_size == 0 {
+                                start_index = thread
+                                    .solve_interaction_index
+                                    .fetch_add(thread.batch_size, Ordering::Relaxed);
+                                start_index -= shift;
+                                batch_size = thread.batch_size;
+                            } else {
+                                start_index += num_solved;
                             }
-                            ThreadContext::lock_until_ge(
-                                &thread.num_solved_interactions,
-                                target_num_desc,
-                            );
                         }
-                    };
-                }
+                        thread.lock_until_ge(&thread.num_solved_interactions, target_num_desc);
+                    }
+                };
+            }
 
-                solve!(joint_constraints);
+            for iteration in 0..params.max_velocity_iterations {
+                solve!(joint_constraints, solve);
                 shift += joint_descs.len();
                 start_index -= joint_descs.len();
-                solve!(contact_constraints);
+                user_slot!(iteration + 1, |constraint| constraint.solve(mj_lambdas));
+                solve!(contact_constraints, solve);
                 shift += contact_descs.len();
                 start_index -= contact_descs.len();
             }
+
+            // The extra iterations only involve a few constraints: they are
+            // solved serially by the first thread to get here.
+            if !joint_constraints.extra_constraints.is_empty() {
+                if thread
+                    .extra_iterations_index
+                    .fetch_add(1, Ordering::Relaxed)
+                    == 0
+                {
+                    solve_extra_iterations(
+                        mj_lambdas,
+                        &mut joint_constraints.velocity_constraints,
+                        &joint_constraints.extra_constraints,
+                        &mut contact_constraints.velocity_constraints,
+                        &contact_constraints.extra_constraints,
+                    );
+                    thread
+                        .num_solved_extra_iterations
+                        .fetch_add(1, Ordering::Release);
+                }
+
+                thread.lock_until_ge(&thread.num_solved_extra_iterations, 1);
+            }
+
+            if params.restitution_model == RestitutionModel::PostSolve {
+                for _ in 0..params.max_velocity_iterations {
+                    solve!(contact_constraints, solve_restitution);
+                    shift += contact_descs.len();
+                    start_index -= contact_descs.len();
+                }
+            }
         }
 
         /*
          * Writeback impulses.
          */
+        user_slot!(params.max_velocity_iterations + 1, |constraint| {
+            constraint.writeback()
+        });
+
         let joint_constraints = &joint_constraints.velocity_constraints;
         let contact_constraints = &contact_constraints.velocity_constraints;
 
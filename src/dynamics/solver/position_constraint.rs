@@ -7,6 +7,7 @@ use crate::math::{
     AngularInertia, Isometry, Point, Real, Rotation, Translation, Vector, MAX_MANIFOLD_POINTS,
 };
 use crate::utils::{WAngularInertia, WCross, WDot};
+use alloc::vec::Vec;
 
 pub(crate) enum AnyPositionConstraint {
     #[cfg(feature = "simd-is-enabled")]
@@ -43,8 +44,8 @@ pub(crate) struct PositionConstraint {
     pub dists: [Real; MAX_MANIFOLD_POINTS],
     pub local_n1: Vector<Real>,
     pub num_contacts: u8,
-    pub im1: Real,
-    pub im2: Real,
+    pub im1: Vector<Real>,
+    pub im2: Vector<Real>,
     pub ii1: AngularInertia<Real>,
     pub ii2: AngularInertia<Real>,
     pub erp: Real,
@@ -79,7 +80,7 @@ impl PositionConstraint {
                 local_p2[l] = rb2
                     .position
                     .inverse_transform_point(&manifold_points[l].point);
-                dists[l] = manifold_points[l].dist;
+                dists[l] = manifold_points[l].dist_from_target();
             }
 
             let constraint = PositionConstraint {
@@ -94,7 +95,7 @@ impl PositionConstraint {
                 ii1: rb1.effective_world_inv_inertia_sqrt.squared(),
                 ii2: rb2.effective_world_inv_inertia_sqrt.squared(),
                 num_contacts: manifold_points.len() as u8,
-                erp: params.erp,
+                erp: params.erp * manifold.data.erp_multiplier,
                 max_linear_correction: params.max_linear_correction,
             };
 
@@ -134,13 +135,15 @@ impl PositionConstraint {
                 let ii_gcross2 = self.ii2.transform_vector(gcross2);
 
                 // Compute impulse.
-                let inv_r =
-                    self.im1 + self.im2 + gcross1.gdot(ii_gcross1) + gcross2.gdot(ii_gcross2);
-                let impulse = err / inv_r;
+                let inv_r = n1.dot(&(self.im1 + self.im2).component_mul(&n1))
+                    + gcross1.gdot(ii_gcross1)
+                    + gcross2.gdot(ii_gcross2);
+                // The translations and rotations of the bodies may be locked.
+                let impulse = if inv_r != 0.0 { err / inv_r } else { 0.0 };
 
                 // Apply impulse.
-                let tra1 = Translation::from(n1 * (impulse * self.im1));
-                let tra2 = Translation::from(n1 * (-impulse * self.im2));
+                let tra1 = Translation::from(n1.component_mul(&self.im1) * impulse);
+                let tra2 = Translation::from(n1.component_mul(&self.im2) * -impulse);
                 let rot1 = Rotation::new(ii_gcross1 * impulse);
                 let rot2 = Rotation::new(ii_gcross2 * impulse);
 
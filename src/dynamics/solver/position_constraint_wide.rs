@@ -7,6 +7,7 @@ use crate::math::{
 };
 use crate::utils::{WAngularInertia, WCross, WDot};
 
+use alloc::vec::Vec;
 use num::Zero;
 use simba::simd::{SimdBool as _, SimdPartialOrd, SimdValue};
 
@@ -18,8 +19,8 @@ pub(crate) struct WPositionConstraint {
     pub local_p2: [Point<SimdReal>; MAX_MANIFOLD_POINTS],
     pub dists: [SimdReal; MAX_MANIFOLD_POINTS],
     pub local_n1: Vector<SimdReal>,
-    pub im1: SimdReal,
-    pub im2: SimdReal,
+    pub im1: Vector<SimdReal>,
+    pub im2: Vector<SimdReal>,
     pub ii1: AngularInertia<SimdReal>,
     pub ii2: AngularInertia<SimdReal>,
     pub erp: SimdReal,
@@ -38,11 +39,11 @@ impl WPositionConstraint {
         let rbs1 = array![|ii| bodies.get(manifolds[ii].data.body_pair.body1).unwrap(); SIMD_WIDTH];
         let rbs2 = array![|ii| bodies.get(manifolds[ii].data.body_pair.body2).unwrap(); SIMD_WIDTH];
 
-        let im1 = SimdReal::from(array![|ii| rbs1[ii].effective_inv_mass; SIMD_WIDTH]);
+        let im1 = Vector::from(array![|ii| rbs1[ii].effective_inv_mass; SIMD_WIDTH]);
         let sqrt_ii1: AngularInertia<SimdReal> = AngularInertia::from(
             array![|ii| rbs1[ii].effective_world_inv_inertia_sqrt; SIMD_WIDTH],
         );
-        let im2 = SimdReal::from(array![|ii| rbs2[ii].effective_inv_mass; SIMD_WIDTH]);
+        let im2 = Vector::from(array![|ii| rbs2[ii].effective_inv_mass; SIMD_WIDTH]);
         let sqrt_ii2: AngularInertia<SimdReal> = AngularInertia::from(
             array![|ii| rbs2[ii].effective_world_inv_inertia_sqrt; SIMD_WIDTH],
         );
@@ -61,7 +62,7 @@ impl WPositionConstraint {
 
         for l in (0..num_active_contacts).step_by(MAX_MANIFOLD_POINTS) {
             let manifold_points = array![|ii| &manifolds[ii].data.solver_contacts[l..]; SIMD_WIDTH];
-            let num_points = manifold_points[0].len().min(MAX_MANIFOLD_POINTS);
+            let num_points = super::max_lane_contacts(&manifold_points);
 
             let mut constraint = WPositionConstraint {
                 rb1,
@@ -74,14 +75,23 @@ impl WPositionConstraint {
                 im2,
                 ii1: sqrt_ii1.squared(),
                 ii2: sqrt_ii2.squared(),
-                erp: SimdReal::splat(params.erp),
+                erp: SimdReal::from(array![|ii| manifolds[ii].data.erp_multiplier; SIMD_WIDTH])
+                    * SimdReal::splat(params.erp),
                 max_linear_correction: SimdReal::splat(params.max_linear_correction),
                 num_contacts: num_points as u8,
             };
 
             for i in 0..num_points {
-                let point = Point::from(array![|ii| manifold_points[ii][i].point; SIMD_WIDTH]);
-                let dist = SimdReal::from(array![|ii| manifold_points[ii][i].dist; SIMD_WIDTH]);
+                let (contacts, _) = super::select_padded_contacts(&manifold_points, i);
+                let point = Point::from(array![|ii| contacts[ii].point; SIMD_WIDTH]);
+                // NOTE: the padded contacts are given an infinite separation
+                //       distance so they never result in any position correction.
+                let dist = SimdReal::from(array![|ii|
+                    if i < manifold_points[ii].len() {
+                        contacts[ii].dist_from_target()
+                    } else {
+                        Real::MAX
+                    }; SIMD_WIDTH]);
                 constraint.local_p1[i] = pos1.inverse_transform_point(&point);
                 constraint.local_p2[i] = pos2.inverse_transform_point(&point);
                 constraint.dists[i] = dist;
@@ -126,14 +136,19 @@ impl WPositionConstraint {
                 let ii_gcross2 = self.ii2.transform_vector(gcross2);
 
                 // Compute impulse.
-                let inv_r =
-                    self.im1 + self.im2 + gcross1.gdot(ii_gcross1) + gcross2.gdot(ii_gcross2);
-                let impulse = err / inv_r;
+                let inv_r = n1.dot(&(self.im1 + self.im2).component_mul(&n1))
+                    + gcross1.gdot(ii_gcross1)
+                    + gcross2.gdot(ii_gcross2);
+                // The translations and rotations of the bodies may be locked.
+                let impulse =
+                    (err / inv_r).select(inv_r.simd_gt(SimdReal::zero()), SimdReal::zero());
 
                 // Apply impulse.
-                pos1.translation = Translation::from(n1 * (impulse * self.im1)) * pos1.translation;
+                pos1.translation =
+                    Translation::from(n1.component_mul(&self.im1) * impulse) * pos1.translation;
                 pos1.rotation = Rotation::new(ii_gcross1 * impulse) * pos1.rotation;
-                pos2.translation = Translation::from(n1 * (-impulse * self.im2)) * pos2.translation;
+                pos2.translation =
+                    Translation::from(n1.component_mul(&self.im2) * -impulse) * pos2.translation;
                 pos2.rotation = Rotation::new(ii_gcross2 * impulse) * pos2.rotation;
             }
         }
@@ -5,6 +5,7 @@ use crate::math::{
     AngularInertia, Isometry, Point, Real, Rotation, Translation, Vector, MAX_MANIFOLD_POINTS,
 };
 use crate::utils::{WAngularInertia, WCross, WDot};
+use alloc::vec::Vec;
 
 pub(crate) struct PositionGroundConstraint {
     pub rb2: usize,
@@ -14,7 +15,7 @@ pub(crate) struct PositionGroundConstraint {
     pub dists: [Real; MAX_MANIFOLD_POINTS],
     pub n1: Vector<Real>,
     pub num_contacts: u8,
-    pub im2: Real,
+    pub im2: Vector<Real>,
     pub ii2: AngularInertia<Real>,
     pub erp: Real,
     pub max_linear_correction: Real,
@@ -33,7 +34,7 @@ impl PositionGroundConstraint {
         let flip = manifold.data.relative_dominance < 0;
 
         let n1 = if flip {
-            std::mem::swap(&mut rb1, &mut rb2);
+            core::mem::swap(&mut rb1, &mut rb2);
             -manifold.data.normal
         } else {
             manifold.data.normal
@@ -54,7 +55,7 @@ impl PositionGroundConstraint {
                 local_p2[k] = rb2
                     .position
                     .inverse_transform_point(&manifold_contacts[k].point);
-                dists[k] = manifold_contacts[k].dist;
+                dists[k] = manifold_contacts[k].dist_from_target();
             }
 
             let constraint = PositionGroundConstraint {
@@ -66,7 +67,7 @@ impl PositionGroundConstraint {
                 im2: rb2.effective_inv_mass,
                 ii2: rb2.effective_world_inv_inertia_sqrt.squared(),
                 num_contacts: manifold_contacts.len() as u8,
-                erp: params.erp,
+                erp: params.erp * manifold.data.erp_multiplier,
                 max_linear_correction: params.max_linear_correction,
             };
 
@@ -101,11 +102,12 @@ impl PositionGroundConstraint {
                 let ii_gcross2 = self.ii2.transform_vector(gcross2);
 
                 // Compute impulse.
-                let inv_r = self.im2 + gcross2.gdot(ii_gcross2);
-                let impulse = err / inv_r;
+                let inv_r = n1.dot(&self.im2.component_mul(&n1)) + gcross2.gdot(ii_gcross2);
+                // The translations and rotations of the body may be locked.
+                let impulse = if inv_r != 0.0 { err / inv_r } else { 0.0 };
 
                 // Apply impulse.
-                let tra2 = Translation::from(n1 * (-impulse * self.im2));
+                let tra2 = Translation::from(n1.component_mul(&self.im2) * -impulse);
                 let rot2 = Rotation::new(ii_gcross2 * impulse);
                 pos2 = Isometry::from_parts(tra2 * pos2.translation, rot2 * pos2.rotation);
             }
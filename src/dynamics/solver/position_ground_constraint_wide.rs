@@ -7,6 +7,7 @@ use crate::math::{
 };
 use crate::utils::{WAngularInertia, WCross, WDot};
 
+use alloc::vec::Vec;
 use num::Zero;
 use simba::simd::{SimdBool as _, SimdPartialOrd, SimdValue};
 
@@ -17,7 +18,7 @@ pub(crate) struct WPositionGroundConstraint {
     pub local_p2: [Point<SimdReal>; MAX_MANIFOLD_POINTS],
     pub dists: [SimdReal; MAX_MANIFOLD_POINTS],
     pub n1: Vector<SimdReal>,
-    pub im2: SimdReal,
+    pub im2: Vector<SimdReal>,
     pub ii2: AngularInertia<SimdReal>,
     pub erp: SimdReal,
     pub max_linear_correction: SimdReal,
@@ -41,11 +42,11 @@ impl WPositionGroundConstraint {
         for ii in 0..SIMD_WIDTH {
             if manifolds[ii].data.relative_dominance < 0 {
                 flipped[ii] = true;
-                std::mem::swap(&mut rbs1[ii], &mut rbs2[ii]);
+                core::mem::swap(&mut rbs1[ii], &mut rbs2[ii]);
             }
         }
 
-        let im2 = SimdReal::from(array![|ii| rbs2[ii].effective_inv_mass; SIMD_WIDTH]);
+        let im2 = Vector::from(array![|ii| rbs2[ii].effective_inv_mass; SIMD_WIDTH]);
         let sqrt_ii2: AngularInertia<SimdReal> = AngularInertia::from(
             array![|ii| rbs2[ii].effective_world_inv_inertia_sqrt; SIMD_WIDTH],
         );
@@ -61,7 +62,7 @@ impl WPositionGroundConstraint {
 
         for l in (0..num_active_contacts).step_by(MAX_MANIFOLD_POINTS) {
             let manifold_points = array![|ii| &manifolds[ii].data.solver_contacts[l..]; SIMD_WIDTH];
-            let num_points = manifold_points[0].len().min(MAX_MANIFOLD_POINTS);
+            let num_points = super::max_lane_contacts(&manifold_points);
 
             let mut constraint = WPositionGroundConstraint {
                 rb2,
@@ -71,14 +72,23 @@ impl WPositionGroundConstraint {
                 dists: [SimdReal::zero(); MAX_MANIFOLD_POINTS],
                 im2,
                 ii2: sqrt_ii2.squared(),
-                erp: SimdReal::splat(params.erp),
+                erp: SimdReal::from(array![|ii| manifolds[ii].data.erp_multiplier; SIMD_WIDTH])
+                    * SimdReal::splat(params.erp),
                 max_linear_correction: SimdReal::splat(params.max_linear_correction),
                 num_contacts: num_points as u8,
             };
 
             for i in 0..num_points {
-                let point = Point::from(array![|ii| manifold_points[ii][i].point; SIMD_WIDTH]);
-                let dist = SimdReal::from(array![|ii| manifold_points[ii][i].dist; SIMD_WIDTH]);
+                let (contacts, _) = super::select_padded_contacts(&manifold_points, i);
+                let point = Point::from(array![|ii| contacts[ii].point; SIMD_WIDTH]);
+                // NOTE: the padded contacts are given an infinite separation
+                //       distance so they never result in any position correction.
+                let dist = SimdReal::from(array![|ii|
+                    if i < manifold_points[ii].len() {
+                        contacts[ii].dist_from_target()
+                    } else {
+                        Real::MAX
+                    }; SIMD_WIDTH]);
                 constraint.p1[i] = point;
                 constraint.local_p2[i] = pos2.inverse_transform_point(&point);
                 constraint.dists[i] = dist;
@@ -117,11 +127,14 @@ impl WPositionGroundConstraint {
                 let ii_gcross2 = self.ii2.transform_vector(gcross2);
 
                 // Compute impulse.
-                let inv_r = self.im2 + gcross2.gdot(ii_gcross2);
-                let impulse = err / inv_r;
+                let inv_r = n1.dot(&self.im2.component_mul(&n1)) + gcross2.gdot(ii_gcross2);
+                // The translations and rotations of the body may be locked.
+                let impulse =
+                    (err / inv_r).select(inv_r.simd_gt(SimdReal::zero()), SimdReal::zero());
 
                 // Apply impulse.
-                pos2.translation = Translation::from(n1 * (-impulse * self.im2)) * pos2.translation;
+                pos2.translation =
+                    Translation::from(n1.component_mul(&self.im2) * -impulse) * pos2.translation;
                 pos2.rotation = Rotation::new(ii_gcross2 * impulse) * pos2.rotation;
             }
         }
@@ -1,6 +1,10 @@
 use super::AnyJointPositionConstraint;
-use crate::dynamics::{solver::AnyPositionConstraint, IntegrationParameters, RigidBodySet};
+use crate::dynamics::{
+    solver::AnyPositionConstraint, IntegrationParameters, PositionOffsetsMap, RigidBodySet,
+    UserPositionConstraint,
+};
 use crate::math::{Isometry, Real};
+use alloc::vec::Vec;
 
 pub(crate) struct PositionSolver {
     positions: Vec<Isometry<Real>>,
@@ -13,6 +17,10 @@ impl PositionSolver {
         }
     }
 
+    pub fn allocated_bytes(&self) -> usize {
+        self.positions.capacity() * core::mem::size_of::<Isometry<Real>>()
+    }
+
     pub fn solve(
         &mut self,
         island_id: usize,
@@ -20,8 +28,13 @@ impl PositionSolver {
         bodies: &mut RigidBodySet,
         contact_constraints: &[AnyPositionConstraint],
         joint_constraints: &[AnyJointPositionConstraint],
+        user_constraints: &mut [&mut dyn UserPositionConstraint],
+        offsets: &PositionOffsetsMap,
     ) {
-        if contact_constraints.is_empty() && joint_constraints.is_empty() {
+        if contact_constraints.is_empty()
+            && joint_constraints.is_empty()
+            && user_constraints.is_empty()
+        {
             // Nothing to do.
             return;
         }
@@ -38,13 +51,19 @@ impl PositionSolver {
                 constraint.solve(params, &mut self.positions)
             }
 
+            for constraint in &mut *user_constraints {
+                constraint.solve(params, &mut self.positions, offsets)
+            }
+
             for constraint in contact_constraints {
                 constraint.solve(params, &mut self.positions)
             }
         }
 
         bodies.foreach_active_island_body_mut_internal(island_id, |_, rb| {
-            rb.set_next_position(self.positions[rb.active_set_offset])
+            if rb.is_position_correction_enabled() {
+                rb.set_next_position(self.positions[rb.active_set_offset])
+            }
         });
     }
 }
@@ -1,5 +1,6 @@
 use super::{
-    AnyJointVelocityConstraint, InteractionGroups, VelocityConstraint, VelocityGroundConstraint,
+    priority_runs, push_extra_constraints, AnyJointVelocityConstraint, ExtraIterations,
+    InteractionGroups, VelocityConstraint, VelocityGroundConstraint,
 };
 #[cfg(feature = "simd-is-enabled")]
 use super::{
@@ -15,6 +16,7 @@ use crate::dynamics::{
 use crate::geometry::{ContactManifold, ContactManifoldIndex};
 #[cfg(feature = "simd-is-enabled")]
 use crate::math::SIMD_WIDTH;
+use alloc::vec::Vec;
 
 pub(crate) struct SolverConstraints<VelocityConstraint, PositionConstraint> {
     pub not_ground_interactions: Vec<usize>,
@@ -23,6 +25,8 @@ pub(crate) struct SolverConstraints<VelocityConstraint, PositionConstraint> {
     pub ground_interaction_groups: InteractionGroups,
     pub velocity_constraints: Vec<VelocityConstraint>,
     pub position_constraints: Vec<PositionConstraint>,
+    // The velocity constraints solved during extra iterations, with their number of extra iterations.
+    pub extra_constraints: Vec<(usize, u8)>,
 }
 
 impl<VelocityConstraint, PositionConstraint>
@@ -36,9 +40,18 @@ impl<VelocityConstraint, PositionConstraint>
             ground_interaction_groups: InteractionGroups::new(),
             velocity_constraints: Vec::new(),
             position_constraints: Vec::new(),
+            extra_constraints: Vec::new(),
         }
     }
 
+    pub fn allocated_bytes(&self) -> usize {
+        (self.not_ground_interactions.capacity() + self.ground_interactions.capacity())
+            * core::mem::size_of::<usize>()
+            + self.velocity_constraints.capacity() * core::mem::size_of::<VelocityConstraint>()
+            + self.position_constraints.capacity() * core::mem::size_of::<PositionConstraint>()
+            + self.extra_constraints.capacity() * core::mem::size_of::<(usize, u8)>()
+    }
+
     pub fn clear(&mut self) {
         self.not_ground_interactions.clear();
         self.ground_interactions.clear();
@@ -46,6 +59,7 @@ impl<VelocityConstraint, PositionConstraint>
         self.ground_interaction_groups.clear();
         self.velocity_constraints.clear();
         self.position_constraints.clear();
+        self.extra_constraints.clear();
     }
 }
 
@@ -99,22 +113,33 @@ impl SolverConstraints<AnyVelocityConstraint, AnyPositionConstraint> {
         bodies: &RigidBodySet,
         manifolds: &[&mut ContactManifold],
         manifold_indices: &[ContactManifoldIndex],
+        extra_iterations: &ExtraIterations,
     ) {
         self.velocity_constraints.clear();
         self.position_constraints.clear();
+        self.extra_constraints.clear();
 
-        self.init_constraint_groups(island_id, bodies, manifolds, manifold_indices);
+        // The manifold indices are sorted by solver priority. The constraints of each
+        // priority are generated, and therefore solved, after the ones of lower priority.
+        for run in priority_runs(bodies, manifolds, manifold_indices) {
+            self.init_constraint_groups(island_id, bodies, manifolds, run);
 
-        #[cfg(feature = "simd-is-enabled")]
-        {
-            self.compute_grouped_constraints(params, bodies, manifolds);
-        }
-        self.compute_nongrouped_constraints(params, bodies, manifolds);
-        #[cfg(feature = "simd-is-enabled")]
-        {
-            self.compute_grouped_ground_constraints(params, bodies, manifolds);
+            #[cfg(feature = "simd-is-enabled")]
+            {
+                self.compute_grouped_constraints(params, bodies, manifolds, extra_iterations);
+            }
+            self.compute_nongrouped_constraints(params, bodies, manifolds, extra_iterations);
+            #[cfg(feature = "simd-is-enabled")]
+            {
+                self.compute_grouped_ground_constraints(
+                    params,
+                    bodies,
+                    manifolds,
+                    extra_iterations,
+                );
+            }
+            self.compute_nongrouped_ground_constraints(params, bodies, manifolds, extra_iterations);
         }
-        self.compute_nongrouped_ground_constraints(params, bodies, manifolds);
     }
 
     #[cfg(feature = "simd-is-enabled")]
@@ -123,6 +148,7 @@ impl SolverConstraints<AnyVelocityConstraint, AnyPositionConstraint> {
         params: &IntegrationParameters,
         bodies: &RigidBodySet,
         manifolds_all: &[&mut ContactManifold],
+        extra_iterations: &ExtraIterations,
     ) {
         for manifolds_i in self
             .interaction_groups
@@ -131,6 +157,7 @@ impl SolverConstraints<AnyVelocityConstraint, AnyPositionConstraint> {
         {
             let manifold_id = array![|ii| manifolds_i[ii]; SIMD_WIDTH];
             let manifolds = array![|ii| &*manifolds_all[manifolds_i[ii]]; SIMD_WIDTH];
+            let first_constraint = self.velocity_constraints.len();
             WVelocityConstraint::generate(
                 params,
                 manifold_id,
@@ -139,6 +166,16 @@ impl SolverConstraints<AnyVelocityConstraint, AnyPositionConstraint> {
                 &mut self.velocity_constraints,
                 true,
             );
+            push_extra_constraints(
+                &mut self.extra_constraints,
+                first_constraint,
+                self.velocity_constraints.len(),
+                manifolds
+                    .iter()
+                    .map(|m| extra_iterations.of_manifold(m))
+                    .max()
+                    .unwrap_or(0),
+            );
             WPositionConstraint::generate(
                 params,
                 manifolds,
@@ -154,9 +191,11 @@ impl SolverConstraints<AnyVelocityConstraint, AnyPositionConstraint> {
         params: &IntegrationParameters,
         bodies: &RigidBodySet,
         manifolds_all: &[&mut ContactManifold],
+        extra_iterations: &ExtraIterations,
     ) {
         for manifold_i in &self.interaction_groups.nongrouped_interactions {
             let manifold = &manifolds_all[*manifold_i];
+            let first_constraint = self.velocity_constraints.len();
             VelocityConstraint::generate(
                 params,
                 *manifold_i,
@@ -165,6 +204,12 @@ impl SolverConstraints<AnyVelocityConstraint, AnyPositionConstraint> {
                 &mut self.velocity_constraints,
                 true,
             );
+            push_extra_constraints(
+                &mut self.extra_constraints,
+                first_constraint,
+                self.velocity_constraints.len(),
+                extra_iterations.of_manifold(manifold),
+            );
             PositionConstraint::generate(
                 params,
                 manifold,
@@ -181,6 +226,7 @@ impl SolverConstraints<AnyVelocityConstraint, AnyPositionConstraint> {
         params: &IntegrationParameters,
         bodies: &RigidBodySet,
         manifolds_all: &[&mut ContactManifold],
+        extra_iterations: &ExtraIterations,
     ) {
         for manifolds_i in self
             .ground_interaction_groups
@@ -189,6 +235,7 @@ impl SolverConstraints<AnyVelocityConstraint, AnyPositionConstraint> {
         {
             let manifold_id = array![|ii| manifolds_i[ii]; SIMD_WIDTH];
             let manifolds = array![|ii| &*manifolds_all[manifolds_i[ii]]; SIMD_WIDTH];
+            let first_constraint = self.velocity_constraints.len();
             WVelocityGroundConstraint::generate(
                 params,
                 manifold_id,
@@ -197,6 +244,16 @@ impl SolverConstraints<AnyVelocityConstraint, AnyPositionConstraint> {
                 &mut self.velocity_constraints,
                 true,
             );
+            push_extra_constraints(
+                &mut self.extra_constraints,
+                first_constraint,
+                self.velocity_constraints.len(),
+                manifolds
+                    .iter()
+                    .map(|m| extra_iterations.of_manifold(m))
+                    .max()
+                    .unwrap_or(0),
+            );
             WPositionGroundConstraint::generate(
                 params,
                 manifolds,
@@ -212,9 +269,11 @@ impl SolverConstraints<AnyVelocityConstraint, AnyPositionConstraint> {
         params: &IntegrationParameters,
         bodies: &RigidBodySet,
         manifolds_all: &[&mut ContactManifold],
+        extra_iterations: &ExtraIterations,
     ) {
         for manifold_i in &self.ground_interaction_groups.nongrouped_interactions {
             let manifold = &manifolds_all[*manifold_i];
+            let first_constraint = self.velocity_constraints.len();
             VelocityGroundConstraint::generate(
                 params,
                 *manifold_i,
@@ -223,6 +282,12 @@ impl SolverConstraints<AnyVelocityConstraint, AnyPositionConstraint> {
                 &mut self.velocity_constraints,
                 true,
             );
+            push_extra_constraints(
+                &mut self.extra_constraints,
+                first_constraint,
+                self.velocity_constraints.len(),
+                extra_iterations.of_manifold(manifold),
+            );
             PositionGroundConstraint::generate(
                 params,
                 manifold,
@@ -235,15 +300,13 @@ impl SolverConstraints<AnyVelocityConstraint, AnyPositionConstraint> {
 }
 
 impl SolverConstraints<AnyJointVelocityConstraint, AnyJointPositionConstraint> {
-    pub fn init(
+    pub fn init_constraint_groups(
         &mut self,
         island_id: usize,
-        params: &IntegrationParameters,
         bodies: &RigidBodySet,
         joints: &[JointGraphEdge],
         joint_constraint_indices: &[JointIndex],
     ) {
-        // Generate constraints for joints.
         self.not_ground_interactions.clear();
         self.ground_interactions.clear();
         categorize_joints(
@@ -254,9 +317,6 @@ impl SolverConstraints<AnyJointVelocityConstraint, AnyJointPositionConstraint> {
             &mut self.not_ground_interactions,
         );
 
-        self.velocity_constraints.clear();
-        self.position_constraints.clear();
-
         self.interaction_groups.clear_groups();
         self.interaction_groups.group_joints(
             island_id,
@@ -279,16 +339,37 @@ impl SolverConstraints<AnyJointVelocityConstraint, AnyJointPositionConstraint> {
         // self.ground_interaction_groups
         //     .nongrouped_interactions
         //     .append(&mut self.ground_interaction_groups.grouped_interactions);
+    }
 
-        self.compute_nongrouped_joint_ground_constraints(params, bodies, joints);
-        #[cfg(feature = "simd-is-enabled")]
-        {
-            self.compute_grouped_joint_ground_constraints(params, bodies, joints);
-        }
-        self.compute_nongrouped_joint_constraints(params, bodies, joints);
-        #[cfg(feature = "simd-is-enabled")]
-        {
-            self.compute_grouped_joint_constraints(params, bodies, joints);
+    pub fn init(
+        &mut self,
+        island_id: usize,
+        params: &IntegrationParameters,
+        bodies: &RigidBodySet,
+        joints: &[JointGraphEdge],
+        joint_constraint_indices: &[JointIndex],
+    ) {
+        self.velocity_constraints.clear();
+        self.position_constraints.clear();
+        self.extra_constraints.clear();
+
+        // Generate constraints for joints.
+        // The joint indices are sorted by solver priority and chain depth. The constraints of
+        // each priority and depth are generated, and therefore solved, after the ones of lower
+        // priority or depth.
+        for run in priority_runs(bodies, joints, joint_constraint_indices) {
+            self.init_constraint_groups(island_id, bodies, joints, run);
+
+            self.compute_nongrouped_joint_ground_constraints(params, bodies, joints);
+            #[cfg(feature = "simd-is-enabled")]
+            {
+                self.compute_grouped_joint_ground_constraints(params, bodies, joints);
+            }
+            self.compute_nongrouped_joint_constraints(params, bodies, joints);
+            #[cfg(feature = "simd-is-enabled")]
+            {
+                self.compute_grouped_joint_constraints(params, bodies, joints);
+            }
         }
     }
 
@@ -302,6 +383,12 @@ impl SolverConstraints<AnyJointVelocityConstraint, AnyJointPositionConstraint> {
             let joint = &joints_all[*joint_i].weight;
             let vel_constraint =
                 AnyJointVelocityConstraint::from_joint_ground(params, *joint_i, joint, bodies);
+            push_extra_constraints(
+                &mut self.extra_constraints,
+                self.velocity_constraints.len(),
+                self.velocity_constraints.len() + 1,
+                joint.extra_solver_iterations,
+            );
             self.velocity_constraints.push(vel_constraint);
             let pos_constraint = AnyJointPositionConstraint::from_joint_ground(joint, bodies);
             self.position_constraints.push(pos_constraint);
@@ -325,6 +412,16 @@ impl SolverConstraints<AnyJointVelocityConstraint, AnyJointPositionConstraint> {
             let vel_constraint = AnyJointVelocityConstraint::from_wide_joint_ground(
                 params, joints_id, joints, bodies,
             );
+            push_extra_constraints(
+                &mut self.extra_constraints,
+                self.velocity_constraints.len(),
+                self.velocity_constraints.len() + 1,
+                joints
+                    .iter()
+                    .map(|j| j.extra_solver_iterations)
+                    .max()
+                    .unwrap_or(0),
+            );
             self.velocity_constraints.push(vel_constraint);
 
             let pos_constraint = AnyJointPositionConstraint::from_wide_joint_ground(joints, bodies);
@@ -342,6 +439,12 @@ impl SolverConstraints<AnyJointVelocityConstraint, AnyJointPositionConstraint> {
             let joint = &joints_all[*joint_i].weight;
             let vel_constraint =
                 AnyJointVelocityConstraint::from_joint(params, *joint_i, joint, bodies);
+            push_extra_constraints(
+                &mut self.extra_constraints,
+                self.velocity_constraints.len(),
+                self.velocity_constraints.len() + 1,
+                joint.extra_solver_iterations,
+            );
             self.velocity_constraints.push(vel_constraint);
             let pos_constraint = AnyJointPositionConstraint::from_joint(joint, bodies);
             self.position_constraints.push(pos_constraint);
@@ -364,6 +467,16 @@ impl SolverConstraints<AnyJointVelocityConstraint, AnyJointPositionConstraint> {
             let joints = array![|ii| &joints_all[joints_i[ii]].weight; SIMD_WIDTH];
             let vel_constraint =
                 AnyJointVelocityConstraint::from_wide_joint(params, joints_id, joints, bodies);
+            push_extra_constraints(
+                &mut self.extra_constraints,
+                self.velocity_constraints.len(),
+                self.velocity_constraints.len() + 1,
+                joints
+                    .iter()
+                    .map(|j| j.extra_solver_iterations)
+                    .max()
+                    .unwrap_or(0),
+            );
             self.velocity_constraints.push(vel_constraint);
 
             let pos_constraint = AnyJointPositionConstraint::from_wide_joint(joints, bodies);
@@ -0,0 +1,70 @@
+/// Statistics about the constraints generated by the solver of a `PhysicsPipeline`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SolverStats {
+    /// The number of contact velocity constraints generated during the last timestep.
+    ///
+    /// With SIMD enabled, a single constraint may involve up to `SIMD_WIDTH` contact manifolds.
+    pub num_contact_constraints: usize,
+    /// The number of joint velocity constraints generated during the last timestep.
+    pub num_joint_constraints: usize,
+    /// The approximate number of bytes allocated by the solver workspaces.
+    ///
+    /// This is only computed if `detailed` was set to `true` when requesting the statistics, and
+    /// zero otherwise.
+    pub bytes: usize,
+    /// The largest number of contact velocity constraints since the last request.
+    pub peak_contact_constraints: usize,
+    /// The largest number of joint velocity constraints since the last request.
+    pub peak_joint_constraints: usize,
+    /// The largest batch size used by the parallel solver during the last timestep.
+    ///
+    /// This is zero without the `parallel` feature.
+    pub parallel_batch_size: usize,
+    /// Contention statistics of the parallel solver during the velocity resolution of the last timestep.
+    pub parallel_velocity_stage: ParallelStageStats,
+    /// Contention statistics of the parallel solver during the position resolution of the last timestep.
+    pub parallel_position_stage: ParallelStageStats,
+}
+
+/// Statistics about the synchronization of the threads of the parallel solver during one stage
+/// of the resolution.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParallelStageStats {
+    /// The number of batches of constraints or bodies claimed by the threads, including the last
+    /// unsuccessful claim of each thread for each loop.
+    pub num_claimed_batches: usize,
+    /// The number of iterations spent by the threads spinning while waiting for each other.
+    pub num_wait_spins: usize,
+}
+
+impl ParallelStageStats {
+    pub(crate) fn accumulate(&mut self, other: &ParallelStageStats) {
+        self.num_claimed_batches += other.num_claimed_batches;
+        self.num_wait_spins += other.num_wait_spins;
+    }
+}
+
+impl SolverStats {
+    pub(crate) fn accumulate(&mut self, other: &SolverStats) {
+        self.num_contact_constraints += other.num_contact_constraints;
+        self.num_joint_constraints += other.num_joint_constraints;
+        self.bytes += other.bytes;
+        self.parallel_batch_size = self.parallel_batch_size.max(other.parallel_batch_size);
+        self.parallel_velocity_stage
+            .accumulate(&other.parallel_velocity_stage);
+        self.parallel_position_stage
+            .accumulate(&other.parallel_position_stage);
+    }
+
+    pub(crate) fn record_peaks(&mut self) {
+        self.peak_contact_constraints = self
+            .peak_contact_constraints
+            .max(self.num_contact_constraints);
+        self.peak_joint_constraints = self.peak_joint_constraints.max(self.num_joint_constraints);
+    }
+
+    pub(crate) fn reset_peaks(&mut self) {
+        self.peak_contact_constraints = self.num_contact_constraints;
+        self.peak_joint_constraints = self.num_joint_constraints;
+    }
+}
@@ -1,12 +1,15 @@
 use crate::dynamics::solver::VelocityGroundConstraint;
 #[cfg(feature = "simd-is-enabled")]
 use crate::dynamics::solver::{WVelocityConstraint, WVelocityGroundConstraint};
-use crate::dynamics::{IntegrationParameters, RigidBodySet};
+use crate::dynamics::{IntegrationParameters, RestitutionModel, RigidBody, RigidBodySet};
 use crate::geometry::{ContactManifold, ContactManifoldIndex};
 use crate::math::{Real, Vector, DIM, MAX_MANIFOLD_POINTS};
+#[cfg(feature = "dim3")]
+use crate::utils::WSign;
 use crate::utils::{WAngularInertia, WBasis, WCross, WDot};
 
 use super::{DeltaVel, VelocityConstraintElement, VelocityConstraintNormalPart};
+use alloc::vec::Vec;
 
 //#[repr(align(64))]
 #[derive(Copy, Clone, Debug)]
@@ -65,6 +68,18 @@ impl AnyVelocityConstraint {
         }
     }
 
+    pub fn solve_restitution(&mut self, mj_lambdas: &mut [DeltaVel<Real>]) {
+        match self {
+            AnyVelocityConstraint::NongroupedGround(c) => c.solve_restitution(mj_lambdas),
+            AnyVelocityConstraint::Nongrouped(c) => c.solve_restitution(mj_lambdas),
+            #[cfg(feature = "simd-is-enabled")]
+            AnyVelocityConstraint::GroupedGround(c) => c.solve_restitution(mj_lambdas),
+            #[cfg(feature = "simd-is-enabled")]
+            AnyVelocityConstraint::Grouped(c) => c.solve_restitution(mj_lambdas),
+            AnyVelocityConstraint::Empty => unreachable!(),
+        }
+    }
+
     pub fn writeback_impulses(&self, manifold_all: &mut [&mut ContactManifold]) {
         match self {
             AnyVelocityConstraint::NongroupedGround(c) => c.writeback_impulses(manifold_all),
@@ -78,6 +93,73 @@ impl AnyVelocityConstraint {
     }
 }
 
+/// Copies the friction and restitution coefficients of the solver contact generated from the
+/// manifold point `contact_id` into the contact data of this point.
+pub(crate) fn writeback_coefficients(manifold: &mut ContactManifold, contact_id: u8) {
+    if let Some(solver_contact) = manifold
+        .data
+        .solver_contacts
+        .iter()
+        .find(|c| c.contact_id == contact_id)
+    {
+        let data = &mut manifold.points[contact_id as usize].data;
+        data.friction = solver_contact.friction;
+        data.restitution = solver_contact.restitution;
+    }
+}
+
+/// The factors by which the effective masses of the two bodies of `manifold` are multiplied
+/// so that their ratio doesn't exceed `IntegrationParameters::max_effective_mass_ratio` at
+/// any of its solver contacts.
+///
+/// Only the lighter body is made heavier. Both factors are `1.0` if the ratio is unbounded.
+pub(crate) fn effective_mass_multipliers(
+    params: &IntegrationParameters,
+    manifold: &ContactManifold,
+    rb1: &RigidBody,
+    rb2: &RigidBody,
+) -> [Real; 2] {
+    let max_ratio = match params.max_effective_mass_ratio {
+        Some(max_ratio) => max_ratio.max(1.0),
+        None => return [1.0; 2],
+    };
+
+    let force_dir1 = -manifold.data.normal;
+    let mut multipliers: [Real; 2] = [1.0; 2];
+
+    for contact in &manifold.data.solver_contacts {
+        let gcross1 = rb1
+            .effective_world_inv_inertia_sqrt
+            .transform_vector((contact.point - rb1.world_com).gcross(force_dir1));
+        let gcross2 = rb2
+            .effective_world_inv_inertia_sqrt
+            .transform_vector((contact.point - rb2.world_com).gcross(-force_dir1));
+        // The effective inverse masses of both bodies along the contact normal.
+        let inv_m1 = force_dir1.dot(&rb1.effective_inv_mass.component_mul(&force_dir1))
+            + gcross1.gdot(gcross1);
+        let inv_m2 = force_dir1.dot(&rb2.effective_inv_mass.component_mul(&force_dir1))
+            + gcross2.gdot(gcross2);
+
+        // NOTE: a zero inverse mass means the body can't move along this direction,
+        //       so raising the mass of the other body wouldn't have any effect.
+        if inv_m2 > 0.0 && inv_m1 > inv_m2 * max_ratio {
+            multipliers[0] = multipliers[0].max(inv_m1 / (inv_m2 * max_ratio));
+        } else if inv_m1 > 0.0 && inv_m2 > inv_m1 * max_ratio {
+            multipliers[1] = multipliers[1].max(inv_m2 / (inv_m1 * max_ratio));
+        }
+    }
+
+    // With unusual geometries, each body may be the lighter one at different contacts.
+    // Only keep the strongest correction so that the heavier body stays unaffected.
+    if multipliers[0] >= multipliers[1] {
+        multipliers[1] = 1.0;
+    } else {
+        multipliers[0] = 1.0;
+    }
+
+    multipliers
+}
+
 #[derive(Copy, Clone, Debug)]
 pub(crate) struct VelocityConstraint {
     // Non-penetration force direction for the first body.
@@ -88,9 +170,22 @@ pub(crate) struct VelocityConstraint {
     #[cfg(feature = "dim3")]
     // Orientation of the tangent basis wrt. the reference basis.
     pub tangent_rot1: na::UnitComplex<Real>,
-    pub im1: Real,
-    pub im2: Real,
+    pub im1: Vector<Real>,
+    pub im2: Vector<Real>,
+    // The factors applied to the effective masses of the bodies by the mass ratio clamping.
+    pub mass_multipliers: [Real; 2],
+    // The square roots of `mass_multipliers`, by which the angular velocity increments of the
+    // bodies are multiplied while this constraint is solved.
+    pub angular_scale1: Real,
+    pub angular_scale2: Real,
+    // Maximum angular velocity increments the contacts can apply to each body, multiplied by
+    // the square root of its angular inertia tensor.
+    pub max_mj_dangvel1: Real,
+    pub max_mj_dangvel2: Real,
     pub limit: Real,
+    // Does any contact of the manifold have a non-zero rolling resistance? If not, the rolling
+    // resistance rows are neither built nor solved.
+    pub has_rolling_resistance: bool,
     pub mj_lambda1: usize,
     pub mj_lambda2: usize,
     pub manifold_id: ContactManifoldIndex,
@@ -117,13 +212,34 @@ impl VelocityConstraint {
         assert_eq!(manifold.data.relative_dominance, 0);
 
         let inv_dt = params.inv_dt();
-        let velocity_based_erp_inv_dt = params.velocity_based_erp_inv_dt();
+        let velocity_based_erp_inv_dt =
+            manifold.data.erp_multiplier * params.velocity_based_erp_inv_dt();
+        let friction_anchor_erp_inv_dt = params.friction_anchor_erp_inv_dt();
+        let post_solve_restitution =
+            (params.restitution_model == RestitutionModel::PostSolve) as u32 as Real;
+        let rhs_restitution = 1.0 - post_solve_restitution;
 
         let rb1 = &bodies[manifold.data.body_pair.body1];
         let rb2 = &bodies[manifold.data.body_pair.body2];
         let mj_lambda1 = rb1.active_set_offset;
         let mj_lambda2 = rb2.active_set_offset;
+        let max_mj_dangvel1 = rb1.max_contact_mj_dangvel(params.dt);
+        let max_mj_dangvel2 = rb2.max_contact_mj_dangvel(params.dt);
         let force_dir1 = -manifold.data.normal;
+        let mass_multipliers = effective_mass_multipliers(params, manifold, rb1, rb2);
+        let im1 = rb1.effective_inv_mass / mass_multipliers[0];
+        let im2 = rb2.effective_inv_mass / mass_multipliers[1];
+        // Dividing the inverse angular inertia by the multiplier divides its square root,
+        // hence the `gcross`, by the square root of the multiplier. The angular velocity
+        // increments are multiplied by the same factor while the constraint is solved, so
+        // that the contacts still see the actual angular velocities of the bodies.
+        let angular_scale1 = mass_multipliers[0].sqrt();
+        let angular_scale2 = mass_multipliers[1].sqrt();
+        let gcross_scale1 = 1.0 / angular_scale1;
+        let gcross_scale2 = 1.0 / angular_scale2;
+        let max_mj_dangvel1 = max_mj_dangvel1 * angular_scale1;
+        let max_mj_dangvel2 = max_mj_dangvel2 * angular_scale2;
+        let im_sum = im1 + im2;
         let warmstart_coeff = manifold.data.warmstart_multiplier * params.warmstart_coeff;
 
         #[cfg(feature = "dim2")]
@@ -146,9 +262,15 @@ impl VelocityConstraint {
                 #[cfg(feature = "dim3")]
                 tangent_rot1,
                 elements: [VelocityConstraintElement::zero(); MAX_MANIFOLD_POINTS],
-                im1: rb1.effective_inv_mass,
-                im2: rb2.effective_inv_mass,
+                im1,
+                im2,
+                mass_multipliers,
+                angular_scale1,
+                angular_scale2,
+                max_mj_dangvel1,
+                max_mj_dangvel2,
                 limit: 0.0,
+                has_rolling_resistance: manifold.data.has_rolling_resistance,
                 mj_lambda1,
                 mj_lambda2,
                 manifold_id,
@@ -173,7 +295,7 @@ impl VelocityConstraint {
                 unsafe {
                     out_constraints.resize_with(new_len, || {
                         AnyVelocityConstraint::Nongrouped(
-                            std::mem::MaybeUninit::uninit().assume_init(),
+                            core::mem::MaybeUninit::uninit().assume_init(),
                         )
                     });
                 }
@@ -194,9 +316,15 @@ impl VelocityConstraint {
                     constraint.tangent1 = tangents1[0];
                     constraint.tangent_rot1 = tangent_rot1;
                 }
-                constraint.im1 = rb1.effective_inv_mass;
-                constraint.im2 = rb2.effective_inv_mass;
+                constraint.im1 = im1;
+                constraint.im2 = im2;
+                constraint.mass_multipliers = mass_multipliers;
+                constraint.angular_scale1 = angular_scale1;
+                constraint.angular_scale2 = angular_scale2;
+                constraint.max_mj_dangvel1 = max_mj_dangvel1;
+                constraint.max_mj_dangvel2 = max_mj_dangvel2;
                 constraint.limit = 0.0;
+                constraint.has_rolling_resistance = manifold.data.has_rolling_resistance;
                 constraint.mj_lambda1 = mj_lambda1;
                 constraint.mj_lambda2 = mj_lambda2;
                 constraint.manifold_id = manifold_id;
@@ -221,33 +349,43 @@ impl VelocityConstraint {
                 {
                     let gcross1 = rb1
                         .effective_world_inv_inertia_sqrt
-                        .transform_vector(dp1.gcross(force_dir1));
+                        .transform_vector(dp1.gcross(force_dir1))
+                        * gcross_scale1;
                     let gcross2 = rb2
                         .effective_world_inv_inertia_sqrt
-                        .transform_vector(dp2.gcross(-force_dir1));
+                        .transform_vector(dp2.gcross(-force_dir1))
+                        * gcross_scale2;
 
-                    let r = 1.0
-                        / (rb1.effective_inv_mass
-                            + rb2.effective_inv_mass
-                            + gcross1.gdot(gcross1)
-                            + gcross2.gdot(gcross2));
+                    let inv_r = force_dir1.dot(&im_sum.component_mul(&force_dir1))
+                        + gcross1.gdot(gcross1)
+                        + gcross2.gdot(gcross2);
+                    // The translations and rotations of the bodies may be locked.
+                    let r = if inv_r != 0.0 { 1.0 / inv_r } else { 0.0 };
 
                     let is_bouncy = manifold_point.is_bouncy() as u32 as Real;
                     let is_resting = 1.0 - is_bouncy;
 
-                    let mut rhs = (1.0 + is_bouncy * manifold_point.restitution)
-                        * (vel1 - vel2).dot(&force_dir1);
-                    rhs += manifold_point.dist.max(0.0) * inv_dt;
+                    let projected_velocity = (vel1 - vel2).dot(&force_dir1);
+                    let mut rhs = (1.0 + is_bouncy * rhs_restitution * manifold_point.restitution)
+                        * projected_velocity;
+                    rhs += manifold_point.dist_from_target().max(0.0) * inv_dt;
                     rhs *= is_bouncy + is_resting * params.velocity_solve_fraction;
-                    rhs += is_resting * velocity_based_erp_inv_dt * manifold_point.dist.min(0.0);
+                    rhs += is_resting
+                        * velocity_based_erp_inv_dt
+                        * manifold_point.dist_from_target().min(0.0);
                     warmstart_correction = (params.warmstart_correction_slope
                         / (rhs - manifold_point.prev_rhs).abs())
                     .min(warmstart_coeff);
+                    let restitution_rhs = is_bouncy
+                        * post_solve_restitution
+                        * (1.0 + manifold_point.restitution)
+                        * projected_velocity.min(0.0);
 
                     constraint.elements[k].normal_part = VelocityConstraintNormalPart {
                         gcross1,
                         gcross2,
                         rhs,
+                        restitution_rhs,
                         impulse: manifold_point.warmstart_impulse * warmstart_correction,
                         r,
                     };
@@ -267,17 +405,21 @@ impl VelocityConstraint {
                     for j in 0..DIM - 1 {
                         let gcross1 = rb1
                             .effective_world_inv_inertia_sqrt
-                            .transform_vector(dp1.gcross(tangents1[j]));
+                            .transform_vector(dp1.gcross(tangents1[j]))
+                            * gcross_scale1;
                         let gcross2 = rb2
                             .effective_world_inv_inertia_sqrt
-                            .transform_vector(dp2.gcross(-tangents1[j]));
-                        let r = 1.0
-                            / (rb1.effective_inv_mass
-                                + rb2.effective_inv_mass
-                                + gcross1.gdot(gcross1)
-                                + gcross2.gdot(gcross2));
-                        let rhs =
-                            (vel1 - vel2 + manifold_point.tangent_velocity).dot(&tangents1[j]);
+                            .transform_vector(dp2.gcross(-tangents1[j]))
+                            * gcross_scale2;
+                        let inv_r = tangents1[j].dot(&im_sum.component_mul(&tangents1[j]))
+                            + gcross1.gdot(gcross1)
+                            + gcross2.gdot(gcross2);
+                        // The translations and rotations of the bodies may be locked.
+                        let r = if inv_r != 0.0 { 1.0 / inv_r } else { 0.0 };
+                        let rhs = (vel1 - vel2
+                            + manifold_point.tangent_velocity
+                            + manifold_point.friction_anchor_offset * friction_anchor_erp_inv_dt)
+                            .dot(&tangents1[j]);
 
                         constraint.elements[k].tangent_part.gcross1[j] = gcross1;
                         constraint.elements[k].tangent_part.gcross2[j] = gcross2;
@@ -285,6 +427,51 @@ impl VelocityConstraint {
                         constraint.elements[k].tangent_part.r[j] = r;
                     }
                 }
+
+                // Rolling resistance part.
+                {
+                    #[cfg(feature = "dim2")]
+                    let rolling_axes: [Real; DIM - 1] = [1.0];
+                    #[cfg(feature = "dim3")]
+                    let rolling_axes = tangents1;
+                    let rolling_part = &mut constraint.elements[k].rolling_part;
+                    #[cfg(feature = "dim2")]
+                    {
+                        rolling_part.impulse = [0.0];
+                    }
+                    #[cfg(feature = "dim3")]
+                    {
+                        rolling_part.impulse = na::zero();
+                    }
+                    rolling_part.rolling_resistance = manifold_point.rolling_resistance;
+
+                    if manifold_point.rolling_resistance != 0.0 {
+                        for j in 0..DIM - 1 {
+                            let gcross1 = rb1
+                                .effective_world_inv_inertia_sqrt
+                                .transform_vector(rolling_axes[j])
+                                * gcross_scale1;
+                            let gcross2 = rb2
+                                .effective_world_inv_inertia_sqrt
+                                .transform_vector(-rolling_axes[j])
+                                * gcross_scale2;
+                            let inv_r = gcross1.gdot(gcross1) + gcross2.gdot(gcross2);
+
+                            rolling_part.gcross1[j] = gcross1;
+                            rolling_part.gcross2[j] = gcross2;
+                            rolling_part.rhs[j] = (rb1.angvel - rb2.angvel).gdot(rolling_axes[j]);
+                            // The rotations of both bodies may be locked.
+                            rolling_part.r[j] = if inv_r != 0.0 { 1.0 / inv_r } else { 0.0 };
+                        }
+                    } else {
+                        // These rows are still solved if another contact of this constraint
+                        // has some rolling resistance, so they must not apply any impulse.
+                        rolling_part.gcross1 = [na::zero(); DIM - 1];
+                        rolling_part.gcross2 = [na::zero(); DIM - 1];
+                        rolling_part.rhs = [0.0; DIM - 1];
+                        rolling_part.r = [0.0; DIM - 1];
+                    }
+                }
             }
 
             #[cfg(not(target_arch = "wasm32"))]
@@ -298,51 +485,92 @@ impl VelocityConstraint {
     }
 
     pub fn warmstart(&self, mj_lambdas: &mut [DeltaVel<Real>]) {
-        let mut mj_lambda1 = DeltaVel::zero();
-        let mut mj_lambda2 = DeltaVel::zero();
+        let mut mj_lambda1 = mj_lambdas[self.mj_lambda1 as usize];
+        let mut mj_lambda2 = mj_lambdas[self.mj_lambda2 as usize];
+        mj_lambda1.angular *= self.angular_scale1;
+        mj_lambda2.angular *= self.angular_scale2;
 
         VelocityConstraintElement::warmstart_group(
             &self.elements[..self.num_contacts as usize],
             &self.dir1,
             #[cfg(feature = "dim3")]
             &self.tangent1,
-            self.im1,
-            self.im2,
+            &self.im1,
+            &self.im2,
+            self.max_mj_dangvel1,
+            self.max_mj_dangvel2,
             &mut mj_lambda1,
             &mut mj_lambda2,
         );
 
-        mj_lambdas[self.mj_lambda1 as usize] += mj_lambda1;
-        mj_lambdas[self.mj_lambda2 as usize] += mj_lambda2;
+        mj_lambda1.angular /= self.angular_scale1;
+        mj_lambda2.angular /= self.angular_scale2;
+        mj_lambdas[self.mj_lambda1 as usize] = mj_lambda1;
+        mj_lambdas[self.mj_lambda2 as usize] = mj_lambda2;
     }
 
     pub fn solve(&mut self, mj_lambdas: &mut [DeltaVel<Real>]) {
         let mut mj_lambda1 = mj_lambdas[self.mj_lambda1 as usize];
         let mut mj_lambda2 = mj_lambdas[self.mj_lambda2 as usize];
+        mj_lambda1.angular *= self.angular_scale1;
+        mj_lambda2.angular *= self.angular_scale2;
 
         VelocityConstraintElement::solve_group(
             &mut self.elements[..self.num_contacts as usize],
             &self.dir1,
             #[cfg(feature = "dim3")]
             &self.tangent1,
-            self.im1,
-            self.im2,
+            &self.im1,
+            &self.im2,
+            self.max_mj_dangvel1,
+            self.max_mj_dangvel2,
             self.limit,
+            self.has_rolling_resistance,
+            &mut mj_lambda1,
+            &mut mj_lambda2,
+        );
+
+        mj_lambda1.angular /= self.angular_scale1;
+        mj_lambda2.angular /= self.angular_scale2;
+        mj_lambdas[self.mj_lambda1 as usize] = mj_lambda1;
+        mj_lambdas[self.mj_lambda2 as usize] = mj_lambda2;
+    }
+
+    pub fn solve_restitution(&mut self, mj_lambdas: &mut [DeltaVel<Real>]) {
+        let mut mj_lambda1 = mj_lambdas[self.mj_lambda1 as usize];
+        let mut mj_lambda2 = mj_lambdas[self.mj_lambda2 as usize];
+        mj_lambda1.angular *= self.angular_scale1;
+        mj_lambda2.angular *= self.angular_scale2;
+
+        VelocityConstraintElement::solve_restitution_group(
+            &mut self.elements[..self.num_contacts as usize],
+            &self.dir1,
+            &self.im1,
+            &self.im2,
+            self.max_mj_dangvel1,
+            self.max_mj_dangvel2,
             &mut mj_lambda1,
             &mut mj_lambda2,
         );
 
+        mj_lambda1.angular /= self.angular_scale1;
+        mj_lambda2.angular /= self.angular_scale2;
         mj_lambdas[self.mj_lambda1 as usize] = mj_lambda1;
         mj_lambdas[self.mj_lambda2 as usize] = mj_lambda2;
     }
 
     pub fn writeback_impulses(&self, manifolds_all: &mut [&mut ContactManifold]) {
         let manifold = &mut manifolds_all[self.manifold_id];
+        manifold.data.effective_mass_multipliers = self.mass_multipliers;
 
         for k in 0..self.num_contacts as usize {
             let contact_id = self.manifold_contact_id[k];
+            writeback_coefficients(manifold, contact_id);
+            let min_reported_impulse = manifold.data.min_reported_impulse;
             let active_contact = &mut manifold.points[contact_id as usize];
-            active_contact.data.impulse = self.elements[k].normal_part.impulse;
+            active_contact
+                .data
+                .set_impulse(self.elements[k].normal_part.impulse, min_reported_impulse);
             active_contact.data.rhs = self.elements[k].normal_part.rhs;
 
             #[cfg(feature = "dim2")]
@@ -355,6 +583,8 @@ impl VelocityConstraint {
                     .tangent_rot1
                     .inverse_transform_vector(&self.elements[k].tangent_part.impulse);
             }
+
+            active_contact.data.release_sliding_friction_anchor();
         }
     }
 }
@@ -367,22 +597,28 @@ pub(crate) fn compute_tangent_contact_directions<N>(
     linvel2: &Vector<N>,
 ) -> ([Vector<N>; DIM - 1], na::UnitComplex<N>)
 where
-    N: na::SimdRealField,
+    N: na::SimdRealField + WSign<N>,
     N::Element: na::RealField,
     Vector<N>: WBasis,
 {
     use na::SimdValue;
 
+    // Swapping the two bodies negates both `force_dir1` and the relative velocity. Multiplying
+    // them by the sign of `force_dir1.z` makes the tangent and the fallback direction independent
+    // from the order of the bodies: only the bitangent is negated. This lets the tangent impulses
+    // be transferred to a pair recreated with the reverse order (see `WarmstartCache::apply`).
+    let sign = force_dir1.z.copy_sign_to(N::one());
+
     // Compute the tangent direction. Pick the direction of
     // the linear relative velocity, if it is not too small.
     // Otherwise use a fallback direction.
-    let relative_linvel = linvel1 - linvel2;
+    let relative_linvel = (linvel1 - linvel2) * sign;
     let mut tangent_relative_linvel =
         relative_linvel - force_dir1 * (force_dir1.dot(&relative_linvel));
     let tangent_linvel_norm = tangent_relative_linvel.normalize_mut();
     let threshold: N::Element = na::convert(1.0e-4);
     let use_fallback = tangent_linvel_norm.simd_lt(N::splat(threshold));
-    let tangent_fallback = force_dir1.orthonormal_vector();
+    let tangent_fallback = (force_dir1 * sign).orthonormal_vector();
 
     let tangent1 = tangent_fallback.select(use_fallback, tangent_relative_linvel);
     let bitangent1 = force_dir1.cross(&tangent1);
@@ -34,8 +34,8 @@ impl<N: SimdRealField> VelocityConstraintTangentPart<N> {
     pub fn warmstart(
         &self,
         tangents1: [&Vector<N>; DIM - 1],
-        im1: N,
-        im2: N,
+        im1: &Vector<N>,
+        im2: &Vector<N>,
         mj_lambda1: &mut DeltaVel<N>,
         mj_lambda2: &mut DeltaVel<N>,
     ) where
@@ -43,10 +43,10 @@ impl<N: SimdRealField> VelocityConstraintTangentPart<N> {
         N::Element: SimdRealField,
     {
         for j in 0..DIM - 1 {
-            mj_lambda1.linear += tangents1[j] * (im1 * self.impulse[j]);
+            mj_lambda1.linear += tangents1[j].component_mul(im1) * self.impulse[j];
             mj_lambda1.angular += self.gcross1[j] * self.impulse[j];
 
-            mj_lambda2.linear += tangents1[j] * (-im2 * self.impulse[j]);
+            mj_lambda2.linear += tangents1[j].component_mul(im2) * -self.impulse[j];
             mj_lambda2.angular += self.gcross2[j] * self.impulse[j];
         }
     }
@@ -55,8 +55,8 @@ impl<N: SimdRealField> VelocityConstraintTangentPart<N> {
     pub fn solve(
         &mut self,
         tangents1: [&Vector<N>; DIM - 1],
-        im1: N,
-        im2: N,
+        im1: &Vector<N>,
+        im2: &Vector<N>,
         limit: N,
         mj_lambda1: &mut DeltaVel<N>,
         mj_lambda2: &mut DeltaVel<N>,
@@ -75,10 +75,10 @@ impl<N: SimdRealField> VelocityConstraintTangentPart<N> {
             let dlambda = new_impulse - self.impulse[0];
             self.impulse[0] = new_impulse;
 
-            mj_lambda1.linear += tangents1[0] * (im1 * dlambda);
+            mj_lambda1.linear += tangents1[0].component_mul(im1) * dlambda;
             mj_lambda1.angular += self.gcross1[0] * dlambda;
 
-            mj_lambda2.linear += tangents1[0] * (-im2 * dlambda);
+            mj_lambda2.linear += tangents1[0].component_mul(im2) * -dlambda;
             mj_lambda2.angular += self.gcross2[0] * dlambda;
         }
 
@@ -103,12 +103,83 @@ impl<N: SimdRealField> VelocityConstraintTangentPart<N> {
             let dlambda = new_impulse - self.impulse;
             self.impulse = new_impulse;
 
-            mj_lambda1.linear +=
-                tangents1[0] * (im1 * dlambda[0]) + tangents1[1] * (im1 * dlambda[1]);
+            mj_lambda1.linear += tangents1[0].component_mul(im1) * dlambda[0]
+                + tangents1[1].component_mul(im1) * dlambda[1];
             mj_lambda1.angular += self.gcross1[0] * dlambda[0] + self.gcross1[1] * dlambda[1];
 
-            mj_lambda2.linear +=
-                tangents1[0] * (-im2 * dlambda[0]) + tangents1[1] * (-im2 * dlambda[1]);
+            mj_lambda2.linear += tangents1[0].component_mul(im2) * -dlambda[0]
+                + tangents1[1].component_mul(im2) * -dlambda[1];
+            mj_lambda2.angular += self.gcross2[0] * dlambda[0] + self.gcross2[1] * dlambda[1];
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct VelocityConstraintRollingPart<N: SimdRealField> {
+    pub gcross1: [AngVector<N>; DIM - 1],
+    pub gcross2: [AngVector<N>; DIM - 1],
+    pub rhs: [N; DIM - 1],
+    #[cfg(feature = "dim2")]
+    pub impulse: [N; DIM - 1],
+    #[cfg(feature = "dim3")]
+    pub impulse: na::Vector2<N>,
+    pub r: [N; DIM - 1],
+    pub rolling_resistance: N,
+}
+
+impl<N: SimdRealField> VelocityConstraintRollingPart<N> {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn zero() -> Self {
+        Self {
+            gcross1: [na::zero(); DIM - 1],
+            gcross2: [na::zero(); DIM - 1],
+            rhs: [na::zero(); DIM - 1],
+            #[cfg(feature = "dim2")]
+            impulse: [na::zero(); DIM - 1],
+            #[cfg(feature = "dim3")]
+            impulse: na::zero(),
+            r: [na::zero(); DIM - 1],
+            rolling_resistance: na::zero(),
+        }
+    }
+
+    #[inline]
+    pub fn solve(&mut self, limit: N, mj_lambda1: &mut DeltaVel<N>, mj_lambda2: &mut DeltaVel<N>)
+    where
+        AngVector<N>: WDot<AngVector<N>, Result = N>,
+        N::Element: SimdRealField,
+    {
+        #[cfg(feature = "dim2")]
+        {
+            let dimpulse = self.gcross1[0].gdot(mj_lambda1.angular)
+                + self.gcross2[0].gdot(mj_lambda2.angular)
+                + self.rhs[0];
+            let new_impulse = (self.impulse[0] - self.r[0] * dimpulse).simd_clamp(-limit, limit);
+            let dlambda = new_impulse - self.impulse[0];
+            self.impulse[0] = new_impulse;
+
+            mj_lambda1.angular += self.gcross1[0] * dlambda;
+            mj_lambda2.angular += self.gcross2[0] * dlambda;
+        }
+
+        #[cfg(feature = "dim3")]
+        {
+            let dimpulse_0 = self.gcross1[0].gdot(mj_lambda1.angular)
+                + self.gcross2[0].gdot(mj_lambda2.angular)
+                + self.rhs[0];
+            let dimpulse_1 = self.gcross1[1].gdot(mj_lambda1.angular)
+                + self.gcross2[1].gdot(mj_lambda2.angular)
+                + self.rhs[1];
+
+            let new_impulse = na::Vector2::new(
+                self.impulse[0] - self.r[0] * dimpulse_0,
+                self.impulse[1] - self.r[1] * dimpulse_1,
+            );
+            let new_impulse = new_impulse.simd_cap_magnitude(limit);
+            let dlambda = new_impulse - self.impulse;
+            self.impulse = new_impulse;
+
+            mj_lambda1.angular += self.gcross1[0] * dlambda[0] + self.gcross1[1] * dlambda[1];
             mj_lambda2.angular += self.gcross2[0] * dlambda[0] + self.gcross2[1] * dlambda[1];
         }
     }
@@ -119,6 +190,8 @@ pub(crate) struct VelocityConstraintNormalPart<N: SimdRealField> {
     pub gcross1: AngVector<N>,
     pub gcross2: AngVector<N>,
     pub rhs: N,
+    // Right-hand-side of the restitution pass (zero if there is no restitution pass).
+    pub restitution_rhs: N,
     pub impulse: N,
     pub r: N,
 }
@@ -130,6 +203,7 @@ impl<N: SimdRealField> VelocityConstraintNormalPart<N> {
             gcross1: na::zero(),
             gcross2: na::zero(),
             rhs: na::zero(),
+            restitution_rhs: na::zero(),
             impulse: na::zero(),
             r: na::zero(),
         }
@@ -139,17 +213,17 @@ impl<N: SimdRealField> VelocityConstraintNormalPart<N> {
     pub fn warmstart(
         &self,
         dir1: &Vector<N>,
-        im1: N,
-        im2: N,
+        im1: &Vector<N>,
+        im2: &Vector<N>,
         mj_lambda1: &mut DeltaVel<N>,
         mj_lambda2: &mut DeltaVel<N>,
     ) where
         AngVector<N>: WDot<AngVector<N>, Result = N>,
     {
-        mj_lambda1.linear += dir1 * (im1 * self.impulse);
+        mj_lambda1.linear += dir1.component_mul(im1) * self.impulse;
         mj_lambda1.angular += self.gcross1 * self.impulse;
 
-        mj_lambda2.linear += dir1 * (-im2 * self.impulse);
+        mj_lambda2.linear += dir1.component_mul(im2) * -self.impulse;
         mj_lambda2.angular += self.gcross2 * self.impulse;
     }
 
@@ -157,8 +231,8 @@ impl<N: SimdRealField> VelocityConstraintNormalPart<N> {
     pub fn solve(
         &mut self,
         dir1: &Vector<N>,
-        im1: N,
-        im2: N,
+        im1: &Vector<N>,
+        im2: &Vector<N>,
         mj_lambda1: &mut DeltaVel<N>,
         mj_lambda2: &mut DeltaVel<N>,
     ) where
@@ -172,10 +246,39 @@ impl<N: SimdRealField> VelocityConstraintNormalPart<N> {
         let dlambda = new_impulse - self.impulse;
         self.impulse = new_impulse;
 
-        mj_lambda1.linear += dir1 * (im1 * dlambda);
+        mj_lambda1.linear += dir1.component_mul(im1) * dlambda;
+        mj_lambda1.angular += self.gcross1 * dlambda;
+
+        mj_lambda2.linear += dir1.component_mul(im2) * -dlambda;
+        mj_lambda2.angular += self.gcross2 * dlambda;
+    }
+
+    #[inline]
+    pub fn solve_restitution(
+        &mut self,
+        dir1: &Vector<N>,
+        im1: &Vector<N>,
+        im2: &Vector<N>,
+        mj_lambda1: &mut DeltaVel<N>,
+        mj_lambda2: &mut DeltaVel<N>,
+    ) where
+        AngVector<N>: WDot<AngVector<N>, Result = N>,
+    {
+        // Only bounce on contacts that were approaching and actually
+        // pushed the bodies apart during the velocity solve.
+        let is_bouncing = self.restitution_rhs.simd_lt(N::zero()) & self.impulse.simd_gt(N::zero());
+        let dimpulse = dir1.dot(&mj_lambda1.linear) + self.gcross1.gdot(mj_lambda1.angular)
+            - dir1.dot(&mj_lambda2.linear)
+            + self.gcross2.gdot(mj_lambda2.angular)
+            + self.restitution_rhs;
+        let new_impulse = (self.impulse - self.r * dimpulse).simd_max(N::zero());
+        let dlambda = (new_impulse - self.impulse).select(is_bouncing, N::zero());
+        self.impulse = self.impulse + dlambda;
+
+        mj_lambda1.linear += dir1.component_mul(im1) * dlambda;
         mj_lambda1.angular += self.gcross1 * dlambda;
 
-        mj_lambda2.linear += dir1 * (-im2 * dlambda);
+        mj_lambda2.linear += dir1.component_mul(im2) * -dlambda;
         mj_lambda2.angular += self.gcross2 * dlambda;
     }
 }
@@ -184,6 +287,7 @@ impl<N: SimdRealField> VelocityConstraintNormalPart<N> {
 pub(crate) struct VelocityConstraintElement<N: SimdRealField> {
     pub normal_part: VelocityConstraintNormalPart<N>,
     pub tangent_part: VelocityConstraintTangentPart<N>,
+    pub rolling_part: VelocityConstraintRollingPart<N>,
 }
 
 impl<N: SimdRealField> VelocityConstraintElement<N> {
@@ -192,6 +296,7 @@ impl<N: SimdRealField> VelocityConstraintElement<N> {
         Self {
             normal_part: VelocityConstraintNormalPart::zero(),
             tangent_part: VelocityConstraintTangentPart::zero(),
+            rolling_part: VelocityConstraintRollingPart::zero(),
         }
     }
 
@@ -200,8 +305,10 @@ impl<N: SimdRealField> VelocityConstraintElement<N> {
         elements: &[Self],
         dir1: &Vector<N>,
         #[cfg(feature = "dim3")] tangent1: &Vector<N>,
-        im1: N,
-        im2: N,
+        im1: &Vector<N>,
+        im2: &Vector<N>,
+        max_mj_dangvel1: N,
+        max_mj_dangvel2: N,
         mj_lambda1: &mut DeltaVel<N>,
         mj_lambda2: &mut DeltaVel<N>,
     ) where
@@ -209,6 +316,9 @@ impl<N: SimdRealField> VelocityConstraintElement<N> {
         AngVector<N>: WDot<AngVector<N>, Result = N>,
         N::Element: SimdRealField,
     {
+        let prev_angular1 = mj_lambda1.angular;
+        let prev_angular2 = mj_lambda2.angular;
+
         #[cfg(feature = "dim3")]
         let tangents1 = [tangent1, &dir1.cross(&tangent1)];
         #[cfg(feature = "dim2")]
@@ -222,6 +332,9 @@ impl<N: SimdRealField> VelocityConstraintElement<N> {
                 .normal_part
                 .warmstart(dir1, im1, im2, mj_lambda1, mj_lambda2);
         }
+
+        cap_contact_angular_increment(mj_lambda1, prev_angular1, max_mj_dangvel1);
+        cap_contact_angular_increment(mj_lambda2, prev_angular2, max_mj_dangvel2);
     }
 
     #[inline]
@@ -229,9 +342,12 @@ impl<N: SimdRealField> VelocityConstraintElement<N> {
         elements: &mut [Self],
         dir1: &Vector<N>,
         #[cfg(feature = "dim3")] tangent1: &Vector<N>,
-        im1: N,
-        im2: N,
+        im1: &Vector<N>,
+        im2: &Vector<N>,
+        max_mj_dangvel1: N,
+        max_mj_dangvel2: N,
         limit: N,
+        solve_rolling: bool,
         mj_lambda1: &mut DeltaVel<N>,
         mj_lambda2: &mut DeltaVel<N>,
     ) where
@@ -239,6 +355,9 @@ impl<N: SimdRealField> VelocityConstraintElement<N> {
         AngVector<N>: WDot<AngVector<N>, Result = N>,
         N::Element: SimdRealField,
     {
+        let prev_angular1 = mj_lambda1.angular;
+        let prev_angular2 = mj_lambda2.angular;
+
         // Solve friction.
         #[cfg(feature = "dim3")]
         let tangents1 = [tangent1, &dir1.cross(&tangent1)];
@@ -251,11 +370,78 @@ impl<N: SimdRealField> VelocityConstraintElement<N> {
             part.solve(tangents1, im1, im2, limit, mj_lambda1, mj_lambda2);
         }
 
+        // Solve rolling resistance.
+        if solve_rolling {
+            for element in elements.iter_mut() {
+                let limit = element.rolling_part.rolling_resistance * element.normal_part.impulse;
+                element.rolling_part.solve(limit, mj_lambda1, mj_lambda2);
+            }
+        }
+
         // Solve penetration.
         for element in elements.iter_mut() {
             element
                 .normal_part
                 .solve(&dir1, im1, im2, mj_lambda1, mj_lambda2);
         }
+
+        cap_contact_angular_increment(mj_lambda1, prev_angular1, max_mj_dangvel1);
+        cap_contact_angular_increment(mj_lambda2, prev_angular2, max_mj_dangvel2);
+    }
+
+    #[inline]
+    pub fn solve_restitution_group(
+        elements: &mut [Self],
+        dir1: &Vector<N>,
+        im1: &Vector<N>,
+        im2: &Vector<N>,
+        max_mj_dangvel1: N,
+        max_mj_dangvel2: N,
+        mj_lambda1: &mut DeltaVel<N>,
+        mj_lambda2: &mut DeltaVel<N>,
+    ) where
+        AngVector<N>: WDot<AngVector<N>, Result = N>,
+        N::Element: SimdRealField,
+    {
+        let prev_angular1 = mj_lambda1.angular;
+        let prev_angular2 = mj_lambda2.angular;
+
+        for element in elements.iter_mut() {
+            element
+                .normal_part
+                .solve_restitution(&dir1, im1, im2, mj_lambda1, mj_lambda2);
+        }
+
+        cap_contact_angular_increment(mj_lambda1, prev_angular1, max_mj_dangvel1);
+        cap_contact_angular_increment(mj_lambda2, prev_angular2, max_mj_dangvel2);
+    }
+}
+
+/// Prevents the contacts from increasing the magnitude of the angular velocity increment of a
+/// rigid-body beyond `max_mj_dangvel`.
+///
+/// Both `mj_lambda.angular` and `max_mj_dangvel` are multiplied by the square root of the angular
+/// inertia tensor of the rigid-body. `prev_angular` is the angular velocity increment before the
+/// contacts were solved: if its magnitude already exceeds `max_mj_dangvel`, it is used as the
+/// limit instead so that the contacts can still slow down the rotation of the rigid-body. The
+/// linear velocity increment is left untouched.
+#[inline]
+pub(crate) fn cap_contact_angular_increment<N: SimdRealField>(
+    mj_lambda: &mut DeltaVel<N>,
+    prev_angular: AngVector<N>,
+    max_mj_dangvel: N,
+) where
+    N::Element: SimdRealField,
+{
+    #[cfg(feature = "dim2")]
+    {
+        let limit = max_mj_dangvel.simd_max(prev_angular.simd_abs());
+        mj_lambda.angular = mj_lambda.angular.simd_clamp(-limit, limit);
+    }
+
+    #[cfg(feature = "dim3")]
+    {
+        let limit = max_mj_dangvel.simd_max(prev_angular.norm());
+        mj_lambda.angular = mj_lambda.angular.simd_cap_magnitude(limit);
     }
 }
@@ -1,14 +1,16 @@
 use super::{
-    AnyVelocityConstraint, DeltaVel, VelocityConstraintElement, VelocityConstraintNormalPart,
+    effective_mass_multipliers, writeback_coefficients, AnyVelocityConstraint, DeltaVel,
+    VelocityConstraintElement, VelocityConstraintNormalPart,
 };
-use crate::dynamics::{IntegrationParameters, RigidBodySet};
-use crate::geometry::{ContactManifold, ContactManifoldIndex};
+use crate::dynamics::{IntegrationParameters, RestitutionModel, RigidBodySet};
+use crate::geometry::{ContactManifold, ContactManifoldIndex, SolverContact};
 use crate::math::{
     AngVector, AngularInertia, Point, Real, SimdReal, Vector, DIM, MAX_MANIFOLD_POINTS, SIMD_WIDTH,
 };
 #[cfg(feature = "dim2")]
 use crate::utils::WBasis;
 use crate::utils::{WAngularInertia, WCross, WDot};
+use alloc::vec::Vec;
 use na::SimdComplexField;
 use num::Zero;
 use simba::simd::{SimdPartialOrd, SimdValue};
@@ -25,9 +27,22 @@ pub(crate) struct WVelocityConstraint {
     pub tangent_rot1: na::UnitComplex<SimdReal>,
     pub elements: [VelocityConstraintElement<SimdReal>; MAX_MANIFOLD_POINTS],
     pub num_contacts: u8,
-    pub im1: SimdReal,
-    pub im2: SimdReal,
+    pub im1: Vector<SimdReal>,
+    pub im2: Vector<SimdReal>,
+    // The factors applied to the effective masses of the bodies by the mass ratio clamping.
+    pub mass_multipliers: [[Real; 2]; SIMD_WIDTH],
+    // The square roots of `mass_multipliers`, by which the angular velocity increments of the
+    // bodies are multiplied while this constraint is solved.
+    pub angular_scale1: SimdReal,
+    pub angular_scale2: SimdReal,
+    // Maximum angular velocity increments the contacts can apply to each body, multiplied by
+    // the square root of its angular inertia tensor.
+    pub max_mj_dangvel1: SimdReal,
+    pub max_mj_dangvel2: SimdReal,
     pub limit: SimdReal,
+    // Does any contact of the manifolds of this group have a non-zero rolling resistance? If
+    // not, the rolling resistance rows are neither built nor solved.
+    pub has_rolling_resistance: bool,
     pub mj_lambda1: [usize; SIMD_WIDTH],
     pub mj_lambda2: [usize; SIMD_WIDTH],
     pub manifold_id: [ContactManifoldIndex; SIMD_WIDTH],
@@ -50,12 +65,33 @@ impl WVelocityConstraint {
         let inv_dt = SimdReal::splat(params.inv_dt());
         let warmstart_correction_slope = SimdReal::splat(params.warmstart_correction_slope);
         let velocity_solve_fraction = SimdReal::splat(params.velocity_solve_fraction);
-        let velocity_based_erp_inv_dt = SimdReal::splat(params.velocity_based_erp_inv_dt());
+        let post_solve_restitution = SimdReal::splat(
+            (params.restitution_model == RestitutionModel::PostSolve) as u32 as Real,
+        );
+        let rhs_restitution = SimdReal::splat(1.0) - post_solve_restitution;
+        let velocity_based_erp_inv_dt =
+            SimdReal::from(array![|ii| manifolds[ii].data.erp_multiplier; SIMD_WIDTH])
+                * SimdReal::splat(params.velocity_based_erp_inv_dt());
+        let friction_anchor_erp_inv_dt = SimdReal::splat(params.friction_anchor_erp_inv_dt());
 
         let rbs1 = array![|ii| &bodies[manifolds[ii].data.body_pair.body1]; SIMD_WIDTH];
         let rbs2 = array![|ii| &bodies[manifolds[ii].data.body_pair.body2]; SIMD_WIDTH];
 
-        let im1 = SimdReal::from(array![|ii| rbs1[ii].effective_inv_mass; SIMD_WIDTH]);
+        let mass_multipliers = array![|ii|
+            effective_mass_multipliers(params, manifolds[ii], rbs1[ii], rbs2[ii]); SIMD_WIDTH];
+        let mass_multiplier1 = SimdReal::from(array![|ii| mass_multipliers[ii][0]; SIMD_WIDTH]);
+        let mass_multiplier2 = SimdReal::from(array![|ii| mass_multipliers[ii][1]; SIMD_WIDTH]);
+        // Dividing the inverse angular inertia by the multiplier divides its square root,
+        // hence the `gcross`, by the square root of the multiplier. The angular velocity
+        // increments are multiplied by the same factor while the constraint is solved, so
+        // that the contacts still see the actual angular velocities of the bodies.
+        let angular_scale1 = mass_multiplier1.simd_sqrt();
+        let angular_scale2 = mass_multiplier2.simd_sqrt();
+        let gcross_scale1 = SimdReal::splat(1.0) / angular_scale1;
+        let gcross_scale2 = SimdReal::splat(1.0) / angular_scale2;
+
+        let im1 = Vector::from(array![|ii| rbs1[ii].effective_inv_mass; SIMD_WIDTH])
+            * (SimdReal::splat(1.0) / mass_multiplier1);
         let ii1: AngularInertia<SimdReal> = AngularInertia::from(
             array![|ii| rbs1[ii].effective_world_inv_inertia_sqrt; SIMD_WIDTH],
         );
@@ -65,7 +101,9 @@ impl WVelocityConstraint {
 
         let world_com1 = Point::from(array![|ii| rbs1[ii].world_com; SIMD_WIDTH]);
 
-        let im2 = SimdReal::from(array![|ii| rbs2[ii].effective_inv_mass; SIMD_WIDTH]);
+        let im2 = Vector::from(array![|ii| rbs2[ii].effective_inv_mass; SIMD_WIDTH])
+            * (SimdReal::splat(1.0) / mass_multiplier2);
+        let im_sum = im1 + im2;
         let ii2: AngularInertia<SimdReal> = AngularInertia::from(
             array![|ii| rbs2[ii].effective_world_inv_inertia_sqrt; SIMD_WIDTH],
         );
@@ -79,11 +117,22 @@ impl WVelocityConstraint {
 
         let mj_lambda1 = array![|ii| rbs1[ii].active_set_offset; SIMD_WIDTH];
         let mj_lambda2 = array![|ii| rbs2[ii].active_set_offset; SIMD_WIDTH];
+        let max_mj_dangvel1 =
+            SimdReal::from(array![|ii| rbs1[ii].max_contact_mj_dangvel(params.dt); SIMD_WIDTH])
+                * angular_scale1;
+        let max_mj_dangvel2 =
+            SimdReal::from(array![|ii| rbs2[ii].max_contact_mj_dangvel(params.dt); SIMD_WIDTH])
+                * angular_scale2;
 
         let warmstart_multiplier =
             SimdReal::from(array![|ii| manifolds[ii].data.warmstart_multiplier; SIMD_WIDTH]);
         let warmstart_coeff = warmstart_multiplier * SimdReal::splat(params.warmstart_coeff);
+        // NOTE: all the lanes have the same number of constraints, but not
+        //       necessarily the same number of contacts.
         let num_active_contacts = manifolds[0].data.num_active_contacts();
+        let has_rolling_resistance = manifolds
+            .iter()
+            .any(|manifold| manifold.data.has_rolling_resistance);
 
         #[cfg(feature = "dim2")]
         let tangents1 = force_dir1.orthonormal_basis();
@@ -92,10 +141,8 @@ impl WVelocityConstraint {
             super::compute_tangent_contact_directions(&force_dir1, &linvel1, &linvel2);
 
         for l in (0..num_active_contacts).step_by(MAX_MANIFOLD_POINTS) {
-            let manifold_points = array![|ii|
-                &manifolds[ii].data.solver_contacts[l..num_active_contacts]; SIMD_WIDTH
-            ];
-            let num_points = manifold_points[0].len().min(MAX_MANIFOLD_POINTS);
+            let manifold_points = array![|ii| &manifolds[ii].data.solver_contacts[l..]; SIMD_WIDTH];
+            let num_points = max_lane_contacts(&manifold_points);
 
             let mut constraint = WVelocityConstraint {
                 dir1: force_dir1,
@@ -106,7 +153,13 @@ impl WVelocityConstraint {
                 elements: [VelocityConstraintElement::zero(); MAX_MANIFOLD_POINTS],
                 im1,
                 im2,
+                mass_multipliers,
+                angular_scale1,
+                angular_scale2,
+                max_mj_dangvel1,
+                max_mj_dangvel2,
                 limit: SimdReal::splat(0.0),
+                has_rolling_resistance,
                 mj_lambda1,
                 mj_lambda2,
                 manifold_id,
@@ -115,24 +168,23 @@ impl WVelocityConstraint {
             };
 
             for k in 0..num_points {
-                let friction =
-                    SimdReal::from(array![|ii| manifold_points[ii][k].friction; SIMD_WIDTH]);
-                let restitution =
-                    SimdReal::from(array![|ii| manifold_points[ii][k].restitution; SIMD_WIDTH]);
+                let (contacts, is_contact) = select_padded_contacts(&manifold_points, k);
+                let friction = SimdReal::from(array![|ii| contacts[ii].friction; SIMD_WIDTH]);
+                let restitution = SimdReal::from(array![|ii| contacts[ii].restitution; SIMD_WIDTH]);
                 let is_bouncy = SimdReal::from(
-                    array![|ii| manifold_points[ii][k].is_bouncy() as u32 as Real; SIMD_WIDTH],
+                    array![|ii| contacts[ii].is_bouncy() as u32 as Real; SIMD_WIDTH],
                 );
                 let is_resting = SimdReal::splat(1.0) - is_bouncy;
-                let point = Point::from(array![|ii| manifold_points[ii][k].point; SIMD_WIDTH]);
-                let dist = SimdReal::from(array![|ii| manifold_points[ii][k].dist; SIMD_WIDTH]);
+                let point = Point::from(array![|ii| contacts[ii].point; SIMD_WIDTH]);
+                let dist = SimdReal::from(array![|ii| contacts[ii].dist_from_target(); SIMD_WIDTH]);
                 let tangent_velocity =
-                    Vector::from(array![|ii| manifold_points[ii][k].tangent_velocity; SIMD_WIDTH]);
+                    Vector::from(array![|ii| contacts[ii].tangent_velocity; SIMD_WIDTH]);
+                let friction_anchor_offset =
+                    Vector::from(array![|ii| contacts[ii].friction_anchor_offset; SIMD_WIDTH]);
 
-                let impulse = SimdReal::from(
-                    array![|ii| manifold_points[ii][k].warmstart_impulse; SIMD_WIDTH],
-                );
-                let prev_rhs =
-                    SimdReal::from(array![|ii| manifold_points[ii][k].prev_rhs; SIMD_WIDTH]);
+                let impulse =
+                    SimdReal::from(array![|ii| contacts[ii].warmstart_impulse; SIMD_WIDTH]);
+                let prev_rhs = SimdReal::from(array![|ii| contacts[ii].prev_rhs; SIMD_WIDTH]);
 
                 let dp1 = point - world_com1;
                 let dp2 = point - world_com2;
@@ -143,19 +195,28 @@ impl WVelocityConstraint {
                 let warmstart_correction;
 
                 constraint.limit = friction;
-                constraint.manifold_contact_id[k] =
-                    array![|ii| manifold_points[ii][k].contact_id; SIMD_WIDTH];
+                constraint.manifold_contact_id[k] = array![|ii|
+                    if k < manifold_points[ii].len() {
+                        contacts[ii].contact_id
+                    } else {
+                        PADDING_CONTACT_ID
+                    }; SIMD_WIDTH];
 
                 // Normal part.
                 {
-                    let gcross1 = ii1.transform_vector(dp1.gcross(force_dir1));
-                    let gcross2 = ii2.transform_vector(dp2.gcross(-force_dir1));
-
-                    let r = SimdReal::splat(1.0)
-                        / (im1 + im2 + gcross1.gdot(gcross1) + gcross2.gdot(gcross2));
+                    let gcross1 = ii1.transform_vector(dp1.gcross(force_dir1)) * gcross_scale1;
+                    let gcross2 = ii2.transform_vector(dp2.gcross(-force_dir1)) * gcross_scale2;
+
+                    let inv_r = force_dir1.dot(&im_sum.component_mul(&force_dir1))
+                        + gcross1.gdot(gcross1)
+                        + gcross2.gdot(gcross2);
+                    // The translations and rotations of the bodies may be locked.
+                    let r = (SimdReal::splat(1.0) / inv_r)
+                        .select(inv_r.simd_gt(SimdReal::zero()), SimdReal::zero());
                     let projected_velocity = (vel1 - vel2).dot(&force_dir1);
-                    let mut rhs =
-                        (SimdReal::splat(1.0) + is_bouncy * restitution) * projected_velocity;
+                    let mut rhs = (SimdReal::splat(1.0)
+                        + is_bouncy * rhs_restitution * restitution)
+                        * projected_velocity;
                     rhs += dist.simd_max(SimdReal::zero()) * inv_dt;
                     rhs *= is_bouncy + is_resting * velocity_solve_fraction;
                     rhs +=
@@ -163,42 +224,82 @@ impl WVelocityConstraint {
                     warmstart_correction = (warmstart_correction_slope
                         / (rhs - prev_rhs).simd_abs())
                     .simd_min(warmstart_coeff);
+                    let restitution_rhs = is_bouncy
+                        * post_solve_restitution
+                        * (SimdReal::splat(1.0) + restitution)
+                        * projected_velocity.simd_min(SimdReal::zero());
 
                     constraint.elements[k].normal_part = VelocityConstraintNormalPart {
-                        gcross1,
-                        gcross2,
-                        rhs,
-                        impulse: impulse * warmstart_correction,
-                        r,
+                        gcross1: gcross1 * is_contact,
+                        gcross2: gcross2 * is_contact,
+                        rhs: rhs * is_contact,
+                        restitution_rhs: restitution_rhs * is_contact,
+                        impulse: impulse * warmstart_correction * is_contact,
+                        r: r * is_contact,
                     };
                 }
 
                 // tangent parts.
                 #[cfg(feature = "dim2")]
                 let impulse = [SimdReal::from(
-                    array![|ii| manifold_points[ii][k].warmstart_tangent_impulse; SIMD_WIDTH],
-                ) * warmstart_correction];
+                    array![|ii| contacts[ii].warmstart_tangent_impulse; SIMD_WIDTH],
+                ) * warmstart_correction
+                    * is_contact];
 
                 #[cfg(feature = "dim3")]
                 let impulse = tangent_rot1
                     * na::Vector2::from(
-                        array![|ii| manifold_points[ii][k].warmstart_tangent_impulse; SIMD_WIDTH],
+                        array![|ii| contacts[ii].warmstart_tangent_impulse; SIMD_WIDTH],
                     )
-                    * warmstart_correction;
+                    * (warmstart_correction * is_contact);
 
                 constraint.elements[k].tangent_part.impulse = impulse;
 
                 for j in 0..DIM - 1 {
-                    let gcross1 = ii1.transform_vector(dp1.gcross(tangents1[j]));
-                    let gcross2 = ii2.transform_vector(dp2.gcross(-tangents1[j]));
-                    let r = SimdReal::splat(1.0)
-                        / (im1 + im2 + gcross1.gdot(gcross1) + gcross2.gdot(gcross2));
-                    let rhs = (vel1 - vel2 + tangent_velocity).dot(&tangents1[j]);
-
-                    constraint.elements[k].tangent_part.gcross1[j] = gcross1;
-                    constraint.elements[k].tangent_part.gcross2[j] = gcross2;
-                    constraint.elements[k].tangent_part.rhs[j] = rhs;
-                    constraint.elements[k].tangent_part.r[j] = r;
+                    let gcross1 = ii1.transform_vector(dp1.gcross(tangents1[j])) * gcross_scale1;
+                    let gcross2 = ii2.transform_vector(dp2.gcross(-tangents1[j])) * gcross_scale2;
+                    let inv_r = tangents1[j].dot(&im_sum.component_mul(&tangents1[j]))
+                        + gcross1.gdot(gcross1)
+                        + gcross2.gdot(gcross2);
+                    // The translations and rotations of the bodies may be locked.
+                    let r = (SimdReal::splat(1.0) / inv_r)
+                        .select(inv_r.simd_gt(SimdReal::zero()), SimdReal::zero());
+                    let rhs = (vel1 - vel2
+                        + tangent_velocity
+                        + friction_anchor_offset * friction_anchor_erp_inv_dt)
+                        .dot(&tangents1[j]);
+
+                    constraint.elements[k].tangent_part.gcross1[j] = gcross1 * is_contact;
+                    constraint.elements[k].tangent_part.gcross2[j] = gcross2 * is_contact;
+                    constraint.elements[k].tangent_part.rhs[j] = rhs * is_contact;
+                    constraint.elements[k].tangent_part.r[j] = r * is_contact;
+                }
+
+                // Rolling resistance part.
+                if has_rolling_resistance {
+                    #[cfg(feature = "dim2")]
+                    let rolling_axes = [SimdReal::splat(1.0)];
+                    #[cfg(feature = "dim3")]
+                    let rolling_axes = tangents1;
+                    let rolling_resistance =
+                        SimdReal::from(array![|ii| contacts[ii].rolling_resistance; SIMD_WIDTH]);
+                    let rolling_part = &mut constraint.elements[k].rolling_part;
+                    rolling_part.rolling_resistance = rolling_resistance * is_contact;
+
+                    for j in 0..DIM - 1 {
+                        let gcross1 = ii1.transform_vector(rolling_axes[j]) * gcross_scale1;
+                        let gcross2 = ii2.transform_vector(-rolling_axes[j]) * gcross_scale2;
+                        let inv_r = gcross1.gdot(gcross1) + gcross2.gdot(gcross2);
+                        // The rotations of both bodies may be locked.
+                        let r = (SimdReal::splat(1.0) / inv_r)
+                            .select(inv_r.simd_gt(SimdReal::zero()), SimdReal::zero());
+                        let rhs = (angvel1 - angvel2).gdot(rolling_axes[j]);
+
+                        rolling_part.gcross1[j] = gcross1 * is_contact;
+                        rolling_part.gcross2[j] = gcross2 * is_contact;
+                        rolling_part.rhs[j] = rhs * is_contact;
+                        rolling_part.r[j] = r * is_contact;
+                    }
                 }
             }
 
@@ -230,17 +331,25 @@ impl WVelocityConstraint {
             ),
         };
 
+        mj_lambda1.angular *= self.angular_scale1;
+        mj_lambda2.angular *= self.angular_scale2;
+
         VelocityConstraintElement::warmstart_group(
             &self.elements[..self.num_contacts as usize],
             &self.dir1,
             #[cfg(feature = "dim3")]
             &self.tangent1,
-            self.im1,
-            self.im2,
+            &self.im1,
+            &self.im2,
+            self.max_mj_dangvel1,
+            self.max_mj_dangvel2,
             &mut mj_lambda1,
             &mut mj_lambda2,
         );
 
+        mj_lambda1.angular /= self.angular_scale1;
+        mj_lambda2.angular /= self.angular_scale2;
+
         for ii in 0..SIMD_WIDTH {
             mj_lambdas[self.mj_lambda1[ii] as usize].linear = mj_lambda1.linear.extract(ii);
             mj_lambdas[self.mj_lambda1[ii] as usize].angular = mj_lambda1.angular.extract(ii);
@@ -270,18 +379,73 @@ impl WVelocityConstraint {
             ),
         };
 
+        mj_lambda1.angular *= self.angular_scale1;
+        mj_lambda2.angular *= self.angular_scale2;
+
         VelocityConstraintElement::solve_group(
             &mut self.elements[..self.num_contacts as usize],
             &self.dir1,
             #[cfg(feature = "dim3")]
             &self.tangent1,
-            self.im1,
-            self.im2,
+            &self.im1,
+            &self.im2,
+            self.max_mj_dangvel1,
+            self.max_mj_dangvel2,
             self.limit,
+            self.has_rolling_resistance,
             &mut mj_lambda1,
             &mut mj_lambda2,
         );
 
+        mj_lambda1.angular /= self.angular_scale1;
+        mj_lambda2.angular /= self.angular_scale2;
+
+        for ii in 0..SIMD_WIDTH {
+            mj_lambdas[self.mj_lambda1[ii] as usize].linear = mj_lambda1.linear.extract(ii);
+            mj_lambdas[self.mj_lambda1[ii] as usize].angular = mj_lambda1.angular.extract(ii);
+        }
+        for ii in 0..SIMD_WIDTH {
+            mj_lambdas[self.mj_lambda2[ii] as usize].linear = mj_lambda2.linear.extract(ii);
+            mj_lambdas[self.mj_lambda2[ii] as usize].angular = mj_lambda2.angular.extract(ii);
+        }
+    }
+
+    pub fn solve_restitution(&mut self, mj_lambdas: &mut [DeltaVel<Real>]) {
+        let mut mj_lambda1 = DeltaVel {
+            linear: Vector::from(
+                array![|ii| mj_lambdas[self.mj_lambda1[ii] as usize].linear; SIMD_WIDTH],
+            ),
+            angular: AngVector::from(
+                array![|ii| mj_lambdas[self.mj_lambda1[ii] as usize].angular; SIMD_WIDTH],
+            ),
+        };
+
+        let mut mj_lambda2 = DeltaVel {
+            linear: Vector::from(
+                array![|ii| mj_lambdas[ self.mj_lambda2[ii] as usize].linear; SIMD_WIDTH],
+            ),
+            angular: AngVector::from(
+                array![|ii| mj_lambdas[ self.mj_lambda2[ii] as usize].angular; SIMD_WIDTH],
+            ),
+        };
+
+        mj_lambda1.angular *= self.angular_scale1;
+        mj_lambda2.angular *= self.angular_scale2;
+
+        VelocityConstraintElement::solve_restitution_group(
+            &mut self.elements[..self.num_contacts as usize],
+            &self.dir1,
+            &self.im1,
+            &self.im2,
+            self.max_mj_dangvel1,
+            self.max_mj_dangvel2,
+            &mut mj_lambda1,
+            &mut mj_lambda2,
+        );
+
+        mj_lambda1.angular /= self.angular_scale1;
+        mj_lambda2.angular /= self.angular_scale2;
+
         for ii in 0..SIMD_WIDTH {
             mj_lambdas[self.mj_lambda1[ii] as usize].linear = mj_lambda1.linear.extract(ii);
             mj_lambdas[self.mj_lambda1[ii] as usize].angular = mj_lambda1.angular.extract(ii);
@@ -293,6 +457,12 @@ impl WVelocityConstraint {
     }
 
     pub fn writeback_impulses(&self, manifolds_all: &mut [&mut ContactManifold]) {
+        for ii in 0..SIMD_WIDTH {
+            manifolds_all[self.manifold_id[ii]]
+                .data
+                .effective_mass_multipliers = self.mass_multipliers[ii];
+        }
+
         for k in 0..self.num_contacts as usize {
             let impulses: [_; SIMD_WIDTH] = self.elements[k].normal_part.impulse.into();
             let rhs: [_; SIMD_WIDTH] = self.elements[k].normal_part.rhs.into();
@@ -304,11 +474,19 @@ impl WVelocityConstraint {
                 .inverse_transform_vector(&self.elements[k].tangent_part.impulse);
 
             for ii in 0..SIMD_WIDTH {
-                let manifold = &mut manifolds_all[self.manifold_id[ii]];
                 let contact_id = self.manifold_contact_id[k][ii];
+                if contact_id == PADDING_CONTACT_ID {
+                    continue;
+                }
+
+                let manifold = &mut manifolds_all[self.manifold_id[ii]];
+                writeback_coefficients(manifold, contact_id);
+                let min_reported_impulse = manifold.data.min_reported_impulse;
                 let active_contact = &mut manifold.points[contact_id as usize];
                 active_contact.data.rhs = rhs[ii];
-                active_contact.data.impulse = impulses[ii];
+                active_contact
+                    .data
+                    .set_impulse(impulses[ii], min_reported_impulse);
 
                 #[cfg(feature = "dim2")]
                 {
@@ -318,7 +496,41 @@ impl WVelocityConstraint {
                 {
                     active_contact.data.tangent_impulse = tangent_impulses.extract(ii);
                 }
+
+                active_contact.data.release_sliding_friction_anchor();
             }
         }
     }
 }
+
+/// The contact id given to the padding contacts of a SIMD constraint.
+pub(crate) const PADDING_CONTACT_ID: u8 = u8::MAX;
+
+/// The number of contacts of the SIMD constraint built from the given manifold points.
+///
+/// This is the largest number of points among all the lanes (capped to `MAX_MANIFOLD_POINTS`).
+#[inline(always)]
+pub(crate) fn max_lane_contacts(manifold_points: &[&[SolverContact]; SIMD_WIDTH]) -> usize {
+    manifold_points
+        .iter()
+        .map(|pts| pts.len().min(MAX_MANIFOLD_POINTS))
+        .max()
+        .unwrap_or(0)
+}
+
+/// Selects the `k`-th contact of each lane of a SIMD constraint.
+///
+/// The lanes with less than `k + 1` contacts are padded with a copy of their last contact.
+/// The returned mask is zero for these padded lanes and one for the others: it must
+/// be used to zero-out the rows of the padded contacts so they produce zero impulse.
+#[inline(always)]
+pub(crate) fn select_padded_contacts(
+    manifold_points: &[&[SolverContact]; SIMD_WIDTH],
+    k: usize,
+) -> ([SolverContact; SIMD_WIDTH], SimdReal) {
+    let contacts =
+        array![|ii| manifold_points[ii][k.min(manifold_points[ii].len() - 1)]; SIMD_WIDTH];
+    let is_contact =
+        SimdReal::from(array![|ii| (k < manifold_points[ii].len()) as u32 as Real; SIMD_WIDTH]);
+    (contacts, is_contact)
+}
@@ -1,5 +1,5 @@
 use super::{
-    AnyVelocityConstraint, DeltaVel, VelocityGroundConstraintElement,
+    writeback_coefficients, AnyVelocityConstraint, DeltaVel, VelocityGroundConstraintElement,
     VelocityGroundConstraintNormalPart,
 };
 use crate::math::{Real, Vector, DIM, MAX_MANIFOLD_POINTS};
@@ -7,8 +7,9 @@ use crate::math::{Real, Vector, DIM, MAX_MANIFOLD_POINTS};
 use crate::utils::WBasis;
 use crate::utils::{WAngularInertia, WCross, WDot};
 
-use crate::dynamics::{IntegrationParameters, RigidBodySet};
+use crate::dynamics::{IntegrationParameters, RestitutionModel, RigidBodySet};
 use crate::geometry::{ContactManifold, ContactManifoldIndex};
+use alloc::vec::Vec;
 
 #[derive(Copy, Clone, Debug)]
 pub(crate) struct VelocityGroundConstraint {
@@ -18,8 +19,14 @@ pub(crate) struct VelocityGroundConstraint {
     #[cfg(feature = "dim3")]
     // One of the friction force directions.
     pub tangent1: Vector<Real>,
-    pub im2: Real,
+    pub im2: Vector<Real>,
+    // Maximum angular velocity increment the contacts can apply to the dynamic body, multiplied
+    // by the square root of its angular inertia tensor.
+    pub max_mj_dangvel2: Real,
     pub limit: Real,
+    // Does any contact of the manifold have a non-zero rolling resistance? If not, the rolling
+    // resistance rows are neither built nor solved.
+    pub has_rolling_resistance: bool,
     pub elements: [VelocityGroundConstraintElement<Real>; MAX_MANIFOLD_POINTS],
 
     #[cfg(feature = "dim3")]
@@ -40,26 +47,44 @@ impl VelocityGroundConstraint {
         push: bool,
     ) {
         let inv_dt = params.inv_dt();
-        let velocity_based_erp_inv_dt = params.velocity_based_erp_inv_dt();
+        let velocity_based_erp_inv_dt =
+            manifold.data.erp_multiplier * params.velocity_based_erp_inv_dt();
+        let friction_anchor_erp_inv_dt = params.friction_anchor_erp_inv_dt();
+        let post_solve_restitution =
+            (params.restitution_model == RestitutionModel::PostSolve) as u32 as Real;
+        let rhs_restitution = 1.0 - post_solve_restitution;
 
         let mut rb1 = &bodies[manifold.data.body_pair.body1];
         let mut rb2 = &bodies[manifold.data.body_pair.body2];
         let flipped = manifold.data.relative_dominance < 0;
 
         let (force_dir1, flipped_multiplier) = if flipped {
-            std::mem::swap(&mut rb1, &mut rb2);
+            core::mem::swap(&mut rb1, &mut rb2);
             (manifold.data.normal, -1.0)
         } else {
             (-manifold.data.normal, 1.0)
         };
 
+        // Velocity change of the dynamic body due to gravity (and other external forces)
+        // that each contact has to cancel when the ground side cannot yield.
+        let support_dvel = if params.gravity_compensation_on_ground_contacts && !rb1.is_dynamic() {
+            rb2.force.component_mul(&rb2.effective_inv_mass)
+                * (params.dt / manifold.data.solver_contacts.len() as Real)
+        } else {
+            Vector::zeros()
+        };
+
         #[cfg(feature = "dim2")]
         let tangents1 = force_dir1.orthonormal_basis();
         #[cfg(feature = "dim3")]
         let (tangents1, tangent_rot1) =
             super::compute_tangent_contact_directions(&force_dir1, &rb1.linvel, &rb2.linvel);
 
+        // NOTE: the ground side already behaves as if it had an infinite mass, so it is never
+        //       affected by `IntegrationParameters::max_effective_mass_ratio`. Raising the mass of
+        //       the dynamic body would only scale the impulses, not its velocity response.
         let mj_lambda2 = rb2.active_set_offset;
+        let max_mj_dangvel2 = rb2.max_contact_mj_dangvel(params.dt);
         let warmstart_coeff = manifold.data.warmstart_multiplier * params.warmstart_coeff;
 
         for (_l, manifold_points) in manifold
@@ -77,7 +102,9 @@ impl VelocityGroundConstraint {
                 tangent_rot1,
                 elements: [VelocityGroundConstraintElement::zero(); MAX_MANIFOLD_POINTS],
                 im2: rb2.effective_inv_mass,
+                max_mj_dangvel2,
                 limit: 0.0,
+                has_rolling_resistance: manifold.data.has_rolling_resistance,
                 mj_lambda2,
                 manifold_id,
                 manifold_contact_id: [0; MAX_MANIFOLD_POINTS],
@@ -101,7 +128,7 @@ impl VelocityGroundConstraint {
                 unsafe {
                     out_constraints.resize_with(new_len, || {
                         AnyVelocityConstraint::NongroupedGround(
-                            std::mem::MaybeUninit::uninit().assume_init(),
+                            core::mem::MaybeUninit::uninit().assume_init(),
                         )
                     });
                 }
@@ -123,7 +150,9 @@ impl VelocityGroundConstraint {
                     constraint.tangent_rot1 = tangent_rot1;
                 }
                 constraint.im2 = rb2.effective_inv_mass;
+                constraint.max_mj_dangvel2 = max_mj_dangvel2;
                 constraint.limit = 0.0;
+                constraint.has_rolling_resistance = manifold.data.has_rolling_resistance;
                 constraint.mj_lambda2 = mj_lambda2;
                 constraint.manifold_id = manifold_id;
                 constraint.manifold_contact_id = [0; MAX_MANIFOLD_POINTS];
@@ -147,24 +176,37 @@ impl VelocityGroundConstraint {
                         .effective_world_inv_inertia_sqrt
                         .transform_vector(dp2.gcross(-force_dir1));
 
-                    let r = 1.0 / (rb2.effective_inv_mass + gcross2.gdot(gcross2));
+                    let inv_r = force_dir1.dot(&rb2.effective_inv_mass.component_mul(&force_dir1))
+                        + gcross2.gdot(gcross2);
+                    // The translations and rotations of the body may be locked.
+                    let r = if inv_r != 0.0 { 1.0 / inv_r } else { 0.0 };
 
                     let is_bouncy = manifold_point.is_bouncy() as u32 as Real;
                     let is_resting = 1.0 - is_bouncy;
 
-                    let mut rhs = (1.0 + is_bouncy * manifold_point.restitution)
-                        * (vel1 - vel2).dot(&force_dir1);
-                    rhs += manifold_point.dist.max(0.0) * inv_dt;
+                    let projected_velocity = (vel1 - vel2).dot(&force_dir1);
+                    let mut rhs = (1.0 + is_bouncy * rhs_restitution * manifold_point.restitution)
+                        * projected_velocity;
+                    rhs += manifold_point.dist_from_target().max(0.0) * inv_dt;
                     rhs *= is_bouncy + is_resting * params.velocity_solve_fraction;
-                    rhs += is_resting * velocity_based_erp_inv_dt * manifold_point.dist.min(0.0);
+                    rhs += is_resting
+                        * velocity_based_erp_inv_dt
+                        * manifold_point.dist_from_target().min(0.0);
                     warmstart_correction = (params.warmstart_correction_slope
                         / (rhs - manifold_point.prev_rhs).abs())
                     .min(warmstart_coeff);
+                    let support_impulse = r * force_dir1.dot(&support_dvel).max(0.0);
+                    let restitution_rhs = is_bouncy
+                        * post_solve_restitution
+                        * (1.0 + manifold_point.restitution)
+                        * projected_velocity.min(0.0);
 
                     constraint.elements[k].normal_part = VelocityGroundConstraintNormalPart {
                         gcross2,
                         rhs,
-                        impulse: manifold_point.warmstart_impulse * warmstart_correction,
+                        restitution_rhs,
+                        impulse: (manifold_point.warmstart_impulse * warmstart_correction)
+                            .max(support_impulse),
                         r,
                     };
                 }
@@ -184,9 +226,16 @@ impl VelocityGroundConstraint {
                         let gcross2 = rb2
                             .effective_world_inv_inertia_sqrt
                             .transform_vector(dp2.gcross(-tangents1[j]));
-                        let r = 1.0 / (rb2.effective_inv_mass + gcross2.gdot(gcross2));
+                        let inv_r = tangents1[j]
+                            .dot(&rb2.effective_inv_mass.component_mul(&tangents1[j]))
+                            + gcross2.gdot(gcross2);
+                        // The translations and rotations of the body may be locked.
+                        let r = if inv_r != 0.0 { 1.0 / inv_r } else { 0.0 };
                         let rhs = (vel1 - vel2
-                            + flipped_multiplier * manifold_point.tangent_velocity)
+                            + flipped_multiplier
+                                * (manifold_point.tangent_velocity
+                                    + manifold_point.friction_anchor_offset
+                                        * friction_anchor_erp_inv_dt))
                             .dot(&tangents1[j]);
 
                         constraint.elements[k].tangent_part.gcross2[j] = gcross2;
@@ -194,6 +243,44 @@ impl VelocityGroundConstraint {
                         constraint.elements[k].tangent_part.r[j] = r;
                     }
                 }
+
+                // Rolling resistance part.
+                {
+                    #[cfg(feature = "dim2")]
+                    let rolling_axes: [Real; DIM - 1] = [1.0];
+                    #[cfg(feature = "dim3")]
+                    let rolling_axes = tangents1;
+                    let rolling_part = &mut constraint.elements[k].rolling_part;
+                    #[cfg(feature = "dim2")]
+                    {
+                        rolling_part.impulse = [0.0];
+                    }
+                    #[cfg(feature = "dim3")]
+                    {
+                        rolling_part.impulse = na::zero();
+                    }
+                    rolling_part.rolling_resistance = manifold_point.rolling_resistance;
+
+                    if manifold_point.rolling_resistance != 0.0 {
+                        for j in 0..DIM - 1 {
+                            let gcross2 = rb2
+                                .effective_world_inv_inertia_sqrt
+                                .transform_vector(-rolling_axes[j]);
+                            let inv_r = gcross2.gdot(gcross2);
+
+                            rolling_part.gcross2[j] = gcross2;
+                            rolling_part.rhs[j] = (rb1.angvel - rb2.angvel).gdot(rolling_axes[j]);
+                            // The rotations of the body may be locked.
+                            rolling_part.r[j] = if inv_r != 0.0 { 1.0 / inv_r } else { 0.0 };
+                        }
+                    } else {
+                        // These rows are still solved if another contact of this constraint
+                        // has some rolling resistance, so they must not apply any impulse.
+                        rolling_part.gcross2 = [na::zero(); DIM - 1];
+                        rolling_part.rhs = [0.0; DIM - 1];
+                        rolling_part.r = [0.0; DIM - 1];
+                    }
+                }
             }
 
             #[cfg(not(target_arch = "wasm32"))]
@@ -207,19 +294,19 @@ impl VelocityGroundConstraint {
     }
 
     pub fn warmstart(&self, mj_lambdas: &mut [DeltaVel<Real>]) {
-        let mut mj_lambda2 = DeltaVel::zero();
+        let mut mj_lambda2 = mj_lambdas[self.mj_lambda2 as usize];
 
         VelocityGroundConstraintElement::warmstart_group(
             &self.elements[..self.num_contacts as usize],
             &self.dir1,
             #[cfg(feature = "dim3")]
             &self.tangent1,
-            self.im2,
+            &self.im2,
+            self.max_mj_dangvel2,
             &mut mj_lambda2,
         );
 
-        mj_lambdas[self.mj_lambda2 as usize].linear += mj_lambda2.linear;
-        mj_lambdas[self.mj_lambda2 as usize].angular += mj_lambda2.angular;
+        mj_lambdas[self.mj_lambda2 as usize] = mj_lambda2;
     }
 
     pub fn solve(&mut self, mj_lambdas: &mut [DeltaVel<Real>]) {
@@ -230,8 +317,24 @@ impl VelocityGroundConstraint {
             &self.dir1,
             #[cfg(feature = "dim3")]
             &self.tangent1,
-            self.im2,
+            &self.im2,
+            self.max_mj_dangvel2,
             self.limit,
+            self.has_rolling_resistance,
+            &mut mj_lambda2,
+        );
+
+        mj_lambdas[self.mj_lambda2 as usize] = mj_lambda2;
+    }
+
+    pub fn solve_restitution(&mut self, mj_lambdas: &mut [DeltaVel<Real>]) {
+        let mut mj_lambda2 = mj_lambdas[self.mj_lambda2 as usize];
+
+        VelocityGroundConstraintElement::solve_restitution_group(
+            &mut self.elements[..self.num_contacts as usize],
+            &self.dir1,
+            &self.im2,
+            self.max_mj_dangvel2,
             &mut mj_lambda2,
         );
 
@@ -241,11 +344,16 @@ impl VelocityGroundConstraint {
     // FIXME: duplicated code. This is exactly the same as in the non-ground velocity constraint.
     pub fn writeback_impulses(&self, manifolds_all: &mut [&mut ContactManifold]) {
         let manifold = &mut manifolds_all[self.manifold_id];
+        manifold.data.effective_mass_multipliers = [1.0; 2];
 
         for k in 0..self.num_contacts as usize {
             let contact_id = self.manifold_contact_id[k];
+            writeback_coefficients(manifold, contact_id);
+            let min_reported_impulse = manifold.data.min_reported_impulse;
             let active_contact = &mut manifold.points[contact_id as usize];
-            active_contact.data.impulse = self.elements[k].normal_part.impulse;
+            active_contact
+                .data
+                .set_impulse(self.elements[k].normal_part.impulse, min_reported_impulse);
             active_contact.data.rhs = self.elements[k].normal_part.rhs;
 
             #[cfg(feature = "dim2")]
@@ -258,6 +366,8 @@ impl VelocityGroundConstraint {
                     .tangent_rot1
                     .inverse_transform_vector(&self.elements[k].tangent_part.impulse);
             }
+
+            active_contact.data.release_sliding_friction_anchor();
         }
     }
 }
@@ -1,4 +1,4 @@
-use super::DeltaVel;
+use super::{cap_contact_angular_increment, DeltaVel};
 use crate::math::{AngVector, Vector, DIM};
 use crate::utils::{WBasis, WDot};
 use na::SimdRealField;
@@ -32,11 +32,11 @@ impl<N: SimdRealField> VelocityGroundConstraintTangentPart<N> {
     pub fn warmstart(
         &self,
         tangents1: [&Vector<N>; DIM - 1],
-        im2: N,
+        im2: &Vector<N>,
         mj_lambda2: &mut DeltaVel<N>,
     ) {
         for j in 0..DIM - 1 {
-            mj_lambda2.linear += tangents1[j] * (-im2 * self.impulse[j]);
+            mj_lambda2.linear += tangents1[j].component_mul(im2) * -self.impulse[j];
             mj_lambda2.angular += self.gcross2[j] * self.impulse[j];
         }
     }
@@ -45,7 +45,7 @@ impl<N: SimdRealField> VelocityGroundConstraintTangentPart<N> {
     pub fn solve(
         &mut self,
         tangents1: [&Vector<N>; DIM - 1],
-        im2: N,
+        im2: &Vector<N>,
         limit: N,
         mj_lambda2: &mut DeltaVel<N>,
     ) where
@@ -61,7 +61,7 @@ impl<N: SimdRealField> VelocityGroundConstraintTangentPart<N> {
             let dlambda = new_impulse - self.impulse[0];
             self.impulse[0] = new_impulse;
 
-            mj_lambda2.linear += tangents1[0] * (-im2 * dlambda);
+            mj_lambda2.linear += tangents1[0].component_mul(im2) * -dlambda;
             mj_lambda2.angular += self.gcross2[0] * dlambda;
         }
 
@@ -83,8 +83,69 @@ impl<N: SimdRealField> VelocityGroundConstraintTangentPart<N> {
 
             self.impulse = new_impulse;
 
-            mj_lambda2.linear +=
-                tangents1[0] * (-im2 * dlambda[0]) + tangents1[1] * (-im2 * dlambda[1]);
+            mj_lambda2.linear += tangents1[0].component_mul(im2) * -dlambda[0]
+                + tangents1[1].component_mul(im2) * -dlambda[1];
+            mj_lambda2.angular += self.gcross2[0] * dlambda[0] + self.gcross2[1] * dlambda[1];
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct VelocityGroundConstraintRollingPart<N: SimdRealField> {
+    pub gcross2: [AngVector<N>; DIM - 1],
+    pub rhs: [N; DIM - 1],
+    #[cfg(feature = "dim2")]
+    pub impulse: [N; DIM - 1],
+    #[cfg(feature = "dim3")]
+    pub impulse: na::Vector2<N>,
+    pub r: [N; DIM - 1],
+    pub rolling_resistance: N,
+}
+
+impl<N: SimdRealField> VelocityGroundConstraintRollingPart<N> {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn zero() -> Self {
+        Self {
+            gcross2: [na::zero(); DIM - 1],
+            rhs: [na::zero(); DIM - 1],
+            #[cfg(feature = "dim2")]
+            impulse: [na::zero(); DIM - 1],
+            #[cfg(feature = "dim3")]
+            impulse: na::zero(),
+            r: [na::zero(); DIM - 1],
+            rolling_resistance: na::zero(),
+        }
+    }
+
+    #[inline]
+    pub fn solve(&mut self, limit: N, mj_lambda2: &mut DeltaVel<N>)
+    where
+        AngVector<N>: WDot<AngVector<N>, Result = N>,
+        N::Element: SimdRealField,
+    {
+        #[cfg(feature = "dim2")]
+        {
+            let dimpulse = self.gcross2[0].gdot(mj_lambda2.angular) + self.rhs[0];
+            let new_impulse = (self.impulse[0] - self.r[0] * dimpulse).simd_clamp(-limit, limit);
+            let dlambda = new_impulse - self.impulse[0];
+            self.impulse[0] = new_impulse;
+
+            mj_lambda2.angular += self.gcross2[0] * dlambda;
+        }
+
+        #[cfg(feature = "dim3")]
+        {
+            let dimpulse_0 = self.gcross2[0].gdot(mj_lambda2.angular) + self.rhs[0];
+            let dimpulse_1 = self.gcross2[1].gdot(mj_lambda2.angular) + self.rhs[1];
+
+            let new_impulse = na::Vector2::new(
+                self.impulse[0] - self.r[0] * dimpulse_0,
+                self.impulse[1] - self.r[1] * dimpulse_1,
+            );
+            let new_impulse = new_impulse.simd_cap_magnitude(limit);
+            let dlambda = new_impulse - self.impulse;
+            self.impulse = new_impulse;
+
             mj_lambda2.angular += self.gcross2[0] * dlambda[0] + self.gcross2[1] * dlambda[1];
         }
     }
@@ -94,6 +155,8 @@ impl<N: SimdRealField> VelocityGroundConstraintTangentPart<N> {
 pub(crate) struct VelocityGroundConstraintNormalPart<N: SimdRealField> {
     pub gcross2: AngVector<N>,
     pub rhs: N,
+    // Right-hand-side of the restitution pass (zero if there is no restitution pass).
+    pub restitution_rhs: N,
     pub impulse: N,
     pub r: N,
 }
@@ -104,19 +167,20 @@ impl<N: SimdRealField> VelocityGroundConstraintNormalPart<N> {
         Self {
             gcross2: na::zero(),
             rhs: na::zero(),
+            restitution_rhs: na::zero(),
             impulse: na::zero(),
             r: na::zero(),
         }
     }
 
     #[inline]
-    pub fn warmstart(&self, dir1: &Vector<N>, im2: N, mj_lambda2: &mut DeltaVel<N>) {
-        mj_lambda2.linear += dir1 * (-im2 * self.impulse);
+    pub fn warmstart(&self, dir1: &Vector<N>, im2: &Vector<N>, mj_lambda2: &mut DeltaVel<N>) {
+        mj_lambda2.linear += dir1.component_mul(im2) * -self.impulse;
         mj_lambda2.angular += self.gcross2 * self.impulse;
     }
 
     #[inline]
-    pub fn solve(&mut self, dir1: &Vector<N>, im2: N, mj_lambda2: &mut DeltaVel<N>)
+    pub fn solve(&mut self, dir1: &Vector<N>, im2: &Vector<N>, mj_lambda2: &mut DeltaVel<N>)
     where
         AngVector<N>: WDot<AngVector<N>, Result = N>,
     {
@@ -126,7 +190,30 @@ impl<N: SimdRealField> VelocityGroundConstraintNormalPart<N> {
         let dlambda = new_impulse - self.impulse;
         self.impulse = new_impulse;
 
-        mj_lambda2.linear += dir1 * (-im2 * dlambda);
+        mj_lambda2.linear += dir1.component_mul(im2) * -dlambda;
+        mj_lambda2.angular += self.gcross2 * dlambda;
+    }
+
+    #[inline]
+    pub fn solve_restitution(
+        &mut self,
+        dir1: &Vector<N>,
+        im2: &Vector<N>,
+        mj_lambda2: &mut DeltaVel<N>,
+    ) where
+        AngVector<N>: WDot<AngVector<N>, Result = N>,
+    {
+        // Only bounce on contacts that were approaching and actually
+        // pushed the bodies apart during the velocity solve.
+        let is_bouncing = self.restitution_rhs.simd_lt(N::zero()) & self.impulse.simd_gt(N::zero());
+        let dimpulse = -dir1.dot(&mj_lambda2.linear)
+            + self.gcross2.gdot(mj_lambda2.angular)
+            + self.restitution_rhs;
+        let new_impulse = (self.impulse - self.r * dimpulse).simd_max(N::zero());
+        let dlambda = (new_impulse - self.impulse).select(is_bouncing, N::zero());
+        self.impulse = self.impulse + dlambda;
+
+        mj_lambda2.linear += dir1.component_mul(im2) * -dlambda;
         mj_lambda2.angular += self.gcross2 * dlambda;
     }
 }
@@ -135,6 +222,7 @@ impl<N: SimdRealField> VelocityGroundConstraintNormalPart<N> {
 pub(crate) struct VelocityGroundConstraintElement<N: SimdRealField> {
     pub normal_part: VelocityGroundConstraintNormalPart<N>,
     pub tangent_part: VelocityGroundConstraintTangentPart<N>,
+    pub rolling_part: VelocityGroundConstraintRollingPart<N>,
 }
 
 impl<N: SimdRealField> VelocityGroundConstraintElement<N> {
@@ -143,6 +231,7 @@ impl<N: SimdRealField> VelocityGroundConstraintElement<N> {
         Self {
             normal_part: VelocityGroundConstraintNormalPart::zero(),
             tangent_part: VelocityGroundConstraintTangentPart::zero(),
+            rolling_part: VelocityGroundConstraintRollingPart::zero(),
         }
     }
 
@@ -151,13 +240,16 @@ impl<N: SimdRealField> VelocityGroundConstraintElement<N> {
         elements: &[Self],
         dir1: &Vector<N>,
         #[cfg(feature = "dim3")] tangent1: &Vector<N>,
-        im2: N,
+        im2: &Vector<N>,
+        max_mj_dangvel2: N,
         mj_lambda2: &mut DeltaVel<N>,
     ) where
         Vector<N>: WBasis,
         AngVector<N>: WDot<AngVector<N>, Result = N>,
         N::Element: SimdRealField,
     {
+        let prev_angular2 = mj_lambda2.angular;
+
         #[cfg(feature = "dim3")]
         let tangents1 = [tangent1, &dir1.cross(&tangent1)];
         #[cfg(feature = "dim2")]
@@ -167,6 +259,8 @@ impl<N: SimdRealField> VelocityGroundConstraintElement<N> {
             element.normal_part.warmstart(dir1, im2, mj_lambda2);
             element.tangent_part.warmstart(tangents1, im2, mj_lambda2);
         }
+
+        cap_contact_angular_increment(mj_lambda2, prev_angular2, max_mj_dangvel2);
     }
 
     #[inline]
@@ -174,14 +268,18 @@ impl<N: SimdRealField> VelocityGroundConstraintElement<N> {
         elements: &mut [Self],
         dir1: &Vector<N>,
         #[cfg(feature = "dim3")] tangent1: &Vector<N>,
-        im2: N,
+        im2: &Vector<N>,
+        max_mj_dangvel2: N,
         limit: N,
+        solve_rolling: bool,
         mj_lambda2: &mut DeltaVel<N>,
     ) where
         Vector<N>: WBasis,
         AngVector<N>: WDot<AngVector<N>, Result = N>,
         N::Element: SimdRealField,
     {
+        let prev_angular2 = mj_lambda2.angular;
+
         // Solve friction.
         #[cfg(feature = "dim3")]
         let tangents1 = [tangent1, &dir1.cross(&tangent1)];
@@ -194,9 +292,41 @@ impl<N: SimdRealField> VelocityGroundConstraintElement<N> {
             part.solve(tangents1, im2, limit, mj_lambda2);
         }
 
+        // Solve rolling resistance.
+        if solve_rolling {
+            for element in elements.iter_mut() {
+                let limit = element.rolling_part.rolling_resistance * element.normal_part.impulse;
+                element.rolling_part.solve(limit, mj_lambda2);
+            }
+        }
+
         // Solve penetration.
         for element in elements.iter_mut() {
             element.normal_part.solve(&dir1, im2, mj_lambda2);
         }
+
+        cap_contact_angular_increment(mj_lambda2, prev_angular2, max_mj_dangvel2);
+    }
+
+    #[inline]
+    pub fn solve_restitution_group(
+        elements: &mut [Self],
+        dir1: &Vector<N>,
+        im2: &Vector<N>,
+        max_mj_dangvel2: N,
+        mj_lambda2: &mut DeltaVel<N>,
+    ) where
+        AngVector<N>: WDot<AngVector<N>, Result = N>,
+        N::Element: SimdRealField,
+    {
+        let prev_angular2 = mj_lambda2.angular;
+
+        for element in elements.iter_mut() {
+            element
+                .normal_part
+                .solve_restitution(&dir1, im2, mj_lambda2);
+        }
+
+        cap_contact_angular_increment(mj_lambda2, prev_angular2, max_mj_dangvel2);
     }
 }
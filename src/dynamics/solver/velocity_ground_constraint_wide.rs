@@ -1,8 +1,8 @@
 use super::{
-    AnyVelocityConstraint, DeltaVel, VelocityGroundConstraintElement,
-    VelocityGroundConstraintNormalPart,
+    writeback_coefficients, AnyVelocityConstraint, DeltaVel, VelocityGroundConstraintElement,
+    VelocityGroundConstraintNormalPart, PADDING_CONTACT_ID,
 };
-use crate::dynamics::{IntegrationParameters, RigidBodySet};
+use crate::dynamics::{IntegrationParameters, RestitutionModel, RigidBodySet};
 use crate::geometry::{ContactManifold, ContactManifoldIndex};
 use crate::math::{
     AngVector, AngularInertia, Point, Real, SimdReal, Vector, DIM, MAX_MANIFOLD_POINTS, SIMD_WIDTH,
@@ -10,6 +10,7 @@ use crate::math::{
 #[cfg(feature = "dim2")]
 use crate::utils::WBasis;
 use crate::utils::{WAngularInertia, WCross, WDot};
+use alloc::vec::Vec;
 use na::SimdComplexField;
 use num::Zero;
 use simba::simd::{SimdPartialOrd, SimdValue};
@@ -26,8 +27,14 @@ pub(crate) struct WVelocityGroundConstraint {
     pub tangent_rot1: na::UnitComplex<SimdReal>,
     pub elements: [VelocityGroundConstraintElement<SimdReal>; MAX_MANIFOLD_POINTS],
     pub num_contacts: u8,
-    pub im2: SimdReal,
+    pub im2: Vector<SimdReal>,
+    // Maximum angular velocity increment the contacts can apply to the dynamic body, multiplied
+    // by the square root of its angular inertia tensor.
+    pub max_mj_dangvel2: SimdReal,
     pub limit: SimdReal,
+    // Does any contact of the manifolds of this group have a non-zero rolling resistance? If
+    // not, the rolling resistance rows are neither built nor solved.
+    pub has_rolling_resistance: bool,
     pub mj_lambda2: [usize; SIMD_WIDTH],
     pub manifold_id: [ContactManifoldIndex; SIMD_WIDTH],
     pub manifold_contact_id: [[u8; SIMD_WIDTH]; MAX_MANIFOLD_POINTS],
@@ -44,7 +51,14 @@ impl WVelocityGroundConstraint {
     ) {
         let inv_dt = SimdReal::splat(params.inv_dt());
         let velocity_solve_fraction = SimdReal::splat(params.velocity_solve_fraction);
-        let velocity_based_erp_inv_dt = SimdReal::splat(params.velocity_based_erp_inv_dt());
+        let post_solve_restitution = SimdReal::splat(
+            (params.restitution_model == RestitutionModel::PostSolve) as u32 as Real,
+        );
+        let rhs_restitution = SimdReal::splat(1.0) - post_solve_restitution;
+        let velocity_based_erp_inv_dt =
+            SimdReal::from(array![|ii| manifolds[ii].data.erp_multiplier; SIMD_WIDTH])
+                * SimdReal::splat(params.velocity_based_erp_inv_dt());
+        let friction_anchor_erp_inv_dt = SimdReal::splat(params.friction_anchor_erp_inv_dt());
 
         let mut rbs1 = array![|ii| &bodies[manifolds[ii].data.body_pair.body1]; SIMD_WIDTH];
         let mut rbs2 = array![|ii| &bodies[manifolds[ii].data.body_pair.body2]; SIMD_WIDTH];
@@ -52,14 +66,17 @@ impl WVelocityGroundConstraint {
 
         for ii in 0..SIMD_WIDTH {
             if manifolds[ii].data.relative_dominance < 0 {
-                std::mem::swap(&mut rbs1[ii], &mut rbs2[ii]);
+                core::mem::swap(&mut rbs1[ii], &mut rbs2[ii]);
                 flipped[ii] = -1.0;
             }
         }
 
         let flipped_sign = SimdReal::from(flipped);
 
-        let im2 = SimdReal::from(array![|ii| rbs2[ii].effective_inv_mass; SIMD_WIDTH]);
+        // NOTE: the ground sides already behave as if they had an infinite mass, so they are never
+        //       affected by `IntegrationParameters::max_effective_mass_ratio`. Raising the mass of
+        //       the dynamic bodies would only scale the impulses, not their velocity response.
+        let im2 = Vector::from(array![|ii| rbs2[ii].effective_inv_mass; SIMD_WIDTH]);
         let ii2: AngularInertia<SimdReal> = AngularInertia::from(
             array![|ii| rbs2[ii].effective_world_inv_inertia_sqrt; SIMD_WIDTH],
         );
@@ -77,12 +94,27 @@ impl WVelocityGroundConstraint {
         let force_dir1 = normal1 * -flipped_sign;
 
         let mj_lambda2 = array![|ii| rbs2[ii].active_set_offset; SIMD_WIDTH];
+        let max_mj_dangvel2 =
+            SimdReal::from(array![|ii| rbs2[ii].max_contact_mj_dangvel(params.dt); SIMD_WIDTH]);
+
+        // Velocity change of the dynamic bodies due to gravity (and other external forces)
+        // that each contact has to cancel when the ground side cannot yield.
+        let support_dvel = Vector::from(array![|ii|
+            if params.gravity_compensation_on_ground_contacts && !rbs1[ii].is_dynamic() {
+                rbs2[ii].force.component_mul(&rbs2[ii].effective_inv_mass)
+                    * (params.dt / manifolds[ii].data.solver_contacts.len() as Real)
+            } else {
+                na::zero()
+            }; SIMD_WIDTH]);
 
         let warmstart_multiplier =
             SimdReal::from(array![|ii| manifolds[ii].data.warmstart_multiplier; SIMD_WIDTH]);
         let warmstart_coeff = warmstart_multiplier * SimdReal::splat(params.warmstart_coeff);
         let warmstart_correction_slope = SimdReal::splat(params.warmstart_correction_slope);
         let num_active_contacts = manifolds[0].data.num_active_contacts();
+        let has_rolling_resistance = manifolds
+            .iter()
+            .any(|manifold| manifold.data.has_rolling_resistance);
 
         #[cfg(feature = "dim2")]
         let tangents1 = force_dir1.orthonormal_basis();
@@ -92,7 +124,7 @@ impl WVelocityGroundConstraint {
 
         for l in (0..num_active_contacts).step_by(MAX_MANIFOLD_POINTS) {
             let manifold_points = array![|ii| &manifolds[ii].data.solver_contacts[l..]; SIMD_WIDTH];
-            let num_points = manifold_points[0].len().min(MAX_MANIFOLD_POINTS);
+            let num_points = super::max_lane_contacts(&manifold_points);
 
             let mut constraint = WVelocityGroundConstraint {
                 dir1: force_dir1,
@@ -102,7 +134,9 @@ impl WVelocityGroundConstraint {
                 tangent_rot1,
                 elements: [VelocityGroundConstraintElement::zero(); MAX_MANIFOLD_POINTS],
                 im2,
+                max_mj_dangvel2,
                 limit: SimdReal::splat(0.0),
+                has_rolling_resistance,
                 mj_lambda2,
                 manifold_id,
                 manifold_contact_id: [[0; SIMD_WIDTH]; MAX_MANIFOLD_POINTS],
@@ -110,24 +144,23 @@ impl WVelocityGroundConstraint {
             };
 
             for k in 0..num_points {
-                let friction =
-                    SimdReal::from(array![|ii| manifold_points[ii][k].friction; SIMD_WIDTH]);
-                let restitution =
-                    SimdReal::from(array![|ii| manifold_points[ii][k].restitution; SIMD_WIDTH]);
+                let (contacts, is_contact) = super::select_padded_contacts(&manifold_points, k);
+                let friction = SimdReal::from(array![|ii| contacts[ii].friction; SIMD_WIDTH]);
+                let restitution = SimdReal::from(array![|ii| contacts[ii].restitution; SIMD_WIDTH]);
                 let is_bouncy = SimdReal::from(
-                    array![|ii| manifold_points[ii][k].is_bouncy() as u32 as Real; SIMD_WIDTH],
+                    array![|ii| contacts[ii].is_bouncy() as u32 as Real; SIMD_WIDTH],
                 );
                 let is_resting = SimdReal::splat(1.0) - is_bouncy;
-                let point = Point::from(array![|ii| manifold_points[ii][k].point; SIMD_WIDTH]);
-                let dist = SimdReal::from(array![|ii| manifold_points[ii][k].dist; SIMD_WIDTH]);
+                let point = Point::from(array![|ii| contacts[ii].point; SIMD_WIDTH]);
+                let dist = SimdReal::from(array![|ii| contacts[ii].dist_from_target(); SIMD_WIDTH]);
                 let tangent_velocity =
-                    Vector::from(array![|ii| manifold_points[ii][k].tangent_velocity; SIMD_WIDTH]);
+                    Vector::from(array![|ii| contacts[ii].tangent_velocity; SIMD_WIDTH]);
+                let friction_anchor_offset =
+                    Vector::from(array![|ii| contacts[ii].friction_anchor_offset; SIMD_WIDTH]);
 
-                let impulse = SimdReal::from(
-                    array![|ii| manifold_points[ii][k].warmstart_impulse; SIMD_WIDTH],
-                );
-                let prev_rhs =
-                    SimdReal::from(array![|ii| manifold_points[ii][k].prev_rhs; SIMD_WIDTH]);
+                let impulse =
+                    SimdReal::from(array![|ii| contacts[ii].warmstart_impulse; SIMD_WIDTH]);
+                let prev_rhs = SimdReal::from(array![|ii| contacts[ii].prev_rhs; SIMD_WIDTH]);
                 let dp1 = point - world_com1;
                 let dp2 = point - world_com2;
 
@@ -136,17 +169,26 @@ impl WVelocityGroundConstraint {
                 let warmstart_correction;
 
                 constraint.limit = friction;
-                constraint.manifold_contact_id[k] =
-                    array![|ii| manifold_points[ii][k].contact_id; SIMD_WIDTH];
+                constraint.manifold_contact_id[k] = array![|ii|
+                    if k < manifold_points[ii].len() {
+                        contacts[ii].contact_id
+                    } else {
+                        PADDING_CONTACT_ID
+                    }; SIMD_WIDTH];
 
                 // Normal part.
                 {
                     let gcross2 = ii2.transform_vector(dp2.gcross(-force_dir1));
 
-                    let r = SimdReal::splat(1.0) / (im2 + gcross2.gdot(gcross2));
+                    let inv_r =
+                        force_dir1.dot(&im2.component_mul(&force_dir1)) + gcross2.gdot(gcross2);
+                    // The translations and rotations of the body may be locked.
+                    let r = (SimdReal::splat(1.0) / inv_r)
+                        .select(inv_r.simd_gt(SimdReal::zero()), SimdReal::zero());
                     let projected_velocity = (vel1 - vel2).dot(&force_dir1);
-                    let mut rhs =
-                        (SimdReal::splat(1.0) + is_bouncy * restitution) * projected_velocity;
+                    let mut rhs = (SimdReal::splat(1.0)
+                        + is_bouncy * rhs_restitution * restitution)
+                        * projected_velocity;
                     rhs += dist.simd_max(SimdReal::zero()) * inv_dt;
                     rhs *= is_bouncy + is_resting * velocity_solve_fraction;
                     rhs +=
@@ -154,36 +196,77 @@ impl WVelocityGroundConstraint {
                     warmstart_correction = (warmstart_correction_slope
                         / (rhs - prev_rhs).simd_abs())
                     .simd_min(warmstart_coeff);
+                    let support_impulse =
+                        r * force_dir1.dot(&support_dvel).simd_max(SimdReal::zero());
+                    let restitution_rhs = is_bouncy
+                        * post_solve_restitution
+                        * (SimdReal::splat(1.0) + restitution)
+                        * projected_velocity.simd_min(SimdReal::zero());
 
                     constraint.elements[k].normal_part = VelocityGroundConstraintNormalPart {
-                        gcross2,
-                        rhs,
-                        impulse: impulse * warmstart_correction,
-                        r,
+                        gcross2: gcross2 * is_contact,
+                        rhs: rhs * is_contact,
+                        restitution_rhs: restitution_rhs * is_contact,
+                        impulse: (impulse * warmstart_correction).simd_max(support_impulse)
+                            * is_contact,
+                        r: r * is_contact,
                     };
                 }
 
                 // tangent parts.
                 #[cfg(feature = "dim2")]
                 let impulse = [SimdReal::from(
-                    array![|ii| manifold_points[ii][k].warmstart_tangent_impulse; SIMD_WIDTH],
-                ) * warmstart_correction];
+                    array![|ii| contacts[ii].warmstart_tangent_impulse; SIMD_WIDTH],
+                ) * warmstart_correction
+                    * is_contact];
                 #[cfg(feature = "dim3")]
                 let impulse = tangent_rot1
                     * na::Vector2::from(
-                        array![|ii| manifold_points[ii][k].warmstart_tangent_impulse; SIMD_WIDTH],
+                        array![|ii| contacts[ii].warmstart_tangent_impulse; SIMD_WIDTH],
                     )
-                    * warmstart_correction;
+                    * (warmstart_correction * is_contact);
                 constraint.elements[k].tangent_part.impulse = impulse;
 
                 for j in 0..DIM - 1 {
                     let gcross2 = ii2.transform_vector(dp2.gcross(-tangents1[j]));
-                    let r = SimdReal::splat(1.0) / (im2 + gcross2.gdot(gcross2));
-                    let rhs = (vel1 - vel2 + tangent_velocity * flipped_sign).dot(&tangents1[j]);
+                    let inv_r =
+                        tangents1[j].dot(&im2.component_mul(&tangents1[j])) + gcross2.gdot(gcross2);
+                    // The translations and rotations of the body may be locked.
+                    let r = (SimdReal::splat(1.0) / inv_r)
+                        .select(inv_r.simd_gt(SimdReal::zero()), SimdReal::zero());
+                    let rhs = (vel1 - vel2
+                        + (tangent_velocity + friction_anchor_offset * friction_anchor_erp_inv_dt)
+                            * flipped_sign)
+                        .dot(&tangents1[j]);
+
+                    constraint.elements[k].tangent_part.gcross2[j] = gcross2 * is_contact;
+                    constraint.elements[k].tangent_part.r[j] = r * is_contact;
+                    constraint.elements[k].tangent_part.rhs[j] = rhs * is_contact;
+                }
 
-                    constraint.elements[k].tangent_part.gcross2[j] = gcross2;
-                    constraint.elements[k].tangent_part.r[j] = r;
-                    constraint.elements[k].tangent_part.rhs[j] = rhs;
+                // Rolling resistance part.
+                if has_rolling_resistance {
+                    #[cfg(feature = "dim2")]
+                    let rolling_axes = [SimdReal::splat(1.0)];
+                    #[cfg(feature = "dim3")]
+                    let rolling_axes = tangents1;
+                    let rolling_resistance =
+                        SimdReal::from(array![|ii| contacts[ii].rolling_resistance; SIMD_WIDTH]);
+                    let rolling_part = &mut constraint.elements[k].rolling_part;
+                    rolling_part.rolling_resistance = rolling_resistance * is_contact;
+
+                    for j in 0..DIM - 1 {
+                        let gcross2 = ii2.transform_vector(-rolling_axes[j]);
+                        let inv_r = gcross2.gdot(gcross2);
+                        // The rotations of the body may be locked.
+                        let r = (SimdReal::splat(1.0) / inv_r)
+                            .select(inv_r.simd_gt(SimdReal::zero()), SimdReal::zero());
+                        let rhs = (angvel1 - angvel2).gdot(rolling_axes[j]);
+
+                        rolling_part.gcross2[j] = gcross2 * is_contact;
+                        rolling_part.rhs[j] = rhs * is_contact;
+                        rolling_part.r[j] = r * is_contact;
+                    }
                 }
             }
 
@@ -211,7 +294,8 @@ impl WVelocityGroundConstraint {
             &self.dir1,
             #[cfg(feature = "dim3")]
             &self.tangent1,
-            self.im2,
+            &self.im2,
+            self.max_mj_dangvel2,
             &mut mj_lambda2,
         );
 
@@ -236,8 +320,34 @@ impl WVelocityGroundConstraint {
             &self.dir1,
             #[cfg(feature = "dim3")]
             &self.tangent1,
-            self.im2,
+            &self.im2,
+            self.max_mj_dangvel2,
             self.limit,
+            self.has_rolling_resistance,
+            &mut mj_lambda2,
+        );
+
+        for ii in 0..SIMD_WIDTH {
+            mj_lambdas[self.mj_lambda2[ii] as usize].linear = mj_lambda2.linear.extract(ii);
+            mj_lambdas[self.mj_lambda2[ii] as usize].angular = mj_lambda2.angular.extract(ii);
+        }
+    }
+
+    pub fn solve_restitution(&mut self, mj_lambdas: &mut [DeltaVel<Real>]) {
+        let mut mj_lambda2 = DeltaVel {
+            linear: Vector::from(
+                array![|ii| mj_lambdas[ self.mj_lambda2[ii] as usize].linear; SIMD_WIDTH],
+            ),
+            angular: AngVector::from(
+                array![|ii| mj_lambdas[ self.mj_lambda2[ii] as usize].angular; SIMD_WIDTH],
+            ),
+        };
+
+        VelocityGroundConstraintElement::solve_restitution_group(
+            &mut self.elements[..self.num_contacts as usize],
+            &self.dir1,
+            &self.im2,
+            self.max_mj_dangvel2,
             &mut mj_lambda2,
         );
 
@@ -249,6 +359,12 @@ impl WVelocityGroundConstraint {
 
     // FIXME: duplicated code. This is exactly the same as in the non-ground velocity constraint.
     pub fn writeback_impulses(&self, manifolds_all: &mut [&mut ContactManifold]) {
+        for ii in 0..SIMD_WIDTH {
+            manifolds_all[self.manifold_id[ii]]
+                .data
+                .effective_mass_multipliers = [1.0; 2];
+        }
+
         for k in 0..self.num_contacts as usize {
             let rhs: [_; SIMD_WIDTH] = self.elements[k].normal_part.rhs.into();
             let impulses: [_; SIMD_WIDTH] = self.elements[k].normal_part.impulse.into();
@@ -260,11 +376,19 @@ impl WVelocityGroundConstraint {
                 .inverse_transform_vector(&self.elements[k].tangent_part.impulse);
 
             for ii in 0..SIMD_WIDTH {
-                let manifold = &mut manifolds_all[self.manifold_id[ii]];
                 let contact_id = self.manifold_contact_id[k][ii];
+                if contact_id == PADDING_CONTACT_ID {
+                    continue;
+                }
+
+                let manifold = &mut manifolds_all[self.manifold_id[ii]];
+                writeback_coefficients(manifold, contact_id);
+                let min_reported_impulse = manifold.data.min_reported_impulse;
                 let active_contact = &mut manifold.points[contact_id as usize];
                 active_contact.data.rhs = rhs[ii];
-                active_contact.data.impulse = impulses[ii];
+                active_contact
+                    .data
+                    .set_impulse(impulses[ii], min_reported_impulse);
 
                 #[cfg(feature = "dim2")]
                 {
@@ -274,6 +398,8 @@ impl WVelocityGroundConstraint {
                 {
                     active_contact.data.tangent_impulse = tangent_impulses.extract(ii);
                 }
+
+                active_contact.data.release_sliding_friction_anchor();
             }
         }
     }
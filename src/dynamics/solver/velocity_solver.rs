@@ -1,11 +1,13 @@
-use super::AnyJointVelocityConstraint;
+use super::{solve_extra_iterations, AnyJointVelocityConstraint};
 use crate::dynamics::{
     solver::{AnyVelocityConstraint, DeltaVel},
-    IntegrationParameters, JointGraphEdge, RigidBodySet,
+    IntegrationParameters, JointGraphEdge, PositionOffsetsMap, RestitutionModel, RigidBodySet,
+    UserVelocityConstraint,
 };
 use crate::geometry::ContactManifold;
 use crate::math::Real;
 use crate::utils::WAngularInertia;
+use alloc::vec::Vec;
 
 pub(crate) struct VelocitySolver {
     pub mj_lambdas: Vec<DeltaVel<Real>>,
@@ -26,7 +28,11 @@ impl VelocitySolver {
         manifolds_all: &mut [&mut ContactManifold],
         joints_all: &mut [JointGraphEdge],
         contact_constraints: &mut [AnyVelocityConstraint],
+        extra_contact_constraints: &[(usize, u8)],
         joint_constraints: &mut [AnyJointVelocityConstraint],
+        extra_joint_constraints: &[(usize, u8)],
+        user_constraints: &mut [&mut dyn UserVelocityConstraint],
+        offsets: &PositionOffsetsMap,
     ) {
         self.mj_lambdas.clear();
         self.mj_lambdas
@@ -36,7 +42,7 @@ impl VelocitySolver {
         bodies.foreach_active_island_body_mut_internal(island_id, |_, rb| {
             let dvel = &mut self.mj_lambdas[rb.active_set_offset];
 
-            dvel.linear += rb.force * (rb.effective_inv_mass * params.dt);
+            dvel.linear += rb.force.component_mul(&rb.effective_inv_mass) * params.dt;
             rb.force = na::zero();
 
             // dvel.angular is actually storing angular velocity delta multiplied by the square root of the inertia tensor:
@@ -44,6 +50,10 @@ impl VelocitySolver {
             rb.torque = na::zero();
         });
 
+        for constraint in &mut *user_constraints {
+            constraint.initialize(params, bodies, offsets);
+        }
+
         /*
          * Warmstart constraints.
          */
@@ -51,6 +61,10 @@ impl VelocitySolver {
             constraint.warmstart(&mut self.mj_lambdas[..]);
         }
 
+        for constraint in &*user_constraints {
+            constraint.warmstart(&mut self.mj_lambdas[..]);
+        }
+
         for constraint in &*contact_constraints {
             constraint.warmstart(&mut self.mj_lambdas[..]);
         }
@@ -63,11 +77,34 @@ impl VelocitySolver {
                 constraint.solve(&mut self.mj_lambdas[..]);
             }
 
+            for constraint in &mut *user_constraints {
+                constraint.solve(&mut self.mj_lambdas[..]);
+            }
+
             for constraint in &mut *contact_constraints {
                 constraint.solve(&mut self.mj_lambdas[..]);
             }
         }
 
+        solve_extra_iterations(
+            &mut self.mj_lambdas[..],
+            joint_constraints,
+            extra_joint_constraints,
+            contact_constraints,
+            extra_contact_constraints,
+        );
+
+        /*
+         * Apply restitution.
+         */
+        if params.restitution_model == RestitutionModel::PostSolve {
+            for _ in 0..params.max_velocity_iterations {
+                for constraint in &mut *contact_constraints {
+                    constraint.solve_restitution(&mut self.mj_lambdas[..]);
+                }
+            }
+        }
+
         // Update velocities.
         bodies.foreach_active_island_body_mut_internal(island_id, |_, rb| {
             let dvel = self.mj_lambdas[rb.active_set_offset];
@@ -85,5 +122,9 @@ impl VelocitySolver {
         for constraint in &*contact_constraints {
             constraint.writeback_impulses(manifolds_all);
         }
+
+        for constraint in &mut *user_constraints {
+            constraint.writeback();
+        }
     }
 }
@@ -0,0 +1,264 @@
+use crate::data::arena::Arena;
+use crate::data::HashMap;
+use crate::dynamics::{DeltaVel, IntegrationParameters, RigidBodyHandle, RigidBodySet};
+use crate::math::{Isometry, Real};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+/// The unique identifier of a user-defined constraint added to a `UserConstraintSet`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct UserConstraintHandle(pub(crate) crate::data::arena::Index);
+
+impl UserConstraintHandle {
+    /// Converts this handle into its (index, generation) components.
+    pub fn into_raw_parts(self) -> (usize, u64) {
+        self.0.into_raw_parts()
+    }
+
+    /// Reconstructs an handle from its (index, generation) components.
+    pub fn from_raw_parts(id: usize, generation: u64) -> Self {
+        Self(crate::data::arena::Index::from_raw_parts(id, generation))
+    }
+
+    /// An always-invalid user constraint handle.
+    pub fn invalid() -> Self {
+        Self(crate::data::arena::Index::from_raw_parts(
+            crate::INVALID_USIZE,
+            crate::INVALID_U64,
+        ))
+    }
+}
+
+/// Maps rigid-body handles to their index in the positions buffer of the position solver, and in
+/// the velocity changes buffer of the velocity solver.
+// NOTE: this only contains data about the island being solved, so the user constraints can be
+//       solved while the other islands are modified by the `parallel` solver.
+#[derive(Clone, Debug, Default)]
+pub struct PositionOffsetsMap {
+    offsets: HashMap<RigidBodyHandle, usize>,
+}
+
+impl PositionOffsetsMap {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Collects the offsets of the active dynamic bodies of the island `island_id`.
+    pub(crate) fn update(&mut self, bodies: &RigidBodySet, island_id: usize) {
+        let island = bodies.active_island(island_id);
+        self.offsets.clear();
+        // NOTE: the offset of a body is its index into its active island.
+        self.offsets
+            .extend(island.iter().enumerate().map(|(i, handle)| (*handle, i)));
+    }
+
+    /// The index of the rigid-body `handle` in the positions or velocity changes buffer given to
+    /// the user constraints.
+    ///
+    /// Returns `None` if the rigid-body is not an active dynamic body of the island being solved.
+    /// The position and velocity of such a body must be considered as fixed by the constraint.
+    pub fn get(&self, handle: RigidBodyHandle) -> Option<usize> {
+        self.offsets.get(&handle).copied()
+    }
+}
+
+/// A user-defined constraint solved by the position solver.
+pub trait UserPositionConstraint: Send + Sync {
+    /// The rigid-bodies affected by this constraint.
+    ///
+    /// The constraint is solved as part of the island of the first active dynamic body of this list.
+    /// Note that user constraints don't merge islands: bodies from other islands won't be
+    /// part of the positions buffer given to `Self::solve`.
+    fn bodies(&self) -> &[RigidBodyHandle];
+
+    /// Solves this constraint.
+    ///
+    /// This is called at each position iteration, right after the joint position constraints.
+    /// The `positions` are the positions of the active dynamic bodies of the island being solved.
+    /// Use `offsets` to find the index of a given rigid-body into `positions`.
+    fn solve(
+        &mut self,
+        params: &IntegrationParameters,
+        positions: &mut [Isometry<Real>],
+        offsets: &PositionOffsetsMap,
+    );
+}
+
+/// A user-defined constraint solved by the velocity solver.
+///
+/// The velocity solver accumulates the velocity changes of the bodies of an island into a
+/// buffer of `DeltaVel`, iteratively updated by each constraint. The velocity of a body
+/// at any point of the resolution is its velocity at the beginning of the timestep, plus its
+/// velocity change.
+pub trait UserVelocityConstraint: Send + Sync {
+    /// The rigid-bodies affected by this constraint.
+    ///
+    /// The constraint is solved as part of the island of the first active dynamic body of this list.
+    /// Note that user constraints don't merge islands: bodies from other islands won't be
+    /// part of the velocity changes buffer given to `Self::solve`.
+    fn bodies(&self) -> &[RigidBodyHandle];
+
+    /// Initializes this constraint for the current timestep.
+    ///
+    /// This is called once per timestep, before `Self::warmstart`. The velocities of the `bodies`
+    /// are their velocities at the beginning of the timestep, and the velocity changes due to
+    /// the external forces are already part of the buffer given to the next methods.
+    /// Use `offsets` to find the index of a given rigid-body into this buffer.
+    fn initialize(
+        &mut self,
+        params: &IntegrationParameters,
+        bodies: &RigidBodySet,
+        offsets: &PositionOffsetsMap,
+    );
+
+    /// Applies the impulses accumulated during the previous timestep, if any.
+    ///
+    /// This is called once per timestep, after `Self::initialize`.
+    fn warmstart(&self, mj_lambdas: &mut [DeltaVel<Real>]);
+
+    /// Solves this constraint by updating the velocity changes of its bodies.
+    ///
+    /// This is called at each velocity iteration, right after the joint velocity constraints.
+    fn solve(&mut self, mj_lambdas: &mut [DeltaVel<Real>]);
+
+    /// Called once per timestep, after the last velocity iteration, to let the constraint save
+    /// its accumulated impulses for the warmstart of the next timestep.
+    fn writeback(&mut self);
+}
+
+/// A set of user-defined constraints solved alongside the built-in constraints.
+pub struct UserConstraintSet {
+    constraints: Arena<Box<dyn UserPositionConstraint>>,
+    velocity_constraints: Arena<Box<dyn UserVelocityConstraint>>,
+}
+
+impl Default for UserConstraintSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UserConstraintSet {
+    /// Creates a new empty set of user constraints.
+    pub fn new() -> Self {
+        Self {
+            constraints: Arena::new(),
+            velocity_constraints: Arena::new(),
+        }
+    }
+
+    /// The number of user constraints on this set.
+    pub fn len(&self) -> usize {
+        self.constraints.len() + self.velocity_constraints.len()
+    }
+
+    /// `true` if there are no user constraints on this set.
+    pub fn is_empty(&self) -> bool {
+        self.constraints.is_empty() && self.velocity_constraints.is_empty()
+    }
+
+    /// Inserts a new user position constraint into this set and retrieves its handle.
+    pub fn insert(&mut self, constraint: Box<dyn UserPositionConstraint>) -> UserConstraintHandle {
+        UserConstraintHandle(self.constraints.insert(constraint))
+    }
+
+    /// Removes a user constraint from this set.
+    pub fn remove(
+        &mut self,
+        handle: UserConstraintHandle,
+    ) -> Option<Box<dyn UserPositionConstraint>> {
+        self.constraints.remove(handle.0)
+    }
+
+    /// Gets the user constraint with the given handle.
+    pub fn get(&self, handle: UserConstraintHandle) -> Option<&dyn UserPositionConstraint> {
+        self.constraints.get(handle.0).map(|c| &**c)
+    }
+
+    /// Gets a mutable reference to the user constraint with the given handle.
+    pub fn get_mut(
+        &mut self,
+        handle: UserConstraintHandle,
+    ) -> Option<&mut (dyn UserPositionConstraint + 'static)> {
+        self.constraints.get_mut(handle.0).map(|c| &mut **c)
+    }
+
+    /// Inserts a new user velocity constraint into this set and retrieves its handle.
+    ///
+    /// The handles of velocity constraints are only valid for the `*_velocity_constraint`
+    /// methods of this set.
+    pub fn insert_velocity_constraint(
+        &mut self,
+        constraint: Box<dyn UserVelocityConstraint>,
+    ) -> UserConstraintHandle {
+        UserConstraintHandle(self.velocity_constraints.insert(constraint))
+    }
+
+    /// Removes a user velocity constraint from this set.
+    pub fn remove_velocity_constraint(
+        &mut self,
+        handle: UserConstraintHandle,
+    ) -> Option<Box<dyn UserVelocityConstraint>> {
+        self.velocity_constraints.remove(handle.0)
+    }
+
+    /// Gets the user velocity constraint with the given handle.
+    pub fn get_velocity_constraint(
+        &self,
+        handle: UserConstraintHandle,
+    ) -> Option<&dyn UserVelocityConstraint> {
+        self.velocity_constraints.get(handle.0).map(|c| &**c)
+    }
+
+    /// Gets a mutable reference to the user velocity constraint with the given handle.
+    pub fn get_velocity_constraint_mut(
+        &mut self,
+        handle: UserConstraintHandle,
+    ) -> Option<&mut (dyn UserVelocityConstraint + 'static)> {
+        self.velocity_constraints
+            .get_mut(handle.0)
+            .map(|c| &mut **c)
+    }
+
+    // The island of the first active dynamic body of the list, if any.
+    fn island_of(bodies: &RigidBodySet, handles: &[RigidBodyHandle]) -> Option<usize> {
+        handles.iter().find_map(|handle| {
+            let rb = bodies.get(*handle)?;
+            let is_active = bodies.active_dynamic_set.get(rb.active_set_id) == Some(handle);
+            Some(rb.active_island_id).filter(|_| is_active)
+        })
+    }
+
+    /// Dispatches the user constraints to the islands they have to be solved with.
+    pub(crate) fn select_active_constraints<'a>(
+        &'a mut self,
+        bodies: &RigidBodySet,
+        out: &mut Vec<Vec<&'a mut dyn UserPositionConstraint>>,
+    ) {
+        out.clear();
+        out.resize_with(bodies.num_islands(), Vec::new);
+
+        for (_, constraint) in self.constraints.iter_mut() {
+            if let Some(island_id) = Self::island_of(bodies, constraint.bodies()) {
+                out[island_id].push(&mut **constraint);
+            }
+        }
+    }
+
+    /// Dispatches the user velocity constraints to the islands they have to be solved with.
+    pub(crate) fn select_active_velocity_constraints<'a>(
+        &'a mut self,
+        bodies: &RigidBodySet,
+        out: &mut Vec<Vec<&'a mut dyn UserVelocityConstraint>>,
+    ) {
+        out.clear();
+        out.resize_with(bodies.num_islands(), Vec::new);
+
+        for (_, constraint) in self.velocity_constraints.iter_mut() {
+            if let Some(island_id) = Self::island_of(bodies, constraint.bodies()) {
+                out[island_id].push(&mut **constraint);
+            }
+        }
+    }
+}
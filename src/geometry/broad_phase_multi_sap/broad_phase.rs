@@ -2,14 +2,56 @@ use super::{
     BroadPhasePairEvent, ColliderPair, SAPLayer, SAPProxies, SAPProxy, SAPProxyData, SAPRegionPool,
 };
 use crate::data::pubsub::Subscription;
+use crate::data::HashSet;
+use crate::dynamics::{RigidBodyHandle, RigidBodySet};
 use crate::geometry::broad_phase_multi_sap::SAPProxyIndex;
 use crate::geometry::collider::ColliderChanges;
-use crate::geometry::{ColliderSet, RemovedCollider};
-use crate::math::Real;
+use crate::geometry::{
+    Collider, ColliderAggregate, ColliderHandle, ColliderSet, RemovedCollider, AABB,
+};
+use crate::math::{Point, Real};
 use crate::utils::IndexMut2;
+use alloc::{vec, vec::Vec};
 use parry::bounding_volume::BoundingVolume;
 use parry::utils::hashmap::HashMap;
 
+/// Statistics about the proxies and regions tracked by the broad-phase.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct BroadPhaseStats {
+    /// The number of proxies, i.e., the number of colliders not part of an aggregate, plus the
+    /// number of aggregates and the number of regions.
+    pub num_proxies: usize,
+    /// The number of layers of the hierarchical grid.
+    pub num_layers: usize,
+    /// The number of regions of all the layers of the hierarchical grid.
+    pub num_regions: usize,
+    /// The approximate number of bytes allocated by the broad-phase.
+    ///
+    /// This is only computed if `detailed` was set to `true` when requesting the statistics, and
+    /// zero otherwise.
+    pub bytes: usize,
+    /// The largest number of proxies since the last request.
+    pub peak_proxies: usize,
+    /// The largest number of regions since the last request.
+    pub peak_regions: usize,
+}
+
+/// The integer coordinates of a cell of the grid used by `BroadPhase::density_report`.
+///
+/// The cell with coordinates `c` covers the points `p` such that `c * cell_size <= p < (c + 1) * cell_size`.
+pub type CellCoord = Point<i32>;
+
+/// The number of colliders and rigid-bodies in one cell of a `BroadPhase::density_report`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct RegionStats {
+    /// The number of colliders with an AABB centered in this cell.
+    pub colliders: usize,
+    /// The number of awake non-static rigid-bodies with at least one collider counted in this cell.
+    pub awake_bodies: usize,
+    /// The number of sleeping non-static rigid-bodies with at least one collider counted in this cell.
+    pub sleeping_bodies: usize,
+}
+
 /// A broad-phase combining a Hierarchical Grid and Sweep-and-Prune.
 ///
 /// The basic Sweep-and-Prune (SAP) algorithm has one significant flaw:
@@ -80,6 +122,14 @@ pub struct BroadPhase {
     removed_colliders: Option<Subscription<RemovedCollider>>,
     deleted_any: bool,
     #[cfg_attr(feature = "serde-serialize", serde(skip))]
+    // Workspace
+    moved_proxies: Vec<SAPProxyIndex>,
+    // The interaction masks of the user-defined broad-phase layers
+    // (see `Collider::broad_phase_layer`).
+    filter_layer_masks: [u32; BroadPhase::MAX_LAYERS],
+    // The user-defined broad-phase layers which had their mask modified since the last update.
+    modified_filter_layers: u32,
+    #[cfg_attr(feature = "serde-serialize", serde(skip))]
     // To avoid repeated allocations.
     region_pool: SAPRegionPool,
     // We could think serializing this workspace is useless.
@@ -102,20 +152,272 @@ pub struct BroadPhase {
     )]
     // Workspace
     reporting: HashMap<(u32, u32), bool>,
+    #[cfg_attr(feature = "serde-serialize", serde(skip))]
+    // Workspace
+    added_aggregate_children: Vec<(ColliderHandle, RigidBodyHandle)>,
+    #[cfg_attr(feature = "serde-serialize", serde(skip))]
+    // Workspace
+    updated_aggregates: Vec<RigidBodyHandle>,
+    // The pairs of colliders found for each overlapping pair of proxies involving at least
+    // one aggregate, sorted by handles.
+    aggregate_pairs: HashMap<(u32, u32), Vec<ColliderPair>>,
+    #[cfg_attr(feature = "serde-serialize", serde(skip))]
+    peak_proxies: usize,
+    #[cfg_attr(feature = "serde-serialize", serde(skip))]
+    peak_regions: usize,
 }
 
 impl BroadPhase {
+    /// The maximum number of user-defined broad-phase layers.
+    pub const MAX_LAYERS: usize = 32;
+
     /// Create a new empty broad-phase.
     pub fn new() -> Self {
         BroadPhase {
             removed_colliders: None,
+            filter_layer_masks: [u32::MAX; Self::MAX_LAYERS],
+            modified_filter_layers: 0,
             proxies: SAPProxies::new(),
             layers: Vec::new(),
             smallest_layer: 0,
             largest_layer: 0,
             region_pool: Vec::new(),
             reporting: HashMap::default(),
+            aggregate_pairs: HashMap::default(),
             deleted_any: false,
+            moved_proxies: Vec::new(),
+            added_aggregate_children: Vec::new(),
+            updated_aggregates: Vec::new(),
+            peak_proxies: 0,
+            peak_regions: 0,
+        }
+    }
+
+    /// Statistics about the proxies and regions tracked by this broad-phase.
+    ///
+    /// If `detailed` is `true`, the allocated memory is computed too, which is linear
+    /// in the number of proxies.
+    ///
+    /// The peak values are reset by each call to this method.
+    pub fn stats(&mut self, detailed: bool) -> BroadPhaseStats {
+        self.record_peaks();
+
+        let mut result = BroadPhaseStats {
+            num_proxies: self.proxies.num_proxies,
+            num_layers: self.layers.len(),
+            num_regions: self.num_regions(),
+            bytes: 0,
+            peak_proxies: self.peak_proxies,
+            peak_regions: self.peak_regions,
+        };
+
+        if detailed {
+            result.bytes = core::mem::size_of::<Self>()
+                + self.proxies.allocated_bytes()
+                + self.layers.capacity() * core::mem::size_of::<SAPLayer>()
+                + self
+                    .layers
+                    .iter()
+                    .map(|l| l.allocated_bytes())
+                    .sum::<usize>()
+                + self.moved_proxies.capacity() * core::mem::size_of::<SAPProxyIndex>()
+                + self
+                    .region_pool
+                    .iter()
+                    .map(|r| r.allocated_bytes())
+                    .sum::<usize>()
+                + self.reporting.capacity() * core::mem::size_of::<((u32, u32), bool)>()
+                + self
+                    .aggregate_pairs
+                    .values()
+                    .map(|pairs| pairs.capacity() * core::mem::size_of::<ColliderPair>())
+                    .sum::<usize>()
+                + self.aggregate_pairs.capacity()
+                    * core::mem::size_of::<((u32, u32), Vec<ColliderPair>)>();
+        }
+
+        self.peak_proxies = result.num_proxies;
+        self.peak_regions = result.num_regions;
+        result
+    }
+
+    fn num_regions(&self) -> usize {
+        self.layers.iter().map(|l| l.regions.len()).sum()
+    }
+
+    fn record_peaks(&mut self) {
+        self.peak_proxies = self.peak_proxies.max(self.proxies.num_proxies);
+        self.peak_regions = self.peak_regions.max(self.num_regions());
+    }
+
+    /// Counts the colliders and rigid-bodies in each cell of a regular grid with cells of width `cell_size`.
+    ///
+    /// Each collider is counted in the cell containing the center of its AABB, as last computed
+    /// by `BroadPhase::update`. Each non-static rigid-body is counted, as awake or sleeping, in
+    /// every cell where at least one of its colliders is counted. Only the non-empty cells are
+    /// reported, sorted by coordinates.
+    ///
+    /// This is a single pass over the proxies of this broad-phase, cheap enough to be called
+    /// every few seconds, e.g., to decide which parts of a streamed world are dense. The
+    /// `cell_size` is independent from the sizes of the regions of this broad-phase and must be positive.
+    pub fn density_report(
+        &self,
+        cell_size: Real,
+        bodies: &RigidBodySet,
+        colliders: &ColliderSet,
+    ) -> Vec<(CellCoord, RegionStats)> {
+        let mut stats: HashMap<CellCoord, RegionStats> = HashMap::default();
+        let mut counted_bodies = HashSet::default();
+
+        for (_, collider, aabb) in self.collider_proxies(colliders) {
+            let cell = super::point_key(aabb.center(), cell_size);
+            let cell_stats = stats.entry(cell).or_default();
+            cell_stats.colliders += 1;
+
+            if let Some(body) = bodies.get(collider.parent) {
+                if !body.is_static() && counted_bodies.insert((cell, collider.parent)) {
+                    if body.is_sleeping() {
+                        cell_stats.sleeping_bodies += 1;
+                    } else {
+                        cell_stats.awake_bodies += 1;
+                    }
+                }
+            }
+        }
+
+        let mut result: Vec<_> = stats.into_iter().collect();
+        result.sort_unstable_by(|a, b| a.0.coords.as_slice().cmp(b.0.coords.as_slice()));
+        result
+    }
+
+    /// Finds the handles of all the colliders with a broad-phase AABB intersecting the given AABB.
+    ///
+    /// Unlike `QueryPipeline::colliders_with_aabb_intersecting_aabb`, this doesn't need any
+    /// acceleration structure to be updated beforehand: it is a pass over the proxies of this
+    /// broad-phase, testing the AABBs computed by the last `BroadPhase::update`, which are enlarged
+    /// by the prediction distance. The colliders removed since that update are not reported.
+    ///
+    /// The query stops as soon as `callback` returns `false`.
+    pub fn colliders_in_region(
+        &self,
+        colliders: &ColliderSet,
+        aabb: &AABB,
+        mut callback: impl FnMut(&ColliderHandle) -> bool,
+    ) {
+        for (handle, _, proxy_aabb) in self.collider_proxies(colliders) {
+            if proxy_aabb.intersects(aabb) && !callback(&handle) {
+                return;
+            }
+        }
+    }
+
+    /// The colliders of `colliders` with a proxy in this broad-phase, with the AABB of their proxy.
+    ///
+    /// The colliders part of an aggregate are given the AABB they have in that aggregate.
+    fn collider_proxies<'a>(
+        &'a self,
+        colliders: &'a ColliderSet,
+    ) -> impl Iterator<Item = (ColliderHandle, &'a Collider, AABB)> + 'a {
+        let own_proxies = self
+            .proxies
+            .elements
+            .iter()
+            .enumerate()
+            .filter_map(move |(i, proxy)| match proxy.data {
+                SAPProxyData::Collider(handle) => {
+                    let collider = colliders.get(handle)?;
+
+                    // NOTE: this skips the free proxies, as well as the proxies of the
+                    //       removed colliders, which handle may have been reused since,
+                    //       and of the detached colliders, until their proxy is removed.
+                    if collider.proxy_index == i as SAPProxyIndex && !collider.is_detached() {
+                        Some((handle, collider, proxy.aabb))
+                    } else {
+                        None
+                    }
+                }
+                SAPProxyData::Aggregate(_) | SAPProxyData::Region(_) => None,
+            });
+        let aggregated = colliders
+            .aggregates
+            .values()
+            .filter(|aggregate| aggregate.proxy_index != crate::INVALID_U32)
+            .flat_map(move |aggregate| {
+                aggregate
+                    .children
+                    .iter()
+                    .enumerate()
+                    .filter_map(move |(i, (handle, _))| {
+                        let collider = colliders.get(*handle)?;
+
+                        // NOTE: the children added since the last update don't have a proxy yet.
+                        if collider.proxy_index == aggregate.proxy_index && !collider.is_detached()
+                        {
+                            Some((*handle, collider, aggregate.child_world_aabb(i)))
+                        } else {
+                            None
+                        }
+                    })
+            });
+
+        own_proxies.chain(aggregated)
+    }
+
+    /// The mask of the user-defined broad-phase layers that can interact with the given `layer`.
+    ///
+    /// The `i`-th bit of the mask is set if colliders on the layer `layer` may interact with
+    /// colliders on the layer `i`.
+    pub fn layer_mask(&self, layer: u8) -> u32 {
+        assert!(
+            (layer as usize) < Self::MAX_LAYERS,
+            "Invalid broad-phase layer."
+        );
+        self.filter_layer_masks[layer as usize]
+    }
+
+    /// Sets the mask of the user-defined broad-phase layers that can interact with the given `layer`.
+    ///
+    /// The broad-phase will never report a pair of colliders unless the layer of each
+    /// collider is part of the mask of the other collider's layer (see [`Collider::broad_phase_layer`](crate::geometry::Collider::broad_phase_layer)).
+    /// This allows the pairs between colliders that never collide (for example, debris
+    /// with debris) to be skipped before they ever reach the narrow-phase.
+    ///
+    /// The layer must be smaller than [`BroadPhase::MAX_LAYERS`]. Changing the mask of a
+    /// layer will re-insert the proxies of all the colliders on this layer during the next
+    /// update, so that their pairs are filtered again. This may be costly so the masks should
+    /// preferably be configured before colliders are added to the broad-phase.
+    pub fn set_layer_mask(&mut self, layer: u8, mask: u32) {
+        assert!(
+            (layer as usize) < Self::MAX_LAYERS,
+            "Invalid broad-phase layer."
+        );
+        if self.filter_layer_masks[layer as usize] != mask {
+            self.filter_layer_masks[layer as usize] = mask;
+            self.modified_filter_layers |= 1 << layer;
+        }
+    }
+
+    /// Marks the colliders on the layers which had their mask modified, so that their proxies
+    /// are re-inserted by `Self::handle_layer_changes`.
+    fn handle_layer_mask_changes(&mut self, colliders: &mut ColliderSet) {
+        if self.modified_filter_layers == 0 {
+            return;
+        }
+
+        let modified_layers = core::mem::replace(&mut self.modified_filter_layers, 0);
+        let handles: Vec<_> = colliders
+            .iter()
+            .filter(|(_, co)| {
+                co.proxy_index != crate::INVALID_U32
+                    && (modified_layers & (1 << co.broad_phase_layer)) != 0
+            })
+            .map(|(handle, _)| handle)
+            .collect();
+
+        for handle in handles {
+            if let Some(co) = colliders.get_mut_internal_with_modification_tracking(handle) {
+                co.changes.insert(ColliderChanges::BROAD_PHASE_LAYER);
+            }
         }
     }
 
@@ -124,7 +426,12 @@ impl BroadPhase {
     /// For each colliders marked as removed, we make their containing layer mark
     /// its proxy as pre-deleted. The actual proxy removal will happen at the end
     /// of the `BroadPhase::update`.
-    fn handle_removed_colliders(&mut self, colliders: &mut ColliderSet) {
+    fn handle_removed_colliders(
+        &mut self,
+        colliders: &mut ColliderSet,
+        aggregates: &mut ColliderAggregates,
+        events: &mut Vec<BroadPhasePairEvent>,
+    ) {
         // Ensure we already subscribed the collider-removed events.
         if self.removed_colliders.is_none() {
             self.removed_colliders = Some(colliders.removed_colliders.subscribe());
@@ -135,7 +442,14 @@ impl BroadPhase {
 
         // Read all the collider-removed events, and remove the corresponding proxy.
         for collider in colliders.removed_colliders.read(&cursor) {
-            self.predelete_proxy(collider.proxy_index);
+            if !self.remove_aggregate_child(
+                aggregates,
+                collider.proxy_index,
+                collider.handle,
+                events,
+            ) {
+                self.predelete_proxy(collider.proxy_index);
+            }
         }
 
         // NOTE: We don't acknowledge the cursor just yet because we need
@@ -146,6 +460,39 @@ impl BroadPhase {
         self.removed_colliders = Some(cursor);
     }
 
+    /// Removes a collider from the aggregate owning the proxy `proxy_index`, if it is one.
+    ///
+    /// The pairs found for this collider by `Self::refine_aggregate_pairs` are reported as deleted.
+    /// Returns `false` if `proxy_index` isn't the proxy of an aggregate.
+    fn remove_aggregate_child(
+        &mut self,
+        aggregates: &mut ColliderAggregates,
+        proxy_index: SAPProxyIndex,
+        handle: ColliderHandle,
+        events: &mut Vec<BroadPhasePairEvent>,
+    ) -> bool {
+        let body = match self.proxies.get(proxy_index).map(|proxy| &proxy.data) {
+            Some(SAPProxyData::Aggregate(body)) => *body,
+            _ => return false,
+        };
+
+        if let Some(aggregate) = aggregates.get_mut(&body) {
+            aggregate.remove_child(handle);
+        }
+
+        for pairs in self.aggregate_pairs.values_mut() {
+            pairs.retain(|pair| {
+                let lost = pair.collider1 == handle || pair.collider2 == handle;
+                if lost {
+                    events.push(BroadPhasePairEvent::DeletePair(*pair));
+                }
+                !lost
+            });
+        }
+
+        true
+    }
+
     /// Pre-deletes a proxy from this broad-phase.
     ///
     /// The removal of a proxy is a semi-lazy process. It will mark
@@ -180,6 +527,33 @@ impl BroadPhase {
             return;
         }
 
+        self.complete_layer_removals();
+
+        /*
+         * Actually remove the colliders' proxies.
+         */
+        let cursor = self.removed_colliders.as_ref().unwrap();
+        for collider in colliders.removed_colliders.read(&cursor) {
+            // NOTE: the proxy of an aggregate is only removed once it has no children left.
+            if collider.proxy_index != crate::INVALID_U32
+                && !matches!(
+                    self.proxies[collider.proxy_index].data,
+                    SAPProxyData::Aggregate(_)
+                )
+            {
+                self.proxies.remove(collider.proxy_index);
+            }
+        }
+        colliders.removed_colliders.ack(&cursor);
+
+        // Remove the proxies of the aggregates which lost all their children.
+        for proxy_index in self.moved_proxies.drain(..) {
+            self.proxies.remove(proxy_index);
+        }
+    }
+
+    /// Removes from all the layers the proxies marked as deletable by `self.predelete_proxy`.
+    fn complete_layer_removals(&mut self) {
         // This is a bottom-up pass:
         // - Complete the removal on the layer `n`. This may cause so regions to be deleted.
         // - Continue with the layer `n + 1`. This will delete from `n + 1` all the proxies
@@ -212,17 +586,48 @@ impl BroadPhase {
                 break;
             }
         }
+    }
 
-        /*
-         * Actually remove the colliders' proxies.
-         */
-        let cursor = self.removed_colliders.as_ref().unwrap();
-        for collider in colliders.removed_colliders.read(&cursor) {
-            if collider.proxy_index != crate::INVALID_U32 {
-                self.proxies.remove(collider.proxy_index);
+    /// Removes the proxies of the colliders which had their broad-phase layer or their parent modified.
+    ///
+    /// The pairs involving these proxies will be reported as deleted, and the colliders which
+    /// aren't detached will be given a new proxy (and thus report new pairs) during the rest of
+    /// the update.
+    fn handle_layer_changes(
+        &mut self,
+        colliders: &mut ColliderSet,
+        aggregates: &mut ColliderAggregates,
+        events: &mut Vec<BroadPhasePairEvent>,
+    ) {
+        let mut num_moved = 0;
+
+        colliders.foreach_modified_colliders_mut_internal(|handle, collider| {
+            if collider
+                .changes
+                .intersects(ColliderChanges::BROAD_PHASE_LAYER | ColliderChanges::PARENT)
+                && collider.proxy_index != crate::INVALID_U32
+            {
+                if !self.remove_aggregate_child(aggregates, collider.proxy_index, handle, events) {
+                    self.predelete_proxy(collider.proxy_index);
+                    self.moved_proxies.push(collider.proxy_index);
+                    num_moved += 1;
+                }
+
+                collider.proxy_index = crate::INVALID_U32;
             }
+        });
+
+        if num_moved == 0 {
+            return;
+        }
+
+        // Report the pairs lost by the moved proxies, and remove them.
+        self.update_layers_and_find_pairs(events);
+        self.complete_layer_removals();
+
+        for proxy_index in self.moved_proxies.drain(..) {
+            self.proxies.remove(proxy_index);
         }
-        colliders.removed_colliders.ack(&cursor);
     }
 
     /// Finalize the insertion of the layer identified by `layer_id`.
@@ -344,50 +749,80 @@ impl BroadPhase {
         colliders: &mut ColliderSet,
         events: &mut Vec<BroadPhasePairEvent>,
     ) {
+        // Extract the aggregates to avoid borrowing issues.
+        let mut aggregates = core::mem::take(&mut colliders.aggregates);
+
+        // Phase 0: re-insert the colliders which changed of broad-phase layer or of parent,
+        //          or which are on a layer which changed of mask.
+        self.handle_layer_mask_changes(colliders);
+        self.handle_layer_changes(colliders, &mut aggregates, events);
+
         // Phase 1: pre-delete the collisions that have been deleted.
-        self.handle_removed_colliders(colliders);
+        self.handle_removed_colliders(colliders, &mut aggregates, events);
 
         let mut need_region_propagation = false;
 
         // Phase 2: pre-delete the collisions that have been deleted.
         colliders.foreach_modified_colliders_mut_internal(|handle, collider| {
-            if !collider.changes.needs_broad_phase_update() {
+            if collider.is_detached() || !collider.changes.needs_broad_phase_update() {
                 return;
             }
 
-            let mut aabb = collider.compute_aabb().loosened(prediction_distance / 2.0);
-            aabb.mins = super::clamp_point(aabb.mins);
-            aabb.maxs = super::clamp_point(aabb.maxs);
-
-            let layer_id = if let Some(proxy) = self.proxies.get_mut(collider.proxy_index) {
-                let mut layer_id = proxy.layer_id;
-                proxy.aabb = aabb;
-
-                if collider.changes.contains(ColliderChanges::SHAPE) {
-                    // If the shape was changed, then we need to see if this proxy should be
-                    // migrated to a larger layer. Indeed, if the shape was replaced by
-                    // a much larger shape, we need to promote the proxy to a bigger layer
-                    // to avoid the O(n²) discretization problem.
-                    let new_layer_depth = super::layer_containing_aabb(&aabb);
-                    if new_layer_depth > proxy.layer_depth {
-                        self.layers[proxy.layer_id as usize].proper_proxy_moved_to_bigger_layer(
-                            &mut self.proxies,
-                            collider.proxy_index,
-                        );
-
-                        // We need to promote the proxy to the bigger layer.
-                        layer_id = self.ensure_layer_exists(new_layer_depth);
-                        self.proxies[collider.proxy_index].layer_id = layer_id;
+            // The aggregates are moved along with their rigid-body, so their children only
+            // need to be visited if they were added or reshaped.
+            let reshaped = collider.changes.intersects(
+                ColliderChanges::SHAPE
+                    | ColliderChanges::SHAPE_REGION
+                    | ColliderChanges::POSITION_WRT_PARENT,
+            );
+            if !reshaped
+                && matches!(
+                    self.proxies.get(collider.proxy_index),
+                    Some(proxy) if matches!(proxy.data, SAPProxyData::Aggregate(_))
+                )
+            {
+                return;
+            }
+
+            if let Some(aggregate) = aggregates.get_mut(&collider.parent) {
+                if aggregate.enabled {
+                    // The proxy of the aggregate is updated once all its children are.
+                    if aggregate.update_child(handle, collider, prediction_distance) {
+                        self.added_aggregate_children
+                            .push((handle, collider.parent));
                     }
+                    return;
                 }
+            }
 
-                layer_id
+            // The AABB of a collider with a predicted position must cover both its current
+            // and its predicted positions for the narrow-phase to see all the pairs it needs.
+            let aabb = match &collider.predicted_position {
+                Some(predicted_position) => collider.compute_swept_aabb(predicted_position),
+                None => collider.compute_aabb(),
+            };
+            let aabb =
+                super::clamp_aabb(aabb.loosened(collider.broad_phase_margin(prediction_distance)));
+
+            let layer_id = if self.proxies.get(collider.proxy_index).is_some() {
+                // If the shape was changed, then we need to see if this proxy should be
+                // migrated to a larger layer.
+                let resized = collider
+                    .changes
+                    .intersects(ColliderChanges::SHAPE | ColliderChanges::SHAPE_REGION);
+                self.update_proxy_aabb(collider.proxy_index, aabb, resized)
             } else {
                 let layer_depth = super::layer_containing_aabb(&aabb);
                 let layer_id = self.ensure_layer_exists(layer_depth);
 
                 // Create the proxy.
-                let proxy = SAPProxy::collider(handle, aabb, layer_id, layer_depth);
+                let proxy = SAPProxy::collider(
+                    handle,
+                    aabb,
+                    layer_id,
+                    layer_depth,
+                    collider.broad_phase_layer,
+                );
                 collider.proxy_index = self.proxies.insert(proxy);
                 layer_id
             };
@@ -395,10 +830,24 @@ impl BroadPhase {
             let layer = &mut self.layers[layer_id as usize];
 
             // Preupdate the collider in the layer.
-            layer.preupdate_collider(collider, &aabb, &mut self.proxies, &mut self.region_pool);
+            layer.preupdate_proxy(
+                collider.proxy_index,
+                &aabb,
+                &mut self.proxies,
+                &mut self.region_pool,
+            );
             need_region_propagation = need_region_propagation || !layer.created_regions.is_empty();
         });
 
+        // Phase 2b: update the proxies of the aggregates which children were modified.
+        need_region_propagation |= self.update_aggregate_proxies(&mut aggregates);
+
+        for (handle, body) in self.added_aggregate_children.drain(..) {
+            if let Some(collider) = colliders.get_mut_internal(handle) {
+                collider.proxy_index = aggregates[&body].proxy_index;
+            }
+        }
+
         // Phase 3: bottom-up pass to propagate new regions from smaller layers to larger layers.
         if need_region_propagation {
             self.propagate_created_regions();
@@ -407,9 +856,231 @@ impl BroadPhase {
         // Phase 4: top-down pass to propagate proxies from larger layers to smaller layers.
         self.update_layers_and_find_pairs(events);
 
+        // Phase 4b: find the pairs of colliders between the proxies overlapping an aggregate.
+        self.refine_aggregate_pairs(colliders, &aggregates, events);
+
+        // Record the peaks before removals actually shrink the number of proxies.
+        self.record_peaks();
+
         // Phase 5: bottom-up pass to remove proxies, and propagate region removed from smaller
         // layers to possible remove regions from larger layers that would become empty that way.
         self.complete_removals(colliders);
+
+        colliders.aggregates = aggregates;
+    }
+
+    /// Sets the AABB of an existing proxy and returns the layer it is part of.
+    ///
+    /// If the proxy was `resized`, it is promoted to a larger layer if needed.
+    fn update_proxy_aabb(&mut self, proxy_index: SAPProxyIndex, aabb: AABB, resized: bool) -> u8 {
+        let proxy = &mut self.proxies[proxy_index];
+        let mut layer_id = proxy.layer_id;
+        proxy.aabb = aabb;
+
+        if resized {
+            // If the shape was replaced by a much larger shape, we need to promote the proxy
+            // to a bigger layer to avoid the O(n²) discretization problem.
+            let new_layer_depth = super::layer_containing_aabb(&aabb);
+            if new_layer_depth > proxy.layer_depth {
+                self.layers[proxy.layer_id as usize]
+                    .proper_proxy_moved_to_bigger_layer(&mut self.proxies, proxy_index);
+
+                // We need to promote the proxy to the bigger layer.
+                layer_id = self.ensure_layer_exists(new_layer_depth);
+                self.proxies[proxy_index].layer_id = layer_id;
+            }
+        }
+
+        layer_id
+    }
+
+    /// Updates the proxies of the aggregates which moved or which children changed.
+    ///
+    /// The proxies of the aggregates without children are pre-deleted, and the aggregates
+    /// no longer used by their rigid-body are dropped once they have no children.
+    /// Returns `true` if regions were created.
+    fn update_aggregate_proxies(&mut self, aggregates: &mut ColliderAggregates) -> bool {
+        let mut need_region_propagation = false;
+
+        // NOTE: sort the aggregates so the proxies are created in a deterministic order.
+        let mut bodies = core::mem::take(&mut self.updated_aggregates);
+        bodies.extend(
+            aggregates
+                .iter()
+                .filter(|(_, aggregate)| {
+                    aggregate.moved || aggregate.dirty || aggregate.children.is_empty()
+                })
+                .map(|(body, _)| *body),
+        );
+        bodies.sort_unstable_by_key(|body| body.into_raw_parts());
+
+        for body in bodies.drain(..) {
+            let aggregate = aggregates.get_mut(&body).unwrap();
+
+            if aggregate.children.is_empty() {
+                if aggregate.proxy_index != crate::INVALID_U32 {
+                    // The proxy is actually removed by `Self::complete_removals`.
+                    self.predelete_proxy(aggregate.proxy_index);
+                    self.moved_proxies.push(aggregate.proxy_index);
+                    aggregate.proxy_index = crate::INVALID_U32;
+                }
+
+                if !aggregate.enabled {
+                    aggregates.remove(&body);
+                }
+
+                continue;
+            }
+
+            let resized = aggregate.dirty;
+            if resized {
+                aggregate.rebuild();
+            }
+
+            aggregate.moved = false;
+            let aabb = super::clamp_aabb(aggregate.world_aabb());
+
+            let layer_id = if aggregate.proxy_index != crate::INVALID_U32 {
+                self.update_proxy_aabb(aggregate.proxy_index, aabb, resized)
+            } else {
+                let layer_depth = super::layer_containing_aabb(&aabb);
+                let layer_id = self.ensure_layer_exists(layer_depth);
+                let proxy = SAPProxy::aggregate(body, aabb, layer_id, layer_depth);
+                aggregate.proxy_index = self.proxies.insert(proxy);
+                layer_id
+            };
+
+            let layer = &mut self.layers[layer_id as usize];
+            layer.preupdate_proxy(
+                aggregate.proxy_index,
+                &aabb,
+                &mut self.proxies,
+                &mut self.region_pool,
+            );
+            need_region_propagation = need_region_propagation || !layer.created_regions.is_empty();
+        }
+
+        self.updated_aggregates = bodies;
+        need_region_propagation
+    }
+
+    /// Finds the pairs of colliders between each pair of overlapping proxies involving an
+    /// aggregate, and reports the pairs which appeared or disappeared since the last update.
+    fn refine_aggregate_pairs(
+        &mut self,
+        colliders: &ColliderSet,
+        aggregates: &ColliderAggregates,
+        events: &mut Vec<BroadPhasePairEvent>,
+    ) {
+        if self.aggregate_pairs.is_empty() {
+            return;
+        }
+
+        // NOTE: sort the proxy pairs so the events are reported in a deterministic order.
+        let mut keys: Vec<_> = self.aggregate_pairs.keys().copied().collect();
+        keys.sort_unstable();
+        let mut found = vec![];
+
+        for key in keys {
+            found.clear();
+            self.find_aggregate_pairs(key, colliders, aggregates, &mut found);
+            let pairs = self.aggregate_pairs.get_mut(&key).unwrap();
+
+            for pair in pairs.iter() {
+                if found
+                    .binary_search_by_key(&pair_key(pair), pair_key)
+                    .is_err()
+                {
+                    events.push(BroadPhasePairEvent::DeletePair(*pair));
+                }
+            }
+
+            for pair in &found {
+                if pairs
+                    .binary_search_by_key(&pair_key(pair), pair_key)
+                    .is_err()
+                {
+                    events.push(BroadPhasePairEvent::AddPair(*pair));
+                }
+            }
+
+            pairs.clear();
+            pairs.extend_from_slice(&found);
+        }
+    }
+
+    /// Pushes to `out`, sorted, the pairs of colliders with intersecting AABBs between the
+    /// two proxies identified by `key`, at least one of them being an aggregate.
+    fn find_aggregate_pairs(
+        &self,
+        key: (SAPProxyIndex, SAPProxyIndex),
+        colliders: &ColliderSet,
+        aggregates: &ColliderAggregates,
+        out: &mut Vec<ColliderPair>,
+    ) {
+        let proxy1 = &self.proxies[key.0];
+        let proxy2 = &self.proxies[key.1];
+        let layer = |handle: ColliderHandle| colliders.get(handle).map(|co| co.broad_phase_layer);
+        let interact = |layer1: Option<u8>, layer2: Option<u8>| match (layer1, layer2) {
+            (Some(layer1), Some(layer2)) => {
+                filter_layers_interact(&self.filter_layer_masks, layer1, layer2)
+            }
+            _ => false,
+        };
+        let mut candidates1 = vec![];
+        let mut candidates2 = vec![];
+
+        match (&proxy1.data, &proxy2.data) {
+            (SAPProxyData::Aggregate(body1), SAPProxyData::Aggregate(body2)) => {
+                let aggregate1 = &aggregates[body1];
+                let aggregate2 = &aggregates[body2];
+                aggregate1.children_intersecting_world_aabb(&proxy2.aabb, &mut candidates1);
+                candidates1.sort_unstable();
+                candidates1.dedup();
+
+                for i in candidates1 {
+                    let handle1 = aggregate1.children[i].0;
+                    candidates2.clear();
+                    aggregate2.children_intersecting_world_aabb(
+                        &aggregate1.child_world_aabb(i),
+                        &mut candidates2,
+                    );
+
+                    for j in &candidates2 {
+                        let handle2 = aggregate2.children[*j].0;
+                        if interact(layer(handle1), layer(handle2)) {
+                            out.push(ColliderPair::new(handle1, handle2));
+                        }
+                    }
+                }
+            }
+            (SAPProxyData::Aggregate(body1), SAPProxyData::Collider(handle2)) => {
+                let aggregate1 = &aggregates[body1];
+                aggregate1.children_intersecting_world_aabb(&proxy2.aabb, &mut candidates1);
+
+                for i in candidates1 {
+                    let handle1 = aggregate1.children[i].0;
+                    if interact(layer(handle1), Some(proxy2.filter_layer)) {
+                        out.push(ColliderPair::new(handle1, *handle2));
+                    }
+                }
+            }
+            (SAPProxyData::Collider(handle1), SAPProxyData::Aggregate(body2)) => {
+                let aggregate2 = &aggregates[body2];
+                aggregate2.children_intersecting_world_aabb(&proxy1.aabb, &mut candidates2);
+
+                for j in candidates2 {
+                    let handle2 = aggregate2.children[j].0;
+                    if interact(Some(proxy1.filter_layer), layer(handle2)) {
+                        out.push(ColliderPair::new(*handle1, handle2));
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        out.sort_unstable_by_key(pair_key);
+        out.dedup();
     }
 
     /// Propagate regions from the smallest layers up to the larger layers.
@@ -483,6 +1154,16 @@ impl BroadPhase {
                 match (&mut proxy1.data, &mut proxy2.data) {
                     (SAPProxyData::Collider(handle1), SAPProxyData::Collider(handle2)) => {
                         if *colliding {
+                            // NOTE: we don't check the layers for deleted pairs because the
+                            //       pair may have been added before a layer mask change.
+                            if !filter_layers_interact(
+                                &self.filter_layer_masks,
+                                proxy1.filter_layer,
+                                proxy2.filter_layer,
+                            ) {
+                                continue;
+                            }
+
                             out_events.push(BroadPhasePairEvent::AddPair(ColliderPair::new(
                                 *handle1, *handle2,
                             )));
@@ -492,27 +1173,39 @@ impl BroadPhase {
                             )));
                         }
                     }
-                    (SAPProxyData::Collider(_), SAPProxyData::Region(_)) => {
+                    (SAPProxyData::Region(_), SAPProxyData::Region(_)) => {
+                        // This will only happen between two adjacent subregions because
+                        // they share some identical bounds. So this case does not matter.
+                    }
+                    (_, SAPProxyData::Region(_)) => {
                         if *colliding {
-                            // Add the collider to the subregion.
+                            // Add the collider or aggregate to the subregion.
                             proxy2
                                 .data
                                 .as_region_mut()
                                 .preupdate_proxy(*proxy_id1, false);
                         }
                     }
-                    (SAPProxyData::Region(_), SAPProxyData::Collider(_)) => {
+                    (SAPProxyData::Region(_), _) => {
                         if *colliding {
-                            // Add the collider to the subregion.
+                            // Add the collider or aggregate to the subregion.
                             proxy1
                                 .data
                                 .as_region_mut()
                                 .preupdate_proxy(*proxy_id2, false);
                         }
                     }
-                    (SAPProxyData::Region(_), SAPProxyData::Region(_)) => {
-                        // This will only happen between two adjacent subregions because
-                        // they share some identical bounds. So this case does not matter.
+                    _ => {
+                        // At least one of the proxies is an aggregate: the pairs of colliders
+                        // are found by `Self::refine_aggregate_pairs`.
+                        let key = ((*proxy_id1).min(*proxy_id2), (*proxy_id1).max(*proxy_id2));
+
+                        if *colliding {
+                            self.aggregate_pairs.entry(key).or_default();
+                        } else if let Some(pairs) = self.aggregate_pairs.remove(&key) {
+                            out_events
+                                .extend(pairs.into_iter().map(BroadPhasePairEvent::DeletePair));
+                        }
                     }
                 }
             }
@@ -522,10 +1215,29 @@ impl BroadPhase {
     }
 }
 
+type ColliderAggregates = HashMap<RigidBodyHandle, ColliderAggregate>;
+
+/// The key used to sort the pairs of colliders found for an aggregate.
+fn pair_key(pair: &ColliderPair) -> ((usize, u64), (usize, u64)) {
+    (
+        pair.collider1.into_raw_parts(),
+        pair.collider2.into_raw_parts(),
+    )
+}
+
+fn filter_layers_interact(masks: &[u32; BroadPhase::MAX_LAYERS], layer1: u8, layer2: u8) -> bool {
+    (masks[layer1 as usize] & (1 << layer2)) != 0 && (masks[layer2 as usize] & (1 << layer1)) != 0
+}
+
 #[cfg(test)]
 mod test {
     use crate::dynamics::{JointSet, RigidBodyBuilder, RigidBodySet};
-    use crate::geometry::{BroadPhase, ColliderBuilder, ColliderSet};
+    use crate::geometry::{
+        BroadPhase, BroadPhasePairEvent, CellCoord, ColliderBuilder, ColliderSet, RegionStats,
+        RemoveSettleMode, AABB,
+    };
+    use crate::math::{Point, Real};
+    use parry::bounding_volume::BoundingVolume;
 
     #[test]
     fn test_add_update_remove() {
@@ -554,4 +1266,312 @@ mod test {
         // Make sure the proxy handles is recycled properly.
         broad_phase.update(0.0, &mut colliders, &mut events);
     }
+
+    #[test]
+    fn test_layer_masks() {
+        let mut broad_phase = BroadPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+
+        // Layer 1 doesn't interact with itself.
+        broad_phase.set_layer_mask(1, !(1 << 1));
+
+        let mut handles = vec![];
+        for _ in 0..2 {
+            let rb = RigidBodyBuilder::new_dynamic().build();
+            let co = ColliderBuilder::ball(0.5).broad_phase_layer(1).build();
+            let hrb = bodies.insert(rb);
+            handles.push(colliders.insert(co, hrb, &mut bodies));
+        }
+
+        let mut events = Vec::new();
+        bodies.handle_user_changes(&mut colliders);
+        broad_phase.update(0.0, &mut colliders, &mut events);
+        colliders.clear_modified_colliders();
+        assert!(events.is_empty());
+
+        // Moving one collider to the layer 0 must re-insert its proxy.
+        colliders[handles[0]].set_broad_phase_layer(0);
+        broad_phase.update(0.0, &mut colliders, &mut events);
+        colliders.clear_modified_colliders();
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, BroadPhasePairEvent::AddPair(_))));
+
+        // Changing the mask of a layer must filter the existing pairs again.
+        events.clear();
+        broad_phase.set_layer_mask(0, !(1 << 1));
+        broad_phase.update(0.0, &mut colliders, &mut events);
+        colliders.clear_modified_colliders();
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, BroadPhasePairEvent::DeletePair(_))));
+        assert!(!events
+            .iter()
+            .any(|e| matches!(e, BroadPhasePairEvent::AddPair(_))));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid broad-phase layer.")]
+    fn test_invalid_layer_mask() {
+        let mut broad_phase = BroadPhase::new();
+        broad_phase.set_layer_mask(BroadPhase::MAX_LAYERS as u8, 0);
+    }
+
+    #[test]
+    fn test_density_report() {
+        let mut broad_phase = BroadPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let cell_size = 10.0;
+
+        // A 3x3 grid of piles, one per cell. The pile `(i, j)` has `i + 3 * j + 1` bodies
+        // with one collider each, plus one more collider on its bottom body. The piles on
+        // the diagonal are sleeping.
+        let pile_cell = |i: i32, j: i32| -> CellCoord {
+            #[cfg(feature = "dim2")]
+            return Point::new(i, j);
+            #[cfg(feature = "dim3")]
+            return Point::new(i, 0, j);
+        };
+        let mut removed = None;
+        let mut sleeping = Vec::new();
+
+        for i in 0..3 {
+            for j in 0..3 {
+                let x = (i as Real + 0.5) * cell_size;
+                let z = (j as Real + 0.5) * cell_size;
+
+                for k in 0..i + 3 * j + 1 {
+                    let height = k as Real * 0.5 - 2.0;
+                    #[cfg(feature = "dim2")]
+                    let rb = RigidBodyBuilder::new_dynamic()
+                        .translation(x, z + height)
+                        .build();
+                    #[cfg(feature = "dim3")]
+                    let rb = RigidBodyBuilder::new_dynamic()
+                        .translation(x, cell_size / 2.0 + height, z)
+                        .build();
+                    let handle = bodies.insert(rb);
+                    let co = ColliderBuilder::ball(0.1).build();
+                    colliders.insert(co, handle, &mut bodies);
+
+                    if k == 0 {
+                        let co = ColliderBuilder::ball(0.1).build();
+                        colliders.insert(co, handle, &mut bodies);
+
+                        // This collider will be removed after the broad-phase update.
+                        if (i, j) == (1, 1) {
+                            let co = ColliderBuilder::ball(0.1).build();
+                            removed = Some(colliders.insert(co, handle, &mut bodies));
+                        }
+                    }
+
+                    if i == j {
+                        sleeping.push(handle);
+                    }
+                }
+            }
+        }
+
+        // The static colliders count, but not their bodies.
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        let co = ColliderBuilder::ball(0.1).build();
+        colliders.insert(co, ground, &mut bodies);
+
+        let mut events = Vec::new();
+        bodies.handle_user_changes(&mut colliders);
+
+        // NOTE: the bodies inserted since the last timestep are woken up when the user
+        //       changes are handled, so the diagonal piles are put to sleep afterwards.
+        for handle in &sleeping {
+            bodies[*handle].sleep();
+        }
+
+        broad_phase.update(0.0, &mut colliders, &mut events);
+        colliders.clear_modified_colliders();
+        colliders.remove(
+            removed.unwrap(),
+            &mut bodies,
+            false,
+            RemoveSettleMode::Immediate,
+        );
+
+        let mut expected = vec![];
+
+        for i in 0..3 {
+            for j in 0..3 {
+                let num_bodies = (i + 3 * j + 1) as usize;
+                let ground = if (i, j) == (0, 0) { 1 } else { 0 };
+                let (awake_bodies, sleeping_bodies) = if i == j {
+                    (0, num_bodies)
+                } else {
+                    (num_bodies, 0)
+                };
+                let stats = RegionStats {
+                    colliders: num_bodies + 1 + ground,
+                    awake_bodies,
+                    sleeping_bodies,
+                };
+                expected.push((pile_cell(i, j), stats));
+            }
+        }
+
+        expected.sort_by(|a, b| a.0.coords.as_slice().cmp(b.0.coords.as_slice()));
+        let report = broad_phase.density_report(cell_size, &bodies, &colliders);
+        assert_eq!(report, expected);
+
+        // With larger cells, all the piles are in the same cell.
+        let report = broad_phase.density_report(cell_size * 3.0, &bodies, &colliders);
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].1.colliders, 45 + 9 + 1);
+        assert_eq!(report[0].1.awake_bodies, 45 - 15);
+        assert_eq!(report[0].1.sleeping_bodies, 15);
+
+        // Only the colliders of the pile `(1, 1)` are in its cell.
+        let center = pile_cell(1, 1).map(|e| (e as Real + 0.5) * cell_size);
+        let half_extents = crate::math::Vector::repeat(cell_size * 0.45);
+        let region = AABB::new(center - half_extents, center + half_extents);
+        let mut found = vec![];
+        broad_phase.colliders_in_region(&colliders, &region, |handle| {
+            found.push(*handle);
+            true
+        });
+        assert_eq!(found.len(), 6);
+        assert!(!found.contains(&removed.unwrap()));
+        assert!(found
+            .iter()
+            .all(|h| colliders[*h].compute_aabb().intersects(&region)));
+
+        // The query stops as soon as the callback returns `false`.
+        let mut num_found = 0;
+        broad_phase.colliders_in_region(&colliders, &region, |_| {
+            num_found += 1;
+            false
+        });
+        assert_eq!(num_found, 1);
+    }
+
+    #[test]
+    fn aabb_overlap_events_are_emitted_without_narrow_phase_work() {
+        use crate::dynamics::{CCDSolver, IntegrationParameters, UserConstraintSet};
+        use crate::geometry::{AabbOverlapEvent, ContactEvent, IntersectionEvent, NarrowPhase};
+        use crate::math::Vector;
+        use crate::pipeline::{ActiveEvents, EventHandler, PhysicsPipeline};
+        use std::sync::Mutex;
+
+        #[derive(Default)]
+        struct Recorder {
+            overlaps: Mutex<Vec<AabbOverlapEvent>>,
+            num_narrow_phase_events: Mutex<usize>,
+        }
+
+        impl EventHandler for Recorder {
+            fn handle_intersection_event(&self, _event: IntersectionEvent) {
+                *self.num_narrow_phase_events.lock().unwrap() += 1;
+            }
+            fn handle_contact_event(&self, _event: ContactEvent) {
+                *self.num_narrow_phase_events.lock().unwrap() += 1;
+            }
+            fn handle_aabb_overlap_event(&self, event: AabbOverlapEvent) {
+                self.overlaps.lock().unwrap().push(event);
+            }
+        }
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::zeros();
+        let integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+        let events = Recorder::default();
+
+        let tower = bodies.insert(RigidBodyBuilder::new_static().build());
+        let tower_collider = ColliderBuilder::ball(2.0)
+            .sensor(true)
+            .active_events(ActiveEvents::PROXIMITY_AABB_EVENTS)
+            .build();
+        let tower_handle = colliders.insert(tower_collider, tower, &mut bodies);
+
+        // The drone flies along the line `x + y = 3.4`: its AABB crosses the tower's AABB
+        // but the shapes stay more than 0.1 apart.
+        #[cfg(feature = "dim2")]
+        let drone = RigidBodyBuilder::new_dynamic()
+            .translation(-4.3, 7.7)
+            .linvel(2.0, -2.0);
+        #[cfg(feature = "dim3")]
+        let drone = RigidBodyBuilder::new_dynamic()
+            .translation(-4.3, 7.7, 0.0)
+            .linvel(2.0, -2.0, 0.0);
+        let drone = bodies.insert(drone.build());
+        let drone_handle = colliders.insert(ColliderBuilder::ball(0.3).build(), drone, &mut bodies);
+
+        let mut step = |bodies: &mut RigidBodySet, colliders: &mut ColliderSet| {
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                bodies,
+                colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &events,
+            );
+            assert_eq!(pipeline.counters.cd.ncontact_pairs, 0);
+        };
+
+        for _ in 0..360 {
+            step(&mut bodies, &mut colliders);
+        }
+
+        let involves_both = |event: &AabbOverlapEvent| {
+            (event.collider1, event.collider2) == (tower_handle, drone_handle)
+                || (event.collider1, event.collider2) == (drone_handle, tower_handle)
+        };
+
+        {
+            let overlaps = events.overlaps.lock().unwrap();
+            assert_eq!(overlaps.len(), 2);
+            assert!(overlaps.iter().all(involves_both));
+            assert!(overlaps[0].overlapping);
+            assert!(!overlaps[1].overlapping);
+        }
+        assert_eq!(*events.num_narrow_phase_events.lock().unwrap(), 0);
+
+        // Removing a collider while its AABB overlaps the tower ends the overlap exactly once.
+        events.overlaps.lock().unwrap().clear();
+        #[cfg(feature = "dim2")]
+        let parked = RigidBodyBuilder::new_static().translation(1.7, 1.7);
+        #[cfg(feature = "dim3")]
+        let parked = RigidBodyBuilder::new_static().translation(1.7, 1.7, 0.0);
+        let parked = bodies.insert(parked.build());
+        let parked_handle =
+            colliders.insert(ColliderBuilder::ball(0.3).build(), parked, &mut bodies);
+        step(&mut bodies, &mut colliders);
+        let _ = colliders.remove(
+            parked_handle,
+            &mut bodies,
+            true,
+            RemoveSettleMode::Immediate,
+        );
+
+        for _ in 0..10 {
+            step(&mut bodies, &mut colliders);
+        }
+
+        let overlaps = events.overlaps.lock().unwrap();
+        assert_eq!(overlaps.len(), 2);
+        assert!(overlaps[0].overlapping);
+        assert!(!overlaps[1].overlapping);
+        assert!(overlaps
+            .iter()
+            .all(|e| e.collider1 == parked_handle || e.collider2 == parked_handle));
+    }
 }
@@ -1,6 +1,7 @@
-pub use self::broad_phase::BroadPhase;
+pub use self::broad_phase::{BroadPhase, BroadPhaseStats, CellCoord, RegionStats};
 pub use self::broad_phase_pair_event::{BroadPhasePairEvent, ColliderPair};
 pub use self::sap_proxy::SAPProxyIndex;
+pub(crate) use self::sap_utils::clamp_aabb;
 
 pub(self) use self::sap_axis::*;
 pub(self) use self::sap_endpoint::*;
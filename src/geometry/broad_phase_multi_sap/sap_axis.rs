@@ -2,10 +2,11 @@ use super::{SAPEndpoint, SAPProxies, NUM_SENTINELS};
 use crate::geometry::broad_phase_multi_sap::DELETED_AABB_VALUE;
 use crate::geometry::SAPProxyIndex;
 use crate::math::Real;
+use alloc::{vec, vec::Vec};
 use bit_vec::BitVec;
+use core::cmp::Ordering;
 use parry::bounding_volume::BoundingVolume;
 use parry::utils::hashmap::HashMap;
-use std::cmp::Ordering;
 
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 #[derive(Clone, Debug)]
@@ -30,6 +31,11 @@ impl SAPAxis {
         }
     }
 
+    pub fn allocated_bytes(&self) -> usize {
+        self.endpoints.capacity() * core::mem::size_of::<SAPEndpoint>()
+            + self.new_endpoints.capacity() * core::mem::size_of::<(SAPEndpoint, usize)>()
+    }
+
     pub fn clear(&mut self) {
         self.new_endpoints.clear();
         self.endpoints.clear();
@@ -1,7 +1,8 @@
 use super::{SAPProxies, SAPProxy, SAPRegion, SAPRegionPool};
 use crate::geometry::broad_phase_multi_sap::DELETED_AABB_VALUE;
-use crate::geometry::{Collider, SAPProxyIndex, AABB};
+use crate::geometry::{SAPProxyIndex, AABB};
 use crate::math::{Point, Real};
+use alloc::{vec, vec::Vec};
 use parry::utils::hashmap::{Entry, HashMap};
 
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
@@ -39,6 +40,14 @@ impl SAPLayer {
         }
     }
 
+    /// The number of bytes allocated by this layer, excluding its regions which are owned
+    /// by the region proxies.
+    pub fn allocated_bytes(&self) -> usize {
+        self.regions.capacity() * core::mem::size_of::<(Point<i32>, SAPProxyIndex)>()
+            + self.regions_to_potentially_remove.capacity() * core::mem::size_of::<Point<i32>>()
+            + self.created_regions.capacity() * core::mem::size_of::<SAPProxyIndex>()
+    }
+
     /// Deletes from all the regions of this layer, all the endpoints corresponding
     /// to subregions. Clears the arrays of subregions indices from all the regions of
     /// this layer.
@@ -212,14 +221,13 @@ impl SAPLayer {
         }
     }
 
-    pub fn preupdate_collider(
+    pub fn preupdate_proxy(
         &mut self,
-        collider: &Collider,
+        proxy_id: SAPProxyIndex,
         aabb: &AABB,
         proxies: &mut SAPProxies,
         pool: &mut SAPRegionPool,
     ) {
-        let proxy_id = collider.proxy_index;
         let start = super::point_key(aabb.mins, self.region_width);
         let end = super::point_key(aabb.maxs, self.region_width);
 
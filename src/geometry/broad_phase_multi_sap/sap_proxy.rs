@@ -1,8 +1,11 @@
 use super::NEXT_FREE_SENTINEL;
+use crate::dynamics::RigidBodyHandle;
 use crate::geometry::broad_phase_multi_sap::SAPRegion;
 use crate::geometry::ColliderHandle;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::ops::{Index, IndexMut};
 use parry::bounding_volume::AABB;
-use std::ops::{Index, IndexMut};
 
 pub type SAPProxyIndex = u32;
 
@@ -10,6 +13,8 @@ pub type SAPProxyIndex = u32;
 #[derive(Clone)]
 pub enum SAPProxyData {
     Collider(ColliderHandle),
+    // The proxy shared by all the colliders of a rigid-body using an aggregate proxy.
+    Aggregate(RigidBodyHandle),
     Region(Option<Box<SAPRegion>>),
 }
 
@@ -56,16 +61,37 @@ pub struct SAPProxy {
     // TODO: pack the layer_id and layer_depth into a single u16?
     pub layer_id: u8,
     pub layer_depth: i8,
+    // The user-defined broad-phase layer (not to be confused
+    // with the hierarchical layer identified by `layer_id`).
+    pub filter_layer: u8,
 }
 
 impl SAPProxy {
-    pub fn collider(handle: ColliderHandle, aabb: AABB, layer_id: u8, layer_depth: i8) -> Self {
+    pub fn collider(
+        handle: ColliderHandle,
+        aabb: AABB,
+        layer_id: u8,
+        layer_depth: i8,
+        filter_layer: u8,
+    ) -> Self {
         Self {
             data: SAPProxyData::Collider(handle),
             aabb,
             next_free: NEXT_FREE_SENTINEL,
             layer_id,
             layer_depth,
+            filter_layer,
+        }
+    }
+
+    pub fn aggregate(body: RigidBodyHandle, aabb: AABB, layer_id: u8, layer_depth: i8) -> Self {
+        Self {
+            data: SAPProxyData::Aggregate(body),
+            aabb,
+            next_free: NEXT_FREE_SENTINEL,
+            layer_id,
+            layer_depth,
+            filter_layer: 0,
         }
     }
 
@@ -76,6 +102,7 @@ impl SAPProxy {
             next_free: NEXT_FREE_SENTINEL,
             layer_id,
             layer_depth,
+            filter_layer: 0,
         }
     }
 }
@@ -85,6 +112,8 @@ impl SAPProxy {
 pub struct SAPProxies {
     pub elements: Vec<SAPProxy>,
     pub first_free: SAPProxyIndex,
+    // Number of proxies which are not part of the free list.
+    pub num_proxies: usize,
 }
 
 impl SAPProxies {
@@ -92,10 +121,12 @@ impl SAPProxies {
         Self {
             elements: Vec::new(),
             first_free: NEXT_FREE_SENTINEL,
+            num_proxies: 0,
         }
     }
 
     pub fn insert(&mut self, proxy: SAPProxy) -> SAPProxyIndex {
+        self.num_proxies += 1;
         let result = if self.first_free != NEXT_FREE_SENTINEL {
             let proxy_id = self.first_free;
             self.first_free = self.elements[proxy_id as usize].next_free;
@@ -113,6 +144,20 @@ impl SAPProxies {
         let proxy = &mut self.elements[proxy_id as usize];
         proxy.next_free = self.first_free;
         self.first_free = proxy_id as u32;
+        self.num_proxies -= 1;
+    }
+
+    pub fn allocated_bytes(&self) -> usize {
+        let region_bytes: usize = self
+            .elements
+            .iter()
+            .filter_map(|proxy| match &proxy.data {
+                SAPProxyData::Region(Some(region)) => Some(region.allocated_bytes()),
+                _ => None,
+            })
+            .sum();
+
+        self.elements.capacity() * core::mem::size_of::<SAPProxy>() + region_bytes
     }
 
     // NOTE: this must not take holes into account.
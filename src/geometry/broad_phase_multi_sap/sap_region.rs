@@ -1,6 +1,8 @@
 use super::{SAPAxis, SAPProxies};
 use crate::geometry::SAPProxyIndex;
 use crate::math::DIM;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
 use bit_vec::BitVec;
 use parry::bounding_volume::AABB;
 use parry::utils::hashmap::HashMap;
@@ -45,6 +47,15 @@ impl SAPRegion {
         }
     }
 
+    /// The number of bytes allocated by this region, including the region itself.
+    pub fn allocated_bytes(&self) -> usize {
+        core::mem::size_of::<Self>()
+            + self.axes.iter().map(|a| a.allocated_bytes()).sum::<usize>()
+            + self.existing_proxies.capacity() / 8
+            + (self.to_insert.capacity() + self.subregions.capacity())
+                * core::mem::size_of::<SAPProxyIndex>()
+    }
+
     pub fn recycle(bounds: AABB, mut old: Box<Self>) -> Box<Self> {
         // Correct the bounds
         for i in 0..DIM {
@@ -23,6 +23,13 @@ pub(crate) fn clamp_point(point: Point<Real>) -> Point<Real> {
     point.map(|e| na::clamp(e, -MAX_AABB_EXTENT, MAX_AABB_EXTENT))
 }
 
+/// Clamps the given AABB so that its extents can be computed without overflowing.
+///
+/// This is needed for shapes with infinite AABBs, like half-spaces.
+pub(crate) fn clamp_aabb(aabb: AABB) -> AABB {
+    AABB::new(clamp_point(aabb.mins), clamp_point(aabb.maxs))
+}
+
 pub(crate) fn point_key(point: Point<Real>, region_width: Real) -> Point<i32> {
     (point / region_width)
         .coords
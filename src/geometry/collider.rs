@@ -1,10 +1,24 @@
 use crate::dynamics::{CoefficientCombineRule, MassProperties, RigidBodyHandle};
-use crate::geometry::{InteractionGroups, SAPProxyIndex, SharedShape, SolverFlags};
+#[cfg(feature = "dim3")]
+use crate::geometry::{mesh_classification, MeshClassification};
+use crate::geometry::{
+    ColliderHandle, HeightField, InteractionGroups, SAPProxyIndex, SharedShape, SolverFlags,
+    TypedShape,
+};
+#[cfg(feature = "dim3")]
+use crate::geometry::{TriMeshFlags, TriMeshInternalEdges};
 use crate::math::{AngVector, Isometry, Point, Real, Rotation, Vector, DIM};
 use crate::parry::transformation::vhacd::VHACDParameters;
+use crate::pipeline::ActiveEvents;
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::{vec, vec::Vec};
+use core::ops::Range;
+#[cfg(feature = "dim3")]
+use na::RealField;
 use na::Unit;
 use parry::bounding_volume::{BoundingVolume, AABB};
-use parry::shape::Shape;
+use parry::shape::{Shape, ShapeType};
 
 bitflags::bitflags! {
     #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
@@ -15,6 +29,7 @@ bitflags::bitflags! {
         const FRICTION_COMBINE_RULE_10 = 1 << 2;
         const RESTITUTION_COMBINE_RULE_01 = 1 << 3;
         const RESTITUTION_COMBINE_RULE_10 = 1 << 4;
+        const DETACHED = 1 << 5;
     }
 }
 
@@ -23,6 +38,10 @@ impl ColliderFlags {
         self.contains(ColliderFlags::SENSOR)
     }
 
+    pub fn is_detached(self) -> bool {
+        self.contains(ColliderFlags::DETACHED)
+    }
+
     pub fn friction_combine_rule_value(self) -> u8 {
         (self.bits & 0b0000_0110) >> 1
     }
@@ -61,6 +80,9 @@ bitflags::bitflags! {
         const SOLVER_GROUPS        = 1 << 4; // => NF update.
         const SHAPE                = 1 << 5; // => BF & NF update. NF pair workspace invalidation.
         const SENSOR               = 1 << 6; // => NF update. NF pair invalidation.
+        const BROAD_PHASE_LAYER    = 1 << 7; // => BF proxy re-insertion.
+        const SHAPE_REGION         = 1 << 8; // => BF & NF update. NF warmstart invalidation in the modified region.
+        const PARENT               = 1 << 9; // => BF proxy re-insertion. NF pair removal.
     }
 }
 
@@ -69,13 +91,52 @@ impl ColliderChanges {
         self.intersects(
             ColliderChanges::POSITION_WRT_PARENT
                 | ColliderChanges::POSITION
-                | ColliderChanges::SHAPE,
+                | ColliderChanges::SHAPE
+                | ColliderChanges::SHAPE_REGION
+                | ColliderChanges::BROAD_PHASE_LAYER
+                | ColliderChanges::PARENT,
         )
     }
 
     pub fn needs_narrow_phase_update(self) -> bool {
         self.bits() > 1
     }
+
+    // The changes that may allow or forbid the interactions of the collider with the colliders
+    // it currently interacts with.
+    pub fn needs_pair_reevaluation(self) -> bool {
+        self.intersects(
+            ColliderChanges::COLLISION_GROUPS
+                | ColliderChanges::SOLVER_GROUPS
+                | ColliderChanges::SENSOR,
+        )
+    }
+}
+
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+/// A block of small user-defined values attached to a collider.
+///
+/// These values are never interpreted by the physics engine: they are copied as-is into the
+/// contact pairs and contact events involving the collider, e.g., to select the sound played
+/// when two colliders hit each other without looking up the entities owning them. The slot
+/// `Self::EFFECT_MATERIAL` holds the effect material of the collider, and the other slots are
+/// free for other subsystems (decals, footsteps, etc.)
+pub struct ColliderUserTags(pub [u16; 4]);
+
+impl ColliderUserTags {
+    /// The slot holding the effect material of the collider.
+    pub const EFFECT_MATERIAL: usize = 0;
+
+    /// The effect material of the collider, e.g., the material of its collision sounds.
+    pub fn effect_material(self) -> u16 {
+        self.0[Self::EFFECT_MATERIAL]
+    }
+
+    /// Sets the effect material of the collider.
+    pub fn set_effect_material(&mut self, effect_material: u16) {
+        self.0[Self::EFFECT_MATERIAL] = effect_material;
+    }
 }
 
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
@@ -96,11 +157,55 @@ pub struct Collider {
     pub friction: Real,
     /// The restitution coefficient of this collider.
     pub restitution: Real,
+    /// The rolling resistance coefficient of this collider.
+    ///
+    /// It is combined with the rolling resistance of the other collider using the friction combine rule.
+    pub rolling_resistance: Real,
+    /// The penetration depth the contacts of this collider are resolved toward (default: `0.0`).
+    ///
+    /// The target penetrations of both colliders in contact are added. A positive value keeps
+    /// the other colliders embedded into this one (e.g., tires sinking into snow), and a negative
+    /// value keeps them at a distance from this one.
+    pub contact_target_penetration: Real,
+    /// The normal impulses smaller than this are reported as zero (default: `0.0`).
+    ///
+    /// This only affects `ContactData::reported_impulse` of the contacts of this collider: the
+    /// impulses used by the constraints solver are unchanged, so the simulation is identical with
+    /// any threshold. If both colliders in contact have a threshold, the largest one is used.
+    pub min_reported_impulse: Real,
+    /// The maximum angle, in radians, by which the contact normals of this collider may rotate
+    /// from one timestep to the next (default: `None`, i.e., no limit).
+    ///
+    /// This smooths the normals of persistent contacts, e.g., for the capsule of a character
+    /// controller moving over the edge of a box, where the normal would otherwise jump from the
+    /// face normal to the edge normal. If both colliders in contact have a limit, the smallest
+    /// one is used. See `ContactModificationContext::smooth_normal` for the caveats.
+    pub normal_smoothing: Option<Real>,
     pub(crate) collision_groups: InteractionGroups,
     pub(crate) solver_groups: InteractionGroups,
+    pub(crate) broad_phase_layer: u8,
+    pub(crate) active_events: ActiveEvents,
     pub(crate) proxy_index: SAPProxyIndex,
+    #[cfg(feature = "dim3")]
+    pub(crate) trimesh_internal_edges: Option<Box<TriMeshInternalEdges>>,
+    /// The local-space region of the shape modified since the last timestep.
+    pub(crate) modified_region: Option<AABB>,
+    /// The world-space position of this collider at the end of the timestep, if the narrow-phase
+    /// must compute its contacts at this position instead of its current position.
+    pub(crate) predicted_position: Option<Isometry<Real>>,
+    /// A stable user-defined identifier of this collider used as key of the warmstart cache of
+    /// the narrow-phase.
+    ///
+    /// See `NarrowPhase::set_warmstart_cache_capacity` for details.
+    pub warmstart_cache_key: Option<u64>,
+    /// The colliders this collider never collides with, sorted by handle.
+    ///
+    /// See `ColliderSet::add_exclusion` for details.
+    pub(crate) exclusions: Vec<ColliderHandle>,
     /// User-defined data associated to this rigid-body.
     pub user_data: u128,
+    /// User-defined tags copied into the contact pairs and contact events involving this collider.
+    pub user_tags: ColliderUserTags,
 }
 
 impl Collider {
@@ -108,6 +213,10 @@ impl Collider {
         self.parent = RigidBodyHandle::invalid();
         self.proxy_index = crate::INVALID_U32;
         self.changes = ColliderChanges::empty();
+        self.modified_region = None;
+        self.predicted_position = None;
+        self.exclusions.clear();
+        self.flags.remove(ColliderFlags::DETACHED);
     }
 
     /// The rigid body this collider is attached to.
@@ -120,6 +229,24 @@ impl Collider {
         self.flags.is_sensor()
     }
 
+    /// The effect material of this collider, i.e., the slot `ColliderUserTags::EFFECT_MATERIAL`
+    /// of its user tags.
+    pub fn effect_material(&self) -> u16 {
+        self.user_tags.effect_material()
+    }
+
+    /// Sets the effect material of this collider.
+    ///
+    /// It is reported by the contact events generated after this change.
+    pub fn set_effect_material(&mut self, effect_material: u16) {
+        self.user_tags.set_effect_material(effect_material);
+    }
+
+    /// Was this collider detached from its parent with `ColliderSet::detach`?
+    pub fn is_detached(&self) -> bool {
+        self.flags.is_detached()
+    }
+
     /// The combine rule used by this collider to combine its friction
     /// coefficient with the friction coefficient of the other collider it
     /// is in contact with.
@@ -202,6 +329,48 @@ impl Collider {
         }
     }
 
+    /// The colliders this collider never collides with, sorted by handle.
+    ///
+    /// Exclusions are added with `ColliderSet::add_exclusion`.
+    pub fn exclusions(&self) -> &[ColliderHandle] {
+        &self.exclusions
+    }
+
+    /// Is the collision between this collider and the collider `other` excluded?
+    pub fn is_excluded_from(&self, other: ColliderHandle) -> bool {
+        self.exclusions
+            .binary_search_by_key(&other.into_raw_parts(), |h| h.into_raw_parts())
+            .is_ok()
+    }
+
+    pub(crate) fn insert_exclusion(&mut self, other: ColliderHandle) -> bool {
+        match self
+            .exclusions
+            .binary_search_by_key(&other.into_raw_parts(), |h| h.into_raw_parts())
+        {
+            Ok(_) => false,
+            Err(i) => {
+                self.exclusions.insert(i, other);
+                self.changes.insert(ColliderChanges::COLLISION_GROUPS);
+                true
+            }
+        }
+    }
+
+    pub(crate) fn remove_exclusion(&mut self, other: ColliderHandle) -> bool {
+        match self
+            .exclusions
+            .binary_search_by_key(&other.into_raw_parts(), |h| h.into_raw_parts())
+        {
+            Ok(i) => {
+                self.exclusions.remove(i);
+                self.changes.insert(ColliderChanges::COLLISION_GROUPS);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
     /// The solver groups used by this collider.
     pub fn solver_groups(&self) -> InteractionGroups {
         self.solver_groups
@@ -215,6 +384,41 @@ impl Collider {
         }
     }
 
+    /// The broad-phase layer of this collider.
+    ///
+    /// See [`BroadPhase::set_layer_mask`](crate::geometry::BroadPhase::set_layer_mask) for details.
+    pub fn broad_phase_layer(&self) -> u8 {
+        self.broad_phase_layer
+    }
+
+    /// Sets the broad-phase layer of this collider.
+    ///
+    /// The layer must be smaller than [`BroadPhase::MAX_LAYERS`](crate::geometry::BroadPhase::MAX_LAYERS).
+    /// Changing the layer of a collider will re-insert its proxy into the broad-phase
+    /// during the next update, which may be costly.
+    pub fn set_broad_phase_layer(&mut self, layer: u8) {
+        assert!(
+            (layer as usize) < crate::geometry::BroadPhase::MAX_LAYERS,
+            "Invalid broad-phase layer."
+        );
+        if self.broad_phase_layer != layer {
+            self.changes.insert(ColliderChanges::BROAD_PHASE_LAYER);
+            self.broad_phase_layer = layer;
+        }
+    }
+
+    /// The events enabled for this collider.
+    pub fn active_events(&self) -> ActiveEvents {
+        self.active_events
+    }
+
+    /// Sets the events enabled for this collider.
+    ///
+    /// This only affects the events generated after this change.
+    pub fn set_active_events(&mut self, active_events: ActiveEvents) {
+        self.active_events = active_events;
+    }
+
     /// The density of this collider, if set.
     pub fn density(&self) -> Option<Real> {
         match &self.mass_info {
@@ -228,22 +432,305 @@ impl Collider {
         &*self.shape.0
     }
 
+    /// The type of the geometric shape of this collider.
+    pub fn shape_type(&self) -> ShapeType {
+        self.shape.shape_type()
+    }
+
+    /// A typed view of the geometric shape of this collider, for pattern matching.
+    pub fn as_typed_shape(&self) -> TypedShape {
+        TypedShape::from_shape(self.shape())
+    }
+
     /// A mutable reference to the geometric shape of this collider.
     ///
     /// If that shape is shared by multiple colliders, it will be
     /// cloned first so that `self` contains a unique copy of that
     /// shape that you can modify.
+    ///
+    /// The internal edges correction enabled by `TriMeshFlags::FIX_INTERNAL_EDGES` is recomputed
+    /// at the next timestep if the modified shape is still a triangle mesh, and disabled otherwise.
     pub fn shape_mut(&mut self) -> &mut dyn Shape {
         self.changes.insert(ColliderChanges::SHAPE);
         self.shape.make_mut()
     }
 
     /// Sets the shape of this collider.
+    ///
+    /// The internal edges correction enabled by `TriMeshFlags::FIX_INTERNAL_EDGES` is recomputed
+    /// at the next timestep if the new shape is a triangle mesh, and disabled otherwise.
     pub fn set_shape(&mut self, shape: SharedShape) {
         self.changes.insert(ColliderChanges::SHAPE);
         self.shape = shape;
     }
 
+    /// Recomputes the internal edges of the triangle mesh shape of this collider, if their
+    /// correction is enabled, after its shape was replaced or modified by the user.
+    #[cfg(feature = "dim3")]
+    pub(crate) fn update_trimesh_internal_edges(&mut self) {
+        if self.trimesh_internal_edges.is_some() {
+            self.trimesh_internal_edges = self.shape.as_trimesh().map(|trimesh| {
+                Box::new(TriMeshInternalEdges::new(
+                    trimesh.vertices(),
+                    trimesh.indices(),
+                ))
+            });
+        }
+    }
+
+    /// Replaces the heights of a sub-region of the heightfield shape of this collider.
+    ///
+    /// The heights with indices in `i_range` are replaced by `new_heights`, which must
+    /// contain `i_range.len()` elements. Contrary to `Collider::set_shape`, only the contacts
+    /// located on the modified region lose their warmstarting impulses, and the bodies in contact
+    /// with that region are woken up, even if this collider is attached to a static body.
+    ///
+    /// Returns `false`, and does nothing, if the shape of this collider isn't a heightfield.
+    #[cfg(feature = "dim2")]
+    pub fn update_heightfield_region(
+        &mut self,
+        i_range: Range<usize>,
+        new_heights: &[Real],
+    ) -> bool {
+        let heightfield = match self.shape().as_heightfield() {
+            Some(heightfield) => heightfield,
+            None => return false,
+        };
+
+        assert_eq!(
+            i_range.len(),
+            new_heights.len(),
+            "The number of new heights must match the size of the modified region."
+        );
+
+        let mut heights = heightfield.heights().clone();
+        assert!(
+            i_range.end <= heights.len(),
+            "Heightfield region out of bounds."
+        );
+
+        // The segments adjacent to the modified heights are modified too.
+        let rows = i_range.start.saturating_sub(1)..(i_range.end + 1).min(heights.len());
+        let (mut min_y, mut max_y) = (Real::MAX, -Real::MAX);
+
+        for i in rows.clone() {
+            min_y = min_y.min(heights[i]);
+            max_y = max_y.max(heights[i]);
+        }
+
+        heights
+            .rows_mut(i_range.start, i_range.len())
+            .copy_from_slice(new_heights);
+
+        for i in rows.clone() {
+            min_y = min_y.min(heights[i]);
+            max_y = max_y.max(heights[i]);
+        }
+
+        let scale = *heightfield.scale();
+        let x = |i: usize| (-0.5 + i as Real / (heights.len() - 1) as Real) * scale.x;
+        let p1 = Point::new(x(rows.start), min_y * scale.y);
+        let p2 = Point::new(x(rows.end - 1), max_y * scale.y);
+
+        let num_segments = heights.len() - 1;
+        let mut new_heightfield = HeightField::new(heights, scale);
+        for i in 0..num_segments {
+            if heightfield.is_segment_removed(i) {
+                new_heightfield.set_segment_removed(i, true);
+            }
+        }
+
+        self.set_shape_region(
+            SharedShape::new(new_heightfield),
+            AABB::new(p1.inf(&p2), p1.sup(&p2)),
+        );
+        true
+    }
+
+    /// Replaces the heights of a sub-region of the heightfield shape of this collider.
+    ///
+    /// The heights with row indices in `i_range` and column indices in `j_range` are replaced by
+    /// `new_heights`, which must have `i_range.len()` rows and `j_range.len()` columns. Contrary to
+    /// `Collider::set_shape`, only the contacts located on the modified region lose their
+    /// warmstarting impulses, and the bodies in contact with that region are woken up, even if
+    /// this collider is attached to a static body.
+    ///
+    /// Returns `false`, and does nothing, if the shape of this collider isn't a heightfield.
+    #[cfg(feature = "dim3")]
+    pub fn update_heightfield_region(
+        &mut self,
+        i_range: Range<usize>,
+        j_range: Range<usize>,
+        new_heights: &na::DMatrix<Real>,
+    ) -> bool {
+        let heightfield = match self.shape().as_heightfield() {
+            Some(heightfield) => heightfield,
+            None => return false,
+        };
+
+        assert_eq!(
+            (i_range.len(), j_range.len()),
+            new_heights.shape(),
+            "The size of the new heights matrix must match the size of the modified region."
+        );
+
+        let mut heights = heightfield.heights().clone();
+        let (nrows, ncols) = heights.shape();
+        assert!(
+            i_range.end <= nrows && j_range.end <= ncols,
+            "Heightfield region out of bounds."
+        );
+
+        // The cells adjacent to the modified heights are modified too.
+        let rows = i_range.start.saturating_sub(1)..(i_range.end + 1).min(nrows);
+        let cols = j_range.start.saturating_sub(1)..(j_range.end + 1).min(ncols);
+        let (mut min_y, mut max_y) = (Real::MAX, -Real::MAX);
+
+        for i in rows.clone() {
+            for j in cols.clone() {
+                min_y = min_y.min(heights[(i, j)]);
+                max_y = max_y.max(heights[(i, j)]);
+            }
+        }
+
+        heights
+            .slice_mut((i_range.start, j_range.start), new_heights.shape())
+            .copy_from(new_heights);
+
+        for i in rows.clone() {
+            for j in cols.clone() {
+                min_y = min_y.min(heights[(i, j)]);
+                max_y = max_y.max(heights[(i, j)]);
+            }
+        }
+
+        let scale = *heightfield.scale();
+        let coord = |k: usize, n: usize| -0.5 + k as Real / (n - 1) as Real;
+        let p1 = Point::new(
+            coord(cols.start, ncols) * scale.x,
+            min_y * scale.y,
+            coord(rows.start, nrows) * scale.z,
+        );
+        let p2 = Point::new(
+            coord(cols.end - 1, ncols) * scale.x,
+            max_y * scale.y,
+            coord(rows.end - 1, nrows) * scale.z,
+        );
+
+        let mut new_heightfield = HeightField::new(heights, scale);
+        *new_heightfield.cells_statuses_mut() = heightfield.cells_statuses().clone();
+
+        self.set_shape_region(
+            SharedShape::new(new_heightfield),
+            AABB::new(p1.inf(&p2), p1.sup(&p2)),
+        );
+        true
+    }
+
+    /// Moves some of the vertices of the triangle mesh shape of this collider.
+    ///
+    /// The vertex with index `indices_changed[i]` is moved to `new_positions[i]`. Contrary to
+    /// `Collider::set_shape`, only the contacts located on the triangles adjacent to the moved
+    /// vertices lose their warmstarting impulses, and the bodies in contact with these triangles
+    /// are woken up, even if this collider is attached to a static body. The internal edges
+    /// correction enabled by `TriMeshFlags::FIX_INTERNAL_EDGES` is preserved.
+    ///
+    /// Returns `false`, and does nothing, if the shape of this collider isn't a triangle mesh.
+    pub fn update_trimesh_vertices(
+        &mut self,
+        indices_changed: &[u32],
+        new_positions: &[Point<Real>],
+    ) -> bool {
+        let trimesh = match self.shape().as_trimesh() {
+            Some(trimesh) => trimesh,
+            None => return false,
+        };
+
+        assert_eq!(
+            indices_changed.len(),
+            new_positions.len(),
+            "The number of new positions must match the number of modified vertices."
+        );
+
+        let mut vertices = trimesh.vertices().to_vec();
+        let indices = trimesh.indices().to_vec();
+        let mut moved = vec![false; vertices.len()];
+        let mut region = AABB::new_invalid();
+
+        for (i, pt) in indices_changed.iter().zip(new_positions.iter()) {
+            let i = *i as usize;
+            region.take_point(vertices[i]);
+            region.take_point(*pt);
+            vertices[i] = *pt;
+            moved[i] = true;
+        }
+
+        // The triangles adjacent to the moved vertices are modified too.
+        for triangle in &indices {
+            if triangle.iter().any(|i| moved[*i as usize]) {
+                for i in triangle {
+                    region.take_point(vertices[*i as usize]);
+                }
+            }
+        }
+
+        #[cfg(feature = "dim3")]
+        {
+            if self.trimesh_internal_edges.is_some() {
+                self.trimesh_internal_edges =
+                    Some(Box::new(TriMeshInternalEdges::new(&vertices, &indices)));
+            }
+        }
+
+        self.set_shape_region(SharedShape::trimesh(vertices, indices), region);
+        true
+    }
+
+    /// Sets the position of this collider at the end of the timestep, given the next position of
+    /// its parent.
+    pub(crate) fn set_predicted_parent_position(&mut self, parent_position: &Isometry<Real>) {
+        self.changes.insert(ColliderChanges::POSITION);
+        self.predicted_position = Some(parent_position * self.delta);
+    }
+
+    fn set_shape_region(&mut self, shape: SharedShape, region: AABB) {
+        self.changes.insert(ColliderChanges::SHAPE_REGION);
+        self.modified_region = Some(match self.modified_region {
+            Some(modified_region) => modified_region.merged(&region),
+            None => region,
+        });
+        self.shape = shape;
+    }
+
+    /// The endpoints and radius of the shape of this collider, if it is a capsule.
+    ///
+    /// The endpoints are expressed in the local-space of this collider.
+    pub fn capsule_segment(&self) -> Option<(Point<Real>, Point<Real>, Real)> {
+        self.shape()
+            .as_capsule()
+            .map(|capsule| (capsule.segment.a, capsule.segment.b, capsule.radius))
+    }
+
+    /// The endpoints of the main axis and the radius of the shape of this collider, if it is a cylinder.
+    ///
+    /// This recognizes the cylinders built with both `ColliderBuilder::cylinder` and
+    /// `ColliderBuilder::cylinder_from_axis`. The endpoints are expressed in the local-space
+    /// of this collider.
+    #[cfg(feature = "dim3")]
+    pub fn cylinder_segment(&self) -> Option<(Point<Real>, Point<Real>, Real)> {
+        let (pos, cylinder) = if let Some(cylinder) = self.shape().as_cylinder() {
+            (Isometry::identity(), cylinder)
+        } else {
+            match self.shape().as_compound()?.shapes() {
+                [(pos, shape)] => (*pos, shape.as_cylinder()?),
+                _ => return None,
+            }
+        };
+
+        let p = Point::from(Vector::y() * cylinder.half_height);
+        Some((pos * -p, pos * p, cylinder.radius))
+    }
+
     /// Compute the axis-aligned bounding box of this collider.
     pub fn compute_aabb(&self) -> AABB {
         self.shape.compute_aabb(&self.position)
@@ -256,6 +743,14 @@ impl Collider {
         aabb1.merged(&aabb2)
     }
 
+    /// The margin the AABB of this collider is loosened with in the broad-phase.
+    ///
+    /// It is increased by the gap the contacts of this collider target, if any, so that the
+    /// narrow-phase sees the pairs before the colliders get that close.
+    pub(crate) fn broad_phase_margin(&self, prediction_distance: Real) -> Real {
+        prediction_distance / 2.0 + (-self.contact_target_penetration).max(0.0)
+    }
+
     /// Compute the local-space mass properties of this collider.
     pub fn mass_properties(&self) -> MassProperties {
         match &self.mass_info {
@@ -284,6 +779,14 @@ pub struct ColliderBuilder {
     pub restitution: Real,
     /// The rule used to combine two restitution coefficients.
     pub restitution_combine_rule: CoefficientCombineRule,
+    /// The rolling resistance coefficient of the collider to be built.
+    pub rolling_resistance: Real,
+    /// The penetration depth the contacts of the collider to be built are resolved toward.
+    pub contact_target_penetration: Real,
+    /// The normal impulses smaller than this are reported as zero for the collider to be built.
+    pub min_reported_impulse: Real,
+    /// The maximum rotation per timestep of the contact normals of the collider to be built.
+    pub normal_smoothing: Option<Real>,
     /// The position of this collider relative to the local frame of the rigid-body it is attached to.
     pub delta: Isometry<Real>,
     /// Is this collider a sensor?
@@ -293,10 +796,20 @@ pub struct ColliderBuilder {
     pub modify_solver_contacts: bool,
     /// The user-data of the collider being built.
     pub user_data: u128,
+    /// The user tags of the collider being built.
+    pub user_tags: ColliderUserTags,
+    /// The key of the collider being built in the warmstart cache of the narrow-phase.
+    pub warmstart_cache_key: Option<u64>,
     /// The collision groups for the collider being built.
     pub collision_groups: InteractionGroups,
     /// The solver groups for the collider being built.
     pub solver_groups: InteractionGroups,
+    /// The broad-phase layer for the collider being built.
+    pub broad_phase_layer: u8,
+    /// The events enabled for the collider being built.
+    pub active_events: ActiveEvents,
+    #[cfg(feature = "dim3")]
+    trimesh_internal_edges: Option<Box<TriMeshInternalEdges>>,
 }
 
 impl ColliderBuilder {
@@ -308,17 +821,34 @@ impl ColliderBuilder {
             mass_properties: None,
             friction: Self::default_friction(),
             restitution: 0.0,
+            rolling_resistance: 0.0,
+            contact_target_penetration: 0.0,
+            min_reported_impulse: 0.0,
+            normal_smoothing: None,
             delta: Isometry::identity(),
             is_sensor: false,
             user_data: 0,
+            user_tags: ColliderUserTags::default(),
+            warmstart_cache_key: None,
             collision_groups: InteractionGroups::all(),
             solver_groups: InteractionGroups::all(),
+            broad_phase_layer: 0,
+            active_events: ActiveEvents::empty(),
             friction_combine_rule: CoefficientCombineRule::Average,
             restitution_combine_rule: CoefficientCombineRule::Average,
             modify_solver_contacts: false,
+            #[cfg(feature = "dim3")]
+            trimesh_internal_edges: None,
         }
     }
 
+    /// Initialize a new collider builder with a user-defined shape.
+    ///
+    /// This shape is exposed as `TypedShape::Custom` by `Collider::as_typed_shape`.
+    pub fn custom(shape: impl Shape) -> Self {
+        Self::new(SharedShape(Arc::new(shape)))
+    }
+
     /// Initialize a new collider builder with a compound shape.
     pub fn compound(shapes: Vec<(Isometry<Real>, SharedShape)>) -> Self {
         Self::new(SharedShape::compound(shapes))
@@ -329,8 +859,19 @@ impl ColliderBuilder {
         Self::new(SharedShape::ball(radius))
     }
 
+    /// Initialize a new collider builder for a projectile with a ball shape defined by its radius.
+    ///
+    /// The collider has no friction and no restitution, so it doesn't bounce nor spin on impact.
+    /// It is meant to be attached to a rigid-body built with `RigidBodyBuilder::projectile`.
+    pub fn projectile(radius: Real) -> Self {
+        Self::ball(radius).friction(0.0).restitution(0.0)
+    }
+
     /// Initialize a new collider build with a half-space shape defined by the outward normal
     /// of its planar boundary.
+    ///
+    /// A half-space is infinite, and has no mass. It is meant to be attached to a static
+    /// rigid-body, e.g., as an infinite ground plane.
     pub fn halfspace(outward_normal: Unit<Vector<Real>>) -> Self {
         Self::new(SharedShape::halfspace(outward_normal))
     }
@@ -342,6 +883,23 @@ impl ColliderBuilder {
         Self::new(SharedShape::cylinder(half_height, radius))
     }
 
+    /// Initialize a new collider builder with a cylindrical shape defined by its main axis,
+    /// its half-height (along this axis), and its radius.
+    ///
+    /// The cylinder is stored as a compound shape containing a single rotated cylinder. This
+    /// leaves the position of the collider relative to its parent free for other uses, and
+    /// results in the same mass properties and contacts as a `ColliderBuilder::cylinder`
+    /// rotated by its position relative to its parent.
+    #[cfg(feature = "dim3")]
+    pub fn cylinder_from_axis(axis: Unit<Vector<Real>>, half_height: Real, radius: Real) -> Self {
+        let rotation = Rotation::rotation_between_axis(&Vector::y_axis(), &axis)
+            .unwrap_or_else(|| Rotation::from_axis_angle(&Vector::x_axis(), Real::pi()));
+        let mut pos = Isometry::identity();
+        pos.rotation = rotation;
+
+        Self::compound(vec![(pos, SharedShape::cylinder(half_height, radius))])
+    }
+
     /// Initialize a new collider builder with a rounded cylindrical shape defined by its half-height
     /// (along along the y axis), its radius, and its roundedness (the
     /// radius of the sphere used for dilating the cylinder).
@@ -382,6 +940,12 @@ impl ColliderBuilder {
         Self::new(SharedShape::round_cuboid(hx, hy, border_radius))
     }
 
+    /// Initialize a new collider builder with a capsule shape defined by the endpoints of its
+    /// segment and its radius.
+    pub fn capsule(a: Point<Real>, b: Point<Real>, radius: Real) -> Self {
+        Self::new(SharedShape::capsule(a, b, radius))
+    }
+
     /// Initialize a new collider builder with a capsule shape aligned with the `x` axis.
     pub fn capsule_x(half_height: Real, radius: Real) -> Self {
         let p = Point::from(Vector::x() * half_height);
@@ -444,6 +1008,71 @@ impl ColliderBuilder {
         Self::new(SharedShape::trimesh(vertices, indices))
     }
 
+    /// Initializes a collider builder with a triangle mesh shape defined by its vertex and index buffers,
+    /// and flags controlling how contacts with this triangle mesh are computed.
+    ///
+    /// Heightfields don't support these flags: a heightfield surface that needs the correction of
+    /// `TriMeshFlags::FIX_INTERNAL_EDGES` must be given to this method as a triangle mesh.
+    #[cfg(feature = "dim3")]
+    pub fn trimesh_with_flags(
+        vertices: Vec<Point<Real>>,
+        indices: Vec<[u32; 3]>,
+        flags: TriMeshFlags,
+    ) -> Self {
+        let internal_edges = if flags.contains(TriMeshFlags::FIX_INTERNAL_EDGES) {
+            Some(Box::new(TriMeshInternalEdges::new(&vertices, &indices)))
+        } else {
+            None
+        };
+
+        let mut builder = Self::trimesh(vertices, indices);
+        builder.trimesh_internal_edges = internal_edges;
+        builder
+    }
+
+    /// Initializes a collider builder with the simplest shape matching the given triangle mesh.
+    ///
+    /// If all the vertices of the mesh are within `tolerance` of the surface of a cuboid, ball,
+    /// capsule, or cylinder, this primitive shape is used, and the mass properties are the ones
+    /// of this primitive shape. Otherwise, the convex-hull of the mesh is used if the mesh is convex,
+    /// the mesh is decomposed into convex parts if it is closed, or it is kept as a triangle mesh.
+    /// The kind of shape chosen is returned alongside the builder.
+    ///
+    /// Primitive shapes which are not centered and aligned with the axes of the mesh are stored as
+    /// a compound shape containing a single shape, like with `Self::cylinder_from_axis`.
+    #[cfg(feature = "dim3")]
+    pub fn from_mesh_auto(
+        vertices: Vec<Point<Real>>,
+        indices: Vec<[u32; 3]>,
+        tolerance: Real,
+    ) -> (Self, MeshClassification) {
+        let points = mesh_classification::used_vertices(&vertices, &indices);
+
+        if mesh_classification::is_convex(&vertices, &indices, &points, tolerance) {
+            if let Some(primitive) =
+                mesh_classification::fit_primitive(&vertices, &indices, &points, tolerance)
+            {
+                return (Self::new(primitive.shape), primitive.classification);
+            }
+
+            if let Some(builder) = Self::convex_hull(&points) {
+                return (builder, MeshClassification::ConvexHull);
+            }
+        }
+
+        if mesh_classification::is_closed(&indices) {
+            (
+                Self::convex_decomposition(&vertices, &indices),
+                MeshClassification::ConvexDecomposition,
+            )
+        } else {
+            (
+                Self::trimesh(vertices, indices),
+                MeshClassification::TriMesh,
+            )
+        }
+    }
+
     /// Initializes a collider builder with a compound shape obtained from the decomposition of
     /// the given trimesh (in 3D) or polyline (in 2D) into convex parts.
     pub fn convex_decomposition(vertices: &[Point<Real>], indices: &[[u32; DIM]]) -> Self {
@@ -550,6 +1179,9 @@ impl ColliderBuilder {
 
     /// Initializes a collider builder with a heightfield shape defined by its set of height and a scale
     /// factor along each coordinate axis.
+    ///
+    /// The contact normals generated on the internal edges of a heightfield are not corrected
+    /// (see `ColliderBuilder::trimesh_with_flags`).
     #[cfg(feature = "dim3")]
     pub fn heightfield(heights: na::DMatrix<Real>, scale: Vector<Real>) -> Self {
         Self::new(SharedShape::heightfield(heights, scale))
@@ -566,6 +1198,31 @@ impl ColliderBuilder {
         self
     }
 
+    /// Sets the user tags of the colliders built by this builder.
+    pub fn user_tags(mut self, user_tags: ColliderUserTags) -> Self {
+        self.user_tags = user_tags;
+        self
+    }
+
+    /// Sets the effect material of the colliders built by this builder.
+    ///
+    /// See `ColliderUserTags` for details.
+    pub fn effect_material(mut self, effect_material: u16) -> Self {
+        self.user_tags.set_effect_material(effect_material);
+        self
+    }
+
+    /// Sets the stable identifier of the colliders built by this builder in the warmstart cache
+    /// of the narrow-phase.
+    ///
+    /// Colliders removed and re-inserted frequently (e.g. pooled projectiles or debris) should
+    /// keep the same key so that their new contacts are warmstarted with the impulses of their
+    /// previous contacts. See `NarrowPhase::set_warmstart_cache_capacity` for details.
+    pub fn warmstart_cache_key(mut self, key: u64) -> Self {
+        self.warmstart_cache_key = Some(key);
+        self
+    }
+
     /// Sets the collision groups used by this collider.
     ///
     /// Two colliders will interact iff. their collision groups are compatible.
@@ -584,6 +1241,22 @@ impl ColliderBuilder {
         self
     }
 
+    /// Sets the broad-phase layer used by this collider.
+    ///
+    /// The broad-phase will never report pairs between two colliders with
+    /// layers masked-out from each other. See [`BroadPhase::set_layer_mask`](crate::geometry::BroadPhase::set_layer_mask)
+    /// for details.
+    pub fn broad_phase_layer(mut self, layer: u8) -> Self {
+        self.broad_phase_layer = layer;
+        self
+    }
+
+    /// Sets the events enabled for the collider built by this builder.
+    pub fn active_events(mut self, active_events: ActiveEvents) -> Self {
+        self.active_events = active_events;
+        self
+    }
+
     /// Sets whether or not the collider built by this builder is a sensor.
     ///
     /// Sensors will have a default density of zero,
@@ -624,6 +1297,41 @@ impl ColliderBuilder {
         self
     }
 
+    /// Sets the rolling resistance coefficient of the collider this builder will build.
+    ///
+    /// The rolling resistance of a contact is combined with the friction combine rule.
+    pub fn rolling_resistance(mut self, rolling_resistance: Real) -> Self {
+        self.rolling_resistance = rolling_resistance;
+        self
+    }
+
+    /// Sets the penetration depth the contacts of the collider this builder will build are
+    /// resolved toward.
+    ///
+    /// See `Collider::contact_target_penetration` for details.
+    pub fn contact_target_penetration(mut self, penetration: Real) -> Self {
+        self.contact_target_penetration = penetration;
+        self
+    }
+
+    /// Sets the threshold below which the normal impulses of the contacts of the collider this
+    /// builder will build are reported as zero.
+    ///
+    /// See `Collider::min_reported_impulse` for details.
+    pub fn min_reported_impulse(mut self, min_reported_impulse: Real) -> Self {
+        self.min_reported_impulse = min_reported_impulse;
+        self
+    }
+
+    /// Sets the maximum angle, in radians, by which the contact normals of the collider this
+    /// builder will build may rotate from one timestep to the next.
+    ///
+    /// See `Collider::normal_smoothing` for details.
+    pub fn normal_smoothing(mut self, max_rotation_per_step: Real) -> Self {
+        self.normal_smoothing = Some(max_rotation_per_step);
+        self
+    }
+
     /// Sets the uniform density of the collider this builder will build.
     ///
     /// This will be overridden by a call to [`Self::mass_properties`] so it only makes sense to call
@@ -692,6 +1400,11 @@ impl ColliderBuilder {
 
     /// Builds a new collider attached to the given rigid-body.
     pub fn build(&self) -> Collider {
+        assert!(
+            (self.broad_phase_layer as usize) < crate::geometry::BroadPhase::MAX_LAYERS,
+            "Invalid broad-phase layer."
+        );
+
         let mass_info = if let Some(mp) = self.mass_properties {
             MassInfo::MassProperties(Box::new(mp))
         } else {
@@ -716,6 +1429,10 @@ impl ColliderBuilder {
             mass_info,
             friction: self.friction,
             restitution: self.restitution,
+            rolling_resistance: self.rolling_resistance,
+            contact_target_penetration: self.contact_target_penetration,
+            min_reported_impulse: self.min_reported_impulse,
+            normal_smoothing: self.normal_smoothing,
             delta: self.delta,
             flags,
             solver_flags,
@@ -723,9 +1440,336 @@ impl ColliderBuilder {
             parent: RigidBodyHandle::invalid(),
             position: Isometry::identity(),
             proxy_index: crate::INVALID_U32,
+            #[cfg(feature = "dim3")]
+            trimesh_internal_edges: self.trimesh_internal_edges.clone(),
+            modified_region: None,
+            predicted_position: None,
             collision_groups: self.collision_groups,
             solver_groups: self.solver_groups,
+            broad_phase_layer: self.broad_phase_layer,
+            active_events: self.active_events,
+            warmstart_cache_key: self.warmstart_cache_key,
+            exclusions: Vec::new(),
             user_data: self.user_data,
+            user_tags: self.user_tags,
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::dynamics::{
+        CCDSolver, IntegrationParameters, JointSet, MassProperties, RigidBodyBuilder, RigidBodySet,
+        UserConstraintSet,
+    };
+    use crate::geometry::{BroadPhase, ColliderBuilder, ColliderSet, NarrowPhase};
+    use crate::math::{Isometry, Point, Real, Vector};
+    use crate::pipeline::PhysicsPipeline;
+
+    // Drops a body with the given collider on the ground and returns the resulting world-space
+    // contact points and distances.
+    fn ground_contacts(collider: ColliderBuilder) -> Vec<(Point<Real>, Real)> {
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_collider = ColliderBuilder::cuboid(10.0, 1.0).build();
+        #[cfg(feature = "dim3")]
+        let ground_collider = ColliderBuilder::cuboid(10.0, 1.0, 10.0).build();
+        let ground_collider = colliders.insert(ground_collider, ground, &mut bodies);
+
+        #[cfg(feature = "dim2")]
+        let rb = RigidBodyBuilder::new_dynamic()
+            .translation(0.0, 1.0)
+            .rotation(0.1)
+            .build();
+        #[cfg(feature = "dim3")]
+        let rb = RigidBodyBuilder::new_dynamic()
+            .translation(0.0, 1.0, 0.0)
+            .rotation(Vector::new(0.1, 0.0, 0.2))
+            .build();
+        let body = bodies.insert(rb);
+        let collider = colliders.insert(collider.build(), body, &mut bodies);
+
+        pipeline.step(
+            &gravity,
+            &integration_parameters,
+            &mut broad_phase,
+            &mut narrow_phase,
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            &mut user_constraints,
+            &mut ccd,
+            &(),
+            &(),
+        );
+
+        narrow_phase
+            .contact_pair(ground_collider, collider)
+            .unwrap()
+            .manifolds
+            .iter()
+            .flat_map(|m| m.data.solver_contacts.iter())
+            .map(|c| (c.point, c.dist))
+            .collect()
+    }
+
+    fn assert_same_mass_properties(mprops1: MassProperties, mprops2: MassProperties) {
+        assert!((1.0 / mprops1.inv_mass - 1.0 / mprops2.inv_mass).abs() < 1.0e-4);
+        assert!((mprops1.local_com - mprops2.local_com).norm() < 1.0e-4);
+        #[cfg(feature = "dim2")]
+        {
+            let inertia1 = 1.0 / mprops1.inv_principal_inertia_sqrt.powi(2);
+            let inertia2 = 1.0 / mprops2.inv_principal_inertia_sqrt.powi(2);
+            assert!((inertia1 - inertia2).abs() < 1.0e-4);
+        }
+        #[cfg(feature = "dim3")]
+        assert!(
+            (mprops1.reconstruct_inertia_matrix() - mprops2.reconstruct_inertia_matrix()).norm()
+                < 1.0e-4
+        );
+    }
+
+    fn assert_same_contacts(contacts1: &[(Point<Real>, Real)], contacts2: &[(Point<Real>, Real)]) {
+        assert!(!contacts1.is_empty());
+        assert_eq!(contacts1.len(), contacts2.len());
+
+        for (c1, c2) in contacts1.iter().zip(contacts2.iter()) {
+            assert!((c1.0 - c2.0).norm() < 1.0e-4);
+            assert!((c1.1 - c2.1).abs() < 1.0e-4);
+        }
+    }
+
+    #[test]
+    fn capsule_from_segment_matches_rotated_capsule() {
+        #[cfg(feature = "dim2")]
+        let pos = Isometry::new(Vector::new(0.1, 0.2), 0.5);
+        #[cfg(feature = "dim3")]
+        let pos = Isometry::new(Vector::new(0.1, 0.2, 0.3), Vector::new(0.5, 0.0, 0.3));
+
+        let a = pos * Point::from(Vector::y() * -0.5);
+        let b = pos * Point::from(Vector::y() * 0.5);
+        let from_segment = ColliderBuilder::capsule(a, b, 0.3);
+        let rotated = ColliderBuilder::capsule_y(0.5, 0.3).position_wrt_parent(pos);
+
+        let (sa, sb, radius) = from_segment.clone().build().capsule_segment().unwrap();
+        assert!((sa - a).norm() < 1.0e-5 && (sb - b).norm() < 1.0e-5);
+        assert_eq!(radius, 0.3);
+
+        assert_same_mass_properties(
+            from_segment.clone().build().mass_properties(),
+            rotated.clone().build().mass_properties().transform_by(&pos),
+        );
+        assert_same_contacts(&ground_contacts(from_segment), &ground_contacts(rotated));
+    }
+
+    #[cfg(feature = "dim3")]
+    #[test]
+    fn cylinder_from_axis_matches_rotated_cylinder() {
+        use na::Unit;
+
+        for axis in &[
+            Vector::new(1.0, 2.0, 0.5),
+            Vector::y(),
+            -Vector::y(),
+            Vector::x(),
+        ] {
+            let axis = Unit::new_normalize(*axis);
+            let from_axis = ColliderBuilder::cylinder_from_axis(axis, 0.5, 0.3);
+
+            let (a, b, radius) = from_axis.clone().build().cylinder_segment().unwrap();
+            assert!((a - Point::from(axis.into_inner() * -0.5)).norm() < 1.0e-5);
+            assert!((b - Point::from(axis.into_inner() * 0.5)).norm() < 1.0e-5);
+            assert_eq!(radius, 0.3);
+
+            let mut pos = Isometry::identity();
+            pos.rotation = from_axis.shape.as_compound().unwrap().shapes()[0]
+                .0
+                .rotation;
+            let rotated = ColliderBuilder::cylinder(0.5, 0.3).position_wrt_parent(pos);
+
+            assert_same_mass_properties(
+                from_axis.clone().build().mass_properties(),
+                rotated.clone().build().mass_properties().transform_by(&pos),
+            );
+            assert_same_contacts(&ground_contacts(from_axis), &ground_contacts(rotated));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "dim3")]
+    fn meshes_are_classified_as_primitive_shapes() {
+        use crate::geometry::{mesh_classification, MeshClassification};
+        use na::RealField;
+
+        // Deterministic noise in [-1.0e-3, 1.0e-3].
+        let noise = |i: usize| ((i * 7919) % 1000) as Real / 1000.0 * 2.0e-3 - 1.0e-3;
+        let add_noise = |vertices: &mut Vec<Point<Real>>| {
+            for (i, pt) in vertices.iter_mut().enumerate() {
+                pt.coords += Vector::new(noise(3 * i), noise(3 * i + 1), noise(3 * i + 2));
+            }
+        };
+
+        // A rotated and translated box.
+        let pos = Isometry::new(Vector::new(1.0, 2.0, 3.0), Vector::new(0.3, 0.2, 0.1));
+        let mut vertices: Vec<_> = (0..8)
+            .map(|k| {
+                let pt = Point::new(
+                    if k & 1 == 0 { -1.0 } else { 1.0 },
+                    if k & 2 == 0 { -0.5 } else { 0.5 },
+                    if k & 4 == 0 { -0.25 } else { 0.25 },
+                );
+                pos * pt
+            })
+            .collect();
+        add_noise(&mut vertices);
+        let indices = vec![
+            [0, 2, 1],
+            [1, 2, 3],
+            [4, 5, 6],
+            [5, 7, 6],
+            [0, 1, 4],
+            [1, 5, 4],
+            [2, 6, 3],
+            [3, 6, 7],
+            [0, 4, 2],
+            [2, 4, 6],
+            [1, 3, 5],
+            [3, 7, 5],
+        ];
+        let (builder, classification) = ColliderBuilder::from_mesh_auto(vertices, indices, 1.0e-2);
+        assert_eq!(classification, MeshClassification::Cuboid);
+        let mprops = builder.build().mass_properties();
+        assert!((1.0 / mprops.inv_mass - 1.0).abs() < 2.0e-2);
+        assert!((mprops.local_com - Point::new(1.0, 2.0, 3.0)).norm() < 1.0e-2);
+
+        // A capsule along the `x` axis, made of rings of 16 vertices.
+        let (half_height, radius) = (1.0, 0.5);
+        let mut vertices = vec![
+            Point::new(-half_height - radius, 0.0, 0.0),
+            Point::new(half_height + radius, 0.0, 0.0),
+        ];
+        let mut rings = Vec::new();
+        let hemisphere_rings = |sign: Real| {
+            (1..=4).map(move |i| {
+                let angle = i as Real * Real::pi() / 8.0;
+                (
+                    sign * (half_height + radius * angle.cos()),
+                    radius * angle.sin(),
+                )
+            })
+        };
+        for (x, ring_radius) in hemisphere_rings(-1.0).chain(hemisphere_rings(1.0).rev()) {
+            rings.push(vertices.len() as u32);
+            for k in 0..16 {
+                let angle = k as Real * Real::pi() / 8.0;
+                vertices.push(Point::new(
+                    x,
+                    ring_radius * angle.cos(),
+                    ring_radius * angle.sin(),
+                ));
+            }
+        }
+        let mut indices = Vec::new();
+        for k in 0..16 {
+            let next = (k + 1) % 16;
+            let (first, last) = (rings[0], rings[rings.len() - 1]);
+            indices.push([0, first + next, first + k]);
+            indices.push([1, last + k, last + next]);
+            for ring in rings.windows(2) {
+                indices.push([ring[0] + k, ring[0] + next, ring[1] + k]);
+                indices.push([ring[0] + next, ring[1] + next, ring[1] + k]);
+            }
+        }
+        add_noise(&mut vertices);
+        let (builder, classification) = ColliderBuilder::from_mesh_auto(vertices, indices, 1.0e-2);
+        assert_eq!(classification, MeshClassification::Capsule);
+        let capsule = builder.shape.as_capsule().unwrap();
+        assert!((capsule.radius - radius).abs() < 1.0e-2);
+        assert!((capsule.half_height() - half_height).abs() < 1.0e-2);
+
+        // An L-shaped prism is concave.
+        let polygon = [
+            [0.0, 0.0],
+            [2.0, 0.0],
+            [2.0, 1.0],
+            [1.0, 1.0],
+            [1.0, 2.0],
+            [0.0, 2.0],
+        ];
+        let vertices: Vec<_> = [-0.5, 0.5]
+            .iter()
+            .flat_map(|z| polygon.iter().map(move |p| Point::new(p[0], p[1], *z)))
+            .collect();
+        // The caps are triangulated from the reflex vertex.
+        let mut indices = vec![[3, 4, 5], [3, 5, 0], [3, 0, 1], [3, 1, 2]];
+        let top_cap: Vec<_> = indices
+            .iter()
+            .map(|t| [t[0] + 6, t[2] + 6, t[1] + 6])
+            .collect();
+        indices.extend(top_cap);
+        for i in 0..6 {
+            let j = (i + 1) % 6;
+            indices.push([i, j, i + 6]);
+            indices.push([j, j + 6, i + 6]);
+        }
+        // NOTE: the convex decomposition chosen by `from_mesh_auto` is too slow to compute
+        //       in debug mode, so only check that the mesh is classified as concave and closed.
+        let points = mesh_classification::used_vertices(&vertices, &indices);
+        assert!(!mesh_classification::is_convex(
+            &vertices, &indices, &points, 1.0e-2
+        ));
+        assert!(mesh_classification::is_closed(&indices));
+    }
+
+    #[cfg(feature = "dim3")]
+    #[test]
+    fn set_shape_recomputes_trimesh_internal_edges() {
+        use crate::geometry::{SharedShape, TriMeshFlags};
+
+        let vertices = vec![
+            Point::new(0.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+            Point::new(0.0, 0.0, 1.0),
+            Point::new(1.0, 0.0, 1.0),
+        ];
+        let indices = vec![[0, 2, 1], [1, 2, 3]];
+
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        let co = ColliderBuilder::trimesh_with_flags(
+            vertices.clone(),
+            indices.clone(),
+            TriMeshFlags::FIX_INTERNAL_EDGES,
+        )
+        .build();
+        let handle = colliders.insert(co, ground, &mut bodies);
+
+        // Replacing the triangle mesh keeps the correction enabled.
+        colliders
+            .get_mut(handle)
+            .unwrap()
+            .set_shape(SharedShape::trimesh(vertices, indices));
+        colliders.handle_user_changes(&mut bodies);
+        assert!(colliders[handle].trimesh_internal_edges.is_some());
+
+        // Any other shape disables it.
+        colliders
+            .get_mut(handle)
+            .unwrap()
+            .set_shape(SharedShape::cuboid(1.0, 1.0, 1.0));
+        colliders.handle_user_changes(&mut bodies);
+        assert!(colliders[handle].trimesh_internal_edges.is_none());
+    }
+}
@@ -0,0 +1,184 @@
+use crate::geometry::collider::ColliderChanges;
+use crate::geometry::{
+    clamp_aabb, Collider, ColliderHandle, Ray, SAPProxyIndex, SimdQuadTree, AABB,
+};
+use crate::math::{Isometry, Real};
+use alloc::vec::Vec;
+use parry::bounding_volume::BoundingVolume;
+use parry::query::visitors::{BoundingVolumeIntersectionsVisitor, RayIntersectionsVisitor};
+
+/// The colliders of a rigid-body sharing a single broad-phase proxy.
+///
+/// The AABBs of the children are expressed in the local-space of the rigid-body, so the
+/// BVH indexing them only has to be rebuilt when a child is added, removed, or reshaped.
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+#[derive(Clone)]
+pub(crate) struct ColliderAggregate {
+    /// The broad-phase proxy shared by all the children.
+    pub proxy_index: SAPProxyIndex,
+    /// The position of the rigid-body at the last broad-phase update.
+    pub position: Isometry<Real>,
+    /// The predicted position of the rigid-body at the last broad-phase update.
+    pub predicted_position: Option<Isometry<Real>>,
+    /// The children, with their local-space AABBs enlarged by half the prediction distance.
+    pub children: Vec<(ColliderHandle, AABB)>,
+    /// The union of the local-space AABBs of the children.
+    pub local_aabb: AABB,
+    /// The BVH of the local-space AABBs of the children, indexed by their position in `children`.
+    pub bvh: SimdQuadTree<usize>,
+    /// Set if `children` changed since the last rebuild of `bvh`.
+    pub dirty: bool,
+    /// Set if the rigid-body moved since the last update of the proxy.
+    pub moved: bool,
+    /// Cleared when the rigid-body stops using an aggregate proxy, or is removed.
+    pub enabled: bool,
+}
+
+impl ColliderAggregate {
+    pub fn new() -> Self {
+        Self {
+            proxy_index: crate::INVALID_U32,
+            position: Isometry::identity(),
+            predicted_position: None,
+            children: Vec::new(),
+            local_aabb: AABB::new_invalid(),
+            bvh: SimdQuadTree::new(),
+            dirty: false,
+            moved: false,
+            enabled: true,
+        }
+    }
+
+    /// Is the BVH up-to-date with the children of this aggregate?
+    pub fn is_built(&self) -> bool {
+        self.enabled && !self.dirty && !self.children.is_empty()
+    }
+
+    /// Sets the position of the rigid-body, and clears its predicted position.
+    pub fn set_position(&mut self, position: Isometry<Real>) {
+        self.position = position;
+        self.predicted_position = None;
+        self.moved = true;
+    }
+
+    /// Sets the position the rigid-body will have at the end of the timestep.
+    pub fn set_predicted_position(&mut self, predicted_position: Isometry<Real>) {
+        self.predicted_position = Some(predicted_position);
+        self.moved = true;
+    }
+
+    /// Takes into account the shape modifications of a child, which is added to this aggregate
+    /// if it doesn't have a broad-phase proxy yet.
+    ///
+    /// The position of the aggregate is set from the rigid-body, not from its children.
+    /// Returns `true` if the child was added.
+    pub fn update_child(
+        &mut self,
+        handle: ColliderHandle,
+        collider: &Collider,
+        prediction_distance: Real,
+    ) -> bool {
+        let added = collider.proxy_index == crate::INVALID_U32;
+
+        if added
+            || collider.changes.intersects(
+                ColliderChanges::SHAPE
+                    | ColliderChanges::SHAPE_REGION
+                    | ColliderChanges::POSITION_WRT_PARENT,
+            )
+        {
+            let local_aabb = collider.shape().compute_aabb(&collider.delta);
+            let local_aabb =
+                clamp_aabb(local_aabb.loosened(collider.broad_phase_margin(prediction_distance)));
+
+            if added {
+                self.children.push((handle, local_aabb));
+            } else if let Some(child) = self.children.iter_mut().find(|child| child.0 == handle) {
+                child.1 = local_aabb;
+            }
+
+            self.dirty = true;
+        }
+
+        added
+    }
+
+    /// Removes a child from this aggregate, returning `false` if it wasn't part of it.
+    pub fn remove_child(&mut self, handle: ColliderHandle) -> bool {
+        let len = self.children.len();
+        self.children.retain(|child| child.0 != handle);
+        self.dirty = self.dirty || self.children.len() != len;
+        self.children.len() != len
+    }
+
+    /// Rebuilds the BVH and the local-space AABB from the children.
+    pub fn rebuild(&mut self) {
+        self.local_aabb = self
+            .children
+            .iter()
+            .fold(AABB::new_invalid(), |aabb, child| aabb.merged(&child.1));
+        let data = self
+            .children
+            .iter()
+            .enumerate()
+            .map(|(i, child)| (i, child.1));
+        self.bvh.clear_and_rebuild(data, 0.0);
+        self.dirty = false;
+    }
+
+    /// The world-space AABB of all the children, swept to the predicted position if there is one.
+    pub fn world_aabb(&self) -> AABB {
+        self.swept_aabb(&self.local_aabb)
+    }
+
+    /// The world-space AABB of the `i`-th child, swept to the predicted position if there is one.
+    pub fn child_world_aabb(&self, i: usize) -> AABB {
+        self.swept_aabb(&self.children[i].1)
+    }
+
+    fn swept_aabb(&self, local_aabb: &AABB) -> AABB {
+        let aabb = local_aabb.transform_by(&self.position);
+
+        match &self.predicted_position {
+            Some(predicted_position) => aabb.merged(&local_aabb.transform_by(predicted_position)),
+            None => aabb,
+        }
+    }
+
+    /// Pushes to `out` the indices of the children which swept AABB may intersect `aabb`.
+    ///
+    /// An index may be pushed twice if the rigid-body has a predicted position.
+    pub fn children_intersecting_world_aabb(&self, aabb: &AABB, out: &mut Vec<usize>) {
+        self.children_intersecting_local_aabb(&aabb.transform_by(&self.position.inverse()), out);
+
+        if let Some(predicted_position) = &self.predicted_position {
+            let local_aabb = aabb.transform_by(&predicted_position.inverse());
+            self.children_intersecting_local_aabb(&local_aabb, out);
+        }
+    }
+
+    /// Pushes to `out` the indices of the children with a local-space AABB intersecting `local_aabb`.
+    pub fn children_intersecting_local_aabb(&self, local_aabb: &AABB, out: &mut Vec<usize>) {
+        let mut callback = |i: &usize| {
+            out.push(*i);
+            true
+        };
+        let mut visitor = BoundingVolumeIntersectionsVisitor::new(local_aabb, &mut callback);
+        self.bvh.traverse_depth_first(&mut visitor);
+    }
+
+    /// Pushes to `out` the handles of the children with a local-space AABB hit by `local_ray`.
+    pub fn children_intersecting_local_ray(
+        &self,
+        local_ray: &Ray,
+        max_toi: Real,
+        out: &mut Vec<ColliderHandle>,
+    ) {
+        let mut callback = |i: &usize| {
+            out.push(self.children[*i].0);
+            true
+        };
+        let mut visitor = RayIntersectionsVisitor::new(local_ray, max_toi, &mut callback);
+        self.bvh.traverse_depth_first(&mut visitor);
+    }
+}
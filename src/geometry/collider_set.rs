@@ -1,10 +1,20 @@
 use crate::data::arena::Arena;
 use crate::data::pubsub::PubSub;
+use crate::data::HashMap;
 use crate::dynamics::{RigidBodyHandle, RigidBodySet};
-use crate::geometry::collider::ColliderChanges;
-use crate::geometry::{Collider, SAPProxyIndex};
+use crate::geometry::collider::{ColliderChanges, ColliderFlags};
+use crate::geometry::collider_welding::{self, WeldOptions};
+use crate::geometry::{
+    Collider, ColliderAggregate, Cuboid, InteractionGroups, SAPProxyIndex, SharedShape,
+};
+use crate::math::{Isometry, Real};
+use crate::pipeline::ActiveEvents;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ops::{Index, IndexMut};
 use parry::partitioning::IndexedData;
-use std::ops::{Index, IndexMut};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 /// The unique identifier of a collider added to a collider set.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
@@ -42,11 +52,49 @@ impl IndexedData for ColliderHandle {
     }
 }
 
+/// How the bodies touching a collider settle after the removal of this collider.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub enum RemoveSettleMode {
+    /// The contacts of the bodies touching the removed collider are solved as usual.
+    ///
+    /// The bodies which were supported by the removed collider may correct their whole
+    /// penetration with their next supports in a single timestep, sometimes popping upward.
+    Immediate,
+    /// The penetration correction and the warmstarting of the contacts involving the colliders
+    /// touching the removed collider are reduced during the given number of timesteps.
+    ///
+    /// Both increase linearly back to their usual values, so the load of the removed collider
+    /// is redistributed over these timesteps.
+    DampedOverNFrames(u8),
+}
+
+impl Default for RemoveSettleMode {
+    fn default() -> Self {
+        RemoveSettleMode::Immediate
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 pub(crate) struct RemovedCollider {
     pub handle: ColliderHandle,
     pub(crate) proxy_index: SAPProxyIndex,
+    pub(crate) active_events: ActiveEvents,
+    pub(crate) settle_mode: RemoveSettleMode,
+    pub(crate) warmstart_cache_key: Option<u64>,
+    pub(crate) detached: bool,
+}
+
+/// Statistics about the colliders of a `ColliderSet`.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ColliderSetStats {
+    /// The number of colliders in the set.
+    pub num_colliders: usize,
+    /// The number of colliders the set can contain without reallocating.
+    pub capacity: usize,
+    /// The approximate number of bytes allocated by the set, not counting the shapes.
+    pub bytes: usize,
 }
 
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
@@ -57,6 +105,8 @@ pub struct ColliderSet {
     pub(crate) colliders: Arena<Collider>,
     pub(crate) modified_colliders: Vec<ColliderHandle>,
     pub(crate) modified_all_colliders: bool,
+    // The aggregates of the rigid-bodies using an aggregate broad-phase proxy.
+    pub(crate) aggregates: parry::utils::hashmap::HashMap<RigidBodyHandle, ColliderAggregate>,
 }
 
 impl ColliderSet {
@@ -67,15 +117,40 @@ impl ColliderSet {
             colliders: Arena::new(),
             modified_colliders: Vec::new(),
             modified_all_colliders: false,
+            aggregates: parry::utils::hashmap::HashMap::default(),
         }
     }
 
+    /// Create a new empty set of colliders, with enough room for `capacity` colliders.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut result = Self::new();
+        result.reserve(capacity);
+        result
+    }
+
+    /// Reserves room for inserting at least `additional` more colliders without reallocating.
+    ///
+    /// This doesn't affect the handles given to the next inserted colliders.
+    pub fn reserve(&mut self, additional: usize) {
+        let num_free = self.colliders.capacity() - self.colliders.len();
+        self.colliders.reserve(additional.saturating_sub(num_free));
+        self.modified_colliders.reserve(additional);
+    }
+
+    /// Releases the excess capacity of this set, e.g., after the removal of many colliders.
+    ///
+    /// This doesn't invalidate any collider handle.
+    pub fn shrink_to_fit(&mut self) {
+        self.colliders.shrink_to_fit();
+        self.modified_colliders.shrink_to_fit();
+    }
+
     /// An always-invalid collider handle.
     pub fn invalid_handle() -> ColliderHandle {
         ColliderHandle::from_raw_parts(crate::INVALID_USIZE, crate::INVALID_U64)
     }
 
-    /// Iterate through all the colliders on this set.
+    /// Iterate through all the colliders on this set, including the detached ones.
     pub fn iter(&self) -> impl ExactSizeIterator<Item = (ColliderHandle, &Collider)> {
         self.colliders.iter().map(|(h, c)| (ColliderHandle(h), c))
     }
@@ -90,6 +165,28 @@ impl ColliderSet {
             .map(|(h, b)| (ColliderHandle(h), b))
     }
 
+    /// Iterates in parallel through all the colliders on this set, including the detached ones.
+    #[cfg(feature = "parallel")]
+    pub fn par_iter(&self) -> impl ParallelIterator<Item = (ColliderHandle, &Collider)> {
+        self.colliders
+            .par_iter()
+            .map(|(h, c)| (ColliderHandle(h), c))
+    }
+
+    /// Iterates mutably, and in parallel, through all the colliders on this set.
+    ///
+    /// Like with `Self::iter_mut`, every collider is considered modified.
+    #[cfg(all(feature = "parallel", not(feature = "dev-remove-slow-accessors")))]
+    pub fn par_iter_mut(
+        &mut self,
+    ) -> impl ParallelIterator<Item = (ColliderHandle, &mut Collider)> {
+        self.modified_colliders.clear();
+        self.modified_all_colliders = true;
+        self.colliders
+            .par_iter_mut()
+            .map(|(h, c)| (ColliderHandle(h), c))
+    }
+
     #[inline(always)]
     pub(crate) fn foreach_modified_colliders(&self, mut f: impl FnMut(ColliderHandle, &Collider)) {
         for handle in &self.modified_colliders {
@@ -116,6 +213,17 @@ impl ColliderSet {
         self.colliders.len()
     }
 
+    /// Statistics about the colliders of this set.
+    pub fn stats(&self) -> ColliderSetStats {
+        ColliderSetStats {
+            num_colliders: self.colliders.len(),
+            capacity: self.colliders.capacity(),
+            bytes: core::mem::size_of::<Self>()
+                + self.colliders.allocated_bytes()
+                + self.modified_colliders.capacity() * core::mem::size_of::<ColliderHandle>(),
+        }
+    }
+
     /// `true` if there are no colliders in this set.
     pub fn is_empty(&self) -> bool {
         self.colliders.is_empty()
@@ -134,6 +242,8 @@ impl ColliderSet {
         if self.modified_all_colliders {
             for collider in self.colliders.iter_mut() {
                 collider.1.changes = ColliderChanges::empty();
+                collider.1.modified_region = None;
+                collider.1.predicted_position = None;
             }
             self.modified_colliders.clear();
             self.modified_all_colliders = false;
@@ -144,6 +254,8 @@ impl ColliderSet {
                 //       so we need to do this `if let`.
                 if let Some(co) = self.colliders.get_mut(handle.0) {
                     co.changes = ColliderChanges::empty();
+                    co.modified_region = None;
+                    co.predicted_position = None;
                 }
             }
         }
@@ -169,25 +281,89 @@ impl ColliderSet {
             .expect("Parent rigid body not found.");
         coll.position = parent.position * coll.delta;
         let handle = ColliderHandle(self.colliders.insert(coll));
-        self.modified_colliders.push(handle);
 
-        let coll = self.colliders.get(handle.0).unwrap();
-        parent.add_collider(handle, &coll);
+        // NOTE: the collider is flagged as modified so it isn't pushed again to the
+        //       modified colliders by the next modification before the update.
+        let coll = self.colliders.get_mut(handle.0).unwrap();
+        Self::mark_as_modified(
+            handle,
+            coll,
+            &mut self.modified_colliders,
+            self.modified_all_colliders,
+        );
+        parent.add_collider(handle, coll);
         handle
     }
 
+    /// Inserts several colliders, each attached to the given rigid-body, into this set and
+    /// retrieves their handles, in the same order.
+    ///
+    /// This is equivalent to inserting each collider with `Self::insert`, and results in the
+    /// same handles, but the memory needed by all the colliders is reserved at once, and the
+    /// world-space mass properties of each parent are only recomputed once all the colliders
+    /// are attached to it.
+    pub fn insert_batch(
+        &mut self,
+        colliders: impl IntoIterator<Item = (Collider, RigidBodyHandle)>,
+        bodies: &mut RigidBodySet,
+    ) -> Vec<ColliderHandle> {
+        let mut colliders = colliders.into_iter();
+        let num_colliders = colliders.size_hint().0;
+        let mut handles = Vec::with_capacity(num_colliders);
+        let mut deferred_parents = Vec::new();
+        self.modified_colliders.reserve(num_colliders);
+
+        while let Some((coll, parent_handle)) = colliders.next() {
+            // NOTE: the arena is only grown once its free slots are used, so that the
+            //       handles are the same as the ones given by `Self::insert`. It grows at
+            //       least geometrically, like with `Self::insert`, if the size hint is too low.
+            if self.colliders.len() == self.colliders.capacity() {
+                let additional = colliders.size_hint().0 + 1;
+                self.colliders.reserve(additional.max(self.colliders.len()));
+            }
+
+            if bodies
+                .get(parent_handle)
+                .map(|parent| !parent.is_mass_update_deferred())
+                .unwrap_or(false)
+            {
+                bodies.begin_mass_update(parent_handle);
+                deferred_parents.push(parent_handle);
+            }
+
+            handles.push(self.insert(coll, parent_handle, bodies));
+        }
+
+        for parent_handle in deferred_parents {
+            bodies.end_mass_update(parent_handle);
+        }
+
+        handles
+    }
+
     /// Remove a collider from this set and update its parent accordingly.
     ///
     /// If `wake_up` is `true`, the rigid-body the removed collider is attached to
-    /// will be woken up.
+    /// will be woken up. The `settle_mode` controls how the bodies touching the
+    /// removed collider settle during the next timesteps.
     pub fn remove(
         &mut self,
         handle: ColliderHandle,
         bodies: &mut RigidBodySet,
         wake_up: bool,
+        settle_mode: RemoveSettleMode,
     ) -> Option<Collider> {
         let collider = self.colliders.remove(handle.0)?;
 
+        /*
+         * Delete the exclusions involving the collider.
+         */
+        for other in &collider.exclusions {
+            if let Some(other) = self.get_mut_internal_with_modification_tracking(*other) {
+                other.remove_exclusion(handle);
+            }
+        }
+
         /*
          * Delete the collider from its parent body.
          */
@@ -207,6 +383,10 @@ impl ColliderSet {
         let message = RemovedCollider {
             handle,
             proxy_index: collider.proxy_index,
+            active_events: collider.active_events,
+            settle_mode,
+            warmstart_cache_key: collider.warmstart_cache_key,
+            detached: false,
         };
 
         self.removed_colliders.publish(message);
@@ -214,6 +394,330 @@ impl ColliderSet {
         Some(collider)
     }
 
+    // Attaches a collider to another rigid-body, without changing its world-space position.
+    pub(crate) fn reparent(
+        &mut self,
+        handle: ColliderHandle,
+        parent_handle: RigidBodyHandle,
+        bodies: &mut RigidBodySet,
+    ) {
+        if let Some(collider) = self.colliders.get_mut(handle.0) {
+            // NOTE: we use `get_mut` instead of `get_mut_internal` so that the
+            // modification flag is updated properly.
+            let parent = bodies
+                .get_mut_internal_with_modification_tracking(parent_handle)
+                .expect("Parent rigid body not found.");
+
+            Self::mark_as_modified(
+                handle,
+                collider,
+                &mut self.modified_colliders,
+                self.modified_all_colliders,
+            );
+            collider
+                .changes
+                .insert(ColliderChanges::PARENT | ColliderChanges::POSITION_WRT_PARENT);
+            collider.parent = parent_handle;
+            collider.delta = parent.position.inverse() * collider.position;
+            parent.add_collider(handle, collider);
+        }
+    }
+
+    /// Removes all the colliders for which `f` returns `false` from this set, and updates their
+    /// parents accordingly.
+    ///
+    /// This is equivalent to removing each of these colliders with `Self::remove`, without
+    /// waking up their parents, but the world-space mass properties of each parent are only
+    /// recomputed once all its colliders are removed. Like with `Self::iter_mut`, every
+    /// collider retained is considered modified.
+    #[cfg(not(feature = "dev-remove-slow-accessors"))]
+    pub fn retain(
+        &mut self,
+        bodies: &mut RigidBodySet,
+        mut f: impl FnMut(ColliderHandle, &mut Collider) -> bool,
+    ) {
+        let removed: Vec<_> = self
+            .iter_mut()
+            .filter_map(|(handle, co)| if f(handle, co) { None } else { Some(handle) })
+            .collect();
+        let mut deferred_parents = Vec::new();
+
+        for handle in removed {
+            let parent_handle = self.colliders[handle.0].parent;
+
+            if bodies
+                .get(parent_handle)
+                .map(|parent| !parent.is_mass_update_deferred())
+                .unwrap_or(false)
+            {
+                bodies.begin_mass_update(parent_handle);
+                deferred_parents.push(parent_handle);
+            }
+
+            let _ = self.remove(handle, bodies, false, RemoveSettleMode::Immediate);
+        }
+
+        for parent_handle in deferred_parents {
+            bodies.end_mass_update(parent_handle);
+        }
+    }
+
+    /// Detaches a collider from its parent rigid-body, and from the simulation, while keeping it in this set.
+    ///
+    /// Until it is re-attached with `Self::attach`, the detached collider has no broad-phase
+    /// proxy, no contact or intersection, no contribution to the mass of its former parent, and
+    /// is ignored by the query pipeline, but its handle remains valid. The former parent is woken
+    /// up. During the next timestep, a `ContactEvent::Stopped` and an `IntersectionEvent` are
+    /// emitted for each active contact and each intersection involving the detached collider.
+    ///
+    /// Returns `false` if the collider doesn't exist or is already detached.
+    pub fn detach(&mut self, handle: ColliderHandle, bodies: &mut RigidBodySet) -> bool {
+        let collider = match self.colliders.get_mut(handle.0) {
+            Some(collider) if !collider.is_detached() => collider,
+            _ => return false,
+        };
+
+        Self::mark_as_modified(
+            handle,
+            collider,
+            &mut self.modified_colliders,
+            self.modified_all_colliders,
+        );
+        collider.changes.insert(ColliderChanges::PARENT);
+        collider.flags.insert(ColliderFlags::DETACHED);
+
+        // NOTE: we use `get_mut` instead of `get_mut_internal` so that the
+        // modification flag is updated properly.
+        if let Some(parent) = bodies.get_mut_internal_with_modification_tracking(collider.parent) {
+            parent.remove_collider_internal(handle, collider);
+            bodies.wake_up(collider.parent, true);
+        }
+
+        // NOTE: the proxy of the collider is removed by the broad-phase because of the
+        //       `PARENT` change, so it isn't removed twice if the collider is re-attached
+        //       before the next timestep.
+        let message = RemovedCollider {
+            handle,
+            proxy_index: crate::INVALID_U32,
+            active_events: collider.active_events,
+            settle_mode: RemoveSettleMode::Immediate,
+            warmstart_cache_key: collider.warmstart_cache_key,
+            detached: true,
+        };
+
+        collider.parent = RigidBodyHandle::invalid();
+        self.removed_colliders.publish(message);
+        true
+    }
+
+    /// Re-attaches a collider detached with `Self::detach` to the given rigid-body.
+    ///
+    /// The new parent may differ from the former one. The collider keeps its position relative
+    /// to its parent, and is simulated again starting with the next timestep.
+    ///
+    /// Returns `false` if the collider doesn't exist or isn't detached.
+    ///
+    /// # Panics
+    /// Panics if the rigid-body `parent_handle` doesn't exist.
+    pub fn attach(
+        &mut self,
+        handle: ColliderHandle,
+        parent_handle: RigidBodyHandle,
+        bodies: &mut RigidBodySet,
+    ) -> bool {
+        let collider = match self.colliders.get_mut(handle.0) {
+            Some(collider) if collider.is_detached() => collider,
+            _ => return false,
+        };
+
+        // NOTE: we use `get_mut` instead of `get_mut_internal` so that the
+        // modification flag is updated properly.
+        let parent = bodies
+            .get_mut_internal_with_modification_tracking(parent_handle)
+            .expect("Parent rigid body not found.");
+
+        Self::mark_as_modified(
+            handle,
+            collider,
+            &mut self.modified_colliders,
+            self.modified_all_colliders,
+        );
+        collider
+            .changes
+            .insert(ColliderChanges::PARENT | ColliderChanges::POSITION);
+        collider.flags.remove(ColliderFlags::DETACHED);
+        collider.parent = parent_handle;
+        collider.position = parent.position * collider.delta;
+        parent.add_collider(handle, collider);
+        true
+    }
+
+    // Inserts a collider detached from another set, keeping it detached.
+    pub(crate) fn insert_detached(&mut self, mut coll: Collider) -> ColliderHandle {
+        coll.reset_internal_references();
+        coll.flags.insert(ColliderFlags::DETACHED);
+        ColliderHandle(self.colliders.insert(coll))
+    }
+
+    /// Replaces the adjacent axis-aligned cuboids attached to static bodies by larger cuboids.
+    ///
+    /// The welded colliders are non-sensor cuboids attached to the same static body, aligned
+    /// with the axes of that body, and with the same friction, restitution, combine rules,
+    /// collision and solver groups, broad-phase layer, active events, and user data. Colliders
+    /// with exclusions are never welded. Cuboids sharing a whole face are welded first into
+    /// strips, then these strips are welded together, so a grid of tiles with the same material
+    /// becomes a handful of cuboids and the bodies rolling on it no longer bump into the seams
+    /// between tiles.
+    ///
+    /// The welded colliders are removed from this set the same way as `ColliderSet::remove`
+    /// does: their contacts are removed, and the bodies touching them are woken up, during the
+    /// next timestep, which then generates a `ContactEvent::Started` for each contact with the
+    /// new colliders. The new colliders are clones of one of the colliders they replace, without
+    /// their `warmstart_cache_key`.
+    ///
+    /// Returns the handle of the new collider replacing each welded collider. The colliders
+    /// which were not welded are left untouched and don't appear in this map.
+    pub fn weld_static_colliders(
+        &mut self,
+        bodies: &mut RigidBodySet,
+        options: &WeldOptions,
+    ) -> HashMap<ColliderHandle, ColliderHandle> {
+        let mut welded_handles = HashMap::default();
+
+        for welded in collider_welding::weld_cuboids(self, bodies, options) {
+            let mut collider = self.colliders[welded.colliders[0].0].clone();
+            let parent = collider.parent;
+            let half_extents = welded.aabb.half_extents();
+            let center = welded.aabb.center();
+            collider.set_shape(SharedShape(Arc::new(Cuboid::new(half_extents))));
+            collider.set_position_wrt_parent(Isometry::new(center.coords, na::zero()));
+            collider.changes = ColliderChanges::all();
+            collider.warmstart_cache_key = None;
+
+            let new_handle = self.insert(collider, parent, bodies);
+
+            for handle in welded.colliders {
+                let _ = self.remove(handle, bodies, false, RemoveSettleMode::Immediate);
+                let _ = welded_handles.insert(handle, new_handle);
+            }
+        }
+
+        welded_handles
+    }
+
+    /// Prevents the colliders `handle1` and `handle2` from ever colliding with each other.
+    ///
+    /// Contacts and intersections between excluded colliders are rejected by the narrow-phase
+    /// after the collision groups test and before the physics hooks are called. This is useful
+    /// to exclude specific pairs, e.g., the neighbor limbs of a ragdoll, without exhausting the
+    /// available collision groups. The exclusions of a collider are removed with the collider.
+    ///
+    /// Returns `false` if one of the colliders doesn't exist, if both handles are equal, or if
+    /// this exclusion already existed.
+    pub fn add_exclusion(&mut self, handle1: ColliderHandle, handle2: ColliderHandle) -> bool {
+        if handle1 == handle2 || !self.contains(handle1) || !self.contains(handle2) {
+            return false;
+        }
+
+        let co1 = self
+            .get_mut_internal_with_modification_tracking(handle1)
+            .unwrap();
+        if !co1.insert_exclusion(handle2) {
+            return false;
+        }
+
+        let co2 = self
+            .get_mut_internal_with_modification_tracking(handle2)
+            .unwrap();
+        co2.insert_exclusion(handle1);
+        true
+    }
+
+    /// Allows again the colliders `handle1` and `handle2` to collide with each other.
+    ///
+    /// Returns `false` if these colliders were not excluded from each other.
+    pub fn remove_exclusion(&mut self, handle1: ColliderHandle, handle2: ColliderHandle) -> bool {
+        let removed = self
+            .get_mut_internal_with_modification_tracking(handle1)
+            .map(|co1| co1.remove_exclusion(handle2))
+            .unwrap_or(false);
+
+        if removed {
+            if let Some(co2) = self.get_mut_internal_with_modification_tracking(handle2) {
+                co2.remove_exclusion(handle1);
+            }
+        }
+
+        removed
+    }
+
+    /// Are the colliders `handle1` and `handle2` excluded from colliding with each other?
+    pub fn is_excluded(&self, handle1: ColliderHandle, handle2: ColliderHandle) -> bool {
+        self.get(handle1)
+            .map(|co1| co1.is_excluded_from(handle2))
+            .unwrap_or(false)
+    }
+
+    /// Sets the collision groups of all the colliders with the given handles.
+    ///
+    /// The invalid handles are ignored. All the changes are taken into account by the same
+    /// timestep: the contacts and intersections between the colliders no longer allowed to
+    /// interact are removed, and their `Stopped` events emitted, during the next call to
+    /// `PhysicsPipeline::step`. The rigid-bodies of the modified colliders are woken up.
+    pub fn set_collision_groups_batch(
+        &mut self,
+        handles: &[ColliderHandle],
+        groups: InteractionGroups,
+        bodies: &mut RigidBodySet,
+    ) {
+        self.modify_batch(handles, bodies, |collider| {
+            collider.set_collision_groups(groups)
+        });
+    }
+
+    /// Sets the solver groups of all the colliders with the given handles.
+    ///
+    /// See `Self::set_collision_groups_batch` for details.
+    pub fn set_solver_groups_batch(
+        &mut self,
+        handles: &[ColliderHandle],
+        groups: InteractionGroups,
+        bodies: &mut RigidBodySet,
+    ) {
+        self.modify_batch(handles, bodies, |collider| {
+            collider.set_solver_groups(groups)
+        });
+    }
+
+    /// Sets whether or not all the colliders with the given handles are sensors.
+    ///
+    /// See `Self::set_collision_groups_batch` for details.
+    pub fn set_sensor_batch(
+        &mut self,
+        handles: &[ColliderHandle],
+        is_sensor: bool,
+        bodies: &mut RigidBodySet,
+    ) {
+        self.modify_batch(handles, bodies, |collider| collider.set_sensor(is_sensor));
+    }
+
+    fn modify_batch(
+        &mut self,
+        handles: &[ColliderHandle],
+        bodies: &mut RigidBodySet,
+        mut modify: impl FnMut(&mut Collider),
+    ) {
+        for handle in handles {
+            if let Some(collider) = self.get_mut_internal_with_modification_tracking(*handle) {
+                modify(collider);
+
+                if collider.changes.needs_pair_reevaluation() {
+                    bodies.wake_up(collider.parent, true);
+                }
+            }
+        }
+    }
+
     /// Gets the collider with the given handle without a known generation.
     ///
     /// This is useful for finding the generation number when only the collider position `i` is known.
@@ -281,6 +785,57 @@ impl ColliderSet {
         Some(result)
     }
 
+    /// Gets mutable references to the two colliders with the given handles.
+    ///
+    /// `None` is returned for the handles that don't match any collider.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `h1` and `h2` are equal.
+    #[cfg(not(feature = "dev-remove-slow-accessors"))]
+    pub fn get2_mut(
+        &mut self,
+        h1: ColliderHandle,
+        h2: ColliderHandle,
+    ) -> (Option<&mut Collider>, Option<&mut Collider>) {
+        let [co1, co2] = self.get_many_mut([h1, h2]);
+        (co1, co2)
+    }
+
+    /// Gets mutable references to the colliders with the given handles.
+    ///
+    /// `None` is returned for the handles that don't match any collider.
+    ///
+    /// # Panics
+    ///
+    /// Panics if two handles are equal.
+    #[cfg(not(feature = "dev-remove-slow-accessors"))]
+    pub fn get_many_mut<const N: usize>(
+        &mut self,
+        handles: [ColliderHandle; N],
+    ) -> [Option<&mut Collider>; N] {
+        let mut indices = [ColliderHandle::invalid().0; N];
+
+        for (index, handle) in indices.iter_mut().zip(handles.iter()) {
+            *index = handle.0;
+        }
+
+        let mut result = self.colliders.get_many_mut(indices);
+
+        for (collider, handle) in result.iter_mut().zip(handles.iter()) {
+            if let Some(collider) = collider {
+                Self::mark_as_modified(
+                    *handle,
+                    collider,
+                    &mut self.modified_colliders,
+                    self.modified_all_colliders,
+                );
+            }
+        }
+
+        result
+    }
+
     pub(crate) fn get_mut_internal(&mut self, handle: ColliderHandle) -> Option<&mut Collider> {
         self.colliders.get_mut(handle.0)
     }
@@ -301,6 +856,80 @@ impl ColliderSet {
         Some(result)
     }
 
+    /// Enables or disables the aggregate broad-phase proxy of the rigid-body `body`.
+    ///
+    /// The proxies of its `colliders` are re-inserted during the next broad-phase update.
+    pub(crate) fn set_aggregate_proxy(
+        &mut self,
+        body: RigidBodyHandle,
+        enabled: bool,
+        colliders: &[ColliderHandle],
+    ) {
+        if enabled {
+            self.aggregates
+                .entry(body)
+                .or_insert_with(ColliderAggregate::new)
+                .enabled = true;
+        } else if let Some(aggregate) = self.aggregates.get_mut(&body) {
+            aggregate.enabled = false;
+        } else {
+            return;
+        }
+
+        for handle in colliders {
+            if let Some(collider) = self.get_mut_internal_with_modification_tracking(*handle) {
+                collider.changes.insert(ColliderChanges::BROAD_PHASE_LAYER);
+            }
+        }
+    }
+
+    /// Sets the position of the aggregate of the rigid-body `body`, if it has one.
+    pub(crate) fn set_aggregate_position(
+        &mut self,
+        body: RigidBodyHandle,
+        position: Isometry<Real>,
+    ) {
+        if let Some(aggregate) = self.aggregates.get_mut(&body) {
+            aggregate.set_position(position);
+        }
+    }
+
+    /// Sets the predicted position of the aggregate of the rigid-body `body`, if it has one.
+    pub(crate) fn set_aggregate_predicted_position(
+        &mut self,
+        body: RigidBodyHandle,
+        predicted_position: Isometry<Real>,
+    ) {
+        if let Some(aggregate) = self.aggregates.get_mut(&body) {
+            aggregate.set_predicted_position(predicted_position);
+        }
+    }
+
+    /// The aggregate of the rigid-body `body`, if it uses one which BVH is up-to-date with the
+    /// rigid-body's `colliders`.
+    pub(crate) fn up_to_date_aggregate(
+        &self,
+        body: RigidBodyHandle,
+        colliders: &[ColliderHandle],
+    ) -> Option<&ColliderAggregate> {
+        let aggregate = self.aggregates.get(&body).filter(|a| a.is_built())?;
+        let outdated = ColliderChanges::SHAPE
+            | ColliderChanges::SHAPE_REGION
+            | ColliderChanges::POSITION_WRT_PARENT
+            | ColliderChanges::PARENT;
+        let up_to_date = aggregate.children.len() == colliders.len()
+            && colliders.iter().all(|handle| {
+                matches!(self.get(*handle), Some(co) if co.proxy_index == aggregate.proxy_index
+                    && !co.changes.intersects(outdated))
+            });
+
+        if up_to_date {
+            Some(aggregate)
+        } else {
+            None
+        }
+    }
+
     // Utility function to avoid some borrowing issue in the `maintain` method.
     fn maintain_one(bodies: &mut RigidBodySet, collider: &mut Collider) {
         if collider
@@ -314,6 +943,11 @@ impl ColliderSet {
                 collider.set_position(position);
             }
         }
+
+        #[cfg(feature = "dim3")]
+        if collider.changes.contains(ColliderChanges::SHAPE) {
+            collider.update_trimesh_internal_edges();
+        }
     }
 
     pub(crate) fn handle_user_changes(&mut self, bodies: &mut RigidBodySet) {
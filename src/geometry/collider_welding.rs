@@ -0,0 +1,199 @@
+use crate::data::HashMap;
+use crate::dynamics::{RigidBodyHandle, RigidBodySet};
+use crate::geometry::{Collider, ColliderHandle, ColliderSet, AABB};
+use crate::math::{Real, DIM};
+use alloc::{vec, vec::Vec};
+use core::cmp::Ordering;
+
+/// Options controlling which colliders are welded by `ColliderSet::weld_static_colliders`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct WeldOptions {
+    /// The distance under which two cuboid faces are considered touching (default: `1.0e-4`).
+    ///
+    /// The face coordinates are snapped to a grid with this spacing before being compared. A
+    /// cuboid whose axis-aligned bounding box exceeds it by more than this distance is considered
+    /// rotated and is never welded.
+    pub tolerance: Real,
+    /// The maximum half-extent, along each axis, of a cuboid resulting from a weld (default: `Real::MAX`).
+    ///
+    /// Limiting it keeps the welded colliders from spanning too many broad-phase regions.
+    pub max_half_extent: Real,
+}
+
+impl Default for WeldOptions {
+    fn default() -> Self {
+        Self {
+            tolerance: 1.0e-4,
+            max_half_extent: Real::MAX,
+        }
+    }
+}
+
+/// A cuboid, in the local-space of its parent, replacing several welded colliders.
+pub(crate) struct WeldedCuboid {
+    pub aabb: AABB,
+    pub colliders: Vec<ColliderHandle>,
+}
+
+/// Computes the cuboids replacing the weldable colliders of `colliders`.
+///
+/// Only the cuboids made of at least two colliders are returned.
+pub(crate) fn weld_cuboids(
+    colliders: &ColliderSet,
+    bodies: &RigidBodySet,
+    options: &WeldOptions,
+) -> Vec<WeldedCuboid> {
+    // The colliders are grouped by parent first, then by material, so the
+    // material comparisons only involve colliders of the same body.
+    let mut groups_per_parent: HashMap<RigidBodyHandle, Vec<usize>> = HashMap::default();
+    let mut groups: Vec<(ColliderHandle, Vec<WeldedCuboid>)> = Vec::new();
+
+    for (handle, collider) in colliders.iter() {
+        if let Some(aabb) = weldable_aabb(collider, bodies, options.tolerance) {
+            let parent_groups = groups_per_parent.entry(collider.parent).or_default();
+            let cuboid = WeldedCuboid {
+                aabb,
+                colliders: vec![handle],
+            };
+
+            let group = parent_groups
+                .iter()
+                .copied()
+                .find(|i| can_weld(&colliders[groups[*i].0], collider));
+
+            if let Some(group) = group {
+                groups[group].1.push(cuboid);
+            } else {
+                parent_groups.push(groups.len());
+                groups.push((handle, vec![cuboid]));
+            }
+        }
+    }
+
+    let mut result = Vec::new();
+
+    for (_, mut cuboids) in groups {
+        loop {
+            let mut welded_any = false;
+
+            for axis in 0..DIM {
+                welded_any |= weld_along_axis(&mut cuboids, axis, options);
+            }
+
+            if !welded_any {
+                break;
+            }
+        }
+
+        result.extend(cuboids.into_iter().filter(|c| c.colliders.len() > 1));
+    }
+
+    result
+}
+
+/// The local-space AABB of the collider, if it is a weldable axis-aligned cuboid.
+fn weldable_aabb(collider: &Collider, bodies: &RigidBodySet, tolerance: Real) -> Option<AABB> {
+    if collider.is_sensor()
+        || !collider.exclusions.is_empty()
+        || !bodies.get(collider.parent)?.is_static()
+    {
+        return None;
+    }
+
+    let cuboid = collider.shape().as_cuboid()?;
+    let aabb = collider
+        .shape()
+        .compute_aabb(collider.position_wrt_parent());
+    let mut cuboid_extents = cuboid.half_extents;
+    let mut aabb_extents = aabb.half_extents();
+    let cmp = |a: &Real, b: &Real| a.partial_cmp(b).unwrap_or(Ordering::Equal);
+    cuboid_extents.as_mut_slice().sort_by(cmp);
+    aabb_extents.as_mut_slice().sort_by(cmp);
+
+    if cuboid_extents
+        .iter()
+        .zip(aabb_extents.iter())
+        .all(|(a, b)| (a - b).abs() <= tolerance)
+    {
+        Some(aabb)
+    } else {
+        None
+    }
+}
+
+/// Can these two colliders be replaced by a single collider without changing their behavior?
+fn can_weld(co1: &Collider, co2: &Collider) -> bool {
+    co1.parent == co2.parent
+        && co1.friction == co2.friction
+        && co1.restitution == co2.restitution
+        && co1.rolling_resistance == co2.rolling_resistance
+        && co1.contact_target_penetration == co2.contact_target_penetration
+        && co1.min_reported_impulse == co2.min_reported_impulse
+        && co1.normal_smoothing == co2.normal_smoothing
+        && co1.flags == co2.flags
+        && co1.solver_flags == co2.solver_flags
+        && co1.collision_groups == co2.collision_groups
+        && co1.solver_groups == co2.solver_groups
+        && co1.broad_phase_layer == co2.broad_phase_layer
+        && co1.active_events == co2.active_events
+        && co1.user_data == co2.user_data
+        && co1.user_tags == co2.user_tags
+}
+
+fn snap(x: Real, tolerance: Real) -> i64 {
+    (x / tolerance).round() as i64
+}
+
+/// Sorts the cuboids by cross-section orthogonal to `axis`, then by position along `axis`,
+/// so that the cuboids which can be welded along `axis` are consecutive.
+fn sort_key(aabb: &AABB, axis: usize, tolerance: Real) -> [i64; 2 * DIM - 1] {
+    let mut key = [0; 2 * DIM - 1];
+    let mut k = 0;
+
+    for i in (0..DIM).filter(|i| *i != axis) {
+        key[k] = snap(aabb.mins[i], tolerance);
+        key[k + 1] = snap(aabb.maxs[i], tolerance);
+        k += 2;
+    }
+
+    key[k] = snap(aabb.mins[axis], tolerance);
+    key
+}
+
+/// Welds the consecutive cuboids with the same cross-section orthogonal to `axis` which
+/// touch or overlap along `axis`.
+///
+/// Returns `true` if at least two cuboids were welded.
+fn weld_along_axis(cuboids: &mut Vec<WeldedCuboid>, axis: usize, options: &WeldOptions) -> bool {
+    let tolerance = options.tolerance;
+    let mut sorted = core::mem::take(cuboids);
+    sorted.sort_by_cached_key(|c| sort_key(&c.aabb, axis, tolerance));
+
+    let mut welded_any = false;
+
+    for cuboid in sorted {
+        if let Some(last) = cuboids.last_mut() {
+            let key1 = sort_key(&last.aabb, axis, tolerance);
+            let key2 = sort_key(&cuboid.aabb, axis, tolerance);
+            let maxs = last.aabb.maxs[axis].max(cuboid.aabb.maxs[axis]);
+
+            if key1[..2 * DIM - 2] == key2[..2 * DIM - 2]
+                && key2[2 * DIM - 2] <= snap(last.aabb.maxs[axis], tolerance)
+                && (maxs - last.aabb.mins[axis]) / 2.0 <= options.max_half_extent
+            {
+                last.aabb = AABB::new(
+                    last.aabb.mins.inf(&cuboid.aabb.mins),
+                    last.aabb.maxs.sup(&cuboid.aabb.maxs),
+                );
+                last.colliders.extend(cuboid.colliders);
+                welded_any = true;
+                continue;
+            }
+        }
+
+        cuboids.push(cuboid);
+    }
+
+    welded_any
+}
@@ -1,6 +1,9 @@
 use crate::dynamics::{BodyPair, RigidBodyHandle};
-use crate::geometry::{ColliderPair, Contact, ContactManifold};
-use crate::math::{Point, Real, Vector};
+use crate::geometry::{
+    ColliderPair, ColliderUserTags, Contact, ContactEvent, ContactEventData, ContactManifold,
+};
+use crate::math::{Isometry, Point, Real, Vector};
+use alloc::vec::Vec;
 use parry::query::ContactManifoldsWorkspace;
 
 bitflags::bitflags! {
@@ -30,6 +33,11 @@ pub struct ContactData {
     ///
     /// The impulse applied to the second collider's rigid-body is given by `-impulse`.
     pub impulse: Real,
+    /// The impulse along the contact normal, as reported to the user.
+    ///
+    /// This is `impulse`, or zero if `impulse` is smaller than the `min_reported_impulse` of the
+    /// colliders in contact. It is not used by the constraints solver.
+    pub reported_impulse: Real,
     /// The friction impulse along the vector orthonormal to the contact normal, applied to the first
     /// collider's rigid-body.
     #[cfg(feature = "dim2")]
@@ -40,16 +48,116 @@ pub struct ContactData {
     pub tangent_impulse: na::Vector2<Real>,
     /// The target velocity correction at the contact point.
     pub rhs: Real,
+    /// The friction coefficient used by the constraints solver at this contact point.
+    ///
+    /// This is the coefficient resulting from the combination of the colliders friction
+    /// coefficients, after its modification by the physics hooks.
+    pub friction: Real,
+    /// The restitution coefficient used by the constraints solver at this contact point.
+    ///
+    /// This is the coefficient resulting from the combination of the colliders restitution
+    /// coefficients, after its modification by the physics hooks.
+    pub restitution: Real,
+    /// The friction anchor of this contact, if it is sticking.
+    ///
+    /// These are the contact points, expressed in the local-space of the first and second
+    /// subshapes respectively, at the time the contact started sticking. This is reset when the
+    /// friction impulse reaches the boundary of the friction cone, i.e., when the contact slides.
+    pub friction_anchor: Option<(Point<Real>, Point<Real>)>,
 }
 
 impl Default for ContactData {
     fn default() -> Self {
         Self {
             impulse: 0.0,
+            reported_impulse: 0.0,
             tangent_impulse: na::zero(),
             rhs: 0.0,
+            friction: 0.0,
+            restitution: 0.0,
+            friction_anchor: None,
+        }
+    }
+}
+
+impl ContactData {
+    /// Sets the normal impulse computed by the constraints solver.
+    ///
+    /// The reported impulse is zeroed if the impulse is smaller than `min_reported_impulse`.
+    pub(crate) fn set_impulse(&mut self, impulse: Real, min_reported_impulse: Real) {
+        self.impulse = impulse;
+        self.reported_impulse = if impulse >= min_reported_impulse {
+            impulse
+        } else {
+            0.0
+        };
+    }
+
+    /// Drops the friction anchor of this contact if its friction impulse reached the boundary
+    /// of the friction cone.
+    pub(crate) fn release_sliding_friction_anchor(&mut self) {
+        #[cfg(feature = "dim2")]
+        let tangent_impulse = self.tangent_impulse.abs();
+        #[cfg(feature = "dim3")]
+        let tangent_impulse = self.tangent_impulse.norm();
+
+        // NOTE: the small margin accounts for the rounding errors of the impulse clamping.
+        if tangent_impulse >= self.friction * self.impulse * 0.999 {
+            self.friction_anchor = None;
+        }
+    }
+}
+
+/// The conditions under which the contact manifolds of a contact pair are frozen.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct ManifoldFreezing {
+    /// The maximum translation of the relative pose of the colliders for them to be still.
+    pub linear_threshold: Real,
+    /// The maximum rotation angle of the relative pose of the colliders for them to be still.
+    pub angular_threshold: Real,
+    /// The number of consecutive steps the colliders must be still before their manifolds freeze.
+    pub min_steps: usize,
+}
+
+// NOTE: a relative tolerance is used so the same value works whatever the mass of the bodies.
+/// The relative change of the total impulse of a contact pair between two steps above which
+/// its contact manifolds are unfrozen.
+const FROZEN_IMPULSE_TOLERANCE: Real = 0.5;
+
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+/// The relative pose tracking of the colliders of a contact pair, for manifold freezing.
+pub(crate) struct RelativePoseTracker {
+    /// The relative pose of the colliders when they became still.
+    reference_pos12: Isometry<Real>,
+    /// The number of consecutive steps the relative pose stayed close to `reference_pos12`.
+    num_still_steps: usize,
+    /// The total normal impulse applied by the contacts of the pair during the last step.
+    total_impulse: Real,
+    /// Are the contact manifolds of the pair frozen?
+    frozen: bool,
+}
+
+impl RelativePoseTracker {
+    fn new(pos12: &Isometry<Real>, total_impulse: Real) -> Self {
+        Self {
+            reference_pos12: *pos12,
+            num_still_steps: 0,
+            total_impulse,
+            frozen: false,
         }
     }
+
+    fn is_still(&self, pos12: &Isometry<Real>, freezing: &ManifoldFreezing) -> bool {
+        let delta = self.reference_pos12.inv_mul(pos12);
+        delta.translation.vector.norm() <= freezing.linear_threshold
+            && delta.rotation.angle().abs() <= freezing.angular_threshold
+    }
+
+    fn is_impulse_anomaly(&self, total_impulse: Real) -> bool {
+        (total_impulse - self.total_impulse).abs()
+            > FROZEN_IMPULSE_TOLERANCE * total_impulse.max(self.total_impulse)
+    }
 }
 
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
@@ -64,7 +172,18 @@ pub struct ContactPair {
     pub manifolds: Vec<ContactManifold>,
     /// Is there any active contact in this contact pair?
     pub has_any_active_contact: bool,
+    /// The prediction distance override returned by the contact pair filter for this pair, if any.
+    ///
+    /// This is updated whenever the contact pair filter is run on this pair.
+    pub prediction_distance: Option<Real>,
     pub(crate) workspace: Option<ContactManifoldsWorkspace>,
+    pub(crate) pose_tracker: Option<RelativePoseTracker>,
+    // Whether the first contacts of this new pair must be initialized from the warmstart cache.
+    pub(crate) warmstart_cache_pending: bool,
+    pub(crate) age_steps: u32,
+    pub(crate) active_duration: Real,
+    pub(crate) user_tags1: ColliderUserTags,
+    pub(crate) user_tags2: ColliderUserTags,
 }
 
 impl ContactPair {
@@ -72,8 +191,142 @@ impl ContactPair {
         Self {
             pair,
             has_any_active_contact: false,
+            prediction_distance: None,
             manifolds: Vec::new(),
             workspace: None,
+            pose_tracker: None,
+            warmstart_cache_pending: false,
+            age_steps: 0,
+            active_duration: 0.0,
+            user_tags1: ColliderUserTags::default(),
+            user_tags2: ColliderUserTags::default(),
+        }
+    }
+
+    /// The user tags of the first collider of this pair.
+    ///
+    /// They are copied from `Collider::user_tags` whenever the narrow-phase processes this pair.
+    pub fn user_tags1(&self) -> ColliderUserTags {
+        self.user_tags1
+    }
+
+    /// The user tags of the second collider of this pair.
+    ///
+    /// They are copied from `Collider::user_tags` whenever the narrow-phase processes this pair.
+    pub fn user_tags2(&self) -> ColliderUserTags {
+        self.user_tags2
+    }
+
+    /// The data attached to the contact events of this pair.
+    pub(crate) fn event_data(&self) -> ContactEventData {
+        ContactEventData {
+            user_tags1: self.user_tags1,
+            user_tags2: self.user_tags2,
+        }
+    }
+
+    /// The number of timesteps since the colliders of this pair first had an active contact.
+    ///
+    /// This is zero if they never had any active contact. It is reset to zero once they separate
+    /// beyond the prediction distance, i.e., once this pair has no contact point left. It keeps
+    /// increasing while the bodies sleep, or while all the contacts are disabled, e.g., by the
+    /// physics hooks, as long as this pair has contact points.
+    pub fn age_steps(&self) -> u32 {
+        self.age_steps
+    }
+
+    /// The total time during which this pair had active contacts, since they were last reset.
+    ///
+    /// This is reset at the same time as `Self::age_steps`.
+    pub fn active_duration(&self) -> Real {
+        self.active_duration
+    }
+
+    /// Removes all the contacts of this pair, e.g., because its colliders are no longer allowed
+    /// to collide.
+    ///
+    /// Returns the `Stopped` event to emit if this pair had any active contact.
+    pub(crate) fn clear_contacts(&mut self) -> Option<(ContactEvent, ContactEventData)> {
+        self.manifolds.clear();
+        self.workspace = None;
+        self.pose_tracker = None;
+
+        if core::mem::replace(&mut self.has_any_active_contact, false) {
+            let event = ContactEvent::Stopped(self.pair.collider1, self.pair.collider2);
+            Some((event, self.event_data()))
+        } else {
+            None
+        }
+    }
+
+    // Updates the age of this pair at the beginning of a timestep of length `dt`.
+    pub(crate) fn update_age(&mut self, dt: Real) {
+        if self.has_any_active_contact {
+            self.age_steps += 1;
+            self.active_duration += dt;
+        } else if self.manifolds.iter().all(|m| m.points.is_empty()) {
+            self.age_steps = 0;
+            self.active_duration = 0.0;
+        } else if self.age_steps != 0 {
+            self.age_steps += 1;
+        }
+    }
+
+    /// Are the contact manifolds of this pair frozen?
+    ///
+    /// The contact manifolds of a pair are frozen when the relative pose of its colliders didn't
+    /// change for `IntegrationParameters::manifold_freezing_min_steps` steps. Frozen contact
+    /// manifolds are not recomputed by the narrow-phase: their contact points move rigidly with
+    /// the colliders. The distances of their contact points are only updated in the solver contacts.
+    pub fn is_frozen(&self) -> bool {
+        self.pose_tracker.map_or(false, |tracker| tracker.frozen)
+    }
+
+    /// Does any contact manifold of this pair link the bodies of its colliders?
+    pub(crate) fn links_bodies(&self) -> bool {
+        self.manifolds
+            .iter()
+            .any(|manifold| manifold.data.links_bodies())
+    }
+
+    /// The sum of the normal impulses applied by all the contacts of this pair during the last step.
+    pub(crate) fn total_impulse(&self) -> Real {
+        self.manifolds
+            .iter()
+            .flat_map(|manifold| manifold.points.iter())
+            .map(|contact| contact.data.impulse)
+            .sum()
+    }
+
+    /// Updates the tracking of the relative pose `pos12` of the colliders of this pair.
+    ///
+    /// Returns `true` if the contact manifolds of this pair are frozen, i.e., if they don't
+    /// need to be recomputed.
+    pub(crate) fn update_pose_tracker(
+        &mut self,
+        pos12: &Isometry<Real>,
+        freezing: &ManifoldFreezing,
+    ) -> bool {
+        let total_impulse = self.total_impulse();
+
+        match &mut self.pose_tracker {
+            Some(tracker)
+                if tracker.is_still(pos12, freezing)
+                    && !tracker.is_impulse_anomaly(total_impulse) =>
+            {
+                tracker.num_still_steps += 1;
+                tracker.total_impulse = total_impulse;
+            }
+            _ => self.pose_tracker = Some(RelativePoseTracker::new(pos12, total_impulse)),
+        }
+
+        let has_manifolds = !self.manifolds.is_empty();
+
+        if let Some(tracker) = &mut self.pose_tracker {
+            tracker.frozen = has_manifolds && tracker.num_still_steps >= freezing.min_steps;
+            tracker.frozen
+        } else {
+            false
         }
     }
 
@@ -118,6 +371,14 @@ pub struct ContactManifoldData {
     /// The pair of body involved in this contact manifold.
     pub body_pair: BodyPair,
     pub(crate) warmstart_multiplier: Real,
+    // Multiplier applied to the penetration correction of this manifold, reduced while the
+    // bodies settle after the removal of a neighbor collider.
+    pub(crate) erp_multiplier: Real,
+    // The normal impulses smaller than this are reported as zero (see `Collider::min_reported_impulse`).
+    pub(crate) min_reported_impulse: Real,
+    // Does any solver contact of this manifold have a non-zero rolling resistance? The solver
+    // skips the rolling resistance rows of the manifolds without any.
+    pub(crate) has_rolling_resistance: bool,
     // The two following are set by the constraints solver.
     #[cfg_attr(feature = "serde-serialize", serde(skip))]
     pub(crate) constraint_index: usize,
@@ -131,6 +392,11 @@ pub struct ContactManifoldData {
     // NOTE: read the comment of `solver_contacts` regarding serialization. It applies
     // to this field as well.
     pub normal: Vector<Real>,
+    /// The world-space contact normal computed by the narrow-phase.
+    ///
+    /// It differs from `self.normal` if the normal was smoothed (see `Collider::normal_smoothing`)
+    /// or modified by the contact modification hooks.
+    pub raw_normal: Vector<Real>,
     /// The contacts that will be seen by the constraints solver for computing forces.
     // NOTE: unfortunately, we can't ignore this field when serializing
     // the contact manifold data. The reason is that the solver contacts
@@ -153,6 +419,25 @@ pub struct ContactManifoldData {
     pub relative_dominance: i16,
     /// A user-defined piece of data.
     pub user_data: u32,
+    /// Did the contact modification hook remove all the solver contacts of this manifold at
+    /// its last update?
+    ///
+    /// Such a manifold neither merges the simulation islands of its bodies, nor propagates
+    /// wake-ups from one body to the other.
+    pub disabled_by_hooks: bool,
+    /// Did the contact modification hook keep some solver contacts of this manifold at its
+    /// last update, after having removed all of them at the previous update?
+    ///
+    /// Such a manifold wakes up its sleeping body right away, as if it applied an arbitrarily
+    /// strong impulse.
+    pub reenabled_by_hooks: bool,
+    /// The factors by which the effective masses of the first and second bodies were multiplied
+    /// by the velocity constraints of this manifold, at the last timestep it was solved.
+    ///
+    /// They are different from `1.0` only if the effective masses of the bodies at the contacts
+    /// differed by more than `IntegrationParameters::max_effective_mass_ratio`. This is meant for
+    /// debugging.
+    pub effective_mass_multipliers: [Real; 2],
 }
 
 /// A contact seen by the constraints solver for computing forces.
@@ -166,10 +451,22 @@ pub struct SolverContact {
     /// The distance between the two original contacts points along the contact normal.
     /// If negative, this is measures the penetration depth.
     pub dist: Real,
+    /// The distance the solver drives `dist` toward (default: the sum of the
+    /// `Collider::contact_target_penetration` of both colliders, negated).
+    ///
+    /// Set to a negative value to keep the colliders embedded, or to a positive value to keep
+    /// them at a distance. A positive value only has an effect on the contacts closer than the
+    /// prediction distance.
+    pub target_dist: Real,
     /// The effective friction coefficient at this contact point.
     pub friction: Real,
     /// The effective restitution coefficient at this contact point.
     pub restitution: Real,
+    /// The effective rolling resistance coefficient at this contact point.
+    ///
+    /// The maximum torque opposing the relative rolling of the two bodies is given by
+    /// this coefficient multiplied by the normal force at this contact point.
+    pub rolling_resistance: Real,
     /// The desired tangent relative velocity at the contact point.
     ///
     /// This is set to zero by default. Set to a non-zero value to
@@ -187,9 +484,22 @@ pub struct SolverContact {
     pub warmstart_tangent_impulse: na::Vector2<Real>,
     /// The last velocity correction targeted by this contact.
     pub prev_rhs: Real,
+    /// The world-space offset between the friction anchors of the first and second bodies.
+    ///
+    /// Only its components orthogonal to the contact normal are used, to pull the contact back
+    /// to its anchor if `IntegrationParameters::friction_anchors` is enabled. This is zero if the
+    /// contact has no friction anchor.
+    pub friction_anchor_offset: Vector<Real>,
 }
 
 impl SolverContact {
+    /// The distance between the contact points, relative to their target distance.
+    ///
+    /// This is the distance corrected by the constraints solver, i.e., `self.dist - self.target_dist`.
+    pub fn dist_from_target(&self) -> Real {
+        self.dist - self.target_dist
+    }
+
     /// Should we treat this contact as a bouncy contact?
     /// If `true`, use [`Self::restitution`].
     pub fn is_bouncy(&self) -> bool {
@@ -219,13 +529,20 @@ impl ContactManifoldData {
         Self {
             body_pair,
             warmstart_multiplier: Self::min_warmstart_multiplier(),
+            erp_multiplier: 1.0,
+            min_reported_impulse: 0.0,
+            has_rolling_resistance: false,
             constraint_index: 0,
             position_constraint_index: 0,
             solver_flags,
             normal: Vector::zeros(),
+            raw_normal: Vector::zeros(),
             solver_contacts: Vec::new(),
             relative_dominance: 0,
             user_data: 0,
+            disabled_by_hooks: false,
+            reenabled_by_hooks: false,
+            effective_mass_multipliers: [1.0; 2],
         }
     }
 
@@ -236,6 +553,13 @@ impl ContactManifoldData {
         self.solver_contacts.len()
     }
 
+    /// Does this manifold link its two bodies, i.e., does it merge their simulation islands
+    /// and propagate wake-ups from one to the other?
+    #[inline]
+    pub(crate) fn links_bodies(&self) -> bool {
+        !self.disabled_by_hooks && !self.solver_contacts.is_empty()
+    }
+
     pub(crate) fn min_warmstart_multiplier() -> Real {
         // Multiplier used to reduce the amount of warm-starting.
         // This coefficient increases exponentially over time, until it reaches 1.0.
@@ -226,7 +226,7 @@ impl<'a, N: Copy, E> Iterator for InteractionsWithMut<'a, N, E> {
             let endpoints = self.graph.edge_endpoints(edge).unwrap();
             let (co1, co2) = (self.graph[endpoints.0], self.graph[endpoints.1]);
             let interaction = &mut self.graph[edge];
-            return Some((co1, co2, edge, unsafe { std::mem::transmute(interaction) }));
+            return Some((co1, co2, edge, unsafe { core::mem::transmute(interaction) }));
         }
 
         let edge = self.outgoing_edge?;
@@ -234,6 +234,6 @@ impl<'a, N: Copy, E> Iterator for InteractionsWithMut<'a, N, E> {
         let endpoints = self.graph.edge_endpoints(edge).unwrap();
         let (co1, co2) = (self.graph[endpoints.0], self.graph[endpoints.1]);
         let interaction = &mut self.graph[edge];
-        Some((co1, co2, edge, unsafe { std::mem::transmute(interaction) }))
+        Some((co1, co2, edge, unsafe { core::mem::transmute(interaction) }))
     }
 }
@@ -0,0 +1,393 @@
+use crate::geometry::SharedShape;
+use crate::math::{Isometry, Point, Real, Rotation, Translation, Vector};
+use alloc::{vec, vec::Vec};
+use core::cmp::Ordering;
+use parry::utils::hashmap::HashMap;
+
+/// The kind of shape chosen by `ColliderBuilder::from_mesh_auto` for a triangle mesh.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MeshClassification {
+    /// The mesh is a box.
+    Cuboid,
+    /// The mesh is a sphere.
+    Ball,
+    /// The mesh is a capsule.
+    Capsule,
+    /// The mesh is a cylinder.
+    Cylinder,
+    /// The mesh is convex but doesn't match any of the other primitive shapes.
+    ConvexHull,
+    /// The mesh is closed but not convex, so it is decomposed into convex parts.
+    ConvexDecomposition,
+    /// The mesh is neither convex nor closed, so it is kept as a triangle mesh.
+    TriMesh,
+}
+
+/// A primitive shape fitted to the vertices of a triangle mesh.
+pub(crate) struct FittedPrimitive {
+    pub classification: MeshClassification,
+    pub shape: SharedShape,
+}
+
+/// The vertices referenced by at least one triangle of the mesh.
+pub(crate) fn used_vertices(vertices: &[Point<Real>], indices: &[[u32; 3]]) -> Vec<Point<Real>> {
+    let mut used = vec![false; vertices.len()];
+    for i in indices.iter().flat_map(|idx| idx.iter()) {
+        used[*i as usize] = true;
+    }
+
+    vertices
+        .iter()
+        .zip(used)
+        .filter(|(_, used)| *used)
+        .map(|(pt, _)| *pt)
+        .collect()
+}
+
+/// Checks if every edge of the mesh is shared by exactly two triangles.
+pub(crate) fn is_closed(indices: &[[u32; 3]]) -> bool {
+    let mut edges: HashMap<(u32, u32), u32> = HashMap::default();
+
+    for idx in indices {
+        for k in 0..3 {
+            let (a, b) = (idx[k], idx[(k + 1) % 3]);
+            *edges.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+        }
+    }
+
+    !edges.is_empty() && edges.values().all(|count| *count == 2)
+}
+
+/// Checks if all the vertices and triangles of the mesh lie within `tolerance` of the boundary
+/// of the convex hull of its vertices `points`.
+///
+/// Some vertices of a concave mesh are always inside of its convex hull. However, they may
+/// still be on its boundary (e.g. the reflex vertices of a prism are on its caps), so the
+/// centers of the triangles are checked as well.
+pub(crate) fn is_convex(
+    vertices: &[Point<Real>],
+    indices: &[[u32; 3]],
+    points: &[Point<Real>],
+    tolerance: Real,
+) -> bool {
+    if points.len() < 4 {
+        return false;
+    }
+
+    let (hull_vertices, hull_indices) = parry::transformation::convex_hull(points);
+    let planes: Vec<_> = hull_indices
+        .iter()
+        .filter_map(|idx| {
+            let a = hull_vertices[idx[0] as usize];
+            let b = hull_vertices[idx[1] as usize];
+            let c = hull_vertices[idx[2] as usize];
+            let normal = (b - a).cross(&(c - a)).try_normalize(Real::EPSILON)?;
+            Some((a, normal))
+        })
+        .collect();
+    let on_boundary = |pt: &Point<Real>| {
+        planes
+            .iter()
+            .any(|(origin, normal)| (pt - origin).dot(normal).abs() <= tolerance)
+    };
+
+    !planes.is_empty()
+        && points.iter().all(on_boundary)
+        && indices.iter().all(|idx| {
+            let center = idx
+                .iter()
+                .map(|i| vertices[*i as usize].coords)
+                .sum::<Vector<Real>>()
+                / 3.0;
+            on_boundary(&Point::from(center))
+        })
+}
+
+/// Fits a cuboid, ball, capsule, or cylinder to the vertices `points` of a convex mesh.
+///
+/// Returns `None` if none of these shapes match all the points within `tolerance`.
+pub(crate) fn fit_primitive(
+    vertices: &[Point<Real>],
+    indices: &[[u32; 3]],
+    points: &[Point<Real>],
+    tolerance: Real,
+) -> Option<FittedPrimitive> {
+    if points.len() < 4 {
+        return None;
+    }
+
+    // The axes of the mesh are tried first so that meshes aligned with them result in
+    // shapes without any rotation. The principal axes of the vertices are not well-defined
+    // for cubes, hence the frame given by the triangle normals.
+    let frames = [
+        Some(Rotation::identity()),
+        face_normals_frame(vertices, indices),
+        principal_axes_frame(points),
+    ];
+
+    let frames: Vec<_> = frames
+        .iter()
+        .flatten()
+        .map(|rotation| LocalPoints::new(points, *rotation))
+        .collect();
+
+    // Each shape is tried in all the frames before the next one. In particular, the vertices
+    // of a box are all on its circumscribed sphere, so it must not be fitted by a ball just
+    // because the first frame isn't aligned with it.
+    let fitters: [fn(&LocalPoints, Real) -> Option<FittedPrimitive>; 4] = [
+        LocalPoints::fit_cuboid,
+        LocalPoints::fit_ball,
+        LocalPoints::fit_capsule,
+        LocalPoints::fit_cylinder,
+    ];
+
+    fitters
+        .iter()
+        .find_map(|fit| frames.iter().find_map(|local| fit(local, tolerance)))
+}
+
+fn rotation_from_axes(axes: [Vector<Real>; 3]) -> Rotation<Real> {
+    let basis = na::Matrix3::from_columns(&axes);
+    Rotation::from_rotation_matrix(&na::Rotation3::from_matrix_unchecked(basis))
+}
+
+/// The frame aligned with the normals of the two largest non-parallel triangles.
+fn face_normals_frame(vertices: &[Point<Real>], indices: &[[u32; 3]]) -> Option<Rotation<Real>> {
+    // The norm of these vectors is twice the area of the triangle.
+    let area_normals: Vec<_> = indices
+        .iter()
+        .map(|idx| {
+            let a = vertices[idx[0] as usize];
+            let b = vertices[idx[1] as usize];
+            let c = vertices[idx[2] as usize];
+            (b - a).cross(&(c - a))
+        })
+        .collect();
+    let largest = |n1: &&Vector<Real>, n2: &&Vector<Real>| {
+        n1.norm_squared()
+            .partial_cmp(&n2.norm_squared())
+            .unwrap_or(Ordering::Equal)
+    };
+
+    let axis1 = area_normals
+        .iter()
+        .max_by(largest)?
+        .try_normalize(Real::EPSILON)?;
+    let axis2 = area_normals
+        .iter()
+        .filter(|n| n.dot(&axis1).abs() < n.norm() * 0.1)
+        .max_by(largest)?;
+    let axis2 = (axis2 - axis1 * axis2.dot(&axis1)).try_normalize(Real::EPSILON)?;
+
+    Some(rotation_from_axes([axis1, axis2, axis1.cross(&axis2)]))
+}
+
+/// The frame aligned with the principal axes of the points.
+fn principal_axes_frame(points: &[Point<Real>]) -> Option<Rotation<Real>> {
+    let center = points.iter().map(|pt| pt.coords).sum::<Vector<Real>>() / points.len() as Real;
+    let covariance = points
+        .iter()
+        .map(|pt| (pt.coords - center) * (pt.coords - center).transpose())
+        .sum::<na::Matrix3<Real>>();
+    let eigenvectors = na::SymmetricEigen::new(covariance).eigenvectors;
+    let axis1 = eigenvectors.column(0).try_normalize(Real::EPSILON)?;
+    let axis2 = eigenvectors.column(1).try_normalize(Real::EPSILON)?;
+
+    Some(rotation_from_axes([axis1, axis2, axis1.cross(&axis2)]))
+}
+
+/// The points of a mesh expressed in a frame centered at the center of their local AABB.
+struct LocalPoints {
+    frame: Isometry<Real>,
+    points: Vec<Point<Real>>,
+    half_extents: Vector<Real>,
+}
+
+impl LocalPoints {
+    fn new(points: &[Point<Real>], rotation: Rotation<Real>) -> Self {
+        let local: Vec<_> = points
+            .iter()
+            .map(|pt| rotation.inverse_transform_point(pt))
+            .collect();
+        let mins = local.iter().fold(local[0], |mins, pt| mins.inf(pt));
+        let maxs = local.iter().fold(local[0], |maxs, pt| maxs.sup(pt));
+        let center = na::center(&mins, &maxs);
+
+        LocalPoints {
+            frame: Isometry::from_parts(Translation::from(rotation * center.coords), rotation),
+            points: local.iter().map(|pt| pt - center.coords).collect(),
+            half_extents: (maxs - mins) / 2.0,
+        }
+    }
+
+    /// Places the given shape, rotated by `rotation` in this local frame, in the frame of the mesh.
+    ///
+    /// The shape is wrapped into a compound shape, unless this would move it by less
+    /// than `tolerance`.
+    fn place(&self, shape: SharedShape, rotation: Rotation<Real>, tolerance: Real) -> SharedShape {
+        let pos = self.frame * rotation;
+        let max_displacement =
+            pos.translation.vector.norm() + pos.rotation.angle() * self.half_extents.norm();
+
+        if max_displacement <= tolerance {
+            shape
+        } else {
+            SharedShape::compound(vec![(pos, shape)])
+        }
+    }
+
+    fn fit_cuboid(&self, tolerance: Real) -> Option<FittedPrimitive> {
+        let he = self.half_extents;
+
+        if he.min() <= tolerance {
+            return None;
+        }
+
+        // All the points must be on a face, and all the corners must be vertices.
+        let on_faces = self
+            .points
+            .iter()
+            .all(|pt| (0..3).any(|i| he[i] - pt[i].abs() <= tolerance));
+        let has_corners = (0..8).all(|k| {
+            let corner = Point::new(
+                if k & 1 == 0 { -he.x } else { he.x },
+                if k & 2 == 0 { -he.y } else { he.y },
+                if k & 4 == 0 { -he.z } else { he.z },
+            );
+            self.points
+                .iter()
+                .any(|pt| na::distance(pt, &corner) <= tolerance)
+        });
+
+        if !on_faces || !has_corners {
+            return None;
+        }
+
+        let shape = SharedShape::cuboid(he.x, he.y, he.z);
+        Some(FittedPrimitive {
+            classification: MeshClassification::Cuboid,
+            shape: self.place(shape, Rotation::identity(), tolerance),
+        })
+    }
+
+    fn fit_ball(&self, tolerance: Real) -> Option<FittedPrimitive> {
+        let radius =
+            self.points.iter().map(|pt| pt.coords.norm()).sum::<Real>() / self.points.len() as Real;
+
+        if radius <= tolerance
+            || self
+                .points
+                .iter()
+                .any(|pt| (pt.coords.norm() - radius).abs() > tolerance)
+        {
+            return None;
+        }
+
+        let shape = SharedShape::ball(radius);
+        Some(FittedPrimitive {
+            classification: MeshClassification::Ball,
+            shape: self.place(shape, Rotation::identity(), tolerance),
+        })
+    }
+
+    /// The radius of a shape of revolution around the local axis `i`, if the extents
+    /// orthogonal to this axis are equal.
+    fn revolution_radius(&self, i: usize, tolerance: Real) -> Option<Real> {
+        let he = self.half_extents;
+        let (j, k) = ((i + 1) % 3, (i + 2) % 3);
+
+        if (he[j] - he[k]).abs() > tolerance {
+            return None;
+        }
+
+        Some((he[j] + he[k]) / 2.0)
+    }
+
+    fn fit_capsule(&self, tolerance: Real) -> Option<FittedPrimitive> {
+        for i in 0..3 {
+            let radius = match self.revolution_radius(i, tolerance) {
+                Some(radius) => radius,
+                None => continue,
+            };
+            let half_height = self.half_extents[i] - radius;
+
+            if half_height <= tolerance || radius <= tolerance {
+                continue;
+            }
+
+            // All the points must be at a distance `radius` from the capsule segment.
+            let on_surface = self.points.iter().all(|pt| {
+                let mut dpt = pt.coords;
+                dpt[i] = (pt[i].abs() - half_height).max(0.0);
+                (dpt.norm() - radius).abs() <= tolerance
+            });
+
+            if on_surface {
+                let mut b = Point::origin();
+                b[i] = half_height;
+                let a = Point::from(-b.coords);
+
+                // NOTE: the capsule is placed through its segment so it never needs
+                //       to be wrapped into a compound shape.
+                let shape = SharedShape::capsule(self.frame * a, self.frame * b, radius);
+                return Some(FittedPrimitive {
+                    classification: MeshClassification::Capsule,
+                    shape,
+                });
+            }
+        }
+
+        None
+    }
+
+    fn fit_cylinder(&self, tolerance: Real) -> Option<FittedPrimitive> {
+        for i in 0..3 {
+            let radius = match self.revolution_radius(i, tolerance) {
+                Some(radius) => radius,
+                None => continue,
+            };
+            let half_height = self.half_extents[i];
+
+            if half_height <= tolerance || radius <= tolerance {
+                continue;
+            }
+
+            let radial_dist = |pt: &Point<Real>| {
+                let mut dpt = pt.coords;
+                dpt[i] = 0.0;
+                dpt.norm()
+            };
+            let on_rim = |pt: &Point<Real>, sign: Real| {
+                (pt[i] - sign * half_height).abs() <= tolerance
+                    && (radial_dist(pt) - radius).abs() <= tolerance
+            };
+
+            // All the points must be on the side or on the caps, and both rims must have
+            // vertices (otherwise this could be, e.g., a double cone).
+            let on_surface = self.points.iter().all(|pt| {
+                let radial_dist = radial_dist(pt);
+                radial_dist <= radius + tolerance
+                    && ((radial_dist - radius).abs() <= tolerance
+                        || (pt[i].abs() - half_height).abs() <= tolerance)
+            });
+            let has_rims = self.points.iter().any(|pt| on_rim(pt, 1.0))
+                && self.points.iter().any(|pt| on_rim(pt, -1.0));
+
+            if on_surface && has_rims {
+                // Rotate the local `y` axis of the cylinder to the axis `i`.
+                let axes = [
+                    Vector::ith((i + 2) % 3, 1.0),
+                    Vector::ith(i, 1.0),
+                    Vector::ith((i + 1) % 3, 1.0),
+                ];
+                let shape = SharedShape::cylinder(half_height, radius);
+                return Some(FittedPrimitive {
+                    classification: MeshClassification::Cylinder,
+                    shape: self.place(shape, rotation_from_axes(axes), tolerance),
+                });
+            }
+        }
+
+        None
+    }
+}
@@ -1,15 +1,21 @@
 //! Structures related to geometry: colliders, shapes, etc.
 
-pub use self::broad_phase_multi_sap::BroadPhase;
-pub use self::collider::{Collider, ColliderBuilder};
-pub use self::collider_set::{ColliderHandle, ColliderSet};
+pub use self::broad_phase_multi_sap::{BroadPhase, BroadPhaseStats, CellCoord, RegionStats};
+pub use self::collider::{Collider, ColliderBuilder, ColliderUserTags};
+pub use self::collider_set::{ColliderHandle, ColliderSet, ColliderSetStats, RemoveSettleMode};
+pub use self::collider_welding::WeldOptions;
 pub use self::contact_pair::{ContactData, ContactManifoldData};
 pub use self::contact_pair::{ContactPair, SolverContact, SolverFlags};
 pub use self::interaction_graph::{
     ColliderGraphIndex, InteractionGraph, RigidBodyGraphIndex, TemporaryInteractionIndex,
 };
 pub use self::interaction_groups::InteractionGroups;
-pub use self::narrow_phase::NarrowPhase;
+#[cfg(feature = "dim3")]
+pub use self::mesh_classification::MeshClassification;
+pub use self::narrow_phase::{NarrowPhase, NarrowPhaseStats};
+#[cfg(feature = "dim3")]
+pub use self::trimesh_internal_edges::TriMeshFlags;
+pub use self::typed_shape::TypedShape;
 
 pub use parry::query::TrackedContact;
 
@@ -47,7 +53,7 @@ pub type PointProjection = parry::query::PointProjection;
 pub type TOI = parry::query::TOI;
 pub use parry::shape::SharedShape;
 
-#[derive(Copy, Clone, Hash, Debug)]
+#[derive(Copy, Clone, Hash, Debug, PartialEq, Eq)]
 /// Events occurring when two collision objects start or stop being in contact (or penetration).
 pub enum ContactEvent {
     /// Event occurring when two collision objects start being in contact.
@@ -60,7 +66,16 @@ pub enum ContactEvent {
     Stopped(ColliderHandle, ColliderHandle),
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+/// The data attached to a `ContactEvent` by `EventHandler::handle_contact_event_with_data`.
+pub struct ContactEventData {
+    /// The user tags of the first collider of the event.
+    pub user_tags1: ColliderUserTags,
+    /// The user tags of the second collider of the event.
+    pub user_tags2: ColliderUserTags,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 /// Events occurring when the state of intersection between two colliders changes.
 pub struct IntersectionEvent {
     /// The first collider to which the intersection event applies.
@@ -81,27 +96,69 @@ impl IntersectionEvent {
     }
 }
 
-pub(crate) use self::broad_phase_multi_sap::{BroadPhasePairEvent, ColliderPair, SAPProxyIndex};
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+/// Events occurring when the broad-phase AABBs of two colliders start or stop overlapping.
+///
+/// These events are only generated for pairs involving at least one collider with the
+/// `ActiveEvents::PROXIMITY_AABB_EVENTS` flag set.
+pub struct AabbOverlapEvent {
+    /// The first collider to which the event applies.
+    pub collider1: ColliderHandle,
+    /// The second collider to which the event applies.
+    pub collider2: ColliderHandle,
+    /// Are the AABBs of the two colliders overlapping?
+    pub overlapping: bool,
+}
+
+impl AabbOverlapEvent {
+    pub fn new(collider1: ColliderHandle, collider2: ColliderHandle, overlapping: bool) -> Self {
+        Self {
+            collider1,
+            collider2,
+            overlapping,
+        }
+    }
+}
+
+pub(crate) use self::broad_phase_multi_sap::{
+    clamp_aabb, BroadPhasePairEvent, ColliderPair, SAPProxyIndex,
+};
+pub(crate) use self::collider_aggregate::ColliderAggregate;
 pub(crate) use self::collider_set::RemovedCollider;
+pub(crate) use self::contact_pair::ManifoldFreezing;
 pub(crate) use self::narrow_phase::ContactManifoldIndex;
+#[cfg(feature = "dim3")]
+pub(crate) use self::trimesh_internal_edges::{
+    correct_internal_edge_normal, TriMeshInternalEdges,
+};
+pub(crate) use self::warmstart_cache::WarmstartCache;
 pub(crate) use parry::partitioning::SimdQuadTree;
 pub use parry::shape::*;
 
 #[cfg(feature = "serde-serialize")]
 pub(crate) fn default_persistent_query_dispatcher(
-) -> std::sync::Arc<dyn parry::query::PersistentQueryDispatcher<ContactManifoldData, ContactData>> {
-    std::sync::Arc::new(parry::query::DefaultQueryDispatcher)
+) -> alloc::sync::Arc<dyn parry::query::PersistentQueryDispatcher<ContactManifoldData, ContactData>>
+{
+    alloc::sync::Arc::new(parry::query::DefaultQueryDispatcher)
 }
 
 #[cfg(feature = "serde-serialize")]
-pub(crate) fn default_query_dispatcher() -> std::sync::Arc<dyn parry::query::QueryDispatcher> {
-    std::sync::Arc::new(parry::query::DefaultQueryDispatcher)
+pub(crate) fn default_query_dispatcher() -> alloc::sync::Arc<dyn parry::query::QueryDispatcher> {
+    alloc::sync::Arc::new(parry::query::DefaultQueryDispatcher)
 }
 
 mod broad_phase_multi_sap;
 mod collider;
+mod collider_aggregate;
 mod collider_set;
+mod collider_welding;
 mod contact_pair;
 mod interaction_graph;
 mod interaction_groups;
+#[cfg(feature = "dim3")]
+mod mesh_classification;
 mod narrow_phase;
+#[cfg(feature = "dim3")]
+mod trimesh_internal_edges;
+mod typed_shape;
+mod warmstart_cache;
@@ -1,23 +1,29 @@
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
 
+use crate::data::graph::Edge;
 use crate::data::pubsub::Subscription;
 use crate::data::Coarena;
-use crate::dynamics::{BodyPair, CoefficientCombineRule, RigidBodySet};
+use crate::data::{HashMap, HashSet};
+use crate::dynamics::{BodyPair, CoefficientCombineRule, RigidBodyHandle, RigidBodySet};
 use crate::geometry::collider::ColliderChanges;
+#[cfg(feature = "dim3")]
+use crate::geometry::correct_internal_edge_normal;
 use crate::geometry::{
-    BroadPhasePairEvent, ColliderGraphIndex, ColliderHandle, ColliderPair, ColliderSet,
-    ContactData, ContactEvent, ContactManifold, ContactManifoldData, ContactPair, InteractionGraph,
-    IntersectionEvent, RemovedCollider, SolverContact, SolverFlags,
+    AabbOverlapEvent, BroadPhasePairEvent, Collider, ColliderGraphIndex, ColliderHandle,
+    ColliderPair, ColliderSet, Contact, ContactData, ContactEvent, ContactManifold,
+    ContactManifoldData, ContactPair, InteractionGraph, IntersectionEvent, ManifoldFreezing,
+    RemoveSettleMode, RemovedCollider, SolverContact, SolverFlags, WarmstartCache,
 };
 use crate::math::{Real, Vector};
 use crate::pipeline::{
-    ContactModificationContext, EventHandler, PairFilterContext, PhysicsHooks, PhysicsHooksFlags,
+    limit_normal_rotation, ActiveEvents, ContactModificationContext, EventHandler,
+    PairFilterContext, PhysicsHooks, PhysicsHooksFlags,
 };
+use alloc::sync::Arc;
+use alloc::{vec, vec::Vec};
 use parry::query::{DefaultQueryDispatcher, PersistentQueryDispatcher};
 use parry::utils::IsometryOpt;
-use std::collections::HashMap;
-use std::sync::Arc;
 
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -35,6 +41,33 @@ impl ColliderGraphIndices {
     }
 }
 
+// A collider which was touching a collider removed with `RemoveSettleMode::DampedOverNFrames`.
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct SettlingCollider {
+    handle: ColliderHandle,
+    num_frames: u8,
+    remaining_frames: u8,
+}
+
+impl SettlingCollider {
+    // The multiplier of the penetration correction and warmstarting of the contacts of this
+    // collider. It increases linearly from `1 / (num_frames + 1)` to `1`.
+    fn multiplier(&self) -> Real {
+        (self.num_frames - self.remaining_frames + 1) as Real / (self.num_frames as Real + 1.0)
+    }
+}
+
+// The multiplier of the penetration correction and warmstarting of the contacts of the given collider.
+fn settle_multiplier(settling_colliders: &[SettlingCollider], handle: ColliderHandle) -> Real {
+    // NOTE: this set is expected to be very small, a linear search is good enough.
+    settling_colliders
+        .iter()
+        .find(|settling| settling.handle == handle)
+        .map(|settling| settling.multiplier())
+        .unwrap_or(1.0)
+}
+
 #[derive(Copy, Clone, PartialEq, Eq)]
 enum PairRemovalMode {
     FromContactGraph,
@@ -42,6 +75,50 @@ enum PairRemovalMode {
     Auto,
 }
 
+/// Statistics about the pairs and contacts tracked by the narrow-phase.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct NarrowPhaseStats {
+    /// The number of pairs of colliders in the contact graph.
+    pub num_contact_pairs: usize,
+    /// The number of pairs of colliders in the intersection graph.
+    pub num_intersection_pairs: usize,
+    /// The number of contact manifolds, as of the last contact computation.
+    pub num_manifolds: usize,
+    /// The number of contact points of all the manifolds, as of the last contact computation.
+    pub num_contact_points: usize,
+    /// The approximate number of bytes allocated by the narrow-phase.
+    ///
+    /// This is only computed if `detailed` was set to `true` when requesting the statistics, and
+    /// zero otherwise.
+    pub bytes: usize,
+    /// The largest number of pairs of colliders in the contact graph since the last request.
+    pub peak_contact_pairs: usize,
+    /// The largest number of pairs of colliders in the intersection graph since the last request.
+    pub peak_intersection_pairs: usize,
+    /// The largest number of contact manifolds since the last request.
+    pub peak_manifolds: usize,
+    /// The largest number of contact points since the last request.
+    pub peak_contact_points: usize,
+}
+
+impl NarrowPhaseStats {
+    fn record_peaks(&mut self) {
+        self.peak_contact_pairs = self.peak_contact_pairs.max(self.num_contact_pairs);
+        self.peak_intersection_pairs = self
+            .peak_intersection_pairs
+            .max(self.num_intersection_pairs);
+        self.peak_manifolds = self.peak_manifolds.max(self.num_manifolds);
+        self.peak_contact_points = self.peak_contact_points.max(self.num_contact_points);
+    }
+
+    fn reset_peaks(&mut self) {
+        self.peak_contact_pairs = self.num_contact_pairs;
+        self.peak_intersection_pairs = self.num_intersection_pairs;
+        self.peak_manifolds = self.num_manifolds;
+        self.peak_contact_points = self.num_contact_points;
+    }
+}
+
 /// The narrow-phase responsible for computing precise contact information between colliders.
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
 #[derive(Clone)]
@@ -55,6 +132,15 @@ pub struct NarrowPhase {
     intersection_graph: InteractionGraph<ColliderHandle, bool>,
     graph_indices: Coarena<ColliderGraphIndices>,
     removed_colliders: Option<Subscription<RemovedCollider>>,
+    // The broad-phase pairs involving at least one collider with
+    // `ActiveEvents::PROXIMITY_AABB_EVENTS`, ordered with `aabb_overlap_key`.
+    aabb_overlaps: HashSet<ColliderPair>,
+    // The colliders whose contacts are settling after the removal of a collider they touched.
+    settling_colliders: Vec<SettlingCollider>,
+    #[cfg_attr(feature = "serde-serialize", serde(skip))]
+    warmstart_cache: WarmstartCache,
+    #[cfg_attr(feature = "serde-serialize", serde(skip))]
+    stats: NarrowPhaseStats,
 }
 
 pub(crate) type ContactManifoldIndex = usize;
@@ -76,7 +162,53 @@ impl NarrowPhase {
             intersection_graph: InteractionGraph::new(),
             graph_indices: Coarena::new(),
             removed_colliders: None,
+            aabb_overlaps: HashSet::default(),
+            settling_colliders: Vec::new(),
+            warmstart_cache: WarmstartCache::default(),
+            stats: NarrowPhaseStats::default(),
+        }
+    }
+
+    /// The maximum number of collider pairs stored in the warmstart cache.
+    pub fn warmstart_cache_capacity(&self) -> usize {
+        self.warmstart_cache.capacity()
+    }
+
+    /// Sets the maximum number of collider pairs stored in the warmstart cache.
+    ///
+    /// When a contact pair between two colliders with a `Collider::warmstart_cache_key` is
+    /// destroyed, e.g., because one of the colliders was removed, the impulses of its contacts
+    /// are stored in the warmstart cache. When a new contact pair between two colliders with the
+    /// same keys is created, its first contacts are initialized with the cached impulses of the
+    /// contacts with the same features. This avoids the softer first frames of contact of
+    /// pooled objects which are frequently removed and re-inserted at the same place.
+    ///
+    /// When the cache is full, the least recently stored pair is evicted. The warmstart cache
+    /// is disabled if `capacity` is zero, which is the default.
+    pub fn set_warmstart_cache_capacity(&mut self, capacity: usize) {
+        self.warmstart_cache.set_capacity(capacity);
+    }
+
+    /// Releases the excess capacity of the pairs and contacts tracked by this narrow-phase,
+    /// e.g., after the removal of many colliders.
+    pub fn shrink_to_fit(&mut self) {
+        for edge in &mut self.contact_graph.graph.edges {
+            let manifolds = &mut edge.weight.manifolds;
+            manifolds.shrink_to_fit();
+
+            for manifold in manifolds {
+                // NOTE: in 2D, the contact points are stored inline in an `ArrayVec`.
+                #[cfg(feature = "dim3")]
+                manifold.points.shrink_to_fit();
+                manifold.data.solver_contacts.shrink_to_fit();
+            }
         }
+
+        self.contact_graph.graph.shrink_to_fit();
+        self.intersection_graph.graph.shrink_to_fit();
+        self.graph_indices.shrink_to_fit();
+        self.aabb_overlaps.shrink_to_fit();
+        self.settling_colliders.shrink_to_fit();
     }
 
     /// The query dispatcher used by this narrow-phase to select the right collision-detection
@@ -87,6 +219,86 @@ impl NarrowPhase {
         &*self.query_dispatcher
     }
 
+    /// Statistics about the pairs and contacts tracked by this narrow-phase.
+    ///
+    /// The number of pairs is always up-to-date, while the number of manifolds and contact
+    /// points are the ones of the last timestep. If `detailed` is `true`, the allocated
+    /// memory is computed too, which is linear in the number of pairs.
+    ///
+    /// The peak values are reset by each call to this method.
+    pub fn stats(&mut self, detailed: bool) -> NarrowPhaseStats {
+        self.stats.num_contact_pairs = self.contact_graph.graph.edges.len();
+        self.stats.num_intersection_pairs = self.intersection_graph.graph.edges.len();
+        self.stats.record_peaks();
+
+        let mut result = self.stats;
+        if detailed {
+            result.bytes = self.allocated_bytes();
+        }
+
+        self.stats.reset_peaks();
+        result
+    }
+
+    fn allocated_bytes(&self) -> usize {
+        let manifold_bytes: usize = self
+            .contact_graph
+            .graph
+            .edges
+            .iter()
+            .map(|edge| {
+                let manifolds = &edge.weight.manifolds;
+                manifolds.capacity() * core::mem::size_of::<ContactManifold>()
+                    + manifolds
+                        .iter()
+                        .map(|m| {
+                            m.points.capacity() * core::mem::size_of::<Contact>()
+                                + m.data.solver_contacts.capacity()
+                                    * core::mem::size_of::<SolverContact>()
+                        })
+                        .sum::<usize>()
+            })
+            .sum();
+
+        core::mem::size_of::<Self>()
+            + self.contact_graph.graph.allocated_bytes()
+            + self.intersection_graph.graph.allocated_bytes()
+            + self.graph_indices.allocated_bytes()
+            + self.aabb_overlaps.capacity() * core::mem::size_of::<ColliderPair>()
+            + self.settling_colliders.capacity() * core::mem::size_of::<SettlingCollider>()
+            + manifold_bytes
+    }
+
+    /// Updates the number of manifolds and contact points reported by `Self::stats`.
+    pub(crate) fn update_stats(&mut self) -> NarrowPhaseStats {
+        let mut num_manifolds = 0;
+        let mut num_contact_points = 0;
+
+        for edge in &self.contact_graph.graph.edges {
+            num_manifolds += edge.weight.manifolds.len();
+            num_contact_points += edge
+                .weight
+                .manifolds
+                .iter()
+                .map(|m| m.points.len())
+                .sum::<usize>();
+        }
+
+        self.stats.num_contact_pairs = self.contact_graph.graph.edges.len();
+        self.stats.num_intersection_pairs = self.intersection_graph.graph.edges.len();
+        self.stats.num_manifolds = num_manifolds;
+        self.stats.num_contact_points = num_contact_points;
+        self.stats.record_peaks();
+        self.stats
+    }
+
+    /// Updates the age of all the contact pairs at the beginning of a timestep of length `dt`.
+    pub(crate) fn update_contact_ages(&mut self, dt: Real) {
+        for edge in &mut self.contact_graph.graph.edges {
+            edge.weight.update_age(dt);
+        }
+    }
+
     /// The contact graph containing all contact pairs and their contact information.
     pub fn contact_graph(&self) -> &InteractionGraph<ColliderHandle, ContactPair> {
         &self.contact_graph
@@ -152,6 +364,52 @@ impl NarrowPhase {
             .map(|c| *c.2)
     }
 
+    /// The rigid-bodies with at least one collider currently inside the given sensor collider.
+    ///
+    /// This is read from the intersections computed by the last timestep, so it includes the
+    /// bodies created already overlapping the sensor once a timestep ran, even though no
+    /// intersection event tracked their entrance. The colliders removed from, or detached in,
+    /// `colliders` since the last timestep, e.g., with their parent rigid-body, are ignored right
+    /// away. Each body is yielded once, even if several of its colliders are inside the sensor.
+    /// If `sensor` isn't a sensor, these are the bodies of the sensors intersecting it.
+    pub fn bodies_inside_sensor(
+        &self,
+        sensor: ColliderHandle,
+        colliders: &ColliderSet,
+    ) -> impl Iterator<Item = RigidBodyHandle> {
+        let mut bodies: Vec<_> = self
+            .colliders_inside_sensor(sensor, colliders)
+            .map(|collider| collider.parent)
+            .collect();
+        bodies.sort_unstable_by_key(|handle| handle.into_raw_parts());
+        bodies.dedup();
+        bodies.into_iter()
+    }
+
+    /// The number of colliders currently inside the given sensor collider.
+    ///
+    /// See `Self::bodies_inside_sensor` for details.
+    pub fn num_colliders_inside(&self, sensor: ColliderHandle, colliders: &ColliderSet) -> usize {
+        self.colliders_inside_sensor(sensor, colliders).count()
+    }
+
+    fn colliders_inside_sensor<'a>(
+        &'a self,
+        sensor: ColliderHandle,
+        colliders: &'a ColliderSet,
+    ) -> impl Iterator<Item = &'a Collider> + 'a {
+        self.intersections_with(sensor)
+            .into_iter()
+            .flatten()
+            .filter(|(_, _, intersecting)| *intersecting)
+            .filter_map(move |(handle1, handle2, _)| {
+                let other = if handle1 == sensor { handle2 } else { handle1 };
+                colliders
+                    .get(other)
+                    .filter(|collider| !collider.is_detached())
+            })
+    }
+
     /// All the contact pairs maintained by this narrow-phase.
     pub fn contact_pairs(&self) -> impl Iterator<Item = &ContactPair> {
         self.contact_graph.interactions()
@@ -166,6 +424,50 @@ impl NarrowPhase {
             .map(|e| (e.0, e.1, *e.2))
     }
 
+    /// All the contact pairs involving at least one rigid-body of the given active island.
+    ///
+    /// Each contact pair is yielded only once, even if both its bodies belong to the island.
+    /// Contact pairs without any contact manifold are skipped. The island indices are the
+    /// ones computed during the last call to `PhysicsPipeline::step`, so this should be
+    /// called right after the step, before any body is added, removed, or woken up.
+    pub fn island_contacts<'a>(
+        &'a self,
+        island_id: usize,
+        bodies: &'a RigidBodySet,
+    ) -> impl Iterator<Item = &'a ContactPair> + 'a {
+        bodies
+            .active_island(island_id)
+            .iter()
+            .filter_map(move |handle| Some((*handle, bodies.get(*handle)?)))
+            .flat_map(move |(handle, rb)| {
+                rb.colliders
+                    .iter()
+                    .filter_map(move |co_handle| self.contacts_with(*co_handle))
+                    .flatten()
+                    .filter_map(move |(_, _, pair)| {
+                        let body_pair = pair.manifolds.first()?.data.body_pair;
+                        let other = if body_pair.body1 == handle {
+                            body_pair.body2
+                        } else {
+                            body_pair.body1
+                        };
+                        let other_in_island = bodies.get(other).map_or(false, |rb2| {
+                            rb2.is_dynamic()
+                                && !rb2.is_sleeping()
+                                && rb2.active_island_id == island_id
+                        });
+
+                        // If both bodies are part of this island, only yield
+                        // the pair when visiting its first body.
+                        if !other_in_island || body_pair.body1 == handle {
+                            Some(pair)
+                        } else {
+                            None
+                        }
+                    })
+            })
+    }
+
     // #[cfg(feature = "parallel")]
     // pub(crate) fn contact_pairs_vec_mut(&mut self) -> &mut Vec<ContactPair> {
     //     &mut self.contact_graph.interactions
@@ -185,14 +487,18 @@ impl NarrowPhase {
 
         let cursor = self.removed_colliders.take().unwrap();
 
+        self.update_settling_colliders();
+
         // TODO: avoid these hash-maps.
         // They are necessary to handle the swap-remove done internally
         // by the contact/intersection graphs when a node is removed.
-        let mut prox_id_remap = HashMap::new();
-        let mut contact_id_remap = HashMap::new();
+        let mut prox_id_remap = HashMap::default();
+        let mut contact_id_remap = HashMap::default();
         let mut i = 0;
 
         while let Some(collider) = colliders.removed_colliders.read_ith(&cursor, i) {
+            self.remove_aabb_overlaps_with(collider.handle, events);
+
             // NOTE: if the collider does not have any graph indices currently, there is nothing
             // to remove in the narrow-phase for this collider.
             if let Some(graph_idx) = self.graph_indices.get(collider.handle.0) {
@@ -205,6 +511,21 @@ impl NarrowPhase {
                     .copied()
                     .unwrap_or(graph_idx.contact_graph_index);
 
+                if let RemoveSettleMode::DampedOverNFrames(num_frames) = collider.settle_mode {
+                    self.start_settling_neighbors(collider.handle, contact_graph_id, num_frames);
+                }
+
+                if let Some(key) = collider.warmstart_cache_key {
+                    self.cache_contacts_with(collider.handle, key, contact_graph_id, colliders);
+                }
+
+                // NOTE: `collider` can't be read once `colliders` is borrowed mutably below.
+                let (handle, detached) = (collider.handle, collider.detached);
+
+                if detached {
+                    self.emit_stopped_events_with(intersection_graph_id, contact_graph_id, events);
+                }
+
                 self.remove_collider(
                     intersection_graph_id,
                     contact_graph_id,
@@ -213,6 +534,13 @@ impl NarrowPhase {
                     &mut prox_id_remap,
                     &mut contact_id_remap,
                 );
+
+                if detached {
+                    // NOTE: the handle of a detached collider remains valid, so its graph
+                    //       indices must be invalidated explicitly for the collider to be
+                    //       added back to the graphs once it is re-attached.
+                    let _ = self.graph_indices.remove(handle.0);
+                }
             }
 
             i += 1;
@@ -221,9 +549,163 @@ impl NarrowPhase {
         colliders.removed_colliders.ack(&cursor);
         self.removed_colliders = Some(cursor);
 
+        // The contacts used by the next solver run were computed at the end of the previous
+        // timestep, so the multipliers of the colliders which just started settling must be
+        // applied right away.
+        let started: Vec<_> = self
+            .settling_colliders
+            .iter()
+            .filter(|settling| settling.remaining_frames == settling.num_frames)
+            .map(|settling| settling.handle)
+            .collect();
+        for handle in started {
+            self.apply_settle_multipliers(handle);
+        }
+
         self.handle_modified_colliders(colliders, bodies, events);
     }
 
+    // Emits the intersection lost events and the contact stopped events of the pairs
+    // involving the collider detached from the graphs with the given indices.
+    fn emit_stopped_events_with(
+        &self,
+        intersection_graph_id: ColliderGraphIndex,
+        contact_graph_id: ColliderGraphIndex,
+        events: &dyn EventHandler,
+    ) {
+        for (h1, h2, intersecting) in self
+            .intersection_graph
+            .interactions_with(intersection_graph_id)
+        {
+            if *intersecting {
+                events.handle_intersection_event(IntersectionEvent::new(h1, h2, false));
+            }
+        }
+
+        for (_, _, pair) in self.contact_graph.interactions_with(contact_graph_id) {
+            if pair.has_any_active_contact {
+                let event = ContactEvent::Stopped(pair.pair.collider1, pair.pair.collider2);
+                events.handle_contact_event_with_data(event, pair.event_data());
+            }
+        }
+    }
+
+    // Stores the contacts of the pairs involving the removed collider `handle` into the
+    // warmstart cache.
+    fn cache_contacts_with(
+        &mut self,
+        handle: ColliderHandle,
+        key: u64,
+        contact_graph_id: ColliderGraphIndex,
+        colliders: &ColliderSet,
+    ) {
+        if !self.warmstart_cache.is_enabled() {
+            return;
+        }
+
+        for (_, _, pair) in self.contact_graph.interactions_with(contact_graph_id) {
+            let is_first = pair.pair.collider1 == handle;
+            let other = if is_first {
+                pair.pair.collider2
+            } else {
+                pair.pair.collider1
+            };
+
+            if let Some(other_key) = colliders.get(other).and_then(|co| co.warmstart_cache_key) {
+                let (key1, key2) = if is_first {
+                    (key, other_key)
+                } else {
+                    (other_key, key)
+                };
+                self.warmstart_cache.store(key1, key2, &pair.manifolds);
+            }
+        }
+    }
+
+    // Marks the colliders with active contacts with the removed collider as settling during
+    // the next `num_frames` timesteps.
+    fn start_settling_neighbors(
+        &mut self,
+        removed: ColliderHandle,
+        contact_graph_id: ColliderGraphIndex,
+        num_frames: u8,
+    ) {
+        if num_frames == 0 {
+            return;
+        }
+
+        for (h1, h2, pair) in self.contact_graph.interactions_with(contact_graph_id) {
+            if !pair.has_any_active_contact {
+                continue;
+            }
+
+            let neighbor = if h1 == removed { h2 } else { h1 };
+            let settling = SettlingCollider {
+                handle: neighbor,
+                num_frames,
+                remaining_frames: num_frames,
+            };
+
+            if let Some(existing) = self
+                .settling_colliders
+                .iter_mut()
+                .find(|existing| existing.handle == neighbor)
+            {
+                *existing = settling;
+            } else {
+                self.settling_colliders.push(settling);
+            }
+        }
+    }
+
+    // Advances the settling colliders by one timestep, and stops the settling of the colliders
+    // which settled during all their timesteps.
+    fn update_settling_colliders(&mut self) {
+        if self.settling_colliders.is_empty() {
+            return;
+        }
+
+        let mut settled = Vec::new();
+
+        for settling in &mut self.settling_colliders {
+            settling.remaining_frames -= 1;
+
+            if settling.remaining_frames == 0 {
+                settled.push(settling.handle);
+            }
+        }
+
+        self.settling_colliders
+            .retain(|settling| settling.remaining_frames > 0);
+
+        // The contacts of the settled colliders are not updated by `compute_contacts` if they
+        // don't move anymore, so their multipliers are restored right away.
+        for handle in settled {
+            self.apply_settle_multipliers(handle);
+        }
+    }
+
+    // Sets the penetration correction and warmstarting multipliers of the contacts of the
+    // collider `handle` from the current settling colliders.
+    fn apply_settle_multipliers(&mut self, handle: ColliderHandle) {
+        if let Some(graph_idx) = self.graph_indices.get(handle.0) {
+            let settling_colliders = &self.settling_colliders;
+
+            for (h1, h2, _, pair) in self
+                .contact_graph
+                .interactions_with_mut(graph_idx.contact_graph_index)
+            {
+                let multiplier = settle_multiplier(settling_colliders, h1)
+                    .min(settle_multiplier(settling_colliders, h2));
+
+                for manifold in &mut pair.manifolds {
+                    manifold.data.warmstart_multiplier = multiplier;
+                    manifold.data.erp_multiplier = multiplier;
+                }
+            }
+        }
+    }
+
     pub(crate) fn remove_collider(
         &mut self,
         intersection_graph_id: ColliderGraphIndex,
@@ -272,7 +754,35 @@ impl NarrowPhase {
         let mut pairs_to_remove = vec![];
 
         colliders.foreach_modified_colliders(|handle, collider| {
-            if collider.changes.needs_narrow_phase_update() {
+            if collider.modified_region.is_some() {
+                if let Some(gid) = self.graph_indices.get(handle.0) {
+                    // Wake-up the bodies in contact with the modified part of the shape, even if
+                    // this collider is attached to a static body. Otherwise, a body sleeping on
+                    // a lowered part of a terrain would keep floating.
+                    for (_, _, pair) in self
+                        .contact_graph
+                        .interactions_with(gid.contact_graph_index)
+                    {
+                        if let (Some(co1), Some(co2)) = (
+                            colliders.get(pair.pair.collider1),
+                            colliders.get(pair.pair.collider2),
+                        ) {
+                            if pair
+                                .manifolds
+                                .iter()
+                                .any(|m| manifold_in_modified_region(m, co1, co2))
+                            {
+                                bodies.wake_up(co1.parent, true);
+                                bodies.wake_up(co2.parent, true);
+                            }
+                        }
+                    }
+                }
+            }
+
+            if collider.changes.needs_narrow_phase_update()
+                && !collider.changes.needs_pair_reevaluation()
+            {
                 // No flag relevant to the narrow-phase is enabled for this collider.
                 return;
             }
@@ -379,16 +889,23 @@ impl NarrowPhase {
                         .remove_edge(gid1.contact_graph_index, gid2.contact_graph_index);
 
                     // Emit a contact stopped event if we had a contact before removing the edge.
-                    // Also wake up the dynamic bodies that were in contact.
+                    // Also wake up the dynamic bodies that were in contact, unless the physics
+                    // hooks disabled all their contacts.
                     if let Some(ctct) = contact_pair {
+                        if let (Some(key1), Some(key2)) =
+                            (co1.warmstart_cache_key, co2.warmstart_cache_key)
+                        {
+                            self.warmstart_cache.store(key1, key2, &ctct.manifolds);
+                        }
+
                         if ctct.has_any_active_contact {
-                            bodies.wake_up(co1.parent, true);
-                            bodies.wake_up(co2.parent, true);
+                            if ctct.links_bodies() {
+                                wake_up_after_contact_loss(bodies, co1.parent, &ctct);
+                                wake_up_after_contact_loss(bodies, co2.parent, &ctct);
+                            }
 
-                            events.handle_contact_event(ContactEvent::Stopped(
-                                pair.collider1,
-                                pair.collider2,
-                            ))
+                            let event = ContactEvent::Stopped(pair.collider1, pair.collider2);
+                            events.handle_contact_event_with_data(event, ctct.event_data())
                         }
                     }
                 }
@@ -458,7 +975,12 @@ impl NarrowPhase {
                     .find_edge(gid1.contact_graph_index, gid2.contact_graph_index)
                     .is_none()
                 {
-                    let interaction = ContactPair::new(*pair);
+                    let mut interaction = ContactPair::new(*pair);
+                    interaction.user_tags1 = co1.user_tags;
+                    interaction.user_tags2 = co2.user_tags;
+                    interaction.warmstart_cache_pending = self.warmstart_cache.is_enabled()
+                        && co1.warmstart_cache_key.is_some()
+                        && co2.warmstart_cache_key.is_some();
                     let _ = self.contact_graph.add_edge(
                         gid1.contact_graph_index,
                         gid2.contact_graph_index,
@@ -469,6 +991,40 @@ impl NarrowPhase {
         }
     }
 
+    // Emits the AABB overlap end events of all the tracked pairs involving the removed
+    // collider `handle`, so that the broad-phase pair deletions that follow are ignored.
+    fn remove_aabb_overlaps_with(&mut self, handle: ColliderHandle, events: &dyn EventHandler) {
+        if self.aabb_overlaps.is_empty() {
+            return;
+        }
+
+        let mut removed: Vec<_> = self
+            .aabb_overlaps
+            .iter()
+            .filter(|pair| pair.collider1 == handle || pair.collider2 == handle)
+            .copied()
+            .collect();
+        // Sort for the events order not to depend on the hash-set iteration order.
+        removed.sort_by_key(|pair| (pair.collider1.0, pair.collider2.0));
+
+        for pair in removed {
+            self.aabb_overlaps.remove(&pair);
+            events.handle_aabb_overlap_event(AabbOverlapEvent::new(
+                pair.collider1,
+                pair.collider2,
+                false,
+            ));
+        }
+    }
+
+    fn aabb_overlap_key(pair: &ColliderPair) -> ColliderPair {
+        if pair.collider1.0 <= pair.collider2.0 {
+            *pair
+        } else {
+            pair.swap()
+        }
+    }
+
     pub(crate) fn register_pairs(
         &mut self,
         colliders: &mut ColliderSet,
@@ -479,15 +1035,71 @@ impl NarrowPhase {
         for event in broad_phase_events {
             match event {
                 BroadPhasePairEvent::AddPair(pair) => {
+                    if let (Some(co1), Some(co2)) =
+                        (colliders.get(pair.collider1), colliders.get(pair.collider2))
+                    {
+                        let flags = co1.active_events | co2.active_events;
+                        if flags.contains(ActiveEvents::PROXIMITY_AABB_EVENTS)
+                            && self.aabb_overlaps.insert(Self::aabb_overlap_key(pair))
+                        {
+                            events.handle_aabb_overlap_event(AabbOverlapEvent::new(
+                                pair.collider1,
+                                pair.collider2,
+                                true,
+                            ));
+                        }
+                    }
+
                     self.add_pair(colliders, pair);
                 }
                 BroadPhasePairEvent::DeletePair(pair) => {
+                    // NOTE: the colliders may have been removed already, so we only rely
+                    // on the tracked pairs here.
+                    if self.aabb_overlaps.remove(&Self::aabb_overlap_key(pair)) {
+                        events.handle_aabb_overlap_event(AabbOverlapEvent::new(
+                            pair.collider1,
+                            pair.collider2,
+                            false,
+                        ));
+                    }
+
                     self.remove_pair(colliders, bodies, pair, events, PairRemovalMode::Auto);
                 }
             }
         }
     }
 
+    /// The indices of the edges of `graph` involving a collider modified since the last
+    /// timestep, sorted and without duplicates.
+    ///
+    /// Returns `None` if all the edges must be visited, which is the case outside of
+    /// `PhysicsPipeline::step_region`: most colliders are modified at each timestep so going
+    /// through all the edges is cheaper than gathering them.
+    fn modified_interactions<E>(
+        graph: &InteractionGraph<ColliderHandle, E>,
+        graph_indices: &Coarena<ColliderGraphIndices>,
+        bodies: &RigidBodySet,
+        colliders: &ColliderSet,
+        graph_index: fn(&ColliderGraphIndices) -> ColliderGraphIndex,
+    ) -> Option<Vec<usize>> {
+        if !bodies.contains_suspended_bodies() || colliders.modified_all_colliders {
+            return None;
+        }
+
+        let mut ids = Vec::new();
+
+        for handle in &colliders.modified_colliders {
+            if let Some(indices) = graph_indices.get(handle.0) {
+                let edges = graph.graph.edges(graph_index(indices));
+                ids.extend(edges.map(|edge| edge.id().index()));
+            }
+        }
+
+        ids.sort_unstable();
+        ids.dedup();
+        Some(ids)
+    }
+
     pub(crate) fn compute_intersections(
         &mut self,
         bodies: &RigidBodySet,
@@ -503,8 +1115,7 @@ impl NarrowPhase {
         let query_dispatcher = &*self.query_dispatcher;
         let active_hooks = hooks.active_hooks();
 
-        // TODO: don't iterate on all the edges.
-        par_iter_mut!(&mut self.intersection_graph.graph.edges).for_each(|edge| {
+        let update_pair = |edge: &mut Edge<bool>| {
             let handle1 = nodes[edge.source().index()].weight;
             let handle2 = nodes[edge.target().index()].weight;
             let co1 = &colliders[handle1];
@@ -513,7 +1124,7 @@ impl NarrowPhase {
             if !co1.changes.needs_narrow_phase_update() && !co2.changes.needs_narrow_phase_update()
             {
                 // No update needed for these colliders.
-                return;
+                return None;
             }
 
             // TODO: avoid lookup into bodies.
@@ -525,12 +1136,18 @@ impl NarrowPhase {
                 || (rb1.is_sleeping() && rb2.is_sleeping())
             {
                 // No need to update this intersection because nothing moved.
-                return;
+                return None;
             }
 
             if !co1.collision_groups.test(co2.collision_groups) {
-                // The intersection is not allowed.
-                return;
+                // The intersection is not allowed. An intersection detected before a
+                // change of the collision groups ends.
+                return clear_intersection(&mut edge.weight, handle1, handle2);
+            }
+
+            if co1.is_excluded_from(handle2) {
+                // The intersection is explicitly excluded.
+                return clear_intersection(&mut edge.weight, handle1, handle2);
             }
 
             if !active_hooks.contains(PhysicsHooksFlags::FILTER_INTERSECTION_PAIR)
@@ -538,7 +1155,7 @@ impl NarrowPhase {
                 && !rb2.is_dynamic()
             {
                 // Default filtering rule: no intersection between two non-dynamic bodies.
-                return;
+                return None;
             }
 
             if active_hooks.contains(PhysicsHooksFlags::FILTER_INTERSECTION_PAIR) {
@@ -553,30 +1170,60 @@ impl NarrowPhase {
 
                 if !hooks.filter_intersection_pair(&context) {
                     // No intersection allowed.
-                    return;
+                    return None;
                 }
             }
 
             let pos12 = co1.position().inv_mul(co2.position());
 
-            if let Ok(intersection) =
-                query_dispatcher.intersection_test(&pos12, co1.shape(), co2.shape())
-            {
-                if intersection != edge.weight {
-                    edge.weight = intersection;
-                    events.handle_intersection_event(IntersectionEvent::new(
-                        handle1,
-                        handle2,
-                        intersection,
-                    ));
-                }
+            let intersection = query_dispatcher
+                .intersection_test(&pos12, co1.shape(), co2.shape())
+                .ok()?;
+
+            if intersection != edge.weight {
+                edge.weight = intersection;
+                Some(IntersectionEvent::new(handle1, handle2, intersection))
+            } else {
+                None
             }
+        };
+
+        let modified_edges = Self::modified_interactions(
+            &self.intersection_graph,
+            &self.graph_indices,
+            bodies,
+            colliders,
+            |indices| indices.intersection_graph_index,
+        );
+        let edges = &mut self.intersection_graph.graph.edges;
+        let mut intersection_events: Vec<_> = if let Some(ids) = modified_edges {
+            let mut modified_edges = edges_at_mut(edges, &ids);
+            par_iter_mut!(modified_edges)
+                .filter_map(|edge| update_pair(edge))
+                .collect()
+        } else {
+            par_iter_mut!(edges).filter_map(&update_pair).collect()
+        };
+
+        // NOTE: the events are sorted so that their order doesn't depend on
+        //       the number of threads nor on the ordering of the graph edges.
+        intersection_events.sort_unstable_by_key(|e| {
+            (
+                e.collider1.into_raw_parts(),
+                e.collider2.into_raw_parts(),
+                e.intersecting,
+            )
         });
+
+        for event in intersection_events {
+            events.handle_intersection_event(event);
+        }
     }
 
     pub(crate) fn compute_contacts(
         &mut self,
         prediction_distance: Real,
+        manifold_freezing: Option<ManifoldFreezing>,
         bodies: &RigidBodySet,
         colliders: &ColliderSet,
         hooks: &dyn PhysicsHooks,
@@ -588,17 +1235,23 @@ impl NarrowPhase {
 
         let query_dispatcher = &*self.query_dispatcher;
         let active_hooks = hooks.active_hooks();
+        let settling_colliders = &self.settling_colliders;
+        let warmstart_cache = &self.warmstart_cache;
 
-        // TODO: don't iterate on all the edges.
-        par_iter_mut!(&mut self.contact_graph.graph.edges).for_each(|edge| {
+        let update_pair = |edge: &mut Edge<ContactPair>| {
             let pair = &mut edge.weight;
             let co1 = &colliders[pair.pair.collider1];
             let co2 = &colliders[pair.pair.collider2];
 
+            // The user tags are copied without any change tracking so the contact events
+            // always report the latest ones.
+            pair.user_tags1 = co1.user_tags;
+            pair.user_tags2 = co2.user_tags;
+
             if !co1.changes.needs_narrow_phase_update() && !co2.changes.needs_narrow_phase_update()
             {
                 // No update needed for these colliders.
-                return;
+                return None;
             }
 
             // TODO: avoid lookup into bodies.
@@ -610,12 +1263,18 @@ impl NarrowPhase {
                 || (rb1.is_sleeping() && rb2.is_sleeping())
             {
                 // No need to update this contact because nothing moved.
-                return;
+                return None;
             }
 
             if !co1.collision_groups.test(co2.collision_groups) {
-                // The collision is not allowed.
-                return;
+                // The collision is not allowed. The contacts computed before a change of
+                // the collision groups are dropped.
+                return pair.clear_contacts();
+            }
+
+            if co1.is_excluded_from(pair.pair.collider2) {
+                // The collision is explicitly excluded.
+                return pair.clear_contacts();
             }
 
             if !active_hooks.contains(PhysicsHooksFlags::FILTER_CONTACT_PAIR)
@@ -623,53 +1282,151 @@ impl NarrowPhase {
                 && !rb2.is_dynamic()
             {
                 // Default filtering rule: no contact between two non-dynamic bodies.
-                return;
+                return None;
             }
 
-            let mut solver_flags = if active_hooks.contains(PhysicsHooksFlags::FILTER_CONTACT_PAIR)
+            if !active_hooks.contains(PhysicsHooksFlags::FILTER_CONTACT_PAIR)
+                && rb1.is_projectile()
+                && rb2.is_projectile()
             {
-                let context = PairFilterContext {
-                    rigid_body1: rb1,
-                    rigid_body2: rb2,
-                    collider_handle1: pair.pair.collider1,
-                    collider_handle2: pair.pair.collider2,
-                    collider1: co1,
-                    collider2: co2,
-                };
+                // Default filtering rule: no contact between two projectiles.
+                return None;
+            }
+
+            let (mut solver_flags, pair_prediction_distance) =
+                if active_hooks.contains(PhysicsHooksFlags::FILTER_CONTACT_PAIR) {
+                    let context = PairFilterContext {
+                        rigid_body1: rb1,
+                        rigid_body2: rb2,
+                        collider_handle1: pair.pair.collider1,
+                        collider_handle2: pair.pair.collider2,
+                        collider1: co1,
+                        collider2: co2,
+                    };
 
-                if let Some(solver_flags) = hooks.filter_contact_pair(&context) {
-                    solver_flags
+                    if let Some(result) = hooks.filter_contact_pair(&context) {
+                        (result.solver_flags, result.prediction_distance)
+                    } else {
+                        // No contact allowed. The solver contacts of the previous update
+                        // must not be solved, nor link the bodies anymore.
+                        for manifold in &mut pair.manifolds {
+                            manifold.data.disabled_by_hooks |=
+                                !manifold.data.solver_contacts.is_empty();
+                            manifold.data.reenabled_by_hooks = false;
+                            manifold.data.has_rolling_resistance = false;
+                            manifold.data.solver_contacts.clear();
+                        }
+                        return None;
+                    }
                 } else {
-                    // No contact allowed.
-                    return;
-                }
-            } else {
-                co1.solver_flags | co2.solver_flags
-            };
+                    (co1.solver_flags | co2.solver_flags, None)
+                };
+
+            pair.prediction_distance = pair_prediction_distance;
+            // Contacts targeting a positive distance must be generated before the colliders
+            // get that close.
+            let target_dist = -(co1.contact_target_penetration + co2.contact_target_penetration);
+            let prediction_distance =
+                pair_prediction_distance.unwrap_or(prediction_distance) + target_dist.max(0.0);
 
             if !co1.solver_groups.test(co2.solver_groups) {
                 solver_flags.remove(SolverFlags::COMPUTE_IMPULSES);
             }
 
+            let mut kept_contacts = Vec::new();
+
             if co1.changes.contains(ColliderChanges::SHAPE)
                 || co2.changes.contains(ColliderChanges::SHAPE)
             {
-                // The shape changed so the workspace is no longer valid.
+                // The shape changed so the workspace and the frozen manifolds are no longer valid.
+                pair.workspace = None;
+                pair.pose_tracker = None;
+            } else if co1.modified_region.is_some() || co2.modified_region.is_some() {
+                // Only a region of the shape changed. The workspace is no longer valid but
+                // the contacts outside of the modified region keep their warmstart impulses.
+                for manifold in &mut pair.manifolds {
+                    if manifold_in_modified_region(manifold, co1, co2) {
+                        for contact in &mut manifold.points {
+                            contact.data = ContactData::default();
+                        }
+                    } else {
+                        kept_contacts.extend(manifold.points.iter().map(|contact| {
+                            (
+                                manifold.subshape1,
+                                manifold.subshape2,
+                                contact.fid1,
+                                contact.fid2,
+                                contact.data,
+                            )
+                        }));
+                    }
+                }
+
                 pair.workspace = None;
+                pair.pose_tracker = None;
             }
 
-            let pos12 = co1.position().inv_mul(co2.position());
-            let _ = query_dispatcher.contact_manifolds(
-                &pos12,
-                co1.shape(),
-                co2.shape(),
-                prediction_distance,
-                &mut pair.manifolds,
-                &mut pair.workspace,
-            );
+            // Colliders attached to kinematic bodies may have their contacts computed at
+            // their predicted position, so contacts are known before they actually occur.
+            let position1 = co1.predicted_position.as_ref().unwrap_or(co1.position());
+            let position2 = co2.predicted_position.as_ref().unwrap_or(co2.position());
+            let predicted = co1.predicted_position.is_some() || co2.predicted_position.is_some();
+
+            let pos12 = position1.inv_mul(position2);
+
+            // Frozen manifolds are not recomputed: their contact points move rigidly with
+            // the colliders, and their distances are recomputed below.
+            let frozen = if let Some(freezing) = &manifold_freezing {
+                pair.update_pose_tracker(&pos12, freezing)
+            } else {
+                pair.pose_tracker = None;
+                false
+            };
+
+            if !frozen {
+                let _ = query_dispatcher.contact_manifolds(
+                    &pos12,
+                    co1.shape(),
+                    co2.shape(),
+                    prediction_distance,
+                    &mut pair.manifolds,
+                    &mut pair.workspace,
+                );
+            }
+
+            for (subshape1, subshape2, fid1, fid2, data) in kept_contacts {
+                let manifold = pair
+                    .manifolds
+                    .iter_mut()
+                    .find(|m| m.subshape1 == subshape1 && m.subshape2 == subshape2);
+
+                if let Some(manifold) = manifold {
+                    for contact in &mut manifold.points {
+                        if contact.fid1 == fid1 && contact.fid2 == fid2 {
+                            contact.data = data;
+                        }
+                    }
+                }
+            }
+
+            // The first contacts of a new pair are warmstarted with the cached contacts of
+            // a previous pair between colliders with the same keys.
+            if pair.warmstart_cache_pending && pair.manifolds.iter().any(|m| !m.points.is_empty()) {
+                pair.warmstart_cache_pending = false;
+
+                if let (Some(key1), Some(key2)) = (co1.warmstart_cache_key, co2.warmstart_cache_key)
+                {
+                    warmstart_cache.apply(key1, key2, &mut pair.manifolds);
+                }
+            }
 
             let mut has_any_active_contact = false;
 
+            // The contacts of colliders settling after the removal of a collider they
+            // touched have their penetration correction and warmstarting reduced.
+            let settling_multiplier = settle_multiplier(settling_colliders, pair.pair.collider1)
+                .min(settle_multiplier(settling_colliders, pair.pair.collider2));
+
             let friction = CoefficientCombineRule::combine(
                 co1.friction,
                 co2.friction,
@@ -682,44 +1439,122 @@ impl NarrowPhase {
                 co1.flags.restitution_combine_rule_value(),
                 co2.flags.restitution_combine_rule_value(),
             );
+            let rolling_resistance = CoefficientCombineRule::combine(
+                co1.rolling_resistance,
+                co2.rolling_resistance,
+                co1.flags.friction_combine_rule_value(),
+                co2.flags.friction_combine_rule_value(),
+            );
+            let normal_smoothing = match (co1.normal_smoothing, co2.normal_smoothing) {
+                (Some(max_rotation1), Some(max_rotation2)) => {
+                    Some(max_rotation1.min(max_rotation2))
+                }
+                (max_rotation1, max_rotation2) => max_rotation1.or(max_rotation2),
+            };
 
             for manifold in &mut pair.manifolds {
                 let world_pos1 = manifold.subshape_pos1.prepend_to(co1.position());
+                // The normal reported at the previous update, if the manifold had solver
+                // contacts then.
+                let prev_normal = if manifold.data.solver_contacts.is_empty() {
+                    None
+                } else {
+                    Some(manifold.data.normal)
+                };
                 manifold.data.solver_contacts.clear();
                 manifold.data.body_pair = BodyPair::new(co1.parent(), co2.parent());
                 manifold.data.solver_flags = solver_flags;
+                manifold.data.warmstart_multiplier = settling_multiplier;
+                manifold.data.erp_multiplier = settling_multiplier;
+                manifold.data.min_reported_impulse =
+                    co1.min_reported_impulse.max(co2.min_reported_impulse);
                 manifold.data.relative_dominance =
                     rb1.effective_dominance_group() - rb2.effective_dominance_group();
                 manifold.data.normal = world_pos1 * manifold.local_n1;
 
+                // Fix the normals generated on the internal edges of triangle meshes.
+                #[cfg(feature = "dim3")]
+                let normal_corrected = correct_internal_edge_normal(co1, co2, manifold);
+                #[cfg(feature = "dim2")]
+                let normal_corrected = false;
+                manifold.data.raw_normal = manifold.data.normal;
+                let world_pos2 = manifold.subshape_pos2.prepend_to(co2.position());
+                let predicted_world_pos1 = manifold.subshape_pos1.prepend_to(position1);
+                let predicted_world_pos2 = manifold.subshape_pos2.prepend_to(position2);
+
                 // Generate solver contacts.
-                for (contact_id, contact) in manifold.points.iter().enumerate() {
+                for (contact_id, contact) in manifold.points.iter_mut().enumerate() {
                     assert!(
                         contact_id <= u8::MAX as usize,
                         "A contact manifold cannot contain more than 255 contacts currently."
                     );
 
-                    if contact.dist < prediction_distance {
+                    let dist = if normal_corrected || frozen {
+                        (predicted_world_pos2 * contact.local_p2
+                            - predicted_world_pos1 * contact.local_p1)
+                            .dot(&manifold.data.normal)
+                    } else {
+                        contact.dist
+                    };
+
+                    if dist < prediction_distance {
+                        // The solver works with the current positions, so it needs the current
+                        // distance. A contact that doesn't exist yet then acts as a speculative
+                        // contact stopping the bodies right where they will touch.
+                        let dist = if predicted {
+                            (world_pos2 * contact.local_p2 - world_pos1 * contact.local_p1)
+                                .dot(&manifold.data.normal)
+                        } else {
+                            dist
+                        };
+
+                        // A new contact is anchored where it is now. It stays anchored there as
+                        // long as it doesn't slide.
+                        let (anchor1, anchor2) = *contact
+                            .data
+                            .friction_anchor
+                            .get_or_insert((contact.local_p1, contact.local_p2));
+                        let friction_anchor_offset = world_pos1 * anchor1 - world_pos2 * anchor2;
+
                         // Generate the solver contact.
                         let solver_contact = SolverContact {
                             contact_id: contact_id as u8,
                             point: world_pos1 * contact.local_p1
-                                + manifold.data.normal * contact.dist / 2.0,
-                            dist: contact.dist,
+                                + manifold.data.normal * dist / 2.0,
+                            dist,
+                            target_dist,
                             friction,
                             restitution,
+                            rolling_resistance,
                             tangent_velocity: Vector::zeros(),
                             warmstart_impulse: contact.data.impulse,
                             warmstart_tangent_impulse: contact.data.tangent_impulse,
                             prev_rhs: contact.data.rhs,
+                            friction_anchor_offset,
                         };
 
                         manifold.data.solver_contacts.push(solver_contact);
                         has_any_active_contact = true;
+                    } else {
+                        contact.data.friction_anchor = None;
+                    }
+                }
+
+                // Limit the rotation of the normal of the persistent contacts. This is done
+                // after generating the solver contacts so their distances and points are
+                // computed with the raw normal.
+                if let (Some(max_rotation), Some(prev_normal)) = (normal_smoothing, prev_normal) {
+                    if !manifold.data.solver_contacts.is_empty() {
+                        manifold.data.normal = limit_normal_rotation(
+                            &prev_normal,
+                            &manifold.data.normal,
+                            max_rotation,
+                        );
                     }
                 }
 
                 // Apply the user-defined contact modification.
+                let num_generated_contacts = manifold.data.solver_contacts.len();
                 if active_hooks.contains(PhysicsHooksFlags::MODIFY_SOLVER_CONTACTS)
                     && manifold
                         .data
@@ -727,7 +1562,7 @@ impl NarrowPhase {
                         .contains(SolverFlags::MODIFY_SOLVER_CONTACTS)
                 {
                     let mut modifiable_solver_contacts =
-                        std::mem::replace(&mut manifold.data.solver_contacts, Vec::new());
+                        core::mem::replace(&mut manifold.data.solver_contacts, Vec::new());
                     let mut modifiable_user_data = manifold.data.user_data;
                     let mut modifiable_normal = manifold.data.normal;
 
@@ -741,6 +1576,7 @@ impl NarrowPhase {
                         manifold,
                         solver_contacts: &mut modifiable_solver_contacts,
                         normal: &mut modifiable_normal,
+                        prev_normal,
                         user_data: &mut modifiable_user_data,
                     };
 
@@ -750,24 +1586,107 @@ impl NarrowPhase {
                     manifold.data.normal = modifiable_normal;
                     manifold.data.user_data = modifiable_user_data;
                 }
+
+                // Keep track of the solver contacts disabled by the hook, so the simulation
+                // islands ignore them, and of the ones it re-enables.
+                let was_disabled = manifold.data.disabled_by_hooks;
+                let is_empty = manifold.data.solver_contacts.is_empty();
+                manifold.data.disabled_by_hooks = num_generated_contacts != 0 && is_empty;
+                manifold.data.reenabled_by_hooks = was_disabled && !is_empty;
+                manifold.data.has_rolling_resistance = manifold
+                    .data
+                    .solver_contacts
+                    .iter()
+                    .any(|contact| contact.rolling_resistance != 0.0);
             }
 
             if has_any_active_contact != pair.has_any_active_contact {
-                if has_any_active_contact {
-                    events.handle_contact_event(ContactEvent::Started(
-                        pair.pair.collider1,
-                        pair.pair.collider2,
-                    ));
+                pair.has_any_active_contact = has_any_active_contact;
+
+                let event = if has_any_active_contact {
+                    ContactEvent::Started(pair.pair.collider1, pair.pair.collider2)
                 } else {
-                    events.handle_contact_event(ContactEvent::Stopped(
-                        pair.pair.collider1,
-                        pair.pair.collider2,
-                    ));
-                }
+                    ContactEvent::Stopped(pair.pair.collider1, pair.pair.collider2)
+                };
 
-                pair.has_any_active_contact = has_any_active_contact;
+                Some((event, pair.event_data()))
+            } else {
+                None
             }
+        };
+
+        let modified_edges = Self::modified_interactions(
+            &self.contact_graph,
+            &self.graph_indices,
+            bodies,
+            colliders,
+            |indices| indices.contact_graph_index,
+        );
+        let edges = &mut self.contact_graph.graph.edges;
+        let mut contact_events: Vec<_> = if let Some(ids) = modified_edges {
+            let mut modified_edges = edges_at_mut(edges, &ids);
+            par_iter_mut!(modified_edges)
+                .filter_map(|edge| update_pair(edge))
+                .collect()
+        } else {
+            par_iter_mut!(edges).filter_map(&update_pair).collect()
+        };
+
+        // NOTE: the events are sorted so that their order doesn't depend on
+        //       the number of threads nor on the ordering of the graph edges.
+        //       The `Started` events are delivered before the `Stopped` events
+        //       of the same pair.
+        contact_events.sort_unstable_by_key(|(e, _)| match e {
+            ContactEvent::Started(h1, h2) => (h1.into_raw_parts(), h2.into_raw_parts(), 0),
+            ContactEvent::Stopped(h1, h2) => (h1.into_raw_parts(), h2.into_raw_parts(), 1),
         });
+
+        for (event, data) in contact_events {
+            events.handle_contact_event_with_data(event, data);
+        }
+    }
+
+    /// The number of pairs of colliders tracked by this narrow-phase.
+    pub(crate) fn num_pairs(&self) -> usize {
+        self.contact_graph.graph.edges.len() + self.intersection_graph.graph.edges.len()
+    }
+
+    /// Ignores the solver contacts exceeding `max_solver_contacts`, keeping the ones
+    /// of the pairs with the smallest collider handles.
+    ///
+    /// Returns the total number of solver contacts before removal.
+    pub(crate) fn limit_solver_contacts(&mut self, max_solver_contacts: usize) -> usize {
+        let edges = &mut self.contact_graph.graph.edges;
+        let num_solver_contacts = edges
+            .iter()
+            .flat_map(|edge| edge.weight.manifolds.iter())
+            .map(|manifold| manifold.data.solver_contacts.len())
+            .sum();
+
+        if num_solver_contacts <= max_solver_contacts {
+            return num_solver_contacts;
+        }
+
+        let mut sorted_edges: Vec<_> = (0..edges.len()).collect();
+        sorted_edges.sort_unstable_by_key(|i| {
+            let pair = edges[*i].weight.pair;
+            (
+                pair.collider1.0.min(pair.collider2.0),
+                pair.collider1.0.max(pair.collider2.0),
+            )
+        });
+
+        let mut remaining = max_solver_contacts;
+
+        for i in sorted_edges {
+            for manifold in &mut edges[i].weight.manifolds {
+                let num_kept = manifold.data.solver_contacts.len().min(remaining);
+                manifold.data.solver_contacts.truncate(num_kept);
+                remaining -= num_kept;
+            }
+        }
+
+        num_solver_contacts
     }
 
     /// Retrieve all the interactions with at least one contact point, happening between two active bodies.
@@ -787,16 +1706,33 @@ impl NarrowPhase {
             for manifold in &mut inter.weight.manifolds {
                 let rb1 = &bodies[manifold.data.body_pair.body1];
                 let rb2 = &bodies[manifold.data.body_pair.body2];
+                let sleeping1 = rb1.is_dynamic() && rb1.is_sleeping();
+                let sleeping2 = rb2.is_dynamic() && rb2.is_sleeping();
+
                 if manifold
                     .data
                     .solver_flags
                     .contains(SolverFlags::COMPUTE_IMPULSES)
                     && manifold.data.num_active_contacts() != 0
-                    && (rb1.is_dynamic() || rb2.is_dynamic())
-                    && (!rb1.is_dynamic() || !rb1.is_sleeping())
-                    && (!rb2.is_dynamic() || !rb2.is_sleeping())
+                    && ((rb1.is_dynamic_with_mass() && rb1.is_dynamic_and_awake())
+                        || (rb2.is_dynamic_with_mass() && rb2.is_dynamic_and_awake()))
                 {
-                    let island_index = if !rb1.is_dynamic() {
+                    // A sleeping body touching an awake body, e.g., because of its `WakePolicy`,
+                    // acts as a static body.
+                    let static_dominance = i8::MAX as i16 + 1;
+                    let dominance1 = if sleeping1 {
+                        static_dominance
+                    } else {
+                        rb1.effective_dominance_group()
+                    };
+                    let dominance2 = if sleeping2 {
+                        static_dominance
+                    } else {
+                        rb2.effective_dominance_group()
+                    };
+                    manifold.data.relative_dominance = dominance1 - dominance2;
+
+                    let island_index = if !rb1.is_dynamic_and_awake() {
                         rb2.active_island_id
                     } else {
                         rb1.active_island_id
@@ -807,5 +1743,82 @@ impl NarrowPhase {
                 }
             }
         }
+
+        // The contacts involving bodies with a higher solver priority are solved last.
+        // NOTE: the sort is stable so this doesn't change anything if all the priorities are equal.
+        for out_island in &mut out[..bodies.num_islands()] {
+            out_island
+                .sort_by_key(|i| bodies.pair_solver_priority(out_manifolds[*i].data.body_pair));
+        }
+    }
+}
+
+/// Wakes up the rigid-body `handle` which lost the contacts of `pair`, unless it is sleeping and
+/// its `WakePolicy` ignores these contacts.
+fn wake_up_after_contact_loss(
+    bodies: &mut RigidBodySet,
+    handle: RigidBodyHandle,
+    pair: &ContactPair,
+) {
+    if let Some(rb) = bodies.get(handle) {
+        if rb.is_sleeping() && !rb.wake_policy().is_woken_by_contact(pair.total_impulse()) {
+            return;
+        }
+    }
+
+    bodies.wake_up(handle, true);
+}
+
+/// Marks the colliders `handle1` and `handle2` as no longer intersecting, e.g., because they are
+/// no longer allowed to interact.
+///
+/// Returns the event to emit if they were intersecting.
+fn clear_intersection(
+    intersecting: &mut bool,
+    handle1: ColliderHandle,
+    handle2: ColliderHandle,
+) -> Option<IntersectionEvent> {
+    if core::mem::replace(intersecting, false) {
+        Some(IntersectionEvent::new(handle1, handle2, false))
+    } else {
+        None
     }
 }
+
+/// Mutable references to the `edges` with the given indices, sorted and without duplicates.
+fn edges_at_mut<'a, E>(mut edges: &'a mut [Edge<E>], ids: &[usize]) -> Vec<&'a mut Edge<E>> {
+    let mut result = Vec::with_capacity(ids.len());
+    let mut offset = 0;
+
+    for id in ids {
+        let (edge, rest) = core::mem::take(&mut edges)[id - offset..]
+            .split_first_mut()
+            .unwrap();
+        result.push(edge);
+        edges = rest;
+        offset = id + 1;
+    }
+
+    result
+}
+
+/// Checks if a contact of this manifold lies in the region of the shape of `co1` or `co2`
+/// modified since the last timestep.
+fn manifold_in_modified_region(manifold: &ContactManifold, co1: &Collider, co2: &Collider) -> bool {
+    manifold.points.iter().any(|contact| {
+        let in_region1 = co1.modified_region.map_or(false, |region| {
+            let pt = manifold
+                .subshape_pos1
+                .map_or(contact.local_p1, |pos| pos * contact.local_p1);
+            region.contains_local_point(&pt)
+        });
+        let in_region2 = co2.modified_region.map_or(false, |region| {
+            let pt = manifold
+                .subshape_pos2
+                .map_or(contact.local_p2, |pos| pos * contact.local_p2);
+            region.contains_local_point(&pt)
+        });
+
+        in_region1 || in_region2
+    })
+}
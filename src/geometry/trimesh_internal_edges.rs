@@ -0,0 +1,230 @@
+use crate::geometry::{Collider, ContactManifold};
+use crate::math::{Point, Real, Vector};
+use alloc::{vec, vec::Vec};
+use parry::utils::hashmap::{Entry, HashMap};
+
+bitflags::bitflags! {
+    #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+    /// Flags controlling how the contacts with a triangle mesh collider are computed.
+    pub struct TriMeshFlags: u8 {
+        /// Corrects the contact normals generated on the edges shared by adjacent triangles,
+        /// so that objects sliding on a tessellated surface don't catch on its internal edges.
+        ///
+        /// This requires the triangles of the mesh to be consistently oriented. Heightfields
+        /// don't support this correction.
+        const FIX_INTERNAL_EDGES = 1 << 0;
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+enum EdgeKind {
+    /// The edge belongs to only one triangle, or to more than two triangles.
+    Boundary,
+    /// The edge is shared with a coplanar triangle, or is concave.
+    NotConvex,
+    /// The edge is convex. The normal of the adjacent triangle is given.
+    Convex(Vector<Real>),
+}
+
+#[derive(Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+struct TriangleEdges {
+    // Zero if the triangle is degenerate.
+    normal: Vector<Real>,
+    // The directions orthogonal to each edge, in the plane of the triangle, pointing outside of it.
+    outward_dirs: [Vector<Real>; 3],
+    kinds: [EdgeKind; 3],
+}
+
+/// The convexity of the edges of a triangle mesh, used to correct the contact normals
+/// generated on its internal edges.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub(crate) struct TriMeshInternalEdges {
+    triangles: Vec<TriangleEdges>,
+}
+
+impl TriMeshInternalEdges {
+    /// Two adjacent triangles with normals closer than this are considered coplanar.
+    const COPLANARITY_COS: Real = 0.9999;
+    /// Contact normals closer than this to the normal of a triangle are never corrected.
+    const FACE_NORMAL_COS: Real = 0.9999;
+
+    /// Computes the convexity of all the edges of a triangle mesh from its adjacency.
+    pub fn new(vertices: &[Point<Real>], indices: &[[u32; 3]]) -> Self {
+        let mut triangles: Vec<_> = indices
+            .iter()
+            .map(|idx| {
+                let [a, b, c] = [
+                    vertices[idx[0] as usize],
+                    vertices[idx[1] as usize],
+                    vertices[idx[2] as usize],
+                ];
+                let normal = (b - a)
+                    .cross(&(c - a))
+                    .try_normalize(Real::EPSILON)
+                    .unwrap_or(Vector::zeros());
+                let outward_dir = |p1: Point<Real>, p2: Point<Real>| {
+                    (p2 - p1)
+                        .cross(&normal)
+                        .try_normalize(Real::EPSILON)
+                        .unwrap_or(Vector::zeros())
+                };
+
+                TriangleEdges {
+                    normal,
+                    outward_dirs: [outward_dir(a, b), outward_dir(b, c), outward_dir(c, a)],
+                    kinds: [EdgeKind::Boundary; 3],
+                }
+            })
+            .collect();
+
+        // Maps each edge to the triangles (and their local edge index) it belongs to.
+        let mut edge_triangles: HashMap<(u32, u32), Vec<(usize, usize)>> = HashMap::default();
+
+        for (tri_id, idx) in indices.iter().enumerate() {
+            for i in 0..3 {
+                let (v1, v2) = (idx[i], idx[(i + 1) % 3]);
+                let key = (v1.min(v2), v1.max(v2));
+
+                match edge_triangles.entry(key) {
+                    Entry::Occupied(mut e) => e.get_mut().push((tri_id, i)),
+                    Entry::Vacant(e) => {
+                        let _ = e.insert(vec![(tri_id, i)]);
+                    }
+                }
+            }
+        }
+
+        for adjacent in edge_triangles.values() {
+            if let [(tri1, edge1), (tri2, edge2)] = adjacent[..] {
+                let kind1 = Self::edge_kind(vertices, indices, tri1, edge1, tri2, &triangles);
+                let kind2 = Self::edge_kind(vertices, indices, tri2, edge2, tri1, &triangles);
+                triangles[tri1].kinds[edge1] = kind1;
+                triangles[tri2].kinds[edge2] = kind2;
+            }
+        }
+
+        Self { triangles }
+    }
+
+    fn edge_kind(
+        vertices: &[Point<Real>],
+        indices: &[[u32; 3]],
+        tri: usize,
+        edge: usize,
+        adjacent_tri: usize,
+        triangles: &[TriangleEdges],
+    ) -> EdgeKind {
+        let normal = triangles[tri].normal;
+        let adjacent_normal = triangles[adjacent_tri].normal;
+
+        if normal == Vector::zeros() || adjacent_normal == Vector::zeros() {
+            return EdgeKind::Boundary;
+        }
+
+        if normal.dot(&adjacent_normal) >= Self::COPLANARITY_COS {
+            return EdgeKind::NotConvex;
+        }
+
+        // The edge is convex if the adjacent triangle lies below the plane of this triangle.
+        let edge_pt = vertices[indices[tri][edge] as usize];
+        let center = indices[adjacent_tri]
+            .iter()
+            .map(|i| vertices[*i as usize].coords)
+            .sum::<Vector<Real>>()
+            / 3.0;
+
+        if (center - edge_pt.coords).dot(&normal) < 0.0 {
+            EdgeKind::Convex(adjacent_normal)
+        } else {
+            EdgeKind::NotConvex
+        }
+    }
+
+    /// Computes the corrected version of a contact normal pointing outside of the given triangle.
+    ///
+    /// The normal is expressed in the local-space of the mesh. Returns `None` if it doesn't need
+    /// to be corrected.
+    pub fn corrected_normal(&self, triangle: u32, normal: &Vector<Real>) -> Option<Vector<Real>> {
+        let tri = self.triangles.get(triangle as usize)?;
+        let cos = normal.dot(&tri.normal);
+
+        // Don't correct contacts with the back side of the triangle.
+        if tri.normal == Vector::zeros() || cos >= Self::FACE_NORMAL_COS || cos < -1.0e-3 {
+            return None;
+        }
+
+        // Find the edge the normal deviates toward.
+        let tangential = normal - tri.normal * cos;
+        let (edge, _) = tri
+            .outward_dirs
+            .iter()
+            .map(|dir| dir.dot(&tangential))
+            .enumerate()
+            .fold(
+                (0, -Real::MAX),
+                |best, (i, dot)| {
+                    if dot > best.1 {
+                        (i, dot)
+                    } else {
+                        best
+                    }
+                },
+            );
+
+        match tri.kinds[edge] {
+            EdgeKind::Boundary => None,
+            EdgeKind::NotConvex => Some(tri.normal),
+            EdgeKind::Convex(adjacent_normal) => {
+                // On a convex edge, the normal may rotate from the normal of the
+                // triangle up to the normal of the adjacent triangle.
+                let dir = tri.outward_dirs[edge];
+                let angle = normal.dot(&dir).atan2(cos);
+                let max_angle = adjacent_normal
+                    .dot(&dir)
+                    .atan2(adjacent_normal.dot(&tri.normal));
+
+                if angle <= max_angle {
+                    None
+                } else {
+                    Some(adjacent_normal)
+                }
+            }
+        }
+    }
+}
+
+/// Corrects the normal of a contact manifold involving a triangle mesh collider with
+/// internal edges information.
+///
+/// Returns `true` if `manifold.data.normal` was modified.
+pub(crate) fn correct_internal_edge_normal(
+    co1: &Collider,
+    co2: &Collider,
+    manifold: &mut ContactManifold,
+) -> bool {
+    if let Some(edges) = co1.trimesh_internal_edges.as_deref() {
+        let local_normal = co1
+            .position()
+            .inverse_transform_vector(&manifold.data.normal);
+
+        if let Some(normal) = edges.corrected_normal(manifold.subshape1, &local_normal) {
+            manifold.data.normal = co1.position() * normal;
+            return true;
+        }
+    } else if let Some(edges) = co2.trimesh_internal_edges.as_deref() {
+        // The normal points outside of the first collider.
+        let local_normal = co2
+            .position()
+            .inverse_transform_vector(&-manifold.data.normal);
+
+        if let Some(normal) = edges.corrected_normal(manifold.subshape2, &local_normal) {
+            manifold.data.normal = -(co2.position() * normal);
+            return true;
+        }
+    }
+
+    false
+}
@@ -0,0 +1,168 @@
+#[cfg(feature = "dim2")]
+use crate::geometry::ConvexPolygon;
+use crate::geometry::{
+    Ball, Capsule, Compound, Cuboid, HalfSpace, HeightField, Polyline, Segment, Shape, ShapeType,
+    TriMesh, Triangle,
+};
+#[cfg(feature = "dim3")]
+use crate::geometry::{Cone, ConvexPolyhedron, Cylinder};
+
+/// A typed view of the shape of a collider, for pattern matching.
+///
+/// Each variant gives access to the parameters of the shape by reference. Shapes without
+/// a dedicated variant, e.g., round shapes or user-defined shapes, are exposed as
+/// `TypedShape::Custom`.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(all(feature = "dim2", feature = "f32"))]
+/// # use rapier2d::{geometry::*, math::*};
+/// # #[cfg(all(feature = "dim2", feature = "f64"))]
+/// # use rapier2d_f64::{geometry::*, math::*};
+/// # #[cfg(all(feature = "dim3", feature = "f32"))]
+/// # use rapier3d::{geometry::*, math::*};
+/// # #[cfg(all(feature = "dim3", feature = "f64"))]
+/// # use rapier3d_f64::{geometry::*, math::*};
+/// # use alloc::sync::Arc;
+/// let cuboid = SharedShape(Arc::new(Cuboid::new(Vector::repeat(1.0))));
+/// let capsule = SharedShape::capsule(Point::origin(), Point::from(Vector::y()), 0.2);
+/// let collider = ColliderBuilder::compound(vec![
+///     (Isometry::identity(), SharedShape::ball(0.5)),
+///     (Isometry::identity(), cuboid),
+///     (Isometry::identity(), capsule),
+/// ])
+/// .build();
+///
+/// if let TypedShape::Compound(compound) = collider.as_typed_shape() {
+///     for (_, part) in compound.shapes() {
+///         match TypedShape::from_shape(&*part.0) {
+///             TypedShape::Ball(ball) => println!("Ball with radius {}", ball.radius),
+///             TypedShape::Cuboid(cuboid) => {
+///                 println!("Cuboid with half-extents {:?}", cuboid.half_extents)
+///             }
+///             TypedShape::Capsule(capsule) => println!(
+///                 "Capsule with radius {} and length {}",
+///                 capsule.radius,
+///                 capsule.segment.length()
+///             ),
+///             other => println!("Other shape: {:?}", other.shape_type()),
+///         }
+///     }
+/// }
+/// ```
+#[derive(Copy, Clone)]
+pub enum TypedShape<'a> {
+    /// A ball shape.
+    Ball(&'a Ball),
+    /// A cuboid shape.
+    Cuboid(&'a Cuboid),
+    /// A capsule shape.
+    Capsule(&'a Capsule),
+    /// A segment shape.
+    Segment(&'a Segment),
+    /// A triangle shape.
+    Triangle(&'a Triangle),
+    /// A cylinder shape.
+    #[cfg(feature = "dim3")]
+    Cylinder(&'a Cylinder),
+    /// A cone shape.
+    #[cfg(feature = "dim3")]
+    Cone(&'a Cone),
+    /// A convex polygon, e.g., built by `ColliderBuilder::convex_hull`.
+    #[cfg(feature = "dim2")]
+    ConvexHull(&'a ConvexPolygon),
+    /// A convex polyhedron, e.g., built by `ColliderBuilder::convex_hull`.
+    #[cfg(feature = "dim3")]
+    ConvexHull(&'a ConvexPolyhedron),
+    /// A polyline shape.
+    Polyline(&'a Polyline),
+    /// A triangle mesh shape.
+    TriMesh(&'a TriMesh),
+    /// A heightfield shape.
+    HeightField(&'a HeightField),
+    /// A half-space shape.
+    HalfSpace(&'a HalfSpace),
+    /// A compound shape, made of several other shapes.
+    Compound(&'a Compound),
+    /// Any other shape, e.g., a round shape or a user-defined shape.
+    Custom(&'a dyn Shape),
+}
+
+impl<'a> TypedShape<'a> {
+    /// The typed view of the given shape.
+    pub fn from_shape(shape: &'a dyn Shape) -> Self {
+        if let Some(s) = shape.as_shape::<Ball>() {
+            TypedShape::Ball(s)
+        } else if let Some(s) = shape.as_shape::<Cuboid>() {
+            TypedShape::Cuboid(s)
+        } else if let Some(s) = shape.as_shape::<Capsule>() {
+            TypedShape::Capsule(s)
+        } else if let Some(s) = shape.as_shape::<Segment>() {
+            TypedShape::Segment(s)
+        } else if let Some(s) = shape.as_shape::<Triangle>() {
+            TypedShape::Triangle(s)
+        } else if let Some(s) = shape.as_shape::<Polyline>() {
+            TypedShape::Polyline(s)
+        } else if let Some(s) = shape.as_shape::<TriMesh>() {
+            TypedShape::TriMesh(s)
+        } else if let Some(s) = shape.as_shape::<HeightField>() {
+            TypedShape::HeightField(s)
+        } else if let Some(s) = shape.as_shape::<HalfSpace>() {
+            TypedShape::HalfSpace(s)
+        } else if let Some(s) = shape.as_shape::<Compound>() {
+            TypedShape::Compound(s)
+        } else {
+            Self::from_dim_specific_shape(shape)
+        }
+    }
+
+    #[cfg(feature = "dim2")]
+    fn from_dim_specific_shape(shape: &'a dyn Shape) -> Self {
+        if let Some(s) = shape.as_shape::<ConvexPolygon>() {
+            TypedShape::ConvexHull(s)
+        } else {
+            TypedShape::Custom(shape)
+        }
+    }
+
+    #[cfg(feature = "dim3")]
+    fn from_dim_specific_shape(shape: &'a dyn Shape) -> Self {
+        if let Some(s) = shape.as_shape::<Cylinder>() {
+            TypedShape::Cylinder(s)
+        } else if let Some(s) = shape.as_shape::<Cone>() {
+            TypedShape::Cone(s)
+        } else if let Some(s) = shape.as_shape::<ConvexPolyhedron>() {
+            TypedShape::ConvexHull(s)
+        } else {
+            TypedShape::Custom(shape)
+        }
+    }
+
+    /// The shape this typed view refers to.
+    pub fn as_shape(&self) -> &'a dyn Shape {
+        match *self {
+            TypedShape::Ball(s) => s,
+            TypedShape::Cuboid(s) => s,
+            TypedShape::Capsule(s) => s,
+            TypedShape::Segment(s) => s,
+            TypedShape::Triangle(s) => s,
+            #[cfg(feature = "dim3")]
+            TypedShape::Cylinder(s) => s,
+            #[cfg(feature = "dim3")]
+            TypedShape::Cone(s) => s,
+            TypedShape::ConvexHull(s) => s,
+            TypedShape::Polyline(s) => s,
+            TypedShape::TriMesh(s) => s,
+            TypedShape::HeightField(s) => s,
+            TypedShape::HalfSpace(s) => s,
+            TypedShape::Compound(s) => s,
+            TypedShape::Custom(s) => s,
+        }
+    }
+
+    /// The type of the shape this typed view refers to.
+    pub fn shape_type(&self) -> ShapeType {
+        self.as_shape().shape_type()
+    }
+}
@@ -0,0 +1,167 @@
+use crate::data::HashMap;
+use crate::geometry::{ContactData, ContactManifold};
+use crate::math::Real;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+#[derive(Copy, Clone, Debug)]
+struct CachedContact {
+    subshape1: u32,
+    subshape2: u32,
+    fid1: u32,
+    fid2: u32,
+    relative_dominance: i16,
+    data: ContactData,
+}
+
+#[derive(Clone, Debug)]
+struct CacheEntry {
+    timestamp: u64,
+    contacts: Vec<CachedContact>,
+}
+
+/// A cache of the contact impulses of the destroyed contact pairs, used to warmstart the
+/// contacts of new pairs between colliders with the same warmstart cache keys.
+///
+/// The entries are keyed by the `Collider::warmstart_cache_key` of both colliders of a pair.
+/// When the cache is full, the least recently stored entry is evicted.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct WarmstartCache {
+    capacity: usize,
+    timestamp: u64,
+    entries: HashMap<(u64, u64), CacheEntry>,
+    // The keys of the entries, ordered by increasing timestamp.
+    lru: BTreeMap<u64, (u64, u64)>,
+}
+
+impl WarmstartCache {
+    pub fn is_enabled(&self) -> bool {
+        self.capacity != 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    /// Stores the contacts of the manifolds of a destroyed pair between the colliders with the
+    /// warmstart cache keys `key1` and `key2`.
+    pub fn store(&mut self, key1: u64, key2: u64, manifolds: &[ContactManifold]) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let contacts: Vec<_> = manifolds
+            .iter()
+            .flat_map(|manifold| {
+                manifold.points.iter().map(move |contact| CachedContact {
+                    subshape1: manifold.subshape1,
+                    subshape2: manifold.subshape2,
+                    fid1: contact.fid1,
+                    fid2: contact.fid2,
+                    relative_dominance: manifold.data.relative_dominance,
+                    data: contact.data,
+                })
+            })
+            .filter(|contact| contact.data.impulse != 0.0)
+            .collect();
+
+        if let Some(old_entry) = self.entries.remove(&(key1, key2)) {
+            let _ = self.lru.remove(&old_entry.timestamp);
+        }
+
+        if contacts.is_empty() {
+            return;
+        }
+
+        self.timestamp += 1;
+        let _ = self.lru.insert(self.timestamp, (key1, key2));
+        let _ = self.entries.insert(
+            (key1, key2),
+            CacheEntry {
+                timestamp: self.timestamp,
+                contacts,
+            },
+        );
+        self.evict();
+    }
+
+    /// Initializes the contacts of the manifolds of a new pair between the colliders with the
+    /// warmstart cache keys `key1` and `key2` with the cached contacts matching their features.
+    pub fn apply(&self, key1: u64, key2: u64, manifolds: &mut [ContactManifold]) {
+        if let Some(entry) = self.entries.get(&(key1, key2)) {
+            for cached in &entry.contacts {
+                for manifold in manifolds
+                    .iter_mut()
+                    .filter(|m| m.subshape1 == cached.subshape1 && m.subshape2 == cached.subshape2)
+                {
+                    for contact in &mut manifold.points {
+                        if contact.fid1 == cached.fid1 && contact.fid2 == cached.fid2 {
+                            contact.data = cached.data;
+                        }
+                    }
+                }
+            }
+        } else if let Some(entry) = self.entries.get(&(key2, key1)) {
+            // The colliders are in the reverse order, so the friction anchors are swapped.
+            for cached in &entry.contacts {
+                for manifold in manifolds
+                    .iter_mut()
+                    .filter(|m| m.subshape1 == cached.subshape2 && m.subshape2 == cached.subshape1)
+                {
+                    for contact in &mut manifold.points {
+                        if contact.fid1 == cached.fid2 && contact.fid2 == cached.fid1 {
+                            contact.data = ContactData {
+                                tangent_impulse: reversed_tangent_impulse(cached),
+                                friction_anchor: cached
+                                    .data
+                                    .friction_anchor
+                                    .map(|(a1, a2)| (a2, a1)),
+                                ..cached.data
+                            };
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn evict(&mut self) {
+        while self.entries.len() > self.capacity {
+            let oldest = self.lru.keys().next().copied();
+
+            if let Some(key) = oldest.and_then(|timestamp| self.lru.remove(&timestamp)) {
+                let _ = self.entries.remove(&key);
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+// The tangent impulse of the cached contact, for the pair with the reverse order.
+#[cfg(feature = "dim2")]
+fn reversed_tangent_impulse(cached: &CachedContact) -> Real {
+    // Either both the tangent and the body the impulse is applied to are reversed, or none of
+    // them for the contacts with a dominant body.
+    cached.data.tangent_impulse
+}
+
+// The tangent impulse of the cached contact, for the pair with the reverse order.
+#[cfg(feature = "dim3")]
+fn reversed_tangent_impulse(cached: &CachedContact) -> na::Vector2<Real> {
+    let impulse = cached.data.tangent_impulse;
+
+    if cached.relative_dominance != 0 {
+        // The contacts with a dominant body are always solved wrt. this body.
+        impulse
+    } else {
+        // The impulse is applied to the other body, and only the bitangent is negated (see
+        // `compute_tangent_contact_directions`).
+        na::Vector2::new(-impulse.x, impulse.y)
+    }
+}
@@ -9,6 +9,9 @@
 //! are compliant with the IEEE 754-2008 floating point standard.
 
 #![warn(missing_docs)]
+#![cfg_attr(feature = "no_std", no_std)]
+
+extern crate alloc;
 
 #[cfg(all(feature = "dim2", feature = "f32"))]
 pub extern crate parry2d as parry;
@@ -19,6 +22,7 @@ pub extern crate parry3d as parry;
 #[cfg(all(feature = "dim3", feature = "f64"))]
 pub extern crate parry3d_f64 as parry;
 
+#[cfg(not(feature = "no_std"))]
 pub extern crate crossbeam;
 pub extern crate nalgebra as na;
 #[cfg(feature = "serde")]
@@ -36,11 +40,15 @@ pub use rayon;
     not(feature = "simd-stable"),
     not(feature = "simd-nightly")
 ))]
-std::compile_error!("The `simd-is-enabled` feature should not be enabled explicitly. Please enable the `simd-stable` or the `simd-nightly` feature instead.");
+core::compile_error!("The `simd-is-enabled` feature should not be enabled explicitly. Please enable the `simd-stable` or the `simd-nightly` feature instead.");
 #[cfg(all(feature = "simd-is-enabled", feature = "enhanced-determinism"))]
-std::compile_error!(
+core::compile_error!(
     "SIMD cannot be enabled when the `enhanced-determinism` feature is also enabled."
 );
+#[cfg(all(feature = "no_std", feature = "parallel"))]
+core::compile_error!(
+    "The `parallel` feature requires `std` and cannot be enabled with the `no_std` feature."
+);
 
 macro_rules! enable_flush_to_zero(
     () => {
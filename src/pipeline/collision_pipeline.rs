@@ -4,6 +4,7 @@ use crate::dynamics::{JointSet, RigidBodySet};
 use crate::geometry::{BroadPhase, BroadPhasePairEvent, ColliderPair, ColliderSet, NarrowPhase};
 use crate::math::Real;
 use crate::pipeline::{EventHandler, PhysicsHooks};
+use alloc::vec::Vec;
 
 /// The collision pipeline, responsible for performing collision detection between colliders.
 ///
@@ -54,20 +55,23 @@ impl CollisionPipeline {
 
         narrow_phase.handle_user_changes(colliders, bodies, events);
         narrow_phase.register_pairs(colliders, bodies, &self.broad_phase_events, events);
-        narrow_phase.compute_contacts(prediction_distance, bodies, colliders, hooks, events);
+        narrow_phase.compute_contacts(prediction_distance, None, bodies, colliders, hooks, events);
         narrow_phase.compute_intersections(bodies, colliders, hooks, events);
+        narrow_phase.update_stats();
 
         bodies.update_active_set_with_contacts(
             colliders,
             narrow_phase,
             self.empty_joints.joint_graph(),
             128,
+            None,
+            true,
         );
 
         // Update colliders positions and kinematic bodies positions.
-        bodies.foreach_active_body_mut_internal(|_, rb| {
+        bodies.foreach_active_body_mut_internal(|handle, rb| {
             rb.position = rb.next_position;
-            rb.update_colliders_positions(colliders);
+            rb.update_colliders_positions(handle, colliders);
 
             for handle in &rb.colliders {
                 let collider = colliders.get_mut_internal(*handle).unwrap();
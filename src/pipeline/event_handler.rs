@@ -1,6 +1,26 @@
-use crate::geometry::{ContactEvent, IntersectionEvent};
+use crate::geometry::{AabbOverlapEvent, ContactEvent, ContactEventData, IntersectionEvent};
+use crate::pipeline::{
+    BodyOutOfBoundsEvent, NonFiniteEvent, PipelineLimitsReport, VelocityClampEvent,
+};
+#[cfg(not(feature = "no_std"))]
 use crossbeam::channel::Sender;
 
+bitflags::bitflags! {
+    #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+    /// Flags selecting the optional events generated for a given collider.
+    pub struct ActiveEvents: u32 {
+        /// If set, an `AabbOverlapEvent` is generated whenever the broad-phase AABB of this
+        /// collider starts or stops overlapping the broad-phase AABB of another collider.
+        const PROXIMITY_AABB_EVENTS = 1 << 0;
+    }
+}
+
+impl Default for ActiveEvents {
+    fn default() -> Self {
+        ActiveEvents::empty()
+    }
+}
+
 /// Trait implemented by structures responsible for handling events generated by the physics engine.
 ///
 /// Implementors of this trait will typically collect these events for future processing.
@@ -14,6 +34,41 @@ pub trait EventHandler: Send + Sync {
     /// A contact event is emitted when two collider start or stop touching, independently from the
     /// number of contact points involved.
     fn handle_contact_event(&self, event: ContactEvent);
+    /// Handle a contact event together with its data.
+    ///
+    /// This is called by the narrow-phase for each contact event. The `data.user_tags1` are the
+    /// user tags of the first collider of the event, and `data.user_tags2` those of the second
+    /// one. The default implementation ignores the data and calls `Self::handle_contact_event`.
+    fn handle_contact_event_with_data(&self, event: ContactEvent, _data: ContactEventData) {
+        self.handle_contact_event(event)
+    }
+    /// Handle a non-finite event.
+    ///
+    /// A non-finite event is emitted when a rigid-body with a non-finite state (e.g. a NaN velocity)
+    /// is detected and `IntegrationParameters::on_non_finite` isn't `OnNonFinite::Panic`.
+    fn handle_non_finite_event(&self, _event: NonFiniteEvent) {}
+    /// Handle a pipeline limits report.
+    ///
+    /// This report is emitted at the end of each timestep during which one of the
+    /// `IntegrationParameters::pipeline_limits` was exceeded.
+    fn handle_pipeline_limits_report(&self, _report: PipelineLimitsReport) {}
+    /// Handle a velocity clamp event.
+    ///
+    /// A velocity clamp event is emitted when the velocity of a rigid-body exceeds
+    /// `IntegrationParameters::max_linear_velocity` or `IntegrationParameters::max_angular_velocity`
+    /// and is clamped.
+    fn handle_velocity_clamp_event(&self, _event: VelocityClampEvent) {}
+    /// Handle an AABB overlap event.
+    ///
+    /// This event is emitted by the broad-phase, before any narrow-phase filtering, when the AABBs
+    /// of two colliders start or stop overlapping and one of them has the
+    /// `ActiveEvents::PROXIMITY_AABB_EVENTS` flag set.
+    fn handle_aabb_overlap_event(&self, _event: AabbOverlapEvent) {}
+    /// Handle a body out of bounds event.
+    ///
+    /// This event is emitted by `PipelineBounds::handle_out_of_bounds_bodies` when the center of
+    /// mass of an active dynamic rigid-body is outside of the bounds.
+    fn handle_body_out_of_bounds_event(&self, _event: BodyOutOfBoundsEvent) {}
 }
 
 impl EventHandler for () {
@@ -22,11 +77,13 @@ impl EventHandler for () {
 }
 
 /// A physics event handler that collects events into a crossbeam channel.
+#[cfg(not(feature = "no_std"))]
 pub struct ChannelEventCollector {
     intersection_event_sender: Sender<IntersectionEvent>,
     contact_event_sender: Sender<ContactEvent>,
 }
 
+#[cfg(not(feature = "no_std"))]
 impl ChannelEventCollector {
     /// Initialize a new physics event handler from crossbeam channel senders.
     pub fn new(
@@ -40,6 +97,7 @@ impl ChannelEventCollector {
     }
 }
 
+#[cfg(not(feature = "no_std"))]
 impl EventHandler for ChannelEventCollector {
     fn handle_intersection_event(&self, event: IntersectionEvent) {
         let _ = self.intersection_event_sender.send(event);
@@ -1,15 +1,35 @@
 //! Structure for combining the various physics components to perform an actual simulation.
 
 pub use collision_pipeline::CollisionPipeline;
-pub use event_handler::{ChannelEventCollector, EventHandler};
+#[cfg(not(feature = "no_std"))]
+pub use event_handler::ChannelEventCollector;
+pub use event_handler::{ActiveEvents, EventHandler};
+pub use non_finite::{NonFiniteEvent, NonFiniteField, OnNonFinite};
 pub use physics_hooks::{
-    ContactModificationContext, PairFilterContext, PhysicsHooks, PhysicsHooksFlags,
+    ContactModificationContext, PairFilterContext, PairFilterResult, PhysicsHooks,
+    PhysicsHooksFlags,
 };
 pub use physics_pipeline::PhysicsPipeline;
+pub use pipeline_limits::{OnLimitExceeded, PipelineLimits, PipelineLimitsReport};
+pub use pipeline_stats::PipelineStats;
 pub use query_pipeline::{QueryPipeline, QueryPipelineMode};
+pub use replay::{ReplayError, ReplayRecorder, ReplaySnapshot, Replayer};
+pub use simulation_frame::SimulationFrame;
+pub use velocity_clamp::VelocityClampEvent;
+pub use world_bounds::{BodyOutOfBoundsEvent, OnOutOfBounds, PipelineBounds};
+
+pub(crate) use physics_hooks::limit_normal_rotation;
 
 mod collision_pipeline;
 mod event_handler;
+mod non_finite;
 mod physics_hooks;
 mod physics_pipeline;
+mod pipeline_limits;
+mod pipeline_stats;
+mod position_budget;
 mod query_pipeline;
+mod replay;
+mod simulation_frame;
+mod velocity_clamp;
+mod world_bounds;
@@ -0,0 +1,175 @@
+use crate::dynamics::{RigidBody, RigidBodyHandle};
+use crate::math::{AngVector, Isometry, Real, Vector};
+use crate::pipeline::EventHandler;
+
+/// What the physics pipeline does when it detects a rigid-body with a non-finite state.
+///
+/// The default is `OnNonFinite::ResetVelocityAndReport`, which keeps the simulation running.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub enum OnNonFinite {
+    /// Panic with a message identifying the offending rigid-body.
+    Panic,
+    /// Freeze the offending rigid-body (see `RigidBody::set_frozen`) and report it to the event handler.
+    ///
+    /// The other bodies of its island keep being simulated.
+    FreezeBodyAndReport,
+    /// Reset the velocities of the offending rigid-body to zero and report it to the event handler.
+    ResetVelocityAndReport,
+}
+
+impl Default for OnNonFinite {
+    fn default() -> Self {
+        OnNonFinite::ResetVelocityAndReport
+    }
+}
+
+/// The part of the state of a rigid-body detected as non-finite.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub enum NonFiniteField {
+    /// The position of the rigid-body.
+    Position,
+    /// The linear velocity of the rigid-body.
+    LinearVelocity,
+    /// The angular velocity of the rigid-body.
+    AngularVelocity,
+    /// The force applied to the rigid-body.
+    Force,
+    /// The torque applied to the rigid-body.
+    Torque,
+    /// The mass properties of the rigid-body.
+    ///
+    /// This is only checked if the `debug-validation` feature is enabled.
+    MassProperties,
+}
+
+/// Event emitted when a rigid-body with a non-finite state is detected.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct NonFiniteEvent {
+    /// The rigid-body with a non-finite state.
+    pub body: RigidBodyHandle,
+    /// The non-finite part of the state of the rigid-body.
+    pub field: NonFiniteField,
+}
+
+fn is_vector_finite(v: &Vector<Real>) -> bool {
+    v.iter().all(|e| e.is_finite())
+}
+
+#[cfg(feature = "dim2")]
+fn is_angvector_finite(v: &AngVector<Real>) -> bool {
+    v.is_finite()
+}
+
+#[cfg(feature = "dim3")]
+fn is_angvector_finite(v: &AngVector<Real>) -> bool {
+    is_vector_finite(v)
+}
+
+#[cfg(feature = "dim2")]
+fn is_isometry_finite(pos: &Isometry<Real>) -> bool {
+    is_vector_finite(&pos.translation.vector)
+        && pos.rotation.re.is_finite()
+        && pos.rotation.im.is_finite()
+}
+
+#[cfg(feature = "dim3")]
+fn is_isometry_finite(pos: &Isometry<Real>) -> bool {
+    is_vector_finite(&pos.translation.vector) && pos.rotation.coords.iter().all(|e| e.is_finite())
+}
+
+#[cfg(feature = "debug-validation")]
+fn are_mass_properties_finite(rb: &RigidBody) -> bool {
+    let inertia = &rb.effective_world_inv_inertia_sqrt;
+    #[cfg(feature = "dim2")]
+    let inertia_is_finite = inertia.is_finite();
+    #[cfg(feature = "dim3")]
+    let inertia_is_finite = [
+        inertia.m11,
+        inertia.m12,
+        inertia.m13,
+        inertia.m22,
+        inertia.m23,
+        inertia.m33,
+    ]
+    .iter()
+    .all(|e| e.is_finite());
+
+    is_vector_finite(&rb.effective_inv_mass)
+        && is_vector_finite(&rb.world_com.coords)
+        && inertia_is_finite
+}
+
+/// Finds the first non-finite part of the state of a rigid-body.
+///
+/// If `check_forces` is `false`, the forces and torques are only checked when the
+/// `debug-validation` feature is enabled.
+pub(crate) fn find_non_finite_field(rb: &RigidBody, check_forces: bool) -> Option<NonFiniteField> {
+    let check_forces = check_forces || cfg!(feature = "debug-validation");
+
+    if !is_isometry_finite(&rb.position) || !is_isometry_finite(&rb.next_position) {
+        Some(NonFiniteField::Position)
+    } else if !is_vector_finite(&rb.linvel) {
+        Some(NonFiniteField::LinearVelocity)
+    } else if !is_angvector_finite(&rb.angvel) {
+        Some(NonFiniteField::AngularVelocity)
+    } else if check_forces && !is_vector_finite(&rb.force) {
+        Some(NonFiniteField::Force)
+    } else if check_forces && !is_angvector_finite(&rb.torque) {
+        Some(NonFiniteField::Torque)
+    } else {
+        #[cfg(feature = "debug-validation")]
+        {
+            if !are_mass_properties_finite(rb) {
+                return Some(NonFiniteField::MassProperties);
+            }
+        }
+
+        None
+    }
+}
+
+/// Applies the `policy` to a rigid-body found to have a non-finite state.
+pub(crate) fn handle_non_finite_body(
+    policy: OnNonFinite,
+    handle: RigidBodyHandle,
+    rb: &mut RigidBody,
+    field: NonFiniteField,
+    events: &dyn EventHandler,
+) {
+    if policy == OnNonFinite::Panic {
+        panic!(
+            "Non-finite {:?} detected on the rigid-body {:?}.",
+            field, handle
+        );
+    }
+
+    // Go back to the last known finite position.
+    if !is_isometry_finite(&rb.position) {
+        rb.position = if is_isometry_finite(&rb.next_position) {
+            rb.next_position
+        } else {
+            Isometry::identity()
+        };
+    }
+
+    rb.next_position = rb.position;
+
+    match policy {
+        OnNonFinite::FreezeBodyAndReport => rb.set_frozen(true),
+        OnNonFinite::ResetVelocityAndReport | OnNonFinite::Panic => {
+            rb.linvel = na::zero();
+            rb.angvel = na::zero();
+            rb.force = na::zero();
+            rb.torque = na::zero();
+            rb.update_world_mass_properties();
+        }
+    }
+
+    events.handle_non_finite_event(NonFiniteEvent {
+        body: handle,
+        field,
+    });
+}
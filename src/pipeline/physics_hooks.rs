@@ -1,9 +1,15 @@
-use crate::dynamics::RigidBody;
-use crate::geometry::{Collider, ColliderHandle, ContactManifold, SolverContact, SolverFlags};
+use crate::dynamics::{RigidBody, RigidBodyHandle, RigidBodySet};
+use crate::geometry::{
+    Collider, ColliderHandle, ColliderUserTags, ContactManifold, SolverContact, SolverFlags,
+};
 use crate::math::{Real, Vector};
-use na::ComplexField;
+use alloc::vec::Vec;
+use na::{ComplexField, Unit};
 
 /// Context given to custom collision filters to filter-out collisions.
+///
+/// The pairs excluded with `ColliderSet::add_exclusion` never reach the filters. The exclusions
+/// of the colliders involved in other pairs can be read with `Collider::exclusions`.
 pub struct PairFilterContext<'a> {
     /// The first rigid-body involved in the potential collision.
     pub rigid_body1: &'a RigidBody,
@@ -19,6 +25,41 @@ pub struct PairFilterContext<'a> {
     pub collider2: &'a Collider,
 }
 
+impl<'a> PairFilterContext<'a> {
+    /// The user tags of the first collider involved in the potential collision.
+    pub fn user_tags1(&self) -> ColliderUserTags {
+        self.collider1.user_tags
+    }
+
+    /// The user tags of the second collider involved in the potential collision.
+    pub fn user_tags2(&self) -> ColliderUserTags {
+        self.collider2.user_tags
+    }
+}
+
+/// The result of a contact pair filter accepting a contact pair.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PairFilterResult {
+    /// The flags configuring the contact manifolds of the pair.
+    pub solver_flags: SolverFlags,
+    /// The prediction distance to use for this pair instead of
+    /// `IntegrationParameters::prediction_distance`, if any.
+    ///
+    /// Contacts closer than this distance are kept by the narrow-phase and given to the
+    /// constraints solver as speculative contacts. A large prediction distance gives smoother
+    /// results to fast-moving bodies, while a small one makes the pair cheaper to solve.
+    pub prediction_distance: Option<Real>,
+}
+
+impl From<SolverFlags> for PairFilterResult {
+    fn from(solver_flags: SolverFlags) -> Self {
+        Self {
+            solver_flags,
+            prediction_distance: None,
+        }
+    }
+}
+
 /// Context given to custom contact modifiers to modify the contacts seen by the constraints solver.
 pub struct ContactModificationContext<'a> {
     /// The first rigid-body involved in the potential collision.
@@ -38,7 +79,12 @@ pub struct ContactModificationContext<'a> {
     /// The solver contacts that can be modified.
     pub solver_contacts: &'a mut Vec<SolverContact>,
     /// The contact normal that can be modified.
+    ///
+    /// The normal computed by the narrow-phase remains available as `manifold.data.raw_normal`.
     pub normal: &'a mut Vector<Real>,
+    /// The contact normal reported by this manifold at its previous update, if it had any
+    /// solver contact then.
+    pub prev_normal: Option<Vector<Real>>,
     /// User-defined data attached to the manifold.
     // NOTE: we keep this a &'a mut u32 to emphasize the
     // fact that this can be modified.
@@ -46,6 +92,25 @@ pub struct ContactModificationContext<'a> {
 }
 
 impl<'a> ContactModificationContext<'a> {
+    /// Limits the rotation of `self.normal` since the previous update of this manifold to
+    /// `max_rotation_per_step` radians.
+    ///
+    /// The normal is slerped from `self.prev_normal` toward its current value, so it doesn't
+    /// jump between timesteps, e.g., when a capsule moves from the face to the edge of a box.
+    /// This does nothing if the manifold didn't have any solver contact at its previous update,
+    /// or if the normal was reversed.
+    ///
+    /// This is a smoothing, not a correction: the constraints solver uses the smoothed normal
+    /// while the contact distances are still measured along the raw normal. So the contact forces
+    /// no longer match the geometry exactly, and can add or remove some energy, e.g., slowing down
+    /// a body rolling over an edge, or slightly lifting it. The raw normal remains available as
+    /// `self.manifold.data.raw_normal`.
+    pub fn smooth_normal(&mut self, max_rotation_per_step: Real) {
+        if let Some(prev_normal) = &self.prev_normal {
+            *self.normal = limit_normal_rotation(prev_normal, self.normal, max_rotation_per_step);
+        }
+    }
+
     /// Helper function to update `self` to emulate a oneway-platform.
     ///
     /// The "oneway" behavior will only allow contacts between two colliders
@@ -115,6 +180,31 @@ impl<'a> ContactModificationContext<'a> {
     }
 }
 
+/// Rotates `normal` toward `prev_normal` so the angle between them doesn't exceed `max_rotation`.
+pub(crate) fn limit_normal_rotation(
+    prev_normal: &Vector<Real>,
+    normal: &Vector<Real>,
+    max_rotation: Real,
+) -> Vector<Real> {
+    let max_rotation = max_rotation.max(0.0);
+    // NOTE: the angle is zero if one of the normals is zero.
+    let angle = prev_normal.angle(normal);
+
+    if angle <= max_rotation {
+        return *normal;
+    }
+
+    // NOTE: there is no preferred rotation direction between opposite normals.
+    Unit::new_normalize(*prev_normal)
+        .try_slerp(
+            &Unit::new_normalize(*normal),
+            max_rotation / angle,
+            Real::EPSILON,
+        )
+        .map(|n| n.into_inner())
+        .unwrap_or(*normal)
+}
+
 bitflags::bitflags! {
     #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
     /// Flags affecting the behavior of the constraints solver for a given contact manifold.
@@ -125,6 +215,8 @@ bitflags::bitflags! {
         const FILTER_INTERSECTION_PAIR = 0b0010;
         /// If set, Rapier will call `PhysicsHooks::modify_solver_contact` whenever relevant.
         const MODIFY_SOLVER_CONTACTS = 0b0100;
+        /// If set, Rapier will call `PhysicsHooks::on_island_solved` whenever relevant.
+        const ON_ISLAND_SOLVED = 0b1000;
     }
 }
 
@@ -152,11 +244,16 @@ pub trait PhysicsHooks: Send + Sync {
     /// not compute any contact manifolds for it.
     /// If this returns `Some`, then the narrow-phase will compute contact manifolds for
     /// this pair of colliders, and configure them with the returned solver flags. For
-    /// example, if this returns `Some(SolverFlags::COMPUTE_IMPULSES)` then the contacts
+    /// example, if this returns `Some(SolverFlags::COMPUTE_IMPULSES.into())` then the contacts
     /// will be taken into account by the constraints solver. If this returns
-    /// `Some(SolverFlags::empty())` then the constraints solver will ignore these
+    /// `Some(SolverFlags::empty().into())` then the constraints solver will ignore these
     /// contacts.
-    fn filter_contact_pair(&self, _context: &PairFilterContext) -> Option<SolverFlags> {
+    ///
+    /// The returned `PairFilterResult::prediction_distance`, if set, replaces the global
+    /// prediction distance for this pair only. Note that the broad-phase still relies on
+    /// the global prediction distance, so a larger per-pair prediction distance only takes
+    /// effect for pairs of colliders with intersecting AABBs.
+    fn filter_contact_pair(&self, _context: &PairFilterContext) -> Option<PairFilterResult> {
         None
     }
 
@@ -198,8 +295,8 @@ pub trait PhysicsHooks: Send + Sync {
     ///
     /// Modifying the solver contacts allow you to achieve various effects, including:
     /// - Simulating conveyor belts by setting the `surface_velocity` of a solver contact.
-    /// - Simulating shapes with multiply materials by modifying the friction and restitution
-    ///   coefficient depending of the features in contacts.
+    /// - Simulating shapes with multiply materials by modifying the friction, restitution, and
+    ///   rolling resistance coefficients depending of the features in contacts.
     /// - Simulating one-way platforms depending on the contact normal.
     ///
     /// Each contact manifold is given a `u32` user-defined data that is persistent between
@@ -208,6 +305,27 @@ pub trait PhysicsHooks: Send + Sync {
     ///
     /// The world-space contact normal can be modified in `context.normal`.
     fn modify_solver_contacts(&self, _context: &mut ContactModificationContext) {}
+
+    /// Notifies that the positions of all the bodies of an island have been solved.
+    ///
+    /// Note that this method will only be called if `self.active_hooks()`
+    /// contains the `PhysicsHooksFlags::ON_ISLAND_SOLVED` flags.
+    ///
+    /// This is called once per island, during the last CCD substep of the timestep, right
+    /// after the position solver wrote its results back to the rigid-bodies of that island.
+    /// The `handles` slice contains the handles of all the active rigid-bodies of the island.
+    /// At this point, the final pose of each of these bodies is given by
+    /// `RigidBody::next_position`, and their velocities are final.
+    ///
+    /// With the `parallel` feature enabled, this is called on the thread running the timestep,
+    /// only once the positions of all the islands have been solved.
+    fn on_island_solved(
+        &self,
+        _island_id: usize,
+        _handles: &[RigidBodyHandle],
+        _bodies: &RigidBodySet,
+    ) {
+    }
 }
 
 impl PhysicsHooks for () {
@@ -215,7 +333,7 @@ impl PhysicsHooks for () {
         PhysicsHooksFlags::empty()
     }
 
-    fn filter_contact_pair(&self, _: &PairFilterContext) -> Option<SolverFlags> {
+    fn filter_contact_pair(&self, _: &PairFilterContext) -> Option<PairFilterResult> {
         None
     }
 
@@ -3,14 +3,28 @@
 use crate::counters::Counters;
 #[cfg(not(feature = "parallel"))]
 use crate::dynamics::IslandSolver;
-use crate::dynamics::{CCDSolver, IntegrationParameters, JointSet, RigidBodySet};
+use crate::dynamics::{
+    CCDSolver, IntegrationParameters, JointSet, RigidBody, RigidBodyHandle, RigidBodySet,
+    SolverStats, UserConstraintSet,
+};
 #[cfg(feature = "parallel")]
-use crate::dynamics::{JointGraphEdge, ParallelIslandSolver as IslandSolver};
+use crate::dynamics::{
+    JointGraphEdge, ParallelIslandSolver as IslandSolver, PositionSolveBudget,
+};
 use crate::geometry::{
     BroadPhase, BroadPhasePairEvent, ColliderPair, ColliderSet, ContactManifoldIndex, NarrowPhase,
+    AABB,
 };
 use crate::math::{Real, Vector};
-use crate::pipeline::{EventHandler, PhysicsHooks};
+use crate::pipeline::non_finite::{find_non_finite_field, handle_non_finite_body};
+use crate::pipeline::pipeline_limits::drop_excess_pairs;
+use crate::pipeline::position_budget::{position_solve_order, PositionBudgetTracker};
+use crate::pipeline::velocity_clamp::clamp_body_velocities;
+use crate::pipeline::{
+    EventHandler, OnLimitExceeded, PhysicsHooks, PhysicsHooksFlags, PipelineLimits,
+    PipelineLimitsReport, PipelineStats, SimulationFrame,
+};
+use alloc::vec::Vec;
 
 /// The physics pipeline, responsible for stepping the whole physics simulation.
 ///
@@ -25,6 +39,8 @@ use crate::pipeline::{EventHandler, PhysicsHooks};
 pub struct PhysicsPipeline {
     /// Counters used for benchmarking only.
     pub counters: Counters,
+    limits_report: Option<PipelineLimitsReport>,
+    solver_stats: SolverStats,
     manifold_indices: Vec<Vec<ContactManifoldIndex>>,
     joint_constraint_indices: Vec<Vec<ContactManifoldIndex>>,
     broadphase_collider_pairs: Vec<ColliderPair>,
@@ -49,6 +65,8 @@ impl PhysicsPipeline {
     pub fn new() -> PhysicsPipeline {
         PhysicsPipeline {
             counters: Counters::new(false),
+            limits_report: None,
+            solver_stats: SolverStats::default(),
             solvers: Vec::new(),
             manifold_indices: Vec::new(),
             joint_constraint_indices: Vec::new(),
@@ -57,6 +75,125 @@ impl PhysicsPipeline {
         }
     }
 
+    /// Releases the memory used by the workspaces of this pipeline, e.g., after the removal of
+    /// many rigid-bodies.
+    ///
+    /// The solver workspaces are released entirely, and reallocated as needed by the next
+    /// timestep.
+    pub fn shrink_to_fit(&mut self) {
+        self.solvers = Vec::new();
+        self.manifold_indices = Vec::new();
+        self.joint_constraint_indices = Vec::new();
+        self.broadphase_collider_pairs = Vec::new();
+        self.broad_phase_events = Vec::new();
+    }
+
+    /// Statistics about the structures involved in the simulation.
+    ///
+    /// The counts are cheap to obtain. If `detailed` is `true`, the memory allocated by the
+    /// broad-phase, narrow-phase, and solver is computed too, which is linear in the number
+    /// of proxies, contact pairs, and constraints.
+    ///
+    /// The peak values of the broad-phase, narrow-phase, and solver statistics are reset
+    /// by each call to this method.
+    pub fn stats(
+        &mut self,
+        detailed: bool,
+        broad_phase: &mut BroadPhase,
+        narrow_phase: &mut NarrowPhase,
+        bodies: &RigidBodySet,
+        colliders: &ColliderSet,
+    ) -> PipelineStats {
+        self.solver_stats.record_peaks();
+        let mut solver = self.solver_stats;
+
+        let mut current = SolverStats::default();
+        for island_solver in self.solvers.iter().take(bodies.num_islands()) {
+            current.accumulate(&island_solver.stats(false));
+        }
+        solver.parallel_batch_size = current.parallel_batch_size;
+        solver.parallel_velocity_stage = current.parallel_velocity_stage;
+        solver.parallel_position_stage = current.parallel_position_stage;
+
+        if detailed {
+            solver.bytes = self
+                .solvers
+                .iter()
+                .map(|island_solver| island_solver.stats(true).bytes)
+                .sum();
+        }
+
+        self.solver_stats.reset_peaks();
+
+        PipelineStats {
+            counters: self.counters,
+            broad_phase: broad_phase.stats(detailed),
+            narrow_phase: narrow_phase.stats(detailed),
+            solver,
+            bodies: bodies.stats(),
+            colliders: colliders.stats(),
+        }
+    }
+
+    /// Exports the state of the rigid-bodies, contacts, and joints into flat arrays.
+    ///
+    /// This doesn't modify the simulation, and can be called at any time. If `filter` is set,
+    /// only the rigid-bodies for which it returns `true` are exported, as well as the contacts
+    /// and joints involving at least one of them. The content of `out` is replaced, but its
+    /// memory is reused.
+    pub fn export_state(
+        &self,
+        bodies: &RigidBodySet,
+        colliders: &ColliderSet,
+        joints: &JointSet,
+        narrow_phase: &NarrowPhase,
+        filter: Option<&dyn Fn(RigidBodyHandle, &RigidBody) -> bool>,
+        out: &mut SimulationFrame,
+    ) {
+        out.fill(bodies, colliders, joints, narrow_phase, filter)
+    }
+
+    fn update_solver_stats(&mut self, num_islands: usize) {
+        let mut current = SolverStats::default();
+
+        for solver in &self.solvers[..num_islands] {
+            current.accumulate(&solver.stats(false));
+        }
+
+        self.solver_stats.num_contact_constraints = current.num_contact_constraints;
+        self.solver_stats.num_joint_constraints = current.num_joint_constraints;
+        self.solver_stats.record_peaks();
+    }
+
+    /// Sets the predicted position of the colliders attached to moving kinematic bodies, so that
+    /// the broad-phase and narrow-phase account for the position they will have at the end of the
+    /// timestep.
+    fn predict_kinematic_collider_positions(
+        integration_parameters: &IntegrationParameters,
+        bodies: &mut RigidBodySet,
+        colliders: &mut ColliderSet,
+    ) {
+        bodies.foreach_active_kinematic_body_mut_internal(|handle, body| {
+            let next_position = body.predicted_kinematic_position(integration_parameters.dt);
+
+            if next_position == body.position {
+                return;
+            }
+
+            if body.uses_aggregate_proxy() {
+                colliders.set_aggregate_predicted_position(handle, next_position);
+            }
+
+            for handle in body.colliders() {
+                if let Some(collider) =
+                    colliders.get_mut_internal_with_modification_tracking(*handle)
+                {
+                    collider.set_predicted_parent_position(&next_position);
+                }
+            }
+        });
+    }
+
     fn detect_collisions(
         &mut self,
         integration_parameters: &IntegrationParameters,
@@ -71,6 +208,10 @@ impl PhysicsPipeline {
         self.counters.stages.collision_detection_time.resume();
         self.counters.cd.broad_phase_time.resume();
 
+        if integration_parameters.use_predicted_kinematic_positions {
+            Self::predict_kinematic_collider_positions(integration_parameters, bodies, colliders);
+        }
+
         // Update broad-phase.
         self.broad_phase_events.clear();
         self.broadphase_collider_pairs.clear();
@@ -87,15 +228,56 @@ impl PhysicsPipeline {
         if handle_user_changes {
             narrow_phase.handle_user_changes(colliders, bodies, events);
         }
+
+        let num_pairs = narrow_phase.num_pairs();
+        let num_new_pairs = self
+            .broad_phase_events
+            .iter()
+            .filter(|e| matches!(e, BroadPhasePairEvent::AddPair(_)))
+            .count();
+        let max_contact_pairs = integration_parameters.pipeline_limits.max_contact_pairs;
+
+        if num_pairs + num_new_pairs > max_contact_pairs {
+            let report = Self::limits_report(
+                &integration_parameters.pipeline_limits,
+                &mut self.limits_report,
+                "too many contact pairs",
+            );
+            report.num_contact_pairs = num_pairs + num_new_pairs;
+            report.set_worst_colliders(&self.broad_phase_events);
+            report.num_dropped_contact_pairs += drop_excess_pairs(
+                &mut self.broad_phase_events,
+                max_contact_pairs.saturating_sub(num_pairs),
+            );
+        }
+
         narrow_phase.register_pairs(colliders, bodies, &self.broad_phase_events, events);
         narrow_phase.compute_contacts(
             integration_parameters.prediction_distance,
+            integration_parameters.manifold_freezing(),
             bodies,
             colliders,
             hooks,
             events,
         );
+
+        let max_manifold_points = integration_parameters
+            .pipeline_limits
+            .max_manifold_points_total;
+        let num_manifold_points = narrow_phase.limit_solver_contacts(max_manifold_points);
+
+        if num_manifold_points > max_manifold_points {
+            let report = Self::limits_report(
+                &integration_parameters.pipeline_limits,
+                &mut self.limits_report,
+                "too many contact points",
+            );
+            report.num_manifold_points = num_manifold_points;
+            report.num_dropped_manifold_points = num_manifold_points - max_manifold_points;
+        }
         narrow_phase.compute_intersections(bodies, colliders, hooks, events);
+        let narrow_phase_stats = narrow_phase.update_stats();
+        self.counters.set_ncontact_pairs(narrow_phase_stats.num_contact_pairs);
 
         // Clear colliders modification flags.
         colliders.clear_modified_colliders();
@@ -108,51 +290,221 @@ impl PhysicsPipeline {
         &mut self,
         integration_parameters: &IntegrationParameters,
         bodies: &mut RigidBodySet,
+        user_constraints_set: &mut UserConstraintSet,
+        hooks: Option<&dyn PhysicsHooks>,
     ) {
+        let hooks = hooks.filter(|h| {
+            h.active_hooks()
+                .contains(PhysicsHooksFlags::ON_ISLAND_SOLVED)
+        });
+
+        let mut user_constraints = Vec::new();
+        user_constraints_set.select_active_constraints(bodies, &mut user_constraints);
+
         #[cfg(not(feature = "parallel"))]
         {
             enable_flush_to_zero!();
 
-            for island_id in 0..bodies.num_islands() {
-                self.solvers[island_id].solve_position_constraints(
-                    island_id,
-                    &mut self.counters,
-                    integration_parameters,
-                    bodies,
-                )
+            let num_islands = bodies.num_islands();
+            let now = self.counters.step_time.clock();
+            let mut budget = integration_parameters
+                .position_solve_budget
+                .map(|budget| PositionBudgetTracker::new(budget, now));
+            let island_order: Vec<_> = if budget.is_some() {
+                let max_penetrations: Vec<_> = self.solvers[..num_islands]
+                    .iter()
+                    .map(|solver| solver.max_penetration())
+                    .collect();
+                position_solve_order(bodies, &bodies.deferred_position_islands, &max_penetrations)
+            } else {
+                (0..num_islands).collect()
+            };
+            bodies.deferred_position_islands.clear();
+
+            for island_id in island_order {
+                let solver = &mut self.solvers[island_id];
+                let num_user_constraints = user_constraints[island_id].len();
+                let cost = solver.position_solve_cost(integration_parameters, num_user_constraints);
+
+                if budget.as_mut().map(|b| b.try_spend(cost)) == Some(false) {
+                    let first_body = bodies.active_island(island_id)[0];
+                    bodies.deferred_position_islands.push(first_body);
+                    if self.counters.enabled() {
+                        self.counters.solver.num_deferred_position_islands += 1;
+                    }
+                } else {
+                    solver.solve_position_constraints(
+                        island_id,
+                        &mut self.counters,
+                        integration_parameters,
+                        bodies,
+                        &mut user_constraints[island_id],
+                    );
+                    if self.counters.enabled() {
+                        self.counters.solver.num_position_constraint_solves += cost;
+                    }
+                }
+
+                if let Some(hooks) = hooks {
+                    hooks.on_island_solved(island_id, bodies.active_island(island_id), bodies);
+                }
             }
         }
 
         #[cfg(feature = "parallel")]
         {
+            use core::sync::atomic::Ordering;
             use rayon::prelude::*;
-            use std::sync::atomic::Ordering;
 
             let num_islands = bodies.num_islands();
             let solvers = &mut self.solvers[..num_islands];
-            let bodies = &std::sync::atomic::AtomicPtr::new(bodies as *mut _);
+
+            // NOTE: the islands to solve are selected beforehand because the
+            //       islands are solved concurrently.
+            let mut solve_island = vec![true; num_islands];
+            if let Some(budget) = integration_parameters.position_solve_budget {
+                assert!(
+                    matches!(budget, PositionSolveBudget::ConstraintSolves(_)),
+                    "Duration position solve budgets are not supported by the parallel solver."
+                );
+                let mut budget =
+                    PositionBudgetTracker::new(budget, self.counters.step_time.clock());
+                let max_penetrations: Vec<_> = solvers
+                    .iter()
+                    .map(|solver| solver.max_penetration())
+                    .collect();
+                let island_order = position_solve_order(
+                    bodies,
+                    &bodies.deferred_position_islands,
+                    &max_penetrations,
+                );
+                bodies.deferred_position_islands.clear();
+
+                for island_id in island_order {
+                    let num_user_constraints = user_constraints[island_id].len();
+                    let cost = solvers[island_id]
+                        .position_solve_cost(integration_parameters, num_user_constraints);
+
+                    if budget.try_spend(cost) {
+                        if self.counters.enabled() {
+                            self.counters.solver.num_position_constraint_solves += cost;
+                        }
+                    } else {
+                        solve_island[island_id] = false;
+                        let first_body = bodies.active_island(island_id)[0];
+                        bodies.deferred_position_islands.push(first_body);
+                        if self.counters.enabled() {
+                            self.counters.solver.num_deferred_position_islands += 1;
+                        }
+                    }
+                }
+            } else {
+                bodies.deferred_position_islands.clear();
+            }
+
+            for (island_id, solver) in solvers.iter_mut().enumerate() {
+                if solve_island[island_id] && !user_constraints[island_id].is_empty() {
+                    solver.update_user_constraint_offsets(bodies, island_id);
+                }
+            }
+
+            let body_ptr = &core::sync::atomic::AtomicPtr::new(bodies as *mut _);
 
             rayon::scope(|scope| {
                 enable_flush_to_zero!();
 
                 solvers
                     .par_iter_mut()
+                    .zip(user_constraints.par_iter_mut())
                     .enumerate()
-                    .for_each(|(island_id, solver)| {
+                    .filter(|(island_id, _)| solve_island[*island_id])
+                    .for_each(|(island_id, (solver, user_constraints))| {
                         let bodies: &mut RigidBodySet =
-                            unsafe { std::mem::transmute(bodies.load(Ordering::Relaxed)) };
+                            unsafe { core::mem::transmute(body_ptr.load(Ordering::Relaxed)) };
 
                         solver.solve_position_constraints(
                             scope,
                             island_id,
                             integration_parameters,
                             bodies,
+                            user_constraints,
                         )
                     });
             });
+
+            // NOTE: the hook is only called once every island is solved because the
+            //       tasks solving the other islands keep modifying the rigid-body set.
+            if let Some(hooks) = hooks {
+                for island_id in 0..num_islands {
+                    hooks.on_island_solved(island_id, bodies.active_island(island_id), bodies);
+                }
+            }
+        }
+    }
+
+    fn limits_report<'a>(
+        limits: &PipelineLimits,
+        report: &'a mut Option<PipelineLimitsReport>,
+        exceeded_limit: &str,
+    ) -> &'a mut PipelineLimitsReport {
+        if limits.on_limit_exceeded == OnLimitExceeded::Panic {
+            panic!("Pipeline limit exceeded: {}.", exceeded_limit);
+        }
+
+        report.get_or_insert_with(PipelineLimitsReport::default)
+    }
+
+    fn validate_modified_bodies(
+        integration_parameters: &IntegrationParameters,
+        bodies: &mut RigidBodySet,
+        events: &dyn EventHandler,
+    ) {
+        let policy = integration_parameters.on_non_finite;
+        let validate = |handle: RigidBodyHandle, rb: &mut RigidBody| {
+            if let Some(field) = find_non_finite_field(rb, true) {
+                handle_non_finite_body(policy, handle, rb, field, events);
+            }
+        };
+
+        // NOTE: with the `debug-validation` feature, all the bodies are validated, not only
+        //       the ones modified by the user.
+        if bodies.modified_all_bodies || cfg!(feature = "debug-validation") {
+            for (handle, rb) in bodies.bodies.iter_mut() {
+                validate(RigidBodyHandle(handle), rb);
+            }
+        } else {
+            for handle in &bodies.modified_bodies {
+                if let Some(rb) = bodies.bodies.get_mut(handle.0) {
+                    validate(*handle, rb);
+                }
+            }
         }
     }
 
+    fn validate_integrated_bodies(
+        integration_parameters: &IntegrationParameters,
+        bodies: &mut RigidBodySet,
+        events: &dyn EventHandler,
+    ) {
+        let policy = integration_parameters.on_non_finite;
+        bodies.foreach_active_dynamic_body_mut_internal(|handle, rb| {
+            if let Some(field) = find_non_finite_field(rb, false) {
+                handle_non_finite_body(policy, handle, rb, field, events);
+            }
+        });
+    }
+
+    fn clamp_velocities(
+        integration_parameters: &IntegrationParameters,
+        bodies: &mut RigidBodySet,
+        include_forces: bool,
+        events: &dyn EventHandler,
+    ) {
+        bodies.foreach_active_dynamic_body_mut_internal(|handle, rb| {
+            clamp_body_velocities(integration_parameters, handle, rb, include_forces, events)
+        });
+    }
+
     fn build_islands_and_solve_velocity_constraints(
         &mut self,
         gravity: &Vector<Real>,
@@ -161,6 +513,8 @@ impl PhysicsPipeline {
         bodies: &mut RigidBodySet,
         colliders: &mut ColliderSet,
         joints: &mut JointSet,
+        user_constraints_set: &mut UserConstraintSet,
+        events: &dyn EventHandler,
     ) {
         self.counters.stages.island_construction_time.resume();
         bodies.update_active_set_with_contacts(
@@ -168,9 +522,25 @@ impl PhysicsPipeline {
             narrow_phase,
             joints.joint_graph(),
             integration_parameters.min_island_size,
+            integration_parameters.max_wake_propagation_depth,
+            integration_parameters.contacts_wake_sleeping_bodies,
         );
         self.counters.stages.island_construction_time.pause();
 
+        let max_island_size = (0..bodies.num_islands())
+            .map(|island_id| bodies.active_island(island_id).len())
+            .max()
+            .unwrap_or(0);
+
+        if max_island_size > integration_parameters.pipeline_limits.max_bodies_per_island {
+            let report = Self::limits_report(
+                &integration_parameters.pipeline_limits,
+                &mut self.limits_report,
+                "too many bodies in an island",
+            );
+            report.max_island_size = report.max_island_size.max(max_island_size);
+        }
+
         if self.manifold_indices.len() < bodies.num_islands() {
             self.manifold_indices
                 .resize(bodies.num_islands(), Vec::new());
@@ -183,13 +553,20 @@ impl PhysicsPipeline {
 
         let mut manifolds = Vec::new();
         narrow_phase.select_active_contacts(bodies, &mut manifolds, &mut self.manifold_indices);
-        joints.select_active_interactions(bodies, &mut self.joint_constraint_indices);
+        joints.select_active_interactions(
+            bodies,
+            integration_parameters.joint_chain_ordering,
+            &mut self.joint_constraint_indices,
+        );
 
         self.counters.stages.update_time.resume();
         bodies.foreach_active_dynamic_body_mut_internal(|_, b| {
             b.update_world_mass_properties();
             b.add_gravity(*gravity)
         });
+        // NOTE: this must happen before the constraints initialization, which
+        //       reads the velocities of the bodies.
+        Self::clamp_velocities(integration_parameters, bodies, true, events);
         self.counters.stages.update_time.pause();
 
         self.counters.stages.solver_time.resume();
@@ -198,6 +575,9 @@ impl PhysicsPipeline {
                 .resize_with(bodies.num_islands(), IslandSolver::new);
         }
 
+        let mut user_constraints = Vec::new();
+        user_constraints_set.select_active_velocity_constraints(bodies, &mut user_constraints);
+
         #[cfg(not(feature = "parallel"))]
         {
             enable_flush_to_zero!();
@@ -212,6 +592,7 @@ impl PhysicsPipeline {
                     &self.manifold_indices[island_id],
                     joints.joints_mut(),
                     &self.joint_constraint_indices[island_id],
+                    &mut user_constraints[island_id],
                 )
             }
         }
@@ -219,14 +600,26 @@ impl PhysicsPipeline {
         #[cfg(feature = "parallel")]
         {
             use crate::geometry::ContactManifold;
+            use core::sync::atomic::Ordering;
             use rayon::prelude::*;
-            use std::sync::atomic::Ordering;
 
             let num_islands = bodies.num_islands();
             let solvers = &mut self.solvers[..num_islands];
-            let bodies = &std::sync::atomic::AtomicPtr::new(bodies as *mut _);
-            let manifolds = &std::sync::atomic::AtomicPtr::new(&mut manifolds as *mut _);
-            let joints = &std::sync::atomic::AtomicPtr::new(joints.joints_vec_mut() as *mut _);
+
+            // NOTE: the user constraints read the whole rigid-body set, so they can't be
+            //       initialized while the other islands are modified by the parallel solver.
+            for (island_id, solver) in solvers.iter_mut().enumerate() {
+                solver.init_user_velocity_constraints(
+                    island_id,
+                    integration_parameters,
+                    bodies,
+                    &mut user_constraints[island_id],
+                );
+            }
+
+            let bodies = &core::sync::atomic::AtomicPtr::new(bodies as *mut _);
+            let manifolds = &core::sync::atomic::AtomicPtr::new(&mut manifolds as *mut _);
+            let joints = &core::sync::atomic::AtomicPtr::new(joints.joints_vec_mut() as *mut _);
             let manifold_indices = &self.manifold_indices[..];
             let joint_constraint_indices = &self.joint_constraint_indices[..];
 
@@ -235,14 +628,15 @@ impl PhysicsPipeline {
 
                 solvers
                     .par_iter_mut()
+                    .zip(user_constraints.par_iter_mut())
                     .enumerate()
-                    .for_each(|(island_id, solver)| {
+                    .for_each(|(island_id, (solver, user_constraints))| {
                         let bodies: &mut RigidBodySet =
-                            unsafe { std::mem::transmute(bodies.load(Ordering::Relaxed)) };
+                            unsafe { core::mem::transmute(bodies.load(Ordering::Relaxed)) };
                         let manifolds: &mut Vec<&mut ContactManifold> =
-                            unsafe { std::mem::transmute(manifolds.load(Ordering::Relaxed)) };
+                            unsafe { core::mem::transmute(manifolds.load(Ordering::Relaxed)) };
                         let joints: &mut Vec<JointGraphEdge> =
-                            unsafe { std::mem::transmute(joints.load(Ordering::Relaxed)) };
+                            unsafe { core::mem::transmute(joints.load(Ordering::Relaxed)) };
 
                         solver.init_constraints_and_solve_velocity_constraints(
                             scope,
@@ -253,11 +647,13 @@ impl PhysicsPipeline {
                             &manifold_indices[island_id],
                             joints,
                             &joint_constraint_indices[island_id],
+                            user_constraints,
                         )
                     });
             });
         }
         self.counters.stages.solver_time.pause();
+        self.update_solver_stats(bodies.num_islands());
     }
 
     fn run_ccd_motion_clamping(
@@ -289,25 +685,40 @@ impl PhysicsPipeline {
         clear_forces: bool,
     ) {
         // Set the rigid-bodies and kinematic bodies to their final position.
-        bodies.foreach_active_body_mut_internal(|_, rb| {
-            if rb.is_kinematic() {
-                rb.linvel = na::zero();
-                rb.angvel = na::zero();
-            }
+        bodies.foreach_active_body_mut_internal(|handle, rb| {
+            rb.prev_position = rb.position;
+            rb.position = rb.next_position;
 
+            // NOTE: the forces are cleared after the last substep only, which is
+            //       also where the motion of the whole timestep is measured.
             if clear_forces {
                 rb.force = na::zero();
                 rb.torque = na::zero();
+                rb.update_step_deltas();
             }
 
-            rb.position = rb.next_position;
-            rb.update_colliders_positions(colliders);
+            if rb.is_kinematic() {
+                // Keep track of the kinematic velocities, so a body switched to dynamic
+                // inherits them.
+                rb.last_kinematic_linvel = rb.linvel;
+                rb.last_kinematic_angvel = rb.angvel;
+                rb.linvel = na::zero();
+                rb.angvel = na::zero();
+
+                // The next position set by the user only applies to this timestep.
+                if clear_forces {
+                    rb.next_kinematic_position_set = false;
+                }
+            }
+
+            rb.update_colliders_positions(handle, colliders);
         });
     }
 
     fn interpolate_kinematic_velocities(
         &mut self,
         integration_parameters: &IntegrationParameters,
+        step_remaining_time: Real,
         bodies: &mut RigidBodySet,
     ) {
         // Update kinematic bodies velocities.
@@ -316,7 +727,10 @@ impl PhysicsPipeline {
         // there to determine if this kinematic body should wake-up dynamic
         // bodies it is touching.
         bodies.foreach_active_kinematic_body_mut_internal(|_, body| {
-            body.compute_velocity_from_next_position(integration_parameters.inv_dt());
+            body.compute_velocity_from_next_position(
+                integration_parameters.dt,
+                step_remaining_time,
+            );
         });
     }
 
@@ -330,12 +744,17 @@ impl PhysicsPipeline {
         bodies: &mut RigidBodySet,
         colliders: &mut ColliderSet,
         joints: &mut JointSet,
+        user_constraints: &mut UserConstraintSet,
         ccd_solver: &mut CCDSolver,
         hooks: &dyn PhysicsHooks,
         events: &dyn EventHandler,
     ) {
         self.counters.reset();
         self.counters.step_started();
+
+        bodies.merge_modified_shards();
+
+        Self::validate_modified_bodies(integration_parameters, bodies, events);
         colliders.handle_user_changes(bodies);
         bodies.handle_user_changes(colliders);
 
@@ -349,6 +768,7 @@ impl PhysicsPipeline {
             events,
             true,
         );
+        narrow_phase.update_contact_ages(integration_parameters.dt);
 
         let mut remaining_time = integration_parameters.dt;
         let mut integration_parameters = *integration_parameters;
@@ -361,6 +781,8 @@ impl PhysicsPipeline {
             };
 
         while remaining_substeps > 0 {
+            let step_remaining_time = remaining_time;
+
             // If there is more than one CCD substep, we need to split
             // the timestep into multiple intervals. First, estimate the
             // size of the time slice we will integrate for this substep.
@@ -372,6 +794,10 @@ impl PhysicsPipeline {
             // If there is only one or zero CCD substeps, there is no need to split
             // the timestep interval. So we can just skip this part.
             if ccd_is_enabled && remaining_substeps > 1 {
+                // NOTE: clamp the velocities first so that exploding bodies don't generate
+                //       huge swept AABBs for the impact prediction.
+                Self::clamp_velocities(&integration_parameters, bodies, false, events);
+
                 // NOTE: Take forces into account when updating the bodies CCD activation flags
                 //       these forces have not been integrated to the body's velocity yet.
                 let ccd_active = ccd_solver.update_ccd_active_flags(bodies, remaining_time, true);
@@ -413,7 +839,11 @@ impl PhysicsPipeline {
 
             self.counters.ccd.num_substeps += 1;
 
-            self.interpolate_kinematic_velocities(&integration_parameters, bodies);
+            self.interpolate_kinematic_velocities(
+                &integration_parameters,
+                step_remaining_time,
+                bodies,
+            );
             self.build_islands_and_solve_velocity_constraints(
                 gravity,
                 &integration_parameters,
@@ -421,7 +851,10 @@ impl PhysicsPipeline {
                 bodies,
                 colliders,
                 joints,
+                user_constraints,
+                events,
             );
+            Self::validate_integrated_bodies(&integration_parameters, bodies, events);
 
             // If CCD is enabled, execute the CCD motion clamping.
             if ccd_is_enabled {
@@ -448,7 +881,19 @@ impl PhysicsPipeline {
             //       This happens because our CCD use the real rigid-body
             //       velocities instead of just interpolating between
             //       isometries.
-            self.solve_position_constraints(&integration_parameters, bodies);
+            // Only notify the user about solved islands on the last substep,
+            // i.e., once the body poses are final.
+            let island_hooks = if remaining_substeps == 0 {
+                Some(hooks)
+            } else {
+                None
+            };
+            self.solve_position_constraints(
+                &integration_parameters,
+                bodies,
+                user_constraints,
+                island_hooks,
+            );
 
             let clear_forces = remaining_substeps == 0;
             self.advance_to_final_positions(bodies, colliders, clear_forces);
@@ -466,23 +911,134 @@ impl PhysicsPipeline {
             bodies.modified_inactive_set.clear();
         }
 
+        if let Some(report) = self.limits_report.take() {
+            events.handle_pipeline_limits_report(report);
+        }
+
         self.counters.step_completed();
     }
+
+    /// Executes one timestep of the physics simulation, suspending the active rigid-bodies
+    /// outside of the given regions.
+    ///
+    /// Only the active dynamic rigid-bodies with a collider intersecting one of the `regions`
+    /// are simulated, together with all the rigid-bodies they interact with through contacts and
+    /// joints so that islands crossing the boundary of the regions are simulated entirely. The
+    /// active kinematic rigid-bodies are moved only if they have a collider intersecting one of
+    /// the `regions`, or if they touch a simulated dynamic rigid-body. The other active
+    /// rigid-bodies are suspended for this timestep: they don't move, keep their velocities and
+    /// their next kinematic position, and don't fall asleep. Their simulation resumes at the
+    /// next timestep including them.
+    ///
+    /// Because the suspended bodies don't move, the broad-phase only updates the colliders of
+    /// the simulated bodies, and the narrow-phase only visits the pairs involving one of these
+    /// colliders instead of going through every pair of the world. The colliders modified by
+    /// the user since the last timestep are updated even if they lie outside of the regions.
+    /// The user tags of the pairs that aren't visited are refreshed at the next timestep
+    /// visiting them.
+    pub fn step_region(
+        &mut self,
+        regions: &[AABB],
+        gravity: &Vector<Real>,
+        integration_parameters: &IntegrationParameters,
+        broad_phase: &mut BroadPhase,
+        narrow_phase: &mut NarrowPhase,
+        bodies: &mut RigidBodySet,
+        colliders: &mut ColliderSet,
+        joints: &mut JointSet,
+        user_constraints: &mut UserConstraintSet,
+        ccd_solver: &mut CCDSolver,
+        hooks: &dyn PhysicsHooks,
+        events: &dyn EventHandler,
+    ) {
+        bodies.suspend_outside_regions(regions, colliders, narrow_phase, joints.joint_graph());
+        self.step(
+            gravity,
+            integration_parameters,
+            broad_phase,
+            narrow_phase,
+            bodies,
+            colliders,
+            joints,
+            user_constraints,
+            ccd_solver,
+            hooks,
+            events,
+        );
+        bodies.resume_suspended();
+    }
 }
 
 #[cfg(test)]
 mod test {
     use crate::dynamics::{
         CCDSolver, IntegrationParameters, JointSet, RigidBodyBuilder, RigidBodySet,
+        UserConstraintSet,
+    };
+    use crate::geometry::{
+        BroadPhase, ColliderBuilder, ColliderSet, NarrowPhase, RemoveSettleMode,
+    };
+    use crate::math::{Real, Vector};
+    use crate::pipeline::{
+        EventHandler, NonFiniteEvent, NonFiniteField, OnNonFinite, PhysicsHooks, PhysicsPipeline,
     };
-    use crate::geometry::{BroadPhase, ColliderBuilder, ColliderSet, NarrowPhase};
-    use crate::math::Vector;
-    use crate::pipeline::PhysicsPipeline;
+
+    // A physics world with the default integration parameters and a downward gravity.
+    struct TestWorld {
+        pipeline: PhysicsPipeline,
+        gravity: Vector<Real>,
+        integration_parameters: IntegrationParameters,
+        broad_phase: BroadPhase,
+        narrow_phase: NarrowPhase,
+        bodies: RigidBodySet,
+        colliders: ColliderSet,
+        joints: JointSet,
+        user_constraints: UserConstraintSet,
+        ccd: CCDSolver,
+    }
+
+    impl TestWorld {
+        fn new() -> Self {
+            Self {
+                pipeline: PhysicsPipeline::new(),
+                gravity: Vector::y() * -9.81,
+                integration_parameters: IntegrationParameters::default(),
+                broad_phase: BroadPhase::new(),
+                narrow_phase: NarrowPhase::new(),
+                bodies: RigidBodySet::new(),
+                colliders: ColliderSet::new(),
+                joints: JointSet::new(),
+                user_constraints: UserConstraintSet::new(),
+                ccd: CCDSolver::new(),
+            }
+        }
+
+        fn step(&mut self) {
+            self.step_with(&(), &())
+        }
+
+        fn step_with(&mut self, hooks: &dyn PhysicsHooks, events: &dyn EventHandler) {
+            self.pipeline.step(
+                &self.gravity,
+                &self.integration_parameters,
+                &mut self.broad_phase,
+                &mut self.narrow_phase,
+                &mut self.bodies,
+                &mut self.colliders,
+                &mut self.joints,
+                &mut self.user_constraints,
+                &mut self.ccd,
+                hooks,
+                events,
+            );
+        }
+    }
 
     #[test]
     fn kinematic_and_static_contact_crash() {
         let mut colliders = ColliderSet::new();
         let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
         let mut pipeline = PhysicsPipeline::new();
         let mut bf = BroadPhase::new();
         let mut nf = NarrowPhase::new();
@@ -506,6 +1062,7 @@ mod test {
             &mut bodies,
             &mut colliders,
             &mut joints,
+            &mut user_constraints,
             &mut CCDSolver::new(),
             &(),
             &(),
@@ -516,6 +1073,7 @@ mod test {
     fn rigid_body_removal_before_step() {
         let mut colliders = ColliderSet::new();
         let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
         let mut pipeline = PhysicsPipeline::new();
         let mut bf = BroadPhase::new();
         let mut nf = NarrowPhase::new();
@@ -550,6 +1108,7 @@ mod test {
             &mut bodies,
             &mut colliders,
             &mut joints,
+            &mut user_constraints,
             &mut CCDSolver::new(),
             &(),
             &(),
@@ -599,6 +1158,7 @@ mod test {
         let mut colliders = ColliderSet::new();
         let mut ccd = CCDSolver::new();
         let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
         let physics_hooks = ();
         let event_handler = ();
 
@@ -606,7 +1166,7 @@ mod test {
         let b_handle = bodies.insert(body);
         let collider = ColliderBuilder::ball(1.0).build();
         let c_handle = colliders.insert(collider, b_handle, &mut bodies);
-        colliders.remove(c_handle, &mut bodies, true);
+        colliders.remove(c_handle, &mut bodies, true, RemoveSettleMode::Immediate);
         bodies.remove(b_handle, &mut colliders, &mut joints);
 
         for _ in 0..10 {
@@ -618,10 +1178,7753 @@ mod test {
                 &mut bodies,
                 &mut colliders,
                 &mut joints,
+                &mut user_constraints,
                 &mut ccd,
                 &physics_hooks,
                 &event_handler,
             );
         }
     }
+
+    #[test]
+    fn island_solved_hook_sees_final_poses() {
+        use crate::dynamics::RigidBodyHandle;
+        use crate::math::{Isometry, Real};
+        use crate::pipeline::{PhysicsHooks, PhysicsHooksFlags};
+        use std::sync::Mutex;
+
+        struct IslandRecorder(Mutex<Vec<(RigidBodyHandle, Isometry<Real>)>>);
+
+        impl PhysicsHooks for IslandRecorder {
+            fn active_hooks(&self) -> PhysicsHooksFlags {
+                PhysicsHooksFlags::ON_ISLAND_SOLVED
+            }
+
+            fn on_island_solved(
+                &self,
+                _island_id: usize,
+                handles: &[RigidBodyHandle],
+                bodies: &RigidBodySet,
+            ) {
+                let mut poses = self.0.lock().unwrap();
+                poses.extend(handles.iter().map(|h| (*h, *bodies[*h].next_position())));
+            }
+        }
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+        let hooks = IslandRecorder(Mutex::new(Vec::new()));
+
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        colliders.insert(ColliderBuilder::ball(10.0).build(), ground, &mut bodies);
+
+        // Two separate stacks so we get more than one island.
+        let mut handles = Vec::new();
+        for x in &[-5.0, 5.0] {
+            for i in 0..3 {
+                let y = 10.5 + i as Real * 1.01;
+                #[cfg(feature = "dim2")]
+                let rb = RigidBodyBuilder::new_dynamic().translation(*x, y).build();
+                #[cfg(feature = "dim3")]
+                let rb = RigidBodyBuilder::new_dynamic()
+                    .translation(*x, y, 0.0)
+                    .build();
+                let handle = bodies.insert(rb);
+                colliders.insert(ColliderBuilder::ball(0.5).build(), handle, &mut bodies);
+                handles.push(handle);
+            }
+        }
+
+        for _ in 0..10 {
+            hooks.0.lock().unwrap().clear();
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &hooks,
+                &(),
+            );
+
+            let poses = hooks.0.lock().unwrap();
+            assert_eq!(poses.len(), handles.len());
+
+            for (handle, pose) in poses.iter() {
+                assert!(handles.contains(handle));
+                assert_eq!(bodies[*handle].position(), pose);
+            }
+        }
+    }
+
+    #[test]
+    fn user_position_constraint_keeps_fixed_distance() {
+        use crate::dynamics::{PositionOffsetsMap, RigidBodyHandle, UserPositionConstraint};
+        use crate::math::{Isometry, Real};
+
+        struct FixedDistance {
+            bodies: [RigidBodyHandle; 2],
+            distance: Real,
+        }
+
+        impl UserPositionConstraint for FixedDistance {
+            fn bodies(&self) -> &[RigidBodyHandle] {
+                &self.bodies
+            }
+
+            fn solve(
+                &mut self,
+                _params: &IntegrationParameters,
+                positions: &mut [Isometry<Real>],
+                offsets: &PositionOffsetsMap,
+            ) {
+                let i1 = offsets.get(self.bodies[0]).unwrap();
+                let i2 = offsets.get(self.bodies[1]).unwrap();
+                let dpos = positions[i2].translation.vector - positions[i1].translation.vector;
+                let dist = dpos.norm();
+                let correction = dpos * ((dist - self.distance) / (2.0 * dist));
+                positions[i1].translation.vector += correction;
+                positions[i2].translation.vector -= correction;
+            }
+        }
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::zeros();
+        let integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        // Two bodies flying away from each other.
+        #[cfg(feature = "dim2")]
+        let (rb1, rb2) = (
+            RigidBodyBuilder::new_dynamic()
+                .translation(-1.0, 0.0)
+                .linvel(-1.0, 0.5)
+                .build(),
+            RigidBodyBuilder::new_dynamic()
+                .translation(1.0, 0.0)
+                .linvel(2.0, 0.0)
+                .build(),
+        );
+        #[cfg(feature = "dim3")]
+        let (rb1, rb2) = (
+            RigidBodyBuilder::new_dynamic()
+                .translation(-1.0, 0.0, 0.0)
+                .linvel(-1.0, 0.5, 0.0)
+                .build(),
+            RigidBodyBuilder::new_dynamic()
+                .translation(1.0, 0.0, 0.0)
+                .linvel(2.0, 0.0, -0.5)
+                .build(),
+        );
+        let h1 = bodies.insert(rb1);
+        let h2 = bodies.insert(rb2);
+        colliders.insert(ColliderBuilder::ball(0.1).build(), h1, &mut bodies);
+        colliders.insert(ColliderBuilder::ball(0.1).build(), h2, &mut bodies);
+
+        user_constraints.insert(Box::new(FixedDistance {
+            bodies: [h1, h2],
+            distance: 2.0,
+        }));
+
+        for _ in 0..20 {
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            );
+
+            let dist = (bodies[h2].position().translation.vector
+                - bodies[h1].position().translation.vector)
+                .norm();
+            assert!((dist - 2.0).abs() < 1.0e-4);
+        }
+    }
+
+    #[test]
+    fn quasi_static_islands_rest_at_the_same_positions() {
+        use crate::math::{Isometry, Real};
+
+        fn settle_stack(integration_parameters: &IntegrationParameters) -> Vec<Isometry<Real>> {
+            let mut pipeline = PhysicsPipeline::new();
+            let gravity = Vector::y() * -9.81;
+            let mut broad_phase = BroadPhase::new();
+            let mut narrow_phase = NarrowPhase::new();
+            let mut bodies = RigidBodySet::new();
+            let mut colliders = ColliderSet::new();
+            let mut ccd = CCDSolver::new();
+            let mut joints = JointSet::new();
+            let mut user_constraints = UserConstraintSet::new();
+
+            let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+            #[cfg(feature = "dim2")]
+            let ground_collider = ColliderBuilder::cuboid(10.0, 0.5).build();
+            #[cfg(feature = "dim3")]
+            let ground_collider = ColliderBuilder::cuboid(10.0, 0.5, 10.0).build();
+            colliders.insert(ground_collider, ground, &mut bodies);
+
+            let mut handles = Vec::new();
+            for i in 0..4 {
+                let y = 1.0 + i as Real;
+                #[cfg(feature = "dim2")]
+                let rb = RigidBodyBuilder::new_dynamic().translation(0.0, y);
+                #[cfg(feature = "dim3")]
+                let rb = RigidBodyBuilder::new_dynamic().translation(0.0, y, 0.0);
+                let handle = bodies.insert(rb.can_sleep(false).build());
+                #[cfg(feature = "dim2")]
+                let collider = ColliderBuilder::cuboid(0.5, 0.5).build();
+                #[cfg(feature = "dim3")]
+                let collider = ColliderBuilder::cuboid(0.5, 0.5, 0.5).build();
+                colliders.insert(collider, handle, &mut bodies);
+                handles.push(handle);
+            }
+
+            for _ in 0..300 {
+                pipeline.step(
+                    &gravity,
+                    integration_parameters,
+                    &mut broad_phase,
+                    &mut narrow_phase,
+                    &mut bodies,
+                    &mut colliders,
+                    &mut joints,
+                    &mut user_constraints,
+                    &mut ccd,
+                    &(),
+                    &(),
+                );
+            }
+
+            handles.iter().map(|h| *bodies[*h].position()).collect()
+        }
+
+        let regenerated = settle_stack(&IntegrationParameters::default());
+        let reused = settle_stack(&IntegrationParameters {
+            quasi_static_velocity_threshold: 0.05,
+            ..IntegrationParameters::default()
+        });
+
+        for (pos1, pos2) in regenerated.iter().zip(reused.iter()) {
+            assert!((pos1.translation.vector - pos2.translation.vector).norm() < 1.0e-2);
+        }
+    }
+
+    fn step_stack_with_nan_impulse(policy: OnNonFinite) {
+        use crate::geometry::{ContactEvent, IntersectionEvent};
+        use crate::math::Real;
+        use crate::pipeline::EventHandler;
+        use std::sync::Mutex;
+
+        struct NonFiniteRecorder(Mutex<Vec<NonFiniteEvent>>);
+
+        impl EventHandler for NonFiniteRecorder {
+            fn handle_intersection_event(&self, _event: IntersectionEvent) {}
+            fn handle_contact_event(&self, _event: ContactEvent) {}
+            fn handle_non_finite_event(&self, event: NonFiniteEvent) {
+                self.0.lock().unwrap().push(event);
+            }
+        }
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters {
+            on_non_finite: policy,
+            ..IntegrationParameters::default()
+        };
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+        let events = NonFiniteRecorder(Mutex::new(Vec::new()));
+
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        colliders.insert(ColliderBuilder::ball(10.0).build(), ground, &mut bodies);
+
+        let mut handles = Vec::new();
+        for i in 0..3 {
+            let y = 10.5 + i as Real * 1.01;
+            #[cfg(feature = "dim2")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(0.0, y).build();
+            #[cfg(feature = "dim3")]
+            let rb = RigidBodyBuilder::new_dynamic()
+                .translation(0.0, y, 0.0)
+                .build();
+            let handle = bodies.insert(rb);
+            colliders.insert(ColliderBuilder::ball(0.5).build(), handle, &mut bodies);
+            handles.push(handle);
+        }
+
+        for i in 0..20 {
+            if i == 5 {
+                let rb = bodies.get_mut(handles[1]).unwrap();
+                rb.apply_impulse(Vector::repeat(Real::NAN), true);
+            }
+
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &events,
+            );
+        }
+
+        // The whole island must have survived.
+        for handle in &handles {
+            let rb = &bodies[*handle];
+            assert!(rb
+                .position()
+                .translation
+                .vector
+                .iter()
+                .all(|e| e.is_finite()));
+            assert!(rb.linvel().iter().all(|e| e.is_finite()));
+        }
+
+        // The other bodies kept moving.
+        assert!(bodies[handles[2]].position().translation.y < 10.5 + 2.02);
+
+        let events = events.0.into_inner().unwrap();
+        assert_eq!(
+            events,
+            vec![NonFiniteEvent {
+                body: handles[1],
+                field: NonFiniteField::LinearVelocity,
+            }]
+        );
+
+        if policy == OnNonFinite::FreezeBodyAndReport {
+            assert!(bodies[handles[1]].is_frozen());
+        }
+    }
+
+    #[test]
+    fn non_finite_velocity_freezes_the_body() {
+        step_stack_with_nan_impulse(OnNonFinite::FreezeBodyAndReport);
+    }
+
+    #[test]
+    fn non_finite_velocity_resets_the_velocity() {
+        step_stack_with_nan_impulse(OnNonFinite::ResetVelocityAndReport);
+    }
+
+    #[test]
+    #[should_panic]
+    fn non_finite_velocity_panics() {
+        step_stack_with_nan_impulse(OnNonFinite::Panic);
+    }
+
+    #[test]
+    fn rescaled_parameters_are_timestep_invariant() {
+        use crate::dynamics::BallJoint;
+        use crate::math::{Isometry, Point};
+
+        fn simulate(integration_parameters: &IntegrationParameters) -> Vec<Isometry<Real>> {
+            let mut world = TestWorld::new();
+            world.integration_parameters = *integration_parameters;
+
+            let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+            #[cfg(feature = "dim2")]
+            let ground_collider = ColliderBuilder::cuboid(10.0, 0.5).build();
+            #[cfg(feature = "dim3")]
+            let ground_collider = ColliderBuilder::cuboid(10.0, 0.5, 10.0).build();
+            world
+                .colliders
+                .insert(ground_collider, ground, &mut world.bodies);
+
+            // A box stack.
+            let mut handles = Vec::new();
+            for i in 0..3 {
+                let y = 1.0 + i as Real;
+                #[cfg(feature = "dim2")]
+                let rb = RigidBodyBuilder::new_dynamic().translation(0.0, y);
+                #[cfg(feature = "dim3")]
+                let rb = RigidBodyBuilder::new_dynamic().translation(0.0, y, 0.0);
+                let handle = world.bodies.insert(rb.build());
+                #[cfg(feature = "dim2")]
+                let collider = ColliderBuilder::cuboid(0.5, 0.5).build();
+                #[cfg(feature = "dim3")]
+                let collider = ColliderBuilder::cuboid(0.5, 0.5, 0.5).build();
+                world.colliders.insert(collider, handle, &mut world.bodies);
+                handles.push(handle);
+            }
+
+            // A damped pendulum, far from the stack.
+            #[cfg(feature = "dim2")]
+            let (pivot_pos, bob_pos) = (Vector::new(5.0, 5.0), Vector::new(7.0, 5.0));
+            #[cfg(feature = "dim3")]
+            let (pivot_pos, bob_pos) = (Vector::new(5.0, 5.0, 0.0), Vector::new(7.0, 5.0, 0.0));
+            let pivot = world.bodies.insert(
+                RigidBodyBuilder::new_static()
+                    .position(Isometry::new(pivot_pos, na::zero()))
+                    .build(),
+            );
+            let bob = world.bodies.insert(
+                RigidBodyBuilder::new_dynamic()
+                    .position(Isometry::new(bob_pos, na::zero()))
+                    .linear_damping(0.5)
+                    .build(),
+            );
+            world
+                .colliders
+                .insert(ColliderBuilder::ball(0.2).build(), bob, &mut world.bodies);
+            let joint = BallJoint::new(Point::origin(), Point::from(pivot_pos - bob_pos));
+            world.joints.insert(&mut world.bodies, pivot, bob, joint);
+            handles.push(bob);
+
+            let num_steps = (2.0 / integration_parameters.dt).round() as usize;
+            for _ in 0..num_steps {
+                world.step();
+            }
+
+            handles
+                .iter()
+                .map(|h| *world.bodies[*h].position())
+                .collect()
+        }
+
+        let params60 = IntegrationParameters::default();
+        let params120 = params60.rescaled_for_dt(1.0 / 120.0);
+        assert!(params120.erp < params60.erp);
+
+        let poses60 = simulate(&params60);
+        let poses120 = simulate(&params120);
+
+        for (pos60, pos120) in poses60.iter().zip(poses120.iter()) {
+            assert!((pos60.translation.vector - pos120.translation.vector).norm() < 0.1);
+        }
+    }
+
+    #[test]
+    fn post_solve_restitution_preserves_energy() {
+        use crate::dynamics::RestitutionModel;
+        use crate::math::Real;
+
+        // Bounces a column of bouncy balls on the ground, and returns the smallest and largest
+        // ratios between their total energy and their initial energy during the bounces.
+        fn energy_ratios(restitution_model: RestitutionModel) -> (Real, Real) {
+            let mut pipeline = PhysicsPipeline::new();
+            let gravity = Vector::y() * -9.81;
+            let integration_parameters = IntegrationParameters {
+                restitution_model,
+                ..IntegrationParameters::default()
+            };
+            let mut broad_phase = BroadPhase::new();
+            let mut narrow_phase = NarrowPhase::new();
+            let mut bodies = RigidBodySet::new();
+            let mut colliders = ColliderSet::new();
+            let mut ccd = CCDSolver::new();
+            let mut joints = JointSet::new();
+            let mut user_constraints = UserConstraintSet::new();
+
+            let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+            let collider = ColliderBuilder::halfspace(Vector::y_axis())
+                .friction(0.0)
+                .restitution(1.0)
+                .build();
+            colliders.insert(collider, ground, &mut bodies);
+
+            let mut handles = Vec::new();
+            for i in 0..5 {
+                let y = 1.0 + i as Real * 2.5;
+                #[cfg(feature = "dim2")]
+                let rb = RigidBodyBuilder::new_dynamic().translation(0.0, y).build();
+                #[cfg(feature = "dim3")]
+                let rb = RigidBodyBuilder::new_dynamic()
+                    .translation(0.0, y, 0.0)
+                    .build();
+                let handle = bodies.insert(rb);
+                let collider = ColliderBuilder::ball(0.5)
+                    .friction(0.0)
+                    .restitution(1.0)
+                    .build();
+                colliders.insert(collider, handle, &mut bodies);
+                handles.push(handle);
+            }
+
+            let total_energy = |bodies: &RigidBodySet| -> Real {
+                handles
+                    .iter()
+                    .map(|h| {
+                        let rb = &bodies[*h];
+                        let kinetic = rb.mass() * rb.linvel().norm_squared() / 2.0;
+                        let potential = rb.mass() * -gravity.y * rb.position().translation.vector.y;
+                        kinetic + potential
+                    })
+                    .sum()
+            };
+
+            let initial_energy = total_energy(&bodies);
+            let mut min_ratio = Real::MAX;
+            let mut max_ratio: Real = 0.0;
+
+            // Five seconds, i.e., several bounces of every ball.
+            for _ in 0..300 {
+                pipeline.step(
+                    &gravity,
+                    &integration_parameters,
+                    &mut broad_phase,
+                    &mut narrow_phase,
+                    &mut bodies,
+                    &mut colliders,
+                    &mut joints,
+                    &mut user_constraints,
+                    &mut ccd,
+                    &(),
+                    &(),
+                );
+
+                let ratio = total_energy(&bodies) / initial_energy;
+                min_ratio = min_ratio.min(ratio);
+                max_ratio = max_ratio.max(ratio);
+            }
+
+            (min_ratio, max_ratio)
+        }
+
+        let (post_solve_min, post_solve_max) = energy_ratios(RestitutionModel::PostSolve);
+        assert!(post_solve_min >= 0.99);
+        assert!(post_solve_max <= 1.01);
+
+        // The balls collide with each other several times, including three balls at once, and
+        // the post-solve restitution stays as close to the initial energy as the restitution of
+        // the right-hand side of the constraints on the same scene.
+        let (rhs_min, rhs_max) = energy_ratios(RestitutionModel::Rhs);
+        let drift = |(min, max): (Real, Real)| (1.0 - min).max(max - 1.0);
+        assert!(drift((post_solve_min, post_solve_max)) <= drift((rhs_min, rhs_max)) + 1.0e-3);
+    }
+
+    #[test]
+    fn gravity_compensation_on_kinematic_elevator() {
+        use crate::math::{Isometry, Real};
+
+        fn elevator_penetration(max_velocity_iterations: usize, compensate: bool) -> Real {
+            let mut pipeline = PhysicsPipeline::new();
+            let gravity = Vector::y() * -9.81;
+            let integration_parameters = IntegrationParameters {
+                max_velocity_iterations,
+                gravity_compensation_on_ground_contacts: compensate,
+                ..IntegrationParameters::default()
+            };
+            let mut broad_phase = BroadPhase::new();
+            let mut narrow_phase = NarrowPhase::new();
+            let mut bodies = RigidBodySet::new();
+            let mut colliders = ColliderSet::new();
+            let mut ccd = CCDSolver::new();
+            let mut joints = JointSet::new();
+            let mut user_constraints = UserConstraintSet::new();
+
+            #[cfg(feature = "dim2")]
+            let (platform_shape, box_shape, box_pos) = (
+                ColliderBuilder::cuboid(2.0, 0.5),
+                ColliderBuilder::cuboid(0.5, 0.5),
+                Isometry::translation(0.0, 1.0),
+            );
+            #[cfg(feature = "dim3")]
+            let (platform_shape, box_shape, box_pos) = (
+                ColliderBuilder::cuboid(2.0, 0.5, 2.0),
+                ColliderBuilder::cuboid(0.5, 0.5, 0.5),
+                Isometry::translation(0.0, 1.0, 0.0),
+            );
+
+            let platform = bodies.insert(RigidBodyBuilder::new_kinematic().build());
+            colliders.insert(platform_shape.build(), platform, &mut bodies);
+
+            let rb = RigidBodyBuilder::new_dynamic().position(box_pos).build();
+            let heavy_box = bodies.insert(rb);
+            colliders.insert(box_shape.density(1000.0).build(), heavy_box, &mut bodies);
+
+            for _ in 0..120 {
+                let mut platform_pos = *bodies[platform].position();
+                platform_pos.translation.vector.y += 0.01;
+                bodies[platform].set_next_kinematic_position(platform_pos);
+
+                pipeline.step(
+                    &gravity,
+                    &integration_parameters,
+                    &mut broad_phase,
+                    &mut narrow_phase,
+                    &mut bodies,
+                    &mut colliders,
+                    &mut joints,
+                    &mut user_constraints,
+                    &mut ccd,
+                    &(),
+                    &(),
+                );
+            }
+
+            let platform_top = bodies[platform].position().translation.vector.y + 0.5;
+            let box_bottom = bodies[heavy_box].position().translation.vector.y - 0.5;
+            platform_top - box_bottom
+        }
+
+        let reference = elevator_penetration(16, false);
+        let compensated = elevator_penetration(4, true);
+        assert!((reference - compensated).abs() <= 1.0e-3);
+    }
+
+    #[test]
+    fn pipeline_limits_drop_excess_pairs_and_contacts() {
+        use crate::geometry::{ContactEvent, IntersectionEvent};
+        use crate::pipeline::{EventHandler, PipelineLimits, PipelineLimitsReport};
+        use std::sync::Mutex;
+
+        struct LimitsRecorder(Mutex<Vec<PipelineLimitsReport>>);
+
+        impl EventHandler for LimitsRecorder {
+            fn handle_intersection_event(&self, _event: IntersectionEvent) {}
+            fn handle_contact_event(&self, _event: ContactEvent) {}
+            fn handle_pipeline_limits_report(&self, report: PipelineLimitsReport) {
+                self.0.lock().unwrap().push(report);
+            }
+        }
+
+        let mut pipeline = PhysicsPipeline::new();
+        let integration_parameters = IntegrationParameters {
+            pipeline_limits: PipelineLimits {
+                max_contact_pairs: 100,
+                max_manifold_points_total: 50,
+                ..PipelineLimits::default()
+            },
+            ..IntegrationParameters::default()
+        };
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+        let events = LimitsRecorder(Mutex::new(Vec::new()));
+
+        let num_balls = 60;
+        for _ in 0..num_balls {
+            let handle = bodies.insert(RigidBodyBuilder::new_dynamic().build());
+            colliders.insert(ColliderBuilder::ball(0.5).build(), handle, &mut bodies);
+        }
+
+        pipeline.step(
+            &Vector::zeros(),
+            &integration_parameters,
+            &mut broad_phase,
+            &mut narrow_phase,
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            &mut user_constraints,
+            &mut CCDSolver::new(),
+            &(),
+            &events,
+        );
+
+        assert!(narrow_phase.contact_pairs().count() <= 100);
+        let num_solver_contacts: usize = narrow_phase
+            .contact_pairs()
+            .flat_map(|pair| pair.manifolds.iter())
+            .map(|manifold| manifold.data.solver_contacts.len())
+            .sum();
+        assert!(num_solver_contacts <= 50);
+
+        let reports = events.0.into_inner().unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(
+            reports[0].num_contact_pairs,
+            num_balls * (num_balls - 1) / 2
+        );
+        assert_eq!(
+            reports[0].num_dropped_contact_pairs,
+            num_balls * (num_balls - 1) / 2 - 100
+        );
+        assert!(!reports[0].worst_colliders.is_empty());
+    }
+
+    #[test]
+    fn removed_jointed_bodies_respawn_with_identical_motion() {
+        use crate::dynamics::BallJoint;
+        use crate::math::{Isometry, Point, Real};
+
+        fn simulate(respawn: bool) -> Vec<Isometry<Real>> {
+            let mut pipeline = PhysicsPipeline::new();
+            let gravity = Vector::y() * -9.81;
+            let integration_parameters = IntegrationParameters::default();
+            let mut broad_phase = BroadPhase::new();
+            let mut narrow_phase = NarrowPhase::new();
+            let mut bodies = RigidBodySet::new();
+            let mut colliders = ColliderSet::new();
+            let mut ccd = CCDSolver::new();
+            let mut joints = JointSet::new();
+            let mut user_constraints = UserConstraintSet::new();
+
+            #[cfg(feature = "dim2")]
+            let (rb1, rb2) = (
+                RigidBodyBuilder::new_dynamic().linvel(2.0, 1.0),
+                RigidBodyBuilder::new_dynamic().translation(1.5, 0.0),
+            );
+            #[cfg(feature = "dim3")]
+            let (rb1, rb2) = (
+                RigidBodyBuilder::new_dynamic().linvel(2.0, 1.0, -1.0),
+                RigidBodyBuilder::new_dynamic().translation(1.5, 0.0, 0.0),
+            );
+            let mut h1 = bodies.insert(rb1.build());
+            let mut h2 = bodies.insert(rb2.build());
+            colliders.insert(ColliderBuilder::ball(0.5).build(), h1, &mut bodies);
+            colliders.insert(ColliderBuilder::ball(0.3).build(), h2, &mut bodies);
+            let joint = BallJoint::new(Point::origin(), Point::from(Vector::x() * -1.5));
+            joints.insert(&mut bodies, h1, h2, joint);
+
+            let mut step =
+                |bodies: &mut RigidBodySet, colliders: &mut ColliderSet, joints: &mut JointSet| {
+                    pipeline.step(
+                        &gravity,
+                        &integration_parameters,
+                        &mut broad_phase,
+                        &mut narrow_phase,
+                        bodies,
+                        colliders,
+                        joints,
+                        &mut user_constraints,
+                        &mut ccd,
+                        &(),
+                        &(),
+                    )
+                };
+
+            for _ in 0..10 {
+                step(&mut bodies, &mut colliders, &mut joints);
+            }
+
+            if respawn {
+                let mut removed1 = bodies
+                    .remove_and_return(h1, &mut colliders, &mut joints)
+                    .unwrap();
+                let removed2 = bodies
+                    .remove_and_return(h2, &mut colliders, &mut joints)
+                    .unwrap();
+                assert_eq!(removed1.colliders.len(), 1);
+                assert_eq!(removed1.joints.len(), 1);
+                assert_eq!(removed1.joints[0].0, h2);
+                assert!(removed2.joints.is_empty());
+                assert!(bodies.is_empty() && colliders.len() == 0 && joints.len() == 0);
+
+                h2 = bodies
+                    .insert_removed(removed2, &mut colliders, &mut joints)
+                    .body;
+                removed1.joints[0].0 = h2;
+                let inserted1 = bodies.insert_removed(removed1, &mut colliders, &mut joints);
+                h1 = inserted1.body;
+                assert_eq!(inserted1.colliders.len(), 1);
+                assert!(inserted1.joints[0].is_ok());
+            }
+
+            for _ in 0..30 {
+                step(&mut bodies, &mut colliders, &mut joints);
+            }
+
+            let positions = vec![*bodies[h1].position(), *bodies[h2].position()];
+
+            if respawn {
+                // The joints attached to a body that no longer exists are given back.
+                let removed1 = bodies
+                    .remove_and_return(h1, &mut colliders, &mut joints)
+                    .unwrap();
+                bodies.remove(h2, &mut colliders, &mut joints);
+                let inserted1 = bodies.insert_removed(removed1, &mut colliders, &mut joints);
+                assert!(matches!(inserted1.joints[..], [Err((other, _))] if other == h2));
+                assert_eq!(joints.len(), 0);
+            }
+
+            positions
+        }
+
+        let reference = simulate(false);
+        let respawned = simulate(true);
+
+        for (pos1, pos2) in reference.iter().zip(respawned.iter()) {
+            assert!((pos1.translation.vector - pos2.translation.vector).norm() < 1.0e-4);
+        }
+    }
+
+    #[test]
+    fn rolling_resistance_per_heightfield_cell() {
+        use crate::math::Real;
+        use crate::pipeline::{ContactModificationContext, PhysicsHooks, PhysicsHooksFlags};
+
+        const NUM_CELLS: usize = 10;
+        const GROUND_SIZE: Real = 100.0;
+
+        // Asphalt on the cells with negative x coordinates, sand on the others.
+        struct TerrainMaterials([Real; NUM_CELLS]);
+
+        impl PhysicsHooks for TerrainMaterials {
+            fn active_hooks(&self) -> PhysicsHooksFlags {
+                PhysicsHooksFlags::MODIFY_SOLVER_CONTACTS
+            }
+
+            fn modify_solver_contacts(&self, context: &mut ContactModificationContext) {
+                for contact in context.solver_contacts.iter_mut() {
+                    let cell = ((contact.point.x / GROUND_SIZE + 0.5) * NUM_CELLS as Real) as usize;
+                    contact.rolling_resistance = self.0[cell.min(NUM_CELLS - 1)];
+                }
+            }
+        }
+
+        let mut materials = [0.001; NUM_CELLS];
+        for material in &mut materials[NUM_CELLS / 2..] {
+            *material = 0.05;
+        }
+        let hooks = TerrainMaterials(materials);
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        #[cfg(feature = "dim2")]
+        let heightfield = ColliderBuilder::heightfield(
+            na::DVector::zeros(NUM_CELLS + 1),
+            Vector::new(GROUND_SIZE, 1.0),
+        );
+        #[cfg(feature = "dim3")]
+        let heightfield = ColliderBuilder::heightfield(
+            na::DMatrix::zeros(NUM_CELLS + 1, NUM_CELLS + 1),
+            Vector::new(GROUND_SIZE, 1.0, GROUND_SIZE),
+        );
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        let co = heightfield.modify_solver_contacts(true).build();
+        colliders.insert(co, ground, &mut bodies);
+
+        // Two balls rolling away from each other, one on each half of the heightfield.
+        let (radius, speed) = (0.5, 2.0);
+        let mut balls = Vec::new();
+        for dir in &[-1.0, 1.0] {
+            #[cfg(feature = "dim2")]
+            let rb = RigidBodyBuilder::new_dynamic()
+                .translation(dir * 20.0, radius)
+                .linvel(dir * speed, 0.0)
+                .angvel(-dir * speed / radius);
+            #[cfg(feature = "dim3")]
+            let rb = RigidBodyBuilder::new_dynamic()
+                .translation(dir * 20.0, radius, 0.0)
+                .linvel(dir * speed, 0.0, 0.0)
+                .angvel(Vector::z() * (-dir * speed / radius));
+            let handle = bodies.insert(rb.build());
+            colliders.insert(ColliderBuilder::ball(radius).build(), handle, &mut bodies);
+            balls.push(handle);
+        }
+
+        for _ in 0..60 {
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &hooks,
+                &(),
+            );
+        }
+
+        let asphalt_speed = bodies[balls[0]].linvel().x.abs();
+        let sand_speed = bodies[balls[1]].linvel().x.abs();
+        assert!(asphalt_speed > 1.8 && asphalt_speed < speed);
+        assert!(sand_speed < asphalt_speed - 0.4);
+    }
+
+    #[test]
+    #[cfg(feature = "dim3")]
+    fn cube_slides_on_trimesh_without_catching_internal_edges() {
+        use crate::geometry::TriMeshFlags;
+        use crate::math::{Point, Real};
+
+        // A flat 60x60 mesh made of 5000 triangles.
+        let n = 50;
+        let cell_size = 60.0 / n as Real;
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+
+        for i in 0..=n {
+            for j in 0..=n {
+                let x = i as Real * cell_size - 30.0;
+                let z = j as Real * cell_size - 30.0;
+                vertices.push(Point::new(x, 0.0, z));
+            }
+        }
+
+        for i in 0..n {
+            for j in 0..n {
+                let v00 = (i * (n + 1) + j) as u32;
+                let v01 = v00 + 1;
+                let v10 = v00 + n as u32 + 1;
+                let v11 = v10 + 1;
+                indices.push([v00, v01, v10]);
+                indices.push([v10, v01, v11]);
+            }
+        }
+
+        assert_eq!(indices.len(), 5000);
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        let co = ColliderBuilder::trimesh_with_flags(
+            vertices,
+            indices,
+            TriMeshFlags::FIX_INTERNAL_EDGES,
+        )
+        .friction(0.0)
+        .build();
+        colliders.insert(co, ground, &mut bodies);
+
+        let rb = RigidBodyBuilder::new_dynamic()
+            .translation(-25.0, 0.5, 0.3)
+            .build();
+        let cube = bodies.insert(rb);
+        let co = ColliderBuilder::cuboid(0.5, 0.5, 0.5).friction(0.0).build();
+        colliders.insert(co, cube, &mut bodies);
+
+        let mut step = |bodies: &mut RigidBodySet, colliders: &mut ColliderSet| {
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                bodies,
+                colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            );
+        };
+
+        // Let the cube settle before pushing it.
+        for _ in 0..20 {
+            step(&mut bodies, &mut colliders);
+        }
+
+        bodies
+            .get_mut(cube)
+            .unwrap()
+            .set_linvel(Vector::x() * 10.0, true);
+        let mut max_vertical_vel: Real = 0.0;
+
+        for _ in 0..300 {
+            step(&mut bodies, &mut colliders);
+            max_vertical_vel = max_vertical_vel.max(bodies[cube].linvel().y.abs());
+        }
+
+        assert!(bodies[cube].position().translation.x > 24.0);
+        assert!(max_vertical_vel < 1.0e-3);
+    }
+
+    #[test]
+    fn pipeline_stats_follow_bodies_additions_removals_and_sleep() {
+        use crate::math::Real;
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_collider = ColliderBuilder::cuboid(20.0, 0.5).build();
+        #[cfg(feature = "dim3")]
+        let ground_collider = ColliderBuilder::cuboid(20.0, 0.5, 20.0).build();
+        colliders.insert(ground_collider, ground, &mut bodies);
+
+        // Ten balls resting on the ground, spaced by `2.0` along the `x` axis.
+        let add_balls = |bodies: &mut RigidBodySet, colliders: &mut ColliderSet, shift: Real| {
+            let mut handles = Vec::new();
+            for i in 0..10 {
+                let x = i as Real * 2.0 - 10.0 + shift;
+                #[cfg(feature = "dim2")]
+                let rb = RigidBodyBuilder::new_dynamic().translation(x, 1.0);
+                #[cfg(feature = "dim3")]
+                let rb = RigidBodyBuilder::new_dynamic().translation(x, 1.0, 0.0);
+                let handle = bodies.insert(rb.build());
+                colliders.insert(ColliderBuilder::ball(0.5).build(), handle, bodies);
+                handles.push(handle);
+            }
+            handles
+        };
+
+        macro_rules! step {
+            () => {
+                pipeline.step(
+                    &gravity,
+                    &integration_parameters,
+                    &mut broad_phase,
+                    &mut narrow_phase,
+                    &mut bodies,
+                    &mut colliders,
+                    &mut joints,
+                    &mut user_constraints,
+                    &mut ccd,
+                    &(),
+                    &(),
+                )
+            };
+        }
+
+        macro_rules! stats {
+            ($detailed: expr) => {
+                pipeline.stats(
+                    $detailed,
+                    &mut broad_phase,
+                    &mut narrow_phase,
+                    &bodies,
+                    &colliders,
+                )
+            };
+        }
+
+        let _ = add_balls(&mut bodies, &mut colliders, 0.0);
+        step!();
+        let stats1 = stats!(true);
+        assert_eq!(stats1.bodies.num_bodies, 11);
+        assert_eq!(stats1.colliders.num_colliders, 11);
+        assert_eq!(stats1.narrow_phase.num_contact_pairs, 10);
+        assert!(stats1.narrow_phase.num_manifolds >= 10);
+        assert!(stats1.narrow_phase.num_contact_points >= 10);
+        assert!(stats1.solver.num_contact_constraints > 0);
+        assert!(stats1.broad_phase.num_proxies >= 11);
+        assert!(stats1.broad_phase.bytes > 0);
+        assert!(stats1.narrow_phase.bytes > 0);
+        assert!(stats1.solver.bytes > 0);
+
+        // Adding balls touching the existing ones increases the counters.
+        let added = add_balls(&mut bodies, &mut colliders, 1.0);
+        step!();
+        let stats2 = stats!(false);
+        assert_eq!(stats2.bodies.num_bodies, 21);
+        assert!(stats2.narrow_phase.num_contact_pairs > stats1.narrow_phase.num_contact_pairs);
+        assert!(stats2.narrow_phase.num_contact_points > stats1.narrow_phase.num_contact_points);
+        assert!(stats2.broad_phase.num_proxies > stats1.broad_phase.num_proxies);
+        assert_eq!(stats2.narrow_phase.bytes, 0);
+
+        // Removing them decreases the counters, but the peaks remember them.
+        for handle in added {
+            bodies.remove(handle, &mut colliders, &mut joints);
+        }
+        step!();
+        let stats3 = stats!(false);
+        assert_eq!(stats3.bodies.num_bodies, 11);
+        assert_eq!(stats3.narrow_phase.num_contact_pairs, 10);
+        assert!(stats3.broad_phase.num_proxies < stats2.broad_phase.num_proxies);
+        assert!(stats3.narrow_phase.peak_contact_pairs >= stats2.narrow_phase.num_contact_pairs);
+        assert!(stats3.broad_phase.peak_proxies >= stats2.broad_phase.num_proxies);
+
+        // Once the balls are asleep, the solver no longer generates constraints.
+        for _ in 0..500 {
+            step!();
+        }
+        let stats4 = stats!(false);
+        assert_eq!(stats4.bodies.num_active_dynamic, 0);
+        assert_eq!(stats4.solver.num_contact_constraints, 0);
+        assert!(stats4.solver.peak_contact_constraints > 0);
+        assert_eq!(stats4.narrow_phase.num_contact_pairs, 10);
+    }
+
+    #[test]
+    fn projectiles_hit_thin_wall_and_ignore_each_other() {
+        use crate::geometry::{ColliderHandle, ContactEvent, IntersectionEvent};
+        use crate::math::Real;
+        use crate::pipeline::EventHandler;
+        use std::collections::HashSet;
+        use std::sync::Mutex;
+
+        struct HitRecorder(Mutex<HashSet<(ColliderHandle, ColliderHandle)>>);
+
+        impl EventHandler for HitRecorder {
+            fn handle_intersection_event(&self, _event: IntersectionEvent) {}
+            fn handle_contact_event(&self, event: ContactEvent) {
+                if let ContactEvent::Started(h1, h2) = event {
+                    let _ = self.0.lock().unwrap().insert((h1, h2));
+                }
+            }
+        }
+
+        // Shoots 1000 overlapping balls at 300m/s toward a thin wall. Returns the set
+        // of balls that touched the wall, the largest `x` coordinate reached by a ball,
+        // and the total number of contact points computed between two balls.
+        fn shoot(projectiles: bool) -> (HashSet<ColliderHandle>, Real, usize) {
+            let mut pipeline = PhysicsPipeline::new();
+            let integration_parameters = IntegrationParameters::default();
+            let mut broad_phase = BroadPhase::new();
+            let mut narrow_phase = NarrowPhase::new();
+            let mut bodies = RigidBodySet::new();
+            let mut colliders = ColliderSet::new();
+            let mut ccd = CCDSolver::new();
+            let mut joints = JointSet::new();
+            let mut user_constraints = UserConstraintSet::new();
+            let events = HitRecorder(Mutex::new(HashSet::new()));
+
+            #[cfg(feature = "dim2")]
+            let rb = RigidBodyBuilder::new_static().translation(10.0, 75.0);
+            #[cfg(feature = "dim3")]
+            let rb = RigidBodyBuilder::new_static().translation(10.0, 75.0, 0.0);
+            let wall = bodies.insert(rb.build());
+            #[cfg(feature = "dim2")]
+            let wall_collider = ColliderBuilder::cuboid(0.05, 100.0).build();
+            #[cfg(feature = "dim3")]
+            let wall_collider = ColliderBuilder::cuboid(0.05, 100.0, 1.0).build();
+            let wall_collider = colliders.insert(wall_collider, wall, &mut bodies);
+
+            let mut balls = Vec::new();
+            for i in 0..1000 {
+                let y = i as Real * 0.15;
+                let builder = if projectiles {
+                    RigidBodyBuilder::projectile()
+                } else {
+                    RigidBodyBuilder::new_dynamic()
+                };
+                #[cfg(feature = "dim2")]
+                let rb = builder.translation(0.0, y).linvel(300.0, 0.0);
+                #[cfg(feature = "dim3")]
+                let rb = builder.translation(0.0, y, 0.0).linvel(300.0, 0.0, 0.0);
+                let handle = bodies.insert(rb.build());
+                let collider = if projectiles {
+                    ColliderBuilder::projectile(0.1)
+                } else {
+                    ColliderBuilder::ball(0.1)
+                };
+                balls.push(colliders.insert(collider.build(), handle, &mut bodies));
+            }
+
+            let mut num_ball_contact_points = 0;
+
+            for _ in 0..5 {
+                pipeline.step(
+                    &Vector::zeros(),
+                    &integration_parameters,
+                    &mut broad_phase,
+                    &mut narrow_phase,
+                    &mut bodies,
+                    &mut colliders,
+                    &mut joints,
+                    &mut user_constraints,
+                    &mut ccd,
+                    &(),
+                    &events,
+                );
+                num_ball_contact_points += narrow_phase
+                    .contact_pairs()
+                    .filter(|pair| {
+                        pair.pair.collider1 != wall_collider && pair.pair.collider2 != wall_collider
+                    })
+                    .flat_map(|pair| pair.manifolds.iter())
+                    .map(|manifold| manifold.points.len())
+                    .sum::<usize>();
+            }
+
+            let hits = events
+                .0
+                .into_inner()
+                .unwrap()
+                .into_iter()
+                .filter_map(|(h1, h2)| {
+                    if h1 == wall_collider {
+                        Some(h2)
+                    } else if h2 == wall_collider {
+                        Some(h1)
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+            let max_x = balls
+                .iter()
+                .map(|h| colliders[*h].position().translation.vector.x)
+                .fold(Real::MIN, Real::max);
+
+            (hits, max_x, num_ball_contact_points)
+        }
+
+        let (hits, max_x, projectile_contact_points) = shoot(true);
+        assert_eq!(hits.len(), 1000);
+        assert!(max_x < 10.0);
+        assert_eq!(projectile_contact_points, 0);
+
+        // Naive dynamic balls spend time computing contacts between themselves.
+        let (_, _, naive_contact_points) = shoot(false);
+        assert!(naive_contact_points > 0);
+    }
+
+    #[test]
+    fn island_contacts_carry_the_tower_weight() {
+        use crate::dynamics::BallJoint;
+        use crate::math::{Point, Real};
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_collider = ColliderBuilder::cuboid(10.0, 0.5).build();
+        #[cfg(feature = "dim3")]
+        let ground_collider = ColliderBuilder::cuboid(10.0, 0.5, 10.0).build();
+        let ground_collider = colliders.insert(ground_collider, ground, &mut bodies);
+
+        // A tower of five unit boxes, with the two topmost boxes attached by a ball joint.
+        let mut tower = Vec::new();
+        for i in 0..5 {
+            let y = 1.0 + i as Real;
+            #[cfg(feature = "dim2")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(0.0, y);
+            #[cfg(feature = "dim3")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(0.0, y, 0.0);
+            let handle = bodies.insert(rb.can_sleep(false).build());
+            #[cfg(feature = "dim2")]
+            let collider = ColliderBuilder::cuboid(0.5, 0.5).build();
+            #[cfg(feature = "dim3")]
+            let collider = ColliderBuilder::cuboid(0.5, 0.5, 0.5).build();
+            colliders.insert(collider, handle, &mut bodies);
+            tower.push(handle);
+        }
+
+        let joint = BallJoint::new(
+            Point::from(Vector::y() * 0.5),
+            Point::from(Vector::y() * -0.5),
+        );
+        joints.insert(&mut bodies, tower[3], tower[4], joint);
+
+        for _ in 0..300 {
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            );
+        }
+
+        let island_id = bodies[tower[4]].active_island_id;
+        assert!(tower
+            .iter()
+            .all(|h| bodies[*h].active_island_id == island_id));
+
+        // Four box-box pairs and one box-ground pair, each yielded once.
+        let island_contacts: Vec<_> = narrow_phase.island_contacts(island_id, &bodies).collect();
+        assert_eq!(island_contacts.len(), 5);
+        assert_eq!(joints.island_joints(island_id, &bodies).count(), 1);
+
+        let ground_impulse: Real = island_contacts
+            .iter()
+            .filter(|pair| {
+                pair.pair.collider1 == ground_collider || pair.pair.collider2 == ground_collider
+            })
+            .flat_map(|pair| pair.manifolds.iter())
+            .flat_map(|manifold| manifold.points.iter())
+            .map(|contact| contact.data.impulse)
+            .sum();
+        let tower_mass: Real = tower.iter().map(|h| bodies[*h].mass()).sum();
+        let expected_impulse = tower_mass * 9.81 * integration_parameters.dt;
+
+        assert!((ground_impulse - expected_impulse).abs() < expected_impulse * 0.05);
+    }
+
+    #[test]
+    #[cfg(feature = "dim3")]
+    fn door_bounces_off_its_limit_or_slows_down_in_the_softness_band() {
+        use crate::dynamics::RevoluteJoint;
+        use crate::math::{Point, Real};
+
+        // Returns the door's angle and angular velocity at each step.
+        fn swing_door(restitution: Real, softness_band: Real) -> Vec<(Real, Real)> {
+            let mut pipeline = PhysicsPipeline::new();
+            let gravity = Vector::zeros();
+            let integration_parameters = IntegrationParameters::default();
+            let mut broad_phase = BroadPhase::new();
+            let mut narrow_phase = NarrowPhase::new();
+            let mut bodies = RigidBodySet::new();
+            let mut colliders = ColliderSet::new();
+            let mut ccd = CCDSolver::new();
+            let mut joints = JointSet::new();
+            let mut user_constraints = UserConstraintSet::new();
+
+            let frame = bodies.insert(RigidBodyBuilder::new_static().build());
+            // The door rotates at 2 rad/s around its hinge, placed at the origin.
+            let door = bodies.insert(
+                RigidBodyBuilder::new_dynamic()
+                    .translation(1.0, 0.0, 0.0)
+                    .linvel(0.0, 0.0, -2.0)
+                    .angvel(Vector::y() * 2.0)
+                    .build(),
+            );
+            colliders.insert(
+                ColliderBuilder::cuboid(1.0, 1.0, 0.05).build(),
+                door,
+                &mut bodies,
+            );
+
+            let mut joint = RevoluteJoint::new(
+                Point::origin(),
+                Vector::y_axis(),
+                Point::new(-1.0, 0.0, 0.0),
+                Vector::y_axis(),
+            );
+            joint.limits_enabled = true;
+            joint.limits = [-1.0, 0.5];
+            joint.limit_restitution = restitution;
+            joint.limit_softness_band = softness_band;
+            joints.insert(&mut bodies, frame, door, joint);
+
+            (0..60)
+                .map(|_| {
+                    pipeline.step(
+                        &gravity,
+                        &integration_parameters,
+                        &mut broad_phase,
+                        &mut narrow_phase,
+                        &mut bodies,
+                        &mut colliders,
+                        &mut joints,
+                        &mut user_constraints,
+                        &mut ccd,
+                        &(),
+                        &(),
+                    );
+                    let door = &bodies[door];
+                    (door.position().rotation.scaled_axis().y, door.angvel().y)
+                })
+                .collect()
+        }
+
+        // The door bounces off its stop with half its angular speed.
+        let bouncing = swing_door(0.5, 0.0);
+        let bounce_angvel = bouncing
+            .iter()
+            .map(|(_, angvel)| *angvel)
+            .find(|angvel| *angvel < 0.0)
+            .unwrap();
+        assert!((bounce_angvel + 1.0).abs() < 0.1);
+
+        // The door decelerates smoothly within the softness band, without any bounce.
+        let soft = swing_door(0.0, 0.3);
+        assert!(soft.iter().all(|(_, angvel)| *angvel > -1.0e-3));
+        assert!(soft.iter().all(|(angle, _)| *angle < 0.5 + 1.0e-2));
+        assert!(soft.windows(2).all(|w| w[1].1 <= w[0].1 + 1.0e-3));
+        let (last_angle, last_angvel) = soft[soft.len() - 1];
+        assert!(last_angle > 0.2);
+        assert!(last_angvel < 0.1);
+    }
+
+    #[test]
+    #[cfg(all(feature = "parallel", feature = "enhanced-determinism"))]
+    fn parallel_solver_results_do_not_depend_on_the_number_of_threads() {
+        use crate::dynamics::BallJoint;
+        use crate::math::{Isometry, Point, Real};
+
+        fn simulate() -> Vec<Isometry<Real>> {
+            let mut pipeline = PhysicsPipeline::new();
+            let gravity = Vector::y() * -9.81;
+            let integration_parameters = IntegrationParameters::default();
+            let mut broad_phase = BroadPhase::new();
+            let mut narrow_phase = NarrowPhase::new();
+            let mut bodies = RigidBodySet::new();
+            let mut colliders = ColliderSet::new();
+            let mut ccd = CCDSolver::new();
+            let mut joints = JointSet::new();
+            let mut user_constraints = UserConstraintSet::new();
+            let mut handles = Vec::new();
+
+            let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+            #[cfg(feature = "dim2")]
+            let ground_collider = ColliderBuilder::cuboid(50.0, 0.5).build();
+            #[cfg(feature = "dim3")]
+            let ground_collider = ColliderBuilder::cuboid(50.0, 0.5, 50.0).build();
+            colliders.insert(ground_collider, ground, &mut bodies);
+
+            // A pyramid of boxes, where each box touches several other boxes.
+            for i in 0..10 {
+                for j in i..10 {
+                    let x = j as Real * 1.05 - i as Real * 0.525 - 5.0;
+                    let y = i as Real * 1.0 + 1.0;
+                    #[cfg(feature = "dim2")]
+                    let rb = RigidBodyBuilder::new_dynamic().translation(x, y);
+                    #[cfg(feature = "dim3")]
+                    let rb = RigidBodyBuilder::new_dynamic().translation(x, y, 0.0);
+                    let handle = bodies.insert(rb.build());
+                    #[cfg(feature = "dim2")]
+                    let collider = ColliderBuilder::cuboid(0.5, 0.5).build();
+                    #[cfg(feature = "dim3")]
+                    let collider = ColliderBuilder::cuboid(0.5, 0.5, 0.5).build();
+                    colliders.insert(collider, handle, &mut bodies);
+                    handles.push(handle);
+                }
+            }
+
+            // A chain of balls falling on the pyramid.
+            let mut prev = None;
+            for i in 0..10 {
+                let x = i as Real - 5.0;
+                #[cfg(feature = "dim2")]
+                let rb = RigidBodyBuilder::new_dynamic().translation(x, 15.0);
+                #[cfg(feature = "dim3")]
+                let rb = RigidBodyBuilder::new_dynamic().translation(x, 15.0, 0.0);
+                let handle = bodies.insert(rb.build());
+                colliders.insert(ColliderBuilder::ball(0.4).build(), handle, &mut bodies);
+
+                if let Some(prev) = prev {
+                    let joint = BallJoint::new(Point::origin(), Point::from(Vector::x() * -1.0));
+                    joints.insert(&mut bodies, prev, handle, joint);
+                }
+
+                prev = Some(handle);
+                handles.push(handle);
+            }
+
+            for _ in 0..200 {
+                pipeline.step(
+                    &gravity,
+                    &integration_parameters,
+                    &mut broad_phase,
+                    &mut narrow_phase,
+                    &mut bodies,
+                    &mut colliders,
+                    &mut joints,
+                    &mut user_constraints,
+                    &mut ccd,
+                    &(),
+                    &(),
+                );
+            }
+
+            handles.iter().map(|h| *bodies[*h].position()).collect()
+        }
+
+        let results: Vec<_> = [1, 4, 16]
+            .iter()
+            .map(|num_threads| {
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(*num_threads)
+                    .build()
+                    .unwrap()
+                    .install(simulate)
+            })
+            .collect();
+
+        assert_eq!(results[0], results[1]);
+        assert_eq!(results[0], results[2]);
+    }
+
+    #[test]
+    fn copy_positions_into_buffers() {
+        use crate::math::{Isometry, Real};
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        let mut handles = vec![ground];
+        for i in 0..5 {
+            #[cfg(feature = "dim2")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(i as Real * 2.0, 10.0);
+            #[cfg(feature = "dim3")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(i as Real * 2.0, 10.0, 0.0);
+            let handle = bodies.insert(rb.build());
+            colliders.insert(ColliderBuilder::ball(0.5).build(), handle, &mut bodies);
+            handles.push(handle);
+        }
+
+        for _ in 0..10 {
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            );
+        }
+
+        let mut out = vec![Isometry::identity(); handles.len()];
+        bodies.copy_positions_into(&handles, &mut out);
+        for (handle, pos) in handles.iter().zip(out.iter()) {
+            assert_eq!(pos, bodies[*handle].position());
+        }
+
+        #[cfg(feature = "parallel")]
+        {
+            let mut par_out = vec![Isometry::identity(); handles.len()];
+            bodies.par_copy_positions_into(&handles, &mut par_out);
+            assert_eq!(par_out, out);
+        }
+
+        let mut active = Vec::new();
+        bodies.copy_active_positions_into(&mut active, None);
+        assert_eq!(active.len(), 5);
+        assert!(active.iter().all(|(h, pos)| pos == bodies[*h].position()));
+
+        // The interpolated positions lie between the previous and current positions.
+        bodies.copy_active_positions_into(&mut active, Some(0.0));
+        assert!(active
+            .iter()
+            .all(|(h, pos)| pos == bodies[*h].previous_position()));
+        bodies.copy_active_positions_into(&mut active, Some(0.5));
+        for (handle, pos) in &active {
+            let rb = &bodies[*handle];
+            let prev = rb.previous_position().translation.vector;
+            let curr = rb.position().translation.vector;
+            assert!(prev.y > curr.y);
+            assert!((pos.translation.vector - (prev + curr) / 2.0).norm() < 1.0e-5);
+        }
+    }
+
+    fn simulate_stack_with_priorities(
+        player: usize,
+        player_priority: u8,
+        other_priority: u8,
+        max_velocity_iterations: usize,
+        num_steps: usize,
+    ) -> Vec<crate::math::Isometry<crate::math::Real>> {
+        use crate::math::Real;
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let mut integration_parameters = IntegrationParameters::default();
+        integration_parameters.max_velocity_iterations = max_velocity_iterations;
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        #[cfg(feature = "dim2")]
+        let (ground, ground_shape) = (
+            RigidBodyBuilder::new_static().translation(0.0, -0.5),
+            ColliderBuilder::cuboid(10.0, 0.5),
+        );
+        #[cfg(feature = "dim3")]
+        let (ground, ground_shape) = (
+            RigidBodyBuilder::new_static().translation(0.0, -0.5, 0.0),
+            ColliderBuilder::cuboid(10.0, 0.5, 10.0),
+        );
+        let ground = bodies.insert(ground.build());
+        colliders.insert(ground_shape.build(), ground, &mut bodies);
+
+        // A column of 10 boxes, where the `player`-th box is the "player".
+        let mut handles = Vec::new();
+        for i in 0..10 {
+            let priority = if i == player {
+                player_priority
+            } else {
+                other_priority
+            };
+            let y = 0.5 + i as Real;
+            #[cfg(feature = "dim2")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(0.0, y);
+            #[cfg(feature = "dim3")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(0.0, y, 0.0);
+            let handle = bodies.insert(rb.solver_priority(priority).build());
+            #[cfg(feature = "dim2")]
+            let collider = ColliderBuilder::cuboid(0.5, 0.5);
+            #[cfg(feature = "dim3")]
+            let collider = ColliderBuilder::cuboid(0.5, 0.5, 0.5);
+            colliders.insert(collider.build(), handle, &mut bodies);
+            handles.push(handle);
+        }
+
+        for _ in 0..num_steps {
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            );
+        }
+
+        handles.iter().map(|h| *bodies[*h].position()).collect()
+    }
+
+    #[test]
+    fn equal_solver_priorities_do_not_change_the_simulation() {
+        assert_eq!(
+            simulate_stack_with_priorities(3, 0, 0, 4, 100),
+            simulate_stack_with_priorities(3, 7, 7, 4, 100)
+        );
+    }
+
+    #[test]
+    fn high_solver_priority_reduces_penetrations() {
+        // The penetration between the player and the box right below it.
+        // The stack is measured while it is being loaded: once it settles, the penetrations
+        // mostly depend on how the stack jitters, not on the solver ordering.
+        let player_penetration = |player: usize, player_priority: u8| {
+            let positions = simulate_stack_with_priorities(player, player_priority, 0, 1, 3);
+            1.0 - (positions[player].translation.vector.y
+                - positions[player - 1].translation.vector.y)
+        };
+
+        // NOTE: the default contact order may already solve the contacts of some players
+        // after the ones of their neighbors, so we sum the penetrations of several players.
+        let default: crate::math::Real = (1..9).map(|player| player_penetration(player, 0)).sum();
+        let prioritized: crate::math::Real =
+            (1..9).map(|player| player_penetration(player, 1)).sum();
+        assert!(prioritized < default / 2.0);
+    }
+
+    #[test]
+    fn kinematic_velocity_matches_the_analytic_platform_motion() {
+        use crate::dynamics::MassProperties;
+        use crate::math::{AngVector, Isometry, Point, Real};
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters::default();
+        let dt = integration_parameters.dt;
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        // A platform rotating around its local origin, with an offset center of mass.
+        let linvel = Vector::x() * 3.0;
+        #[cfg(feature = "dim2")]
+        let (angvel, local_com, props) = {
+            let local_com = Point::new(2.0, 0.0);
+            (0.5, local_com, MassProperties::new(local_com, 1.0, 1.0))
+        };
+        #[cfg(feature = "dim3")]
+        let (angvel, local_com, props) = {
+            let local_com = Point::new(2.0, 0.0, 0.0);
+            let props = MassProperties::new(local_com, 1.0, Vector::repeat(1.0));
+            (Vector::z() * 0.5, local_com, props)
+        };
+        let pose = |t: Real| Isometry::new(linvel * t, angvel * t);
+
+        let platform = RigidBodyBuilder::new_kinematic()
+            .additional_mass_properties(props)
+            .build();
+        let platform = bodies.insert(platform);
+
+        for i in 0..60 {
+            let t = i as Real * dt;
+            bodies[platform].set_next_kinematic_position(pose(t + dt));
+            assert!(bodies[platform].is_moving());
+
+            // The velocity of the center of mass includes the rotation around the origin.
+            let com = pose(t) * local_com;
+            let next_com = pose(t + dt) * local_com;
+            let (implied_linvel, implied_angvel): (Vector<Real>, AngVector<Real>) =
+                bodies[platform].kinematic_velocity(dt);
+            assert!((implied_linvel - (next_com - com) / dt).norm() < 1.0e-3);
+            #[cfg(feature = "dim2")]
+            assert!((implied_angvel - angvel).abs() < 1.0e-3);
+            #[cfg(feature = "dim3")]
+            assert!((implied_angvel - angvel).norm() < 1.0e-3);
+
+            let half_step = bodies[platform].predicted_position_at(dt / 2.0, dt);
+            let expected = pose(t + dt / 2.0);
+            assert!((half_step.translation.vector - expected.translation.vector).norm() < 1.0e-3);
+            assert!(half_step.rotation.angle_to(&expected.rotation) < 1.0e-3);
+
+            // The prediction time is clamped to the timestep.
+            let clamped = bodies[platform].predicted_position_at(2.0 * dt, dt);
+            let next = *bodies[platform].next_position();
+            assert!((clamped.translation.vector - next.translation.vector).norm() < 1.0e-3);
+
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            );
+        }
+    }
+
+    #[test]
+    fn step_region_suspends_the_bodies_outside_the_regions() {
+        use crate::geometry::AABB;
+        use crate::math::{Point, Real};
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        let mut insert_ball = |x: Real, y: Real| {
+            #[cfg(feature = "dim2")]
+            let rb = RigidBodyBuilder::new_dynamic()
+                .translation(x, y)
+                .linvel(1.0, 0.0);
+            #[cfg(feature = "dim3")]
+            let rb = RigidBodyBuilder::new_dynamic()
+                .translation(x, y, 0.0)
+                .linvel(1.0, 0.0, 0.0);
+            let handle = bodies.insert(rb.build());
+            colliders.insert(ColliderBuilder::ball(0.5).build(), handle, &mut bodies);
+            handle
+        };
+
+        // Two resting balls. Only the top one is inside the region but they touch,
+        // so both must be simulated.
+        let inside = insert_ball(0.0, 1.0);
+        let touching = insert_ball(0.0, 0.0);
+        // A distant ball, outside of the region.
+        let outside = insert_ball(100.0, 0.0);
+
+        // A distant moving kinematic body, outside of the region.
+        #[cfg(feature = "dim2")]
+        let rb = RigidBodyBuilder::new_kinematic().translation(-100.0, 0.0);
+        #[cfg(feature = "dim3")]
+        let rb = RigidBodyBuilder::new_kinematic().translation(-100.0, 0.0, 0.0);
+        let kinematic = bodies.insert(rb.build());
+        colliders.insert(ColliderBuilder::ball(0.5).build(), kinematic, &mut bodies);
+        bodies[kinematic].set_kinematic_velocity(Vector::x(), na::zero());
+
+        #[cfg(feature = "dim2")]
+        let region = AABB::new(Point::new(-1.0, 0.8), Point::new(1.0, 2.0));
+        #[cfg(feature = "dim3")]
+        let region = AABB::new(Point::new(-1.0, 0.8, -1.0), Point::new(1.0, 2.0, 1.0));
+
+        let mut step = |bodies: &mut RigidBodySet, colliders: &mut ColliderSet, in_region: bool| {
+            if in_region {
+                pipeline.step_region(
+                    &[region],
+                    &gravity,
+                    &integration_parameters,
+                    &mut broad_phase,
+                    &mut narrow_phase,
+                    bodies,
+                    colliders,
+                    &mut joints,
+                    &mut user_constraints,
+                    &mut ccd,
+                    &(),
+                    &(),
+                );
+            } else {
+                pipeline.step(
+                    &gravity,
+                    &integration_parameters,
+                    &mut broad_phase,
+                    &mut narrow_phase,
+                    bodies,
+                    colliders,
+                    &mut joints,
+                    &mut user_constraints,
+                    &mut ccd,
+                    &(),
+                    &(),
+                );
+            }
+        };
+
+        // A first regular step so the contact between the two first balls is known.
+        step(&mut bodies, &mut colliders, false);
+        let outside_pos = *bodies[outside].position();
+        let outside_linvel = *bodies[outside].linvel();
+        let touching_pos = *bodies[touching].position();
+        let kinematic_pos = *bodies[kinematic].position();
+
+        for _ in 0..10 {
+            step(&mut bodies, &mut colliders, true);
+        }
+
+        assert_eq!(*bodies[outside].position(), outside_pos);
+        assert_eq!(*bodies[kinematic].position(), kinematic_pos);
+        assert_eq!(*bodies[outside].linvel(), outside_linvel);
+        assert!(!bodies[outside].is_sleeping());
+        assert!(bodies.iter_active_dynamic().any(|(h, _)| h == outside));
+        assert_ne!(*bodies[touching].position(), touching_pos);
+        assert!(bodies[inside].position().translation.y < 1.0);
+
+        // The simulation of the distant ball resumes with a regular step.
+        step(&mut bodies, &mut colliders, false);
+        assert_ne!(*bodies[outside].position(), outside_pos);
+        assert_ne!(*bodies[kinematic].position(), kinematic_pos);
+    }
+
+    #[test]
+    fn contact_data_reports_the_combined_friction() {
+        use crate::dynamics::CoefficientCombineRule;
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let rubber = ColliderBuilder::cuboid(10.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let rubber = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+        let rubber = colliders.insert(rubber.friction(1.0).build(), ground, &mut bodies);
+
+        #[cfg(feature = "dim2")]
+        let (block, ice) = (
+            RigidBodyBuilder::new_dynamic().translation(0.0, 1.0),
+            ColliderBuilder::cuboid(0.5, 0.5),
+        );
+        #[cfg(feature = "dim3")]
+        let (block, ice) = (
+            RigidBodyBuilder::new_dynamic().translation(0.0, 1.0, 0.0),
+            ColliderBuilder::cuboid(0.5, 0.5, 0.5),
+        );
+        let block = bodies.insert(block.build());
+        let ice = ice
+            .friction(0.05)
+            .friction_combine_rule(CoefficientCombineRule::Min)
+            .build();
+        let ice = colliders.insert(ice, block, &mut bodies);
+
+        for _ in 0..10 {
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            );
+        }
+
+        let pair = narrow_phase.contact_pair(ice, rubber).unwrap();
+        let mut num_contacts = 0;
+
+        for manifold in &pair.manifolds {
+            for solver_contact in &manifold.data.solver_contacts {
+                let data = &manifold.points[solver_contact.contact_id as usize].data;
+                assert_eq!(data.friction, 0.05);
+                assert_eq!(data.restitution, 0.0);
+                num_contacts += 1;
+            }
+        }
+
+        assert!(num_contacts > 0);
+    }
+
+    #[test]
+    fn degenerate_mass_bodies_do_not_move_until_a_massive_collider_is_attached() {
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        #[cfg(feature = "dim2")]
+        let rb = RigidBodyBuilder::new_dynamic()
+            .translation(0.0, 10.0)
+            .linvel(1.0, 2.0)
+            .angvel(3.0);
+        #[cfg(feature = "dim3")]
+        let rb = RigidBodyBuilder::new_dynamic()
+            .translation(0.0, 10.0, 0.0)
+            .linvel(1.0, 2.0, 3.0)
+            .angvel(Vector::new(3.0, 2.0, 1.0));
+        let handle = bodies.insert(rb.build());
+
+        // A massless sensor doesn't give any mass to the body.
+        let sensor = ColliderBuilder::ball(0.5).sensor(true).density(0.0).build();
+        colliders.insert(sensor, handle, &mut bodies);
+        assert!(bodies[handle].is_degenerate_mass());
+
+        let initial_pos = *bodies[handle].position();
+        let mut step = |bodies: &mut RigidBodySet, colliders: &mut ColliderSet| {
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                bodies,
+                colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            )
+        };
+
+        step(&mut bodies, &mut colliders);
+        let rb = &bodies[handle];
+        assert_eq!(*rb.position(), initial_pos);
+        assert!(rb.effective_inv_mass.iter().all(|e| e.is_finite()));
+        assert!(rb.linvel().iter().all(|e| e.is_finite()));
+
+        // Attaching a collider with a mass makes the body fall.
+        colliders.insert(ColliderBuilder::ball(0.5).build(), handle, &mut bodies);
+        assert!(!bodies[handle].is_degenerate_mass());
+        bodies[handle].set_linvel(Vector::zeros(), true);
+
+        for _ in 0..10 {
+            step(&mut bodies, &mut colliders);
+        }
+
+        assert!(bodies[handle].position().translation.y < initial_pos.translation.y);
+        assert!(bodies[handle].linvel().y < 0.0);
+    }
+
+    #[test]
+    fn joint_chain_ordering_solves_chains_from_their_anchor() {
+        use crate::dynamics::BallJoint;
+        use crate::math::{Isometry, Point, Real};
+
+        const NUM_LINKS: usize = 40;
+
+        // Simulates a chain attached to a static body. Returns the chain depths of its joints,
+        // in the order they are solved, and the final positions of its links.
+        fn simulate(chain_ordering: bool, reversed: bool) -> (Vec<u32>, Vec<Isometry<Real>>) {
+            let mut pipeline = PhysicsPipeline::new();
+            let gravity = Vector::y() * -9.81;
+            let integration_parameters = IntegrationParameters {
+                max_velocity_iterations: 4,
+                joint_chain_ordering: chain_ordering,
+                ..IntegrationParameters::default()
+            };
+            let mut broad_phase = BroadPhase::new();
+            let mut narrow_phase = NarrowPhase::new();
+            let mut bodies = RigidBodySet::new();
+            let mut colliders = ColliderSet::new();
+            let mut ccd = CCDSolver::new();
+            let mut joints = JointSet::new();
+            let mut user_constraints = UserConstraintSet::new();
+
+            let mut links = vec![bodies.insert(RigidBodyBuilder::new_static().build())];
+
+            for i in 1..=NUM_LINKS {
+                #[cfg(feature = "dim2")]
+                let rb = RigidBodyBuilder::new_dynamic().translation(i as Real, 0.0);
+                #[cfg(feature = "dim3")]
+                let rb = RigidBodyBuilder::new_dynamic().translation(i as Real, 0.0, 0.0);
+                let handle = bodies.insert(rb.build());
+                colliders.insert(ColliderBuilder::ball(0.2).build(), handle, &mut bodies);
+                links.push(handle);
+            }
+
+            // Insert the joints from the free end of the chain if `reversed` is `true`.
+            let mut pairs: Vec<_> = links.windows(2).map(|w| (w[0], w[1])).collect();
+            if reversed {
+                pairs.reverse();
+            }
+
+            for (h1, h2) in pairs {
+                let joint = BallJoint::new(Point::origin(), Point::from(Vector::x() * -1.0));
+                joints.insert(&mut bodies, h1, h2, joint);
+            }
+
+            for _ in 0..50 {
+                pipeline.step(
+                    &gravity,
+                    &integration_parameters,
+                    &mut broad_phase,
+                    &mut narrow_phase,
+                    &mut bodies,
+                    &mut colliders,
+                    &mut joints,
+                    &mut user_constraints,
+                    &mut ccd,
+                    &(),
+                    &(),
+                );
+            }
+
+            let mut indices = vec![Vec::new(); bodies.num_islands()];
+            joints.select_active_interactions(&bodies, chain_ordering, &mut indices);
+            let edges = &joints.joint_graph().raw_graph().edges;
+            let depths = indices
+                .iter()
+                .flatten()
+                .map(|i| edges[*i].weight.chain_depth)
+                .collect();
+            let positions = links[1..].iter().map(|h| *bodies[*h].position()).collect();
+            (depths, positions)
+        }
+
+        let anchor_first: Vec<u32> = (0..NUM_LINKS as u32).collect();
+        let (depths, positions) = simulate(true, false);
+        let (reversed_depths, reversed_positions) = simulate(true, true);
+        assert_eq!(depths, anchor_first);
+        assert_eq!(reversed_depths, anchor_first);
+
+        // The solver order, and therefore the simulation, no longer depends on the order
+        // the joints were inserted in.
+        assert_eq!(positions, reversed_positions);
+
+        let (depths, _) = simulate(false, true);
+        assert_eq!(depths, vec![0; NUM_LINKS]);
+    }
+
+    #[test]
+    fn iter_moved_reports_only_the_pushed_box() {
+        use crate::math::Real;
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters::default();
+        let dt = integration_parameters.dt;
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        #[cfg(feature = "dim2")]
+        let (ground_rb, ground_co) = (
+            RigidBodyBuilder::new_static().translation(0.0, -0.5),
+            ColliderBuilder::cuboid(50.0, 0.5),
+        );
+        #[cfg(feature = "dim3")]
+        let (ground_rb, ground_co) = (
+            RigidBodyBuilder::new_static().translation(0.0, -0.5, 0.0),
+            ColliderBuilder::cuboid(50.0, 0.5, 50.0),
+        );
+        let ground = bodies.insert(ground_rb.build());
+        colliders.insert(ground_co.build(), ground, &mut bodies);
+
+        let mut insert_box = |x: Real, y: Real| {
+            #[cfg(feature = "dim2")]
+            let (rb, co) = (
+                RigidBodyBuilder::new_dynamic().translation(x, y),
+                ColliderBuilder::cuboid(0.5, 0.5),
+            );
+            #[cfg(feature = "dim3")]
+            let (rb, co) = (
+                RigidBodyBuilder::new_dynamic().translation(x, y, 0.0),
+                ColliderBuilder::cuboid(0.5, 0.5, 0.5),
+            );
+            let handle = bodies.insert(rb.build());
+            colliders.insert(co.build(), handle, &mut bodies);
+            handle
+        };
+
+        // A resting pile, and a box resting far from it.
+        for i in 0..3 {
+            insert_box(0.0, 0.5 + i as Real);
+        }
+        let lone_box = insert_box(10.0, 0.5);
+
+        let mut step = |bodies: &mut RigidBodySet, colliders: &mut ColliderSet| {
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                bodies,
+                colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            )
+        };
+
+        for _ in 0..300 {
+            step(&mut bodies, &mut colliders);
+        }
+
+        assert_eq!(bodies.iter_moved(1.0e-3, 1.0e-3).count(), 0);
+
+        // Push the lone box.
+        let linvel_before = *bodies[lone_box].linvel();
+        let pos_before = *bodies[lone_box].position();
+        bodies[lone_box].set_linvel(Vector::x() * 2.0, true);
+        step(&mut bodies, &mut colliders);
+
+        let moved: Vec<_> = bodies.iter_moved(1.0e-3, 1.0e-3).map(|(h, _)| h).collect();
+        assert_eq!(moved, vec![lone_box]);
+
+        let rb = &bodies[lone_box];
+        let delta = rb.position_delta();
+        let pos_after = pos_before * delta;
+        assert!((pos_after.translation.vector - rb.position().translation.vector).norm() < 1.0e-5);
+        assert!(pos_after.rotation.angle_to(&rb.position().rotation) < 1.0e-5);
+        assert!(delta.translation.x > 0.0 && delta.translation.x <= 2.0 * dt + 1.0e-5);
+        assert!((delta.translation.x - rb.linvel().x * dt).abs() < 1.0e-3);
+
+        let (linvel_delta, _) = rb.velocity_delta();
+        assert!((linvel_delta - (rb.linvel() - linvel_before)).norm() < 1.0e-5);
+        assert!(linvel_delta.x > 1.0);
+    }
+
+    #[test]
+    fn kinematic_targets_with_dt_are_reached_over_several_steps() {
+        use crate::math::{Isometry, Real};
+
+        const STEPS_PER_FRAME: usize = 4;
+        const NUM_FRAMES: usize = 10;
+
+        // Moves a platform carrying a box by 1m per frame. If `with_dt` is `true` the platform
+        // target is set once per frame, otherwise an intermediate target is set at each step.
+        fn simulate(with_dt: bool) -> Vec<Isometry<Real>> {
+            let mut pipeline = PhysicsPipeline::new();
+            let gravity = Vector::y() * -9.81;
+            let integration_parameters = IntegrationParameters::default();
+            let dt = integration_parameters.dt;
+            let frame_dt = dt * STEPS_PER_FRAME as Real;
+            let mut broad_phase = BroadPhase::new();
+            let mut narrow_phase = NarrowPhase::new();
+            let mut bodies = RigidBodySet::new();
+            let mut colliders = ColliderSet::new();
+            let mut ccd = CCDSolver::new();
+            let mut joints = JointSet::new();
+            let mut user_constraints = UserConstraintSet::new();
+
+            // The friction is high enough for the box to keep up with the platform.
+            #[cfg(feature = "dim2")]
+            let (platform_co, box_rb, box_co) = (
+                ColliderBuilder::cuboid(30.0, 0.5).friction(10.0),
+                RigidBodyBuilder::new_dynamic().translation(-10.0, 1.0),
+                ColliderBuilder::cuboid(0.5, 0.5).friction(10.0),
+            );
+            #[cfg(feature = "dim3")]
+            let (platform_co, box_rb, box_co) = (
+                ColliderBuilder::cuboid(30.0, 0.5, 30.0).friction(10.0),
+                RigidBodyBuilder::new_dynamic().translation(-10.0, 1.0, 0.0),
+                ColliderBuilder::cuboid(0.5, 0.5, 0.5).friction(10.0),
+            );
+            let platform = bodies.insert(RigidBodyBuilder::new_kinematic().build());
+            colliders.insert(platform_co.build(), platform, &mut bodies);
+            let cube = bodies.insert(box_rb.build());
+            colliders.insert(box_co.build(), cube, &mut bodies);
+
+            let mut box_positions = Vec::new();
+
+            for frame in 0..NUM_FRAMES {
+                let start = Vector::x() * frame as Real;
+                let target = Isometry::new(start + Vector::x(), na::zero());
+
+                if with_dt {
+                    bodies[platform].set_next_kinematic_position_with_dt(target, frame_dt);
+                }
+
+                for i in 0..STEPS_PER_FRAME {
+                    if with_dt {
+                        // The implied velocity is the same for all the steps of the frame.
+                        let (linvel, _) = bodies[platform].kinematic_velocity(dt);
+                        assert!((linvel - Vector::x() / frame_dt).norm() < 1.0e-3);
+                        assert!(bodies[platform].is_moving());
+                    } else {
+                        let fraction = (i + 1) as Real / STEPS_PER_FRAME as Real;
+                        let translation = start + Vector::x() * fraction;
+                        bodies[platform]
+                            .set_next_kinematic_position(Isometry::new(translation, na::zero()));
+                    }
+
+                    pipeline.step(
+                        &gravity,
+                        &integration_parameters,
+                        &mut broad_phase,
+                        &mut narrow_phase,
+                        &mut bodies,
+                        &mut colliders,
+                        &mut joints,
+                        &mut user_constraints,
+                        &mut ccd,
+                        &(),
+                        &(),
+                    );
+                }
+
+                let platform_pos = bodies[platform].position();
+                assert!(
+                    (platform_pos.translation.vector - target.translation.vector).norm() < 1.0e-4
+                );
+                box_positions.push(*bodies[cube].position());
+            }
+
+            // The box was carried by the platform.
+            assert!(box_positions.last().unwrap().translation.x > -5.0);
+            box_positions
+        }
+
+        let with_dt = simulate(true);
+        let per_step = simulate(false);
+
+        for (pos1, pos2) in with_dt.iter().zip(per_step.iter()) {
+            assert!((pos1.translation.vector - pos2.translation.vector).norm() < 1.0e-3);
+            assert!(pos1.rotation.angle_to(&pos2.rotation) < 1.0e-3);
+        }
+    }
+
+    #[test]
+    fn lowering_a_heightfield_region_wakes_up_the_bodies_resting_on_it() {
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        // A flat heightfield with cells of 1m.
+        #[cfg(feature = "dim2")]
+        let heightfield =
+            ColliderBuilder::heightfield(na::DVector::zeros(21), Vector::new(20.0, 1.0));
+        #[cfg(feature = "dim3")]
+        let heightfield =
+            ColliderBuilder::heightfield(na::DMatrix::zeros(21, 21), Vector::new(20.0, 1.0, 20.0));
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        let terrain = colliders.insert(heightfield.build(), ground, &mut bodies);
+
+        let mut boxes = Vec::new();
+        for x in &[-5.0, 5.0] {
+            #[cfg(feature = "dim2")]
+            let (rb, co) = (
+                RigidBodyBuilder::new_dynamic().translation(*x, 0.5),
+                ColliderBuilder::cuboid(0.5, 0.5),
+            );
+            #[cfg(feature = "dim3")]
+            let (rb, co) = (
+                RigidBodyBuilder::new_dynamic().translation(*x, 0.5, *x),
+                ColliderBuilder::cuboid(0.5, 0.5, 0.5),
+            );
+            let handle = bodies.insert(rb.build());
+            colliders.insert(co.build(), handle, &mut bodies);
+            boxes.push(handle);
+        }
+
+        let mut step = |bodies: &mut RigidBodySet, colliders: &mut ColliderSet| {
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                bodies,
+                colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            )
+        };
+
+        for _ in 0..30 {
+            step(&mut bodies, &mut colliders);
+        }
+
+        for handle in &boxes {
+            bodies[*handle].sleep();
+        }
+
+        // Dig a crater below the first box.
+        #[cfg(feature = "dim2")]
+        assert!(colliders[terrain].update_heightfield_region(3..8, &[-2.0; 5]));
+        #[cfg(feature = "dim3")]
+        assert!(colliders[terrain].update_heightfield_region(
+            3..8,
+            3..8,
+            &na::DMatrix::repeat(5, 5, -2.0)
+        ));
+
+        step(&mut bodies, &mut colliders);
+        assert!(!bodies[boxes[0]].is_sleeping());
+        assert!(bodies[boxes[1]].is_sleeping());
+
+        for _ in 0..60 {
+            step(&mut bodies, &mut colliders);
+        }
+
+        assert!(bodies[boxes[0]].position().translation.y < -1.0);
+        assert!((bodies[boxes[1]].position().translation.y - 0.5).abs() < 1.0e-2);
+    }
+
+    #[test]
+    fn parallel_batch_size_is_reported_in_the_stats() {
+        use crate::math::Real;
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let mut integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_collider = ColliderBuilder::cuboid(20.0, 0.5).build();
+        #[cfg(feature = "dim3")]
+        let ground_collider = ColliderBuilder::cuboid(20.0, 0.5, 20.0).build();
+        colliders.insert(ground_collider, ground, &mut bodies);
+
+        // A stack of boxes.
+        for i in 0..10 {
+            #[cfg(feature = "dim2")]
+            let (rb, co) = (
+                RigidBodyBuilder::new_dynamic().translation(0.0, 1.0 + i as Real),
+                ColliderBuilder::cuboid(0.5, 0.5),
+            );
+            #[cfg(feature = "dim3")]
+            let (rb, co) = (
+                RigidBodyBuilder::new_dynamic().translation(0.0, 1.0 + i as Real, 0.0),
+                ColliderBuilder::cuboid(0.5, 0.5, 0.5),
+            );
+            let handle = bodies.insert(rb.build());
+            colliders.insert(co.build(), handle, &mut bodies);
+        }
+
+        for batch_size in &[None, Some(3)] {
+            integration_parameters.parallel_batch_size = *batch_size;
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            );
+
+            let stats = pipeline.stats(
+                false,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &bodies,
+                &colliders,
+            );
+
+            if cfg!(feature = "parallel") {
+                if let Some(batch_size) = batch_size {
+                    assert_eq!(stats.solver.parallel_batch_size, *batch_size);
+                } else {
+                    assert!(stats.solver.parallel_batch_size > 0);
+                }
+
+                assert!(stats.solver.parallel_velocity_stage.num_claimed_batches > 0);
+                assert!(stats.solver.parallel_position_stage.num_claimed_batches > 0);
+            } else {
+                assert_eq!(stats.solver.parallel_batch_size, 0);
+                assert_eq!(
+                    stats.solver.parallel_velocity_stage,
+                    crate::dynamics::ParallelStageStats::default()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn exported_frames_round_trip_and_match_the_contact_impulses() {
+        use crate::dynamics::{BallJoint, RigidBody, RigidBodyHandle};
+        use crate::math::Point;
+        use crate::pipeline::SimulationFrame;
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_collider = ColliderBuilder::cuboid(20.0, 0.5).build();
+        #[cfg(feature = "dim3")]
+        let ground_collider = ColliderBuilder::cuboid(20.0, 0.5, 20.0).build();
+        colliders.insert(ground_collider, ground, &mut bodies);
+
+        // A box resting on the ground, and a swinging pendulum attached to a static body.
+        #[cfg(feature = "dim2")]
+        let (cube, cube_co, bob) = (
+            RigidBodyBuilder::new_dynamic().translation(0.0, 1.0),
+            ColliderBuilder::cuboid(0.5, 0.5),
+            RigidBodyBuilder::new_dynamic().translation(6.0, 6.0),
+        );
+        #[cfg(feature = "dim3")]
+        let (cube, cube_co, bob) = (
+            RigidBodyBuilder::new_dynamic().translation(0.0, 1.0, 0.0),
+            ColliderBuilder::cuboid(0.5, 0.5, 0.5),
+            RigidBodyBuilder::new_dynamic().translation(6.0, 6.0, 0.0),
+        );
+        let cube = bodies.insert(cube.build());
+        colliders.insert(cube_co.build(), cube, &mut bodies);
+        let bob = bodies.insert(bob.build());
+        colliders.insert(ColliderBuilder::ball(0.2).build(), bob, &mut bodies);
+        let anchor = bodies.insert(RigidBodyBuilder::new_static().build());
+        let joint = BallJoint::new(
+            Point::from(Vector::x() * 5.0 + Vector::y() * 6.0),
+            Point::origin(),
+        );
+        joints.insert(&mut bodies, anchor, bob, joint);
+
+        let mut frame = SimulationFrame::new();
+        let mut frames = Vec::new();
+
+        for _ in 0..2 {
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            );
+
+            pipeline.export_state(
+                &bodies,
+                &colliders,
+                &joints,
+                &narrow_phase,
+                None,
+                &mut frame,
+            );
+            assert_eq!(frame.num_bodies(), 4);
+            assert_eq!(frame.num_joints(), 1);
+            assert!(frame.num_contacts() > 0);
+
+            // The impulses match the ones given by the narrow-phase.
+            for (i, (h1, h2)) in frame.contact_colliders.iter().enumerate() {
+                let pair = narrow_phase.contact_pair(*h1, *h2).unwrap();
+                let impulses: Vec<_> = pair
+                    .manifolds
+                    .iter()
+                    .flat_map(|m| m.points.iter().map(|pt| pt.data.impulse))
+                    .collect();
+                let impulse = frame.contact_impulses[i * SimulationFrame::CONTACT_IMPULSE_DIM];
+                assert!(impulses.contains(&impulse));
+            }
+
+            let bytes = frame.to_bytes();
+            assert_eq!(SimulationFrame::from_bytes(&bytes), Some(frame.clone()));
+            assert_eq!(SimulationFrame::from_bytes(&bytes[..bytes.len() - 1]), None);
+            frames.push(frame.clone());
+        }
+
+        // The bodies are sorted by handle, so consecutive frames can be compared element-wise.
+        assert_eq!(frames[0].body_handles, frames[1].body_handles);
+        assert_ne!(frames[0].body_poses, frames[1].body_poses);
+
+        // Only the contacts and joints involving the filtered bodies are exported.
+        let filter = |handle: RigidBodyHandle, _: &RigidBody| handle == bob;
+        pipeline.export_state(
+            &bodies,
+            &colliders,
+            &joints,
+            &narrow_phase,
+            Some(&filter),
+            &mut frame,
+        );
+        assert_eq!(frame.body_handles, vec![bob]);
+        assert_eq!(frame.num_contacts(), 0);
+        assert_eq!(frame.num_joints(), 1);
+    }
+
+    #[test]
+    fn kinematic_platform_lifts_a_resting_box_without_penetration() {
+        use crate::math::{Isometry, Real};
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters {
+            use_predicted_kinematic_positions: true,
+            ..IntegrationParameters::default()
+        };
+        let dt = integration_parameters.dt;
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        // The platform top is at y = 0.5 and the box bottom starts slightly above it.
+        #[cfg(feature = "dim2")]
+        let (platform_co, box_rb, box_co) = (
+            ColliderBuilder::cuboid(5.0, 0.5),
+            RigidBodyBuilder::new_dynamic().translation(0.0, 1.01),
+            ColliderBuilder::cuboid(0.5, 0.5),
+        );
+        #[cfg(feature = "dim3")]
+        let (platform_co, box_rb, box_co) = (
+            ColliderBuilder::cuboid(5.0, 0.5, 5.0),
+            RigidBodyBuilder::new_dynamic().translation(0.0, 1.01, 0.0),
+            ColliderBuilder::cuboid(0.5, 0.5, 0.5),
+        );
+        let platform = bodies.insert(RigidBodyBuilder::new_kinematic().build());
+        colliders.insert(platform_co.build(), platform, &mut bodies);
+        let cube = bodies.insert(box_rb.can_sleep(false).build());
+        colliders.insert(box_co.build(), cube, &mut bodies);
+
+        let mut max_penetration: Real = 0.0;
+
+        for frame in 0..90 {
+            // Let the box settle on the platform, then move the platform up at 5m/s.
+            if frame >= 30 {
+                let y = (frame - 29) as Real * 5.0 * dt;
+                bodies[platform]
+                    .set_next_kinematic_position(Isometry::new(Vector::y() * y, na::zero()));
+            }
+
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            );
+
+            let platform_top = bodies[platform].position().translation.vector.y + 0.5;
+            let box_bottom = bodies[cube].position().translation.vector.y - 0.5;
+            max_penetration = max_penetration.max(platform_top - box_bottom);
+        }
+
+        // The penetration never exceeds what the resting contact already allows.
+        assert!(max_penetration < 0.01, "penetration: {}", max_penetration);
+        assert!((bodies[cube].linvel().y - 5.0).abs() < 0.1);
+        assert!(bodies[cube].position().translation.vector.y > 5.0);
+    }
+
+    #[test]
+    fn friction_anchors_stop_boxes_from_creeping_down_slopes() {
+        use crate::math::{Isometry, Real, Translation};
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters {
+            friction_anchors: true,
+            ..IntegrationParameters::default()
+        };
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        // A 20° slope with a box resting on it. The box would slide if μ < tan(20°) ≈ 0.36.
+        let angle = (20.0 as Real).to_radians();
+        #[cfg(feature = "dim2")]
+        let (slope_pos, slope_co, box_co) = (
+            Isometry::new(na::zero(), angle),
+            ColliderBuilder::cuboid(10.0, 0.5),
+            ColliderBuilder::cuboid(0.5, 0.5),
+        );
+        #[cfg(feature = "dim3")]
+        let (slope_pos, slope_co, box_co) = (
+            Isometry::new(na::zero(), Vector::z() * angle),
+            ColliderBuilder::cuboid(10.0, 0.5, 10.0),
+            ColliderBuilder::cuboid(0.5, 0.5, 0.5),
+        );
+        let box_pos = slope_pos * Translation::from(Vector::y());
+
+        let slope = bodies.insert(RigidBodyBuilder::new_static().position(slope_pos).build());
+        colliders.insert(slope_co.friction(0.5).build(), slope, &mut bodies);
+        let cube = bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(box_pos)
+                .can_sleep(false)
+                .build(),
+        );
+        colliders.insert(box_co.friction(0.5).build(), cube, &mut bodies);
+
+        let mut step = |bodies: &mut RigidBodySet, colliders: &mut ColliderSet| {
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                bodies,
+                colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            )
+        };
+
+        // Let the box settle, then simulate 10 minutes.
+        for _ in 0..60 {
+            step(&mut bodies, &mut colliders);
+        }
+
+        let start = bodies[cube].position().translation.vector;
+
+        for _ in 0..60 * 60 * 10 {
+            step(&mut bodies, &mut colliders);
+        }
+
+        let drift = (bodies[cube].position().translation.vector - start).norm();
+        assert!(drift < 1.0e-4, "drift: {}", drift);
+    }
+
+    #[test]
+    fn wake_up_propagation_is_bounded_by_the_maximum_depth() {
+        use crate::math::Real;
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters {
+            max_wake_propagation_depth: Some(2),
+            ..IntegrationParameters::default()
+        };
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_co = ColliderBuilder::cuboid(50.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_co = ColliderBuilder::cuboid(50.0, 0.5, 50.0);
+        colliders.insert(ground_co.build(), ground, &mut bodies);
+
+        // A row of boxes, each slightly penetrating its neighbors.
+        let boxes: Vec<_> = (0..10)
+            .map(|i| {
+                #[cfg(feature = "dim2")]
+                let (rb, co) = (
+                    RigidBodyBuilder::new_dynamic().translation(i as Real * 0.999, 1.0),
+                    ColliderBuilder::cuboid(0.5, 0.5),
+                );
+                #[cfg(feature = "dim3")]
+                let (rb, co) = (
+                    RigidBodyBuilder::new_dynamic().translation(i as Real * 0.999, 1.0, 0.0),
+                    ColliderBuilder::cuboid(0.5, 0.5, 0.5),
+                );
+                let handle = bodies.insert(rb.build());
+                colliders.insert(co.build(), handle, &mut bodies);
+                handle
+            })
+            .collect();
+
+        let mut step = |bodies: &mut RigidBodySet, colliders: &mut ColliderSet| {
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                bodies,
+                colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            )
+        };
+        let num_awake =
+            |bodies: &RigidBodySet| boxes.iter().filter(|h| !bodies[**h].is_sleeping()).count();
+
+        for _ in 0..500 {
+            step(&mut bodies, &mut colliders);
+        }
+        assert_eq!(num_awake(&bodies), 0);
+
+        // Hit the first box. Only the boxes at most two contacts away from it wake up.
+        bodies[boxes[0]].set_linvel(Vector::x() * 2.0, true);
+        step(&mut bodies, &mut colliders);
+        assert_eq!(num_awake(&bodies), 3);
+        assert_eq!(bodies.pending_wake_ups(), &[boxes[3]]);
+
+        // The propagation continues from where it stopped at the next timestep.
+        step(&mut bodies, &mut colliders);
+        assert!(!bodies[boxes[3]].is_sleeping());
+        assert!(!bodies[boxes[5]].is_sleeping());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn sleep_islands_are_restored_after_deserialization() {
+        let mut pipeline = PhysicsPipeline::new();
+        let mut bf = BroadPhase::new();
+        let mut nf = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut joints = JointSet::new();
+        let params = IntegrationParameters::default();
+        let gravity = Vector::y() * -9.81;
+
+        #[cfg(feature = "dim2")]
+        let ground_co = ColliderBuilder::cuboid(10.0, 0.1).build();
+        #[cfg(feature = "dim3")]
+        let ground_co = ColliderBuilder::cuboid(10.0, 0.1, 10.0).build();
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        colliders.insert(ground_co, ground, &mut bodies);
+
+        // A box resting on the ground, that falls asleep, and a box falling from far
+        // above, that is still awake when the world is saved.
+        let mut boxes = Vec::new();
+        for (x, y) in [(-5.0, 0.6), (5.0, 1000.0)].iter() {
+            #[cfg(feature = "dim2")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(*x, *y);
+            #[cfg(feature = "dim3")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(*x, *y, 0.0);
+            #[cfg(feature = "dim2")]
+            let co = ColliderBuilder::cuboid(0.5, 0.5).build();
+            #[cfg(feature = "dim3")]
+            let co = ColliderBuilder::cuboid(0.5, 0.5, 0.5).build();
+            let handle = bodies.insert(rb.build());
+            colliders.insert(co, handle, &mut bodies);
+            boxes.push(handle);
+        }
+
+        #[cfg(feature = "dim2")]
+        let kinematic = RigidBodyBuilder::new_kinematic().translation(0.0, 5.0);
+        #[cfg(feature = "dim3")]
+        let kinematic = RigidBodyBuilder::new_kinematic().translation(0.0, 5.0, 0.0);
+        bodies.insert(kinematic.build());
+
+        let mut step = |bodies: &mut RigidBodySet,
+                        colliders: &mut ColliderSet,
+                        bf: &mut BroadPhase,
+                        nf: &mut NarrowPhase,
+                        joints: &mut JointSet,
+                        pipeline: &mut PhysicsPipeline| {
+            pipeline.step(
+                &gravity,
+                &params,
+                bf,
+                nf,
+                bodies,
+                colliders,
+                joints,
+                user_constraints,
+                &mut CCDSolver::new(),
+                &(),
+                &(),
+            );
+        };
+
+        for _ in 0..300 {
+            step(
+                &mut bodies,
+                &mut colliders,
+                &mut bf,
+                &mut nf,
+                &mut joints,
+                &mut pipeline,
+            );
+        }
+
+        assert!(bodies[boxes[0]].is_sleeping());
+        assert!(!bodies[boxes[1]].is_sleeping());
+
+        let mut bodies2: RigidBodySet =
+            bincode::deserialize(&bincode::serialize(&bodies).unwrap()).unwrap();
+        let mut colliders2: ColliderSet =
+            bincode::deserialize(&bincode::serialize(&colliders).unwrap()).unwrap();
+        let mut bf2: BroadPhase = bincode::deserialize(&bincode::serialize(&bf).unwrap()).unwrap();
+        let mut nf2: NarrowPhase = bincode::deserialize(&bincode::serialize(&nf).unwrap()).unwrap();
+        let mut joints2: JointSet =
+            bincode::deserialize(&bincode::serialize(&joints).unwrap()).unwrap();
+        let mut pipeline2 = PhysicsPipeline::new();
+
+        assert_eq!(bodies2.active_dynamic_set, bodies.active_dynamic_set);
+        assert_eq!(bodies2.active_kinematic_set, bodies.active_kinematic_set);
+        assert_eq!(bodies2.active_islands, bodies.active_islands);
+
+        for _ in 0..10 {
+            step(
+                &mut bodies,
+                &mut colliders,
+                &mut bf,
+                &mut nf,
+                &mut joints,
+                &mut pipeline,
+            );
+            step(
+                &mut bodies2,
+                &mut colliders2,
+                &mut bf2,
+                &mut nf2,
+                &mut joints2,
+                &mut pipeline2,
+            );
+
+            assert_eq!(bodies2.hash_state(), bodies.hash_state());
+            assert_eq!(bodies2.active_dynamic_set, bodies.active_dynamic_set);
+            assert!(bodies2[boxes[0]].is_sleeping());
+        }
+    }
+
+    #[test]
+    fn gear_joint_transmits_rotations_with_its_ratio() {
+        #[cfg(feature = "dim2")]
+        use crate::dynamics::BallJoint;
+        #[cfg(feature = "dim3")]
+        use crate::dynamics::RevoluteJoint;
+        use crate::dynamics::{GearJoint, RigidBody};
+        use crate::math::{Isometry, Point, Real};
+
+        // The angular velocity and angle of a wheel.
+        #[cfg(feature = "dim2")]
+        fn spin(rb: &RigidBody) -> (Real, Real) {
+            (rb.angvel(), rb.position().rotation.angle())
+        }
+        #[cfg(feature = "dim3")]
+        fn spin(rb: &RigidBody) -> (Real, Real) {
+            (rb.angvel().z, rb.position().rotation.scaled_axis().z)
+        }
+
+        let mut pipeline = PhysicsPipeline::new();
+        let mut bf = BroadPhase::new();
+        let mut nf = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+        let params = IntegrationParameters::default();
+
+        // Two wheels rotating around the hinges of a common chassis. The first
+        // one is driven by a motor, the second one by a 2:1 gear.
+        let chassis = bodies.insert(RigidBodyBuilder::new_static().build());
+        let centers = [Vector::zeros(), Vector::x() * 4.0];
+        let mut wheels = Vec::new();
+
+        for (i, center) in centers.iter().enumerate() {
+            let wheel = bodies.insert(
+                RigidBodyBuilder::new_dynamic()
+                    .position(Isometry::new(*center, na::zero()))
+                    .build(),
+            );
+            let radius = 1.0 + i as Real;
+            colliders.insert(ColliderBuilder::ball(radius).build(), wheel, &mut bodies);
+
+            #[cfg(feature = "dim2")]
+            let mut hinge = BallJoint::new(Point::from(*center), Point::origin());
+            #[cfg(feature = "dim3")]
+            let mut hinge = RevoluteJoint::new(
+                Point::from(*center),
+                Vector::z_axis(),
+                Point::origin(),
+                Vector::z_axis(),
+            );
+
+            if i == 0 {
+                hinge.configure_motor_velocity(2.0, 1.0);
+            }
+
+            joints.insert(&mut bodies, chassis, wheel, hinge);
+            wheels.push(wheel);
+        }
+
+        #[cfg(feature = "dim2")]
+        let gear = GearJoint::new(2.0);
+        #[cfg(feature = "dim3")]
+        let gear = GearJoint::new(Vector::z_axis(), Vector::z_axis(), 2.0);
+        joints.insert(&mut bodies, wheels[0], wheels[1], gear);
+
+        for _ in 0..60 {
+            pipeline.step(
+                &Vector::zeros(),
+                &params,
+                &mut bf,
+                &mut nf,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut CCDSolver::new(),
+                &(),
+                &(),
+            );
+        }
+
+        let (angvel1, angle1) = spin(&bodies[wheels[0]]);
+        let (angvel2, angle2) = spin(&bodies[wheels[1]]);
+        assert!((angvel1 - 2.0).abs() < 0.05);
+        assert!((angvel2 - angvel1 / 2.0).abs() < 1.0e-2);
+        assert!((angle2 - angle1 / 2.0).abs() < 1.0e-2);
+
+        // The gear doesn't fight against the hinges.
+        for (wheel, center) in wheels.iter().zip(centers.iter()) {
+            let translation = bodies[*wheel].position().translation.vector;
+            assert!((translation - center).norm() < 1.0e-2);
+        }
+    }
+
+    #[test]
+    fn pulley_joint_lifts_a_weight_with_a_mechanical_advantage() {
+        use crate::dynamics::PulleyJoint;
+        use crate::math::{Isometry, Point};
+
+        let mut pipeline = PhysicsPipeline::new();
+        let mut bf = BroadPhase::new();
+        let mut nf = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+        let params = IntegrationParameters::default();
+        let gravity = Vector::y() * -9.81;
+
+        // A weight hanging from a rope with a ratio of 2 lifts a weight 1.5 times
+        // heavier, hanging from the other side of the pulley.
+        let ratio = 2.0;
+        let (light_mass, heavy_mass) = (1.0, 1.5);
+        let start1 = Vector::x() * -2.0 + Vector::y() * 5.0;
+        let start2 = Vector::x() * 2.0 + Vector::y() * 5.0;
+        let pulley1 = Point::from(start1 + Vector::y() * 5.0);
+        let pulley2 = Point::from(start2 + Vector::y() * 5.0);
+        let light = bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(start1, na::zero()))
+                .additional_mass(light_mass)
+                .build(),
+        );
+        let heavy = bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(start2, na::zero()))
+                .additional_mass(heavy_mass)
+                .build(),
+        );
+        let length = 5.0 + ratio * 5.0;
+        let pulley = PulleyJoint::new(
+            pulley1,
+            pulley2,
+            Point::origin(),
+            Point::origin(),
+            ratio,
+            length,
+        );
+        joints.insert(&mut bodies, light, heavy, pulley);
+
+        for _ in 0..60 {
+            pipeline.step(
+                &gravity,
+                &params,
+                &mut bf,
+                &mut nf,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut CCDSolver::new(),
+                &(),
+                &(),
+            );
+        }
+
+        let pos1 = bodies[light].position().translation.vector;
+        let pos2 = bodies[heavy].position().translation.vector;
+        let drop = start1.y - pos1.y;
+        let rise = pos2.y - start2.y;
+
+        // The acceleration of the heavy weight is `(ratio * m1 - m2) * g / (m2 + ratio² * m1)`.
+        let accel =
+            (ratio * light_mass - heavy_mass) * 9.81 / (heavy_mass + ratio * ratio * light_mass);
+        let expected_rise = accel / 2.0;
+        assert!((rise - expected_rise).abs() < 0.05 * expected_rise);
+        assert!((drop - ratio * rise).abs() < 1.0e-2);
+
+        let length1 = (Point::from(pos1) - pulley1).norm();
+        let length2 = (Point::from(pos2) - pulley2).norm();
+        assert!((length1 + ratio * length2 - length).abs() < 1.0e-2);
+    }
+
+    #[test]
+    fn absurd_impulses_are_clamped_to_the_maximum_velocities() {
+        use crate::geometry::{ContactEvent, IntersectionEvent};
+        use crate::math::Real;
+        use crate::pipeline::{EventHandler, VelocityClampEvent};
+        use std::sync::Mutex;
+        use std::time::{Duration, Instant};
+
+        struct ClampRecorder(Mutex<Vec<VelocityClampEvent>>);
+
+        impl EventHandler for ClampRecorder {
+            fn handle_intersection_event(&self, _event: IntersectionEvent) {}
+            fn handle_contact_event(&self, _event: ContactEvent) {}
+            fn handle_velocity_clamp_event(&self, event: VelocityClampEvent) {
+                self.0.lock().unwrap().push(event);
+            }
+        }
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters {
+            max_ccd_substeps: 4,
+            ..IntegrationParameters::default()
+        };
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+        let events = ClampRecorder(Mutex::new(Vec::new()));
+
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        for i in 0..10 {
+            let x = i as Real * 3.0;
+            #[cfg(feature = "dim2")]
+            let collider = ColliderBuilder::cuboid(1.0, 1.0)
+                .translation(x, 0.0)
+                .build();
+            #[cfg(feature = "dim3")]
+            let collider = ColliderBuilder::cuboid(1.0, 1.0, 1.0)
+                .translation(x, 0.0, 0.0)
+                .build();
+            colliders.insert(collider, ground, &mut bodies);
+        }
+
+        #[cfg(feature = "dim2")]
+        let rb = RigidBodyBuilder::new_dynamic()
+            .translation(0.0, 5.0)
+            .ccd_enabled(true)
+            .build();
+        #[cfg(feature = "dim3")]
+        let rb = RigidBodyBuilder::new_dynamic()
+            .translation(0.0, 5.0, 0.0)
+            .ccd_enabled(true)
+            .build();
+        let handle = bodies.insert(rb);
+        colliders.insert(ColliderBuilder::ball(0.5).build(), handle, &mut bodies);
+
+        let mass = bodies[handle].mass();
+        let impulse = 1.0e9;
+        #[cfg(feature = "dim2")]
+        let torque_impulse = 1.0e9;
+        #[cfg(feature = "dim3")]
+        let torque_impulse = Vector::z() * 1.0e9;
+        let rb = bodies.get_mut(handle).unwrap();
+        rb.apply_impulse(Vector::x() * impulse, true);
+        rb.apply_torque_impulse(torque_impulse, true);
+
+        let start = Instant::now();
+
+        for _ in 0..10 {
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &events,
+            );
+        }
+
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        let rb = &bodies[handle];
+        assert!(rb.linvel().norm() <= integration_parameters.max_linear_velocity * 1.001);
+        #[cfg(feature = "dim2")]
+        let angvel = rb.angvel().abs();
+        #[cfg(feature = "dim3")]
+        let angvel = rb.angvel().norm();
+        assert!(angvel <= integration_parameters.max_angular_velocity * 1.001);
+
+        let events = events.0.lock().unwrap();
+        let first = events.first().expect("The velocity clamp wasn't reported.");
+        assert_eq!(first.body, handle);
+        let expected_excess = impulse / mass - integration_parameters.max_linear_velocity;
+        assert!((first.linear_excess - expected_excess).abs() <= expected_excess * 1.0e-3);
+        assert!(first.angular_excess > 0.0);
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn collision_events_do_not_depend_on_the_number_of_threads() {
+        use crate::geometry::{ContactEvent, IntersectionEvent};
+        use crate::math::Real;
+        use crate::pipeline::EventHandler;
+        use std::sync::Mutex;
+
+        #[derive(Copy, Clone, Debug, PartialEq)]
+        enum RecordedEvent {
+            Contact(usize, ContactEvent),
+            Intersection(usize, IntersectionEvent),
+        }
+
+        struct EventRecorder {
+            step: Mutex<usize>,
+            events: Mutex<Vec<RecordedEvent>>,
+        }
+
+        impl EventHandler for EventRecorder {
+            fn handle_intersection_event(&self, event: IntersectionEvent) {
+                let step = *self.step.lock().unwrap();
+                let event = RecordedEvent::Intersection(step, event);
+                self.events.lock().unwrap().push(event);
+            }
+
+            fn handle_contact_event(&self, event: ContactEvent) {
+                let step = *self.step.lock().unwrap();
+                let event = RecordedEvent::Contact(step, event);
+                self.events.lock().unwrap().push(event);
+            }
+        }
+
+        fn record_events(num_threads: usize) -> Vec<RecordedEvent> {
+            let mut pipeline = PhysicsPipeline::new();
+            let gravity = Vector::y() * -9.81;
+            let integration_parameters = IntegrationParameters::default();
+            let mut broad_phase = BroadPhase::new();
+            let mut narrow_phase = NarrowPhase::new();
+            let mut bodies = RigidBodySet::new();
+            let mut colliders = ColliderSet::new();
+            let mut ccd = CCDSolver::new();
+            let mut joints = JointSet::new();
+            let mut user_constraints = UserConstraintSet::new();
+            let events = EventRecorder {
+                step: Mutex::new(0),
+                events: Mutex::new(Vec::new()),
+            };
+
+            let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+            #[cfg(feature = "dim2")]
+            let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+            #[cfg(feature = "dim3")]
+            let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+            colliders.insert(ground_shape.build(), ground, &mut bodies);
+            #[cfg(feature = "dim2")]
+            let sensor = ColliderBuilder::cuboid(3.0, 1.0).translation(0.0, 3.0);
+            #[cfg(feature = "dim3")]
+            let sensor = ColliderBuilder::cuboid(3.0, 1.0, 3.0).translation(0.0, 3.0, 0.0);
+            colliders.insert(sensor.sensor(true).build(), ground, &mut bodies);
+
+            // Balls dropped in a pile, each one slightly shifted so that they scatter.
+            for i in 0..200 {
+                let x = (i % 10) as Real * 0.45 - 2.0 + (i / 10) as Real * 0.01;
+                let y = 1.5 + (i / 10) as Real * 0.6;
+                #[cfg(feature = "dim2")]
+                let rb = RigidBodyBuilder::new_dynamic().translation(x, y).build();
+                #[cfg(feature = "dim3")]
+                let rb = RigidBodyBuilder::new_dynamic()
+                    .translation(x, y, (i % 3) as Real * 0.3)
+                    .build();
+                let handle = bodies.insert(rb);
+                colliders.insert(ColliderBuilder::ball(0.25).build(), handle, &mut bodies);
+            }
+
+            let thread_pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(num_threads)
+                .build()
+                .unwrap();
+
+            thread_pool.install(|| {
+                for step in 0..100 {
+                    *events.step.lock().unwrap() = step;
+                    pipeline.step(
+                        &gravity,
+                        &integration_parameters,
+                        &mut broad_phase,
+                        &mut narrow_phase,
+                        &mut bodies,
+                        &mut colliders,
+                        &mut joints,
+                        &mut user_constraints,
+                        &mut ccd,
+                        &(),
+                        &events,
+                    );
+                }
+            });
+
+            events.events.into_inner().unwrap()
+        }
+
+        let single_threaded = record_events(1);
+        let multi_threaded = record_events(8);
+        assert!(!single_threaded.is_empty());
+        assert_eq!(single_threaded, multi_threaded);
+    }
+
+    #[test]
+    fn extra_solver_iterations_stiffen_a_loaded_arm() {
+        use crate::dynamics::BallJoint;
+        use crate::math::{Point, Real, Rotation};
+
+        // Returns the droop of the tip of an arm holding 10kg at full extension, and the
+        // total time spent in the solver.
+        fn simulate_arm(
+            max_velocity_iterations: usize,
+            extra_solver_iterations: u8,
+        ) -> (Real, f64) {
+            let mut pipeline = PhysicsPipeline::new();
+            pipeline.counters.enable();
+            let gravity = Vector::y() * -9.81;
+            let integration_parameters = IntegrationParameters {
+                max_velocity_iterations,
+                ..IntegrationParameters::default()
+            };
+            let mut broad_phase = BroadPhase::new();
+            let mut narrow_phase = NarrowPhase::new();
+            let mut bodies = RigidBodySet::new();
+            let mut colliders = ColliderSet::new();
+            let mut ccd = CCDSolver::new();
+            let mut joints = JointSet::new();
+            let mut user_constraints = UserConstraintSet::new();
+
+            // The rest of the scene: a pile of balls which never falls asleep.
+            #[cfg(feature = "dim2")]
+            let ground = RigidBodyBuilder::new_static().translation(-20.0, -5.0);
+            #[cfg(feature = "dim3")]
+            let ground = RigidBodyBuilder::new_static().translation(-20.0, -5.0, 0.0);
+            let ground = bodies.insert(ground.build());
+            #[cfg(feature = "dim2")]
+            let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+            #[cfg(feature = "dim3")]
+            let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+            colliders.insert(ground_shape.build(), ground, &mut bodies);
+
+            for i in 0..200 {
+                let x = (i % 20) as Real * 0.5 - 25.0;
+                let y = (i / 20) as Real * 0.5 - 4.2;
+                #[cfg(feature = "dim2")]
+                let rb = RigidBodyBuilder::new_dynamic().translation(x, y);
+                #[cfg(feature = "dim3")]
+                let rb = RigidBodyBuilder::new_dynamic().translation(x, y, 0.0);
+                let handle = bodies.insert(rb.can_sleep(false).build());
+                colliders.insert(ColliderBuilder::ball(0.24).build(), handle, &mut bodies);
+            }
+
+            // The arm: three motorized links, the last one holding the load.
+            let base = bodies.insert(RigidBodyBuilder::new_static().build());
+            let mut parent = base;
+            let mut parent_anchor = Point::origin();
+
+            for i in 0..3 {
+                let x = i as Real + 0.5;
+                #[cfg(feature = "dim2")]
+                let rb = RigidBodyBuilder::new_dynamic().translation(x, 0.0);
+                #[cfg(feature = "dim3")]
+                let rb = RigidBodyBuilder::new_dynamic().translation(x, 0.0, 0.0);
+                let rb = if i == 2 { rb.additional_mass(10.0) } else { rb };
+                let link = bodies.insert(rb.can_sleep(false).build());
+                #[cfg(feature = "dim2")]
+                let shape = ColliderBuilder::cuboid(0.45, 0.05);
+                #[cfg(feature = "dim3")]
+                let shape = ColliderBuilder::cuboid(0.45, 0.05, 0.05);
+                colliders.insert(shape.build(), link, &mut bodies);
+
+                let mut joint = BallJoint::new(parent_anchor, Point::from(Vector::x() * -0.5));
+                joint.configure_motor_position(Rotation::identity(), 0.2, 1.0);
+                let handle = joints.insert(&mut bodies, parent, link, joint);
+                joints.get_mut(handle).unwrap().extra_solver_iterations = extra_solver_iterations;
+
+                parent = link;
+                parent_anchor = Point::from(Vector::x() * 0.5);
+            }
+
+            let mut solver_time = 0.0;
+
+            for _ in 0..120 {
+                pipeline.step(
+                    &gravity,
+                    &integration_parameters,
+                    &mut broad_phase,
+                    &mut narrow_phase,
+                    &mut bodies,
+                    &mut colliders,
+                    &mut joints,
+                    &mut user_constraints,
+                    &mut ccd,
+                    &(),
+                    &(),
+                );
+                solver_time += pipeline.counters.stages.solver_time.time();
+            }
+
+            let tip = bodies[parent].position() * parent_anchor;
+            (-tip.y, solver_time)
+        }
+
+        let (droop, time) = simulate_arm(1, 0);
+        let (boosted_droop, boosted_time) = simulate_arm(1, 4);
+        let (_, global_time) = simulate_arm(5, 0);
+
+        assert!(droop > 0.0);
+        assert!(boosted_droop * 5.0 <= droop);
+        assert!(boosted_time - time < (global_time - time) / 2.0);
+    }
+
+    #[test]
+    fn damped_collider_removal_does_not_pop_a_tower() {
+        use crate::geometry::RemoveSettleMode;
+        use crate::math::Real;
+
+        // Returns the peak upward velocity of the remaining blocks of a tower of 10 blocks
+        // after the removal of the middle one.
+        fn pull_middle_plate(settle_mode: RemoveSettleMode) -> Real {
+            let mut pipeline = PhysicsPipeline::new();
+            let gravity = Vector::y() * -9.81;
+            let integration_parameters = IntegrationParameters::default();
+            let mut broad_phase = BroadPhase::new();
+            let mut narrow_phase = NarrowPhase::new();
+            let mut bodies = RigidBodySet::new();
+            let mut colliders = ColliderSet::new();
+            let mut ccd = CCDSolver::new();
+            let mut joints = JointSet::new();
+            let mut user_constraints = UserConstraintSet::new();
+
+            #[cfg(feature = "dim2")]
+            let ground = RigidBodyBuilder::new_static().translation(0.0, -0.5);
+            #[cfg(feature = "dim3")]
+            let ground = RigidBodyBuilder::new_static().translation(0.0, -0.5, 0.0);
+            let ground = bodies.insert(ground.build());
+            #[cfg(feature = "dim2")]
+            let ground_shape = ColliderBuilder::cuboid(5.0, 0.5);
+            #[cfg(feature = "dim3")]
+            let ground_shape = ColliderBuilder::cuboid(5.0, 0.5, 5.0);
+            colliders.insert(ground_shape.build(), ground, &mut bodies);
+
+            let mut blocks = Vec::new();
+
+            for i in 0..10 {
+                let y = i as Real + 0.5;
+                #[cfg(feature = "dim2")]
+                let rb = RigidBodyBuilder::new_dynamic().translation(0.0, y);
+                #[cfg(feature = "dim3")]
+                let rb = RigidBodyBuilder::new_dynamic().translation(0.0, y, 0.0);
+                let handle = bodies.insert(rb.can_sleep(false).build());
+                #[cfg(feature = "dim2")]
+                let shape = ColliderBuilder::cuboid(0.5, 0.5);
+                #[cfg(feature = "dim3")]
+                let shape = ColliderBuilder::cuboid(0.5, 0.5, 0.5);
+                let collider = colliders.insert(shape.build(), handle, &mut bodies);
+                blocks.push((handle, collider));
+            }
+
+            let mut step =
+                |bodies: &mut RigidBodySet, colliders: &mut ColliderSet, joints: &mut JointSet| {
+                    pipeline.step(
+                        &gravity,
+                        &integration_parameters,
+                        &mut broad_phase,
+                        &mut narrow_phase,
+                        bodies,
+                        colliders,
+                        joints,
+                        &mut user_constraints,
+                        &mut ccd,
+                        &(),
+                        &(),
+                    )
+                };
+
+            for _ in 0..120 {
+                step(&mut bodies, &mut colliders, &mut joints);
+            }
+
+            let (middle, middle_collider) = blocks.remove(5);
+            colliders.remove(middle_collider, &mut bodies, true, settle_mode);
+            bodies.remove(middle, &mut colliders, &mut joints);
+
+            let mut peak_upward_velocity: Real = 0.0;
+
+            for _ in 0..120 {
+                step(&mut bodies, &mut colliders, &mut joints);
+
+                for (handle, _) in &blocks {
+                    peak_upward_velocity = peak_upward_velocity.max(bodies[*handle].linvel().y);
+                }
+            }
+
+            peak_upward_velocity
+        }
+
+        let popped = pull_middle_plate(RemoveSettleMode::Immediate);
+        let settled = pull_middle_plate(RemoveSettleMode::DampedOverNFrames(120));
+
+        // The blocks above the removed one fall onto the lower half of the tower.
+        assert!(popped > 1.5);
+        assert!(settled < 0.6);
+    }
+
+    #[test]
+    fn never_woken_pile_ignores_a_pebble_but_not_an_explosion() {
+        use crate::dynamics::WakePolicy;
+
+        // Rolls a pebble onto a sleeping pile, then optionally blows the pile up.
+        // Returns whether the pebble touched the pile, whether the pile was woken up by the
+        // pebble, and whether the pile was woken up by the explosion.
+        fn roll_pebble_onto_pile(wake_policy: WakePolicy, explode: bool) -> (bool, bool, bool) {
+            let mut pipeline = PhysicsPipeline::new();
+            let gravity = Vector::y() * -9.81;
+            let integration_parameters = IntegrationParameters::default();
+            let mut broad_phase = BroadPhase::new();
+            let mut narrow_phase = NarrowPhase::new();
+            let mut bodies = RigidBodySet::new();
+            let mut colliders = ColliderSet::new();
+            let mut ccd = CCDSolver::new();
+            let mut joints = JointSet::new();
+            let mut user_constraints = UserConstraintSet::new();
+
+            #[cfg(feature = "dim2")]
+            let ground = RigidBodyBuilder::new_static().translation(0.0, -0.5);
+            #[cfg(feature = "dim3")]
+            let ground = RigidBodyBuilder::new_static().translation(0.0, -0.5, 0.0);
+            let ground = bodies.insert(ground.build());
+            #[cfg(feature = "dim2")]
+            let ground_shape = ColliderBuilder::cuboid(10.0, 0.5);
+            #[cfg(feature = "dim3")]
+            let ground_shape = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+            colliders.insert(ground_shape.build(), ground, &mut bodies);
+
+            // The pile: three boxes on the ground and two boxes on top of them.
+            let mut pile = Vec::new();
+
+            let positions = [
+                (2.0, 0.25),
+                (2.5, 0.25),
+                (3.0, 0.25),
+                (2.25, 0.75),
+                (2.75, 0.75),
+            ];
+
+            for (x, y) in positions.iter() {
+                #[cfg(feature = "dim2")]
+                let rb = RigidBodyBuilder::new_dynamic().translation(*x, *y);
+                #[cfg(feature = "dim3")]
+                let rb = RigidBodyBuilder::new_dynamic().translation(*x, *y, 0.0);
+                let handle = bodies.insert(rb.wake_policy(wake_policy).build());
+                #[cfg(feature = "dim2")]
+                let shape = ColliderBuilder::cuboid(0.25, 0.25);
+                #[cfg(feature = "dim3")]
+                let shape = ColliderBuilder::cuboid(0.25, 0.25, 0.25);
+                let collider = colliders.insert(shape.build(), handle, &mut bodies);
+                pile.push((handle, collider));
+            }
+
+            #[cfg(feature = "dim2")]
+            let pebble = RigidBodyBuilder::new_dynamic()
+                .translation(0.0, 0.1)
+                .linvel(3.0, 0.0);
+            #[cfg(feature = "dim3")]
+            let pebble = RigidBodyBuilder::new_dynamic()
+                .translation(0.0, 0.1, 0.0)
+                .linvel(3.0, 0.0, 0.0);
+            let pebble = bodies.insert(pebble.build());
+            let pebble_collider =
+                colliders.insert(ColliderBuilder::ball(0.1).build(), pebble, &mut bodies);
+
+            let mut step = |narrow_phase: &mut NarrowPhase,
+                            bodies: &mut RigidBodySet,
+                            colliders: &mut ColliderSet| {
+                pipeline.step(
+                    &gravity,
+                    &integration_parameters,
+                    &mut broad_phase,
+                    narrow_phase,
+                    bodies,
+                    colliders,
+                    &mut joints,
+                    &mut user_constraints,
+                    &mut ccd,
+                    &(),
+                    &(),
+                )
+            };
+
+            // NOTE: the bodies inserted since the last timestep are woken up by the next
+            //       timestep, so the pile is put to sleep after the first one.
+            step(&mut narrow_phase, &mut bodies, &mut colliders);
+
+            for (handle, _) in &pile {
+                bodies.get_mut(*handle).unwrap().sleep();
+            }
+
+            let mut touched = false;
+            let mut woken_by_pebble = false;
+
+            for _ in 0..120 {
+                step(&mut narrow_phase, &mut bodies, &mut colliders);
+                touched |= pile.iter().any(|(_, collider)| {
+                    narrow_phase
+                        .contact_pair(pebble_collider, *collider)
+                        .map_or(false, |pair| pair.has_any_active_contact)
+                });
+                woken_by_pebble |= pile.iter().any(|(h, _)| !bodies[*h].is_sleeping());
+            }
+
+            if !explode {
+                return (touched, woken_by_pebble, false);
+            }
+
+            for (handle, _) in &pile {
+                bodies
+                    .get_mut(*handle)
+                    .unwrap()
+                    .apply_impulse(Vector::y() * 5.0, true);
+            }
+
+            step(&mut narrow_phase, &mut bodies, &mut colliders);
+            let woken_by_explosion = pile
+                .iter()
+                .all(|(h, _)| !bodies[*h].is_sleeping() && bodies[*h].linvel().y > 0.0);
+
+            (touched, woken_by_pebble, woken_by_explosion)
+        }
+
+        let (touched, woken_by_pebble, woken_by_explosion) =
+            roll_pebble_onto_pile(WakePolicy::Never, true);
+        assert!(touched);
+        assert!(!woken_by_pebble);
+        assert!(woken_by_explosion);
+
+        // The same pile is woken up by the pebble with the default policy.
+        let (touched, woken_by_pebble, _) = roll_pebble_onto_pile(WakePolicy::Always, false);
+        assert!(touched);
+        assert!(woken_by_pebble);
+    }
+
+    #[test]
+    fn pair_filter_overrides_the_prediction_distance_of_a_pair() {
+        use crate::geometry::SolverFlags;
+        use crate::math::Real;
+        use crate::pipeline::{
+            PairFilterContext, PairFilterResult, PhysicsHooks, PhysicsHooksFlags,
+        };
+
+        const PREDICTION_DISTANCE: Real = 0.2;
+
+        // Accepts all the contact pairs, with the given prediction distance.
+        struct PairPrediction(Option<Real>);
+
+        impl PhysicsHooks for PairPrediction {
+            fn active_hooks(&self) -> PhysicsHooksFlags {
+                PhysicsHooksFlags::FILTER_CONTACT_PAIR
+            }
+
+            fn filter_contact_pair(&self, _: &PairFilterContext) -> Option<PairFilterResult> {
+                Some(PairFilterResult {
+                    solver_flags: SolverFlags::default(),
+                    prediction_distance: self.0,
+                })
+            }
+        }
+
+        // Steps once with two balls with intersecting AABBs, separated by a gap larger than
+        // `PREDICTION_DISTANCE` but smaller than twice `PREDICTION_DISTANCE`. Returns the
+        // number of solver contacts between the balls.
+        fn count_solver_contacts(hooks: &PairPrediction) -> usize {
+            let mut pipeline = PhysicsPipeline::new();
+            let gravity = Vector::zeros();
+            let mut integration_parameters = IntegrationParameters::default();
+            integration_parameters.prediction_distance = PREDICTION_DISTANCE;
+            let mut broad_phase = BroadPhase::new();
+            let mut narrow_phase = NarrowPhase::new();
+            let mut bodies = RigidBodySet::new();
+            let mut colliders = ColliderSet::new();
+            let mut ccd = CCDSolver::new();
+            let mut joints = JointSet::new();
+            let mut user_constraints = UserConstraintSet::new();
+
+            let rb1 = bodies.insert(RigidBodyBuilder::new_dynamic().build());
+            #[cfg(feature = "dim2")]
+            let rb2 = RigidBodyBuilder::new_dynamic().translation(0.9, 0.9);
+            #[cfg(feature = "dim3")]
+            let rb2 = RigidBodyBuilder::new_dynamic().translation(0.9, 0.9, 0.0);
+            let rb2 = bodies.insert(rb2.build());
+            let co1 = colliders.insert(ColliderBuilder::ball(0.5).build(), rb1, &mut bodies);
+            let co2 = colliders.insert(ColliderBuilder::ball(0.5).build(), rb2, &mut bodies);
+
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                hooks,
+                &(),
+            );
+
+            let pair = narrow_phase
+                .contact_pair(co1, co2)
+                .expect("The AABBs of the balls intersect.");
+            assert_eq!(pair.prediction_distance, hooks.0);
+
+            pair.manifolds
+                .iter()
+                .map(|manifold| manifold.data.solver_contacts.len())
+                .sum()
+        }
+
+        let gap = 0.9 * Real::sqrt(2.0) - 1.0;
+        assert!(gap > PREDICTION_DISTANCE && gap < PREDICTION_DISTANCE * 2.0);
+
+        assert_eq!(count_solver_contacts(&PairPrediction(None)), 0);
+        assert!(count_solver_contacts(&PairPrediction(Some(PREDICTION_DISTANCE * 2.0))) > 0);
+    }
+
+    #[test]
+    fn resting_cargo_freezes_its_contact_manifolds() {
+        use crate::math::{Isometry, Real};
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let mut integration_parameters = IntegrationParameters::default();
+        integration_parameters.manifold_freezing_linear_threshold = 1.0e-3;
+        integration_parameters.manifold_freezing_angular_threshold = 1.0e-3;
+        let dt = integration_parameters.dt;
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        // A truck bed moving at a constant velocity, carrying a crate.
+        let truck = bodies.insert(RigidBodyBuilder::new_kinematic().build());
+        #[cfg(feature = "dim2")]
+        let truck_shape = ColliderBuilder::cuboid(5.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let truck_shape = ColliderBuilder::cuboid(5.0, 0.5, 5.0);
+        let truck_collider = colliders.insert(truck_shape.build(), truck, &mut bodies);
+
+        #[cfg(feature = "dim2")]
+        let cargo = RigidBodyBuilder::new_dynamic()
+            .translation(0.0, 0.75)
+            .linvel(1.0, 0.0);
+        #[cfg(feature = "dim3")]
+        let cargo = RigidBodyBuilder::new_dynamic()
+            .translation(0.0, 0.75, 0.0)
+            .linvel(1.0, 0.0, 0.0);
+        let cargo = bodies.insert(cargo.build());
+        #[cfg(feature = "dim2")]
+        let cargo_shape = ColliderBuilder::cuboid(0.25, 0.25);
+        #[cfg(feature = "dim3")]
+        let cargo_shape = ColliderBuilder::cuboid(0.25, 0.25, 0.25);
+        let cargo_collider = colliders.insert(cargo_shape.build(), cargo, &mut bodies);
+
+        let mut num_frozen_steps = 0;
+
+        for i in 0..120 {
+            let t = (i + 1) as Real * dt;
+            bodies[truck].set_next_kinematic_position(Isometry::new(Vector::x() * t, na::zero()));
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            );
+
+            let pair = narrow_phase
+                .contact_pair(truck_collider, cargo_collider)
+                .unwrap();
+            assert!(pair.has_any_active_contact);
+
+            if i >= 60 && pair.is_frozen() {
+                num_frozen_steps += 1;
+            }
+        }
+
+        // The crate stays on the truck while its manifolds are frozen.
+        assert_eq!(num_frozen_steps, 60);
+        let cargo_pos = bodies[cargo].position().translation.vector;
+        let truck_pos = bodies[truck].position().translation.vector;
+        assert!((cargo_pos.x - truck_pos.x).abs() < 0.01);
+        assert!((cargo_pos.y - 0.75).abs() < 0.01);
+
+        // Moving the crate unfreezes its manifolds.
+        let mut new_pos = *bodies[cargo].position();
+        new_pos.translation.vector.x += 0.5;
+        bodies.get_mut(cargo).unwrap().set_position(new_pos, true);
+        pipeline.step(
+            &gravity,
+            &integration_parameters,
+            &mut broad_phase,
+            &mut narrow_phase,
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            &mut user_constraints,
+            &mut ccd,
+            &(),
+            &(),
+        );
+
+        let pair = narrow_phase
+            .contact_pair(truck_collider, cargo_collider)
+            .unwrap();
+        assert!(!pair.is_frozen());
+    }
+
+    #[test]
+    fn released_kinematic_body_inherits_the_kinematic_velocity() {
+        use crate::dynamics::BodyStatus;
+        use crate::math::{Isometry, Real};
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters::default();
+        let dt = integration_parameters.dt;
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        // An object held by a "physics hand", i.e., moved kinematically.
+        let object = bodies.insert(RigidBodyBuilder::new_kinematic().build());
+        colliders.insert(ColliderBuilder::ball(0.1).build(), object, &mut bodies);
+        let hand_vel = Vector::x() * 3.0 + Vector::y() * 2.0;
+
+        let mut step = |bodies: &mut RigidBodySet| {
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            )
+        };
+
+        for i in 0..30 {
+            let t = (i + 1) as Real * dt;
+            bodies[object].set_next_kinematic_position(Isometry::new(hand_vel * t, na::zero()));
+            step(&mut bodies);
+        }
+
+        // The thrown object inherits the motion of the hand.
+        bodies[object].set_body_status(BodyStatus::Dynamic);
+        assert!((bodies[object].linvel() - hand_vel).norm() < 1.0e-3);
+
+        step(&mut bodies);
+        let expected_vel = hand_vel + gravity * dt;
+        assert!((bodies[object].linvel() - expected_vel).norm() < 1.0e-3);
+
+        // Grabbing it again records its velocities.
+        let linvel = *bodies[object].linvel();
+        bodies[object].set_body_status(BodyStatus::Kinematic);
+        let (recorded_linvel, _) = bodies[object].velocities_before_kinematic().unwrap();
+        assert_eq!(recorded_linvel, linvel);
+    }
+
+    #[test]
+    fn door_does_not_jump_when_its_hinge_body_toggles_status() {
+        #[cfg(feature = "dim2")]
+        use crate::dynamics::BallJoint;
+        #[cfg(feature = "dim3")]
+        use crate::dynamics::RevoluteJoint;
+        use crate::dynamics::{BodyStatus, FixedJoint};
+        use crate::math::{Isometry, Point};
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        // A wall, a hinge body fixed to the wall, and a door hanging from the hinge body.
+        let wall = bodies.insert(RigidBodyBuilder::new_static().build());
+        let hinge = bodies.insert(RigidBodyBuilder::new_dynamic().build());
+        colliders.insert(ColliderBuilder::ball(0.2).build(), hinge, &mut bodies);
+        joints.insert(
+            &mut bodies,
+            wall,
+            hinge,
+            FixedJoint::new(Isometry::identity(), Isometry::identity()),
+        );
+
+        let door_pos = Vector::y() * -1.0;
+        let door = bodies.insert(
+            RigidBodyBuilder::new_dynamic()
+                .position(Isometry::new(door_pos, na::zero()))
+                .build(),
+        );
+        #[cfg(feature = "dim2")]
+        let door_shape = ColliderBuilder::cuboid(0.1, 0.8);
+        #[cfg(feature = "dim3")]
+        let door_shape = ColliderBuilder::cuboid(0.1, 0.8, 0.5);
+        colliders.insert(door_shape.build(), door, &mut bodies);
+
+        // NOTE: the door is the first body of the joint, so the joint constraint is flipped
+        //       while the hinge body is kinematic.
+        #[cfg(feature = "dim2")]
+        let door_hinge = BallJoint::new(Point::from(-door_pos), Point::origin());
+        #[cfg(feature = "dim3")]
+        let door_hinge = RevoluteJoint::new(
+            Point::from(-door_pos),
+            Vector::z_axis(),
+            Point::origin(),
+            Vector::z_axis(),
+        );
+        joints.insert(&mut bodies, door, hinge, door_hinge);
+
+        let mut max_displacement = 0.0;
+
+        for i in 0..120 {
+            if i % 10 == 0 {
+                let status = if bodies[hinge].is_dynamic() {
+                    BodyStatus::Kinematic
+                } else {
+                    BodyStatus::Dynamic
+                };
+                bodies[hinge].set_body_status(status);
+            }
+
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            );
+
+            let displacement = (bodies[door].position().translation.vector - door_pos).norm();
+            max_displacement = displacement.max(max_displacement);
+        }
+
+        assert!(max_displacement < 0.02);
+    }
+
+    #[test]
+    fn max_contact_angular_acceleration_limits_the_spin_of_debris() {
+        use crate::math::Real;
+        use crate::utils::ang::ang_magnitude;
+
+        // Returns the largest angular velocity change of a coin hit by a truck during one
+        // timestep, and the final linear velocity of the coin.
+        fn hit_coin(max_accel: Option<Real>) -> (Real, Vector<Real>) {
+            let mut pipeline = PhysicsPipeline::new();
+            let gravity = Vector::zeros();
+            let integration_parameters = IntegrationParameters::default();
+            let mut broad_phase = BroadPhase::new();
+            let mut narrow_phase = NarrowPhase::new();
+            let mut bodies = RigidBodySet::new();
+            let mut colliders = ColliderSet::new();
+            let mut ccd = CCDSolver::new();
+            let mut joints = JointSet::new();
+            let mut user_constraints = UserConstraintSet::new();
+
+            #[cfg(feature = "dim2")]
+            let truck_builder = RigidBodyBuilder::new_dynamic().linvel(10.0, 0.0);
+            #[cfg(feature = "dim3")]
+            let truck_builder = RigidBodyBuilder::new_dynamic().linvel(10.0, 0.0, 0.0);
+            let truck = bodies.insert(truck_builder.build());
+            #[cfg(feature = "dim2")]
+            let truck_shape = ColliderBuilder::cuboid(1.0, 1.0);
+            #[cfg(feature = "dim3")]
+            let truck_shape = ColliderBuilder::cuboid(1.0, 1.0, 1.0);
+            colliders.insert(truck_shape.density(100.0).build(), truck, &mut bodies);
+
+            // The front of the truck touches the coin below its center, so the hit spins it.
+            #[cfg(feature = "dim2")]
+            let mut coin_builder = RigidBodyBuilder::new_dynamic().translation(1.051, 1.1);
+            #[cfg(feature = "dim3")]
+            let mut coin_builder = RigidBodyBuilder::new_dynamic().translation(1.051, 1.1, 0.0);
+            if let Some(max_accel) = max_accel {
+                coin_builder = coin_builder.max_contact_angular_acceleration(max_accel);
+            }
+            let coin = bodies.insert(coin_builder.build());
+            #[cfg(feature = "dim2")]
+            let coin_shape = ColliderBuilder::cuboid(0.05, 0.2);
+            #[cfg(feature = "dim3")]
+            let coin_shape = ColliderBuilder::cuboid(0.05, 0.2, 0.2);
+            colliders.insert(coin_shape.build(), coin, &mut bodies);
+
+            let mut max_dangvel: Real = 0.0;
+
+            for _ in 0..30 {
+                #[cfg(feature = "dim2")]
+                let prev_angvel = bodies[coin].angvel();
+                #[cfg(feature = "dim3")]
+                let prev_angvel = *bodies[coin].angvel();
+                pipeline.step(
+                    &gravity,
+                    &integration_parameters,
+                    &mut broad_phase,
+                    &mut narrow_phase,
+                    &mut bodies,
+                    &mut colliders,
+                    &mut joints,
+                    &mut user_constraints,
+                    &mut ccd,
+                    &(),
+                    &(),
+                );
+                let dangvel = ang_magnitude(&(bodies[coin].angvel() - prev_angvel));
+                max_dangvel = max_dangvel.max(dangvel);
+            }
+
+            (max_dangvel, *bodies[coin].linvel())
+        }
+
+        let dt = IntegrationParameters::default().dt;
+        let max_accel = 60.0;
+
+        let (uncapped_dangvel, _) = hit_coin(None);
+        assert!(uncapped_dangvel > max_accel * dt * 10.0);
+
+        let (capped_dangvel, coin_linvel) = hit_coin(Some(max_accel));
+        assert!(capped_dangvel <= max_accel * dt + 1.0e-4);
+        // The linear response is unaffected: the coin is still pushed by the truck.
+        assert!(coin_linvel.iter().all(|e| e.is_finite()));
+        assert!(coin_linvel.x > 5.0);
+    }
+
+    #[test]
+    fn joints_inserted_at_world_anchors_match_local_anchors() {
+        #[cfg(feature = "dim2")]
+        use crate::dynamics::BallJoint;
+        #[cfg(feature = "dim3")]
+        use crate::dynamics::RevoluteJoint;
+        use crate::dynamics::{FixedJoint, PrismaticJoint, WorldJointParams};
+        use crate::math::{Isometry, Point, Real};
+        #[cfg(feature = "dim3")]
+        use crate::utils::WBasis;
+
+        // Simulates a chain of three bodies attached to a static body, and returns the positions
+        // of the chain at each timestep.
+        fn simulate_chain(at_world: bool) -> Vec<Isometry<Real>> {
+            let mut pipeline = PhysicsPipeline::new();
+            let gravity = Vector::y() * -9.81;
+            let integration_parameters = IntegrationParameters::default();
+            let mut broad_phase = BroadPhase::new();
+            let mut narrow_phase = NarrowPhase::new();
+            let mut bodies = RigidBodySet::new();
+            let mut colliders = ColliderSet::new();
+            let mut ccd = CCDSolver::new();
+            let mut joints = JointSet::new();
+            let mut user_constraints = UserConstraintSet::new();
+
+            #[cfg(feature = "dim2")]
+            let poses = [
+                Isometry::new(Vector::new(0.0, 3.0), 0.4),
+                Isometry::new(Vector::new(1.0, 2.0), -0.3),
+                Isometry::new(Vector::new(2.0, 1.5), 1.2),
+                Isometry::new(Vector::new(2.5, 0.5), 0.1),
+            ];
+            #[cfg(feature = "dim3")]
+            let poses = [
+                Isometry::new(Vector::new(0.0, 3.0, 0.0), Vector::new(0.4, 0.0, 0.2)),
+                Isometry::new(Vector::new(1.0, 2.0, 0.5), Vector::new(-0.3, 0.2, 0.1)),
+                Isometry::new(Vector::new(2.0, 1.5, 0.2), Vector::new(0.1, 1.2, -0.4)),
+                Isometry::new(Vector::new(2.5, 0.5, 0.0), Vector::new(0.0, 0.1, 0.3)),
+            ];
+
+            let ground = bodies.insert(RigidBodyBuilder::new_static().position(poses[0]).build());
+            let chain: Vec<_> = poses[1..]
+                .iter()
+                .map(|pose| {
+                    let handle =
+                        bodies.insert(RigidBodyBuilder::new_dynamic().position(*pose).build());
+                    colliders.insert(ColliderBuilder::ball(0.2).build(), handle, &mut bodies);
+                    handle
+                })
+                .collect();
+
+            #[cfg(feature = "dim2")]
+            let hinge_anchor = Point::new(0.5, 2.5);
+            #[cfg(feature = "dim3")]
+            let hinge_anchor = Point::new(0.5, 2.5, 0.0);
+            let slider_anchor = Point::from(poses[2].translation.vector);
+            let slider_axis = Vector::ith_axis(0);
+            let weld_frame = Isometry::new(Vector::y(), na::zero());
+
+            if at_world {
+                #[cfg(feature = "dim2")]
+                let hinge = WorldJointParams::BallJoint(hinge_anchor);
+                #[cfg(feature = "dim3")]
+                let hinge = WorldJointParams::RevoluteJoint(hinge_anchor, Vector::z_axis());
+                joints.insert_at_world(&mut bodies, ground, chain[0], hinge);
+                joints.insert_at_world(
+                    &mut bodies,
+                    chain[0],
+                    chain[1],
+                    WorldJointParams::PrismaticJoint(slider_anchor, slider_axis),
+                );
+                joints.insert_at_world(
+                    &mut bodies,
+                    chain[1],
+                    chain[2],
+                    WorldJointParams::FixedJoint(weld_frame),
+                );
+            } else {
+                // Convert the world-space anchors and axes by hand.
+                #[cfg(feature = "dim2")]
+                let hinge = BallJoint::new(
+                    poses[0].inverse_transform_point(&hinge_anchor),
+                    poses[1].inverse_transform_point(&hinge_anchor),
+                );
+                #[cfg(feature = "dim3")]
+                let hinge = RevoluteJoint::new(
+                    poses[0].inverse_transform_point(&hinge_anchor),
+                    poses[0].inverse_transform_unit_vector(&Vector::z_axis()),
+                    poses[1].inverse_transform_point(&hinge_anchor),
+                    poses[1].inverse_transform_unit_vector(&Vector::z_axis()),
+                );
+                joints.insert(&mut bodies, ground, chain[0], hinge);
+
+                #[cfg(feature = "dim2")]
+                let slider = PrismaticJoint::new(
+                    poses[1].inverse_transform_point(&slider_anchor),
+                    poses[1].inverse_transform_unit_vector(&slider_axis),
+                    poses[2].inverse_transform_point(&slider_anchor),
+                    poses[2].inverse_transform_unit_vector(&slider_axis),
+                );
+                #[cfg(feature = "dim3")]
+                let slider_tangent = slider_axis.orthonormal_basis()[0];
+                #[cfg(feature = "dim3")]
+                let slider = PrismaticJoint::new(
+                    poses[1].inverse_transform_point(&slider_anchor),
+                    poses[1].inverse_transform_unit_vector(&slider_axis),
+                    poses[1].inverse_transform_vector(&slider_tangent),
+                    poses[2].inverse_transform_point(&slider_anchor),
+                    poses[2].inverse_transform_unit_vector(&slider_axis),
+                    poses[2].inverse_transform_vector(&slider_tangent),
+                );
+                joints.insert(&mut bodies, chain[0], chain[1], slider);
+
+                let weld =
+                    FixedJoint::new(poses[2].inv_mul(&weld_frame), poses[3].inv_mul(&weld_frame));
+                joints.insert(&mut bodies, chain[1], chain[2], weld);
+            }
+
+            let mut trajectory = Vec::new();
+
+            for _ in 0..60 {
+                pipeline.step(
+                    &gravity,
+                    &integration_parameters,
+                    &mut broad_phase,
+                    &mut narrow_phase,
+                    &mut bodies,
+                    &mut colliders,
+                    &mut joints,
+                    &mut user_constraints,
+                    &mut ccd,
+                    &(),
+                    &(),
+                );
+                trajectory.extend(chain.iter().map(|h| *bodies[*h].position()));
+            }
+
+            trajectory
+        }
+
+        assert_eq!(simulate_chain(true), simulate_chain(false));
+    }
+
+    #[test]
+    #[should_panic]
+    fn joint_with_non_unit_world_axis_panics() {
+        use crate::dynamics::PrismaticJoint;
+        use crate::math::Point;
+        use na::Unit;
+
+        let rb1 = RigidBodyBuilder::new_static().build();
+        let rb2 = RigidBodyBuilder::new_dynamic().build();
+        let axis = Unit::new_unchecked(Vector::x() * 2.0);
+        PrismaticJoint::from_world_anchor(&rb1, &rb2, Point::origin(), axis);
+    }
+
+    #[test]
+    fn warmstart_cache_survives_collider_reinsertion() {
+        use crate::math::Real;
+        use crate::pipeline::{ContactModificationContext, PhysicsHooks, PhysicsHooksFlags};
+        use std::sync::Mutex;
+
+        // Records the warmstart impulses of the first contacts given to the solver.
+        struct FirstContacts(Mutex<Option<Vec<Real>>>);
+
+        impl PhysicsHooks for FirstContacts {
+            fn active_hooks(&self) -> PhysicsHooksFlags {
+                PhysicsHooksFlags::MODIFY_SOLVER_CONTACTS
+            }
+
+            fn modify_solver_contacts(&self, context: &mut ContactModificationContext) {
+                let mut first_impulses = self.0.lock().unwrap();
+                if first_impulses.is_none() && !context.solver_contacts.is_empty() {
+                    *first_impulses = Some(
+                        context
+                            .solver_contacts
+                            .iter()
+                            .map(|c| c.warmstart_impulse)
+                            .collect(),
+                    );
+                }
+            }
+        }
+
+        let hooks = FirstContacts(Mutex::new(None));
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+        narrow_phase.set_warmstart_cache_capacity(16);
+
+        #[cfg(feature = "dim2")]
+        let (ground_shape, box_shape) = (
+            ColliderBuilder::cuboid(10.0, 0.5),
+            ColliderBuilder::cuboid(0.5, 0.5),
+        );
+        #[cfg(feature = "dim3")]
+        let (ground_shape, box_shape) = (
+            ColliderBuilder::cuboid(10.0, 0.5, 10.0),
+            ColliderBuilder::cuboid(0.5, 0.5, 0.5),
+        );
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        colliders.insert(
+            ground_shape.warmstart_cache_key(0).build(),
+            ground,
+            &mut bodies,
+        );
+
+        // Spawns a box resting on the ground, simulates it, and despawns it. Returns the
+        // warmstart impulses of its first contacts, and its trajectory.
+        let mut spawn_and_despawn = |bodies: &mut RigidBodySet, colliders: &mut ColliderSet| {
+            #[cfg(feature = "dim2")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(0.0, 1.0);
+            #[cfg(feature = "dim3")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(0.0, 1.0, 0.0);
+            let handle = bodies.insert(rb.build());
+            colliders.insert(
+                box_shape
+                    .clone()
+                    .warmstart_cache_key(1)
+                    .modify_solver_contacts(true)
+                    .build(),
+                handle,
+                bodies,
+            );
+
+            *hooks.0.lock().unwrap() = None;
+            let mut trajectory: Vec<Real> = Vec::new();
+
+            for _ in 0..30 {
+                pipeline.step(
+                    &gravity,
+                    &integration_parameters,
+                    &mut broad_phase,
+                    &mut narrow_phase,
+                    bodies,
+                    colliders,
+                    &mut joints,
+                    &mut user_constraints,
+                    &mut ccd,
+                    &hooks,
+                    &(),
+                );
+
+                trajectory.push(bodies[handle].position().translation.y);
+            }
+
+            bodies.remove(handle, colliders, &mut joints);
+            let first_impulses = hooks.0.lock().unwrap().take().unwrap();
+            (first_impulses, trajectory)
+        };
+
+        let (cold_impulses, _) = spawn_and_despawn(&mut bodies, &mut colliders);
+        let (warm_impulses1, trajectory1) = spawn_and_despawn(&mut bodies, &mut colliders);
+        let (warm_impulses2, trajectory2) = spawn_and_despawn(&mut bodies, &mut colliders);
+
+        // Only the first contacts of the first cycle start without warmstarting.
+        assert!(!cold_impulses.is_empty());
+        assert!(cold_impulses.iter().all(|impulse| *impulse == 0.0));
+        assert!(!warm_impulses1.is_empty());
+        assert!(warm_impulses1.iter().any(|impulse| *impulse > 0.0));
+
+        for (impulse1, impulse2) in warm_impulses1.iter().zip(warm_impulses2.iter()) {
+            assert!((impulse1 - impulse2).abs() < 1.0e-4);
+        }
+
+        for (y1, y2) in trajectory1.iter().zip(trajectory2.iter()) {
+            assert!((y1 - y2).abs() < 1.0e-5);
+        }
+    }
+
+    #[test]
+    fn contacts_resolve_toward_the_target_penetration() {
+        use crate::math::Real;
+
+        // Drops a ball on a snow-like ground and returns its final penetration depth.
+        fn settled_penetration(target_penetration: Real, gravity: Real) -> Real {
+            let mut pipeline = PhysicsPipeline::new();
+            let gravity = Vector::y() * -gravity;
+            let integration_parameters = IntegrationParameters {
+                allowed_linear_error: 0.0,
+                ..IntegrationParameters::default()
+            };
+            let mut broad_phase = BroadPhase::new();
+            let mut narrow_phase = NarrowPhase::new();
+            let mut bodies = RigidBodySet::new();
+            let mut colliders = ColliderSet::new();
+            let mut ccd = CCDSolver::new();
+            let mut joints = JointSet::new();
+            let mut user_constraints = UserConstraintSet::new();
+
+            #[cfg(feature = "dim2")]
+            let snow = ColliderBuilder::cuboid(10.0, 0.5);
+            #[cfg(feature = "dim3")]
+            let snow = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+            let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+            colliders.insert(
+                snow.contact_target_penetration(target_penetration).build(),
+                ground,
+                &mut bodies,
+            );
+
+            #[cfg(feature = "dim2")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(0.0, 1.0);
+            #[cfg(feature = "dim3")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(0.0, 1.0, 0.0);
+            let ball = bodies.insert(rb.build());
+            colliders.insert(ColliderBuilder::ball(0.5).build(), ball, &mut bodies);
+
+            for _ in 0..300 {
+                pipeline.step(
+                    &gravity,
+                    &integration_parameters,
+                    &mut broad_phase,
+                    &mut narrow_phase,
+                    &mut bodies,
+                    &mut colliders,
+                    &mut joints,
+                    &mut user_constraints,
+                    &mut ccd,
+                    &(),
+                    &(),
+                );
+            }
+
+            1.0 - bodies[ball].position().translation.y
+        }
+
+        let snow_penetration = settled_penetration(0.02, 9.81);
+        assert!((snow_penetration - 0.02).abs() < 1.0e-3);
+
+        // A negative target penetration keeps a gap between the colliders.
+        let gap_penetration = settled_penetration(-0.02, 9.81);
+        assert!((gap_penetration + 0.02).abs() < 1.0e-3);
+
+        // The snow resists further penetration as stiffly as a regular ground.
+        let ground_penetration = settled_penetration(0.0, 9.81);
+        let loaded_snow_penetration = settled_penetration(0.02, 100.0);
+        let loaded_ground_penetration = settled_penetration(0.0, 100.0);
+        assert!(
+            ((loaded_snow_penetration - snow_penetration)
+                - (loaded_ground_penetration - ground_penetration))
+                .abs()
+                < 1.0e-3
+        );
+    }
+
+    #[test]
+    fn batched_impulses_match_individual_impulses() {
+        use crate::math::Real;
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::zeros();
+        let integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        let mut handles = Vec::new();
+        for i in 0..10 {
+            let rb = if i == 3 {
+                RigidBodyBuilder::new_static()
+            } else {
+                RigidBodyBuilder::new_dynamic().sleeping(i % 2 == 0)
+            };
+            #[cfg(feature = "dim2")]
+            let rb = rb.translation(i as Real * 3.0, 0.0);
+            #[cfg(feature = "dim3")]
+            let rb = rb.translation(i as Real * 3.0, 0.0, 0.0);
+            let handle = bodies.insert(rb.build());
+            colliders.insert(ColliderBuilder::ball(0.5).build(), handle, &mut bodies);
+            handles.push(handle);
+        }
+
+        // Out of order, with duplicates, and including a static body.
+        let batch: Vec<_> = handles
+            .iter()
+            .rev()
+            .chain(handles.iter().step_by(3))
+            .enumerate()
+            .map(|(k, handle)| {
+                let impulse = Vector::x() * k as Real - Vector::y();
+                let point = bodies[*handle].world_com + Vector::y();
+                (*handle, impulse, point)
+            })
+            .collect();
+        let impulses: Vec<_> = batch.iter().map(|(h, impulse, _)| (*h, *impulse)).collect();
+
+        let mut individual_bodies = bodies.clone();
+        let mut point_bodies = bodies.clone();
+        let mut individual_point_bodies = bodies.clone();
+
+        bodies.apply_impulses(&impulses);
+        for (handle, impulse) in &impulses {
+            individual_bodies[*handle].apply_impulse(*impulse, true);
+        }
+
+        point_bodies.apply_impulses_at_points(&batch);
+        for (handle, impulse, point) in &batch {
+            individual_point_bodies[*handle].apply_impulse_at_point(*impulse, *point, true);
+        }
+
+        for handle in &handles {
+            for (batched, individual) in [
+                (&bodies[*handle], &individual_bodies[*handle]),
+                (&point_bodies[*handle], &individual_point_bodies[*handle]),
+            ]
+            .iter()
+            {
+                assert_eq!(batched.linvel(), individual.linvel());
+                assert_eq!(batched.angvel(), individual.angvel());
+                assert_eq!(batched.is_sleeping(), individual.is_sleeping());
+            }
+        }
+
+        // The woken-up bodies are simulated by the next timestep.
+        pipeline.step(
+            &gravity,
+            &integration_parameters,
+            &mut broad_phase,
+            &mut narrow_phase,
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            &mut user_constraints,
+            &mut ccd,
+            &(),
+            &(),
+        );
+
+        for (i, handle) in handles.iter().enumerate() {
+            let moved = bodies[*handle].position() != individual_point_bodies[*handle].position();
+            assert_eq!(moved, i != 3);
+        }
+    }
+
+    #[test]
+    fn user_velocity_constraint_cancels_the_momentum_of_a_closed_loop() {
+        use crate::dynamics::{
+            DeltaVel, PositionOffsetsMap, RigidBodyHandle, UserVelocityConstraint,
+        };
+        use crate::math::Real;
+
+        // Cancels the total momentum of the bodies along the x axis.
+        struct ZeroMomentum {
+            bodies: Vec<RigidBodyHandle>,
+            masses: Vec<Real>,
+            offsets: Vec<usize>,
+            momentum: Real,
+            impulse: Real,
+        }
+
+        impl UserVelocityConstraint for ZeroMomentum {
+            fn bodies(&self) -> &[RigidBodyHandle] {
+                &self.bodies
+            }
+
+            fn initialize(
+                &mut self,
+                _params: &IntegrationParameters,
+                bodies: &RigidBodySet,
+                offsets: &PositionOffsetsMap,
+            ) {
+                self.masses = self.bodies.iter().map(|h| bodies[*h].mass()).collect();
+                self.offsets = self
+                    .bodies
+                    .iter()
+                    .map(|h| offsets.get(*h).unwrap())
+                    .collect();
+                self.momentum = self
+                    .bodies
+                    .iter()
+                    .map(|h| bodies[*h].mass() * bodies[*h].linvel().x)
+                    .sum();
+            }
+
+            fn warmstart(&self, mj_lambdas: &mut [DeltaVel<Real>]) {
+                for i in &self.offsets {
+                    mj_lambdas[*i].linear.x += self.impulse;
+                }
+            }
+
+            fn solve(&mut self, mj_lambdas: &mut [DeltaVel<Real>]) {
+                let mut momentum = self.momentum;
+                for (i, mass) in self.offsets.iter().zip(self.masses.iter()) {
+                    momentum += mass * mj_lambdas[*i].linear.x;
+                }
+
+                // All the bodies get the same velocity change.
+                let total_mass: Real = self.masses.iter().sum();
+                let dvel = -momentum / total_mass;
+                self.impulse += dvel;
+
+                for i in &self.offsets {
+                    mj_lambdas[*i].linear.x += dvel;
+                }
+            }
+
+            fn writeback(&mut self) {}
+        }
+
+        // Returns the displacement of the center of mass of three bodies pulled along the
+        // x axis by gravity, with a total momentum cancelled either by a user velocity
+        // constraint, or by correcting the velocities before each step.
+        fn center_of_mass_drift(in_solver: bool) -> Real {
+            let mut pipeline = PhysicsPipeline::new();
+            let gravity = Vector::x() * -1.0;
+            let integration_parameters = IntegrationParameters::default();
+            let mut broad_phase = BroadPhase::new();
+            let mut narrow_phase = NarrowPhase::new();
+            let mut bodies = RigidBodySet::new();
+            let mut colliders = ColliderSet::new();
+            let mut ccd = CCDSolver::new();
+            let mut joints = JointSet::new();
+            let mut user_constraints = UserConstraintSet::new();
+
+            let mut handles = Vec::new();
+            for i in 0..3 {
+                #[cfg(feature = "dim2")]
+                let rb = RigidBodyBuilder::new_dynamic()
+                    .translation(i as Real * 2.0, 0.0)
+                    .linvel(0.0, 1.0);
+                #[cfg(feature = "dim3")]
+                let rb = RigidBodyBuilder::new_dynamic()
+                    .translation(i as Real * 2.0, 0.0, 0.0)
+                    .linvel(0.0, 1.0, 0.0);
+                let handle = bodies.insert(rb.build());
+                let co = ColliderBuilder::ball(0.5).density(i as Real + 1.0);
+                colliders.insert(co.build(), handle, &mut bodies);
+                handles.push(handle);
+            }
+
+            if in_solver {
+                user_constraints.insert_velocity_constraint(Box::new(ZeroMomentum {
+                        bodies: handles.clone(),
+                        masses: Vec::new(),
+                        offsets: Vec::new(),
+                        momentum: 0.0,
+                        impulse: 0.0,
+                    }));
+            }
+
+            let center_of_mass = |bodies: &RigidBodySet| {
+                let (moment, mass) = handles.iter().fold((0.0, 0.0), |(moment, mass), h| {
+                    let rb = &bodies[*h];
+                    (moment + rb.mass() * rb.world_com.x, mass + rb.mass())
+                });
+                moment / mass
+            };
+            let initial_center_of_mass = center_of_mass(&bodies);
+
+            for _ in 0..60 {
+                if !in_solver {
+                    let total_mass: Real = handles.iter().map(|h| bodies[*h].mass()).sum();
+                    let momentum: Real = handles
+                        .iter()
+                        .map(|h| bodies[*h].mass() * bodies[*h].linvel().x)
+                        .sum();
+
+                    for h in &handles {
+                        let mut linvel = *bodies[*h].linvel();
+                        linvel.x -= momentum / total_mass;
+                        bodies[*h].set_linvel(linvel, true);
+                    }
+                }
+
+                pipeline.step(
+                    &gravity,
+                    &integration_parameters,
+                    &mut broad_phase,
+                    &mut narrow_phase,
+                    &mut bodies,
+                    &mut colliders,
+                    &mut joints,
+                    &mut user_constraints,
+                    &mut ccd,
+                    &(),
+                    &(),
+                );
+            }
+
+            (center_of_mass(&bodies) - initial_center_of_mass).abs()
+        }
+
+        assert!(center_of_mass_drift(true) < 1.0e-6);
+        assert!(center_of_mass_drift(false) > 1.0e-2);
+    }
+
+    #[test]
+    fn off_center_impulses_and_forces_spin_up_a_box() {
+        #[cfg(feature = "dim3")]
+        use crate::math::AngVector;
+        use crate::math::Real;
+        use crate::utils::ang::ang_magnitude;
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::zeros();
+        let integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        let (hx, hy): (Real, Real) = (1.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let hz: Real = 0.25;
+        let density = 2.0;
+        let hit = 3.0;
+
+        #[cfg(feature = "dim2")]
+        let (shape, mass, corner) = (
+            ColliderBuilder::cuboid(hx, hy),
+            density * 4.0 * hx * hy,
+            Vector::new(hx, hy),
+        );
+        #[cfg(feature = "dim3")]
+        let (shape, mass, corner) = (
+            ColliderBuilder::cuboid(hx, hy, hz),
+            density * 8.0 * hx * hy * hz,
+            Vector::new(hx, hy, hz),
+        );
+        #[cfg(feature = "dim2")]
+        let expected_angvel = hx * hit / (mass * (hx * hx + hy * hy) / 3.0);
+        #[cfg(feature = "dim3")]
+        let expected_angvel = AngVector::new(
+            -hz * hit / (mass * (hy * hy + hz * hz) / 3.0),
+            0.0,
+            hx * hit / (mass * (hx * hx + hy * hy) / 3.0),
+        );
+
+        // The boxes are far apart so they don't touch each other.
+        let mut spawn_box = |bodies: &mut RigidBodySet, builder: RigidBodyBuilder, x: Real| {
+            #[cfg(feature = "dim2")]
+            let builder = builder.translation(x, 0.0);
+            #[cfg(feature = "dim3")]
+            let builder = builder.translation(x, 0.0, 0.0);
+            let handle = bodies.insert(builder.sleeping(true).build());
+            colliders.insert(shape.clone().density(density).build(), handle, bodies);
+            handle
+        };
+        let impulse_box = spawn_box(&mut bodies, RigidBodyBuilder::new_dynamic(), 0.0);
+        let locked_box = spawn_box(
+            &mut bodies,
+            RigidBodyBuilder::new_dynamic().lock_rotations(),
+            10.0,
+        );
+        let force_box = spawn_box(&mut bodies, RigidBodyBuilder::new_dynamic(), 20.0);
+
+        // A bullet hitting the corner of the box.
+        for handle in &[impulse_box, locked_box] {
+            let point = bodies[*handle].world_com + corner;
+            bodies[*handle].apply_impulse_at_point(Vector::y() * hit, point, true);
+            assert!(!bodies[*handle].is_sleeping());
+            assert!((bodies[*handle].linvel() - Vector::y() * (hit / mass)).norm() < 1.0e-5);
+        }
+
+        assert!(ang_magnitude(&(bodies[impulse_box].angvel - expected_angvel)) < 1.0e-5);
+        assert_eq!(ang_magnitude(&bodies[locked_box].angvel), 0.0);
+
+        // The same force applied during one timestep yields the same velocities.
+        let dt = integration_parameters.dt;
+        let point = bodies[force_box].world_com + corner;
+        bodies[force_box].apply_force_at_point(Vector::y() * (hit / dt), point, true);
+        pipeline.step(
+            &gravity,
+            &integration_parameters,
+            &mut broad_phase,
+            &mut narrow_phase,
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            &mut user_constraints,
+            &mut ccd,
+            &(),
+            &(),
+        );
+
+        assert!((bodies[force_box].linvel() - Vector::y() * (hit / mass)).norm() < 1.0e-4);
+        assert!(ang_magnitude(&(bodies[force_box].angvel - expected_angvel)) < 1.0e-4);
+    }
+
+    #[test]
+    fn excluded_ragdoll_limbs_never_collide_with_each_other() {
+        use crate::geometry::{ColliderHandle, SolverFlags};
+        use crate::math::Real;
+        use crate::pipeline::{
+            PairFilterContext, PairFilterResult, PhysicsHooks, PhysicsHooksFlags,
+        };
+
+        // Accepts all the contact pairs, but must never see an excluded pair.
+        struct CheckExclusions;
+
+        impl PhysicsHooks for CheckExclusions {
+            fn active_hooks(&self) -> PhysicsHooksFlags {
+                PhysicsHooksFlags::FILTER_CONTACT_PAIR
+            }
+
+            fn filter_contact_pair(&self, context: &PairFilterContext) -> Option<PairFilterResult> {
+                assert!(!context.collider1.is_excluded_from(context.collider_handle2));
+                assert!(!context.collider2.is_excluded_from(context.collider_handle1));
+                Some(PairFilterResult {
+                    solver_flags: SolverFlags::default(),
+                    prediction_distance: None,
+                })
+            }
+        }
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::zeros();
+        let integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        // Two ragdolls made of overlapping limbs, one lying on top of the other. The neighbor
+        // limbs of a ragdoll are excluded from each other.
+        const NUM_LIMBS: usize = 4;
+        let mut ragdolls: Vec<Vec<ColliderHandle>> = Vec::new();
+        for y in &[0.0, 0.8] {
+            let mut limbs = Vec::new();
+            for i in 0..NUM_LIMBS {
+                #[cfg(feature = "dim2")]
+                let rb = RigidBodyBuilder::new_dynamic().translation(i as Real * 0.8, *y);
+                #[cfg(feature = "dim3")]
+                let rb = RigidBodyBuilder::new_dynamic().translation(i as Real * 0.8, *y, 0.0);
+                let body = bodies.insert(rb.build());
+                let collider = ColliderBuilder::ball(0.5).build();
+                limbs.push(colliders.insert(collider, body, &mut bodies));
+            }
+
+            for pair in limbs.windows(2) {
+                assert!(colliders.add_exclusion(pair[0], pair[1]));
+                assert!(!colliders.add_exclusion(pair[1], pair[0]));
+                assert!(colliders.is_excluded(pair[1], pair[0]));
+            }
+
+            ragdolls.push(limbs);
+        }
+        let (ragdoll1, ragdoll2) = (&ragdolls[0], &ragdolls[1]);
+
+        pipeline.step(
+            &gravity,
+            &integration_parameters,
+            &mut broad_phase,
+            &mut narrow_phase,
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            &mut user_constraints,
+            &mut ccd,
+            &CheckExclusions,
+            &(),
+        );
+
+        let has_contacts = |narrow_phase: &NarrowPhase, co1, co2| {
+            narrow_phase
+                .contact_pair(co1, co2)
+                .map_or(false, |pair| pair.has_any_active_contact)
+        };
+
+        for ragdoll in &ragdolls {
+            for pair in ragdoll.windows(2) {
+                assert!(!has_contacts(&narrow_phase, pair[0], pair[1]));
+            }
+        }
+
+        for (limb1, limb2) in ragdoll1.iter().zip(ragdoll2.iter()) {
+            assert!(has_contacts(&narrow_phase, *limb1, *limb2));
+        }
+
+        // Removing a limb removes its exclusions.
+        let limb = ragdoll1[1];
+        let body = colliders[limb].parent();
+        bodies.remove(body, &mut colliders, &mut joints);
+        assert!(colliders[ragdoll1[0]].exclusions().is_empty());
+        assert_eq!(colliders[ragdoll1[2]].exclusions(), &[ragdoll1[3]]);
+        assert!(!colliders.remove_exclusion(ragdoll1[0], limb));
+        assert!(colliders.remove_exclusion(ragdoll1[3], ragdoll1[2]));
+        assert!(!colliders.is_excluded(ragdoll1[2], ragdoll1[3]));
+    }
+
+    #[test]
+    fn locked_axes_are_enforced_by_the_solver_and_can_change_at_runtime() {
+        use crate::dynamics::RigidBody;
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        // A platform that can only slide horizontally, and spin around the vertical axis in 3D.
+        #[cfg(feature = "dim2")]
+        let platform = RigidBodyBuilder::new_dynamic()
+            .restrict_translations(true, false)
+            .lock_rotations();
+        #[cfg(feature = "dim3")]
+        let platform = RigidBodyBuilder::new_dynamic()
+            .restrict_translations(true, false, true)
+            .restrict_rotations(false, true, false);
+        let platform = bodies.insert(platform.build());
+        #[cfg(feature = "dim2")]
+        let platform_shape = ColliderBuilder::cuboid(2.0, 0.25);
+        #[cfg(feature = "dim3")]
+        let platform_shape = ColliderBuilder::cuboid(2.0, 0.25, 2.0);
+        colliders.insert(platform_shape.build(), platform, &mut bodies);
+
+        // A ball falling on a corner of the platform.
+        #[cfg(feature = "dim2")]
+        let ball = RigidBodyBuilder::new_dynamic().translation(1.5, 1.0);
+        #[cfg(feature = "dim3")]
+        let ball = RigidBodyBuilder::new_dynamic().translation(1.5, 1.0, 1.5);
+        let ball = bodies.insert(ball.build());
+        colliders.insert(ColliderBuilder::ball(0.5).build(), ball, &mut bodies);
+
+        // An off-center impulse with a component along every axis.
+        let point = bodies[platform].world_com + Vector::x() * 2.0;
+        bodies[platform].apply_impulse_at_point(Vector::repeat(1.0), point, true);
+
+        let assert_locks_hold = |rb: &RigidBody| {
+            assert_eq!(rb.linvel().y, 0.0);
+            assert_eq!(rb.position().translation.y, 0.0);
+            #[cfg(feature = "dim2")]
+            assert_eq!(rb.angvel(), 0.0);
+            #[cfg(feature = "dim3")]
+            assert!(rb.angvel().x.abs() < 1.0e-6 && rb.angvel().z.abs() < 1.0e-6);
+            #[cfg(feature = "dim3")]
+            assert!((rb.position().rotation * Vector::y() - Vector::y()).norm() < 1.0e-5);
+        };
+
+        assert!(bodies[platform].linvel().x > 0.0);
+        #[cfg(feature = "dim3")]
+        assert!(bodies[platform].angvel().y != 0.0);
+        assert_locks_hold(&bodies[platform]);
+
+        for _ in 0..60 {
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            );
+            assert_locks_hold(&bodies[platform]);
+        }
+
+        // The ball landed on the platform instead of pushing it down.
+        assert!(bodies[ball].position().translation.y > 0.5);
+
+        // Unlocking the vertical translation at runtime lets the platform fall.
+        #[cfg(feature = "dim2")]
+        bodies[platform].restrict_translations(true, true, true);
+        #[cfg(feature = "dim3")]
+        bodies[platform].restrict_translations(true, true, true, true);
+        for _ in 0..10 {
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            );
+        }
+
+        assert!(bodies[platform].effective_inv_mass.y > 0.0);
+        assert!(bodies[platform].position().translation.y < 0.0);
+
+        // Locking all the translations again removes the linear velocity right away.
+        bodies[platform].lock_translations(true, true);
+        assert_eq!(*bodies[platform].linvel(), Vector::zeros());
+        assert!(bodies[platform].is_translation_locked());
+    }
+
+    #[test]
+    fn bodies_passing_through_a_one_way_platform_dont_wake_its_pile() {
+        use crate::dynamics::WakePolicy;
+        use crate::math::Real;
+        use crate::pipeline::{ContactModificationContext, PhysicsHooks, PhysicsHooksFlags};
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        const PASSING: u128 = 1;
+
+        // The contacts between the platform and the passing bodies are disabled while the
+        // platform lets them pass through.
+        struct OneWayPlatform {
+            pass_through: AtomicBool,
+        }
+
+        impl PhysicsHooks for OneWayPlatform {
+            fn active_hooks(&self) -> PhysicsHooksFlags {
+                PhysicsHooksFlags::MODIFY_SOLVER_CONTACTS
+            }
+
+            fn modify_solver_contacts(&self, context: &mut ContactModificationContext) {
+                let passing = context.collider1.user_data == PASSING
+                    || context.collider2.user_data == PASSING;
+                if passing && self.pass_through.load(Ordering::Relaxed) {
+                    context.solver_contacts.clear();
+                }
+            }
+        }
+
+        let hooks = OneWayPlatform {
+            pass_through: AtomicBool::new(true),
+        };
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_shape = ColliderBuilder::cuboid(60.0, 0.5).translation(0.0, -0.5);
+        #[cfg(feature = "dim3")]
+        let ground_shape = ColliderBuilder::cuboid(60.0, 0.5, 60.0).translation(0.0, -0.5, 0.0);
+        colliders.insert(ground_shape.build(), ground, &mut bodies);
+
+        // A sleeping platform, which can only be woken up by a strong impulse, carrying a
+        // sleeping pile on its left end.
+        #[cfg(feature = "dim2")]
+        let platform = RigidBodyBuilder::new_dynamic().translation(0.0, 3.0);
+        #[cfg(feature = "dim3")]
+        let platform = RigidBodyBuilder::new_dynamic().translation(0.0, 3.0, 0.0);
+        let platform = platform
+            .lock_translations()
+            .lock_rotations()
+            .wake_policy(WakePolicy::OnlyStrongImpulse(1.0e6));
+        let platform = bodies.insert(platform.build());
+        #[cfg(feature = "dim2")]
+        let platform_shape = ColliderBuilder::cuboid(50.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let platform_shape = ColliderBuilder::cuboid(50.0, 0.5, 50.0);
+        let platform_shape = platform_shape.modify_solver_contacts(true).build();
+        colliders.insert(platform_shape, platform, &mut bodies);
+
+        let mut pile = Vec::new();
+        for i in 0..3 {
+            let y = 4.0 + i as Real;
+            #[cfg(feature = "dim2")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(-45.0, y);
+            #[cfg(feature = "dim3")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(-45.0, y, 0.0);
+            let handle = bodies.insert(rb.build());
+            #[cfg(feature = "dim2")]
+            let shape = ColliderBuilder::cuboid(0.5, 0.5);
+            #[cfg(feature = "dim3")]
+            let shape = ColliderBuilder::cuboid(0.5, 0.5, 0.5);
+            colliders.insert(shape.build(), handle, &mut bodies);
+            pile.push(handle);
+        }
+
+        // A crowd of 100 bodies falling through the platform, far from the pile.
+        let mut crowd = Vec::new();
+        for i in 0..100 {
+            #[cfg(feature = "dim2")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(i as Real * 0.5 - 20.0, 3.0);
+            #[cfg(feature = "dim3")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(
+                (i % 10) as Real * 0.5,
+                3.0,
+                (i / 10) as Real * 0.5,
+            );
+            let handle = bodies.insert(rb.build());
+            let collider = ColliderBuilder::ball(0.2).user_data(PASSING).build();
+            colliders.insert(collider, handle, &mut bodies);
+            crowd.push(handle);
+        }
+
+        // A body floating inside of the platform, and kept awake by its velocity.
+        #[cfg(feature = "dim2")]
+        let probe = RigidBodyBuilder::new_dynamic()
+            .translation(35.0, 3.0)
+            .linvel(0.5, 0.0);
+        #[cfg(feature = "dim3")]
+        let probe = RigidBodyBuilder::new_dynamic()
+            .translation(35.0, 3.0, 0.0)
+            .linvel(0.5, 0.0, 0.0);
+        let probe = bodies.insert(probe.gravity_scale(0.0).build());
+        let collider = ColliderBuilder::ball(0.2).user_data(PASSING).build();
+        colliders.insert(collider, probe, &mut bodies);
+
+        let mut step = |bodies: &mut RigidBodySet, colliders: &mut ColliderSet| {
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                bodies,
+                colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &hooks,
+                &(),
+            )
+        };
+
+        // NOTE: the bodies inserted since the last timestep are woken up by the next
+        //       timestep, so the platform and the pile are put to sleep after the first one.
+        step(&mut bodies, &mut colliders);
+
+        for handle in pile.iter().chain(std::iter::once(&platform)) {
+            bodies.get_mut(*handle).unwrap().sleep();
+        }
+
+        for _ in 0..120 {
+            step(&mut bodies, &mut colliders);
+
+            assert!(bodies[platform].is_sleeping());
+            assert!(pile.iter().all(|h| bodies[*h].is_sleeping()));
+        }
+
+        assert!(crowd
+            .iter()
+            .all(|h| bodies[*h].position().translation.y < 2.0));
+        assert!(!bodies[probe].is_sleeping());
+
+        // Re-enabling the contacts of the floating body wakes the platform up right away,
+        // despite its wake policy. The hook sees the contacts at the end of the first timestep,
+        // and they wake the platform up at the start of the second one.
+        hooks.pass_through.store(false, Ordering::Relaxed);
+        step(&mut bodies, &mut colliders);
+        assert!(bodies[platform].is_sleeping());
+        step(&mut bodies, &mut colliders);
+        assert!(!bodies[platform].is_sleeping());
+    }
+
+    #[test]
+    fn recreated_sliding_pair_with_swapped_colliders_keeps_its_friction() {
+        use crate::geometry::{ColliderHandle, ContactPair};
+        use crate::math::Real;
+        use crate::utils::WBasis;
+
+        // The world-space friction impulse applied on the collider `co`, given a tangent impulse
+        // of a contact of `pair` with the contact normal `normal`.
+        #[cfg(feature = "dim2")]
+        fn world_friction(
+            pair: &ContactPair,
+            co: ColliderHandle,
+            normal: &Vector<Real>,
+            impulse: Real,
+        ) -> Vector<Real> {
+            let sign = if pair.pair.collider1 == co { 1.0 } else { -1.0 };
+            (-normal).orthonormal_vector() * impulse * sign
+        }
+
+        #[cfg(feature = "dim3")]
+        fn world_friction(
+            pair: &ContactPair,
+            co: ColliderHandle,
+            normal: &Vector<Real>,
+            impulse: na::Vector2<Real>,
+        ) -> Vector<Real> {
+            let sign = if pair.pair.collider1 == co { 1.0 } else { -1.0 };
+            let force_dir1 = -normal;
+            let tangent = (force_dir1 * (1.0 as Real).copysign(force_dir1.z)).orthonormal_vector();
+            (tangent * impulse.x + force_dir1.cross(&tangent) * impulse.y) * sign
+        }
+
+        // Slides a puck on a dynamic plate. If `reinsert_plate_first` is set, both colliders are
+        // removed and reinserted mid-slide in this order, so that their pair is recreated. Returns
+        // the velocities of the puck after the recreation, and whether the recreated pair has
+        // the reverse order.
+        fn slide(reinsert_plate_first: Option<bool>) -> (Vec<Vector<Real>>, bool) {
+            let mut pipeline = PhysicsPipeline::new();
+            let gravity = Vector::y() * -9.81;
+            let integration_parameters = IntegrationParameters::default();
+            let mut broad_phase = BroadPhase::new();
+            let mut narrow_phase = NarrowPhase::new();
+            let mut bodies = RigidBodySet::new();
+            let mut colliders = ColliderSet::new();
+            let mut ccd = CCDSolver::new();
+            let mut joints = JointSet::new();
+            let mut user_constraints = UserConstraintSet::new();
+            narrow_phase.set_warmstart_cache_capacity(16);
+
+            // The plate is dynamic, so the pair isn't solved as a ground contact.
+            let plate = RigidBodyBuilder::new_dynamic()
+                .lock_translations()
+                .lock_rotations()
+                .can_sleep(false);
+            let plate = bodies.insert(plate.build());
+            #[cfg(feature = "dim2")]
+            let (plate_shape, rb, linvel) = (
+                ColliderBuilder::cuboid(20.0, 0.5),
+                RigidBodyBuilder::new_dynamic().translation(0.0, 1.0),
+                Vector::new(4.0, 0.0),
+            );
+            #[cfg(feature = "dim3")]
+            let (plate_shape, rb, linvel) = (
+                ColliderBuilder::cuboid(20.0, 0.5, 20.0),
+                RigidBodyBuilder::new_dynamic().translation(0.0, 1.0, 0.0),
+                Vector::new(4.0, 0.0, 2.0),
+            );
+            let puck = bodies.insert(rb.lock_rotations().build());
+            let puck_shape = ColliderBuilder::ball(0.5);
+            let mut plate_co = colliders.insert(
+                plate_shape.clone().warmstart_cache_key(0).build(),
+                plate,
+                &mut bodies,
+            );
+            let mut puck_co = colliders.insert(
+                puck_shape.clone().warmstart_cache_key(1).build(),
+                puck,
+                &mut bodies,
+            );
+
+            let mut friction_before_swap = Vector::zeros();
+            let mut velocities = Vec::new();
+            let mut reversed = false;
+
+            for i in 0..40 {
+                if i == 20 {
+                    bodies[puck].set_linvel(linvel, true);
+                }
+
+                let plate_was_first = narrow_phase
+                    .contact_pair(plate_co, puck_co)
+                    .map(|p| p.pair.collider1 == plate_co);
+
+                if let (Some(plate_first), 30) = (reinsert_plate_first, i) {
+                    let reinserted = if plate_first {
+                        [plate_co, puck_co]
+                    } else {
+                        [puck_co, plate_co]
+                    };
+
+                    for co in &reinserted {
+                        colliders.remove(*co, &mut bodies, false, RemoveSettleMode::Immediate);
+                    }
+
+                    for co in &reinserted {
+                        if *co == plate_co {
+                            let collider = plate_shape.clone().warmstart_cache_key(0).build();
+                            plate_co = colliders.insert(collider, plate, &mut bodies);
+                        } else {
+                            let collider = puck_shape.clone().warmstart_cache_key(1).build();
+                            puck_co = colliders.insert(collider, puck, &mut bodies);
+                        }
+                    }
+                }
+
+                pipeline.step(
+                    &gravity,
+                    &integration_parameters,
+                    &mut broad_phase,
+                    &mut narrow_phase,
+                    &mut bodies,
+                    &mut colliders,
+                    &mut joints,
+                    &mut user_constraints,
+                    &mut ccd,
+                    &(),
+                    &(),
+                );
+
+                let pair = narrow_phase.contact_pair(plate_co, puck_co).unwrap();
+
+                if i == 29 {
+                    for manifold in &pair.manifolds {
+                        for contact in &manifold.points {
+                            let impulse = contact.data.tangent_impulse;
+                            friction_before_swap +=
+                                world_friction(pair, puck_co, &manifold.data.normal, impulse);
+                        }
+                    }
+                }
+
+                if reinsert_plate_first.is_some() && i == 30 {
+                    reversed = plate_was_first != Some(pair.pair.collider1 == plate_co);
+
+                    // The warmstart friction of the new pair matches the friction of the old one.
+                    let mut warmstart_friction = Vector::zeros();
+                    for manifold in &pair.manifolds {
+                        for contact in &manifold.data.solver_contacts {
+                            let impulse = contact.warmstart_tangent_impulse;
+                            warmstart_friction +=
+                                world_friction(pair, puck_co, &manifold.data.normal, impulse);
+                        }
+                    }
+
+                    assert!(friction_before_swap.norm() > 1.0e-3);
+                    assert!(
+                        (warmstart_friction - friction_before_swap).norm()
+                            <= friction_before_swap.norm() * 1.0e-2
+                    );
+                }
+
+                if i >= 30 {
+                    velocities.push(*bodies[puck].linvel());
+                }
+            }
+
+            (velocities, reversed)
+        }
+
+        let (reference, _) = slide(None);
+        let mut any_reversed = false;
+
+        // The order of the recreated pair depends on the broad-phase proxies given to the
+        // reinserted colliders, so both insertion orders are tried.
+        for plate_first in [true, false].iter() {
+            let (velocities, reversed) = slide(Some(*plate_first));
+            any_reversed |= reversed;
+
+            // The puck keeps sliding in the same direction, without any lateral velocity spike.
+            for (v1, v2) in reference.iter().zip(velocities.iter()) {
+                assert!(v1.x > 0.0);
+                assert!((v1 - v2).norm() < 1.0e-2);
+            }
+        }
+
+        assert!(any_reversed);
+    }
+
+    #[test]
+    fn velocity_driven_kinematic_platform_carries_the_bodies_riding_it() {
+        use crate::math::{Isometry, Real};
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters::default();
+        let dt = integration_parameters.dt;
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        #[cfg(feature = "dim2")]
+        let (platform_shape, box_shape, box_body, no_rotation) = (
+            ColliderBuilder::cuboid(10.0, 0.5),
+            ColliderBuilder::cuboid(0.5, 0.5),
+            RigidBodyBuilder::new_dynamic().translation(0.0, 1.0),
+            0.0,
+        );
+        #[cfg(feature = "dim3")]
+        let (platform_shape, box_shape, box_body, no_rotation) = (
+            ColliderBuilder::cuboid(10.0, 0.5, 10.0),
+            ColliderBuilder::cuboid(0.5, 0.5, 0.5),
+            RigidBodyBuilder::new_dynamic().translation(0.0, 1.0, 0.0),
+            Vector::zeros(),
+        );
+
+        let platform = bodies.insert(RigidBodyBuilder::new_kinematic().build());
+        colliders.insert(platform_shape.build(), platform, &mut bodies);
+        let cube = bodies.insert(box_body.build());
+        colliders.insert(box_shape.build(), cube, &mut bodies);
+
+        let mut step = |bodies: &mut RigidBodySet, colliders: &mut ColliderSet| {
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                bodies,
+                colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            )
+        };
+
+        // A platform with zero velocities doesn't move, so the box falls asleep.
+        bodies[platform].set_kinematic_velocity(Vector::zeros(), no_rotation);
+        assert!(!bodies[platform].is_moving());
+        for _ in 0..300 {
+            step(&mut bodies, &mut colliders);
+        }
+        assert!(bodies[cube].is_sleeping());
+        assert_eq!(
+            bodies[platform].position().translation.vector,
+            Vector::zeros()
+        );
+
+        // The velocities of the platform wake up the box and carry it along.
+        let speed: Real = 2.0;
+        bodies[platform].set_kinematic_velocity(Vector::x() * speed, no_rotation);
+        assert!(bodies[platform].is_moving());
+        assert_eq!(
+            bodies[platform].kinematic_velocity(dt).0,
+            Vector::x() * speed
+        );
+
+        for i in 0..60 {
+            step(&mut bodies, &mut colliders);
+            let x = bodies[platform].position().translation.x;
+            assert!((x - speed * (i + 1) as Real * dt).abs() < 1.0e-4);
+        }
+
+        assert!(!bodies[cube].is_sleeping());
+        assert!((bodies[cube].linvel().x - speed).abs() < 5.0e-2);
+
+        // The next position takes precedence over velocities set after it.
+        let start = *bodies[platform].position();
+        let target = Vector::x() * 0.1 + start.translation.vector;
+        let next_position = Isometry::from_parts(target.into(), start.rotation);
+        bodies[platform].set_next_kinematic_position(next_position);
+        bodies[platform].set_kinematic_velocity(-Vector::x() * speed, no_rotation);
+        step(&mut bodies, &mut colliders);
+        assert!((bodies[platform].position().translation.vector - target).norm() < 1.0e-5);
+
+        // The velocities apply again to the following timesteps.
+        for i in 0..10 {
+            step(&mut bodies, &mut colliders);
+            let x = bodies[platform].position().translation.x;
+            assert!((x - (target.x - speed * (i + 1) as Real * dt)).abs() < 1.0e-4);
+        }
+    }
+
+    #[test]
+    fn replayed_log_reproduces_the_recorded_checksums() {
+        use crate::dynamics::MassProperties;
+        use crate::math::{Isometry, Point, Real};
+        use crate::pipeline::{ReplayError, ReplayRecorder, ReplaySnapshot, Replayer};
+
+        let mut pipeline = PhysicsPipeline::new();
+        let mut gravity = Vector::y() * -9.81;
+        let mut integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        #[cfg(feature = "dim2")]
+        let (ground_shape, props) = (
+            ColliderBuilder::cuboid(20.0, 0.5),
+            MassProperties::new(Point::origin(), 1.0, 1.0),
+        );
+        #[cfg(feature = "dim3")]
+        let (ground_shape, props) = (
+            ColliderBuilder::cuboid(20.0, 0.5, 20.0),
+            MassProperties::new(Point::origin(), 1.0, Vector::repeat(1.0)),
+        );
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        colliders.insert(ground_shape.build(), ground, &mut bodies);
+
+        // A kinematic paddle sweeping through a pile of boxes and balls.
+        let paddle = bodies.insert(RigidBodyBuilder::new_kinematic().build());
+        #[cfg(feature = "dim2")]
+        let paddle_shape = ColliderBuilder::cuboid(0.2, 2.0);
+        #[cfg(feature = "dim3")]
+        let paddle_shape = ColliderBuilder::cuboid(0.2, 2.0, 4.0);
+        colliders.insert(paddle_shape.build(), paddle, &mut bodies);
+
+        let mut dynamic = Vec::new();
+        for i in 0..30 {
+            let (x, y) = ((i % 5) as Real * 1.1 - 2.2, 1.0 + (i / 5) as Real * 1.1);
+            #[cfg(feature = "dim2")]
+            let (rb, shape) = (
+                RigidBodyBuilder::new_dynamic().translation(x, y),
+                if i % 2 == 0 {
+                    ColliderBuilder::cuboid(0.5, 0.5)
+                } else {
+                    ColliderBuilder::ball(0.5)
+                },
+            );
+            #[cfg(feature = "dim3")]
+            let (rb, shape) = (
+                RigidBodyBuilder::new_dynamic().translation(x, y, (i % 3) as Real * 0.3),
+                if i % 2 == 0 {
+                    ColliderBuilder::cuboid(0.5, 0.5, 0.5)
+                } else {
+                    ColliderBuilder::ball(0.5)
+                },
+            );
+            let handle = bodies.insert(rb.build());
+            colliders.insert(shape.restitution(0.5).build(), handle, &mut bodies);
+            dynamic.push(handle);
+        }
+
+        let snapshot = ReplaySnapshot::new(
+            &broad_phase,
+            &narrow_phase,
+            &bodies,
+            &colliders,
+            &joints,
+            &ccd,
+        );
+        let mut recorder = ReplayRecorder::new();
+
+        for i in 0..300 {
+            let t = i as Real * integration_parameters.dt;
+            let paddle_x = -6.0 + 4.0 * (t * 2.0).sin();
+            #[cfg(feature = "dim2")]
+            let paddle_position = Isometry::translation(paddle_x, 1.5);
+            #[cfg(feature = "dim3")]
+            let paddle_position = Isometry::translation(paddle_x, 1.5, 0.0);
+            bodies[paddle].set_next_kinematic_position(paddle_position);
+
+            if i % 7 == 0 {
+                let impulse = Vector::x() * (i % 5) as Real - Vector::y() * 2.0;
+                bodies[dynamic[i % dynamic.len()]].apply_impulse(impulse, true);
+            }
+
+            if i == 60 {
+                gravity.x = 2.0;
+            }
+
+            if i == 100 {
+                integration_parameters.max_velocity_iterations = 6;
+            }
+
+            if i == 150 {
+                #[cfg(feature = "dim2")]
+                let projectile = RigidBodyBuilder::new_dynamic().linvel(3.0, 0.0);
+                #[cfg(feature = "dim3")]
+                let projectile = RigidBodyBuilder::new_dynamic().linvel(3.0, 0.0, 0.0);
+                let projectile = projectile.additional_mass_properties(props).build();
+                dynamic.push(bodies.insert(projectile));
+            }
+
+            if i == 200 {
+                let removed = dynamic.remove(3);
+                bodies.remove(removed, &mut colliders, &mut joints);
+            }
+
+            recorder.record_changes(&gravity, &integration_parameters, &mut bodies);
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            );
+            recorder.record_checksum(&bodies);
+        }
+
+        let log = recorder.save();
+        assert_eq!(recorder.num_steps(), 300);
+
+        // Every replayed timestep reproduces the recorded checksum.
+        let mut replayer = Replayer::new(snapshot.clone(), &log).unwrap();
+        for checksum in recorder.checksums() {
+            assert_eq!(replayer.step(), Ok(Some(*checksum)));
+        }
+        assert_eq!(replayer.step(), Ok(None));
+        assert_eq!(replayer.world().bodies.len(), bodies.len());
+
+        let last = *recorder.checksums().last().unwrap();
+        assert_eq!(Replayer::run(snapshot.clone(), &log, 300), Ok(last));
+        assert_eq!(
+            Replayer::run(snapshot.clone(), &log, 150),
+            Ok(recorder.checksums()[149])
+        );
+
+        // The replay reports the first timestep with a checksum mismatch.
+        let mut tampered = log.clone();
+        let last_byte = tampered.len() - 1;
+        tampered[last_byte] ^= 1;
+        assert!(matches!(
+            Replayer::run(snapshot.clone(), &tampered, 300),
+            Err(ReplayError::Diverged { step: 299, .. })
+        ));
+        assert_eq!(
+            Replayer::new(snapshot, &log[..3]).err(),
+            Some(ReplayError::InvalidLog)
+        );
+    }
+
+    #[test]
+    fn rigid_body_user_data_is_kept_without_modifying_the_body() {
+        let mut pipeline = PhysicsPipeline::new();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+        let mut ccd = CCDSolver::new();
+
+        let rb = RigidBodyBuilder::new_dynamic().user_data(42).build();
+        let handle = bodies.insert(rb);
+        colliders.insert(ColliderBuilder::ball(0.5).build(), handle, &mut bodies);
+        assert_eq!(bodies[handle].user_data, 42);
+
+        pipeline.step(
+            &Vector::zeros(),
+            &IntegrationParameters::default(),
+            &mut broad_phase,
+            &mut narrow_phase,
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            &mut user_constraints,
+            &mut ccd,
+            &(),
+            &(),
+        );
+
+        // Setting the user data has no physical effect, so the body isn't marked as modified.
+        let num_modified = bodies.modified_bodies.len();
+        assert!(bodies.set_user_data(handle, u128::MAX));
+        assert_eq!(bodies.modified_bodies.len(), num_modified);
+        assert_eq!(bodies[handle].user_data, u128::MAX);
+
+        #[cfg(feature = "serde")]
+        {
+            let bodies: RigidBodySet =
+                bincode::deserialize(&bincode::serialize(&bodies).unwrap()).unwrap();
+            assert_eq!(bodies[handle].user_data, u128::MAX);
+        }
+
+        let removed = bodies.remove(handle, &mut colliders, &mut joints).unwrap();
+        assert_eq!(removed.user_data, u128::MAX);
+        assert!(!bodies.set_user_data(handle, 0));
+
+        let handle = bodies.insert(removed);
+        assert_eq!(bodies[handle].user_data, u128::MAX);
+    }
+
+    #[test]
+    fn welded_tiles_become_a_single_seamless_cuboid() {
+        use crate::geometry::{ContactEvent, WeldOptions};
+        use crate::math::Real;
+        use crate::pipeline::ChannelEventCollector;
+
+        let mut pipeline = PhysicsPipeline::new();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+        let mut ccd = CCDSolver::new();
+        let gravity = Vector::y() * -9.81;
+        let params = IntegrationParameters::default();
+        let (contact_send, contact_recv) = crossbeam::channel::unbounded();
+        let (intersection_send, _intersection_recv) = crossbeam::channel::unbounded();
+        let events = ChannelEventCollector::new(intersection_send, contact_send);
+
+        // A floor of unit tiles, next to a tile with another material.
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let num_tiles = 16;
+        #[cfg(feature = "dim3")]
+        let num_tiles = 16 * 16;
+        let mut tiles = Vec::new();
+
+        for i in 0..num_tiles {
+            #[cfg(feature = "dim2")]
+            let tile = ColliderBuilder::cuboid(0.5, 0.5).translation((i % 16) as Real, -0.5);
+            #[cfg(feature = "dim3")]
+            let tile = ColliderBuilder::cuboid(0.5, 0.5, 0.5).translation(
+                (i % 16) as Real,
+                -0.5,
+                (i / 16) as Real,
+            );
+            tiles.push(colliders.insert(tile.build(), ground, &mut bodies));
+        }
+
+        #[cfg(feature = "dim2")]
+        let icy = ColliderBuilder::cuboid(0.5, 0.5).translation(16.0, -0.5);
+        #[cfg(feature = "dim3")]
+        let icy = ColliderBuilder::cuboid(0.5, 0.5, 0.5).translation(16.0, -0.5, 0.0);
+        let icy = colliders.insert(icy.friction(0.0).build(), ground, &mut bodies);
+
+        #[cfg(feature = "dim2")]
+        let ball = RigidBodyBuilder::new_dynamic().translation(1.0, 0.25);
+        #[cfg(feature = "dim3")]
+        let ball = RigidBodyBuilder::new_dynamic().translation(1.0, 0.25, 1.0);
+        let ball = bodies.insert(ball.build());
+        let ball_co = colliders.insert(ColliderBuilder::ball(0.25).build(), ball, &mut bodies);
+
+        for _ in 0..10 {
+            pipeline.step(
+                &gravity,
+                &params,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &events,
+            );
+        }
+
+        let num_colliders = colliders.len();
+        let welded = colliders.weld_static_colliders(&mut bodies, &WeldOptions::default());
+        let floor = welded[&tiles[0]];
+        assert_eq!(welded.len(), num_tiles);
+        assert!(tiles
+            .iter()
+            .all(|tile| welded[tile] == floor && !colliders.contains(*tile)));
+        assert!(!welded.contains_key(&icy) && colliders.contains(icy));
+        assert_eq!(colliders.len(), num_colliders - num_tiles + 1);
+
+        #[cfg(feature = "dim2")]
+        let expected_half_extents = Vector::new(8.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let expected_half_extents = Vector::new(8.0, 0.5, 8.0);
+        let half_extents = colliders[floor].shape().as_cuboid().unwrap().half_extents;
+        assert!((half_extents - expected_half_extents).norm() < 1.0e-5);
+
+        // The ball now touches the welded floor instead of the tiles.
+        while contact_recv.try_recv().is_ok() {}
+        pipeline.step(
+            &gravity,
+            &params,
+            &mut broad_phase,
+            &mut narrow_phase,
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            &mut user_constraints,
+            &mut ccd,
+            &(),
+            &events,
+        );
+        assert!(contact_recv.try_iter().any(|event| {
+            event == ContactEvent::Started(ball_co, floor)
+                || event == ContactEvent::Started(floor, ball_co)
+        }));
+        assert!(narrow_phase.contacts_with(tiles[0]).is_none());
+        assert!(
+            narrow_phase
+                .contact_pair(ball_co, floor)
+                .unwrap()
+                .has_any_active_contact
+        );
+
+        // Without seams, the ball rolls over the floor without bumping.
+        bodies[ball].set_linvel(Vector::x() * 4.0, true);
+        let mut max_vertical_speed: Real = 0.0;
+
+        for _ in 0..120 {
+            pipeline.step(
+                &gravity,
+                &params,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &events,
+            );
+            max_vertical_speed = max_vertical_speed.max(bodies[ball].linvel().y.abs());
+        }
+
+        assert!(max_vertical_speed < 1.0e-2);
+        assert!(bodies[ball].position().translation.x > 5.0);
+    }
+
+    #[test]
+    fn position_solve_budget_spreads_the_penetration_correction() {
+        use crate::dynamics::PositionSolveBudget;
+        use crate::math::Real;
+
+        let mut pipeline = PhysicsPipeline::new();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+        let mut ccd = CCDSolver::new();
+        // The contact manifold of each pair gives one position constraint in 2D, and two
+        // in 3D because it has four points.
+        #[cfg(feature = "dim2")]
+        let island_cost = 1;
+        #[cfg(feature = "dim3")]
+        let island_cost = 2;
+        let params = IntegrationParameters {
+            min_island_size: 1,
+            position_solve_budget: Some(PositionSolveBudget::ConstraintSolves(4 * island_cost)),
+            ..IntegrationParameters::default()
+        };
+        pipeline.counters.enable();
+
+        // Pairs of deeply overlapping boxes, each pair forming its own island.
+        let num_pairs = 20;
+        let mut handles = Vec::new();
+
+        for i in 0..num_pairs * 2 {
+            let x = (i / 2) as Real * 10.0 + (i % 2) as Real * 0.5;
+            #[cfg(feature = "dim2")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(x, 0.0);
+            #[cfg(feature = "dim3")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(x, 0.0, 0.0);
+            #[cfg(feature = "dim2")]
+            let co = ColliderBuilder::cuboid(0.5, 0.5);
+            #[cfg(feature = "dim3")]
+            let co = ColliderBuilder::cuboid(0.5, 0.5, 0.5);
+            let handle = bodies.insert(rb.can_sleep(false).build());
+            colliders.insert(co.build(), handle, &mut bodies);
+            handles.push(handle);
+        }
+
+        let mut num_deferred = Vec::new();
+
+        for _ in 0..150 {
+            pipeline.step(
+                &Vector::zeros(),
+                &params,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            );
+
+            // Four islands are solved per step.
+            let counters = pipeline.counters.solver;
+            assert!(counters.num_position_constraint_solves <= 4 * island_cost);
+            num_deferred.push(counters.num_deferred_position_islands);
+
+            let resolved = handles.chunks(2).all(|pair| {
+                let x1 = bodies[pair[0]].position().translation.x;
+                let x2 = bodies[pair[1]].position().translation.x;
+                x2 - x1 > 0.98
+            });
+
+            if resolved {
+                break;
+            }
+        }
+
+        // The deferred islands get their turn during the next steps, so all the
+        // penetrations are resolved within a bounded number of steps.
+        assert_eq!(num_deferred[0], num_pairs - 4);
+        assert!(num_deferred.len() < 150);
+    }
+
+    #[test]
+    fn sensor_occupancy_follows_the_bodies_inside_the_sensor() {
+        use crate::geometry::IntersectionEvent;
+        use crate::math::Isometry;
+        use crate::pipeline::ChannelEventCollector;
+        use std::collections::HashSet;
+
+        let mut pipeline = PhysicsPipeline::new();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+        let mut ccd = CCDSolver::new();
+        let (intersection_send, intersection_recv) = crossbeam::channel::unbounded();
+        let (contact_send, _contact_recv) = crossbeam::channel::unbounded();
+        let events = ChannelEventCollector::new(intersection_send, contact_send);
+
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        let sensor = ColliderBuilder::ball(2.0).sensor(true).build();
+        let sensor = colliders.insert(sensor, ground, &mut bodies);
+
+        // A body with two colliders created already inside the sensor, and one outside of it.
+        let inside = bodies.insert(RigidBodyBuilder::new_dynamic().build());
+        colliders.insert(ColliderBuilder::ball(0.5).build(), inside, &mut bodies);
+        #[cfg(feature = "dim2")]
+        let cuboid = ColliderBuilder::cuboid(0.5, 0.5);
+        #[cfg(feature = "dim3")]
+        let cuboid = ColliderBuilder::cuboid(0.5, 0.5, 0.5);
+        colliders.insert(cuboid.build(), inside, &mut bodies);
+        let far = Isometry::new(Vector::x() * 20.0, na::zero());
+        let outside = RigidBodyBuilder::new_dynamic().position(far).build();
+        let outside = bodies.insert(outside);
+        let outside_co = colliders.insert(ColliderBuilder::ball(0.5).build(), outside, &mut bodies);
+
+        // The colliders inside the sensor, according to the intersection events.
+        let mut from_events = HashSet::new();
+        let update_from_events = |from_events: &mut HashSet<_>| {
+            for event in intersection_recv.try_iter() {
+                let IntersectionEvent {
+                    collider1,
+                    collider2,
+                    intersecting,
+                } = event;
+                let other = if collider1 == sensor {
+                    collider2
+                } else {
+                    collider1
+                };
+
+                if intersecting {
+                    from_events.insert(other);
+                } else {
+                    from_events.remove(&other);
+                }
+            }
+        };
+
+        pipeline.step(
+            &Vector::zeros(),
+            &IntegrationParameters::default(),
+            &mut broad_phase,
+            &mut narrow_phase,
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            &mut user_constraints,
+            &mut ccd,
+            &(),
+            &events,
+        );
+        update_from_events(&mut from_events);
+        let inside_bodies: Vec<_> = narrow_phase
+            .bodies_inside_sensor(sensor, &colliders)
+            .collect();
+        assert_eq!(inside_bodies, vec![inside]);
+        assert_eq!(narrow_phase.num_colliders_inside(sensor, &colliders), 2);
+        assert_eq!(from_events.len(), 2);
+
+        // Teleport the first body out of the sensor, and the second one into it.
+        bodies[inside].set_position(far.inverse(), true);
+        bodies[outside].set_position(Isometry::identity(), true);
+        pipeline.step(
+            &Vector::zeros(),
+            &IntegrationParameters::default(),
+            &mut broad_phase,
+            &mut narrow_phase,
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            &mut user_constraints,
+            &mut ccd,
+            &(),
+            &events,
+        );
+        update_from_events(&mut from_events);
+        let inside_bodies: Vec<_> = narrow_phase
+            .bodies_inside_sensor(sensor, &colliders)
+            .collect();
+        assert_eq!(inside_bodies, vec![outside]);
+        assert_eq!(narrow_phase.num_colliders_inside(sensor, &colliders), 1);
+        assert_eq!(from_events, [outside_co].iter().copied().collect());
+
+        // Removing the parent of a collider inside the sensor doesn't generate any event,
+        // but the occupancy is updated right away.
+        bodies.remove(outside, &mut colliders, &mut joints);
+        assert_eq!(
+            narrow_phase
+                .bodies_inside_sensor(sensor, &colliders)
+                .count(),
+            0
+        );
+        assert_eq!(narrow_phase.num_colliders_inside(sensor, &colliders), 0);
+        pipeline.step(
+            &Vector::zeros(),
+            &IntegrationParameters::default(),
+            &mut broad_phase,
+            &mut narrow_phase,
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            &mut user_constraints,
+            &mut ccd,
+            &(),
+            &events,
+        );
+        update_from_events(&mut from_events);
+        assert_eq!(narrow_phase.num_colliders_inside(sensor, &colliders), 0);
+        assert!(from_events.contains(&outside_co));
+    }
+
+    #[test]
+    fn additional_mass_properties_are_added_to_the_colliders_contributions() {
+        use crate::dynamics::MassProperties;
+        use crate::math::{Point, Real};
+
+        let mut pipeline = PhysicsPipeline::new();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        // A chassis with a lumped engine mass in front of it.
+        #[cfg(feature = "dim2")]
+        let (chassis_co, engine_pos, engine) = {
+            let engine_pos = Point::new(1.0, 0.0);
+            let engine = MassProperties::new(engine_pos, 3.0, 0.1);
+            (ColliderBuilder::cuboid(1.0, 0.5), engine_pos, engine)
+        };
+        #[cfg(feature = "dim3")]
+        let (chassis_co, engine_pos, engine) = {
+            let engine_pos = Point::new(1.0, 0.0, 0.0);
+            let engine = MassProperties::new(engine_pos, 3.0, Vector::repeat(0.1));
+            (ColliderBuilder::cuboid(1.0, 0.5, 0.5), engine_pos, engine)
+        };
+        let chassis_co = chassis_co.density(1.0).build();
+        let chassis_mass = 1.0 / chassis_co.mass_properties().inv_mass;
+
+        let chassis = bodies.insert(RigidBodyBuilder::new_dynamic().build());
+        let chassis_co = colliders.insert(chassis_co, chassis, &mut bodies);
+        bodies
+            .get_mut(chassis)
+            .unwrap()
+            .set_additional_mass_properties(engine, true);
+
+        let expected_com = |engine_mass: Real| {
+            Point::from(engine_pos.coords * engine_mass / (chassis_mass + engine_mass))
+        };
+        let rb = &bodies[chassis];
+        assert!((rb.mass() - (chassis_mass + 3.0)).abs() < 1.0e-4);
+        assert!((rb.world_com - expected_com(3.0)).norm() < 1.0e-4);
+        assert!((rb.effective_inv_mass.x - 1.0 / (chassis_mass + 3.0)).abs() < 1.0e-4);
+
+        // Replacing the additional mass properties at runtime doesn't discard the
+        // contribution of the collider.
+        #[cfg(feature = "dim2")]
+        let lighter_engine = MassProperties::new(engine_pos, 1.0, 0.1);
+        #[cfg(feature = "dim3")]
+        let lighter_engine = MassProperties::new(engine_pos, 1.0, Vector::repeat(0.1));
+        bodies.begin_mass_update(chassis);
+        bodies
+            .get_mut(chassis)
+            .unwrap()
+            .set_additional_mass_properties(lighter_engine, true);
+        bodies.end_mass_update(chassis);
+        pipeline.step(
+            &Vector::zeros(),
+            &IntegrationParameters::default(),
+            &mut broad_phase,
+            &mut narrow_phase,
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            &mut user_constraints,
+            &mut ccd,
+            &(),
+            &(),
+        );
+        let rb = &bodies[chassis];
+        assert!((rb.mass() - (chassis_mass + 1.0)).abs() < 1.0e-4);
+        assert!((rb.world_com - expected_com(1.0)).norm() < 1.0e-4);
+        assert!((rb.effective_inv_mass.x - 1.0 / (chassis_mass + 1.0)).abs() < 1.0e-4);
+
+        // The additional mass properties remain once the collider is removed.
+        colliders.remove(chassis_co, &mut bodies, true, RemoveSettleMode::Immediate);
+        let rb = &bodies[chassis];
+        assert!((rb.mass() - 1.0).abs() < 1.0e-4);
+        assert!((rb.world_com - engine_pos).norm() < 1.0e-4);
+        assert_eq!(
+            rb.additional_mass_properties().inv_mass,
+            lighter_engine.inv_mass
+        );
+    }
+
+    #[test]
+    fn mass_ratio_clamping_stabilizes_heavy_bodies_on_light_ones() {
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters {
+            max_velocity_iterations: 4,
+            max_effective_mass_ratio: Some(100.0),
+            ..IntegrationParameters::default()
+        };
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_collider = ColliderBuilder::cuboid(10.0, 0.5).build();
+        #[cfg(feature = "dim3")]
+        let ground_collider = ColliderBuilder::cuboid(10.0, 0.5, 10.0).build();
+        colliders.insert(ground_collider, ground, &mut bodies);
+
+        // A 1 kg table supporting a 1000 kg crate.
+        #[cfg(feature = "dim2")]
+        let (table, table_collider) = (
+            RigidBodyBuilder::new_dynamic().translation(0.0, 0.6),
+            ColliderBuilder::cuboid(0.5, 0.1),
+        );
+        #[cfg(feature = "dim3")]
+        let (table, table_collider) = (
+            RigidBodyBuilder::new_dynamic().translation(0.0, 0.6, 0.0),
+            ColliderBuilder::cuboid(0.5, 0.1, 0.5),
+        );
+        let table = bodies.insert(table.can_sleep(false).build());
+        let table_collider =
+            colliders.insert(table_collider.density(5.0).build(), table, &mut bodies);
+
+        #[cfg(feature = "dim2")]
+        let (crate_body, crate_collider) = (
+            RigidBodyBuilder::new_dynamic().translation(0.0, 1.2),
+            ColliderBuilder::cuboid(0.5, 0.5),
+        );
+        #[cfg(feature = "dim3")]
+        let (crate_body, crate_collider) = (
+            RigidBodyBuilder::new_dynamic().translation(0.0, 1.2, 0.0),
+            ColliderBuilder::cuboid(0.5, 0.5, 0.5),
+        );
+        let crate_body = bodies.insert(crate_body.can_sleep(false).build());
+        let crate_collider = crate_collider.density(1000.0).build();
+        let crate_collider = colliders.insert(crate_collider, crate_body, &mut bodies);
+        assert!((bodies[table].mass() - 1.0).abs() < 1.0e-3);
+        assert!((bodies[crate_body].mass() - 1000.0).abs() < 1.0e-1);
+
+        for _ in 0..300 {
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            );
+        }
+
+        // The stack rests on the ground instead of exploding, and the crate doesn't crush the
+        // table into the ground like it does without the clamping.
+        let crate_y = bodies[crate_body].position().translation.vector.y;
+        let table_y = bodies[table].position().translation.vector.y;
+        assert!(crate_y > 1.05 && crate_y < 1.25, "crate at {}", crate_y);
+        assert!(table_y > 0.55 && table_y < 0.65, "table at {}", table_y);
+        assert!(bodies[crate_body].linvel().norm() < 0.5);
+        assert!(bodies[table].linvel().norm() < 0.5);
+
+        // Only the table is made heavier, and only for its contacts with the crate.
+        let data = &narrow_phase
+            .contact_pair(table_collider, crate_collider)
+            .unwrap()
+            .manifolds[0]
+            .data;
+        let [mut table_multiplier, mut crate_multiplier] = data.effective_mass_multipliers;
+        if data.body_pair.body1 != table {
+            std::mem::swap(&mut table_multiplier, &mut crate_multiplier);
+        }
+        assert!(table_multiplier > 1.0);
+        assert_eq!(crate_multiplier, 1.0);
+    }
+
+    #[test]
+    fn mass_ratio_clamping_leaves_equal_masses_untouched() {
+        use crate::math::{Isometry, Real};
+
+        // A Newton's cradle: the first ball hits a row of touching balls.
+        fn simulate_cradle(integration_parameters: &IntegrationParameters) -> Vec<Isometry<Real>> {
+            let mut pipeline = PhysicsPipeline::new();
+            let mut broad_phase = BroadPhase::new();
+            let mut narrow_phase = NarrowPhase::new();
+            let mut bodies = RigidBodySet::new();
+            let mut colliders = ColliderSet::new();
+            let mut ccd = CCDSolver::new();
+            let mut joints = JointSet::new();
+            let mut user_constraints = UserConstraintSet::new();
+
+            let mut handles = Vec::new();
+            for i in 0..5 {
+                let x = if i == 0 { -0.5 } else { i as Real };
+                #[cfg(feature = "dim2")]
+                let rb = RigidBodyBuilder::new_dynamic().translation(x, 0.0);
+                #[cfg(feature = "dim3")]
+                let rb = RigidBodyBuilder::new_dynamic().translation(x, 0.0, 0.0);
+                let handle = bodies.insert(rb.can_sleep(false).build());
+                let collider = ColliderBuilder::ball(0.5).restitution(1.0).build();
+                colliders.insert(collider, handle, &mut bodies);
+                handles.push(handle);
+            }
+
+            bodies[handles[0]].set_linvel(Vector::x() * 5.0, true);
+            let mass = bodies[handles[0]].mass();
+
+            for _ in 0..60 {
+                pipeline.step(
+                    &Vector::zeros(),
+                    integration_parameters,
+                    &mut broad_phase,
+                    &mut narrow_phase,
+                    &mut bodies,
+                    &mut colliders,
+                    &mut joints,
+                    &mut user_constraints,
+                    &mut ccd,
+                    &(),
+                    &(),
+                );
+
+                // The contacts between bodies of equal masses conserve the momentum.
+                let momentum: Vector<Real> =
+                    handles.iter().map(|h| *bodies[*h].linvel() * mass).sum();
+                assert!((momentum - Vector::x() * 5.0 * mass).norm() < 1.0e-3 * mass);
+            }
+
+            for pair in narrow_phase.contact_pairs() {
+                for manifold in &pair.manifolds {
+                    assert_eq!(manifold.data.effective_mass_multipliers, [1.0; 2]);
+                }
+            }
+
+            handles.iter().map(|h| *bodies[*h].position()).collect()
+        }
+
+        let unclamped = simulate_cradle(&IntegrationParameters::default());
+        let clamped = simulate_cradle(&IntegrationParameters {
+            max_effective_mass_ratio: Some(100.0),
+            ..IntegrationParameters::default()
+        });
+        assert_eq!(unclamped, clamped);
+    }
+
+    #[test]
+    fn normal_smoothing_limits_the_rotation_of_contact_normals() {
+        use crate::math::Real;
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+        let max_rotation = 0.01;
+        let speed = 0.3;
+
+        // A box with its top face at `y = 0.5` and an edge at `x = 1.0`.
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let ground_collider = ColliderBuilder::cuboid(1.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_collider = ColliderBuilder::cuboid(1.0, 0.5, 1.0);
+        let ground_collider =
+            colliders.insert(ground_collider.friction(0.0).build(), ground, &mut bodies);
+
+        // A capsule standing on the box, moved slowly over its edge so it rolls around it.
+        #[cfg(feature = "dim2")]
+        let capsule = RigidBodyBuilder::new_dynamic().translation(0.8, 1.3);
+        #[cfg(feature = "dim3")]
+        let capsule = RigidBodyBuilder::new_dynamic().translation(0.8, 1.3, 0.0);
+        let capsule = bodies.insert(capsule.lock_rotations().can_sleep(false).build());
+        let capsule_collider = ColliderBuilder::capsule_y(0.5, 0.3)
+            .friction(0.0)
+            .normal_smoothing(max_rotation)
+            .build();
+        let capsule_collider = colliders.insert(capsule_collider, capsule, &mut bodies);
+
+        let mut prev_normals: Option<(Vector<Real>, Vector<Real>)> = None;
+        let mut max_raw_rotation: Real = 0.0;
+        let mut num_smoothed_steps = 0;
+
+        for _ in 0..120 {
+            let mut linvel = *bodies[capsule].linvel();
+            linvel.x = speed;
+            bodies[capsule].set_linvel(linvel, true);
+
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            );
+
+            let normals = narrow_phase
+                .contact_pair(ground_collider, capsule_collider)
+                .and_then(|pair| {
+                    pair.manifolds
+                        .iter()
+                        .find(|m| !m.data.solver_contacts.is_empty())
+                })
+                .map(|m| (m.data.normal, m.data.raw_normal));
+
+            if let (Some((prev_normal, prev_raw_normal)), Some((normal, raw_normal))) =
+                (prev_normals, normals)
+            {
+                let rotation = prev_normal.angle(&normal);
+                assert!(rotation <= max_rotation + 1.0e-4, "rotation: {}", rotation);
+
+                max_raw_rotation = max_raw_rotation.max(prev_raw_normal.angle(&raw_normal));
+                if normal != raw_normal {
+                    num_smoothed_steps += 1;
+                }
+            }
+
+            prev_normals = normals;
+        }
+
+        // The raw normal did rotate faster than the limit while rolling over the edge.
+        assert!(max_raw_rotation > max_rotation);
+        assert!(num_smoothed_steps > 0);
+    }
+
+    #[test]
+    fn detached_colliders_leave_the_simulation_without_leaking() {
+        use crate::geometry::{ContactEvent, IntersectionEvent};
+        use crate::pipeline::ChannelEventCollector;
+
+        let mut pipeline = PhysicsPipeline::new();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+        let mut ccd = CCDSolver::new();
+        let gravity = Vector::y() * -9.81;
+        let params = IntegrationParameters::default();
+        let (contact_send, contact_recv) = crossbeam::channel::unbounded();
+        let (intersection_send, intersection_recv) = crossbeam::channel::unbounded();
+        let events = ChannelEventCollector::new(intersection_send, contact_send);
+
+        // A ball resting on the floor, inside a sensor.
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let floor = ColliderBuilder::cuboid(10.0, 0.5).translation(0.0, -0.5);
+        #[cfg(feature = "dim3")]
+        let floor = ColliderBuilder::cuboid(10.0, 0.5, 10.0).translation(0.0, -0.5, 0.0);
+        let floor = colliders.insert(floor.build(), ground, &mut bodies);
+        let sensor = ColliderBuilder::ball(1.0).sensor(true).build();
+        let sensor = colliders.insert(sensor, ground, &mut bodies);
+
+        #[cfg(feature = "dim2")]
+        let ball = RigidBodyBuilder::new_dynamic().translation(0.0, 0.25);
+        #[cfg(feature = "dim3")]
+        let ball = RigidBodyBuilder::new_dynamic().translation(0.0, 0.25, 0.0);
+        let ball = bodies.insert(ball.build());
+        let ball_co = colliders.insert(ColliderBuilder::ball(0.25).build(), ball, &mut bodies);
+
+        for _ in 0..10 {
+            pipeline.step(
+                &gravity,
+                &params,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &events,
+            );
+        }
+
+        let mass = bodies[ball].mass();
+        let num_proxies = broad_phase.stats(false).num_proxies;
+        let stats = narrow_phase.stats(false);
+        assert_eq!(stats.num_contact_pairs, 1);
+        assert_eq!(stats.num_intersection_pairs, 1);
+        while contact_recv.try_recv().is_ok() {}
+        while intersection_recv.try_recv().is_ok() {}
+
+        assert!(colliders.detach(ball_co, &mut bodies));
+        assert!(!colliders.detach(ball_co, &mut bodies));
+        assert!(colliders[ball_co].is_detached());
+        assert!(bodies[ball].colliders().is_empty());
+        assert_eq!(bodies[ball].mass(), 0.0);
+
+        pipeline.step(
+            &gravity,
+            &params,
+            &mut broad_phase,
+            &mut narrow_phase,
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            &mut user_constraints,
+            &mut ccd,
+            &(),
+            &events,
+        );
+
+        // The pairs of the detached collider are reported as lost, and removed.
+        let contact_event = contact_recv.try_recv().unwrap();
+        assert!(
+            contact_event == ContactEvent::Stopped(floor, ball_co)
+                || contact_event == ContactEvent::Stopped(ball_co, floor)
+        );
+        let intersection_event = intersection_recv.try_recv().unwrap();
+        assert!(!intersection_event.intersecting);
+        assert!(
+            intersection_event == IntersectionEvent::new(sensor, ball_co, false)
+                || intersection_event == IntersectionEvent::new(ball_co, sensor, false)
+        );
+        assert_eq!(narrow_phase.num_colliders_inside(sensor, &colliders), 0);
+        // The proxies of the regions only containing the ball are removed with its own proxy.
+        let detached_proxies = broad_phase.stats(false).num_proxies;
+        assert!(detached_proxies < num_proxies);
+        let stats = narrow_phase.stats(false);
+        assert_eq!(stats.num_contact_pairs, 0);
+        assert_eq!(stats.num_intersection_pairs, 0);
+
+        // Half of the cycles re-attach and detach the collider before the next timestep.
+        for i in 0..1000 {
+            assert!(colliders.attach(ball_co, ball, &mut bodies));
+
+            if i % 2 == 0 {
+                pipeline.step(
+                    &gravity,
+                    &params,
+                    &mut broad_phase,
+                    &mut narrow_phase,
+                    &mut bodies,
+                    &mut colliders,
+                    &mut joints,
+                    &mut user_constraints,
+                    &mut ccd,
+                    &(),
+                    &events,
+                );
+            }
+
+            assert!(colliders.detach(ball_co, &mut bodies));
+            pipeline.step(
+                &gravity,
+                &params,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &events,
+            );
+        }
+
+        assert_eq!(broad_phase.stats(false).num_proxies, detached_proxies);
+        let stats = narrow_phase.stats(false);
+        assert_eq!(stats.num_contact_pairs, 0);
+        assert_eq!(stats.num_intersection_pairs, 0);
+
+        assert!(colliders.attach(ball_co, ball, &mut bodies));
+        assert!(!colliders.attach(ball_co, ball, &mut bodies));
+        pipeline.step(
+            &gravity,
+            &params,
+            &mut broad_phase,
+            &mut narrow_phase,
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            &mut user_constraints,
+            &mut ccd,
+            &(),
+            &events,
+        );
+
+        assert!(!colliders[ball_co].is_detached());
+        assert_eq!(bodies[ball].colliders(), &[ball_co]);
+        assert_eq!(bodies[ball].mass(), mass);
+        assert_eq!(broad_phase.stats(false).num_proxies, num_proxies);
+        let stats = narrow_phase.stats(false);
+        assert_eq!(stats.num_contact_pairs, 1);
+        assert_eq!(stats.num_intersection_pairs, 1);
+        assert_eq!(narrow_phase.num_colliders_inside(sensor, &colliders), 1);
+    }
+
+    #[test]
+    fn contact_pair_age_is_only_reset_beyond_the_prediction_distance() {
+        let mut pipeline = PhysicsPipeline::new();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+        let mut ccd = CCDSolver::new();
+        let gravity = Vector::y() * -9.81;
+        let params = IntegrationParameters::default();
+
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        #[cfg(feature = "dim2")]
+        let floor = ColliderBuilder::cuboid(10.0, 0.5).translation(0.0, -0.5);
+        #[cfg(feature = "dim3")]
+        let floor = ColliderBuilder::cuboid(10.0, 0.5, 10.0).translation(0.0, -0.5, 0.0);
+        let floor = colliders.insert(floor.build(), ground, &mut bodies);
+
+        #[cfg(feature = "dim2")]
+        let (body, co) = (
+            RigidBodyBuilder::new_dynamic().translation(0.0, 0.5),
+            ColliderBuilder::cuboid(0.5, 0.5),
+        );
+        #[cfg(feature = "dim3")]
+        let (body, co) = (
+            RigidBodyBuilder::new_dynamic().translation(0.0, 0.5, 0.0),
+            ColliderBuilder::cuboid(0.5, 0.5, 0.5),
+        );
+        let body = bodies.insert(body.build());
+        let co = colliders.insert(co.build(), body, &mut bodies);
+
+        for _ in 0..120 {
+            pipeline.step(
+                &gravity,
+                &params,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            );
+        }
+
+        let pair = narrow_phase.contact_pair(floor, co).unwrap();
+        assert_eq!(pair.age_steps(), 120);
+        assert!((pair.active_duration() - 120.0 * params.dt).abs() < 1.0e-3);
+
+        // Lifting the box within the prediction distance doesn't reset the age.
+        for _ in 0..3 {
+            let mut position = *bodies[body].position();
+            position.translation.vector.y = 0.5 + params.prediction_distance / 2.0;
+            bodies[body].set_position(position, true);
+            pipeline.step(
+                &gravity,
+                &params,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            );
+        }
+
+        let pair = narrow_phase.contact_pair(floor, co).unwrap();
+        assert_eq!(pair.age_steps(), 123);
+        assert!((pair.active_duration() - 123.0 * params.dt).abs() < 1.0e-3);
+
+        // Separating it fully does.
+        let mut position = *bodies[body].position();
+        position.translation.vector.y = 2.0;
+        bodies[body].set_position(position, true);
+        pipeline.step(
+            &gravity,
+            &params,
+            &mut broad_phase,
+            &mut narrow_phase,
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            &mut user_constraints,
+            &mut ccd,
+            &(),
+            &(),
+        );
+
+        let age = narrow_phase
+            .contact_pair(floor, co)
+            .map(|pair| pair.age_steps())
+            .unwrap_or(0);
+        assert_eq!(age, 0);
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn positions_set_through_par_iter_mut_are_applied_on_the_next_step() {
+        use crate::math::Real;
+        use rayon::prelude::*;
+
+        let mut pipeline = PhysicsPipeline::new();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+        let mut ccd = CCDSolver::new();
+        let gravity = Vector::zeros();
+        let params = IntegrationParameters::default();
+
+        for i in 0..100 {
+            #[cfg(feature = "dim2")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(i as Real * 2.0, 0.0);
+            #[cfg(feature = "dim3")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(i as Real * 2.0, 0.0, 0.0);
+            let body = bodies.insert(rb.build());
+            colliders.insert(ColliderBuilder::ball(0.5).build(), body, &mut bodies);
+        }
+
+        pipeline.step(
+            &gravity,
+            &params,
+            &mut broad_phase,
+            &mut narrow_phase,
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            &mut user_constraints,
+            &mut ccd,
+            &(),
+            &(),
+        );
+
+        bodies.par_iter_mut().for_each(|(_, rb)| {
+            let mut pos = *rb.position();
+            pos.translation.vector.y = 10.0;
+            rb.set_position(pos, true);
+        });
+        assert!(bodies.modified_all_bodies);
+
+        colliders
+            .par_iter_mut()
+            .for_each(|(_, co)| co.friction = 0.25);
+        assert!(colliders.modified_all_colliders);
+
+        pipeline.step(
+            &gravity,
+            &params,
+            &mut broad_phase,
+            &mut narrow_phase,
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            &mut user_constraints,
+            &mut ccd,
+            &(),
+            &(),
+        );
+
+        assert!(!bodies.modified_all_bodies);
+        assert!(!colliders.modified_all_colliders);
+
+        let num_moved = colliders
+            .par_iter()
+            .filter(|(_, co)| {
+                co.position().translation.vector.y == 10.0
+                    && co.position() == bodies[co.parent()].position()
+                    && co.friction == 0.25
+            })
+            .count();
+        assert_eq!(num_moved, 100);
+        assert_eq!(bodies.par_iter().count(), 100);
+    }
+
+    #[test]
+    fn boxes_far_from_the_origin_rest_on_a_halfspace() {
+        use crate::geometry::{InteractionGroups, Ray};
+        use crate::math::{Point, Real};
+        use crate::pipeline::QueryPipeline;
+
+        let mut pipeline = PhysicsPipeline::new();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+        let mut ccd = CCDSolver::new();
+        let gravity = Vector::y() * -9.81;
+        let params = IntegrationParameters::default();
+
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        let halfspace = ColliderBuilder::halfspace(Vector::y_axis()).build();
+        let halfspace = colliders.insert(halfspace, ground, &mut bodies);
+        assert_eq!(colliders[halfspace].mass_properties().inv_mass, 0.0);
+
+        let mut boxes = Vec::new();
+        for i in 0..5 {
+            let x = 1.0e6 + i as Real * 2.0;
+            #[cfg(feature = "dim2")]
+            let (rb, co) = (
+                RigidBodyBuilder::new_dynamic().translation(x, 0.51),
+                ColliderBuilder::cuboid(0.5, 0.5),
+            );
+            #[cfg(feature = "dim3")]
+            let (rb, co) = (
+                RigidBodyBuilder::new_dynamic().translation(x, 0.51, 0.0),
+                ColliderBuilder::cuboid(0.5, 0.5, 0.5),
+            );
+            let handle = bodies.insert(rb.build());
+            colliders.insert(co.build(), handle, &mut bodies);
+            boxes.push((handle, x));
+        }
+
+        for _ in 0..500 {
+            pipeline.step(
+                &gravity,
+                &params,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            );
+        }
+
+        // The boxes didn't slide nor jitter on the plane, and fell asleep.
+        for (handle, x) in &boxes {
+            let rb = &bodies[*handle];
+            let translation = rb.position().translation.vector;
+            assert_eq!(translation.x, *x);
+            assert!((translation.y - 0.5).abs() < 0.05);
+            assert!(rb.position().rotation.angle().abs() < 1.0e-3);
+            assert!(rb.is_sleeping());
+        }
+
+        assert_eq!(narrow_phase.contacts_with(halfspace).unwrap().count(), 5);
+
+        // The half-space can be hit by ray-casts anywhere on the plane.
+        let mut query_pipeline = QueryPipeline::new();
+        query_pipeline.update(&bodies, &colliders);
+
+        for x in &[-1.0e6, 0.0, 5.0e6] {
+            #[cfg(feature = "dim2")]
+            let ray = Ray::new(Point::new(*x, 10.0), -Vector::y());
+            #[cfg(feature = "dim3")]
+            let ray = Ray::new(Point::new(*x, 10.0, 0.0), -Vector::y());
+            let hit = query_pipeline.cast_ray(
+                &colliders,
+                &ray,
+                Real::MAX,
+                true,
+                InteractionGroups::all(),
+                None,
+            );
+            let (hit_collider, toi) = hit.unwrap();
+            assert_eq!(hit_collider, halfspace);
+            assert!((toi - 10.0).abs() < 1.0e-3);
+        }
+    }
+
+    #[test]
+    fn islands_can_be_iterated_after_a_step() {
+        use crate::math::Real;
+
+        let mut pipeline = PhysicsPipeline::new();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+        let mut ccd = CCDSolver::new();
+        let gravity = Vector::y() * -9.81;
+        let params = IntegrationParameters {
+            min_island_size: 1,
+            ..IntegrationParameters::default()
+        };
+
+        assert_eq!(bodies.num_islands(), 0);
+        assert_eq!(bodies.iter_island(0).count(), 0);
+
+        let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+        let halfspace = ColliderBuilder::halfspace(Vector::y_axis()).build();
+        colliders.insert(halfspace, ground, &mut bodies);
+
+        // Three columns of two boxes, only interacting through the static ground.
+        let mut columns = Vec::new();
+        for i in 0..3 {
+            let mut column = Vec::new();
+            for j in 0..2 {
+                let (x, y) = (i as Real * 5.0, 0.5 + j as Real);
+                #[cfg(feature = "dim2")]
+                let (rb, co) = (
+                    RigidBodyBuilder::new_dynamic().translation(x, y),
+                    ColliderBuilder::cuboid(0.5, 0.5),
+                );
+                #[cfg(feature = "dim3")]
+                let (rb, co) = (
+                    RigidBodyBuilder::new_dynamic().translation(x, y, 0.0),
+                    ColliderBuilder::cuboid(0.5, 0.5, 0.5),
+                );
+                let handle = bodies.insert(rb.build());
+                colliders.insert(co.build(), handle, &mut bodies);
+                column.push(handle);
+            }
+            columns.push(column);
+        }
+
+        let mut step = |bodies: &mut RigidBodySet, colliders: &mut ColliderSet| {
+            pipeline.step(
+                &gravity,
+                &params,
+                &mut broad_phase,
+                &mut narrow_phase,
+                bodies,
+                colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            )
+        };
+
+        step(&mut bodies, &mut colliders);
+        assert_eq!(bodies[ground].island_id(), None);
+        assert_eq!(bodies.num_islands(), 3);
+
+        for column in &columns {
+            let island_id = bodies[column[0]].island_id().unwrap();
+            assert_eq!(bodies[column[1]].island_id(), Some(island_id));
+
+            let mut island: Vec<_> = bodies.iter_island(island_id).map(|(h, _)| h).collect();
+            island.sort_by_key(|h| h.into_raw_parts());
+            assert_eq!(island, *column);
+        }
+
+        // The sleeping bodies are not part of any island.
+        for handle in &columns[1] {
+            bodies.get_mut(*handle).unwrap().sleep();
+        }
+
+        step(&mut bodies, &mut colliders);
+        assert_eq!(bodies.num_islands(), 2);
+        assert_eq!(bodies[columns[1][0]].island_id(), None);
+        assert_eq!(bodies[columns[1][1]].island_id(), None);
+        assert_eq!(bodies.iter_island(2).count(), 0);
+
+        let num_bodies_in_islands: usize = (0..bodies.num_islands())
+            .map(|island_id| bodies.iter_island(island_id).count())
+            .sum();
+        assert_eq!(num_bodies_in_islands, 4);
+    }
+
+    #[test]
+    fn shrink_to_fit_releases_the_memory_of_the_removed_contacts() {
+        let mut world = TestWorld::new();
+
+        #[cfg(feature = "dim2")]
+        let ground_shape = ColliderBuilder::cuboid(1000.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_shape = ColliderBuilder::cuboid(1000.0, 0.5, 1.0);
+        let ground = world.bodies.insert(RigidBodyBuilder::new_static().build());
+        world
+            .colliders
+            .insert(ground_shape.build(), ground, &mut world.bodies);
+
+        // A row of 1000 balls touching each other and the ground.
+        let mut balls = Vec::new();
+        for i in 0..1000 {
+            #[cfg(feature = "dim2")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(i as Real - 500.0, 1.0);
+            #[cfg(feature = "dim3")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(i as Real - 500.0, 1.0, 0.0);
+            let handle = world.bodies.insert(rb.build());
+            world.colliders.insert(
+                ColliderBuilder::ball(0.5).build(),
+                handle,
+                &mut world.bodies,
+            );
+            balls.push(handle);
+        }
+
+        world.step();
+        let narrow_phase_bytes = world.narrow_phase.stats(true).bytes;
+
+        // Remove 90% of the balls.
+        for handle in &balls[100..] {
+            world
+                .bodies
+                .remove(*handle, &mut world.colliders, &mut world.joints);
+        }
+
+        world.step();
+        world.bodies.shrink_to_fit();
+        world.colliders.shrink_to_fit();
+        world.joints.shrink_to_fit();
+        world.narrow_phase.shrink_to_fit();
+        world.pipeline.shrink_to_fit();
+
+        assert!(world.narrow_phase.stats(true).bytes < narrow_phase_bytes / 5);
+
+        let stats = world.pipeline.stats(
+            true,
+            &mut world.broad_phase,
+            &mut world.narrow_phase,
+            &world.bodies,
+            &world.colliders,
+        );
+        assert_eq!(stats.solver.bytes, 0);
+
+        // The simulation can continue.
+        world.bodies.insert(RigidBodyBuilder::new_dynamic().build());
+        world.step();
+        assert_eq!(world.bodies.len(), 102);
+    }
+
+    #[test]
+    fn impulses_below_the_reporting_threshold_do_not_change_the_simulation() {
+        use crate::math::{Isometry, Real};
+
+        // Returns the positions of the stack, and the physical and reported contact impulses.
+        fn settle_stack(min_reported_impulse: Real) -> (Vec<Isometry<Real>>, Vec<(Real, Real)>) {
+            let mut pipeline = PhysicsPipeline::new();
+            let gravity = Vector::y() * -9.81;
+            let integration_parameters = IntegrationParameters::default();
+            let mut broad_phase = BroadPhase::new();
+            let mut narrow_phase = NarrowPhase::new();
+            let mut bodies = RigidBodySet::new();
+            let mut colliders = ColliderSet::new();
+            let mut ccd = CCDSolver::new();
+            let mut joints = JointSet::new();
+            let mut user_constraints = UserConstraintSet::new();
+
+            let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+            #[cfg(feature = "dim2")]
+            let ground_collider = ColliderBuilder::cuboid(10.0, 0.5);
+            #[cfg(feature = "dim3")]
+            let ground_collider = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+            let ground_collider = ground_collider.min_reported_impulse(min_reported_impulse);
+            colliders.insert(ground_collider.build(), ground, &mut bodies);
+
+            let mut handles = Vec::new();
+            for i in 0..4 {
+                let y = 1.0 + i as Real;
+                #[cfg(feature = "dim2")]
+                let rb = RigidBodyBuilder::new_dynamic().translation(0.0, y);
+                #[cfg(feature = "dim3")]
+                let rb = RigidBodyBuilder::new_dynamic().translation(0.0, y, 0.0);
+                let handle = bodies.insert(rb.can_sleep(false).build());
+                #[cfg(feature = "dim2")]
+                let collider = ColliderBuilder::cuboid(0.5, 0.5);
+                #[cfg(feature = "dim3")]
+                let collider = ColliderBuilder::cuboid(0.5, 0.5, 0.5);
+                let collider = collider.min_reported_impulse(min_reported_impulse);
+                colliders.insert(collider.build(), handle, &mut bodies);
+                handles.push(handle);
+            }
+
+            for _ in 0..200 {
+                pipeline.step(
+                    &gravity,
+                    &integration_parameters,
+                    &mut broad_phase,
+                    &mut narrow_phase,
+                    &mut bodies,
+                    &mut colliders,
+                    &mut joints,
+                    &mut user_constraints,
+                    &mut ccd,
+                    &(),
+                    &(),
+                );
+            }
+
+            let positions = handles.iter().map(|h| *bodies[*h].position()).collect();
+            let impulses = narrow_phase
+                .contact_pairs()
+                .flat_map(|pair| pair.manifolds.iter())
+                .flat_map(|manifold| manifold.points.iter())
+                .map(|contact| (contact.data.impulse, contact.data.reported_impulse))
+                .collect();
+            (positions, impulses)
+        }
+
+        let (positions, impulses) = settle_stack(0.0);
+        let (filtered_positions, filtered_impulses) = settle_stack(1.0e6);
+
+        assert!(impulses.iter().any(|(_, reported)| *reported > 0.0));
+        assert!(impulses
+            .iter()
+            .all(|(impulse, reported)| impulse == reported));
+        assert!(filtered_impulses
+            .iter()
+            .all(|(_, reported)| *reported == 0.0));
+
+        // The physical impulses, and thus the simulation, are unaffected by the threshold.
+        assert_eq!(positions, filtered_positions);
+        assert_eq!(impulses.len(), filtered_impulses.len());
+        for ((impulse, _), (filtered_impulse, _)) in impulses.iter().zip(filtered_impulses.iter()) {
+            assert_eq!(impulse, filtered_impulse);
+        }
+    }
+
+    #[test]
+    fn ghosting_a_building_stops_its_ground_contacts_in_a_single_step() {
+        use crate::geometry::{ColliderHandle, ContactEvent, InteractionGroups, IntersectionEvent};
+        use crate::math::Real;
+        use crate::pipeline::EventHandler;
+        use std::sync::Mutex;
+
+        struct ContactRecorder(Mutex<Vec<ContactEvent>>);
+
+        impl EventHandler for ContactRecorder {
+            fn handle_intersection_event(&self, _event: IntersectionEvent) {}
+            fn handle_contact_event(&self, event: ContactEvent) {
+                self.0.lock().unwrap().push(event);
+            }
+        }
+
+        const GHOST: u16 = 0b10;
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+        let recorder = ContactRecorder(Mutex::new(Vec::new()));
+
+        // The ground doesn't interact with the ghosts.
+        #[cfg(feature = "dim2")]
+        let ground = RigidBodyBuilder::new_static().translation(0.0, -0.5);
+        #[cfg(feature = "dim3")]
+        let ground = RigidBodyBuilder::new_static().translation(0.0, -0.5, 0.0);
+        let ground = bodies.insert(ground.build());
+        #[cfg(feature = "dim2")]
+        let ground_collider = ColliderBuilder::cuboid(20.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_collider = ColliderBuilder::cuboid(20.0, 0.5, 20.0);
+        let ground_collider = ground_collider
+            .collision_groups(InteractionGroups::new(0b01, 0xffff))
+            .build();
+        let ground_collider = colliders.insert(ground_collider, ground, &mut bodies);
+
+        // A building made of 10 columns of 10 boxes.
+        let mut building = Vec::new();
+        for i in 0..10 {
+            for j in 0..10 {
+                let x = (i as Real - 4.5) * 1.1;
+                let y = j as Real + 0.5;
+                #[cfg(feature = "dim2")]
+                let rb = RigidBodyBuilder::new_dynamic().translation(x, y);
+                #[cfg(feature = "dim3")]
+                let rb = RigidBodyBuilder::new_dynamic().translation(x, y, 0.0);
+                let handle = bodies.insert(rb.can_sleep(false).build());
+                #[cfg(feature = "dim2")]
+                let collider = ColliderBuilder::cuboid(0.5, 0.5).build();
+                #[cfg(feature = "dim3")]
+                let collider = ColliderBuilder::cuboid(0.5, 0.5, 0.5).build();
+                building.push(colliders.insert(collider, handle, &mut bodies));
+            }
+        }
+
+        let mut num_ground_contacts = 0;
+        let mut events = Vec::new();
+
+        for i in 0..30 {
+            if i == 20 {
+                num_ground_contacts = building
+                    .iter()
+                    .filter(|part| {
+                        narrow_phase
+                            .contact_pair(ground_collider, **part)
+                            .map_or(false, |pair| pair.has_any_active_contact)
+                    })
+                    .count();
+                colliders.set_collision_groups_batch(
+                    &building,
+                    InteractionGroups::new(GHOST, GHOST),
+                    &mut bodies,
+                );
+            }
+
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &recorder,
+            );
+            events.push(std::mem::replace(
+                &mut *recorder.0.lock().unwrap(),
+                Vec::new(),
+            ));
+        }
+
+        let is_part = |handle: &ColliderHandle| building.contains(handle);
+        assert!(num_ground_contacts >= 10);
+
+        // All the contacts with the ground stop during the same step, while the contacts
+        // between the parts of the building are unaffected.
+        assert_eq!(events[20].len(), num_ground_contacts);
+        for event in &events[20] {
+            match event {
+                ContactEvent::Stopped(h1, h2) => {
+                    assert!(*h1 == ground_collider || *h2 == ground_collider);
+                    assert!(is_part(h1) || is_part(h2));
+                }
+                ContactEvent::Started(..) => panic!("No contact must start."),
+            }
+        }
+
+        // NOTE: the contacts between the falling parts may still stop once they are
+        //       unloaded, but the contacts with the ground never start again.
+        for event in events[21..].iter().flatten() {
+            let (h1, h2) = match event {
+                ContactEvent::Started(h1, h2) | ContactEvent::Stopped(h1, h2) => (h1, h2),
+            };
+            assert!(*h1 != ground_collider && *h2 != ground_collider);
+        }
+
+        // Nothing holds the building anymore.
+        for part in building.iter().step_by(10) {
+            let parent = colliders[*part].parent();
+            assert!(bodies[parent].position().translation.vector.y < 0.45);
+        }
+    }
+
+    #[test]
+    fn user_tags_are_reported_by_the_contact_events() {
+        use crate::geometry::{
+            ColliderHandle, ColliderUserTags, ContactEvent, ContactEventData, IntersectionEvent,
+        };
+        use crate::math::Isometry;
+        use crate::pipeline::EventHandler;
+        use std::sync::Mutex;
+
+        struct ContactRecorder(Mutex<Vec<(ContactEvent, ContactEventData)>>);
+
+        impl EventHandler for ContactRecorder {
+            fn handle_intersection_event(&self, _event: IntersectionEvent) {}
+            fn handle_contact_event(&self, _event: ContactEvent) {}
+            fn handle_contact_event_with_data(&self, event: ContactEvent, data: ContactEventData) {
+                self.0.lock().unwrap().push((event, data));
+            }
+        }
+
+        let ground_tags = ColliderUserTags([1, 2, 3, 4]);
+        let ball_tags = ColliderUserTags([5, 6, 7, 8]);
+
+        let insert_ground = |bodies: &mut RigidBodySet, colliders: &mut ColliderSet| {
+            let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+            #[cfg(feature = "dim2")]
+            let collider = ColliderBuilder::cuboid(10.0, 0.5);
+            #[cfg(feature = "dim3")]
+            let collider = ColliderBuilder::cuboid(10.0, 0.5, 10.0);
+            colliders.insert(collider.user_tags(ground_tags).build(), ground, bodies)
+        };
+        let insert_ball = |bodies: &mut RigidBodySet, colliders: &mut ColliderSet| {
+            #[cfg(feature = "dim2")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(0.0, 1.0);
+            #[cfg(feature = "dim3")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(0.0, 1.0, 0.0);
+            let ball = bodies.insert(rb.build());
+            let collider = ColliderBuilder::ball(0.5)
+                .user_tags(ColliderUserTags([0, 6, 7, 8]))
+                .effect_material(5);
+            colliders.insert(collider.build(), ball, bodies)
+        };
+
+        // The order of the colliders in the contact pair depends on their insertion order.
+        for ground_first in [true, false].iter().copied() {
+            let mut pipeline = PhysicsPipeline::new();
+            let gravity = Vector::y() * -9.81;
+            let integration_parameters = IntegrationParameters::default();
+            let mut broad_phase = BroadPhase::new();
+            let mut narrow_phase = NarrowPhase::new();
+            let mut bodies = RigidBodySet::new();
+            let mut colliders = ColliderSet::new();
+            let mut ccd = CCDSolver::new();
+            let mut joints = JointSet::new();
+            let mut user_constraints = UserConstraintSet::new();
+            let recorder = ContactRecorder(Mutex::new(Vec::new()));
+
+            let (ground, ball) = if ground_first {
+                let ground = insert_ground(&mut bodies, &mut colliders);
+                (ground, insert_ball(&mut bodies, &mut colliders))
+            } else {
+                let ball = insert_ball(&mut bodies, &mut colliders);
+                (insert_ground(&mut bodies, &mut colliders), ball)
+            };
+            assert_eq!(colliders[ball].effect_material(), 5);
+            assert_eq!(colliders[ball].user_tags, ball_tags);
+
+            let tags = |handle: ColliderHandle| {
+                if handle == ground {
+                    ground_tags
+                } else {
+                    ball_tags
+                }
+            };
+
+            for i in 0..6 {
+                if i == 5 {
+                    let pair = narrow_phase.contact_pair(ground, ball).unwrap();
+                    assert_eq!(pair.user_tags1(), tags(pair.pair.collider1));
+                    assert_eq!(pair.user_tags2(), tags(pair.pair.collider2));
+
+                    // Move the ball away from the ground so that their contact stops.
+                    #[cfg(feature = "dim2")]
+                    let far_away = Isometry::translation(0.0, 10.0);
+                    #[cfg(feature = "dim3")]
+                    let far_away = Isometry::translation(0.0, 10.0, 0.0);
+                    bodies[colliders[ball].parent()].set_position(far_away, true);
+                }
+
+                pipeline.step(
+                    &gravity,
+                    &integration_parameters,
+                    &mut broad_phase,
+                    &mut narrow_phase,
+                    &mut bodies,
+                    &mut colliders,
+                    &mut joints,
+                    &mut user_constraints,
+                    &mut ccd,
+                    &(),
+                    &recorder,
+                );
+            }
+
+            let events = recorder.0.into_inner().unwrap();
+            assert_eq!(events.len(), 2);
+            assert!(matches!(events[0].0, ContactEvent::Started(..)));
+            assert!(matches!(events[1].0, ContactEvent::Stopped(..)));
+
+            for (event, data) in &events {
+                let (h1, h2) = match *event {
+                    ContactEvent::Started(h1, h2) | ContactEvent::Stopped(h1, h2) => (h1, h2),
+                };
+                assert_eq!(data.user_tags1, tags(h1));
+                assert_eq!(data.user_tags2, tags(h2));
+            }
+        }
+    }
+
+    #[test]
+    fn slowly_spinning_bodies_stay_awake_with_a_small_angular_sleep_threshold() {
+        use crate::math::Real;
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::zeros();
+        let integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        let mut insert = |i: usize, rb: RigidBodyBuilder| {
+            #[cfg(feature = "dim2")]
+            let rb = rb.translation(i as Real * 3.0, 0.0);
+            #[cfg(feature = "dim3")]
+            let rb = rb.translation(i as Real * 3.0, 0.0, 0.0);
+            let handle = bodies.insert(rb.build());
+            colliders.insert(ColliderBuilder::ball(0.5).build(), handle, &mut bodies);
+            handle
+        };
+
+        // A fan spinning at 0.05 rad/s, with the default thresholds and with a small angular one.
+        let fan = || RigidBodyBuilder::new_dynamic().angvel_z(0.05);
+        let default_fan = insert(0, fan());
+        let fan = insert(1, fan().sleep_thresholds(0.1, 0.01));
+        // The angular threshold doesn't keep slowly sliding bodies awake.
+        #[cfg(feature = "dim2")]
+        let slider = RigidBodyBuilder::new_dynamic().linvel(0.05, 0.0);
+        #[cfg(feature = "dim3")]
+        let slider = RigidBodyBuilder::new_dynamic().linvel(0.05, 0.0, 0.0);
+        let slider = insert(2, slider.sleep_thresholds(0.1, 0.01));
+
+        for _ in 0..300 {
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                &mut bodies,
+                &mut colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            );
+        }
+
+        assert!(bodies[default_fan].is_sleeping());
+        assert!(!bodies[fan].is_sleeping());
+        assert!(bodies[slider].is_sleeping());
+    }
+
+    #[test]
+    fn aggregate_proxies_find_the_same_contacts() {
+        use crate::geometry::ColliderHandle;
+        use crate::math::{Isometry, Real};
+        use na::RealField;
+
+        type Pairs = Vec<(ColliderHandle, ColliderHandle)>;
+
+        fn run(aggregate: bool) -> (Vec<Pairs>, Vec<Vector<Real>>, usize) {
+            let mut pipeline = PhysicsPipeline::new();
+            let gravity = Vector::zeros();
+            let integration_parameters = IntegrationParameters::default();
+            let mut broad_phase = BroadPhase::new();
+            let mut narrow_phase = NarrowPhase::new();
+            let mut bodies = RigidBodySet::new();
+            let mut colliders = ColliderSet::new();
+            let mut ccd = CCDSolver::new();
+            let mut joints = JointSet::new();
+            let mut user_constraints = UserConstraintSet::new();
+
+            // A heavy spinning station made of a ring of parts, hit by balls coming from outside.
+            let station = bodies.insert(RigidBodyBuilder::new_dynamic().angvel_z(0.5).build());
+            bodies[station].use_aggregate_proxy(aggregate);
+            let mut parts = vec![];
+            let mut balls = vec![];
+
+            for i in 0..32 {
+                let angle = i as Real * Real::pi() / 16.0;
+                let (sin, cos) = angle.sin_cos();
+                #[cfg(feature = "dim2")]
+                let pos = Isometry::translation(cos * 5.0, sin * 5.0);
+                #[cfg(feature = "dim3")]
+                let pos = Isometry::translation(cos * 5.0, sin * 5.0, 0.0);
+                let part = ColliderBuilder::ball(0.5)
+                    .density(100.0)
+                    .position_wrt_parent(pos)
+                    .build();
+                parts.push(colliders.insert(part, station, &mut bodies));
+
+                if i % 4 == 0 {
+                    let angle = angle + 0.1;
+                    let (sin, cos) = angle.sin_cos();
+                    #[cfg(feature = "dim2")]
+                    let rb = RigidBodyBuilder::new_dynamic()
+                        .translation(cos * 8.0, sin * 8.0)
+                        .linvel(-cos * 3.0, -sin * 3.0);
+                    #[cfg(feature = "dim3")]
+                    let rb = RigidBodyBuilder::new_dynamic()
+                        .translation(cos * 8.0, sin * 8.0, 0.0)
+                        .linvel(-cos * 3.0, -sin * 3.0, 0.0);
+                    let ball = bodies.insert(rb.build());
+                    colliders.insert(ColliderBuilder::ball(0.5).build(), ball, &mut bodies);
+                    balls.push(ball);
+                }
+            }
+
+            let mut contacts = vec![];
+            let mut num_proxies = 0;
+
+            for step in 0..120 {
+                // Exercise the removal of the children of the aggregate.
+                if step == 60 {
+                    colliders.remove(parts[3], &mut bodies, true, RemoveSettleMode::Immediate);
+                }
+
+                pipeline.step(
+                    &gravity,
+                    &integration_parameters,
+                    &mut broad_phase,
+                    &mut narrow_phase,
+                    &mut bodies,
+                    &mut colliders,
+                    &mut joints,
+                    &mut user_constraints,
+                    &mut ccd,
+                    &(),
+                    &(),
+                );
+
+                let mut pairs: Vec<_> = narrow_phase
+                    .contact_pairs()
+                    .filter(|pair| pair.has_any_active_contact)
+                    .map(|pair| {
+                        let (h1, h2) = (pair.pair.collider1, pair.pair.collider2);
+                        if h1.into_raw_parts() < h2.into_raw_parts() {
+                            (h1, h2)
+                        } else {
+                            (h2, h1)
+                        }
+                    })
+                    .collect();
+                pairs.sort_by_key(|(h1, h2)| (h1.into_raw_parts(), h2.into_raw_parts()));
+                contacts.push(pairs);
+                let stats = broad_phase.stats(false);
+                num_proxies = num_proxies.max(stats.num_proxies - stats.num_regions);
+            }
+
+            let positions = balls
+                .iter()
+                .map(|ball| bodies[*ball].position().translation.vector)
+                .collect();
+            (contacts, positions, num_proxies)
+        }
+
+        let (contacts, positions, num_proxies) = run(false);
+        let (aggregate_contacts, aggregate_positions, aggregate_num_proxies) = run(true);
+
+        assert!(contacts.iter().any(|pairs| !pairs.is_empty()));
+        assert_eq!(contacts, aggregate_contacts);
+
+        // NOTE: the pairs found through the aggregate are reported in another order, so the
+        //       contacts are solved in another order, and the positions drift slightly apart.
+        for (pos, aggregate_pos) in positions.iter().zip(aggregate_positions.iter()) {
+            assert!((pos - aggregate_pos).norm() < 1.0e-2);
+        }
+
+        // The 32 parts of the station share a single proxy.
+        assert_eq!(num_proxies, 32 + 8);
+        assert_eq!(aggregate_num_proxies, 1 + 8);
+    }
+
+    #[test]
+    #[cfg(any(feature = "simd-is-enabled", feature = "parallel"))]
+    fn grouped_contacts_with_a_never_woken_pile_ignore_its_stale_offsets() {
+        use crate::dynamics::WakePolicy;
+        use crate::math::Real;
+
+        let mut pipeline = PhysicsPipeline::new();
+        let gravity = Vector::y() * -9.81;
+        let integration_parameters = IntegrationParameters::default();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut ccd = CCDSolver::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+
+        #[cfg(feature = "dim2")]
+        let ground = RigidBodyBuilder::new_static().translation(0.0, -0.5);
+        #[cfg(feature = "dim3")]
+        let ground = RigidBodyBuilder::new_static().translation(0.0, -0.5, 0.0);
+        let ground = bodies.insert(ground.build());
+        #[cfg(feature = "dim2")]
+        let ground_shape = ColliderBuilder::cuboid(20.0, 0.5);
+        #[cfg(feature = "dim3")]
+        let ground_shape = ColliderBuilder::cuboid(20.0, 0.5, 20.0);
+        colliders.insert(ground_shape.build(), ground, &mut bodies);
+
+        // A long pile of boxes, awake during the first timestep so they get large offsets
+        // in the active set.
+        let mut pile = Vec::new();
+        for i in 0..16 {
+            let x = i as Real * 0.5;
+            #[cfg(feature = "dim2")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(x, 0.25);
+            #[cfg(feature = "dim3")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(x, 0.25, 0.0);
+            let handle = bodies.insert(rb.wake_policy(WakePolicy::Never).build());
+            #[cfg(feature = "dim2")]
+            let shape = ColliderBuilder::cuboid(0.25, 0.25);
+            #[cfg(feature = "dim3")]
+            let shape = ColliderBuilder::cuboid(0.25, 0.25, 0.25);
+            colliders.insert(shape.build(), handle, &mut bodies);
+            pile.push(handle);
+        }
+
+        // A few balls resting on top of the pile, enough to fill SIMD groups.
+        let mut balls = Vec::new();
+        for i in 0..8 {
+            let x = 0.25 + i as Real;
+            #[cfg(feature = "dim2")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(x, 0.75);
+            #[cfg(feature = "dim3")]
+            let rb = RigidBodyBuilder::new_dynamic().translation(x, 0.75, 0.0);
+            let handle = bodies.insert(rb.build());
+            colliders.insert(ColliderBuilder::ball(0.25).build(), handle, &mut bodies);
+            balls.push(handle);
+        }
+
+        let mut step = |bodies: &mut RigidBodySet, colliders: &mut ColliderSet| {
+            pipeline.step(
+                &gravity,
+                &integration_parameters,
+                &mut broad_phase,
+                &mut narrow_phase,
+                bodies,
+                colliders,
+                &mut joints,
+                &mut user_constraints,
+                &mut ccd,
+                &(),
+                &(),
+            )
+        };
+
+        step(&mut bodies, &mut colliders);
+
+        // The pile falls asleep, so the balls form a much smaller active island and the
+        // offsets of the pile bodies are out of its bounds.
+        let pile_positions: Vec<_> = pile
+            .iter()
+            .map(|h| {
+                let rb = bodies.get_mut(*h).unwrap();
+                rb.sleep();
+                *rb.position()
+            })
+            .collect();
+
+        for _ in 0..60 {
+            step(&mut bodies, &mut colliders);
+        }
+
+        for (handle, position) in pile.iter().zip(pile_positions.iter()) {
+            assert!(bodies[*handle].is_sleeping());
+            assert_eq!(bodies[*handle].position(), position);
+        }
+
+        // The balls keep resting on the pile acting as a static body.
+        for handle in &balls {
+            assert!(bodies[*handle].position().translation.vector.y > 0.7);
+        }
+    }
+
+    #[test]
+    fn joints_ignore_the_degrees_of_freedom_locked_on_both_bodies() {
+        use crate::dynamics::{BallJoint, FixedJoint, JointParams};
+        use crate::math::{Isometry, Point};
+
+        let joint_kinds: [JointParams; 2] = [
+            BallJoint::new(Point::origin(), Point::from(Vector::x() * -1.0)).into(),
+            FixedJoint::new(
+                Isometry::identity(),
+                Isometry::new(Vector::x() * -1.0, na::zero()),
+            )
+            .into(),
+        ];
+
+        for joint in joint_kinds.iter() {
+            let mut pipeline = PhysicsPipeline::new();
+            let gravity = Vector::y() * -9.81;
+            let integration_parameters = IntegrationParameters::default();
+            let mut broad_phase = BroadPhase::new();
+            let mut narrow_phase = NarrowPhase::new();
+            let mut bodies = RigidBodySet::new();
+            let mut colliders = ColliderSet::new();
+            let mut ccd = CCDSolver::new();
+            let mut joints = JointSet::new();
+            let mut user_constraints = UserConstraintSet::new();
+
+            let anchor = bodies.insert(RigidBodyBuilder::new_static().build());
+
+            // Neither body can move along the Y axis, so the joint can't apply any impulse along
+            // it: its effective mass along this axis is zero.
+            #[cfg(feature = "dim2")]
+            let platform = RigidBodyBuilder::new_dynamic()
+                .translation(1.0, 0.0)
+                .restrict_translations(true, false)
+                .lock_rotations()
+                .linvel(1.0, 0.0);
+            #[cfg(feature = "dim3")]
+            let platform = RigidBodyBuilder::new_dynamic()
+                .translation(1.0, 0.0, 0.0)
+                .restrict_translations(true, false, true)
+                .restrict_rotations(false, true, false)
+                .angvel(Vector::y() * 1.0);
+            let platform = bodies.insert(platform.build());
+            #[cfg(feature = "dim2")]
+            let platform_shape = ColliderBuilder::cuboid(0.5, 0.1);
+            #[cfg(feature = "dim3")]
+            let platform_shape = ColliderBuilder::cuboid(0.5, 0.1, 0.5);
+            colliders.insert(platform_shape.build(), platform, &mut bodies);
+
+            joints.insert(&mut bodies, anchor, platform, *joint);
+
+            for _ in 0..60 {
+                pipeline.step(
+                    &gravity,
+                    &integration_parameters,
+                    &mut broad_phase,
+                    &mut narrow_phase,
+                    &mut bodies,
+                    &mut colliders,
+                    &mut joints,
+                    &mut user_constraints,
+                    &mut ccd,
+                    &(),
+                    &(),
+                );
+            }
+
+            let rb = &bodies[platform];
+            assert!(rb.linvel().iter().all(|x| x.is_finite()));
+            assert!(rb
+                .position()
+                .translation
+                .vector
+                .iter()
+                .all(|x| x.is_finite()));
+            assert!(rb.position().translation.vector.y.abs() < 1.0e-5);
+            // The joint keeps the platform at one unit of the anchor along the free axes.
+            assert!((rb.position().translation.vector.norm() - 1.0).abs() < 1.0e-2);
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "simd-is-enabled", not(feature = "parallel")))]
+    fn padded_simd_contact_constraints_match_the_scalar_ones() {
+        use crate::math::{Isometry, Real, SIMD_WIDTH};
+
+        type BodyStates = Vec<(Isometry<Real>, Vector<Real>)>;
+
+        // Simulates `SIMD_WIDTH` bodies sliding on the ground, each with a different shape.
+        // Returns the number of contact constraints of the first timestep, the number of solver
+        // contacts of each body during the first timestep, and their final state.
+        fn slide_bodies(min_island_size: usize) -> (usize, Vec<usize>, BodyStates) {
+            let mut pipeline = PhysicsPipeline::new();
+            let gravity = Vector::y() * -9.81;
+            let integration_parameters = IntegrationParameters {
+                min_island_size,
+                ..IntegrationParameters::default()
+            };
+            let mut broad_phase = BroadPhase::new();
+            let mut narrow_phase = NarrowPhase::new();
+            let mut bodies = RigidBodySet::new();
+            let mut colliders = ColliderSet::new();
+            let mut ccd = CCDSolver::new();
+            let mut joints = JointSet::new();
+            let mut user_constraints = UserConstraintSet::new();
+
+            #[cfg(feature = "dim2")]
+            let ground = RigidBodyBuilder::new_static().translation(0.0, -0.5);
+            #[cfg(feature = "dim3")]
+            let ground = RigidBodyBuilder::new_static().translation(0.0, -0.5, 0.0);
+            let ground = bodies.insert(ground.build());
+            #[cfg(feature = "dim2")]
+            let ground_shape = ColliderBuilder::cuboid(50.0, 0.5);
+            #[cfg(feature = "dim3")]
+            let ground_shape = ColliderBuilder::cuboid(50.0, 0.5, 50.0);
+            let ground_collider = colliders.insert(ground_shape.build(), ground, &mut bodies);
+
+            let mut sliding = Vec::new();
+            for i in 0..SIMD_WIDTH {
+                // The shapes have different numbers of contacts with the ground.
+                #[cfg(feature = "dim2")]
+                let (shape, half_height) = match i % 3 {
+                    0 => (ColliderBuilder::ball(0.5), 0.5),
+                    1 => (ColliderBuilder::cuboid(0.5, 0.25), 0.25),
+                    _ => (ColliderBuilder::capsule_x(0.5, 0.25), 0.25),
+                };
+                #[cfg(feature = "dim3")]
+                let (shape, half_height) = match i % 3 {
+                    0 => (ColliderBuilder::ball(0.5), 0.5),
+                    1 => (ColliderBuilder::cuboid(0.5, 0.25, 0.5), 0.25),
+                    _ => (ColliderBuilder::capsule_x(0.5, 0.25), 0.25),
+                };
+
+                let x = i as Real * 4.0;
+                #[cfg(feature = "dim2")]
+                let rb = RigidBodyBuilder::new_dynamic()
+                    .translation(x, half_height)
+                    .linvel(1.0 + i as Real, 0.0);
+                #[cfg(feature = "dim3")]
+                let rb = RigidBodyBuilder::new_dynamic()
+                    .translation(x, half_height, 0.0)
+                    .linvel(1.0 + i as Real, 0.0, 0.5);
+                let handle = bodies.insert(rb.build());
+                let collider = colliders.insert(shape.build(), handle, &mut bodies);
+                sliding.push((handle, collider));
+            }
+
+            let mut num_contact_constraints = 0;
+            let mut num_solver_contacts = Vec::new();
+
+            for k in 0..60 {
+                pipeline.step(
+                    &gravity,
+                    &integration_parameters,
+                    &mut broad_phase,
+                    &mut narrow_phase,
+                    &mut bodies,
+                    &mut colliders,
+                    &mut joints,
+                    &mut user_constraints,
+                    &mut ccd,
+                    &(),
+                    &(),
+                );
+
+                if k == 0 {
+                    num_contact_constraints = pipeline
+                        .stats(
+                            false,
+                            &mut broad_phase,
+                            &mut narrow_phase,
+                            &bodies,
+                            &colliders,
+                        )
+                        .solver
+                        .num_contact_constraints;
+                    num_solver_contacts = sliding
+                        .iter()
+                        .map(|(_, collider)| {
+                            let pair = narrow_phase.contact_pair(ground_collider, *collider);
+                            pair.unwrap().manifolds[0].data.solver_contacts.len()
+                        })
+                        .collect();
+                }
+            }
+
+            let states = sliding
+                .iter()
+                .map(|(handle, _)| (*bodies[*handle].position(), *bodies[*handle].linvel()))
+                .collect();
+            (num_contact_constraints, num_solver_contacts, states)
+        }
+
+        // All the bodies are in the same island so their contacts are grouped into a single
+        // SIMD constraint, even if they don't have the same number of contacts.
+        let (grouped, num_contacts, simd_states) = slide_bodies(128);
+        assert_eq!(grouped, 1);
+        assert!(num_contacts.iter().any(|n| *n != num_contacts[0]));
+
+        // Each body is in its own island so their contacts are solved by scalar constraints.
+        let (nongrouped, _, scalar_states) = slide_bodies(1);
+        assert_eq!(nongrouped, SIMD_WIDTH);
+
+        // The padding contacts of the SIMD constraint don't apply any impulse.
+        for ((simd_pos, simd_vel), (scalar_pos, scalar_vel)) in
+            simd_states.iter().zip(scalar_states.iter())
+        {
+            let dpos = simd_pos.translation.vector - scalar_pos.translation.vector;
+            assert!(dpos.norm() < 1.0e-3);
+            assert!((simd_vel - scalar_vel).norm() < 1.0e-3);
+        }
+    }
 }
@@ -0,0 +1,131 @@
+use crate::data::{HashMap, HashSet};
+use crate::geometry::{BroadPhasePairEvent, ColliderHandle, ColliderPair};
+use alloc::vec::Vec;
+
+/// What the physics pipeline does when one of the `PipelineLimits` is exceeded.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub enum OnLimitExceeded {
+    /// Panic with a message describing the exceeded limit.
+    Panic,
+    /// Drop the interactions exceeding the limit, and report it to the event handler.
+    ///
+    /// The interactions involving the colliders with the smallest handles are kept.
+    DropExcessAndReport,
+}
+
+impl Default for OnLimitExceeded {
+    fn default() -> Self {
+        OnLimitExceeded::DropExcessAndReport
+    }
+}
+
+/// Safety limits protecting the physics pipeline against degenerate scenes, e.g.,
+/// thousands of colliders spawned at the same location.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct PipelineLimits {
+    /// The maximum number of pairs of colliders tracked by the narrow-phase (default: `usize::MAX`).
+    ///
+    /// The new pairs exceeding this limit are never registered, i.e., they won't be considered again
+    /// until the two colliders stop, and start again, overlapping according to the broad-phase.
+    pub max_contact_pairs: usize,
+    /// The maximum total number of contact points given to the constraints solver (default: `usize::MAX`).
+    ///
+    /// The contact points exceeding this limit are ignored for the current timestep.
+    pub max_manifold_points_total: usize,
+    /// The maximum number of dynamic bodies in a single island (default: `usize::MAX`).
+    ///
+    /// Exceeding this limit is reported, or panics, but doesn't drop anything.
+    pub max_bodies_per_island: usize,
+    /// What to do when one of these limits is exceeded (default: `OnLimitExceeded::DropExcessAndReport`).
+    pub on_limit_exceeded: OnLimitExceeded,
+}
+
+impl Default for PipelineLimits {
+    fn default() -> Self {
+        Self {
+            max_contact_pairs: usize::MAX,
+            max_manifold_points_total: usize::MAX,
+            max_bodies_per_island: usize::MAX,
+            on_limit_exceeded: OnLimitExceeded::default(),
+        }
+    }
+}
+
+/// Report emitted at the end of a timestep during which one of the `PipelineLimits` was exceeded.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PipelineLimitsReport {
+    /// The number of pairs of colliders that would have been tracked by the narrow-phase without limit.
+    pub num_contact_pairs: usize,
+    /// The number of new pairs of colliders that were not registered.
+    pub num_dropped_contact_pairs: usize,
+    /// The number of contact points that would have been given to the constraints solver without limit.
+    pub num_manifold_points: usize,
+    /// The number of contact points that were ignored.
+    pub num_dropped_manifold_points: usize,
+    /// The number of dynamic bodies of the largest island.
+    pub max_island_size: usize,
+    /// The colliders involved in the largest number of new pairs during this timestep, with their number
+    /// of new pairs, sorted by decreasing number of pairs.
+    pub worst_colliders: Vec<(ColliderHandle, usize)>,
+}
+
+impl PipelineLimitsReport {
+    /// The maximum number of colliders listed in `Self::worst_colliders`.
+    pub const MAX_WORST_COLLIDERS: usize = 8;
+
+    pub(crate) fn set_worst_colliders(&mut self, broad_phase_events: &[BroadPhasePairEvent]) {
+        let mut counts = HashMap::default();
+
+        for event in broad_phase_events {
+            if let BroadPhasePairEvent::AddPair(pair) = event {
+                *counts.entry(pair.collider1).or_insert(0) += 1;
+                *counts.entry(pair.collider2).or_insert(0) += 1;
+            }
+        }
+
+        let mut worst: Vec<_> = counts.into_iter().collect();
+        worst.sort_by_key(|(handle, count)| (core::cmp::Reverse(*count), handle.0));
+        worst.truncate(Self::MAX_WORST_COLLIDERS);
+        self.worst_colliders = worst;
+    }
+}
+
+/// Removes the new pairs exceeding `max_new_pairs` from the broad-phase events, keeping the
+/// pairs with the smallest collider handles.
+///
+/// Returns the number of removed pairs.
+pub(crate) fn drop_excess_pairs(
+    broad_phase_events: &mut Vec<BroadPhasePairEvent>,
+    max_new_pairs: usize,
+) -> usize {
+    fn key(pair: &ColliderPair) -> (crate::data::arena::Index, crate::data::arena::Index) {
+        (
+            pair.collider1.0.min(pair.collider2.0),
+            pair.collider1.0.max(pair.collider2.0),
+        )
+    }
+
+    let mut new_pairs: Vec<_> = broad_phase_events
+        .iter()
+        .filter_map(|event| match event {
+            BroadPhasePairEvent::AddPair(pair) => Some(key(pair)),
+            BroadPhasePairEvent::DeletePair(_) => None,
+        })
+        .collect();
+
+    if new_pairs.len() <= max_new_pairs {
+        return 0;
+    }
+
+    new_pairs.sort_unstable();
+    let kept: HashSet<_> = new_pairs[..max_new_pairs].iter().copied().collect();
+
+    broad_phase_events.retain(|event| match event {
+        BroadPhasePairEvent::AddPair(pair) => kept.contains(&key(pair)),
+        BroadPhasePairEvent::DeletePair(_) => true,
+    });
+
+    new_pairs.len() - max_new_pairs
+}
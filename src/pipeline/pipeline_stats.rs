@@ -0,0 +1,35 @@
+use crate::counters::Counters;
+use crate::dynamics::{RigidBodySetStats, SolverStats};
+use crate::geometry::{BroadPhaseStats, ColliderSetStats, NarrowPhaseStats};
+
+/// A full report of the statistics of the structures involved in a simulation,
+/// as returned by `PhysicsPipeline::stats`.
+#[derive(Copy, Clone)]
+pub struct PipelineStats {
+    /// The performance counters of the physics pipeline during the last timestep.
+    pub counters: Counters,
+    /// Statistics about the broad-phase.
+    pub broad_phase: BroadPhaseStats,
+    /// Statistics about the narrow-phase.
+    pub narrow_phase: NarrowPhaseStats,
+    /// Statistics about the constraints solver.
+    pub solver: SolverStats,
+    /// Statistics about the rigid-body set.
+    pub bodies: RigidBodySetStats,
+    /// Statistics about the collider set.
+    pub colliders: ColliderSetStats,
+}
+
+impl PipelineStats {
+    /// The approximate total number of bytes allocated by the structures of the simulation.
+    ///
+    /// The memory of the broad-phase, narrow-phase, and solver is only taken into account if
+    /// the statistics were requested with `detailed` set to `true`.
+    pub fn total_bytes(&self) -> usize {
+        self.broad_phase.bytes
+            + self.narrow_phase.bytes
+            + self.solver.bytes
+            + self.bodies.bytes
+            + self.colliders.bytes
+    }
+}
@@ -0,0 +1,89 @@
+use crate::dynamics::{PositionSolveBudget, RigidBodyHandle, RigidBodySet};
+use crate::math::Real;
+use alloc::{vec, vec::Vec};
+use core::cmp::Ordering;
+
+/// Tracks the work done by the position solver during a timestep against the
+/// `IntegrationParameters::position_solve_budget`.
+pub(crate) struct PositionBudgetTracker {
+    budget: PositionSolveBudget,
+    now: fn() -> f64,
+    start: f64,
+    num_solves: usize,
+    exhausted: bool,
+}
+
+impl PositionBudgetTracker {
+    /// Starts tracking the `budget`, measuring the time with the clock `now` of the counters.
+    pub fn new(budget: PositionSolveBudget, now: fn() -> f64) -> Self {
+        Self {
+            budget,
+            now,
+            start: now(),
+            num_solves: 0,
+            exhausted: false,
+        }
+    }
+
+    /// Checks if an island costing `cost` constraint solves can be solved within the budget.
+    ///
+    /// Once an island doesn't fit, the budget is exhausted and all the following islands are
+    /// rejected too, so they keep their priority during the next timestep. The first island
+    /// with a non-zero cost is always accepted, so the position correction always progresses.
+    pub fn try_spend(&mut self, cost: usize) -> bool {
+        if cost == 0 {
+            return true;
+        }
+
+        if !self.exhausted && self.num_solves != 0 {
+            self.exhausted = match self.budget {
+                PositionSolveBudget::ConstraintSolves(max_solves) => {
+                    self.num_solves + cost > max_solves
+                }
+                PositionSolveBudget::Duration(max_duration) => {
+                    // NOTE: the clocks are in milliseconds.
+                    ((self.now)() - self.start) / 1000.0 >= max_duration.as_secs_f64()
+                }
+            };
+        }
+
+        if !self.exhausted {
+            self.num_solves += cost;
+        }
+
+        !self.exhausted
+    }
+}
+
+/// The order in which the islands are position-solved with a budget: the islands containing
+/// a body of an island deferred during the last timestep first, then by decreasing penetration.
+pub(crate) fn position_solve_order(
+    bodies: &RigidBodySet,
+    deferred_bodies: &[RigidBodyHandle],
+    max_penetrations: &[Real],
+) -> Vec<usize> {
+    let mut deferred = vec![false; max_penetrations.len()];
+
+    for handle in deferred_bodies {
+        if let Some(rb) = bodies.get(*handle) {
+            // NOTE: the body may have fallen asleep in the meantime.
+            if bodies.active_dynamic_set.get(rb.active_set_id) == Some(handle) {
+                if let Some(deferred) = deferred.get_mut(rb.active_island_id) {
+                    *deferred = true;
+                }
+            }
+        }
+    }
+
+    let mut order: Vec<_> = (0..max_penetrations.len()).collect();
+    // NOTE: the sort is stable so islands with the same priority are solved
+    //       in the order of their ids, keeping the simulation deterministic.
+    order.sort_by(|a, b| {
+        deferred[*b].cmp(&deferred[*a]).then_with(|| {
+            max_penetrations[*b]
+                .partial_cmp(&max_penetrations[*a])
+                .unwrap_or(Ordering::Equal)
+        })
+    });
+    order
+}
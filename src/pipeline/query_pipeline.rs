@@ -1,9 +1,10 @@
-use crate::dynamics::RigidBodySet;
+use crate::dynamics::{RigidBodyHandle, RigidBodySet};
 use crate::geometry::{
-    Collider, ColliderHandle, ColliderSet, InteractionGroups, PointProjection, Ray,
+    clamp_aabb, Collider, ColliderHandle, ColliderSet, InteractionGroups, PointProjection, Ray,
     RayIntersection, SimdQuadTree, AABB,
 };
 use crate::math::{Isometry, Point, Real, Vector};
+use parry::bounding_volume::BoundingVolume;
 use parry::query::details::{
     IntersectionCompositeShapeShapeBestFirstVisitor,
     NonlinearTOICompositeShapeShapeBestFirstVisitor, PointCompositeShapeProjBestFirstVisitor,
@@ -14,9 +15,13 @@ use parry::query::details::{
 use parry::query::visitors::{
     BoundingVolumeIntersectionsVisitor, PointIntersectionsVisitor, RayIntersectionsVisitor,
 };
-use parry::query::{DefaultQueryDispatcher, NonlinearRigidMotion, QueryDispatcher, TOI};
+use parry::query::{DefaultQueryDispatcher, NonlinearRigidMotion, QueryDispatcher, RayCast, TOI};
 use parry::shape::{FeatureId, Shape, TypedSimdCompositeShape};
-use std::sync::Arc;
+
+use alloc::sync::Arc;
+use alloc::{vec, vec::Vec};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 /// A pipeline for performing queries on all the colliders of a scene.
 #[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
@@ -145,26 +150,34 @@ impl QueryPipeline {
         colliders: &ColliderSet,
         mode: QueryPipelineMode,
     ) {
+        // NOTE: the AABBs are clamped so that the infinite AABBs of the half-spaces
+        //       don't result in non-finite values inside of the tree.
         if !self.tree_built {
+            // NOTE: the detached colliders are ignored by all the queries.
+            let attached_colliders: Vec<_> =
+                colliders.iter().filter(|(_, c)| !c.is_detached()).collect();
+
             match mode {
                 QueryPipelineMode::CurrentPosition => {
-                    let data = colliders.iter().map(|(h, c)| (h, c.compute_aabb()));
+                    let data = attached_colliders
+                        .into_iter()
+                        .map(|(h, c)| (h, clamp_aabb(c.compute_aabb())));
                     self.quadtree.clear_and_rebuild(data, self.dilation_factor);
                 }
                 QueryPipelineMode::SweepTestWithNextPosition => {
-                    let data = colliders.iter().map(|(h, c)| {
+                    let data = attached_colliders.into_iter().map(|(h, c)| {
                         let next_position =
                             bodies[c.parent()].next_position * c.position_wrt_parent();
-                        (h, c.compute_swept_aabb(&next_position))
+                        (h, clamp_aabb(c.compute_swept_aabb(&next_position)))
                     });
                     self.quadtree.clear_and_rebuild(data, self.dilation_factor);
                 }
                 QueryPipelineMode::SweepTestWithPredictedPosition { dt } => {
-                    let data = colliders.iter().map(|(h, c)| {
+                    let data = attached_colliders.into_iter().map(|(h, c)| {
                         let next_position = bodies[c.parent()]
                             .predict_position_using_velocity_and_forces(dt)
                             * c.position_wrt_parent();
-                        (h, c.compute_swept_aabb(&next_position))
+                        (h, clamp_aabb(c.compute_swept_aabb(&next_position)))
                     });
                     self.quadtree.clear_and_rebuild(data, self.dilation_factor);
                 }
@@ -187,7 +200,7 @@ impl QueryPipeline {
         match mode {
             QueryPipelineMode::CurrentPosition => {
                 self.quadtree.update(
-                    |handle| colliders[*handle].compute_aabb(),
+                    |handle| clamp_aabb(colliders[*handle].compute_aabb()),
                     self.dilation_factor,
                 );
             }
@@ -197,7 +210,7 @@ impl QueryPipeline {
                         let co = &colliders[*handle];
                         let next_position =
                             bodies[co.parent()].next_position * co.position_wrt_parent();
-                        co.compute_swept_aabb(&next_position)
+                        clamp_aabb(co.compute_swept_aabb(&next_position))
                     },
                     self.dilation_factor,
                 );
@@ -209,7 +222,7 @@ impl QueryPipeline {
                         let next_position = bodies[co.parent()]
                             .predict_position_using_velocity_and_forces(dt)
                             * co.position_wrt_parent();
-                        co.compute_swept_aabb(&next_position)
+                        clamp_aabb(co.compute_swept_aabb(&next_position))
                     },
                     self.dilation_factor,
                 );
@@ -285,6 +298,121 @@ impl QueryPipeline {
         self.quadtree.traverse_best_first(&mut visitor).map(|h| h.1)
     }
 
+    /// Find the closest intersection between each ray of a set of rays and a set of colliders.
+    ///
+    /// The content of `out` is replaced by the results of the casts: `out[i]` is the result of
+    /// the cast of `rays[i]`, as given by `Self::cast_ray`. With the `parallel` feature enabled,
+    /// the rays are cast in parallel.
+    ///
+    /// # Parameters
+    /// - `rays`: the rays to cast.
+    /// - `max_toi`: the maximum time-of-impact that can be reported by each cast. This effectively
+    ///              limits the length of each ray to `ray.dir.norm() * max_toi`.
+    ///              Use `Real::MAX` for unbounded rays.
+    /// - `solid`: if this is `true` an impact at time 0.0 (i.e. at the ray origin) is returned if
+    ///            it starts inside of a shape. If this `false` then the ray will hit the shape's boundary
+    ///            even if its starts inside of it.
+    /// - `query_groups`: the interaction groups which will be tested against the collider's `contact_group`
+    ///                   to determine if it should be taken into account by this query.
+    /// - `filter`: a more fine-grained filter. A collider is taken into account by this query if
+    ///             its `contact_group` is compatible with the `query_groups`, and if this `filter`
+    ///             is either `None` or returns `true`.
+    /// - `out`: the buffer the results are written to.
+    pub fn cast_rays(
+        &self,
+        colliders: &ColliderSet,
+        rays: &[Ray],
+        max_toi: Real,
+        solid: bool,
+        query_groups: InteractionGroups,
+        filter: Option<&(dyn Fn(ColliderHandle, &Collider) -> bool + Sync)>,
+        out: &mut Vec<Option<(ColliderHandle, Real)>>,
+    ) {
+        out.clear();
+        out.resize(rays.len(), None);
+
+        #[cfg(not(feature = "parallel"))]
+        {
+            let filter = filter.map(|f| f as &dyn Fn(ColliderHandle, &Collider) -> bool);
+            let pipeline_shape = self.as_composite_shape(colliders, query_groups, filter);
+
+            for (ray, hit) in rays.iter().zip(out.iter_mut()) {
+                let mut visitor =
+                    RayCompositeShapeToiBestFirstVisitor::new(&pipeline_shape, ray, max_toi, solid);
+                *hit = self.quadtree.traverse_best_first(&mut visitor).map(|h| h.1);
+            }
+        }
+
+        #[cfg(feature = "parallel")]
+        {
+            rays.par_iter()
+                .zip(out.par_iter_mut())
+                .for_each(|(ray, hit)| {
+                    let filter = filter.map(|f| f as &dyn Fn(ColliderHandle, &Collider) -> bool);
+                    *hit = self.cast_ray(colliders, ray, max_toi, solid, query_groups, filter);
+                });
+        }
+    }
+
+    /// Find the closest intersection between a ray and the colliders attached to a single rigid-body.
+    ///
+    /// This does not traverse the acceleration structure of this pipeline, and only tests the
+    /// colliders attached to the rigid-body `body`. It is therefore much faster than using a
+    /// filter with `Self::cast_ray_and_get_normal` when there are many colliders in the scene.
+    ///
+    /// If the rigid-body uses an aggregate proxy (see `RigidBody::use_aggregate_proxy`), only the
+    /// colliders which AABB is hit by the ray are tested, using the BVH of the aggregate.
+    ///
+    /// Returns `None` if `body` does not exist or if the ray doesn't hit any of its colliders.
+    ///
+    /// # Parameters
+    /// - `body`: the rigid-body which colliders are tested.
+    /// - `ray`: the ray to cast.
+    /// - `max_toi`: the maximum time-of-impact that can be reported by this cast. This effectively
+    ///              limits the length of the ray to `ray.dir.norm() * max_toi`.
+    ///              Use `Real::MAX` for an unbounded ray.
+    /// - `solid`: if this is `true` an impact at time 0.0 (i.e. at the ray origin) is returned if
+    ///            it starts inside of a shape. If this `false` then the ray will hit the shape's boundary
+    ///            even if its starts inside of it.
+    pub fn cast_ray_against_body(
+        &self,
+        bodies: &RigidBodySet,
+        colliders: &ColliderSet,
+        body: RigidBodyHandle,
+        ray: &Ray,
+        max_toi: Real,
+        solid: bool,
+    ) -> Option<(ColliderHandle, RayIntersection)> {
+        let mut best: Option<(ColliderHandle, RayIntersection)> = None;
+        let body_colliders = bodies.get(body)?.colliders();
+        let mut candidates = vec![];
+
+        // Only test the colliders which AABB is hit, if the body uses an aggregate proxy.
+        let handles = match colliders.up_to_date_aggregate(body, body_colliders) {
+            Some(aggregate) => {
+                let frame = Self::aggregate_frame(colliders, body_colliders);
+                let local_ray = ray.inverse_transform_by(&frame);
+                aggregate.children_intersecting_local_ray(&local_ray, max_toi, &mut candidates);
+                &candidates[..]
+            }
+            None => body_colliders,
+        };
+
+        for handle in handles {
+            let co = &colliders[*handle];
+            let max_toi = best.map(|(_, inter)| inter.toi).unwrap_or(max_toi);
+
+            if let Some(inter) =
+                co.shape()
+                    .cast_ray_and_get_normal(co.position(), ray, max_toi, solid)
+            {
+                best = Some((*handle, inter));
+            }
+        }
+
+        best
+    }
+
     /// Find the all intersections between a ray and a set of collider and passes them to a callback.
     ///
     /// # Parameters
@@ -518,6 +646,156 @@ impl QueryPipeline {
         self.quadtree.traverse_best_first(&mut visitor).map(|h| h.1)
     }
 
+    /// Casts a shape from several initial positions and at several constant linear velocities, and
+    /// retrieve the first collider it hits for each cast.
+    ///
+    /// The content of `out` is replaced by the results of the casts: `out[i]` is the result of
+    /// the cast from `casts[i]`, as given by `Self::cast_shape`. With the `parallel` feature
+    /// enabled, the casts are performed in parallel.
+    ///
+    /// # Parameters
+    /// * `colliders` - The set of colliders taking part in this pipeline.
+    /// * `casts` - The initial positions of the shape to cast, and the constant velocities
+    ///             (i.e. the cast directions) of each cast.
+    /// * `shape` - The shape to cast.
+    /// * `max_toi` - The maximum time-of-impact that can be reported by each cast. This effectively
+    ///               limits the distance traveled by the shape to `shapeVel.norm() * max_toi`.
+    /// * `query_groups` - the interaction groups which will be tested against the collider's `contact_group`
+    ///                    to determine if it should be taken into account by this query.
+    /// * `filter` - a more fine-grained filter. A collider is taken into account by this query if
+    ///             its `contact_group` is compatible with the `query_groups`, and if this `filter`
+    ///             is either `None` or returns `true`.
+    /// * `out` - The buffer the results are written to.
+    pub fn cast_shapes(
+        &self,
+        colliders: &ColliderSet,
+        casts: &[(Isometry<Real>, Vector<Real>)],
+        shape: &dyn Shape,
+        max_toi: Real,
+        query_groups: InteractionGroups,
+        filter: Option<&(dyn Fn(ColliderHandle, &Collider) -> bool + Sync)>,
+        out: &mut Vec<Option<(ColliderHandle, TOI)>>,
+    ) {
+        out.clear();
+        out.resize(casts.len(), None);
+
+        #[cfg(not(feature = "parallel"))]
+        {
+            let filter = filter.map(|f| f as &dyn Fn(ColliderHandle, &Collider) -> bool);
+            let pipeline_shape = self.as_composite_shape(colliders, query_groups, filter);
+
+            for ((shape_pos, shape_vel), hit) in casts.iter().zip(out.iter_mut()) {
+                let mut visitor = TOICompositeShapeShapeBestFirstVisitor::new(
+                    &*self.query_dispatcher,
+                    shape_pos,
+                    shape_vel,
+                    &pipeline_shape,
+                    shape,
+                    max_toi,
+                );
+                *hit = self.quadtree.traverse_best_first(&mut visitor).map(|h| h.1);
+            }
+        }
+
+        #[cfg(feature = "parallel")]
+        {
+            casts
+                .par_iter()
+                .zip(out.par_iter_mut())
+                .for_each(|((shape_pos, shape_vel), hit)| {
+                    let filter = filter.map(|f| f as &dyn Fn(ColliderHandle, &Collider) -> bool);
+                    *hit = self.cast_shape(
+                        colliders,
+                        shape_pos,
+                        shape_vel,
+                        shape,
+                        max_toi,
+                        query_groups,
+                        filter,
+                    );
+                });
+        }
+    }
+
+    /// Casts a shape at a constant linear velocity against the colliders attached to a single rigid-body.
+    ///
+    /// This does not traverse the acceleration structure of this pipeline, and only tests the
+    /// colliders attached to the rigid-body `body`.
+    ///
+    /// If the rigid-body uses an aggregate proxy (see `RigidBody::use_aggregate_proxy`), only the
+    /// colliders which AABB is hit by the swept shape are tested, using the BVH of the aggregate.
+    ///
+    /// Returns `None` if `body` does not exist or if the shape doesn't hit any of its colliders.
+    ///
+    /// # Parameters
+    /// * `body` - The rigid-body which colliders are tested.
+    /// * `shape_pos` - The initial position of the shape to cast.
+    /// * `shape_vel` - The constant velocity of the shape to cast (i.e. the cast direction).
+    /// * `shape` - The shape to cast.
+    /// * `max_toi` - The maximum time-of-impact that can be reported by this cast. This effectively
+    ///               limits the distance traveled by the shape to `shapeVel.norm() * max_toi`.
+    pub fn cast_shape_against_body(
+        &self,
+        bodies: &RigidBodySet,
+        colliders: &ColliderSet,
+        body: RigidBodyHandle,
+        shape_pos: &Isometry<Real>,
+        shape_vel: &Vector<Real>,
+        shape: &dyn Shape,
+        max_toi: Real,
+    ) -> Option<(ColliderHandle, TOI)> {
+        let mut best: Option<(ColliderHandle, TOI)> = None;
+        let body_colliders = bodies.get(body)?.colliders();
+        let mut candidates = vec![];
+
+        // Only test the colliders which AABB is hit, if the body uses an aggregate proxy.
+        let handles = match colliders.up_to_date_aggregate(body, body_colliders) {
+            Some(aggregate) => {
+                let frame = Self::aggregate_frame(colliders, body_colliders);
+                let local_pos = frame.inv_mul(shape_pos);
+                let local_vel = frame.inverse_transform_vector(shape_vel) * max_toi;
+                let aabb = shape.compute_aabb(&local_pos);
+                let swept_aabb =
+                    aabb.merged(&AABB::new(aabb.mins + local_vel, aabb.maxs + local_vel));
+                let mut children = vec![];
+                aggregate.children_intersecting_local_aabb(&clamp_aabb(swept_aabb), &mut children);
+                candidates.extend(children.into_iter().map(|i| aggregate.children[i].0));
+                &candidates[..]
+            }
+            None => body_colliders,
+        };
+
+        for handle in handles {
+            let co = &colliders[*handle];
+            let max_toi = best.map(|(_, toi)| toi.toi).unwrap_or(max_toi);
+            let pos12 = co.position().inv_mul(shape_pos);
+            let vel12 = co.position().inverse_transform_vector(shape_vel);
+
+            if let Some(mut toi) = self
+                .query_dispatcher
+                .time_of_impact(&pos12, &vel12, co.shape(), shape, max_toi)
+                .unwrap_or(None)
+            {
+                // Express the witness and normal on the collider in world-space.
+                toi.witness1 = co.position() * toi.witness1;
+                toi.normal1 = co.position() * toi.normal1;
+                best = Some((*handle, toi));
+            }
+        }
+
+        best
+    }
+
+    /// The position of a rigid-body, deduced from its colliders, in which the BVH of its
+    /// aggregate is expressed.
+    fn aggregate_frame(
+        colliders: &ColliderSet,
+        body_colliders: &[ColliderHandle],
+    ) -> Isometry<Real> {
+        let co = &colliders[body_colliders[0]];
+        co.position() * co.position_wrt_parent().inverse()
+    }
+
     /// Casts a shape with an arbitrary continuous motion and retrieve the first collider it hits.
     ///
     /// # Parameters
@@ -611,3 +889,195 @@ impl QueryPipeline {
         self.quadtree.traverse_depth_first(&mut visitor);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::dynamics::{RigidBodyBuilder, RigidBodySet};
+    use crate::geometry::{ColliderBuilder, ColliderSet, Ray};
+    use crate::math::{Isometry, Point, Real, Vector};
+    use crate::pipeline::QueryPipeline;
+    use parry::shape::Ball;
+
+    fn ball_at(x: Real) -> ColliderBuilder {
+        #[cfg(feature = "dim2")]
+        return ColliderBuilder::ball(0.5).translation(x, 0.0);
+        #[cfg(feature = "dim3")]
+        return ColliderBuilder::ball(0.5).translation(x, 0.0, 0.0);
+    }
+
+    #[test]
+    fn casts_against_body_return_nearest_part() {
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let pipeline = QueryPipeline::new();
+
+        let body = bodies.insert(RigidBodyBuilder::new_dynamic().build());
+        let other = bodies.insert(RigidBodyBuilder::new_dynamic().build());
+
+        colliders.insert(ball_at(0.0).build(), body, &mut bodies);
+        let nearest = colliders.insert(ball_at(3.0).build(), body, &mut bodies);
+        colliders.insert(ball_at(-3.0).build(), body, &mut bodies);
+        // This one is in front of the body, but not attached to it.
+        colliders.insert(ball_at(6.0).build(), other, &mut bodies);
+
+        let mut origin = Point::origin();
+        origin.x = 10.0;
+        let dir = -Vector::x();
+        let ray = Ray::new(origin, dir);
+
+        let (handle, inter) = pipeline
+            .cast_ray_against_body(&bodies, &colliders, body, &ray, Real::MAX, true)
+            .unwrap();
+        assert_eq!(handle, nearest);
+        assert!((inter.toi - 6.5).abs() < 1.0e-5);
+
+        let shape_pos = Isometry::new(origin.coords, na::zero());
+        let (handle, toi) = pipeline
+            .cast_shape_against_body(
+                &bodies,
+                &colliders,
+                body,
+                &shape_pos,
+                &dir,
+                &Ball::new(0.5),
+                Real::MAX,
+            )
+            .unwrap();
+        assert_eq!(handle, nearest);
+        assert!((toi.toi - 6.0).abs() < 1.0e-5);
+
+        assert!(pipeline
+            .cast_ray_against_body(&bodies, &colliders, body, &ray, 5.0, true)
+            .is_none());
+    }
+
+    #[test]
+    fn batch_casts_match_individual_casts() {
+        use crate::geometry::{Collider, ColliderHandle, InteractionGroups};
+
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut pipeline = QueryPipeline::new();
+
+        let body = bodies.insert(RigidBodyBuilder::new_static().build());
+        for i in 0..10 {
+            colliders.insert(ball_at(i as Real * 2.0).build(), body, &mut bodies);
+        }
+        pipeline.update(&bodies, &colliders);
+
+        // Vertical rays, half of them passing between the balls.
+        let rays: Vec<_> = (0..40)
+            .map(|i| {
+                let mut origin = Point::origin();
+                origin.x = i as Real * 0.5;
+                origin.y = 10.0;
+                Ray::new(origin, -Vector::y())
+            })
+            .collect();
+        let groups = InteractionGroups::all();
+        let filter = |_: ColliderHandle, _: &Collider| true;
+
+        let mut hits = vec![None; 3];
+        pipeline.cast_rays(&colliders, &rays, 20.0, true, groups, None, &mut hits);
+        assert_eq!(hits.len(), rays.len());
+        assert!(hits.iter().any(|hit| hit.is_some()));
+        assert!(hits.iter().any(|hit| hit.is_none()));
+
+        for (ray, hit) in rays.iter().zip(hits.iter()) {
+            let expected = pipeline.cast_ray(&colliders, ray, 20.0, true, groups, None);
+            assert_eq!(*hit, expected);
+        }
+
+        let casts: Vec<_> = rays
+            .iter()
+            .map(|ray| (Isometry::new(ray.origin.coords, na::zero()), ray.dir))
+            .collect();
+        let shape = Ball::new(0.1);
+        let mut toi_hits = Vec::new();
+        pipeline.cast_shapes(
+            &colliders,
+            &casts,
+            &shape,
+            20.0,
+            groups,
+            Some(&filter),
+            &mut toi_hits,
+        );
+        assert_eq!(toi_hits.len(), casts.len());
+
+        for ((shape_pos, shape_vel), hit) in casts.iter().zip(toi_hits.iter()) {
+            let expected =
+                pipeline.cast_shape(&colliders, shape_pos, shape_vel, &shape, 20.0, groups, None);
+            assert_eq!(
+                hit.map(|h| (h.0, h.1.toi)),
+                expected.map(|h| (h.0, h.1.toi))
+            );
+        }
+    }
+
+    #[test]
+    fn casts_against_body_use_the_aggregate_bvh() {
+        use crate::geometry::BroadPhase;
+
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut broad_phase = BroadPhase::new();
+        let pipeline = QueryPipeline::new();
+
+        let body = bodies.insert(RigidBodyBuilder::new_dynamic().build());
+        bodies[body].use_aggregate_proxy(true);
+        for i in 0..10 {
+            colliders.insert(ball_at(i as Real * -2.0).build(), body, &mut bodies);
+        }
+
+        let update =
+            |bodies: &mut RigidBodySet, colliders: &mut ColliderSet, bf: &mut BroadPhase| {
+                bodies.handle_user_changes(colliders);
+                bf.update(0.0, colliders, &mut Vec::new());
+                colliders.clear_modified_colliders();
+            };
+        update(&mut bodies, &mut colliders, &mut broad_phase);
+        assert!(colliders
+            .up_to_date_aggregate(body, bodies[body].colliders())
+            .is_some());
+
+        let mut origin = Point::origin();
+        origin.x = 10.0;
+        let dir = -Vector::x();
+        let ray = Ray::new(origin, dir);
+        let shape_pos = Isometry::new(origin.coords, na::zero());
+        let cast_ray = |bodies: &RigidBodySet, colliders: &ColliderSet| {
+            pipeline
+                .cast_ray_against_body(bodies, colliders, body, &ray, Real::MAX, true)
+                .map(|(handle, inter)| (handle, inter.toi))
+        };
+        let cast_shape = |bodies: &RigidBodySet, colliders: &ColliderSet| {
+            let ball = Ball::new(0.5);
+            pipeline
+                .cast_shape_against_body(bodies, colliders, body, &shape_pos, &dir, &ball, 20.0)
+                .map(|(handle, toi)| (handle, toi.toi))
+        };
+
+        let (_, toi) = cast_ray(&bodies, &colliders).unwrap();
+        assert!((toi - 9.5).abs() < 1.0e-5);
+        let (_, toi) = cast_shape(&bodies, &colliders).unwrap();
+        assert!((toi - 9.0).abs() < 1.0e-5);
+
+        // A collider attached after the last broad-phase update isn't part of the BVH yet, but
+        // it is still found.
+        let nearest = colliders.insert(ball_at(3.0).build(), body, &mut bodies);
+        assert!(colliders
+            .up_to_date_aggregate(body, bodies[body].colliders())
+            .is_none());
+        assert_eq!(cast_ray(&bodies, &colliders).unwrap().0, nearest);
+
+        update(&mut bodies, &mut colliders, &mut broad_phase);
+        assert!(colliders
+            .up_to_date_aggregate(body, bodies[body].colliders())
+            .is_some());
+        let (handle, toi) = cast_ray(&bodies, &colliders).unwrap();
+        assert_eq!(handle, nearest);
+        assert!((toi - 6.5).abs() < 1.0e-5);
+        assert_eq!(cast_shape(&bodies, &colliders).unwrap().0, nearest);
+    }
+}
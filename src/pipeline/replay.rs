@@ -0,0 +1,918 @@
+use crate::dynamics::{
+    BodyStatus, CCDSolver, IntegrationParameters, JointSet, MassProperties, ParamValue,
+    PositionSolveBudget, RestitutionModel, RigidBody, RigidBodyArenaChange, RigidBodyBuilder,
+    RigidBodyChanges, RigidBodyFlags, RigidBodyHandle, RigidBodySet, UserConstraintSet,
+};
+use crate::geometry::{BroadPhase, ColliderSet, NarrowPhase};
+use crate::math::{AngVector, AngularInertia, Isometry, Point, Real, Vector, DIM};
+use crate::pipeline::{OnLimitExceeded, OnNonFinite, PhysicsPipeline, PipelineLimits};
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use core::fmt;
+use core::time::Duration;
+
+const MAGIC: &[u8; 4] = b"RPLY";
+const VERSION: u8 = 2;
+
+// 64-bits FNV-1a, like `RigidBodySet::hash_state`.
+const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// Folds the hash of the state of the rigid-bodies after a timestep into the rolling checksum of
+/// all the previous timesteps.
+fn roll_checksum(checksum: u64, state_hash: u64) -> u64 {
+    state_hash
+        .to_le_bytes()
+        .iter()
+        .fold(checksum, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(FNV_PRIME)
+        })
+}
+
+/// Records the changes made by the user between the timesteps of a `PhysicsPipeline`, together
+/// with a rolling checksum of the state of the rigid-bodies after each timestep.
+///
+/// Call `self.record_changes` right before each `PhysicsPipeline::step` to record, and
+/// `self.record_checksum` right after it. The recorded log, obtained with `self.save()`, can then be replayed by a `Replayer` from a `ReplaySnapshot` of
+/// the simulation taken right before the first recorded timestep. This makes it possible to
+/// reproduce a run which desynchronized, and to find the first timestep at which it diverged.
+///
+/// The following changes are recorded:
+/// - The gravity and the integration parameters passed to `PhysicsPipeline::step`.
+/// - The insertion and removal of rigid-bodies, in the order they happened.
+/// - The position, velocities, forces, sleep state, status, locked axes, damping, gravity scale,
+///   and mass properties of the rigid-bodies modified by the user, e.g., through
+///   `RigidBodySet::get_mut`. This includes the impulses applied to them.
+///
+/// The colliders and joints inserted, removed, or modified after the snapshot are not recorded,
+/// so the rigid-bodies inserted during the recording are replayed without any collider. Only the
+/// timesteps executed with `PhysicsPipeline::step` can be recorded, and the replay uses a new
+/// pipeline without any physics hooks nor user-defined constraints.
+#[derive(Clone, Debug)]
+pub struct ReplayRecorder {
+    log: Vec<u8>,
+    checksums: Vec<u64>,
+    gravity: Option<Vector<Real>>,
+    params: Vec<(&'static str, ParamValue)>,
+}
+
+impl Default for ReplayRecorder {
+    fn default() -> Self {
+        ReplayRecorder::new()
+    }
+}
+
+impl ReplayRecorder {
+    /// Creates a new recorder which didn't record any timestep yet.
+    pub fn new() -> Self {
+        // The replay starts with the default parameters, so only the other ones are recorded.
+        let params = IntegrationParameters::default()
+            .describe()
+            .into_iter()
+            .map(|descriptor| (descriptor.name, descriptor.value))
+            .collect();
+
+        Self {
+            log: Vec::new(),
+            checksums: Vec::new(),
+            gravity: None,
+            params,
+        }
+    }
+
+    /// The number of timesteps recorded so far.
+    pub fn num_steps(&self) -> usize {
+        self.checksums.len()
+    }
+
+    /// The rolling checksum of the state of the rigid-bodies after each recorded timestep.
+    ///
+    /// The checksum of a timestep depends on the states of the rigid-bodies after this timestep,
+    /// as given by `RigidBodySet::hash_state`, and after all the previous recorded timesteps.
+    pub fn checksums(&self) -> &[u64] {
+        &self.checksums
+    }
+
+    /// The recorded log, which can be replayed with a `Replayer`.
+    ///
+    /// The log can only be replayed by a version of Rapier with the same dimension and
+    /// floating-point precision.
+    pub fn save(&self) -> Vec<u8> {
+        let mut result = Vec::with_capacity(MAGIC.len() + 3 + self.log.len());
+        result.extend_from_slice(MAGIC);
+        result.push(VERSION);
+        result.push(DIM as u8);
+        result.push(core::mem::size_of::<Real>() as u8);
+        result.extend_from_slice(&self.log);
+        result
+    }
+
+    /// Records the changes made since the last recorded timestep.
+    ///
+    /// This must be called right before `PhysicsPipeline::step`, with the gravity and the
+    /// integration parameters passed to it.
+    pub fn record_changes(
+        &mut self,
+        gravity: &Vector<Real>,
+        integration_parameters: &IntegrationParameters,
+        bodies: &mut RigidBodySet,
+    ) {
+        bodies.merge_modified_shards();
+
+        let params: Vec<_> = integration_parameters
+            .describe()
+            .into_iter()
+            .map(|descriptor| (descriptor.name, descriptor.value))
+            .collect();
+        let changed: Vec<_> = params
+            .iter()
+            .filter(|param| !self.params.contains(param))
+            .collect();
+
+        let mut out = LogWriter(&mut self.log);
+
+        if self.gravity != Some(*gravity) {
+            self.gravity = Some(*gravity);
+            out.u8(1);
+            out.vector(gravity);
+        } else {
+            out.u8(0);
+        }
+
+        out.u32(changed.len() as u32);
+        for (name, value) in changed {
+            out.u8(name.len() as u8);
+            out.0.extend_from_slice(name.as_bytes());
+            out.param_value(value);
+        }
+        self.params = params;
+
+        out.u32(bodies.arena_changes.len() as u32);
+        for change in &bodies.arena_changes {
+            match change {
+                RigidBodyArenaChange::Inserted(handle) => {
+                    out.u8(0);
+                    out.handle(*handle);
+                }
+                RigidBodyArenaChange::Removed(handle) => {
+                    out.u8(1);
+                    out.handle(*handle);
+                }
+            }
+        }
+
+        // NOTE: the order of the modified bodies is recorded because it determines the
+        //       order of the active sets, hence the order of the constraints.
+        out.u8(bodies.modified_all_bodies as u8);
+        out.u32(bodies.modified_bodies.len() as u32);
+        for handle in &bodies.modified_bodies {
+            out.handle(*handle);
+        }
+
+        if bodies.modified_all_bodies {
+            out.u32(bodies.len() as u32);
+            for (handle, rb) in bodies.iter() {
+                out.handle(handle);
+                out.body(rb);
+            }
+        } else {
+            let modified: Vec<_> = bodies
+                .modified_bodies
+                .iter()
+                .filter_map(|handle| Some((*handle, bodies.get(*handle)?)))
+                .collect();
+            out.u32(modified.len() as u32);
+            for (handle, rb) in modified {
+                out.handle(handle);
+                out.body(rb);
+            }
+        }
+    }
+
+    /// Records the checksum of the state of the rigid-bodies at the end of a timestep.
+    ///
+    /// This must be called right after the `PhysicsPipeline::step` following
+    /// `Self::record_changes`.
+    pub fn record_checksum(&mut self, bodies: &RigidBodySet) {
+        let previous = self.checksums.last().copied().unwrap_or(FNV_OFFSET);
+        let checksum = roll_checksum(previous, bodies.hash_state());
+        LogWriter(&mut self.log).u64(checksum);
+        self.checksums.push(checksum);
+    }
+}
+
+/// The state of a simulation from which a log recorded by a `ReplayRecorder` can be replayed.
+#[derive(Clone)]
+pub struct ReplaySnapshot {
+    /// The broad-phase of the simulation.
+    pub broad_phase: BroadPhase,
+    /// The narrow-phase of the simulation.
+    pub narrow_phase: NarrowPhase,
+    /// The rigid-bodies of the simulation.
+    pub bodies: RigidBodySet,
+    /// The colliders of the simulation.
+    pub colliders: ColliderSet,
+    /// The joints of the simulation.
+    pub joints: JointSet,
+    /// The CCD solver of the simulation.
+    pub ccd_solver: CCDSolver,
+}
+
+impl ReplaySnapshot {
+    /// Copies the state of a simulation.
+    ///
+    /// This must be called right before the first timestep recorded by the `ReplayRecorder`,
+    /// and before the first timestep of its pipeline because the replay uses a new pipeline.
+    pub fn new(
+        broad_phase: &BroadPhase,
+        narrow_phase: &NarrowPhase,
+        bodies: &RigidBodySet,
+        colliders: &ColliderSet,
+        joints: &JointSet,
+        ccd_solver: &CCDSolver,
+    ) -> Self {
+        Self {
+            broad_phase: broad_phase.clone(),
+            narrow_phase: narrow_phase.clone(),
+            bodies: bodies.clone(),
+            colliders: colliders.clone(),
+            joints: joints.clone(),
+            ccd_solver: ccd_solver.clone(),
+        }
+    }
+}
+
+/// An error returned by a `Replayer`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ReplayError {
+    /// The log is truncated or corrupted, or was recorded with a different dimension or
+    /// floating-point precision.
+    InvalidLog,
+    /// A rigid-body inserted or removed by the replay doesn't have the handle it had during
+    /// the recording, e.g., because the snapshot wasn't taken right before the recording.
+    UnexpectedHandle {
+        /// The index of the replayed timestep.
+        step: usize,
+        /// The handle of the rigid-body during the recording.
+        handle: RigidBodyHandle,
+    },
+    /// The state of the rigid-bodies after a replayed timestep doesn't match the recording.
+    Diverged {
+        /// The index of the first replayed timestep which diverged.
+        step: usize,
+        /// The checksum recorded for this timestep.
+        expected: u64,
+        /// The checksum obtained by the replay.
+        found: u64,
+    },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::InvalidLog => write!(f, "the replay log is invalid"),
+            ReplayError::UnexpectedHandle { step, handle } => write!(
+                f,
+                "the rigid-body {:?} couldn't be replayed with the same handle at the timestep {}",
+                handle, step
+            ),
+            ReplayError::Diverged {
+                step,
+                expected,
+                found,
+            } => write!(
+                f,
+                "the replay diverged at the timestep {}: expected the checksum {:#x}, found {:#x}",
+                step, expected, found
+            ),
+        }
+    }
+}
+
+#[cfg(not(feature = "no_std"))]
+impl std::error::Error for ReplayError {}
+
+/// Replays a log recorded by a `ReplayRecorder` from a `ReplaySnapshot`.
+///
+/// Each replayed timestep applies the recorded changes to the simulation, runs the timestep, and
+/// checks that the checksum of the state of the rigid-bodies matches the recorded one.
+pub struct Replayer {
+    pipeline: PhysicsPipeline,
+    world: ReplaySnapshot,
+    gravity: Vector<Real>,
+    integration_parameters: IntegrationParameters,
+    log: Vec<u8>,
+    cursor: usize,
+    checksum: u64,
+    num_steps: usize,
+}
+
+impl Replayer {
+    /// Prepares the replay of `log` from `snapshot`.
+    pub fn new(snapshot: ReplaySnapshot, log: &[u8]) -> Result<Self, ReplayError> {
+        let header = [VERSION, DIM as u8, core::mem::size_of::<Real>() as u8];
+
+        if log.get(..MAGIC.len()) != Some(&MAGIC[..])
+            || log.get(MAGIC.len()..MAGIC.len() + header.len()) != Some(&header[..])
+        {
+            return Err(ReplayError::InvalidLog);
+        }
+
+        Ok(Self {
+            pipeline: PhysicsPipeline::new(),
+            world: snapshot,
+            gravity: Vector::zeros(),
+            integration_parameters: IntegrationParameters::default(),
+            log: log.to_vec(),
+            cursor: MAGIC.len() + header.len(),
+            checksum: FNV_OFFSET,
+            num_steps: 0,
+        })
+    }
+
+    /// Replays `num_steps` timesteps of `log` from `snapshot`, and returns the checksum of the
+    /// last one.
+    ///
+    /// The replay stops early if the log contains less timesteps, and returns the checksum of
+    /// the snapshot, i.e., the initial rolling checksum, if no timestep is replayed.
+    pub fn run(snapshot: ReplaySnapshot, log: &[u8], num_steps: usize) -> Result<u64, ReplayError> {
+        let mut replayer = Self::new(snapshot, log)?;
+
+        for _ in 0..num_steps {
+            if replayer.step()?.is_none() {
+                break;
+            }
+        }
+
+        Ok(replayer.checksum)
+    }
+
+    /// The current state of the replayed simulation.
+    pub fn world(&self) -> &ReplaySnapshot {
+        &self.world
+    }
+
+    /// The number of timesteps replayed so far.
+    pub fn num_steps(&self) -> usize {
+        self.num_steps
+    }
+
+    /// Replays the next recorded timestep, and returns its checksum.
+    ///
+    /// Returns `Ok(None)` if all the recorded timesteps were replayed.
+    pub fn step(&mut self) -> Result<Option<u64>, ReplayError> {
+        if self.cursor == self.log.len() {
+            return Ok(None);
+        }
+
+        let mut log = LogReader {
+            bytes: &self.log,
+            cursor: self.cursor,
+        };
+        let step = self.num_steps;
+        let world = &mut self.world;
+
+        if log.u8()? != 0 {
+            self.gravity = log.vector()?;
+        }
+
+        for _ in 0..log.u32()? {
+            let name_len = log.u8()? as usize;
+            let name =
+                core::str::from_utf8(log.bytes(name_len)?).map_err(|_| ReplayError::InvalidLog)?;
+            let value = log.param_value()?;
+            self.integration_parameters
+                .set_by_name(name, value)
+                .map_err(|_| ReplayError::InvalidLog)?;
+        }
+
+        let mut arena_changes = Vec::new();
+        for _ in 0..log.u32()? {
+            let change = match log.u8()? {
+                0 => RigidBodyArenaChange::Inserted(log.handle()?),
+                1 => RigidBodyArenaChange::Removed(log.handle()?),
+                _ => return Err(ReplayError::InvalidLog),
+            };
+            arena_changes.push(change);
+        }
+
+        let modified_all_bodies = log.u8()? != 0;
+        let mut modified_bodies = Vec::new();
+        for _ in 0..log.u32()? {
+            modified_bodies.push(log.handle()?);
+        }
+
+        let mut states = Vec::new();
+        for _ in 0..log.u32()? {
+            states.push((log.handle()?, log.body()?));
+        }
+
+        for change in arena_changes {
+            match change {
+                RigidBodyArenaChange::Inserted(handle) => {
+                    // The bodies inserted before the snapshot, but after the last timestep,
+                    // are already part of the replayed world.
+                    if world.bodies.contains(handle) {
+                        continue;
+                    }
+
+                    // A body removed before the end of the timestep it was inserted at wasn't
+                    // recorded, but it still needs to take its slot in the set.
+                    let mut rb = RigidBodyBuilder::new_static().build();
+                    if let Some((_, state)) = states.iter().find(|(h, _)| *h == handle) {
+                        state.apply(&mut rb);
+                    }
+
+                    if world.bodies.insert(rb) != handle {
+                        return Err(ReplayError::UnexpectedHandle { step, handle });
+                    }
+                }
+                RigidBodyArenaChange::Removed(handle) => {
+                    if !world.bodies.contains(handle) {
+                        return Err(ReplayError::UnexpectedHandle { step, handle });
+                    }
+
+                    world
+                        .bodies
+                        .remove(handle, &mut world.colliders, &mut world.joints);
+                }
+            }
+        }
+
+        world.bodies.modified_bodies = modified_bodies;
+        world.bodies.modified_all_bodies = modified_all_bodies;
+
+        for (handle, state) in &states {
+            match world.bodies.bodies.get_mut(handle.0) {
+                Some(rb) => state.apply(rb),
+                None => {
+                    let handle = *handle;
+                    return Err(ReplayError::UnexpectedHandle { step, handle });
+                }
+            }
+        }
+
+        self.pipeline.step(
+            &self.gravity,
+            &self.integration_parameters,
+            &mut world.broad_phase,
+            &mut world.narrow_phase,
+            &mut world.bodies,
+            &mut world.colliders,
+            &mut world.joints,
+            &mut UserConstraintSet::new(),
+            &mut world.ccd_solver,
+            &(),
+            &(),
+        );
+
+        let expected = log.u64()?;
+        self.cursor = log.cursor;
+        self.checksum = roll_checksum(self.checksum, world.bodies.hash_state());
+        self.num_steps += 1;
+
+        if self.checksum != expected {
+            return Err(ReplayError::Diverged {
+                step,
+                expected,
+                found: self.checksum,
+            });
+        }
+
+        Ok(Some(self.checksum))
+    }
+}
+
+/// The recorded state of a rigid-body.
+struct BodyState {
+    body_status: BodyStatus,
+    flags: u16,
+    changes: u32,
+    position: Isometry<Real>,
+    next_position: Isometry<Real>,
+    prev_position: Isometry<Real>,
+    kinematic_target: Option<(Isometry<Real>, Real)>,
+    kinematic_linvel: Vector<Real>,
+    kinematic_angvel: AngVector<Real>,
+    next_kinematic_position_set: bool,
+    linvel: Vector<Real>,
+    angvel: AngVector<Real>,
+    last_kinematic_linvel: Vector<Real>,
+    last_kinematic_angvel: AngVector<Real>,
+    force: Vector<Real>,
+    torque: AngVector<Real>,
+    gravity_scale: Real,
+    linear_damping: Real,
+    angular_damping: Real,
+    linear_activation_threshold: Real,
+    angular_activation_threshold: Real,
+    linear_activation_energy: Real,
+    angular_activation_energy: Real,
+    sleeping: bool,
+    mass_properties: MassProperties,
+    additional_mass_properties: MassProperties,
+    world_com: Point<Real>,
+    effective_inv_mass: Vector<Real>,
+    effective_world_inv_inertia_sqrt: AngularInertia<Real>,
+}
+
+impl BodyState {
+    fn apply(&self, rb: &mut RigidBody) {
+        rb.body_status = self.body_status;
+        rb.flags = RigidBodyFlags::from_bits_truncate(self.flags);
+        rb.changes = RigidBodyChanges::from_bits_truncate(self.changes);
+        rb.position = self.position;
+        rb.next_position = self.next_position;
+        rb.prev_position = self.prev_position;
+        rb.kinematic_target = self.kinematic_target;
+        rb.kinematic_linvel = self.kinematic_linvel;
+        rb.kinematic_angvel = self.kinematic_angvel;
+        rb.next_kinematic_position_set = self.next_kinematic_position_set;
+        rb.linvel = self.linvel;
+        rb.angvel = self.angvel;
+        rb.last_kinematic_linvel = self.last_kinematic_linvel;
+        rb.last_kinematic_angvel = self.last_kinematic_angvel;
+        rb.force = self.force;
+        rb.torque = self.torque;
+        rb.gravity_scale = self.gravity_scale;
+        rb.linear_damping = self.linear_damping;
+        rb.angular_damping = self.angular_damping;
+        rb.activation.linear_threshold = self.linear_activation_threshold;
+        rb.activation.angular_threshold = self.angular_activation_threshold;
+        rb.activation.linear_energy = self.linear_activation_energy;
+        rb.activation.angular_energy = self.angular_activation_energy;
+        rb.activation.sleeping = self.sleeping;
+        rb.mass_properties = self.mass_properties;
+        rb.additional_mass_properties = self.additional_mass_properties;
+        rb.world_com = self.world_com;
+        rb.effective_inv_mass = self.effective_inv_mass;
+        rb.effective_world_inv_inertia_sqrt = self.effective_world_inv_inertia_sqrt;
+    }
+}
+
+struct LogWriter<'a>(&'a mut Vec<u8>);
+
+impl<'a> LogWriter<'a> {
+    fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn real(&mut self, value: Real) {
+        self.0.extend_from_slice(&value.to_bits().to_le_bytes());
+    }
+
+    fn reals(&mut self, values: &[Real]) {
+        for value in values {
+            self.real(*value);
+        }
+    }
+
+    fn vector(&mut self, value: &Vector<Real>) {
+        self.reals(value.as_slice());
+    }
+
+    #[cfg(feature = "dim2")]
+    fn ang_vector(&mut self, value: &AngVector<Real>) {
+        self.real(*value);
+    }
+
+    #[cfg(feature = "dim3")]
+    fn ang_vector(&mut self, value: &AngVector<Real>) {
+        self.vector(value);
+    }
+
+    #[cfg(feature = "dim2")]
+    fn angular_inertia(&mut self, value: &AngularInertia<Real>) {
+        self.real(*value);
+    }
+
+    #[cfg(feature = "dim3")]
+    fn angular_inertia(&mut self, value: &AngularInertia<Real>) {
+        self.reals(&[
+            value.m11, value.m12, value.m13, value.m22, value.m23, value.m33,
+        ]);
+    }
+
+    fn isometry(&mut self, value: &Isometry<Real>) {
+        self.vector(&value.translation.vector);
+        #[cfg(feature = "dim2")]
+        self.reals(&[value.rotation.re, value.rotation.im]);
+        #[cfg(feature = "dim3")]
+        self.reals(value.rotation.coords.as_slice());
+    }
+
+    fn mass_properties(&mut self, props: &MassProperties) {
+        self.vector(&props.local_com.coords);
+        self.real(props.inv_mass);
+        self.ang_vector(&props.inv_principal_inertia_sqrt);
+        #[cfg(feature = "dim3")]
+        self.reals(props.principal_inertia_local_frame.coords.as_slice());
+    }
+
+    fn handle(&mut self, handle: RigidBodyHandle) {
+        let (id, generation) = handle.into_raw_parts();
+        self.u64(id as u64);
+        self.u64(generation);
+    }
+
+    fn param_value(&mut self, value: &ParamValue) {
+        match value {
+            ParamValue::Real(value) => {
+                self.u8(0);
+                self.real(*value);
+            }
+            ParamValue::Integer(value) => {
+                self.u8(1);
+                self.u64(*value as u64);
+            }
+            ParamValue::OptionalInteger(None) => self.u8(2),
+            ParamValue::OptionalInteger(Some(value)) => {
+                self.u8(3);
+                self.u64(*value as u64);
+            }
+            ParamValue::Bool(value) => {
+                self.u8(4);
+                self.u8(*value as u8);
+            }
+            ParamValue::RestitutionModel(RestitutionModel::Rhs) => self.u8(5),
+            ParamValue::RestitutionModel(RestitutionModel::PostSolve) => self.u8(6),
+            ParamValue::PositionSolveBudget(None) => self.u8(7),
+            ParamValue::PositionSolveBudget(Some(PositionSolveBudget::ConstraintSolves(value))) => {
+                self.u8(8);
+                self.u64(*value as u64);
+            }
+            ParamValue::PositionSolveBudget(Some(PositionSolveBudget::Duration(value))) => {
+                self.u8(9);
+                self.u64(value.as_secs());
+                self.u32(value.subsec_nanos());
+            }
+            ParamValue::OptionalReal(None) => self.u8(10),
+            ParamValue::OptionalReal(Some(value)) => {
+                self.u8(11);
+                self.real(*value);
+            }
+            ParamValue::OnNonFinite(OnNonFinite::Panic) => self.u8(12),
+            ParamValue::OnNonFinite(OnNonFinite::FreezeBodyAndReport) => self.u8(13),
+            ParamValue::OnNonFinite(OnNonFinite::ResetVelocityAndReport) => self.u8(14),
+            ParamValue::PipelineLimits(limits) => {
+                self.u8(15);
+                self.u64(limits.max_contact_pairs as u64);
+                self.u64(limits.max_manifold_points_total as u64);
+                self.u64(limits.max_bodies_per_island as u64);
+                self.u8((limits.on_limit_exceeded == OnLimitExceeded::Panic) as u8);
+            }
+        }
+    }
+
+    fn body(&mut self, rb: &RigidBody) {
+        self.u8(match rb.body_status {
+            BodyStatus::Dynamic => 0,
+            BodyStatus::Static => 1,
+            BodyStatus::Kinematic => 2,
+        });
+        self.0.extend_from_slice(&rb.flags.bits().to_le_bytes());
+        self.u32(rb.changes.bits());
+        self.isometry(&rb.position);
+        self.isometry(&rb.next_position);
+        self.isometry(&rb.prev_position);
+
+        if let Some((target, remaining)) = &rb.kinematic_target {
+            self.u8(1);
+            self.isometry(target);
+            self.real(*remaining);
+        } else {
+            self.u8(0);
+        }
+
+        self.vector(&rb.kinematic_linvel);
+        self.ang_vector(&rb.kinematic_angvel);
+        self.u8(rb.next_kinematic_position_set as u8);
+        self.vector(&rb.linvel);
+        self.ang_vector(&rb.angvel);
+        self.vector(&rb.last_kinematic_linvel);
+        self.ang_vector(&rb.last_kinematic_angvel);
+        self.vector(&rb.force);
+        self.ang_vector(&rb.torque);
+        self.reals(&[rb.gravity_scale, rb.linear_damping, rb.angular_damping]);
+        self.reals(&[
+            rb.activation.linear_threshold,
+            rb.activation.angular_threshold,
+            rb.activation.linear_energy,
+            rb.activation.angular_energy,
+        ]);
+        self.u8(rb.activation.sleeping as u8);
+
+        self.mass_properties(&rb.mass_properties);
+        self.mass_properties(&rb.additional_mass_properties);
+        self.vector(&rb.world_com.coords);
+        self.vector(&rb.effective_inv_mass);
+        self.angular_inertia(&rb.effective_world_inv_inertia_sqrt);
+    }
+}
+
+struct LogReader<'a> {
+    bytes: &'a [u8],
+    cursor: usize,
+}
+
+impl<'a> LogReader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], ReplayError> {
+        let result = self
+            .bytes
+            .get(self.cursor..self.cursor + len)
+            .ok_or(ReplayError::InvalidLog)?;
+        self.cursor += len;
+        Ok(result)
+    }
+
+    fn u8(&mut self) -> Result<u8, ReplayError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, ReplayError> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32, ReplayError> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, ReplayError> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    fn bool(&mut self) -> Result<bool, ReplayError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(ReplayError::InvalidLog),
+        }
+    }
+
+    fn real(&mut self) -> Result<Real, ReplayError> {
+        let bytes = self.bytes(core::mem::size_of::<Real>())?;
+        Ok(Real::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn vector(&mut self) -> Result<Vector<Real>, ReplayError> {
+        let mut result = Vector::zeros();
+        for coord in result.iter_mut() {
+            *coord = self.real()?;
+        }
+        Ok(result)
+    }
+
+    #[cfg(feature = "dim2")]
+    fn ang_vector(&mut self) -> Result<AngVector<Real>, ReplayError> {
+        self.real()
+    }
+
+    #[cfg(feature = "dim3")]
+    fn ang_vector(&mut self) -> Result<AngVector<Real>, ReplayError> {
+        self.vector()
+    }
+
+    #[cfg(feature = "dim2")]
+    fn angular_inertia(&mut self) -> Result<AngularInertia<Real>, ReplayError> {
+        self.real()
+    }
+
+    #[cfg(feature = "dim3")]
+    fn angular_inertia(&mut self) -> Result<AngularInertia<Real>, ReplayError> {
+        let mut m = [0.0; 6];
+        for value in &mut m {
+            *value = self.real()?;
+        }
+        Ok(AngularInertia::new(m[0], m[1], m[2], m[3], m[4], m[5]))
+    }
+
+    fn isometry(&mut self) -> Result<Isometry<Real>, ReplayError> {
+        let translation = self.vector()?;
+        #[cfg(feature = "dim2")]
+        let rotation = {
+            let (re, im) = (self.real()?, self.real()?);
+            na::UnitComplex::new_unchecked(na::Complex::new(re, im))
+        };
+        #[cfg(feature = "dim3")]
+        let rotation = self.unit_quaternion()?;
+        Ok(Isometry::from_parts(translation.into(), rotation))
+    }
+
+    #[cfg(feature = "dim3")]
+    fn unit_quaternion(&mut self) -> Result<na::UnitQuaternion<Real>, ReplayError> {
+        let (i, j, k, w) = (self.real()?, self.real()?, self.real()?, self.real()?);
+        Ok(na::UnitQuaternion::new_unchecked(na::Quaternion::new(
+            w, i, j, k,
+        )))
+    }
+
+    fn mass_properties(&mut self) -> Result<MassProperties, ReplayError> {
+        Ok(MassProperties {
+            local_com: Point::from(self.vector()?),
+            inv_mass: self.real()?,
+            inv_principal_inertia_sqrt: self.ang_vector()?,
+            #[cfg(feature = "dim3")]
+            principal_inertia_local_frame: self.unit_quaternion()?,
+        })
+    }
+
+    fn handle(&mut self) -> Result<RigidBodyHandle, ReplayError> {
+        let id = self.u64()? as usize;
+        let generation = self.u64()?;
+        Ok(RigidBodyHandle::from_raw_parts(id, generation))
+    }
+
+    fn param_value(&mut self) -> Result<ParamValue, ReplayError> {
+        match self.u8()? {
+            0 => Ok(ParamValue::Real(self.real()?)),
+            1 => Ok(ParamValue::Integer(self.u64()? as usize)),
+            2 => Ok(ParamValue::OptionalInteger(None)),
+            3 => Ok(ParamValue::OptionalInteger(Some(self.u64()? as usize))),
+            4 => Ok(ParamValue::Bool(self.bool()?)),
+            5 => Ok(ParamValue::RestitutionModel(RestitutionModel::Rhs)),
+            6 => Ok(ParamValue::RestitutionModel(RestitutionModel::PostSolve)),
+            7 => Ok(ParamValue::PositionSolveBudget(None)),
+            8 => Ok(ParamValue::PositionSolveBudget(Some(
+                PositionSolveBudget::ConstraintSolves(self.u64()? as usize),
+            ))),
+            9 => Ok(ParamValue::PositionSolveBudget(Some(
+                PositionSolveBudget::Duration(Duration::new(self.u64()?, self.u32()?)),
+            ))),
+            10 => Ok(ParamValue::OptionalReal(None)),
+            11 => Ok(ParamValue::OptionalReal(Some(self.real()?))),
+            12 => Ok(ParamValue::OnNonFinite(OnNonFinite::Panic)),
+            13 => Ok(ParamValue::OnNonFinite(OnNonFinite::FreezeBodyAndReport)),
+            14 => Ok(ParamValue::OnNonFinite(OnNonFinite::ResetVelocityAndReport)),
+            15 => Ok(ParamValue::PipelineLimits(PipelineLimits {
+                max_contact_pairs: self.u64()? as usize,
+                max_manifold_points_total: self.u64()? as usize,
+                max_bodies_per_island: self.u64()? as usize,
+                on_limit_exceeded: if self.bool()? {
+                    OnLimitExceeded::Panic
+                } else {
+                    OnLimitExceeded::DropExcessAndReport
+                },
+            })),
+            _ => Err(ReplayError::InvalidLog),
+        }
+    }
+
+    fn body(&mut self) -> Result<BodyState, ReplayError> {
+        let body_status = match self.u8()? {
+            0 => BodyStatus::Dynamic,
+            1 => BodyStatus::Static,
+            2 => BodyStatus::Kinematic,
+            _ => return Err(ReplayError::InvalidLog),
+        };
+        let flags = self.u16()?;
+        let changes = self.u32()?;
+        let position = self.isometry()?;
+        let next_position = self.isometry()?;
+        let prev_position = self.isometry()?;
+        let kinematic_target = if self.bool()? {
+            Some((self.isometry()?, self.real()?))
+        } else {
+            None
+        };
+
+        Ok(BodyState {
+            body_status,
+            flags,
+            changes,
+            position,
+            next_position,
+            prev_position,
+            kinematic_target,
+            kinematic_linvel: self.vector()?,
+            kinematic_angvel: self.ang_vector()?,
+            next_kinematic_position_set: self.bool()?,
+            linvel: self.vector()?,
+            angvel: self.ang_vector()?,
+            last_kinematic_linvel: self.vector()?,
+            last_kinematic_angvel: self.ang_vector()?,
+            force: self.vector()?,
+            torque: self.ang_vector()?,
+            gravity_scale: self.real()?,
+            linear_damping: self.real()?,
+            angular_damping: self.real()?,
+            linear_activation_threshold: self.real()?,
+            angular_activation_threshold: self.real()?,
+            linear_activation_energy: self.real()?,
+            angular_activation_energy: self.real()?,
+            sleeping: self.bool()?,
+            mass_properties: self.mass_properties()?,
+            additional_mass_properties: self.mass_properties()?,
+            world_com: Point::from(self.vector()?),
+            effective_inv_mass: self.vector()?,
+            effective_world_inv_inertia_sqrt: self.angular_inertia()?,
+        })
+    }
+}
@@ -0,0 +1,435 @@
+use crate::dynamics::{
+    JointHandle, JointParams, JointSet, RigidBody, RigidBodyHandle, RigidBodySet,
+};
+use crate::geometry::{ColliderHandle, ColliderSet, NarrowPhase};
+use crate::math::{Isometry, Real, DIM};
+use alloc::vec::Vec;
+use core::convert::TryInto;
+use parry::utils::IsometryOpt;
+
+// The number of values of a linear velocity followed by an angular velocity.
+#[cfg(feature = "dim2")]
+const SPATIAL_DIM: usize = 3;
+#[cfg(feature = "dim3")]
+const SPATIAL_DIM: usize = 6;
+
+/// A snapshot of the state of a simulation, stored as flat arrays.
+///
+/// All the arrays of the same section (bodies, contacts, or joints) describe the same elements in
+/// the same order: the `i`-th body has the handle `body_handles[i]`, and its pose is stored in
+/// `body_poses[i * SimulationFrame::POSE_DIM..(i + 1) * SimulationFrame::POSE_DIM]`. The elements
+/// are sorted by handle, so two frames of the same simulation can be compared element-wise.
+///
+/// This is filled by `PhysicsPipeline::export_state`. The same frame can be reused for every
+/// export, in which case no allocation happens once its arrays are large enough.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SimulationFrame {
+    /// The handles of the exported rigid-bodies.
+    pub body_handles: Vec<RigidBodyHandle>,
+    /// The world-space poses of the rigid-bodies, `POSE_DIM` values per body.
+    ///
+    /// In 2D, each pose is `[x, y, angle]`. In 3D, each pose is `[x, y, z, i, j, k, w]`, i.e., the
+    /// translation followed by the coordinates of the rotation quaternion.
+    pub body_poses: Vec<Real>,
+    /// The velocities of the rigid-bodies, `VELOCITY_DIM` values per body.
+    ///
+    /// Each velocity is the linear velocity followed by the angular velocity.
+    pub body_velocities: Vec<Real>,
+    /// The masses of the rigid-bodies, one value per body.
+    pub body_masses: Vec<Real>,
+    /// The two colliders involved in each exported contact point.
+    pub contact_colliders: Vec<(ColliderHandle, ColliderHandle)>,
+    /// The world-space location of each contact point on the first collider, `DIM` values per contact.
+    pub contact_points: Vec<Real>,
+    /// The world-space contact normal of each contact point, pointing outward of the first
+    /// collider, `DIM` values per contact.
+    pub contact_normals: Vec<Real>,
+    /// The impulses applied by each contact point during the last timestep, `CONTACT_IMPULSE_DIM`
+    /// values per contact.
+    ///
+    /// Each impulse is the impulse along the contact normal followed by the friction impulses.
+    pub contact_impulses: Vec<Real>,
+    /// The handles of the exported joints.
+    pub joint_handles: Vec<JointHandle>,
+    /// The two rigid-bodies attached to each joint.
+    pub joint_bodies: Vec<(RigidBodyHandle, RigidBodyHandle)>,
+    /// The type of each joint, as given by `JointParams::type_id`.
+    pub joint_types: Vec<usize>,
+    /// The pose of the second rigid-body relative to the first one, for each joint, `POSE_DIM`
+    /// values per joint.
+    pub joint_states: Vec<Real>,
+    /// The impulses applied by each joint during the last timestep, `JOINT_IMPULSE_DIM` values
+    /// per joint.
+    ///
+    /// The impulses of joints with less than `JOINT_IMPULSE_DIM` constrained degrees of freedom
+    /// are padded with zeros.
+    pub joint_impulses: Vec<Real>,
+    // Pairs of colliders in contact, reused to sort the contacts without allocating.
+    // This is always empty outside of `SimulationFrame::fill`.
+    sorted_pairs: Vec<(ColliderHandle, ColliderHandle)>,
+}
+
+impl SimulationFrame {
+    /// The number of values describing the pose of a rigid-body.
+    #[cfg(feature = "dim2")]
+    pub const POSE_DIM: usize = 3;
+    /// The number of values describing the pose of a rigid-body.
+    #[cfg(feature = "dim3")]
+    pub const POSE_DIM: usize = 7;
+    /// The number of values describing the velocity of a rigid-body.
+    pub const VELOCITY_DIM: usize = SPATIAL_DIM;
+    /// The number of values describing the impulse of a contact point.
+    pub const CONTACT_IMPULSE_DIM: usize = DIM;
+    /// The number of values describing the impulse of a joint.
+    pub const JOINT_IMPULSE_DIM: usize = SPATIAL_DIM;
+
+    /// Creates an empty simulation frame.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of rigid-bodies of this frame.
+    pub fn num_bodies(&self) -> usize {
+        self.body_handles.len()
+    }
+
+    /// The number of contact points of this frame.
+    pub fn num_contacts(&self) -> usize {
+        self.contact_colliders.len()
+    }
+
+    /// The number of joints of this frame.
+    pub fn num_joints(&self) -> usize {
+        self.joint_handles.len()
+    }
+
+    /// Removes all the elements of this frame, keeping the allocated memory.
+    pub fn clear(&mut self) {
+        self.body_handles.clear();
+        self.body_poses.clear();
+        self.body_velocities.clear();
+        self.body_masses.clear();
+        self.contact_colliders.clear();
+        self.contact_points.clear();
+        self.contact_normals.clear();
+        self.contact_impulses.clear();
+        self.joint_handles.clear();
+        self.joint_bodies.clear();
+        self.joint_types.clear();
+        self.joint_states.clear();
+        self.joint_impulses.clear();
+        self.sorted_pairs.clear();
+    }
+
+    pub(crate) fn fill(
+        &mut self,
+        bodies: &RigidBodySet,
+        colliders: &ColliderSet,
+        joints: &JointSet,
+        narrow_phase: &NarrowPhase,
+        filter: Option<&dyn Fn(RigidBodyHandle, &RigidBody) -> bool>,
+    ) {
+        self.clear();
+
+        let keep_body = |handle: RigidBodyHandle| {
+            bodies
+                .get(handle)
+                .map(|rb| filter.map_or(true, |f| f(handle, rb)))
+                .unwrap_or(false)
+        };
+
+        /*
+         * Bodies.
+         */
+        for (handle, rb) in bodies.iter() {
+            if !keep_body(handle) {
+                continue;
+            }
+
+            self.body_handles.push(handle);
+            push_pose(&mut self.body_poses, rb.position());
+            self.body_velocities
+                .extend_from_slice(rb.linvel().as_slice());
+            #[cfg(feature = "dim2")]
+            self.body_velocities.push(rb.angvel());
+            #[cfg(feature = "dim3")]
+            self.body_velocities
+                .extend_from_slice(rb.angvel().as_slice());
+            self.body_masses.push(rb.mass());
+        }
+
+        /*
+         * Contacts.
+         */
+        for pair in narrow_phase.contact_pairs() {
+            if !pair.has_any_active_contact {
+                continue;
+            }
+
+            let (co1, co2) = match (
+                colliders.get(pair.pair.collider1),
+                colliders.get(pair.pair.collider2),
+            ) {
+                (Some(co1), Some(co2)) => (co1, co2),
+                _ => continue,
+            };
+
+            if keep_body(co1.parent()) || keep_body(co2.parent()) {
+                self.sorted_pairs
+                    .push((pair.pair.collider1, pair.pair.collider2));
+            }
+        }
+
+        self.sorted_pairs
+            .sort_unstable_by_key(|(h1, h2)| (h1.into_raw_parts(), h2.into_raw_parts()));
+
+        for (handle1, handle2) in &self.sorted_pairs {
+            let pair = narrow_phase.contact_pair(*handle1, *handle2).unwrap();
+            let co1 = &colliders[*handle1];
+
+            for manifold in &pair.manifolds {
+                let world_pos1 = manifold.subshape_pos1.prepend_to(co1.position());
+
+                for contact in &manifold.points {
+                    self.contact_colliders.push((*handle1, *handle2));
+                    self.contact_points
+                        .extend_from_slice((world_pos1 * contact.local_p1).coords.as_slice());
+                    self.contact_normals
+                        .extend_from_slice(manifold.data.normal.as_slice());
+                    self.contact_impulses.push(contact.data.impulse);
+                    #[cfg(feature = "dim2")]
+                    self.contact_impulses.push(contact.data.tangent_impulse);
+                    #[cfg(feature = "dim3")]
+                    self.contact_impulses
+                        .extend_from_slice(contact.data.tangent_impulse.as_slice());
+                }
+            }
+        }
+
+        self.sorted_pairs.clear();
+
+        /*
+         * Joints.
+         */
+        for (handle, joint) in joints.iter() {
+            if !keep_body(joint.body1) && !keep_body(joint.body2) {
+                continue;
+            }
+
+            let (rb1, rb2) = match (bodies.get(joint.body1), bodies.get(joint.body2)) {
+                (Some(rb1), Some(rb2)) => (rb1, rb2),
+                _ => continue,
+            };
+
+            self.joint_handles.push(handle);
+            self.joint_bodies.push((joint.body1, joint.body2));
+            self.joint_types.push(joint.params.type_id());
+            push_pose(
+                &mut self.joint_states,
+                &rb1.position().inv_mul(rb2.position()),
+            );
+
+            let impulse = match &joint.params {
+                JointParams::BallJoint(j) => j.impulse.as_slice(),
+                JointParams::FixedJoint(j) => j.impulse.as_slice(),
+                JointParams::PrismaticJoint(j) => j.impulse.as_slice(),
+                #[cfg(feature = "dim3")]
+                JointParams::RevoluteJoint(j) => j.impulse.as_slice(),
+                JointParams::GearJoint(j) => core::slice::from_ref(&j.impulse),
+                JointParams::PulleyJoint(j) => core::slice::from_ref(&j.impulse),
+            };
+            self.joint_impulses.extend_from_slice(impulse);
+            self.joint_impulses
+                .extend((impulse.len()..Self::JOINT_IMPULSE_DIM).map(|_| 0.0));
+        }
+    }
+
+    /// Serializes this frame into a flat buffer of bytes.
+    ///
+    /// All the values are little-endian. The buffer contains, in this order:
+    /// 1. A header made of two `u8`: the dimension (2 or 3) and the size in bytes of a real
+    ///    number (4 for `f32`, 8 for `f64`), followed by three `u32`: the number of bodies,
+    ///    contacts, and joints.
+    /// 2. For each body, its handle, then for each body its pose, then for each body its
+    ///    velocity, then for each body its mass.
+    /// 3. For each contact, its two collider handles, then for each contact its point, then
+    ///    for each contact its normal, then for each contact its impulse.
+    /// 4. For each joint, its handle, then for each joint its two body handles, then for
+    ///    each joint its type as a `u32`, then for each joint its state, then for each joint
+    ///    its impulse.
+    ///
+    /// Each handle is encoded as a `u32` index followed by a `u64` generation. Each pose,
+    /// velocity, point, normal, state, and impulse is encoded as the corresponding sequence
+    /// of real numbers described in the documentation of the fields of `SimulationFrame`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.write_bytes(&mut out);
+        out
+    }
+
+    /// Serializes this frame into the given buffer, using the layout described in
+    /// `SimulationFrame::to_bytes`.
+    ///
+    /// The buffer is cleared first.
+    pub fn write_bytes(&self, out: &mut Vec<u8>) {
+        fn put_handle(out: &mut Vec<u8>, (id, generation): (usize, u64)) {
+            out.extend_from_slice(&(id as u32).to_le_bytes());
+            out.extend_from_slice(&generation.to_le_bytes());
+        }
+
+        fn put_reals(out: &mut Vec<u8>, reals: &[Real]) {
+            for real in reals {
+                out.extend_from_slice(&real.to_le_bytes());
+            }
+        }
+
+        out.clear();
+        out.push(DIM as u8);
+        out.push(core::mem::size_of::<Real>() as u8);
+        out.extend_from_slice(&(self.num_bodies() as u32).to_le_bytes());
+        out.extend_from_slice(&(self.num_contacts() as u32).to_le_bytes());
+        out.extend_from_slice(&(self.num_joints() as u32).to_le_bytes());
+
+        for handle in &self.body_handles {
+            put_handle(out, handle.into_raw_parts());
+        }
+        put_reals(out, &self.body_poses);
+        put_reals(out, &self.body_velocities);
+        put_reals(out, &self.body_masses);
+
+        for (handle1, handle2) in &self.contact_colliders {
+            put_handle(out, handle1.into_raw_parts());
+            put_handle(out, handle2.into_raw_parts());
+        }
+        put_reals(out, &self.contact_points);
+        put_reals(out, &self.contact_normals);
+        put_reals(out, &self.contact_impulses);
+
+        for handle in &self.joint_handles {
+            put_handle(out, handle.into_raw_parts());
+        }
+        for (handle1, handle2) in &self.joint_bodies {
+            put_handle(out, handle1.into_raw_parts());
+            put_handle(out, handle2.into_raw_parts());
+        }
+        for joint_type in &self.joint_types {
+            out.extend_from_slice(&(*joint_type as u32).to_le_bytes());
+        }
+        put_reals(out, &self.joint_states);
+        put_reals(out, &self.joint_impulses);
+    }
+
+    /// Deserializes a frame serialized with `SimulationFrame::to_bytes`.
+    ///
+    /// Returns `None` if the buffer is truncated, or if it was serialized with a different
+    /// dimension or real number type.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        struct Reader<'a>(&'a [u8]);
+
+        impl<'a> Reader<'a> {
+            fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+                if self.0.len() < n {
+                    return None;
+                }
+
+                let (result, rest) = self.0.split_at(n);
+                self.0 = rest;
+                Some(result)
+            }
+
+            fn u8(&mut self) -> Option<u8> {
+                self.take(1).map(|bytes| bytes[0])
+            }
+
+            fn u32(&mut self) -> Option<u32> {
+                let bytes = self.take(4)?.try_into().ok()?;
+                Some(u32::from_le_bytes(bytes))
+            }
+
+            fn handle(&mut self) -> Option<(usize, u64)> {
+                let id = self.u32()? as usize;
+                let generation = u64::from_le_bytes(self.take(8)?.try_into().ok()?);
+                Some((id, generation))
+            }
+
+            fn reals(&mut self, n: usize, out: &mut Vec<Real>) -> Option<()> {
+                let size = core::mem::size_of::<Real>();
+                for _ in 0..n {
+                    out.push(Real::from_le_bytes(self.take(size)?.try_into().ok()?));
+                }
+                Some(())
+            }
+        }
+
+        let mut reader = Reader(bytes);
+        let dim = reader.u8()?;
+        let real_size = reader.u8()?;
+
+        if dim as usize != DIM || real_size as usize != core::mem::size_of::<Real>() {
+            return None;
+        }
+
+        let num_bodies = reader.u32()? as usize;
+        let num_contacts = reader.u32()? as usize;
+        let num_joints = reader.u32()? as usize;
+        let mut frame = Self::new();
+
+        for _ in 0..num_bodies {
+            let (id, generation) = reader.handle()?;
+            frame
+                .body_handles
+                .push(RigidBodyHandle::from_raw_parts(id, generation));
+        }
+        reader.reals(num_bodies * Self::POSE_DIM, &mut frame.body_poses)?;
+        reader.reals(num_bodies * Self::VELOCITY_DIM, &mut frame.body_velocities)?;
+        reader.reals(num_bodies, &mut frame.body_masses)?;
+
+        for _ in 0..num_contacts {
+            let (id1, generation1) = reader.handle()?;
+            let (id2, generation2) = reader.handle()?;
+            frame.contact_colliders.push((
+                ColliderHandle::from_raw_parts(id1, generation1),
+                ColliderHandle::from_raw_parts(id2, generation2),
+            ));
+        }
+        reader.reals(num_contacts * DIM, &mut frame.contact_points)?;
+        reader.reals(num_contacts * DIM, &mut frame.contact_normals)?;
+        reader.reals(
+            num_contacts * Self::CONTACT_IMPULSE_DIM,
+            &mut frame.contact_impulses,
+        )?;
+
+        for _ in 0..num_joints {
+            let (id, generation) = reader.handle()?;
+            frame
+                .joint_handles
+                .push(JointHandle::from_raw_parts(id, generation));
+        }
+        for _ in 0..num_joints {
+            let (id1, generation1) = reader.handle()?;
+            let (id2, generation2) = reader.handle()?;
+            frame.joint_bodies.push((
+                RigidBodyHandle::from_raw_parts(id1, generation1),
+                RigidBodyHandle::from_raw_parts(id2, generation2),
+            ));
+        }
+        for _ in 0..num_joints {
+            frame.joint_types.push(reader.u32()? as usize);
+        }
+        reader.reals(num_joints * Self::POSE_DIM, &mut frame.joint_states)?;
+        reader.reals(
+            num_joints * Self::JOINT_IMPULSE_DIM,
+            &mut frame.joint_impulses,
+        )?;
+
+        Some(frame)
+    }
+}
+
+fn push_pose(out: &mut Vec<Real>, pose: &Isometry<Real>) {
+    out.extend_from_slice(pose.translation.vector.as_slice());
+    #[cfg(feature = "dim2")]
+    out.push(pose.rotation.angle());
+    #[cfg(feature = "dim3")]
+    out.extend_from_slice(pose.rotation.coords.as_slice());
+}
@@ -4,6 +4,7 @@ use crate::dynamics::{
     RigidBodyIds, RigidBodyPosition, RigidBodyType,
 };
 use crate::geometry::{ColliderChanges, ColliderHandle, ColliderParent, ColliderPosition};
+use alloc::vec::Vec;
 
 pub(crate) fn handle_user_changes_to_colliders<Colliders>(
     bodies: &mut impl ComponentSet<RigidBodyPosition>,
@@ -0,0 +1,75 @@
+use crate::dynamics::{IntegrationParameters, RigidBody, RigidBodyHandle};
+use crate::math::Real;
+use crate::pipeline::EventHandler;
+use crate::utils::ang::ang_magnitude;
+
+/// Event emitted when the velocity of a rigid-body is clamped to the maximum velocities
+/// set by the `IntegrationParameters`.
+///
+/// Such events usually mean that the simulation is exploding, e.g., because of a huge
+/// impulse applied by the user or because of bodies trapped into each other.
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct VelocityClampEvent {
+    /// The rigid-body with a clamped velocity.
+    pub body: RigidBodyHandle,
+    /// The amount by which the norm of the linear velocity of the rigid-body exceeded
+    /// `IntegrationParameters::max_linear_velocity`, or zero if it didn't.
+    pub linear_excess: Real,
+    /// The amount by which the norm of the angular velocity of the rigid-body exceeded
+    /// `IntegrationParameters::max_angular_velocity`, or zero if it didn't.
+    pub angular_excess: Real,
+}
+
+/// Clamps the velocities of a rigid-body to the maximum velocities set by the `params`.
+///
+/// If `include_forces` is `true`, the velocities are clamped as they will be once the forces
+/// applied to the rigid-body are integrated during `params.dt`. In that case, the forces of a
+/// rigid-body which needs clamping are integrated right away and reset to zero.
+pub(crate) fn clamp_body_velocities(
+    params: &IntegrationParameters,
+    handle: RigidBodyHandle,
+    rb: &mut RigidBody,
+    include_forces: bool,
+    events: &dyn EventHandler,
+) {
+    let mut linvel = rb.linvel;
+    let mut angvel = rb.angvel;
+
+    if include_forces {
+        linvel += rb.force.component_mul(&rb.effective_inv_mass) * params.dt;
+        angvel += rb.effective_world_inv_inertia_sqrt
+            * (rb.effective_world_inv_inertia_sqrt * rb.torque)
+            * params.dt;
+    }
+
+    let linear_excess = (linvel.norm() - params.max_linear_velocity).max(0.0);
+    let angular_excess = (ang_magnitude(&angvel) - params.max_angular_velocity).max(0.0);
+
+    if linear_excess == 0.0 && angular_excess == 0.0 {
+        return;
+    }
+
+    if linear_excess > 0.0 {
+        linvel *= params.max_linear_velocity / (params.max_linear_velocity + linear_excess);
+    }
+
+    if angular_excess > 0.0 {
+        angvel *= params.max_angular_velocity / (params.max_angular_velocity + angular_excess);
+    }
+
+    rb.linvel = linvel;
+    rb.angvel = angvel;
+
+    if include_forces {
+        // The forces are already part of the clamped velocities.
+        rb.force = na::zero();
+        rb.torque = na::zero();
+    }
+
+    events.handle_velocity_clamp_event(VelocityClampEvent {
+        body: handle,
+        linear_excess,
+        angular_excess,
+    });
+}
@@ -0,0 +1,232 @@
+use crate::dynamics::{JointSet, RemovedRigidBody, RigidBodyHandle, RigidBodySet};
+use crate::geometry::{ColliderSet, AABB};
+use crate::math::{Point, Real};
+use crate::pipeline::EventHandler;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+/// The bounds of the simulated world, outside of which rigid-bodies are no longer simulated.
+///
+/// Only the center of mass of the active dynamic rigid-bodies is checked against these bounds,
+/// by calling `Self::handle_out_of_bounds_bodies` after each timestep.
+pub enum PipelineBounds {
+    /// The rigid-bodies with a center of mass outside of this AABB are out of bounds.
+    Aabb(AABB),
+    /// The rigid-bodies with a center of mass for which this function returns `false` are out
+    /// of bounds.
+    Callback(Box<dyn Fn(&Point<Real>) -> bool + Send + Sync>),
+}
+
+impl PipelineBounds {
+    /// Is the given world-space point inside of these bounds?
+    pub fn contains(&self, point: &Point<Real>) -> bool {
+        match self {
+            PipelineBounds::Aabb(aabb) => aabb.contains_local_point(point),
+            PipelineBounds::Callback(callback) => callback(point),
+        }
+    }
+
+    /// Applies the `policy` to the active dynamic rigid-bodies outside of these bounds, and
+    /// returns the rigid-bodies it removed with their colliders and joints.
+    ///
+    /// A `BodyOutOfBoundsEvent` is emitted for each of them. Sleeping rigid-bodies are not
+    /// checked since they are not part of the active set.
+    pub fn handle_out_of_bounds_bodies(
+        &self,
+        policy: OnOutOfBounds,
+        bodies: &mut RigidBodySet,
+        colliders: &mut ColliderSet,
+        joints: &mut JointSet,
+        events: &dyn EventHandler,
+    ) -> Vec<RemovedRigidBody> {
+        let out_of_bounds: Vec<_> = bodies
+            .iter_active_dynamic()
+            .filter(|(_, rb)| !rb.is_sleeping() && !rb.is_frozen() && !self.contains(&rb.world_com))
+            .map(|(handle, _)| handle)
+            .collect();
+        let mut removed = Vec::new();
+
+        for handle in out_of_bounds {
+            events.handle_body_out_of_bounds_event(BodyOutOfBoundsEvent { body: handle });
+
+            match policy {
+                OnOutOfBounds::Freeze => {
+                    if let Some(rb) = bodies.get_mut_internal(handle) {
+                        rb.set_frozen(true);
+                    }
+                }
+                OnOutOfBounds::Sleep => {
+                    if let Some(rb) = bodies.get_mut_internal(handle) {
+                        rb.sleep();
+                    }
+                }
+                OnOutOfBounds::Remove => {
+                    removed.extend(bodies.remove_and_return(handle, colliders, joints));
+                }
+            }
+        }
+
+        removed
+    }
+}
+
+/// What `PipelineBounds::handle_out_of_bounds_bodies` does with a rigid-body outside of the bounds.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub enum OnOutOfBounds {
+    /// Freeze the rigid-body (see `RigidBody::set_frozen`).
+    ///
+    /// Frozen rigid-bodies are no longer checked against the bounds.
+    Freeze,
+    /// Put the rigid-body to sleep.
+    ///
+    /// It will be checked against the bounds again once it wakes up.
+    Sleep,
+    /// Remove the rigid-body, together with its colliders and joints.
+    ///
+    /// The removed parts are returned by `PipelineBounds::handle_out_of_bounds_bodies`, e.g., for
+    /// inserting them back with `RigidBodySet::insert_removed`.
+    Remove,
+}
+
+impl Default for OnOutOfBounds {
+    fn default() -> Self {
+        OnOutOfBounds::Sleep
+    }
+}
+
+/// Event emitted when an active rigid-body is found outside of the `PipelineBounds`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize, Deserialize))]
+pub struct BodyOutOfBoundsEvent {
+    /// The rigid-body that left the bounds.
+    ///
+    /// With `OnOutOfBounds::Remove`, this handle is no longer valid once the event is emitted.
+    pub body: RigidBodyHandle,
+}
+
+#[cfg(test)]
+mod test {
+    use crate::dynamics::{
+        CCDSolver, IntegrationParameters, JointSet, RigidBodyBuilder, RigidBodySet,
+        UserConstraintSet,
+    };
+    use crate::geometry::{BroadPhase, ColliderBuilder, ColliderSet, NarrowPhase};
+    use crate::math::Vector;
+    use crate::pipeline::PhysicsPipeline;
+
+    #[test]
+    fn balls_rolling_off_the_edge_leave_the_world_bounds_once() {
+        use crate::geometry::{ContactEvent, IntersectionEvent, AABB};
+        use crate::math::Point;
+        use crate::pipeline::{BodyOutOfBoundsEvent, EventHandler, OnOutOfBounds, PipelineBounds};
+        use std::sync::Mutex;
+
+        struct OutOfBoundsRecorder(Mutex<Vec<BodyOutOfBoundsEvent>>);
+
+        impl EventHandler for OutOfBoundsRecorder {
+            fn handle_intersection_event(&self, _event: IntersectionEvent) {}
+            fn handle_contact_event(&self, _event: ContactEvent) {}
+            fn handle_body_out_of_bounds_event(&self, event: BodyOutOfBoundsEvent) {
+                self.0.lock().unwrap().push(event);
+            }
+        }
+
+        for policy in &[
+            OnOutOfBounds::Freeze,
+            OnOutOfBounds::Sleep,
+            OnOutOfBounds::Remove,
+        ] {
+            let mut pipeline = PhysicsPipeline::new();
+            let mut broad_phase = BroadPhase::new();
+            let mut narrow_phase = NarrowPhase::new();
+            let mut bodies = RigidBodySet::new();
+            let mut colliders = ColliderSet::new();
+            let mut joints = JointSet::new();
+            let mut user_constraints = UserConstraintSet::new();
+            let mut ccd = CCDSolver::new();
+            let gravity = Vector::y() * -9.81;
+            let params = IntegrationParameters::default();
+            let events = OutOfBoundsRecorder(Mutex::new(Vec::new()));
+
+            #[cfg(feature = "dim2")]
+            let (ground_shape, bounds, ball) = (
+                ColliderBuilder::cuboid(5.0, 0.5),
+                AABB::new(Point::new(-100.0, -20.0), Point::new(100.0, 100.0)),
+                RigidBodyBuilder::new_dynamic()
+                    .translation(3.0, 1.0)
+                    .linvel(5.0, 0.0),
+            );
+            #[cfg(feature = "dim3")]
+            let (ground_shape, bounds, ball) = (
+                ColliderBuilder::cuboid(5.0, 0.5, 5.0),
+                AABB::new(
+                    Point::new(-100.0, -20.0, -100.0),
+                    Point::new(100.0, 100.0, 100.0),
+                ),
+                RigidBodyBuilder::new_dynamic()
+                    .translation(3.0, 1.0, 0.0)
+                    .linvel(5.0, 0.0, 0.0),
+            );
+
+            let bounds = PipelineBounds::Aabb(bounds);
+            let mut removed = Vec::new();
+
+            let ground = bodies.insert(RigidBodyBuilder::new_static().build());
+            colliders.insert(ground_shape.build(), ground, &mut bodies);
+
+            let ball = bodies.insert(ball.build());
+            colliders.insert(ColliderBuilder::ball(0.5).build(), ball, &mut bodies);
+
+            for _ in 0..300 {
+                pipeline.step(
+                    &gravity,
+                    &params,
+                    &mut broad_phase,
+                    &mut narrow_phase,
+                    &mut bodies,
+                    &mut colliders,
+                    &mut joints,
+                    &mut user_constraints,
+                    &mut ccd,
+                    &(),
+                    &events,
+                );
+                removed.extend(bounds.handle_out_of_bounds_bodies(
+                    *policy,
+                    &mut bodies,
+                    &mut colliders,
+                    &mut joints,
+                    &events,
+                ));
+            }
+
+            assert_eq!(
+                *events.0.lock().unwrap(),
+                vec![BodyOutOfBoundsEvent { body: ball }]
+            );
+
+            match policy {
+                OnOutOfBounds::Freeze => {
+                    assert!(bodies[ball].is_frozen());
+                    assert!(bodies[ball].position().translation.y > -25.0);
+                }
+                OnOutOfBounds::Sleep => {
+                    assert!(bodies[ball].is_sleeping());
+                    assert!(bodies[ball].position().translation.y > -25.0);
+                }
+                OnOutOfBounds::Remove => {
+                    // No trace of the ball is left in the sets.
+                    assert!(bodies.get(ball).is_none());
+                    assert_eq!(bodies.len(), 1);
+                    assert_eq!(colliders.len(), 1);
+                    assert!(bodies.iter_active_dynamic().next().is_none());
+
+                    assert_eq!(removed.len(), 1);
+                    assert_eq!(removed[0].handle, ball);
+                    assert_eq!(removed[0].colliders.len(), 1);
+                }
+            }
+        }
+    }
+}
@@ -1,10 +1,17 @@
 //! Miscellaneous utilities.
 
-use na::{Matrix3, Point2, Point3, Scalar, SimdRealField, Vector2, Vector3};
+use na::allocator::Allocator;
+use na::{
+    Cholesky, DefaultAllocator, DimName, Matrix3, OMatrix, Point2, Point3, Scalar, SimdRealField,
+    Vector2, Vector3,
+};
 use num::Zero;
 use simba::simd::SimdValue;
-use std::ops::IndexMut;
 
+use alloc::vec::Vec;
+use core::ops::IndexMut;
+#[cfg(feature = "dim2")]
+use parry::utils::SdpMatrix2;
 use parry::utils::SdpMatrix3;
 use {
     crate::math::{Real, SimdReal},
@@ -392,6 +399,97 @@ impl WDot<SimdReal> for SimdReal {
     }
 }
 
+/// Trait to add the components of a vector to the diagonal of a symmetric matrix.
+#[cfg(feature = "dim3")]
+pub(crate) trait WAddDiagonal<V>: Sized {
+    fn add_diagonal_vector(self, diagonal: &V) -> Self;
+}
+
+#[cfg(feature = "dim3")]
+impl<N: SimdRealField + Copy> WAddDiagonal<Vector3<N>> for SdpMatrix3<N> {
+    fn add_diagonal_vector(mut self, diagonal: &Vector3<N>) -> Self {
+        self.m11 += diagonal.x;
+        self.m22 += diagonal.y;
+        self.m33 += diagonal.z;
+        self
+    }
+}
+
+/// Trait to invert a symmetric positive semi-definite matrix, ignoring the degrees of freedom
+/// with a zero diagonal element.
+///
+/// The row and the column of a zero diagonal element of such a matrix are zero too. With the
+/// joints, this happens along the constrained degrees of freedom neither rigid-body can move
+/// along, e.g., because of their locked axes. The reduced system without these rows and columns
+/// is inverted, and the inverse is zero along them, so no impulse is applied there.
+pub(crate) trait WReducedInverse: Sized {
+    fn reduced_inverse(self) -> Self;
+}
+
+#[cfg(feature = "dim2")]
+impl<N: SimdRealField + Copy> WReducedInverse for SdpMatrix2<N> {
+    fn reduced_inverse(mut self) -> Self {
+        let (zero, one) = (N::zero(), N::one());
+        let locked1 = self.m11.simd_eq(zero);
+        let locked2 = self.m22.simd_eq(zero);
+        self.m11 = one.select(locked1, self.m11);
+        self.m22 = one.select(locked2, self.m22);
+
+        let mut inv = self.inverse_unchecked();
+        inv.m11 = zero.select(locked1, inv.m11);
+        inv.m12 = zero.select(locked1 | locked2, inv.m12);
+        inv.m22 = zero.select(locked2, inv.m22);
+        inv
+    }
+}
+
+impl<N: SimdRealField + Copy> WReducedInverse for SdpMatrix3<N> {
+    fn reduced_inverse(mut self) -> Self {
+        let (zero, one) = (N::zero(), N::one());
+        let locked1 = self.m11.simd_eq(zero);
+        let locked2 = self.m22.simd_eq(zero);
+        let locked3 = self.m33.simd_eq(zero);
+        self.m11 = one.select(locked1, self.m11);
+        self.m22 = one.select(locked2, self.m22);
+        self.m33 = one.select(locked3, self.m33);
+
+        let mut inv = self.inverse_unchecked();
+        inv.m11 = zero.select(locked1, inv.m11);
+        inv.m12 = zero.select(locked1 | locked2, inv.m12);
+        inv.m13 = zero.select(locked1 | locked3, inv.m13);
+        inv.m22 = zero.select(locked2, inv.m22);
+        inv.m23 = zero.select(locked2 | locked3, inv.m23);
+        inv.m33 = zero.select(locked3, inv.m33);
+        inv
+    }
+}
+
+impl<N: SimdRealField + Copy, D: DimName> WReducedInverse for OMatrix<N, D, D>
+where
+    DefaultAllocator: Allocator<N, D, D> + Allocator<N, D>,
+{
+    fn reduced_inverse(mut self) -> Self {
+        let (zero, one) = (N::zero(), N::one());
+        let diagonal = self.diagonal();
+
+        for i in 0..D::dim() {
+            self[(i, i)] = one.select(diagonal[i].simd_eq(zero), diagonal[i]);
+        }
+
+        // NOTE: Cholesky only reads the lower-triangular part of the matrix.
+        let mut inv = Cholesky::new_unchecked(self).inverse();
+
+        for i in 0..D::dim() {
+            for j in 0..D::dim() {
+                let locked = diagonal[i].simd_eq(zero) | diagonal[j].simd_eq(zero);
+                inv[(i, j)] = zero.select(locked, inv[(i, j)]);
+            }
+        }
+
+        inv
+    }
+}
+
 pub(crate) trait WAngularInertia<N> {
     type AngVector;
     type LinVector;
@@ -638,9 +736,9 @@ impl FlushToZeroDenormalsAreZeroFlags {
     pub fn flush_denormal_to_zero() -> Self {
         unsafe {
             #[cfg(target_arch = "x86")]
-            use std::arch::x86::{_mm_getcsr, _mm_setcsr, _MM_FLUSH_ZERO_ON};
+            use core::arch::x86::{_mm_getcsr, _mm_setcsr, _MM_FLUSH_ZERO_ON};
             #[cfg(target_arch = "x86_64")]
-            use std::arch::x86_64::{_mm_getcsr, _mm_setcsr, _MM_FLUSH_ZERO_ON};
+            use core::arch::x86_64::{_mm_getcsr, _mm_setcsr, _MM_FLUSH_ZERO_ON};
 
             // Flush denormals & underflows to zero as this as a significant impact on the solver's performances.
             // To enable this we need to set the bit 15 (given by _MM_FLUSH_ZERO_ON) and the bit 6 (for denormals-are-zero).
@@ -661,11 +759,11 @@ impl Drop for FlushToZeroDenormalsAreZeroFlags {
     fn drop(&mut self) {
         #[cfg(target_arch = "x86")]
         unsafe {
-            std::arch::x86::_mm_setcsr(self.original_flags)
+            core::arch::x86::_mm_setcsr(self.original_flags)
         }
         #[cfg(target_arch = "x86_64")]
         unsafe {
-            std::arch::x86_64::_mm_setcsr(self.original_flags)
+            core::arch::x86_64::_mm_setcsr(self.original_flags)
         }
     }
 }
@@ -708,3 +806,95 @@ impl<T> IndexMut2<usize> for Vec<T> {
         }
     }
 }
+
+/// Helpers to manipulate angular quantities the same way in 2D and 3D.
+///
+/// Angular velocities, torques, and rotation vectors are represented as an `AngVector<Real>`,
+/// which is a `Real` in 2D and a `Vector<Real>` in 3D. These helpers let code targeting both
+/// dimensions treat 2D angular quantities as rotations around the `z` axis.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(all(feature = "dim2", feature = "f32"))]
+/// # use rapier2d::{dynamics::*, math::*, utils::ang};
+/// # #[cfg(all(feature = "dim2", feature = "f64"))]
+/// # use rapier2d_f64::{dynamics::*, math::*, utils::ang};
+/// # #[cfg(all(feature = "dim3", feature = "f32"))]
+/// # use rapier3d::{dynamics::*, math::*, utils::ang};
+/// # #[cfg(all(feature = "dim3", feature = "f64"))]
+/// # use rapier3d_f64::{dynamics::*, math::*, utils::ang};
+/// // This compiles and behaves the same way in 2D and in 3D.
+/// let mut rb = RigidBodyBuilder::new_dynamic()
+///     .rotation_z(0.5)
+///     .angvel_z(2.0)
+///     .build();
+/// assert_eq!(rb.angvel_z(), 2.0);
+///
+/// rb.set_angvel_z(-3.0, true);
+/// rb.apply_torque(ang::ang_from_z(10.0), true);
+/// assert_eq!(ang::ang_magnitude(&rb.angvel()), 3.0);
+///
+/// let vel = ang::ang_cross_point(&rb.angvel(), &Point::from(Vector::x()));
+/// assert_eq!(vel, Vector::y() * -3.0);
+/// ```
+pub mod ang {
+    use super::WCross;
+    use crate::math::{AngVector, Point, Real, Vector};
+
+    /// The angular vector of a rotation around the `z` axis with the given magnitude.
+    ///
+    /// In 2D, this is `z` itself.
+    #[cfg(feature = "dim2")]
+    pub fn ang_from_z(z: Real) -> AngVector<Real> {
+        z
+    }
+
+    /// The angular vector of a rotation around the `z` axis with the given magnitude.
+    ///
+    /// In 2D, this is `z` itself.
+    #[cfg(feature = "dim3")]
+    pub fn ang_from_z(z: Real) -> AngVector<Real> {
+        Vector::z() * z
+    }
+
+    /// The component of an angular vector along the `z` axis.
+    ///
+    /// In 2D, this is the angular vector itself.
+    #[cfg(feature = "dim2")]
+    pub fn ang_z(ang: &AngVector<Real>) -> Real {
+        *ang
+    }
+
+    /// The component of an angular vector along the `z` axis.
+    ///
+    /// In 2D, this is the angular vector itself.
+    #[cfg(feature = "dim3")]
+    pub fn ang_z(ang: &AngVector<Real>) -> Real {
+        ang.z
+    }
+
+    /// The magnitude of an angular vector.
+    ///
+    /// In 2D, this is the absolute value of the angular vector.
+    #[cfg(feature = "dim2")]
+    pub fn ang_magnitude(ang: &AngVector<Real>) -> Real {
+        ang.abs()
+    }
+
+    /// The magnitude of an angular vector.
+    ///
+    /// In 2D, this is the absolute value of the angular vector.
+    #[cfg(feature = "dim3")]
+    pub fn ang_magnitude(ang: &AngVector<Real>) -> Real {
+        ang.norm()
+    }
+
+    /// The cross product of an angular vector with the coordinates of a point.
+    ///
+    /// If `ang` is an angular velocity, this is the velocity of `point` due to the rotation
+    /// around the origin.
+    pub fn ang_cross_point(ang: &AngVector<Real>, point: &Point<Real>) -> Vector<Real> {
+        ang.gcross(point.coords)
+    }
+}
@@ -150,6 +150,12 @@ impl Box2dWorld {
 
                     self.world.create_joint(&def);
                 }
+                JointParams::GearJoint(_) => {
+                    eprintln!("Joint type currently unsupported by the Box2D backend: GearJoint.")
+                }
+                JointParams::PulleyJoint(_) => {
+                    eprintln!("Joint type currently unsupported by the Box2D backend: PulleyJoint.")
+                }
             }
         }
     }
@@ -4,7 +4,7 @@ use crate::{
 };
 use kiss3d::window::Window;
 use plugin::HarnessPlugin;
-use rapier::dynamics::{CCDSolver, IntegrationParameters, JointSet, RigidBodySet};
+use rapier::dynamics::{CCDSolver, IntegrationParameters, JointSet, RigidBodySet, UserConstraintSet};
 use rapier::geometry::{BroadPhase, ColliderSet, NarrowPhase};
 use rapier::math::Vector;
 use rapier::pipeline::{ChannelEventCollector, PhysicsHooks, PhysicsPipeline, QueryPipeline};
@@ -128,6 +128,7 @@ impl Harness {
         self.physics.bodies = bodies;
         self.physics.colliders = colliders;
         self.physics.joints = joints;
+        self.physics.user_constraints = UserConstraintSet::new();
         self.physics.hooks = Box::new(hooks);
 
         self.physics.broad_phase = BroadPhase::new();
@@ -180,6 +181,7 @@ impl Harness {
                     &mut physics.bodies,
                     &mut physics.colliders,
                     &mut physics.joints,
+                    &mut physics.user_constraints,
                     &mut physics.ccd_solver,
                     &*physics.hooks,
                     event_handler,
@@ -196,6 +198,7 @@ impl Harness {
             &mut self.physics.bodies,
             &mut self.physics.colliders,
             &mut self.physics.joints,
+            &mut self.physics.user_constraints,
             &mut self.physics.ccd_solver,
             &*self.physics.hooks,
             &self.event_handler,
@@ -121,6 +121,16 @@ impl NPhysicsWorld {
                     }
 
                     nphysics_joints.insert(c);
+                }
+                JointParams::GearJoint(_) => {
+                    eprintln!(
+                        "Joint type currently unsupported by the nphysics backend: GearJoint."
+                    )
+                }
+                JointParams::PulleyJoint(_) => {
+                    eprintln!(
+                        "Joint type currently unsupported by the nphysics backend: PulleyJoint."
+                    )
                 } // JointParams::GenericJoint(_) => {
                   //     eprintln!(
                   //         "Joint type currently unsupported by the nphysics backend: GenericJoint."
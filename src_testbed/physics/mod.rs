@@ -1,16 +1,128 @@
 use crossbeam::channel::Receiver;
-use rapier::dynamics::{CCDSolver, IntegrationParameters, JointSet, RigidBodySet};
-use rapier::geometry::{BroadPhase, ColliderSet, ContactEvent, IntersectionEvent, NarrowPhase};
-use rapier::math::Vector;
+use rapier::dynamics::{
+    CCDSolver, IntegrationParameters, JointSet, RigidBodyHandle, RigidBodySet, UserConstraintSet,
+};
+use rapier::geometry::{
+    BroadPhase, ColliderHandle, ColliderSet, ContactEvent, IntersectionEvent, NarrowPhase,
+};
+use rapier::math::{Real, Vector};
 use rapier::pipeline::{PhysicsHooks, PhysicsPipeline, QueryPipeline};
+use std::fmt;
+
+const SNAPSHOT_MAGIC: &[u8; 4] = b"RSNP";
+
+/// The version of the schema of a serialized `PhysicsSnapshot`.
+///
+/// A snapshot can be read by any build supporting its major version with a greater or equal
+/// minor version. Older major versions are converted by `PhysicsSnapshot::restore_compat`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SchemaVersion {
+    pub major: u16,
+    pub minor: u16,
+}
+
+impl SchemaVersion {
+    /// The first schema, without the CCD solver.
+    pub const V1: Self = Self { major: 1, minor: 0 };
+    /// The schema including the CCD solver.
+    pub const V2: Self = Self { major: 2, minor: 0 };
+    /// The schema of the snapshots written by this build.
+    pub const CURRENT: Self = Self::V2;
+}
+
+impl fmt::Display for SchemaVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+/// An error returned when reading a serialized `PhysicsSnapshot`.
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// The bytes don't start with the header of a snapshot.
+    InvalidHeader,
+    /// The snapshot was written with a schema this build can't read, e.g., by a newer build.
+    UnsupportedVersion(SchemaVersion),
+    /// The content of the snapshot couldn't be decoded.
+    Corrupted(bincode::Error),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::InvalidHeader => write!(f, "the data is not a physics snapshot"),
+            SnapshotError::UnsupportedVersion(version) => write!(
+                f,
+                "the snapshot schema version {} is not supported (the current version is {})",
+                version,
+                SchemaVersion::CURRENT
+            ),
+            SnapshotError::Corrupted(e) => write!(f, "the snapshot is corrupted: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<bincode::Error> for SnapshotError {
+    fn from(e: bincode::Error) -> Self {
+        SnapshotError::Corrupted(e)
+    }
+}
+
+/// A snapshot serialized with the schema `SchemaVersion::V1`.
+pub struct SnapshotV1 {
+    timestep_id: usize,
+    broad_phase: Vec<u8>,
+    narrow_phase: Vec<u8>,
+    bodies: Vec<u8>,
+    colliders: Vec<u8>,
+    joints: Vec<u8>,
+}
+
+impl SnapshotV1 {
+    fn decode(payload: &[u8]) -> bincode::Result<Self> {
+        let (timestep_id, broad_phase, narrow_phase, bodies, colliders, joints) =
+            bincode::deserialize(payload)?;
+        Ok(Self {
+            timestep_id,
+            broad_phase,
+            narrow_phase,
+            bodies,
+            colliders,
+            joints,
+        })
+    }
+}
+
+/// A snapshot serialized with the schema `SchemaVersion::V2`.
+pub type SnapshotV2 = PhysicsSnapshot;
+
+impl From<SnapshotV1> for SnapshotV2 {
+    fn from(snapshot: SnapshotV1) -> Self {
+        Self {
+            schema_version: SchemaVersion::V1,
+            timestep_id: snapshot.timestep_id,
+            broad_phase: snapshot.broad_phase,
+            narrow_phase: snapshot.narrow_phase,
+            bodies: snapshot.bodies,
+            colliders: snapshot.colliders,
+            joints: snapshot.joints,
+            // The CCD solver is reset by the restoration.
+            ccd_solver: None,
+        }
+    }
+}
 
 pub struct PhysicsSnapshot {
+    schema_version: SchemaVersion,
     timestep_id: usize,
     broad_phase: Vec<u8>,
     narrow_phase: Vec<u8>,
     bodies: Vec<u8>,
     colliders: Vec<u8>,
     joints: Vec<u8>,
+    ccd_solver: Option<Vec<u8>>,
 }
 
 impl PhysicsSnapshot {
@@ -21,14 +133,86 @@ impl PhysicsSnapshot {
         bodies: &RigidBodySet,
         colliders: &ColliderSet,
         joints: &JointSet,
+        ccd_solver: &CCDSolver,
     ) -> bincode::Result<Self> {
         Ok(Self {
+            schema_version: SchemaVersion::CURRENT,
             timestep_id,
             broad_phase: bincode::serialize(broad_phase)?,
             narrow_phase: bincode::serialize(narrow_phase)?,
             bodies: bincode::serialize(bodies)?,
             colliders: bincode::serialize(colliders)?,
             joints: bincode::serialize(joints)?,
+            ccd_solver: Some(bincode::serialize(ccd_solver)?),
+        })
+    }
+
+    /// The schema version this snapshot was read from.
+    ///
+    /// This is `SchemaVersion::CURRENT` for the snapshots taken by this build.
+    pub fn schema_version(&self) -> SchemaVersion {
+        self.schema_version
+    }
+
+    /// Serializes this snapshot with the current schema, preceded by its version.
+    pub fn to_bytes(&self) -> bincode::Result<Vec<u8>> {
+        let payload = bincode::serialize(&(
+            self.timestep_id,
+            &self.broad_phase,
+            &self.narrow_phase,
+            &self.bodies,
+            &self.colliders,
+            &self.joints,
+            &self.ccd_solver,
+        ))?;
+
+        let mut bytes = Vec::with_capacity(SNAPSHOT_MAGIC.len() + 4 + payload.len());
+        bytes.extend_from_slice(SNAPSHOT_MAGIC);
+        bytes.extend_from_slice(&SchemaVersion::CURRENT.major.to_le_bytes());
+        bytes.extend_from_slice(&SchemaVersion::CURRENT.minor.to_le_bytes());
+        bytes.extend_from_slice(&payload);
+        Ok(bytes)
+    }
+
+    /// Reads a snapshot serialized by `Self::to_bytes`, with the current schema or a previous one.
+    ///
+    /// The snapshots with a previous schema are converted to the current schema, the new parts
+    /// being reset to their default value by `Self::restore`.
+    pub fn restore_compat(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        let header_len = SNAPSHOT_MAGIC.len() + 4;
+
+        if bytes.len() < header_len || bytes[..SNAPSHOT_MAGIC.len()] != SNAPSHOT_MAGIC[..] {
+            return Err(SnapshotError::InvalidHeader);
+        }
+
+        let version = SchemaVersion {
+            major: u16::from_le_bytes([bytes[4], bytes[5]]),
+            minor: u16::from_le_bytes([bytes[6], bytes[7]]),
+        };
+        let payload = &bytes[header_len..];
+
+        match version.major {
+            1 if version <= SchemaVersion::V1 => Ok(SnapshotV1::decode(payload)?.into()),
+            2 if version <= SchemaVersion::V2 => Ok(Self {
+                schema_version: version,
+                ..Self::decode(payload)?
+            }),
+            _ => Err(SnapshotError::UnsupportedVersion(version)),
+        }
+    }
+
+    fn decode(payload: &[u8]) -> bincode::Result<Self> {
+        let (timestep_id, broad_phase, narrow_phase, bodies, colliders, joints, ccd_solver) =
+            bincode::deserialize(payload)?;
+        Ok(Self {
+            schema_version: SchemaVersion::V2,
+            timestep_id,
+            broad_phase,
+            narrow_phase,
+            bodies,
+            colliders,
+            joints,
+            ccd_solver,
         })
     }
 
@@ -41,7 +225,13 @@ impl PhysicsSnapshot {
         RigidBodySet,
         ColliderSet,
         JointSet,
+        CCDSolver,
     )> {
+        let ccd_solver = match &self.ccd_solver {
+            Some(ccd_solver) => bincode::deserialize(ccd_solver)?,
+            None => CCDSolver::new(),
+        };
+
         Ok((
             self.timestep_id,
             bincode::deserialize(&self.broad_phase)?,
@@ -49,6 +239,7 @@ impl PhysicsSnapshot {
             bincode::deserialize(&self.bodies)?,
             bincode::deserialize(&self.colliders)?,
             bincode::deserialize(&self.joints)?,
+            ccd_solver,
         ))
     }
 
@@ -65,6 +256,138 @@ impl PhysicsSnapshot {
         println!("|_ colliders: {}B", self.colliders.len());
         println!("|_ joints: {}B", self.joints.len());
     }
+
+    /// Lists the differences between two snapshots that exceed the given `tolerance`.
+    ///
+    /// This is useful for finding the first body responsible for the
+    /// divergence of two simulations that should be deterministic.
+    pub fn diff(&self, other: &PhysicsSnapshot, tolerance: Real) -> bincode::Result<SnapshotDiff> {
+        let (_, _, narrow_phase1, bodies1, _, _, _) = self.restore()?;
+        let (_, _, narrow_phase2, bodies2, _, _, _) = other.restore()?;
+
+        let mut result = SnapshotDiff {
+            timestep_ids: (self.timestep_id, other.timestep_id),
+            bodies: Vec::new(),
+            missing_bodies: Vec::new(),
+            manifold_count_mismatches: Vec::new(),
+            warmstart_mismatches: Vec::new(),
+        };
+
+        for (handle, rb1) in bodies1.iter() {
+            if let Some(rb2) = bodies2.get(handle) {
+                let pos1 = rb1.position();
+                let pos2 = rb2.position();
+                let body_diff = BodyDiff {
+                    handle,
+                    translation_delta: (pos1.translation.vector - pos2.translation.vector).norm(),
+                    rotation_delta: pos1.rotation.angle_to(&pos2.rotation),
+                    linvel_delta: (rb1.linvel() - rb2.linvel()).norm(),
+                    #[cfg(feature = "dim2")]
+                    angvel_delta: (rb1.angvel() - rb2.angvel()).abs(),
+                    #[cfg(feature = "dim3")]
+                    angvel_delta: (rb1.angvel() - rb2.angvel()).norm(),
+                };
+
+                if body_diff.max_delta() > tolerance {
+                    result.bodies.push(body_diff);
+                }
+            } else {
+                result.missing_bodies.push(handle);
+            }
+        }
+
+        for (handle, _) in bodies2.iter() {
+            if !bodies1.contains(handle) {
+                result.missing_bodies.push(handle);
+            }
+        }
+
+        for pair1 in narrow_phase1.contact_pairs() {
+            let (h1, h2) = (pair1.pair.collider1, pair1.pair.collider2);
+            let manifolds2 = narrow_phase2
+                .contact_pair(h1, h2)
+                .map(|p| &p.manifolds[..])
+                .unwrap_or(&[]);
+
+            if pair1.manifolds.len() != manifolds2.len() {
+                result.manifold_count_mismatches.push((
+                    h1,
+                    h2,
+                    pair1.manifolds.len(),
+                    manifolds2.len(),
+                ));
+                continue;
+            }
+
+            let warmstart_differs =
+                pair1.manifolds.iter().zip(manifolds2).any(|(m1, m2)| {
+                    m1.points.len() != m2.points.len()
+                        || m1.points.iter().zip(&m2.points).any(|(pt1, pt2)| {
+                            (pt1.data.impulse - pt2.data.impulse).abs() > tolerance
+                        })
+                });
+
+            if warmstart_differs {
+                result.warmstart_mismatches.push((h1, h2));
+            }
+        }
+
+        for pair2 in narrow_phase2.contact_pairs() {
+            let (h1, h2) = (pair2.pair.collider1, pair2.pair.collider2);
+
+            if narrow_phase1.contact_pair(h1, h2).is_none() && !pair2.manifolds.is_empty() {
+                result
+                    .manifold_count_mismatches
+                    .push((h1, h2, 0, pair2.manifolds.len()));
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+/// The difference between the state of a rigid-body on two snapshots.
+#[derive(Copy, Clone, Debug)]
+pub struct BodyDiff {
+    pub handle: RigidBodyHandle,
+    pub translation_delta: Real,
+    pub rotation_delta: Real,
+    pub linvel_delta: Real,
+    pub angvel_delta: Real,
+}
+
+impl BodyDiff {
+    pub fn max_delta(&self) -> Real {
+        self.translation_delta
+            .max(self.rotation_delta)
+            .max(self.linvel_delta)
+            .max(self.angvel_delta)
+    }
+}
+
+/// The differences between two physics snapshots, as computed by `PhysicsSnapshot::diff`.
+#[derive(Clone, Debug)]
+pub struct SnapshotDiff {
+    /// The timesteps at which both snapshots were taken.
+    pub timestep_ids: (usize, usize),
+    /// The bodies with a state differing by more than the tolerance, in handle order.
+    pub bodies: Vec<BodyDiff>,
+    /// The bodies existing on only one of the snapshots.
+    pub missing_bodies: Vec<RigidBodyHandle>,
+    /// The contact pairs with a different number of manifolds on each snapshot.
+    pub manifold_count_mismatches: Vec<(ColliderHandle, ColliderHandle, usize, usize)>,
+    /// The contact pairs with warmstart impulses differing by more than the tolerance.
+    pub warmstart_mismatches: Vec<(ColliderHandle, ColliderHandle)>,
+}
+
+impl SnapshotDiff {
+    /// Returns `true` if no difference was found.
+    pub fn is_empty(&self) -> bool {
+        self.bodies.is_empty()
+            && self.missing_bodies.is_empty()
+            && self.manifold_count_mismatches.is_empty()
+            && self.warmstart_mismatches.is_empty()
+    }
 }
 
 pub struct PhysicsState {
@@ -73,6 +396,7 @@ pub struct PhysicsState {
     pub bodies: RigidBodySet,
     pub colliders: ColliderSet,
     pub joints: JointSet,
+    pub user_constraints: UserConstraintSet,
     pub ccd_solver: CCDSolver,
     pub pipeline: PhysicsPipeline,
     pub query_pipeline: QueryPipeline,
@@ -89,6 +413,7 @@ impl PhysicsState {
             bodies: RigidBodySet::new(),
             colliders: ColliderSet::new(),
             joints: JointSet::new(),
+            user_constraints: UserConstraintSet::new(),
             ccd_solver: CCDSolver::new(),
             pipeline: PhysicsPipeline::new(),
             query_pipeline: QueryPipeline::new(),
@@ -110,3 +435,158 @@ impl PhysicsEvents {
         while let Ok(_) = self.intersection_events.try_recv() {}
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{PhysicsSnapshot, SchemaVersion, SnapshotError};
+    use rapier::dynamics::{
+        CCDSolver, IntegrationParameters, JointSet, RigidBodyBuilder, RigidBodySet,
+    };
+    use rapier::geometry::{BroadPhase, ColliderBuilder, ColliderSet, NarrowPhase};
+    use rapier::math::{Isometry, Real, Vector};
+    use rapier::pipeline::PhysicsPipeline;
+
+    // A snapshot written with the schema `SchemaVersion::V1`. The serialized sets are opaque to
+    // the schema, so they are replaced by placeholder bytes.
+    const SNAPSHOT_V1: &[u8] = include_bytes!("snapshot_v1.bin");
+
+    #[test]
+    fn snapshots_with_the_previous_schema_keep_loading() {
+        let snapshot = PhysicsSnapshot::restore_compat(SNAPSHOT_V1).unwrap();
+        assert_eq!(snapshot.schema_version(), SchemaVersion::V1);
+        assert_eq!(snapshot.timestep_id, 42);
+        assert_eq!(snapshot.broad_phase, b"broad-phase");
+        assert_eq!(snapshot.bodies, b"bodies");
+        assert_eq!(snapshot.joints, b"joints");
+        assert!(snapshot.ccd_solver.is_none());
+
+        // Saving it again upgrades it to the current schema.
+        let upgraded = PhysicsSnapshot::restore_compat(&snapshot.to_bytes().unwrap()).unwrap();
+        assert_eq!(upgraded.schema_version(), SchemaVersion::CURRENT);
+        assert_eq!(upgraded.timestep_id, 42);
+        assert_eq!(upgraded.colliders, b"colliders");
+        assert!(upgraded.ccd_solver.is_none());
+    }
+
+    #[test]
+    fn snapshots_with_the_current_schema_are_restored() {
+        let mut bodies = RigidBodySet::new();
+        let handle = bodies.insert(RigidBodyBuilder::new_dynamic().build());
+        let snapshot = PhysicsSnapshot::new(
+            7,
+            &BroadPhase::new(),
+            &NarrowPhase::new(),
+            &bodies,
+            &ColliderSet::new(),
+            &JointSet::new(),
+            &CCDSolver::new(),
+        )
+        .unwrap();
+
+        let bytes = snapshot.to_bytes().unwrap();
+        let snapshot = PhysicsSnapshot::restore_compat(&bytes).unwrap();
+        assert_eq!(snapshot.schema_version(), SchemaVersion::CURRENT);
+
+        let (timestep_id, _, _, bodies, _, _, _) = snapshot.restore().unwrap();
+        assert_eq!(timestep_id, 7);
+        assert!(bodies.contains(handle));
+    }
+
+    #[test]
+    fn snapshots_with_an_unknown_schema_are_rejected() {
+        let mut bytes = SNAPSHOT_V1.to_vec();
+        let newer = SchemaVersion::CURRENT.major + 1;
+        bytes[4..6].copy_from_slice(&newer.to_le_bytes());
+
+        match PhysicsSnapshot::restore_compat(&bytes) {
+            Err(SnapshotError::UnsupportedVersion(version)) => assert_eq!(version.major, newer),
+            _ => panic!("The snapshot of a newer schema must be rejected."),
+        }
+
+        assert!(matches!(
+            PhysicsSnapshot::restore_compat(&SNAPSHOT_V1[..6]),
+            Err(SnapshotError::InvalidHeader)
+        ));
+    }
+
+    #[test]
+    fn diff_reports_the_differences_of_both_snapshots() {
+        let mut pipeline = PhysicsPipeline::new();
+        let mut broad_phase = BroadPhase::new();
+        let mut narrow_phase = NarrowPhase::new();
+        let mut bodies = RigidBodySet::new();
+        let mut colliders = ColliderSet::new();
+        let mut joints = JointSet::new();
+        let mut user_constraints = UserConstraintSet::new();
+        let mut ccd_solver = CCDSolver::new();
+        let mut handles = vec![];
+
+        // A row of three overlapping balls.
+        for i in 0..3 {
+            let mut pos = Isometry::identity();
+            pos.translation.vector.x = i as Real * 0.9;
+            let handle = bodies.insert(RigidBodyBuilder::new_dynamic().position(pos).build());
+            colliders.insert(ColliderBuilder::ball(0.5).build(), handle, &mut bodies);
+            handles.push(handle);
+        }
+
+        let snapshot = |bodies: &RigidBodySet, narrow_phase: &NarrowPhase| {
+            PhysicsSnapshot::new(
+                0,
+                &BroadPhase::new(),
+                narrow_phase,
+                bodies,
+                &ColliderSet::new(),
+                &JointSet::new(),
+                &CCDSolver::new(),
+            )
+            .unwrap()
+        };
+        let before_step = snapshot(&bodies, &narrow_phase);
+
+        pipeline.step(
+            &Vector::zeros(),
+            &IntegrationParameters::default(),
+            &mut broad_phase,
+            &mut narrow_phase,
+            &mut bodies,
+            &mut colliders,
+            &mut joints,
+            &mut user_constraints,
+            &mut ccd_solver,
+            &(),
+            &(),
+        );
+        let after_step = snapshot(&bodies, &narrow_phase);
+
+        // The contact pairs existing only on the second snapshot are reported too.
+        let diff = before_step.diff(&after_step, Real::MAX).unwrap();
+        assert_eq!(diff.manifold_count_mismatches.len(), 2);
+        assert!(diff
+            .manifold_count_mismatches
+            .iter()
+            .all(|(_, _, n1, n2)| *n1 == 0 && *n2 > 0));
+        let diff = after_step.diff(&before_step, Real::MAX).unwrap();
+        assert_eq!(diff.manifold_count_mismatches.len(), 2);
+        assert!(diff
+            .manifold_count_mismatches
+            .iter()
+            .all(|(_, _, n1, n2)| *n1 > 0 && *n2 == 0));
+
+        // A tiny perturbation of a single body is reported for that body only.
+        let mut perturbed_bodies = bodies.clone();
+        let rb = &mut perturbed_bodies[handles[0]];
+        let mut pos = *rb.position();
+        pos.translation.vector.x += 1.0e-7;
+        rb.set_position(pos, false);
+        let perturbed = snapshot(&perturbed_bodies, &narrow_phase);
+
+        assert!(after_step.diff(&after_step, 0.0).unwrap().is_empty());
+        let diff = after_step.diff(&perturbed, 1.0e-8).unwrap();
+        assert_eq!(diff.bodies.len(), 1);
+        assert_eq!(diff.bodies[0].handle, handles[0]);
+        assert!(diff.missing_bodies.is_empty());
+        assert!(diff.manifold_count_mismatches.is_empty());
+        assert!(diff.warmstart_mismatches.is_empty());
+    }
+}
@@ -464,6 +464,16 @@ impl PhysxWorld {
                             actor2,
                             &frame2 as *const _,
                         );
+                    }
+                    JointParams::GearJoint(_) => {
+                        eprintln!(
+                            "Joint type currently unsupported by the PhysX backend: GearJoint."
+                        )
+                    }
+                    JointParams::PulleyJoint(_) => {
+                        eprintln!(
+                            "Joint type currently unsupported by the PhysX backend: PulleyJoint."
+                        )
                     } // JointParams::GenericJoint(_) => {
                       //     eprintln!(
                       //         "Joint type currently unsupported by the PhysX backend: GenericJoint."
@@ -21,7 +21,7 @@ use na::{self, Point2, Point3, Vector3};
 use rapier::dynamics::{
     ActivationStatus, IntegrationParameters, JointSet, RigidBodyHandle, RigidBodySet,
 };
-use rapier::geometry::{ColliderHandle, ColliderSet, NarrowPhase};
+use rapier::geometry::{ColliderHandle, ColliderSet, NarrowPhase, RemoveSettleMode};
 #[cfg(feature = "dim3")]
 use rapier::geometry::{InteractionGroups, Ray};
 use rapier::math::Vector;
@@ -589,6 +589,7 @@ impl Testbed {
                         to_delete[0],
                         &mut self.harness.physics.bodies,
                         true,
+                        RemoveSettleMode::Immediate,
                     );
                 }
             }
@@ -1136,6 +1137,7 @@ impl State for Testbed {
                     &self.harness.physics.bodies,
                     &self.harness.physics.colliders,
                     &self.harness.physics.joints,
+                    &self.harness.physics.ccd_solver,
                 )
                 .ok();
 
@@ -1164,6 +1166,7 @@ impl State for Testbed {
                         self.set_world(w.3, w.4, w.5);
                         self.harness.physics.broad_phase = w.1;
                         self.harness.physics.narrow_phase = w.2;
+                        self.harness.physics.ccd_solver = w.6;
                         self.harness.state.timestep_id = w.0;
                     }
                 }
@@ -1207,12 +1210,16 @@ impl State for Testbed {
             {
                 if self.state.flags.contains(TestbedStateFlags::SLEEP) {
                     for (_, mut body) in self.harness.physics.bodies.iter_mut() {
-                        body.activation.threshold = ActivationStatus::default_threshold();
+                        body.activation.linear_threshold =
+                            ActivationStatus::default_linear_threshold();
+                        body.activation.angular_threshold =
+                            ActivationStatus::default_angular_threshold();
                     }
                 } else {
                     for (_, mut body) in self.harness.physics.bodies.iter_mut() {
                         body.wake_up(true);
-                        body.activation.threshold = -1.0;
+                        body.activation.linear_threshold = -1.0;
+                        body.activation.angular_threshold = -1.0;
                     }
                 }
             }